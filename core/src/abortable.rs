@@ -1,6 +1,12 @@
 //!
 //! Abortable trigger, can be used to cancel (abort) an asynchronous task.
 //!
+//! In addition to polling [`Abortable::is_aborted()`], futures and streams
+//! can be bound to an [`Abortable`] using [`Abortable::wrap()`] and
+//! [`Abortable::wrap_stream()`], which terminate as soon as
+//! [`Abortable::abort()`] is called. [`AbortOnDrop`] aborts when
+//! going out of scope.
+//!
 
 use wasm_bindgen::prelude::*;
 
@@ -9,6 +15,14 @@ use std::sync::{
     Arc,
 };
 
+cfg_if::cfg_if! {
+    if #[cfg(not(target_arch = "bpf"))] {
+        use crate::trigger::SingleTrigger;
+        use futures::{Future, FutureExt, Stream, StreamExt};
+        use std::sync::Mutex;
+    }
+}
+
 /// Error emitted by [`Abortable`].
 /// @category General
 #[wasm_bindgen]
@@ -28,6 +42,14 @@ impl std::fmt::Display for Aborted {
     }
 }
 
+#[derive(Default)]
+struct Inner {
+    aborted: AtomicBool,
+    /// Fired on abort, replaced on reset
+    #[cfg(not(target_arch = "bpf"))]
+    trigger: Mutex<SingleTrigger>,
+}
+
 ///
 /// Abortable trigger wraps an `Arc<AtomicBool>`, which can be cloned
 /// to signal task terminating using an atomic bool.
@@ -42,24 +64,26 @@ impl std::fmt::Display for Aborted {
 /// @category General
 #[derive(Default, Clone)]
 #[wasm_bindgen]
-pub struct Abortable(Arc<AtomicBool>);
+pub struct Abortable(Arc<Inner>);
 
 #[wasm_bindgen]
 impl Abortable {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
-        Self(Arc::new(AtomicBool::new(false)))
+        Self::default()
     }
 
     #[inline]
     #[wasm_bindgen(js_name=isAborted)]
     pub fn is_aborted(&self) -> bool {
-        self.0.load(Ordering::SeqCst)
+        self.0.aborted.load(Ordering::SeqCst)
     }
 
     #[inline]
     pub fn abort(&self) {
-        self.0.store(true, Ordering::SeqCst);
+        self.0.aborted.store(true, Ordering::SeqCst);
+        #[cfg(not(target_arch = "bpf"))]
+        self.0.trigger.lock().unwrap().trigger.trigger();
     }
 
     #[inline]
@@ -73,7 +97,104 @@ impl Abortable {
 
     #[inline]
     pub fn reset(&self) {
-        self.0.store(false, Ordering::SeqCst);
+        #[cfg(not(target_arch = "bpf"))]
+        {
+            let mut trigger = self.0.trigger.lock().unwrap();
+            if trigger.listener.is_triggered() {
+                *trigger = SingleTrigger::new();
+            }
+        }
+        self.0.aborted.store(false, Ordering::SeqCst);
+    }
+}
+
+#[cfg(not(target_arch = "bpf"))]
+impl Abortable {
+    /// Returns a future that resolves when [`Abortable::abort()`] is called
+    /// (immediately if already aborted).
+    pub fn cancelled(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.0.trigger.lock().unwrap().listener.clone()
+    }
+
+    /// Wraps `future`, resolving with `Err(Aborted)` as soon as
+    /// [`Abortable::abort()`] is called. The future is dropped on abort.
+    pub fn wrap<F>(&self, future: F) -> impl Future<Output = Result<F::Output, Aborted>>
+    where
+        F: Future,
+    {
+        let cancelled = self.cancelled().fuse();
+        async move {
+            let future = future.fuse();
+            futures::pin_mut!(future, cancelled);
+            futures::select_biased! {
+                _ = cancelled => Err(Aborted),
+                output = future => Ok(output),
+            }
+        }
+    }
+
+    /// Wraps `stream`, ending it as soon as [`Abortable::abort()`] is called.
+    pub fn wrap_stream<S>(&self, stream: S) -> impl Stream<Item = S::Item>
+    where
+        S: Stream,
+    {
+        stream.take_until(self.cancelled())
+    }
+
+    /// Creates a guard aborting this [`Abortable`] when dropped.
+    pub fn abort_on_drop(&self) -> AbortOnDrop {
+        AbortOnDrop::new(self)
+    }
+}
+
+/// Wraps `future` into a future that can be aborted using the
+/// returned [`Abortable`] (see [`Abortable::wrap()`]).
+#[cfg(not(target_arch = "bpf"))]
+pub fn abortable<F>(future: F) -> (impl Future<Output = Result<F::Output, Aborted>>, Abortable)
+where
+    F: Future,
+{
+    let abortable = Abortable::new();
+    (abortable.wrap(future), abortable)
+}
+
+///
+/// Guard that aborts the [`Abortable`] when dropped, allowing tasks
+/// to be tied to a scope.
+///
+/// ```text
+/// let abortable = Abortable::new();
+/// spawn(abortable.wrap(my_task()));
+/// let _guard = abortable.abort_on_drop();
+/// // my_task() is aborted when leaving the scope
+/// ```
+///
+#[cfg(not(target_arch = "bpf"))]
+pub struct AbortOnDrop(Option<Abortable>);
+
+#[cfg(not(target_arch = "bpf"))]
+impl AbortOnDrop {
+    pub fn new(abortable: &Abortable) -> Self {
+        Self(Some(abortable.clone()))
+    }
+
+    /// The guarded [`Abortable`]
+    pub fn abortable(&self) -> &Abortable {
+        self.0.as_ref().unwrap()
+    }
+
+    /// Consumes the guard without aborting
+    pub fn disarm(mut self) -> Abortable {
+        self.0.take().unwrap()
+    }
+}
+
+#[cfg(not(target_arch = "bpf"))]
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        if let Some(abortable) = self.0.take() {
+            abortable.abort();
+        }
     }
 }
 
@@ -110,3 +231,77 @@ impl TryFrom<&JsValue> for Abortable {
         }
     }
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(test, feature = "task"))]
+mod tests {
+    use super::*;
+    use crate::task::{interval, sleep};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_abortable_wrap() {
+        let abortable = Abortable::new();
+        let task = tokio::spawn(abortable.wrap(futures::future::pending::<()>()));
+        sleep(Duration::from_millis(20)).await;
+        abortable.abort();
+        let result = tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .expect("aborted future should resolve")
+            .unwrap();
+        assert!(matches!(result, Err(Aborted)));
+
+        // already aborted
+        assert!(abortable.wrap(async { 1 }).await.is_err());
+
+        // reset re-arms the trigger
+        abortable.reset();
+        assert_eq!(abortable.wrap(async { 1 }).await.unwrap(), 1);
+
+        let (future, abortable) = super::abortable(sleep(Duration::from_secs(10)));
+        abortable.abort();
+        assert!(future.await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_abortable_wrap_stream() {
+        let abortable = Abortable::new();
+        let stream = abortable.wrap_stream(interval(Duration::from_millis(10)));
+        let abortable_ = abortable.clone();
+        let task = tokio::spawn(async move {
+            futures::pin_mut!(stream);
+            let mut ticks = 0;
+            while stream.next().await.is_some() {
+                ticks += 1;
+                if ticks == 3 {
+                    abortable_.abort();
+                }
+            }
+            ticks
+        });
+        let ticks = tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .expect("aborted stream should end")
+            .unwrap();
+        assert_eq!(ticks, 3);
+    }
+
+    #[tokio::test]
+    async fn test_abort_on_drop() {
+        let abortable = Abortable::new();
+        {
+            let _guard = abortable.abort_on_drop();
+            assert!(!abortable.is_aborted());
+        }
+        assert!(abortable.is_aborted());
+
+        let abortable = Abortable::new();
+        let guard = AbortOnDrop::new(&abortable);
+        guard.disarm();
+        assert!(!abortable.is_aborted());
+        assert!(abortable
+            .wrap(sleep(Duration::from_millis(1)))
+            .await
+            .is_ok());
+    }
+}
@@ -1,15 +1,22 @@
 //! [`async_std::channel`] re-exports and shims
-use crate::id::Id;
 pub use async_channel::{
     bounded, unbounded, Receiver, RecvError, SendError, Sender, TryRecvError, TrySendError,
 };
 use std::{
-    collections::HashMap,
-    marker::PhantomData,
-    sync::{Arc, Mutex},
+    fmt,
+    sync::atomic::{AtomicIsize, AtomicU64, AtomicUsize, Ordering},
+    sync::{Arc, Mutex, Weak},
 };
 use thiserror::Error;
 
+pub mod select;
+pub use select::{Select, Selected};
+
+#[cfg(feature = "id")]
+mod multiplexer;
+#[cfg(feature = "id")]
+pub use multiplexer::{Multiplexer, MultiplexerChannel};
+
 #[derive(Error, Debug)]
 pub enum ChannelError<T> {
     #[error(transparent)]
@@ -63,52 +70,232 @@ impl<T, R> DuplexChannel<T, R> {
     }
 }
 
+/// Statistics of a named [`Channel`], updated by the [`Channel`]
+/// send and receive functions using relaxed atomic operations.
+#[derive(Debug)]
+pub struct ChannelStats {
+    name: String,
+    capacity: Option<usize>,
+    sends: AtomicU64,
+    receives: AtomicU64,
+    try_send_failures: AtomicU64,
+    // may transiently drop below zero if a message
+    // is received before the send is accounted for
+    depth: AtomicIsize,
+    high_water_mark: AtomicUsize,
+}
+
+impl ChannelStats {
+    fn new(name: &str, capacity: Option<usize>) -> Self {
+        Self {
+            name: name.to_string(),
+            capacity,
+            sends: AtomicU64::new(0),
+            receives: AtomicU64::new(0),
+            try_send_failures: AtomicU64::new(0),
+            depth: AtomicIsize::new(0),
+            high_water_mark: AtomicUsize::new(0),
+        }
+    }
+
+    #[inline]
+    fn sent(&self) {
+        self.sends.fetch_add(1, Ordering::Relaxed);
+        let depth = self.depth.fetch_add(1, Ordering::Relaxed) + 1;
+        if depth > 0 {
+            self.high_water_mark
+                .fetch_max(depth as usize, Ordering::Relaxed);
+        }
+    }
+
+    #[inline]
+    fn received(&self) {
+        self.receives.fetch_add(1, Ordering::Relaxed);
+        self.depth.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn try_send_failed(&self) {
+        self.try_send_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> ChannelSnapshot {
+        ChannelSnapshot {
+            name: self.name.clone(),
+            capacity: self.capacity,
+            sends: self.sends.load(Ordering::Relaxed),
+            receives: self.receives.load(Ordering::Relaxed),
+            try_send_failures: self.try_send_failures.load(Ordering::Relaxed),
+            depth: self.depth.load(Ordering::Relaxed).max(0) as usize,
+            high_water_mark: self.high_water_mark.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Point-in-time statistics of a named [`Channel`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelSnapshot {
+    pub name: String,
+    /// Channel capacity (`None` for unbounded channels)
+    pub capacity: Option<usize>,
+    pub sends: u64,
+    pub receives: u64,
+    pub try_send_failures: u64,
+    /// Number of messages pending in the channel
+    pub depth: usize,
+    /// Maximum number of messages pending in the channel
+    pub high_water_mark: usize,
+}
+
+impl fmt::Display for ChannelSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let capacity = self
+            .capacity
+            .map(|capacity| capacity.to_string())
+            .unwrap_or_else(|| "unbounded".to_string());
+        write!(
+            f,
+            "{}: depth {}/{} (high-water mark {}), sends {}, receives {}, try_send failures {}",
+            self.name,
+            self.depth,
+            capacity,
+            self.high_water_mark,
+            self.sends,
+            self.receives,
+            self.try_send_failures
+        )
+    }
+}
+
+static REGISTRY: Mutex<Vec<Weak<ChannelStats>>> = Mutex::new(Vec::new());
+
+fn register(stats: &Arc<ChannelStats>) {
+    let mut registry = REGISTRY.lock().unwrap();
+    registry.retain(|stats| stats.strong_count() > 0);
+    registry.push(Arc::downgrade(stats));
+}
+
+/// Returns statistics of all live named channels
+/// (created using [`Channel::named()`] or [`Channel::named_bounded()`]).
+pub fn registry_snapshot() -> Vec<ChannelSnapshot> {
+    let mut registry = REGISTRY.lock().unwrap();
+    registry.retain(|stats| stats.strong_count() > 0);
+    registry
+        .iter()
+        .filter_map(|stats| stats.upgrade().map(|stats| stats.snapshot()))
+        .collect()
+}
+
 /// [`Channel`] struct that combines [`async_std::channel::Sender`] and
 /// [`async_std::channel::Receiver`] into a single struct with `sender`
 /// and `receiver` members representing a single channel.
+///
+/// Named channels collect statistics listed by [`registry_snapshot()`].
+/// Only messages passing through the [`Channel`] functions are accounted
+/// for; using the `sender` and `receiver` members directly bypasses
+/// the instrumentation.
 #[derive(Debug, Clone)]
 pub struct Channel<T = ()> {
     pub sender: Sender<T>,
     pub receiver: Receiver<T>,
+    stats: Option<Arc<ChannelStats>>,
 }
 
 impl<T> Channel<T> {
     pub fn unbounded() -> Self {
         let (sender, receiver) = unbounded();
-        Self { sender, receiver }
+        Self {
+            sender,
+            receiver,
+            stats: None,
+        }
     }
 
     pub fn bounded(cap: usize) -> Self {
         let (sender, receiver) = bounded(cap);
-        Self { sender, receiver }
+        Self {
+            sender,
+            receiver,
+            stats: None,
+        }
     }
 
     pub fn oneshot() -> Self {
         let (sender, receiver) = bounded(1);
-        Self { sender, receiver }
+        Self {
+            sender,
+            receiver,
+            stats: None,
+        }
+    }
+
+    /// Creates an unbounded channel collecting statistics
+    /// under the given `name`.
+    pub fn named(name: &str) -> Self {
+        let stats = Arc::new(ChannelStats::new(name, None));
+        register(&stats);
+        Self {
+            stats: Some(stats),
+            ..Self::unbounded()
+        }
+    }
+
+    /// Creates a bounded channel collecting statistics
+    /// under the given `name`.
+    pub fn named_bounded(name: &str, cap: usize) -> Self {
+        let stats = Arc::new(ChannelStats::new(name, Some(cap)));
+        register(&stats);
+        Self {
+            stats: Some(stats),
+            ..Self::bounded(cap)
+        }
+    }
+
+    /// Statistics of a named channel
+    pub fn stats(&self) -> Option<ChannelSnapshot> {
+        self.stats.as_ref().map(|stats| stats.snapshot())
     }
 
     pub fn drain(&self) -> std::result::Result<(), TryRecvError> {
         while !self.receiver.is_empty() {
-            self.receiver.try_recv()?;
+            self.try_recv()?;
         }
         Ok(())
     }
 
     pub async fn recv(&self) -> Result<T, RecvError> {
-        self.receiver.recv().await
+        let msg = self.receiver.recv().await?;
+        if let Some(stats) = &self.stats {
+            stats.received();
+        }
+        Ok(msg)
     }
 
     pub fn try_recv(&self) -> Result<T, TryRecvError> {
-        self.receiver.try_recv()
+        let msg = self.receiver.try_recv()?;
+        if let Some(stats) = &self.stats {
+            stats.received();
+        }
+        Ok(msg)
     }
 
     pub async fn send(&self, msg: T) -> Result<(), SendError<T>> {
-        self.sender.send(msg).await
+        self.sender.send(msg).await?;
+        if let Some(stats) = &self.stats {
+            stats.sent();
+        }
+        Ok(())
     }
 
     pub fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
-        self.sender.try_send(msg)
+        let result = self.sender.try_send(msg);
+        if let Some(stats) = &self.stats {
+            match result {
+                Ok(()) => stats.sent(),
+                Err(_) => stats.try_send_failed(),
+            }
+        }
+        result
     }
 
     pub fn len(&self) -> usize {
@@ -128,17 +315,24 @@ impl<T> Channel<T> {
     }
 
     pub fn iter(&self) -> ChannelIterator<T> {
-        ChannelIterator::new(self.receiver.clone())
+        ChannelIterator {
+            receiver: self.receiver.clone(),
+            stats: self.stats.clone(),
+        }
     }
 }
 
 pub struct ChannelIterator<T> {
     receiver: Receiver<T>,
+    stats: Option<Arc<ChannelStats>>,
 }
 
 impl<T> ChannelIterator<T> {
     pub fn new(receiver: Receiver<T>) -> Self {
-        ChannelIterator { receiver }
+        ChannelIterator {
+            receiver,
+            stats: None,
+        }
     }
 }
 
@@ -148,176 +342,55 @@ impl<T> Iterator for ChannelIterator<T> {
         if self.receiver.is_empty() {
             None
         } else {
-            self.receiver.try_recv().ok()
-        }
-    }
-}
-
-/// A simple MPMC (one to many) channel Multiplexer that broadcasts to
-/// multiple registered receivers.  [`Multiplexer<T>`] itself can be
-/// cloned and used to broadcast using [`Multiplexer::broadcast()`]
-/// or [`Multiplexer::try_broadcast()`].  To create a receiving channel,
-/// you can call [`MultiplexerChannel<T>::from()`] and supply the
-/// desired Multiplexer instance, or  simply call [`Multiplexer::channel()`]
-/// to create a new [`MultiplexerChannel`] instance.  The receiving channel
-/// gets unregistered when [`MultiplexerChannel`] is dropped or the
-/// underlying [`Receiver`] is closed.
-#[derive(Clone)]
-pub struct Multiplexer<T>
-where
-    T: Clone + Send + Sync + 'static,
-{
-    pub channels: Arc<Mutex<HashMap<Id, Arc<Sender<T>>>>>,
-    t: PhantomData<T>,
-}
-
-impl<T> Default for Multiplexer<T>
-where
-    T: Clone + Send + Sync + 'static,
-{
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl<T> Multiplexer<T>
-where
-    T: Clone + Send + Sync + 'static,
-{
-    /// Create a new Multiplexer instance
-    pub fn new() -> Multiplexer<T> {
-        Multiplexer {
-            channels: Arc::new(Mutex::new(HashMap::default())),
-            t: PhantomData,
-        }
-    }
-
-    /// Create a new multiplexer receiving channel
-    pub fn channel(&self) -> MultiplexerChannel<T> {
-        MultiplexerChannel::from(self)
-    }
-
-    fn register_event_channel(&self) -> (Id, Sender<T>, Receiver<T>) {
-        let (sender, receiver) = unbounded();
-        let id = Id::new();
-        self.channels
-            .lock()
-            .unwrap()
-            .insert(id, Arc::new(sender.clone()));
-        (id, sender, receiver)
-    }
-
-    fn unregister_event_channel(&self, id: Id) {
-        self.channels.lock().unwrap().remove(&id);
-    }
-
-    /// Async [`Multiplexer::broadcast`] function that calls [`Sender::send()`] on all registered [`MultiplexerChannel`] instances.
-    pub async fn broadcast(&self, event: T) -> Result<(), ChannelError<T>> {
-        let mut removed = vec![];
-        let channels = self
-            .channels
-            .lock()
-            .unwrap()
-            .iter()
-            .map(|(k, v)| (*k, v.clone()))
-            .collect::<Vec<_>>();
-        for (id, sender) in channels.iter() {
-            match sender.send(event.clone()).await {
-                Ok(_) => {}
-                Err(_err) => {
-                    removed.push(*id);
-                }
-            }
-        }
-        if !removed.is_empty() {
-            let mut channels = self.channels.lock().unwrap();
-            for id in removed.iter() {
-                channels.remove(id);
+            let msg = self.receiver.try_recv().ok();
+            if let (Some(_), Some(stats)) = (&msg, &self.stats) {
+                stats.received();
             }
+            msg
         }
-
-        Ok(())
-    }
-
-    /// A synchronous [`Multiplexer::try_broadcast`] function that calls [`Sender::try_send()`] on all registered [`MultiplexerChannel`] instances.
-    /// This function holds a mutex for the duration of the broadcast.
-    pub fn try_broadcast(&self, event: T) -> Result<(), ChannelError<T>> {
-        let mut removed = vec![];
-        let mut channels = self.channels.lock().unwrap();
-        for (id, sender) in channels.iter() {
-            match sender.try_send(event.clone()) {
-                Ok(_) => {}
-                Err(_err) => {
-                    removed.push(*id);
-                }
-            }
-        }
-        if !removed.is_empty() {
-            for id in removed.iter() {
-                channels.remove(id);
-            }
-        }
-
-        Ok(())
     }
 }
 
-/// Receiving channel endpoint for the [`Multiplexer`].  [`MultiplexerChannel<T>`] holds a [`Sender`] and the [`Receiver`] channel endpoints.
-/// The [`Sender`] is provided for convenience, allowing internal relay within this channel instance.
-/// To process events, simply iterate over [`MultiplexerChannel::recv()`] by calling `channel.recv().await`.
-#[derive(Clone)]
-pub struct MultiplexerChannel<T>
-where
-    T: Clone + Send + Sync + 'static,
-{
-    multiplexer: Multiplexer<T>,
-    pub id: Id,
-    pub sender: Sender<T>,
-    pub receiver: Receiver<T>,
-}
+#[cfg(not(any(target_arch = "wasm32", target_arch = "bpf")))]
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-impl<T> MultiplexerChannel<T>
-where
-    T: Clone + Send + Sync + 'static,
-{
-    /// Close the receiving channel.  This will unregister the channel from the [`Multiplexer`].
-    pub fn close(&self) {
-        self.multiplexer.unregister_event_channel(self.id);
-    }
-
-    /// Receive an event from the channel.  This is a blocking async call.
-    pub async fn recv(&self) -> Result<T, RecvError> {
-        self.receiver.recv().await
-    }
-
-    /// Receive an event from the channel.  This is a non-blocking sync call that
-    /// follows [`Receiver::try_recv`] semantics.
-    pub fn try_recv(&self) -> Result<T, TryRecvError> {
-        self.receiver.try_recv()
-    }
-}
+    #[tokio::test]
+    async fn test_channel_high_water_mark() {
+        let channel = Channel::<usize>::named_bounded("test-high-water-mark", 8);
+        for n in 0..6 {
+            channel.send(n).await.unwrap();
+        }
+        assert_eq!(channel.try_recv().unwrap(), 0);
+        assert_eq!(channel.recv().await.unwrap(), 1);
 
-/// Create a [`MultiplexerChannel`] from [`Multiplexer`] by reference.
-impl<T> From<&Multiplexer<T>> for MultiplexerChannel<T>
-where
-    T: Clone + Send + Sync + 'static,
-{
-    fn from(multiplexer: &Multiplexer<T>) -> Self {
-        let (id, sender, receiver) = multiplexer.register_event_channel();
-        MultiplexerChannel {
-            multiplexer: multiplexer.clone(),
-            id,
-            sender,
-            receiver,
+        // burst beyond the capacity
+        for n in 0..8 {
+            channel.try_send(n).ok();
         }
-    }
-}
+        let stats = channel.stats().unwrap();
+        assert_eq!(stats.sends, 10);
+        assert_eq!(stats.receives, 2);
+        assert_eq!(stats.try_send_failures, 4);
+        assert_eq!(stats.depth, 8);
+        assert_eq!(stats.high_water_mark, 8);
+
+        channel.drain().unwrap();
+        let stats = channel.stats().unwrap();
+        assert_eq!(stats.depth, 0);
+        assert_eq!(stats.high_water_mark, 8);
+        assert!(registry_snapshot().contains(&stats));
+
+        // channels are unregistered once dropped
+        drop(channel);
+        assert!(!registry_snapshot()
+            .iter()
+            .any(|stats| stats.name == "test-high-water-mark"));
 
-impl<T> Drop for MultiplexerChannel<T>
-where
-    T: Clone + Send + Sync + 'static,
-{
-    fn drop(&mut self) {
-        self.multiplexer.unregister_event_channel(self.id);
+        // unnamed channels are not instrumented
+        let channel = Channel::<usize>::bounded(1);
+        channel.try_send(0).unwrap();
+        assert!(channel.stats().is_none());
     }
 }
@@ -0,0 +1,353 @@
+//!
+//! Awaiting the first message among a set of [`Receiver`]s using the
+//! [`select!`](crate::select) macro or the [`Select`] future.
+//!
+//! Receivers are polled in their declaration order, so the behavior
+//! is deterministic and identical on native and `wasm32` targets.
+//! Only the winning branch receives a message; messages available in
+//! the remaining receivers stay in their channels.
+//!
+//! ```ignore
+//! loop {
+//!     select! {
+//!         event = events.receiver => handle_event(event),
+//!         _ = shutdown.receiver => break,
+//!         complete => break,
+//!     }
+//! }
+//! ```
+//!
+
+use super::Receiver;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use std::any::Any;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+type Message = Box<dyn Any + Send>;
+
+/// Outcome of the [`Select`] future
+pub enum Selected {
+    /// Message received from the receiver at the given index
+    /// (decode using [`downcast()`])
+    Message(usize, Message),
+    /// None of the receivers has a message ready (produced
+    /// only if enabled via [`Select::with_default()`])
+    Default,
+    /// All receivers are closed (produced only if enabled via
+    /// [`Select::with_complete()`] or [`Select::with_default()`])
+    Complete,
+}
+
+/// Future resolving with the first message available in any of
+/// the registered receivers (see the [module](self) documentation).
+#[derive(Default)]
+pub struct Select<'a> {
+    branches: Vec<Option<BoxFuture<'a, Option<Message>>>>,
+    default: bool,
+    complete: bool,
+}
+
+impl<'a> Select<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a receiver, returning its index reported by [`Selected::Message`]
+    pub fn recv<T>(&mut self, receiver: &'a Receiver<T>) -> usize
+    where
+        T: Send + 'static,
+    {
+        self.branches.push(Some(
+            async move {
+                receiver
+                    .recv()
+                    .await
+                    .ok()
+                    .map(|msg| Box::new(msg) as Message)
+            }
+            .boxed(),
+        ));
+        self.branches.len() - 1
+    }
+
+    /// Resolve with [`Selected::Default`] instead of waiting
+    /// if none of the receivers has a message ready
+    pub fn with_default(&mut self) -> &mut Self {
+        self.default = true;
+        self
+    }
+
+    /// Resolve with [`Selected::Complete`] once all receivers are
+    /// closed; otherwise the [`Select`] future panics in this case
+    /// (unless [`Select::with_default()`] is enabled).
+    pub fn with_complete(&mut self) -> &mut Self {
+        self.complete = true;
+        self
+    }
+}
+
+impl Future for Select<'_> {
+    type Output = Selected;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Selected> {
+        for (index, branch) in self.branches.iter_mut().enumerate() {
+            if let Some(future) = branch {
+                match future.poll_unpin(cx) {
+                    Poll::Ready(Some(msg)) => return Poll::Ready(Selected::Message(index, msg)),
+                    // the channel is closed and empty
+                    Poll::Ready(None) => *branch = None,
+                    Poll::Pending => {}
+                }
+            }
+        }
+
+        if self.branches.iter().all(Option::is_none) {
+            if self.complete {
+                Poll::Ready(Selected::Complete)
+            } else if self.default {
+                Poll::Ready(Selected::Default)
+            } else {
+                panic!("select: all channels are closed and no `complete` branch was provided")
+            }
+        } else if self.default {
+            Poll::Ready(Selected::Default)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Recovers a message received from `_receiver`
+pub fn downcast<T: 'static>(_receiver: &Receiver<T>, msg: Message) -> T {
+    *msg.downcast::<T>()
+        .expect("select: message type does not match the receiver")
+}
+
+/// Awaits the first message among a fixed set of [`Receiver`]s,
+/// executing the branch of the receiver that has produced it:
+///
+/// ```ignore
+/// let value = select! {
+///     msg = rx1 => { format!("rx1: {msg}") },
+///     msg = rx2 => format!("rx2: {msg}"),
+///     // optional: executed if no messages are ready
+///     default => "idle".to_string(),
+///     // optional: executed when all channels are closed
+///     complete => "closed".to_string(),
+/// };
+/// ```
+///
+/// Each branch binds the message to an irrefutable pattern. Receivers
+/// are evaluated once and polled in the declaration order; messages
+/// are only taken from the winning receiver. Closed receivers are
+/// skipped; if all of them are closed, the `complete` branch is
+/// executed (or `default` if `complete` is not provided). Without
+/// either branch, the macro panics in that case. The branch bodies
+/// are not closures, so they can use `break`, `continue`, `return`
+/// and `?` in the enclosing context. Must be used within an async context.
+#[macro_export]
+macro_rules! select {
+    ($($tokens:tt)*) => {
+        $crate::__select_parse!(@branches [] @default [] @complete [] $($tokens)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __select_parse {
+    (@branches $branches:tt @default [] @complete $complete:tt default => $body:block $(,)? $($rest:tt)*) => {
+        $crate::__select_parse!(@branches $branches @default [$body] @complete $complete $($rest)*)
+    };
+    (@branches $branches:tt @default [] @complete $complete:tt default => $body:expr $(, $($rest:tt)*)?) => {
+        $crate::__select_parse!(@branches $branches @default [$body] @complete $complete $($($rest)*)?)
+    };
+    (@branches $branches:tt @default $default:tt @complete [] complete => $body:block $(,)? $($rest:tt)*) => {
+        $crate::__select_parse!(@branches $branches @default $default @complete [$body] $($rest)*)
+    };
+    (@branches $branches:tt @default $default:tt @complete [] complete => $body:expr $(, $($rest:tt)*)?) => {
+        $crate::__select_parse!(@branches $branches @default $default @complete [$body] $($($rest)*)?)
+    };
+    (@branches [$($branches:tt)*] @default $default:tt @complete $complete:tt $pat:pat = $receiver:expr => $body:block $(,)? $($rest:tt)*) => {
+        $crate::__select_parse!(@branches [$($branches)* [$pat, $receiver, $body]] @default $default @complete $complete $($rest)*)
+    };
+    (@branches [$($branches:tt)*] @default $default:tt @complete $complete:tt $pat:pat = $receiver:expr => $body:expr $(, $($rest:tt)*)?) => {
+        $crate::__select_parse!(@branches [$($branches)* [$pat, $receiver, $body]] @default $default @complete $complete $($($rest)*)?)
+    };
+    (@branches $branches:tt @default $default:tt @complete $complete:tt) => {
+        $crate::__select_gen!(@bindings [] @arms [] @index [0] @default $default @complete $complete $branches)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __select_gen {
+    // binds each receiver expression to an identifier unique to this expansion step
+    (@bindings [$($bindings:tt)*] @arms [$($arms:tt)*] @index [$($index:tt)*] @default $default:tt @complete $complete:tt [[$pat:pat, $receiver:expr, $body:expr] $($branches:tt)*]) => {
+        $crate::__select_gen!(
+            @bindings [$($bindings)* let __receiver = &$receiver;]
+            @arms [$($arms)* [__receiver, $pat, $body, ($($index)*)]]
+            @index [$($index)* + 1]
+            @default $default
+            @complete $complete
+            [$($branches)*]
+        )
+    };
+    (@bindings [$($bindings:tt)*] @arms [$([$receiver:ident, $pat:pat, $body:expr, ($($index:tt)*)])*] @index $_index:tt @default [$($default:tt)?] @complete [$($complete:tt)?] []) => {{
+        $($bindings)*
+        let mut __select = $crate::channel::select::Select::new();
+        $( __select.recv($receiver); )*
+        if $crate::__select_some!([$($default)?]) {
+            __select.with_default();
+        }
+        if $crate::__select_some!([$($complete)?]) {
+            __select.with_complete();
+        }
+        match __select.await {
+            $(
+                $crate::channel::select::Selected::Message(__index, __msg) if __index == $($index)* => {
+                    let $pat = $crate::channel::select::downcast($receiver, __msg);
+                    $body
+                }
+            )*
+            $crate::channel::select::Selected::Default => $crate::__select_or!([$($default)?] unreachable!()),
+            $crate::channel::select::Selected::Complete => $crate::__select_or!([$($complete)?] unreachable!()),
+            #[allow(unreachable_patterns)]
+            _ => unreachable!(),
+        }
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __select_some {
+    ([]) => {
+        false
+    };
+    ([$($tokens:tt)+]) => {
+        true
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __select_or {
+    ([] $fallback:expr) => {
+        $fallback
+    };
+    ([$body:expr] $fallback:expr) => {
+        $body
+    };
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_arch = "bpf")))]
+#[cfg(test)]
+mod tests {
+    use crate::channel::{bounded, unbounded};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_select_racing_receivers() {
+        let (tx1, rx1) = unbounded::<u32>();
+        let (tx2, rx2) = unbounded::<String>();
+
+        tx1.send(1).await.unwrap();
+        tx2.send("a".to_string()).await.unwrap();
+        tx1.send(2).await.unwrap();
+
+        // both ready: the first declared receiver wins,
+        // the message of the other one stays in its channel
+        let mut received = Vec::new();
+        for _ in 0..3 {
+            let value = select! {
+                n = rx1 => format!("rx1: {n}"),
+                s = rx2 => { format!("rx2: {s}") }
+            };
+            received.push(value);
+        }
+        assert_eq!(received, vec!["rx1: 1", "rx1: 2", "rx2: a"]);
+        assert!(rx1.is_empty() && rx2.is_empty());
+
+        // waits for the first message posted by another task
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            tx2.send("b".to_string()).await.unwrap();
+        });
+        let value = select! {
+            n = rx1 => n.to_string(),
+            s = rx2 => s,
+        };
+        assert_eq!(value, "b");
+        assert!(rx1.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_select_closed_channels() {
+        let (tx1, rx1) = bounded::<u32>(4);
+        let (tx2, rx2) = bounded::<u32>(4);
+
+        // a closed channel is skipped once drained
+        tx1.send(1).await.unwrap();
+        drop(tx1);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            tx2.send(2).await.unwrap();
+        });
+
+        let mut received = Vec::new();
+        loop {
+            select! {
+                n = rx1 => received.push(n),
+                n = rx2 => received.push(n * 10),
+                complete => break,
+            }
+        }
+        assert_eq!(received, vec![1, 20]);
+
+        // the default branch is used when no `complete` branch is provided
+        let value = select! {
+            n = rx1 => n,
+            default => 0,
+        };
+        assert_eq!(value, 0);
+    }
+
+    #[tokio::test]
+    #[should_panic]
+    async fn test_select_closed_without_complete() {
+        let (_, rx) = unbounded::<u32>();
+        select! {
+            _ = rx => {}
+        }
+    }
+
+    #[tokio::test]
+    async fn test_select_default() {
+        let (tx1, rx1) = unbounded::<u32>();
+        let (_tx2, rx2) = unbounded::<u32>();
+
+        let poll = || async {
+            select! {
+                n = rx1 => Some(n),
+                n = rx2 => Some(n),
+                default => None,
+                complete => unreachable!(),
+            }
+        };
+        assert_eq!(poll().await, None);
+        tx1.send(7).await.unwrap();
+        assert_eq!(poll().await, Some(7));
+        assert_eq!(poll().await, None);
+
+        // non-blocking polls do not consume messages from the other receivers
+        tx1.send(8).await.unwrap();
+        let value = select! {
+            _ = rx2 => unreachable!(),
+            default => 0,
+        };
+        assert_eq!(value, 0);
+        assert_eq!(rx1.try_recv().unwrap(), 8);
+    }
+}
@@ -0,0 +1,385 @@
+//!
+//! Suspend-aware timers and clock discontinuity detection.
+//!
+//! The monotonic clock does not advance while the system is suspended
+//! and browsers clamp timers in background tabs, so timers backed by the
+//! platform can fire long after their deadline. [`sleep()`] and [`sleep_until()`]
+//! wait in slices of at most [`SLEEP_SLICE`], re-checking the deadline on each
+//! wake against the monotonic clock extended by the time the system spent
+//! suspended; after a system resume the sleep completes once the deadline has
+//! passed instead of waiting for the full duration again. The suspended time
+//! is derived from the suspend-inclusive platform clock (Linux, Android, macOS
+//! and iOS); elsewhere sleeps follow the monotonic clock only.
+//!
+//! Sleeps are not affected by wall-clock adjustments (NTP steps or manual
+//! changes of the system time), which are reported as [`ClockJump`] events.
+//!
+//! [`clock_jump()`] returns a stream of [`ClockJump`] events produced by a
+//! lightweight watchdog task when the wall clock and the monotonic clock
+//! diverge (system suspend, wall-clock adjustments) or the watchdog is not
+//! scheduled in time (process stalls, throttled background tabs). Connection
+//! keepalives and request timeouts can subscribe to re-validate connections
+//! without waiting for their own timers (the native `workflow-websocket`
+//! client pings the server and the `workflow-rpc` client re-checks its
+//! pending request timeouts).
+//!
+//! Clock readings honor the manual clock of
+//! [`testing::manual_clock()`](crate::testing::manual_clock).
+//!
+
+use crate::channel::{unbounded, Receiver, Sender};
+use crate::time::{unixtime_as_millis_u64, Duration, Instant};
+use futures::future::FusedFuture;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+
+/// Maximum duration of a single platform timer used by [`Sleep`]
+pub const SLEEP_SLICE: Duration = Duration::from_secs(1);
+/// Interval at which the [`clock_jump()`] watchdog checks the clocks
+pub const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+/// Clock discontinuity reported by the [`clock_jump()`] watchdog
+pub const CLOCK_JUMP_THRESHOLD: Duration = Duration::from_secs(2);
+
+/// Simultaneous monotonic and wall-clock readings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockReading {
+    pub instant: Instant,
+    /// UNIX time in milliseconds
+    pub unixtime: u64,
+    /// Total time the system spent suspended (zero if not
+    /// reported by the platform, see [`suspended()`])
+    pub suspended: Duration,
+}
+
+/// Reads the monotonic and the wall clock.
+pub fn now() -> ClockReading {
+    let reading = ClockReading {
+        instant: Instant::now(),
+        unixtime: unixtime_as_millis_u64(),
+        suspended: suspended(),
+    };
+
+    // same cfg as the `testing` module
+    #[cfg(all(
+        not(any(target_arch = "wasm32", target_arch = "bpf")),
+        any(test, feature = "test"),
+        feature = "dirs",
+        feature = "id"
+    ))]
+    if let Some((monotonic, wall, suspended)) = crate::testing::clock_offsets() {
+        return ClockReading {
+            instant: reading.instant + monotonic,
+            unixtime: reading.unixtime.saturating_add_signed(wall),
+            suspended: reading.suspended + suspended,
+        };
+    }
+
+    reading
+}
+
+/// Total time the system spent suspended since boot: the difference of the
+/// suspend-inclusive clock and the monotonic clock backing [`Instant`].
+/// Returns zero on platforms that do not report it (on Windows, [`Instant`]
+/// keeps advancing during suspend; browsers provide no such clock).
+pub fn suspended() -> Duration {
+    cfg_if::cfg_if! {
+        if #[cfg(any(target_os = "linux", target_os = "android"))] {
+            clock_gettime(libc::CLOCK_BOOTTIME).saturating_sub(clock_gettime(libc::CLOCK_MONOTONIC))
+        } else if #[cfg(any(target_os = "macos", target_os = "ios"))] {
+            clock_gettime(libc::CLOCK_MONOTONIC).saturating_sub(clock_gettime(libc::CLOCK_UPTIME_RAW))
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios"
+))]
+fn clock_gettime(clock: libc::clockid_t) -> Duration {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `time` is a valid `timespec` and `clock` is supported by the platform
+    unsafe { libc::clock_gettime(clock, &mut time) };
+    Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+}
+
+type Timer = Pin<Box<dyn Future<Output = ()> + Send + Sync>>;
+
+fn timer(duration: Duration) -> Timer {
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            Box::pin(crate::wasm::sleep::sleep(duration))
+        } else {
+            Box::pin(tokio::time::sleep(duration))
+        }
+    }
+}
+
+/// Future returned by [`sleep()`] and [`sleep_until()`]
+pub struct Sleep {
+    deadline: Instant,
+    /// Suspended time at the creation of the sleep
+    suspended: Duration,
+    timer: Option<Timer>,
+    terminated: bool,
+}
+
+impl Sleep {
+    fn new(deadline: Instant) -> Self {
+        Sleep {
+            deadline,
+            suspended: now().suspended,
+            timer: None,
+            terminated: false,
+        }
+    }
+
+    /// Monotonic deadline of the sleep
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Returns `true` if the deadline has passed, counting the time
+    /// the system spent suspended since the sleep was created.
+    pub fn is_elapsed(&self) -> bool {
+        self.remaining(&now()).is_zero()
+    }
+
+    fn remaining(&self, now: &ClockReading) -> Duration {
+        self.deadline
+            .saturating_duration_since(now.instant)
+            .saturating_sub(now.suspended.saturating_sub(self.suspended))
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            let remaining = self.remaining(&now());
+            if remaining.is_zero() {
+                self.timer = None;
+                self.terminated = true;
+                return Poll::Ready(());
+            }
+
+            let slice = remaining.min(SLEEP_SLICE);
+            let timer = self.timer.get_or_insert_with(|| timer(slice));
+            match timer.as_mut().poll(cx) {
+                Poll::Ready(()) => self.timer = None,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl FusedFuture for Sleep {
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+/// Suspends the task for the given `duration`
+/// (see the [module](self) documentation).
+pub fn sleep(duration: Duration) -> Sleep {
+    Sleep::new(now().instant + duration)
+}
+
+/// Suspends the task until the `deadline`
+/// (see the [module](self) documentation).
+pub fn sleep_until(deadline: Instant) -> Sleep {
+    Sleep::new(deadline)
+}
+
+/// Clock discontinuity detected by the [`Watchdog`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockJump {
+    /// Monotonic time elapsed since the previous check
+    pub monotonic: Duration,
+    /// Wall-clock time elapsed since the previous check in milliseconds
+    /// (negative if the wall clock was set back)
+    pub wall_millis: i64,
+    /// Time the system spent suspended since the previous check
+    /// (zero if not reported by the platform, see [`suspended()`])
+    pub suspended: Duration,
+}
+
+impl ClockJump {
+    /// Wall-clock time not accounted for by the monotonic clock in
+    /// milliseconds (positive after a system suspend)
+    pub fn drift_millis(&self) -> i64 {
+        self.wall_millis - self.monotonic.as_millis() as i64
+    }
+}
+
+/// Detects clock discontinuities by comparing the clock readings of
+/// consecutive checks, expected to be performed every `interval`.
+pub struct Watchdog {
+    interval: Duration,
+    threshold: Duration,
+    last: Mutex<ClockReading>,
+}
+
+impl Watchdog {
+    pub fn new(interval: Duration, threshold: Duration) -> Self {
+        Watchdog {
+            interval,
+            threshold,
+            last: Mutex::new(now()),
+        }
+    }
+
+    /// Returns a [`ClockJump`] if, since the previous check, the wall clock
+    /// drifted from the monotonic clock by more than the threshold or the
+    /// monotonic time elapsed exceeds the interval by more than the threshold.
+    pub fn check(&self) -> Option<ClockJump> {
+        let now = now();
+        let last = std::mem::replace(&mut *self.last.lock().unwrap(), now);
+        let jump = ClockJump {
+            monotonic: now.instant.saturating_duration_since(last.instant),
+            wall_millis: now.unixtime as i64 - last.unixtime as i64,
+            suspended: now.suspended.saturating_sub(last.suspended),
+        };
+
+        let threshold = self.threshold.as_millis() as i64;
+        (jump.drift_millis().abs() > threshold
+            || jump.monotonic > self.interval + self.threshold
+            || jump.suspended > self.threshold)
+            .then_some(jump)
+    }
+}
+
+static SUBSCRIBERS: Mutex<Vec<Sender<ClockJump>>> = Mutex::new(Vec::new());
+static WATCHDOG: AtomicBool = AtomicBool::new(false);
+
+/// Resets the [`WATCHDOG`] flag when the watchdog task exits.
+struct Running;
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        WATCHDOG.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Subscribes to clock discontinuities. The watchdog task is spawned on
+/// the first subscription (and must be called within the async runtime);
+/// it checks the clocks every [`WATCHDOG_INTERVAL`], reporting jumps
+/// exceeding [`CLOCK_JUMP_THRESHOLD`], and exits once all receivers
+/// have been dropped.
+pub fn clock_jump() -> Receiver<ClockJump> {
+    let (sender, receiver) = unbounded();
+    let mut subscribers = SUBSCRIBERS.lock().unwrap();
+    subscribers.push(sender);
+    if !WATCHDOG.swap(true, Ordering::SeqCst) {
+        crate::task::spawn(async move {
+            // allows a new watchdog to be spawned if this task
+            // is dropped (e.g. by the shutdown of its runtime)
+            let running = Running;
+            let watchdog = Watchdog::new(WATCHDOG_INTERVAL, CLOCK_JUMP_THRESHOLD);
+            loop {
+                sleep(WATCHDOG_INTERVAL).await;
+                let jump = watchdog.check();
+                let mut subscribers = SUBSCRIBERS.lock().unwrap();
+                match jump {
+                    Some(jump) => subscribers.retain(|sender| sender.try_send(jump).is_ok()),
+                    None => subscribers.retain(|sender| !sender.is_closed()),
+                }
+                if subscribers.is_empty() {
+                    // reset while holding the lock to not miss a new subscriber
+                    WATCHDOG.store(false, Ordering::SeqCst);
+                    std::mem::forget(running);
+                    break;
+                }
+            }
+        });
+    }
+    receiver
+}
+
+#[cfg(all(test, feature = "dirs", feature = "id"))]
+mod tests {
+    use super::*;
+    use crate::testing::manual_clock;
+    use futures::future::join;
+
+    #[tokio::test]
+    async fn test_sleep_completes_after_suspend() {
+        let clock = manual_clock();
+        let start = std::time::Instant::now();
+        join(sleep(Duration::from_secs(60)), async {
+            crate::task::yield_now().await;
+            clock.suspend(Duration::from_secs(60));
+        })
+        .await;
+        assert!(start.elapsed() <= SLEEP_SLICE * 2);
+
+        let deadline = Instant::now() + Duration::from_millis(20);
+        let sleep = sleep_until(deadline);
+        assert_eq!(sleep.deadline(), deadline);
+        assert!(!sleep.is_elapsed());
+        sleep.await;
+        assert!(Instant::now() >= deadline);
+    }
+
+    #[tokio::test]
+    async fn test_sleep_ignores_wall_clock_adjustments() {
+        let clock = manual_clock();
+        let mut sleep = sleep(Duration::from_secs(60));
+        clock.advance_wall(Duration::from_secs(120));
+        assert!(!sleep.is_elapsed());
+        assert!(
+            tokio::time::timeout(SLEEP_SLICE * 2, &mut sleep)
+                .await
+                .is_err(),
+            "a wall-clock adjustment must not complete the sleep"
+        );
+
+        // a suspend covering the rest of the deadline does
+        clock.suspend(Duration::from_secs(60));
+        assert!(sleep.is_elapsed());
+        sleep.await;
+    }
+
+    #[test]
+    fn test_watchdog_detects_clock_jumps() {
+        let clock = manual_clock();
+        let watchdog = Watchdog::new(WATCHDOG_INTERVAL, CLOCK_JUMP_THRESHOLD);
+        assert_eq!(watchdog.check(), None);
+
+        clock.suspend(Duration::from_secs(30));
+        let jump = watchdog.check().expect("suspend must be detected");
+        assert!(jump.monotonic < WATCHDOG_INTERVAL);
+        assert!((30_000..31_000).contains(&jump.drift_millis()));
+        assert_eq!(jump.suspended, Duration::from_secs(30));
+        assert_eq!(watchdog.check(), None);
+
+        clock.advance_wall(Duration::from_secs(30));
+        let jump = watchdog
+            .check()
+            .expect("wall-clock adjustment must be detected");
+        assert!((30_000..31_000).contains(&jump.drift_millis()));
+        assert_eq!(jump.suspended, Duration::ZERO);
+
+        clock.rewind(Duration::from_secs(10));
+        let jump = watchdog
+            .check()
+            .expect("wall-clock rewind must be detected");
+        assert!(jump.wall_millis <= -9_000);
+
+        // both clocks advance, but the check is late
+        clock.advance(Duration::from_secs(10));
+        let jump = watchdog.check().expect("stall must be detected");
+        assert!(jump.monotonic >= Duration::from_secs(10));
+        assert!(jump.drift_millis().abs() < 1_000);
+    }
+}
@@ -15,6 +15,16 @@ pub fn home_dir() -> Option<PathBuf> {
                 panic!("workflow_core::dirs::home_dir() is not supported on this platform (must be native of nodejs)");
             }
         } else {
+            // same cfg as the `testing` module
+            #[cfg(all(
+                not(target_arch = "bpf"),
+                any(test, feature = "test"),
+                feature = "task",
+                feature = "id"
+            ))]
+            if let Some(dir) = crate::testing::home_dir() {
+                return Some(dir);
+            }
             dirs::home_dir()
         }
     }
@@ -29,6 +39,16 @@ pub fn data_dir() -> Option<PathBuf> {
                 panic!("workflow_core::dirs::home_dir() is not supported on this platform (must be native of nodejs)");
             }
         } else {
+            // same cfg as the `testing` module
+            #[cfg(all(
+                not(target_arch = "bpf"),
+                any(test, feature = "test"),
+                feature = "task",
+                feature = "id"
+            ))]
+            if let Some(dir) = crate::testing::data_dir() {
+                return Some(dir);
+            }
             dirs::data_dir()
         }
     }
@@ -20,6 +20,17 @@ pub fn var(_key: &str) -> Result<String, VarError> {
                 panic!("workflow_core::env::var() is not supported on this platform (must be native of nodejs)");
             }
         } else {
+            // same cfg as the `testing` module
+            #[cfg(all(
+                not(target_arch = "bpf"),
+                any(test, feature = "test"),
+                feature = "dirs",
+                feature = "task",
+                feature = "id"
+            ))]
+            if let Some(value) = crate::testing::var(_key) {
+                return value;
+            }
             std::env::var(_key)
         }
     }
@@ -0,0 +1,113 @@
+//!
+//! Feature matrix tests building `workflow-core` with each cargo
+//! feature in isolation (natively and for the `wasm32` target).
+//! These tests invoke `cargo` and are ignored by default:
+//! `cargo test -p workflow-core --lib features -- --ignored`
+//!
+
+use std::path::PathBuf;
+use std::process::Command;
+
+const FEATURES: &[&str] = &[
+    "channel", "task", "time", "id", "dirs", "env", "lookup", "trigger", "sendable", "full",
+];
+
+const WASM32: &str = "wasm32-unknown-unknown";
+
+/// Runs `cargo check` on this crate with the default features disabled,
+/// returning the compiler output on failure.
+fn check(features: &str, target: Option<&str>) -> Result<(), String> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // separate target directory to avoid contention with the outer build
+    let target_dir = manifest_dir.join("../target/feature-matrix");
+
+    let mut command = Command::new(env!("CARGO"));
+    command
+        .arg("check")
+        .arg("--manifest-path")
+        .arg(manifest_dir.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(target_dir)
+        .args(["--no-default-features", "--features", features]);
+    match target {
+        Some(target) => command.args(["--lib", "--target", target]),
+        None => command.args(["--lib", "--tests"]),
+    };
+
+    let output = command
+        .output()
+        .map_err(|err| format!("unable to run cargo: {err}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).into_owned())
+    }
+}
+
+/// Lists the native (non-dev) dependencies of this crate built with the
+/// default features disabled, returning the crate names.
+fn dependencies(features: &str) -> Result<Vec<String>, String> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let output = Command::new(env!("CARGO"))
+        .arg("tree")
+        .arg("--manifest-path")
+        .arg(manifest_dir.join("Cargo.toml"))
+        .args(["--no-default-features", "--features", features])
+        .args(["--edges", "normal", "--prefix", "none"])
+        .output()
+        .map_err(|err| format!("unable to run cargo: {err}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).into_owned());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(String::from)
+        .collect())
+}
+
+fn check_all(target: Option<&str>) {
+    let failures = FEATURES
+        .iter()
+        .filter_map(|feature| {
+            check(feature, target)
+                .err()
+                .map(|output| format!("feature `{feature}`:\n{output}"))
+        })
+        .collect::<Vec<_>>();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+#[ignore]
+fn test_features_native() {
+    check_all(None);
+}
+
+#[test]
+#[ignore]
+fn test_features_wasm32() {
+    check_all(Some(WASM32));
+}
+
+#[test]
+#[ignore]
+fn test_channel_only_wasm32() {
+    // `default-features = false, features = ["channel"]`
+    if let Err(output) = check("channel", Some(WASM32)) {
+        panic!("channel-only build failed on {WASM32}:\n{output}");
+    }
+}
+
+#[test]
+#[ignore]
+fn test_channel_only_dependencies() {
+    // native-only dependencies of the `task` feature must not be pulled in
+    let dependencies = dependencies("channel").unwrap_or_else(|output| panic!("{output}"));
+    for name in ["ctrlc", "tokio", "async-std"] {
+        assert!(
+            !dependencies.iter().any(|dependency| dependency == name),
+            "channel-only build depends on `{name}`"
+        );
+    }
+}
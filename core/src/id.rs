@@ -1,10 +1,11 @@
 //!
 //! 64-bit random identifier struct [`Id`] that renders its value as a base58 string
+//! and 128-bit time-ordered identifier struct [`SortableId`] (ULID).
 //!
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use std::{fmt, str::FromStr};
+use std::{fmt, str::FromStr, sync::Mutex};
 use thiserror::Error;
 use wasm_bindgen::JsValue;
 
@@ -16,6 +17,10 @@ pub enum Error {
     InvalidBufferSize,
     #[error("Unable to decode id: JsValue must be a string")]
     JsValueNotString,
+    #[error("Invalid character in id: '{0}'")]
+    InvalidCharacter(char),
+    #[error("Id value overflow")]
+    Overflow,
 }
 
 /// 64-bit identifier that renders the value as a base58 string.
@@ -126,3 +131,279 @@ impl<'de> Deserialize<'de> for Id {
         FromStr::from_str(&s).map_err(serde::de::Error::custom)
     }
 }
+
+const TIMESTAMP_BITS: u32 = 48;
+const RANDOM_BITS: u32 = 80;
+const TIMESTAMP_MASK: u64 = (1 << TIMESTAMP_BITS) - 1;
+const RANDOM_MASK: u128 = (1 << RANDOM_BITS) - 1;
+/// Length of the canonical [`SortableId`] string
+const SORTABLE_ID_LEN: usize = 26;
+/// Crockford's base32 alphabet (excludes `I`, `L`, `O` and `U`)
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Last generated `(timestamp, randomness)` pair ensuring
+/// monotonicity of ids generated within the same millisecond
+static LAST_SORTABLE_ID: Mutex<(u64, u128)> = Mutex::new((0, 0));
+
+/// 128-bit time-ordered identifier following the ULID specification:
+/// a 48-bit millisecond unix timestamp followed by 80 bits of randomness,
+/// rendered as a 26-character Crockford base32 string.
+///
+/// Ids sort by their creation time (both as values and as strings), making
+/// them suitable as database keys. Ids generated by the same process are
+/// strictly increasing: within the same millisecond the randomness of the
+/// previous id is incremented. Randomness is obtained from `getrandom`
+/// (`crypto.getRandomValues()` in the browser).
+#[repr(transparent)]
+#[derive(
+    Clone, Copy, Default, Eq, Hash, Ord, PartialEq, PartialOrd, BorshSerialize, BorshDeserialize,
+)]
+pub struct SortableId(pub(crate) [u8; 16]);
+
+impl SortableId {
+    pub fn new() -> SortableId {
+        let now = crate::time::unixtime_as_millis_u64() & TIMESTAMP_MASK;
+        let mut last = LAST_SORTABLE_ID
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        let (timestamp, random) = if now > last.0 {
+            (now, rand::random::<u128>() & RANDOM_MASK)
+        } else if last.1 < RANDOM_MASK {
+            // same millisecond (or the clock went backwards)
+            (last.0, last.1 + 1)
+        } else {
+            // randomness exhausted, advance to the next millisecond
+            (
+                (last.0 + 1) & TIMESTAMP_MASK,
+                rand::random::<u128>() & RANDOM_MASK,
+            )
+        };
+        *last = (timestamp, random);
+        Self::from_parts(timestamp, random)
+    }
+
+    /// Creates an id from a millisecond unix timestamp (truncated to 48 bits)
+    /// and randomness (truncated to 80 bits).
+    pub fn from_parts(timestamp: u64, random: u128) -> Self {
+        let value =
+            (((timestamp & TIMESTAMP_MASK) as u128) << RANDOM_BITS) | (random & RANDOM_MASK);
+        Self(value.to_be_bytes())
+    }
+
+    pub fn new_from_slice(vec: &[u8]) -> Self {
+        Self(
+            <[u8; 16]>::try_from(<&[u8]>::clone(&vec))
+                .expect("Error: invalid slice size for sortable id"),
+        )
+    }
+
+    pub fn to_bytes(self) -> [u8; 16] {
+        self.0
+    }
+
+    /// Millisecond unix timestamp of the id creation
+    pub fn timestamp(&self) -> u64 {
+        (self.as_u128() >> RANDOM_BITS) as u64
+    }
+
+    /// Random component of the id (80 bits)
+    pub fn random(&self) -> u128 {
+        self.as_u128() & RANDOM_MASK
+    }
+
+    fn as_u128(&self) -> u128 {
+        u128::from_be_bytes(self.0)
+    }
+}
+
+impl From<SortableId> for String {
+    fn from(id: SortableId) -> Self {
+        id.to_string()
+    }
+}
+
+impl AsRef<[u8]> for SortableId {
+    fn as_ref(&self) -> &[u8] {
+        &self.0[..]
+    }
+}
+
+impl AsMut<[u8]> for SortableId {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.0[..]
+    }
+}
+
+impl fmt::Debug for SortableId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for SortableId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.as_u128();
+        // the leading character encodes the top 3 bits,
+        // followed by 25 characters encoding 5 bits each
+        let mut buf = [0u8; SORTABLE_ID_LEN];
+        for (idx, c) in buf.iter_mut().enumerate() {
+            let shift = 5 * (SORTABLE_ID_LEN - 1 - idx);
+            *c = CROCKFORD[((value >> shift) & 0x1f) as usize];
+        }
+        // the alphabet is ASCII
+        f.write_str(std::str::from_utf8(&buf).unwrap())
+    }
+}
+
+/// Decodes a Crockford base32 character (case-insensitive,
+/// `I` and `L` decode as `1`, `O` decodes as `0`)
+fn crockford_decode(c: char) -> Option<u8> {
+    let value = match c.to_ascii_uppercase() {
+        c @ '0'..='9' => c as u8 - b'0',
+        'O' => 0,
+        'I' | 'L' => 1,
+        c => CROCKFORD.iter().position(|&v| v as char == c)? as u8,
+    };
+    Some(value)
+}
+
+impl FromStr for SortableId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != SORTABLE_ID_LEN {
+            return Err(Error::InvalidBufferSize);
+        }
+        let mut value = 0u128;
+        for (idx, c) in s.chars().enumerate() {
+            let digit = crockford_decode(c).ok_or(Error::InvalidCharacter(c))?;
+            // the leading character carries only 3 bits
+            if idx == 0 && digit > 7 {
+                return Err(Error::Overflow);
+            }
+            value = (value << 5) | digit as u128;
+        }
+        Ok(SortableId(value.to_be_bytes()))
+    }
+}
+
+impl TryFrom<&str> for SortableId {
+    type Error = Error;
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        SortableId::from_str(s)
+    }
+}
+
+impl TryFrom<JsValue> for SortableId {
+    type Error = Error;
+    fn try_from(value: JsValue) -> Result<Self, Self::Error> {
+        let value_str = value.as_string().ok_or(Error::JsValueNotString)?;
+        FromStr::from_str(&value_str)
+    }
+}
+
+impl From<SortableId> for JsValue {
+    fn from(id: SortableId) -> Self {
+        JsValue::from_str(&id.to_string())
+    }
+}
+
+impl Serialize for SortableId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for SortableId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <std::string::String as Deserialize>::deserialize(deserializer)?;
+        FromStr::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn test_sortable_id_ordering() {
+        let start = crate::time::unixtime_as_millis_u64();
+        let ids = (0..10_000).map(|_| SortableId::new()).collect::<Vec<_>>();
+        let end = crate::time::unixtime_as_millis_u64();
+
+        let strings = ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        for idx in 1..ids.len() {
+            assert!(ids[idx - 1] < ids[idx]);
+            assert!(strings[idx - 1] < strings[idx]);
+            assert!(ids[idx - 1].timestamp() <= ids[idx].timestamp());
+        }
+        assert!(ids
+            .iter()
+            .all(|id| (start..=end + 1).contains(&id.timestamp())));
+
+        // batches generated later sort after earlier ones
+        let later = SortableId::new();
+        assert!(ids.iter().all(|id| *id < later));
+    }
+
+    #[test]
+    fn test_sortable_id_encoding() {
+        // ULID specification example timestamp
+        let id = SortableId::from_parts(1469918176385, 0);
+        assert_eq!(id.to_string(), "01ARYZ6S410000000000000000");
+        assert_eq!(id.timestamp(), 1469918176385);
+
+        let id = SortableId::from_parts(u64::MAX, u128::MAX);
+        assert_eq!(id.to_string(), "7ZZZZZZZZZZZZZZZZZZZZZZZZZ");
+        assert_eq!(id.timestamp(), TIMESTAMP_MASK);
+        assert_eq!(id.random(), RANDOM_MASK);
+        assert_eq!(SortableId::default().to_string(), "0".repeat(26));
+    }
+
+    #[test]
+    fn test_sortable_id_parsing() {
+        let id = SortableId::new();
+        let s = id.to_string();
+        assert_eq!(s.len(), 26);
+        assert_eq!(s.parse::<SortableId>().unwrap(), id);
+        assert_eq!(SortableId::try_from(s.to_lowercase().as_str()).unwrap(), id);
+        assert_eq!(
+            "01ARYZ6S41TSV4RRFFQ69G5FAV".parse::<SortableId>().unwrap(),
+            "01aryz6s41tsv4rrffq69g5fav".parse::<SortableId>().unwrap()
+        );
+        assert_eq!(
+            "0IL0000000000000000000000O".parse::<SortableId>().unwrap(),
+            "01100000000000000000000000".parse::<SortableId>().unwrap()
+        );
+
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, format!("\"{s}\""));
+        assert_eq!(serde_json::from_str::<SortableId>(&json).unwrap(), id);
+
+        let bytes = borsh::to_vec(&id).unwrap();
+        assert_eq!(bytes, id.to_bytes());
+        assert_eq!(borsh::from_slice::<SortableId>(&bytes).unwrap(), id);
+
+        assert!(matches!(
+            "01ARYZ6S41".parse::<SortableId>(),
+            Err(Error::InvalidBufferSize)
+        ));
+        assert!(matches!(
+            "01ARYZ6S41TSV4RRFFQ69G5FAU".parse::<SortableId>(),
+            Err(Error::InvalidCharacter('U'))
+        ));
+        assert!(matches!(
+            "81ARYZ6S41TSV4RRFFQ69G5FAV".parse::<SortableId>(),
+            Err(Error::Overflow)
+        ));
+    }
+}
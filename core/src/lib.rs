@@ -17,31 +17,68 @@
 //! - task spawn, sleep and interval functions
 //! - random identifiers
 //! - async-friendly and thread-safe event triggers
+//! - retry combinator with exponential backoff
+//! - graceful shutdown coordination with ordered phases and native signal handling
 //! - time (Instant and Duration) as well as functions to obtain UNIX time (native and WASM)
 //! - yield_executor() function to yield Rust executor to browser using `requestAnimationFrame()` (this prevents async Rust applications from locking down the Browser UX)
 //! - runtime auto detection, allowing to identify the operating environment at runtime
 //! - home and data folder access (useful when combined with `workflow_store` crate)
+//!
+//! # Features
+//!
+//! The functionality is gated by the following cargo features, all of which
+//! are enabled by the default `full` feature:
+//!
+//! - `channel` - async channels, [`oneshot`](channel::oneshot), duplex channels and [`Select`](channel::Select)
+//! - `task` - task spawn, sleep, interval and yield functions (enables `channel` and `time`)
+//! - `time` - [`Instant`](time::Instant), [`Duration`](time::Duration) and UNIX time functions
+//! - `id` - random and sortable identifiers (enables `time` and the channel [`Multiplexer`](channel::Multiplexer))
+//! - `dirs` - home and data folder access (enables `env`)
+//! - `env` - environment variable access and feature toggles
+//! - `lookup` - async object lookup combinator
+//! - `trigger` - event triggers and the [`Abortable`](abortable::Abortable) signal
+//! - `sendable` - [`Sendable`](sendable::Sendable) wrapper for non-`Send` values
+//!
+//! Crates that only need a subset of the functionality can depend on
+//! `workflow-core` with `default-features = false` and declare the
+//! required features, for example `features = ["channel"]`.
 
 extern crate self as workflow_core;
 
+#[cfg(feature = "trigger")]
 pub mod abortable;
 pub mod enums;
 pub mod extensions;
 pub mod prelude;
 pub mod runtime;
+#[cfg(feature = "sendable")]
 pub mod sendable;
 pub mod utils;
 
 #[cfg(feature = "version")]
 pub mod version;
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "task"))]
 mod native;
 mod wasm;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub mod fd;
 
+// sandboxed `dirs` and `env` for tests
+#[cfg(all(
+    not(any(target_arch = "wasm32", target_arch = "bpf")),
+    any(test, feature = "test"),
+    feature = "dirs",
+    feature = "task",
+    feature = "id"
+))]
+pub mod testing;
+
+// builds of each cargo feature in isolation
+#[cfg(all(test, not(any(target_arch = "wasm32", target_arch = "bpf"))))]
+mod features;
+
 /// Seal macro that prevents accidental modification of the enclosed source code
 /// by hashing the source code and comparing it to the supplied hash.  If the code
 /// is modified, the macro will fail to compile, and the developer will need to
@@ -49,25 +86,49 @@ pub mod fd;
 /// code to prevent their accidental change.
 pub use workflow_core_macros::seal;
 
+// modules exporting macros are declared outside of `cfg_if!` so that
+// the macros can be referred to via `$crate` paths within this crate
+
+// channel re-exports and shims
+#[cfg(all(not(target_arch = "bpf"), feature = "channel"))]
+pub mod channel;
+// feature toggles (env, JSON document and runtime overrides)
+#[cfg(all(not(target_arch = "bpf"), feature = "env"))]
+pub mod toggles;
+
 cfg_if::cfg_if! {
 
 
     if #[cfg(not(target_arch = "bpf"))] {
-        // Generic 8-byte identifier
+        // Generic 8-byte identifier and sortable (ULID) identifier
+        #[cfg(feature = "id")]
         pub mod id;
         // task re-exports and shims
+        #[cfg(feature = "task")]
         pub mod task;
-        // channel re-exports and shims
-        pub mod channel;
         // async object lookup combinator
+        #[cfg(feature = "lookup")]
         pub mod lookup;
+        // retry combinator with exponential backoff
+        #[cfg(all(feature = "task", feature = "trigger", feature = "id"))]
+        pub mod retry;
+        // graceful shutdown coordinator
+        #[cfg(all(feature = "task", feature = "trigger"))]
+        pub mod shutdown;
         // time functions and utilities
+        #[cfg(feature = "time")]
         pub mod time;
+        // suspend-aware sleep and clock discontinuity detection
+        #[cfg(feature = "task")]
+        pub mod clock;
         // environment variable access (native and Node.js abstraction)
+        #[cfg(feature = "env")]
         pub mod env;
         // Directory access (home folder, data folder) (native and Node.js abstraction)
+        #[cfg(feature = "dirs")]
         pub mod dirs;
         /// Trigger crate re-exports and shims
+        #[cfg(feature = "trigger")]
         pub mod trigger;
         // hex serialization traits
         pub mod hex;
@@ -8,18 +8,144 @@
 //! key will get queued into a set of futures all of which will resolve once
 //! the initial request is resolved.
 //!
+//! A handler created using [`LookupHandler::new_batched()`] additionally
+//! coalesces lookups of distinct keys issued via [`LookupHandler::lookup()`]
+//! into a single call of the batch function (for backends capable of
+//! fetching multiple keys in one request).
+//!
 
 #![allow(unused)]
 
 use crate::channel::*;
+use crate::task::{sleep, spawn};
+use crate::time::Duration;
+use futures::future::BoxFuture;
 use std::collections::HashMap;
 use std::hash::Hash;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
+use thiserror::Error;
 
 /// Custom result type used by [`LookupHandler`]
 pub type LookupResult<V, E> = std::result::Result<V, E>;
+
+/// Error produced by [`LookupHandler::lookup()`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum LookupError<E> {
+    /// The key is missing from the result of the batch function.
+    #[error("lookup key not found")]
+    NotFound,
+    /// The batch function has failed for the key.
+    #[error("{0}")]
+    Failed(E),
+    /// The batch has been terminated without producing a result
+    /// (i.e. the batch function has panicked).
+    #[error("lookup batch terminated")]
+    Terminated,
+}
+
+/// Batch function supplied to [`LookupHandler::new_batched()`]
+pub type BatchFn<K, V, E> =
+    Arc<dyn Fn(Vec<K>) -> BoxFuture<'static, HashMap<K, LookupResult<V, E>>> + Send + Sync>;
+
+type BatchSender<V, E> = Sender<LookupResult<V, LookupError<E>>>;
+
+struct BatchState<K, V, E> {
+    /// Keys of the batch being collected
+    queue: Vec<K>,
+    /// Waiters of the collected and in-flight keys
+    waiters: HashMap<K, Vec<BatchSender<V, E>>>,
+    /// Number of batches dispatched so far
+    generation: u64,
+}
+
+struct Batch<K, V, E> {
+    max_batch: usize,
+    max_delay: Duration,
+    batch_fn: BatchFn<K, V, E>,
+    state: Mutex<BatchState<K, V, E>>,
+}
+
+impl<K, V, E> Batch<K, V, E>
+where
+    K: Clone + Eq + Hash + Send + Sync + 'static,
+    V: Clone + Send + 'static,
+    E: Clone + Send + 'static,
+{
+    /// Takes the collected keys (if the batch of the `generation` is
+    /// still being collected) and spawns the call of the batch function.
+    /// The batch is executed in its own task so that it completes even
+    /// if the waiters are dropped.
+    fn dispatch(self: &Arc<Self>, generation: u64) {
+        let keys = {
+            let mut state = self.state.lock().unwrap();
+            if state.generation != generation || state.queue.is_empty() {
+                return;
+            }
+            state.generation += 1;
+            std::mem::take(&mut state.queue)
+        };
+
+        let in_flight = InFlight {
+            batch: self.clone(),
+            keys,
+        };
+        spawn(async move {
+            let results = (in_flight.batch.batch_fn)(in_flight.keys.clone()).await;
+            in_flight.complete(results);
+        });
+    }
+}
+
+/// Keys of a dispatched batch. Waiters of keys that have not been
+/// completed when this is dropped (i.e. the batch function has panicked)
+/// are dropped, resolving to [`LookupError::Terminated`].
+struct InFlight<K, V, E>
+where
+    K: Eq + Hash,
+{
+    batch: Arc<Batch<K, V, E>>,
+    keys: Vec<K>,
+}
+
+impl<K, V, E> InFlight<K, V, E>
+where
+    K: Eq + Hash,
+    V: Clone,
+    E: Clone,
+{
+    fn complete(mut self, mut results: HashMap<K, LookupResult<V, E>>) {
+        let mut state = self.batch.state.lock().unwrap();
+        for key in std::mem::take(&mut self.keys) {
+            let result = match results.remove(&key) {
+                Some(Ok(value)) => Ok(value),
+                Some(Err(err)) => Err(LookupError::Failed(err)),
+                None => Err(LookupError::NotFound),
+            };
+            for sender in state.waiters.remove(&key).unwrap_or_default() {
+                // the waiter may have been dropped
+                sender.try_send(result.clone()).ok();
+            }
+        }
+    }
+}
+
+impl<K, V, E> Drop for InFlight<K, V, E>
+where
+    K: Eq + Hash,
+{
+    fn drop(&mut self) {
+        let mut state = self
+            .batch
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for key in self.keys.iter() {
+            state.waiters.remove(key);
+        }
+    }
+}
 pub enum RequestType<V, E> {
     New(Receiver<LookupResult<V, E>>),
     Pending(Receiver<LookupResult<V, E>>),
@@ -64,6 +190,7 @@ pub type SenderList<V, E> = Vec<Sender<LookupResult<V, E>>>;
 pub struct LookupHandler<K, V, E> {
     pub map: Arc<Mutex<HashMap<K, SenderList<V, E>>>>,
     pending: AtomicUsize,
+    batch: Option<Arc<Batch<K, V, E>>>,
 }
 
 /// Default trait for the LookupHandler
@@ -89,12 +216,18 @@ where
         LookupHandler {
             map: Arc::new(Mutex::new(HashMap::new())),
             pending: AtomicUsize::new(0),
+            batch: None,
         }
     }
 
     /// Returns the total number of pending requests
+    /// (including keys awaiting a batched lookup)
     pub fn pending(&self) -> usize {
-        self.pending.load(Ordering::SeqCst)
+        let batched = self
+            .batch
+            .as_ref()
+            .map_or(0, |batch| batch.state.lock().unwrap().waiters.len());
+        self.pending.load(Ordering::SeqCst) + batched
     }
 
     /// Queue the request for key `K`. Returns [`RequestType::New`] if
@@ -135,9 +268,94 @@ where
     }
 }
 
+impl<K, V, E> LookupHandler<K, V, E>
+where
+    K: Clone + Eq + Hash + std::fmt::Debug + Send + Sync + 'static,
+    V: Clone + Send + 'static,
+    E: Clone + Send + 'static,
+{
+    /// Create a LookupHandler coalescing the keys requested via
+    /// [`LookupHandler::lookup()`] into a single call of the `batch_fn`.
+    /// The batch is dispatched `max_delay` after its first key has been
+    /// requested or once it contains `max_batch` keys.
+    ///
+    /// ```ignore
+    /// let handler = LookupHandler::new_batched(64, Duration::from_millis(5), |keys: Vec<Pubkey>| {
+    ///     Box::pin(async move { backend.get_many(keys).await })
+    /// });
+    /// let data = handler.lookup(&pubkey).await?;
+    /// ```
+    pub fn new_batched<F>(max_batch: usize, max_delay: Duration, batch_fn: F) -> Self
+    where
+        F: Fn(Vec<K>) -> BoxFuture<'static, HashMap<K, LookupResult<V, E>>> + Send + Sync + 'static,
+    {
+        assert!(max_batch > 0, "lookup max_batch must be greater than 0");
+        LookupHandler {
+            batch: Some(Arc::new(Batch {
+                max_batch,
+                max_delay,
+                batch_fn: Arc::new(batch_fn),
+                state: Mutex::new(BatchState {
+                    queue: Vec::new(),
+                    waiters: HashMap::new(),
+                    generation: 0,
+                }),
+            })),
+            ..Self::new()
+        }
+    }
+
+    /// Lookup the `key` using the batch function. Concurrent lookups of the
+    /// same key share a single result. Keys missing from the result of the
+    /// batch function resolve to [`LookupError::NotFound`]. Dropping the
+    /// returned future does not cancel the batch.
+    ///
+    /// Panics if the handler has not been created using [`LookupHandler::new_batched()`].
+    pub async fn lookup(&self, key: &K) -> LookupResult<V, LookupError<E>> {
+        let batch = self
+            .batch
+            .as_ref()
+            .expect("LookupHandler::lookup() requires a batched handler");
+        let (sender, receiver) = oneshot();
+
+        let dispatch = {
+            let mut state = batch.state.lock().unwrap();
+            if let Some(waiters) = state.waiters.get_mut(key) {
+                waiters.push(sender);
+                None
+            } else {
+                state.waiters.insert(key.clone(), vec![sender]);
+                state.queue.push(key.clone());
+                let generation = state.generation;
+                if state.queue.len() >= batch.max_batch {
+                    Some(generation)
+                } else {
+                    if state.queue.len() == 1 {
+                        let batch = batch.clone();
+                        spawn(async move {
+                            sleep(batch.max_delay).await;
+                            batch.dispatch(generation);
+                        });
+                    }
+                    None
+                }
+            }
+        };
+        if let Some(generation) = dispatch {
+            batch.dispatch(generation);
+        }
+
+        receiver
+            .recv()
+            .await
+            .unwrap_or(Err(LookupError::Terminated))
+    }
+}
+
 #[cfg(not(target_arch = "bpf"))]
 #[cfg(any(test, feature = "test"))]
 mod tests {
+    use super::LookupError;
     use super::LookupHandler;
     use super::RequestType;
     use std::sync::Arc;
@@ -146,11 +364,13 @@ mod tests {
     use std::time::Duration;
 
     use crate::task::sleep;
+    use futures::future::join_all;
     use futures::join;
+    use futures::FutureExt;
     use std::collections::HashMap;
     use workflow_core::channel::RecvError;
 
-    #[derive(thiserror::Error, Debug, Clone)]
+    #[derive(thiserror::Error, Debug, Clone, PartialEq)]
     pub enum Error {
         #[error("{0}")]
         String(String),
@@ -263,6 +483,87 @@ mod tests {
         Ok(())
     }
 
+    /// Batched handler recording the keys of each batch. Even keys
+    /// resolve to `key * 10`, keys divisible by 3 (and odd) fail and
+    /// other keys are missing from the result.
+    fn batched_handler(
+        max_batch: usize,
+        batches: Arc<Mutex<Vec<Vec<u32>>>>,
+    ) -> LookupHandler<u32, u32, Error> {
+        LookupHandler::new_batched(
+            max_batch,
+            Duration::from_millis(20),
+            move |keys: Vec<u32>| {
+                batches.lock().unwrap().push(keys.clone());
+                Box::pin(async move {
+                    sleep(Duration::from_millis(10)).await;
+                    keys.into_iter()
+                        .filter_map(|key| match key {
+                            key if key % 2 == 0 => Some((key, Ok(key * 10))),
+                            key if key % 3 == 0 => {
+                                Some((key, Err(Error::String(format!("{key}")))))
+                            }
+                            _ => None,
+                        })
+                        .collect::<HashMap<_, _>>()
+                })
+            },
+        )
+    }
+
+    pub async fn batched_lookup_test() -> Result<()> {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let handler = batched_handler(16, batches.clone());
+
+        let keys = (0..10).collect::<Vec<u32>>();
+        let results = join_all(keys.iter().map(|key| handler.lookup(key))).await;
+        assert_eq!(batches.lock().unwrap().len(), 1);
+        assert_eq!(batches.lock().unwrap()[0], keys);
+        for (key, result) in (0..10).zip(results) {
+            match key {
+                key if key % 2 == 0 => assert_eq!(result, Ok(key * 10)),
+                key if key % 3 == 0 => {
+                    assert!(
+                        matches!(result, Err(LookupError::Failed(Error::String(err))) if err == key.to_string())
+                    )
+                }
+                _ => assert!(matches!(result, Err(LookupError::NotFound))),
+            }
+        }
+        assert_eq!(handler.pending(), 0);
+
+        // concurrent lookups of the same key share a single batch entry
+        let results = join_all([4, 4, 6].iter().map(|key| handler.lookup(key))).await;
+        assert_eq!(batches.lock().unwrap()[1], [4, 6]);
+        assert_eq!(results, [Ok(40), Ok(40), Ok(60)]);
+
+        Ok(())
+    }
+
+    pub async fn batched_lookup_max_batch_test() -> Result<()> {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let handler = batched_handler(2, batches.clone());
+
+        let results = join_all([0, 2, 4, 6, 8].iter().map(|key| handler.lookup(key))).await;
+        assert_eq!(results, [Ok(0), Ok(20), Ok(40), Ok(60), Ok(80)]);
+        assert_eq!(*batches.lock().unwrap(), [vec![0, 2], vec![4, 6], vec![8]]);
+
+        Ok(())
+    }
+
+    pub async fn batched_lookup_cancellation_test() -> Result<()> {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let handler = batched_handler(16, batches.clone());
+
+        // the dropped waiter does not cancel the batch
+        assert!(handler.lookup(&2).now_or_never().is_none());
+        assert_eq!(handler.lookup(&4).await, Ok(40));
+        assert_eq!(*batches.lock().unwrap(), [vec![2, 4]]);
+        assert_eq!(handler.pending(), 0);
+
+        Ok(())
+    }
+
     #[cfg(not(any(target_arch = "wasm32", target_arch = "bpf")))]
     #[cfg(test)]
     mod native_tests {
@@ -272,5 +573,20 @@ mod tests {
         pub async fn lookup_handler_test() -> Result<()> {
             super::lookup_handler_test().await
         }
+
+        #[tokio::test]
+        pub async fn batched_lookup_test() -> Result<()> {
+            super::batched_lookup_test().await
+        }
+
+        #[tokio::test]
+        pub async fn batched_lookup_max_batch_test() -> Result<()> {
+            super::batched_lookup_max_batch_test().await
+        }
+
+        #[tokio::test]
+        pub async fn batched_lookup_cancellation_test() -> Result<()> {
+            super::batched_lookup_cancellation_test().await
+        }
     }
 }
@@ -0,0 +1,33 @@
+//!
+//! `dispatch_blocking()` executing CPU-bound work on the
+//! Tokio blocking thread pool.
+//!
+
+use crate::task::BlockingMode;
+use std::ops::ControlFlow;
+
+/// Execution mode of [`dispatch_blocking()`] on native platforms.
+pub fn blocking_mode() -> BlockingMode {
+    BlockingMode::ThreadPool
+}
+
+/// Executes the `step` closure until it returns [`ControlFlow::Break`]
+/// on the Tokio blocking thread pool, leaving the async executor free
+/// to make progress. A panic within `step` is propagated to the caller.
+pub async fn dispatch_blocking<F, T>(mut step: F) -> T
+where
+    F: FnMut() -> ControlFlow<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let handle = tokio::task::spawn_blocking(move || loop {
+        if let ControlFlow::Break(value) = step() {
+            break value;
+        }
+    });
+
+    match handle.await {
+        Ok(value) => value,
+        Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+        Err(err) => panic!("dispatch_blocking() task failure: {err}"),
+    }
+}
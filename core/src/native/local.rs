@@ -0,0 +1,140 @@
+//!
+//! Local (single-threaded) executor for `!Send` futures on native
+//! platforms, backing [`spawn_local()`](crate::task::spawn_local) and
+//! [`spawn_local_with()`](crate::task::spawn_local_with).
+//!
+//! The executor is a Tokio current-thread runtime driving a [`LocalSet`]
+//! on a dedicated background thread started on first use. The runtime has
+//! the time and I/O drivers enabled, so [`sleep()`](crate::task::sleep),
+//! intervals and channels function within local tasks.
+//!
+
+use futures::Future;
+use std::cell::Cell;
+use std::sync::OnceLock;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::task::LocalSet;
+
+type Spawner = Box<dyn FnOnce() + Send>;
+
+static EXECUTOR: OnceLock<UnboundedSender<Spawner>> = OnceLock::new();
+
+thread_local! {
+    static IS_EXECUTOR_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
+fn executor() -> &'static UnboundedSender<Spawner> {
+    EXECUTOR.get_or_init(|| {
+        let (sender, mut receiver) = unbounded_channel::<Spawner>();
+        std::thread::Builder::new()
+            .name("workflow-local-executor".to_string())
+            .spawn(move || {
+                IS_EXECUTOR_THREAD.with(|flag| flag.set(true));
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .expect("unable to create the local executor runtime");
+                LocalSet::new().block_on(&runtime, async move {
+                    while let Some(spawner) = receiver.recv().await {
+                        spawner();
+                    }
+                });
+            })
+            .expect("unable to start the local executor thread");
+        sender
+    })
+}
+
+/// `true` if called from within a task of the local executor
+pub fn is_local_executor() -> bool {
+    IS_EXECUTOR_THREAD.with(|flag| flag.get())
+}
+
+/// Spawn a `!Send` future on the current [`LocalSet`]. This function must be
+/// called from a task running on the local executor (see [`spawn_local_with()`])
+/// or from within a user-managed [`LocalSet`]; a `!Send` future can not migrate
+/// to the executor thread from any other thread.
+///
+/// # Panics
+///
+/// Panics if called outside of a [`LocalSet`] context.
+pub fn spawn_local<F, T>(future: F)
+where
+    F: Future<Output = T> + 'static,
+    T: 'static,
+{
+    tokio::task::spawn_local(future);
+}
+
+/// Spawn a `!Send` future on the local executor from any thread. The `factory`
+/// is sent to the executor thread where it creates the future, allowing the
+/// future to hold `Rc`/`RefCell`-based state.
+pub fn spawn_local_with<F, Fut>(factory: F)
+where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: Future + 'static,
+{
+    if is_local_executor() {
+        tokio::task::spawn_local(factory());
+    } else {
+        executor()
+            .send(Box::new(move || {
+                tokio::task::spawn_local(factory());
+            }))
+            .unwrap_or_else(|_| panic!("the local executor has terminated"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::oneshot;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_spawn_local_rc_state() {
+        let (sender, receiver) = oneshot::<(usize, bool)>();
+        spawn_local_with(move || async move {
+            let state = Rc::new(RefCell::new(Vec::new()));
+            let (done_sender, done_receiver) = oneshot::<()>();
+            let nested = state.clone();
+            spawn_local(async move {
+                for n in 0..3 {
+                    crate::task::sleep(Duration::from_millis(5)).await;
+                    nested.borrow_mut().push(n);
+                }
+                done_sender.send(()).await.unwrap();
+            });
+            done_receiver.recv().await.unwrap();
+            let len = state.borrow().len();
+            sender.send((len, is_local_executor())).await.unwrap();
+        });
+
+        let (len, local) = receiver.recv().await.unwrap();
+        assert_eq!(len, 3);
+        assert!(local);
+        assert!(!is_local_executor());
+    }
+
+    #[test]
+    fn test_spawn_local_with_from_plain_thread() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            spawn_local_with(move || async move {
+                let counter = Rc::new(Cell::new(0));
+                for _ in 0..4 {
+                    tokio::task::yield_now().await;
+                    counter.set(counter.get() + 1);
+                }
+                sender.send(counter.get()).unwrap();
+            });
+        })
+        .join()
+        .unwrap();
+
+        let value = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(value, 4);
+    }
+}
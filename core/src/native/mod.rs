@@ -1,2 +1,5 @@
+pub mod blocking;
 pub mod interval;
 pub mod overrides;
+#[cfg(not(any(target_arch = "wasm32", target_arch = "bpf")))]
+pub mod local;
@@ -1,8 +1,15 @@
 //! The prelude module re-exports the most commonly used traits and types from the workflow_core crate.
+#[cfg(feature = "trigger")]
 pub use crate::abortable::Abortable;
-pub use crate::channel::{oneshot, Channel, DuplexChannel, Multiplexer};
+#[cfg(all(feature = "channel", feature = "id"))]
+pub use crate::channel::Multiplexer;
+#[cfg(feature = "channel")]
+pub use crate::channel::{oneshot, Channel, DuplexChannel};
 pub use crate::enums::Describe;
 pub use crate::extensions::*;
+#[cfg(feature = "sendable")]
 pub use crate::sendable::Sendable;
+#[cfg(feature = "task")]
 pub use crate::task::{dispatch, interval, sleep, spawn, yield_executor, yield_now};
+#[cfg(feature = "time")]
 pub use crate::time::{unixtime_as_millis_f64, unixtime_as_millis_u128, Duration, Instant};
@@ -0,0 +1,380 @@
+//!
+//! Retry combinator with exponential backoff, based on
+//! [`task::sleep()`](crate::task::sleep) (native and WASM).
+//!
+//! ```ignore
+//! let policy = Policy::new()
+//!     .with_max_attempts(5)
+//!     .with_initial_delay(Duration::from_millis(250))
+//!     .with_jitter(0.2)
+//!     .with_retryable(|err: &Error| !matches!(err, Error::Unauthorized));
+//!
+//! let response = retry(&policy, || async { client.get(&url).await }).await?;
+//! ```
+//!
+
+use crate::abortable::Abortable;
+use crate::task::sleep;
+use crate::time::Duration;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Error produced by [`retry()`].
+#[derive(Error, Debug)]
+pub enum RetryError<E> {
+    /// The error is not retryable according to [`Policy::with_retryable()`].
+    #[error("{0}")]
+    Fatal(E),
+    /// The maximum number of attempts has been reached.
+    #[error("failure after {attempts} attempts: {error}")]
+    Exhausted { attempts: usize, error: E },
+    /// Retrying has been aborted using the [`Abortable`] supplied
+    /// via [`Policy::with_abortable()`].
+    #[error("retry aborted")]
+    Aborted,
+}
+
+impl<E> RetryError<E> {
+    /// Returns the last error produced by the operation.
+    pub fn into_inner(self) -> Option<E> {
+        match self {
+            RetryError::Fatal(error) | RetryError::Exhausted { error, .. } => Some(error),
+            RetryError::Aborted => None,
+        }
+    }
+}
+
+/// Failed attempt reported to [`Policy::with_on_retry()`] callback.
+pub struct Attempt<'e, E> {
+    /// Number of the failed attempt (starting at 1).
+    pub attempt: usize,
+    pub error: &'e E,
+    /// Delay before the next attempt.
+    pub delay: Duration,
+}
+
+type RetryableFn<E> = Arc<dyn Fn(&E) -> bool + Send + Sync>;
+type OnRetryFn<E> = Arc<dyn Fn(&Attempt<'_, E>) + Send + Sync>;
+
+/// Retry policy used by [`retry()`]. The delay after the failed attempt
+/// `n` is `initial_delay * multiplier^(n-1)`, capped at `max_delay` and
+/// randomized by `±jitter` (a fraction of the delay).
+pub struct Policy<E> {
+    max_attempts: Option<usize>,
+    initial_delay: Duration,
+    multiplier: f64,
+    max_delay: Duration,
+    jitter: f64,
+    retryable: Option<RetryableFn<E>>,
+    on_retry: Option<OnRetryFn<E>>,
+    abortable: Option<Abortable>,
+}
+
+impl<E> Clone for Policy<E> {
+    fn clone(&self) -> Self {
+        Self {
+            max_attempts: self.max_attempts,
+            initial_delay: self.initial_delay,
+            multiplier: self.multiplier,
+            max_delay: self.max_delay,
+            jitter: self.jitter,
+            retryable: self.retryable.clone(),
+            on_retry: self.on_retry.clone(),
+            abortable: self.abortable.clone(),
+        }
+    }
+}
+
+impl<E> fmt::Debug for Policy<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Policy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_delay", &self.initial_delay)
+            .field("multiplier", &self.multiplier)
+            .field("max_delay", &self.max_delay)
+            .field("jitter", &self.jitter)
+            .finish()
+    }
+}
+
+impl<E> Default for Policy<E> {
+    fn default() -> Self {
+        Self {
+            max_attempts: Some(5),
+            initial_delay: Duration::from_millis(100),
+            multiplier: 2.0,
+            max_delay: Duration::from_secs(30),
+            jitter: 0.0,
+            retryable: None,
+            on_retry: None,
+            abortable: None,
+        }
+    }
+}
+
+impl<E> Policy<E> {
+    /// Creates a policy with 5 attempts, an initial delay of 100 msec
+    /// doubled after each attempt up to 30 seconds and no jitter.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of attempts including the first one.
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = Some(max_attempts.max(1));
+        self
+    }
+
+    /// Retries until the operation succeeds, fails with a fatal error
+    /// or is aborted.
+    pub fn with_unlimited_attempts(mut self) -> Self {
+        self.max_attempts = None;
+        self
+    }
+
+    pub fn with_initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    pub fn with_max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Randomizes each delay by up to `±jitter` of its value
+    /// (clamped to `0.0..=1.0`).
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Predicate deciding whether an error is retryable; errors
+    /// are retryable by default.
+    pub fn with_retryable<F>(mut self, retryable: F) -> Self
+    where
+        F: Fn(&E) -> bool + Send + Sync + 'static,
+    {
+        self.retryable = Some(Arc::new(retryable));
+        self
+    }
+
+    /// Callback invoked after each failed attempt that will be retried.
+    pub fn with_on_retry<F>(mut self, on_retry: F) -> Self
+    where
+        F: Fn(&Attempt<'_, E>) + Send + Sync + 'static,
+    {
+        self.on_retry = Some(Arc::new(on_retry));
+        self
+    }
+
+    /// Allows retrying to be cancelled between attempts.
+    pub fn with_abortable(mut self, abortable: &Abortable) -> Self {
+        self.abortable = Some(abortable.clone());
+        self
+    }
+
+    pub fn max_attempts(&self) -> Option<usize> {
+        self.max_attempts
+    }
+
+    pub fn is_retryable(&self, error: &E) -> bool {
+        self.retryable
+            .as_ref()
+            .is_none_or(|retryable| retryable(error))
+    }
+
+    /// Delay after the failed `attempt` (starting at 1) without jitter.
+    pub fn base_delay(&self, attempt: usize) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as usize) as i32;
+        let nanos = self.initial_delay.as_nanos() as f64 * self.multiplier.powi(exponent);
+        if nanos.is_finite() && nanos < self.max_delay.as_nanos() as f64 {
+            Duration::from_nanos(nanos.round() as u64)
+        } else {
+            self.max_delay
+        }
+    }
+
+    /// Delay after the failed `attempt` (starting at 1) including jitter.
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let delay = self.base_delay(attempt);
+        if self.jitter > 0.0 {
+            let factor = 1.0 + self.jitter * (rand::random::<f64>() * 2.0 - 1.0);
+            Duration::from_secs_f64(delay.as_secs_f64() * factor)
+        } else {
+            delay
+        }
+    }
+
+    fn is_aborted(&self) -> bool {
+        self.abortable
+            .as_ref()
+            .is_some_and(|abortable| abortable.is_aborted())
+    }
+}
+
+/// Invokes `f` until it succeeds, retrying failed attempts according
+/// to `policy`.
+pub async fn retry<T, E, F, Fut>(policy: &Policy<E>, mut f: F) -> Result<T, RetryError<E>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut attempt = 0;
+    loop {
+        if policy.is_aborted() {
+            return Err(RetryError::Aborted);
+        }
+
+        attempt += 1;
+        let error = match f().await {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+
+        if !policy.is_retryable(&error) {
+            return Err(RetryError::Fatal(error));
+        }
+        if policy.max_attempts.is_some_and(|max| attempt >= max) {
+            return Err(RetryError::Exhausted {
+                attempts: attempt,
+                error,
+            });
+        }
+
+        let delay = policy.delay(attempt);
+        if let Some(on_retry) = &policy.on_retry {
+            on_retry(&Attempt {
+                attempt,
+                error: &error,
+                delay,
+            });
+        }
+        if policy.is_aborted() {
+            return Err(RetryError::Aborted);
+        }
+        sleep(delay).await;
+    }
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_arch = "bpf")))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    #[derive(Debug, PartialEq)]
+    enum TestError {
+        Transient,
+        Fatal,
+    }
+
+    impl fmt::Display for TestError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{self:?}")
+        }
+    }
+
+    fn policy(delays: &Arc<Mutex<Vec<Duration>>>) -> Policy<TestError> {
+        let delays = delays.clone();
+        Policy::new()
+            .with_max_attempts(6)
+            .with_initial_delay(Duration::from_millis(1))
+            .with_multiplier(2.0)
+            .with_max_delay(Duration::from_millis(10))
+            .with_retryable(|err| *err == TestError::Transient)
+            .with_on_retry(move |attempt| {
+                assert_eq!(attempt.error, &TestError::Transient);
+                delays.lock().unwrap().push(attempt.delay);
+            })
+    }
+
+    #[tokio::test]
+    async fn test_retry_delay_sequence() {
+        let delays = Arc::new(Mutex::new(Vec::new()));
+        let counter = AtomicUsize::new(0);
+        let result = retry(&policy(&delays), || async {
+            if counter.fetch_add(1, Ordering::SeqCst) < 4 {
+                Err(TestError::Transient)
+            } else {
+                Ok(42)
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(counter.load(Ordering::SeqCst), 5);
+        assert_eq!(
+            *delays.lock().unwrap(),
+            [1, 2, 4, 8].map(Duration::from_millis)
+        );
+
+        // exhausted; the delay is capped at `max_delay`
+        let delays = Arc::new(Mutex::new(Vec::new()));
+        let result = retry(&policy(&delays), || async {
+            Err::<(), _>(TestError::Transient)
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(RetryError::Exhausted {
+                attempts: 6,
+                error: TestError::Transient
+            })
+        ));
+        assert_eq!(
+            *delays.lock().unwrap(),
+            [1, 2, 4, 8, 10].map(Duration::from_millis)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_fatal_error() {
+        let delays = Arc::new(Mutex::new(Vec::new()));
+        let counter = AtomicUsize::new(0);
+        let result = retry(&policy(&delays), || async {
+            match counter.fetch_add(1, Ordering::SeqCst) {
+                0 => Err::<(), _>(TestError::Transient),
+                _ => Err(TestError::Fatal),
+            }
+        })
+        .await;
+        assert!(matches!(result, Err(RetryError::Fatal(TestError::Fatal))));
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+        assert_eq!(delays.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_abort_and_jitter() {
+        let abortable = Abortable::new();
+        let abortable_ = abortable.clone();
+        let policy = Policy::new()
+            .with_unlimited_attempts()
+            .with_initial_delay(Duration::from_millis(1))
+            .with_jitter(0.5)
+            .with_abortable(&abortable)
+            .with_on_retry(move |attempt| {
+                let base = Duration::from_millis(1u64 << (attempt.attempt - 1)).as_secs_f64();
+                let delay = attempt.delay.as_secs_f64();
+                assert!(delay >= base * 0.5 && delay <= base * 1.5);
+                if attempt.attempt == 3 {
+                    abortable_.abort();
+                }
+            });
+        let counter = AtomicUsize::new(0);
+        let result = retry(&policy, || async {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(TestError::Transient)
+        })
+        .await;
+        assert!(matches!(result, Err(RetryError::Aborted)));
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+    }
+}
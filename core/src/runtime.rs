@@ -3,10 +3,77 @@
 //! (Windows,Linux,MacOS,*BSD) as well as the type of a web environment (Browser or NWJS).
 //! This is useful for an application of an API to detect which environment it is operating under
 //! and subsequently restrict the functionality to the capabilities to this environment.
+//!
+//! The underlying JavaScript environment features are reported by [`capabilities()`]
+//! (also available as `workflow_node::runtime::capabilities()` along with guards
+//! such as `require_node()`).
 
 use cfg_if::cfg_if;
+use std::fmt;
+use std::str::FromStr;
 use std::sync::OnceLock;
 
+/// Version of the JavaScript runtime (such as Node.js or NW.js)
+/// parsed from the `major.minor.patch` string; missing components
+/// default to `0` and pre-release suffixes are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().trim_start_matches('v');
+        let s = s.split(['-', '+']).next().unwrap_or_default();
+        let mut parts = s.splitn(3, '.');
+        let major = parts.next().unwrap_or_default().parse()?;
+        let mut next = || parts.next().map(str::parse).transpose();
+        let minor = next()?.unwrap_or_default();
+        let patch = next()?.unwrap_or_default();
+        Ok(Version::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Features of the JavaScript environment, determined by probing the global
+/// object (all flags are unset in native applications).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// `process` object is available (Node.js, NW.js, Electron)
+    pub has_process: bool,
+    /// `require()` is available (globally or via the main module)
+    pub has_require: bool,
+    /// `nw` object is available (NW.js)
+    pub has_nw: bool,
+    /// Node.js version (`process.versions.node`)
+    pub node_version: Option<Version>,
+    /// NW.js version (`process.versions.nw`)
+    pub nw_version: Option<Version>,
+    /// Running within a Web Worker (`WorkerGlobalScope`)
+    pub is_worker: bool,
+    /// `document` supporting element creation is available
+    pub has_dom: bool,
+}
+
 cfg_if! {
     if #[cfg(target_arch = "wasm32")]{
         use js_sys::Reflect;
@@ -35,11 +102,79 @@ cfg_if! {
             js_sys::Reflect::get(object, &property.into()).map(|v|!v.is_falsy()).unwrap_or(false)
         }
 
+        fn is_function(object : &JsValue, property: &str) -> bool {
+            js_sys::Reflect::get(object, &property.into()).map(|v|v.is_function()).unwrap_or(false)
+        }
+
+        fn version(versions : &JsValue, property: &str) -> Option<Version> {
+            Reflect::get(versions, &property.into()).ok()?.as_string()?.parse().ok()
+        }
+
+        /// Test if the prototype chain of `object` contains
+        /// the prototype of the global class named `class`
+        fn is_instance_of(object : &JsValue, class: &str) -> bool {
+            let prototype = Reflect::get(&js_sys::global(), &class.into())
+                .and_then(|class|Reflect::get(&class, &"prototype".into()));
+            let Ok(prototype) = prototype else {
+                return false;
+            };
+            if !prototype.is_object() {
+                return false;
+            }
+            let mut object = js_sys::Object::get_prototype_of(object);
+            while !object.is_null() {
+                if js_sys::Object::is(&object, &prototype) {
+                    return true;
+                }
+                object = js_sys::Object::get_prototype_of(&object);
+            }
+            false
+        }
+
+        /// Features of the JavaScript environment (the result is cached).
+        pub fn capabilities() -> &'static Capabilities {
+            static CAPABILITIES: OnceLock<Capabilities> = OnceLock::new();
+            CAPABILITIES.get_or_init(|| {
+                let global = js_sys::global();
+
+                let process = Reflect::get(&global, &"process".into())
+                    .ok()
+                    .filter(|process|process.is_object());
+                let versions = process
+                    .as_ref()
+                    .and_then(|process|Reflect::get(process, &"versions".into()).ok())
+                    .filter(|versions|versions.is_object());
+
+                // `require` is module-scoped in CommonJS (Node.js) and global in NW.js
+                let has_require = is_function(&global, "require") || process
+                    .as_ref()
+                    .and_then(|process|Reflect::get(process, &"mainModule".into()).ok())
+                    .map(|module|is_function(&module, "require"))
+                    .unwrap_or(false);
+
+                let has_dom = Reflect::get(&global, &"document".into())
+                    .map(|document|is_function(&document, "createElement"))
+                    .unwrap_or(false);
+
+                Capabilities {
+                    has_process : process.is_some(),
+                    has_require,
+                    has_nw : Reflect::get(&global, &"nw".into())
+                        .map(|nw|exists_prop(&nw, "Window")).unwrap_or(false),
+                    node_version : versions.as_ref().and_then(|versions|version(versions, "node")),
+                    nw_version : versions.as_ref().and_then(|versions|version(versions, "nw")),
+                    is_worker : is_instance_of(&global, "WorkerGlobalScope"),
+                    has_dom,
+                }
+            })
+        }
+
         #[inline]
         fn detect() -> &'static JavaScriptRuntime {
             static JAVASCRIPT_RUNTIME: OnceLock<JavaScriptRuntime> = OnceLock::new();
             JAVASCRIPT_RUNTIME.get_or_init(|| {
                 let global = js_sys::global();
+                let capabilities = capabilities();
 
                 let mut browser = exists("window") && exists("document") && exists("location") && exists("navigator");
 
@@ -48,9 +183,7 @@ cfg_if! {
                     .clone()
                     .and_then(|process|Reflect::get(&process, &"versions".into()));
 
-                let nodejs = versions
-                    .clone()
-                    .map(|versions|exists_prop(&versions, "node")).unwrap_or(false);
+                let nodejs = capabilities.node_version.is_some();
 
                 let electron = versions
                     .clone()
@@ -63,8 +196,7 @@ cfg_if! {
                     }
                 }
 
-                let nwjs = Reflect::get(&global, &"nw".into())
-                    .map(|nw|exists_prop(&nw, "Window")).unwrap_or(false);
+                let nwjs = capabilities.has_nw;
 
                 let web = !nodejs && !nwjs && !electron;
 
@@ -132,6 +264,13 @@ cfg_if! {
         }
     }else{
 
+        /// Features of the JavaScript environment
+        /// (none are available in native applications).
+        pub fn capabilities() -> &'static Capabilities {
+            static CAPABILITIES: OnceLock<Capabilities> = OnceLock::new();
+            CAPABILITIES.get_or_init(Capabilities::default)
+        }
+
         /// Helper to test whether the application is running under
         /// NodeJs-compatible environment.
         pub fn is_node() -> bool {
@@ -468,3 +607,45 @@ pub fn is_chrome_extension() -> bool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_parse() {
+        assert_eq!("20.11.1".parse(), Ok(Version::new(20, 11, 1)));
+        assert_eq!("v18.2".parse(), Ok(Version::new(18, 2, 0)));
+        assert_eq!("0.86.0-sdk".parse(), Ok(Version::new(0, 86, 0)));
+        assert!("node".parse::<Version>().is_err());
+        assert!(Version::new(20, 0, 0) > Version::new(18, 19, 1));
+        assert_eq!(Version::new(1, 2, 3).to_string(), "1.2.3");
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_native_capabilities() {
+        assert_eq!(capabilities(), &Capabilities::default());
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_browser_capabilities() {
+        let capabilities = capabilities();
+        assert!(capabilities.has_dom);
+        assert!(!capabilities.has_process);
+        assert!(!capabilities.has_require);
+        assert!(!capabilities.has_nw);
+        assert!(!capabilities.is_worker);
+        assert_eq!(capabilities.node_version, None);
+        assert_eq!(capabilities.nw_version, None);
+        assert!(is_web());
+    }
+}
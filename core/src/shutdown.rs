@@ -0,0 +1,435 @@
+//!
+//! Graceful shutdown coordinator. Components register named shutdown
+//! hooks within an ordering [`Phase`]; [`Coordinator::shutdown()`] runs
+//! the phases sequentially and the hooks of each phase concurrently,
+//! bounding each phase by a timeout.
+//!
+//! ```ignore
+//! let coordinator = Coordinator::new();
+//! coordinator.register("ws-server", Phase::AcceptStop, move || async move {
+//!     server.stop().ok();
+//! })?;
+//! coordinator.register("store", Phase::Flush, move || async move {
+//!     store.flush().await.ok();
+//! })?;
+//!
+//! // worker tasks park until the shutdown is initiated
+//! let reason = coordinator.wait_for_shutdown().await;
+//!
+//! // native: Ctrl-C / SIGTERM initiate the shutdown
+//! coordinator.bind_signals()?;
+//! let report = coordinator.wait_for_completion().await;
+//! ```
+//!
+//! In WASM, the shutdown can only be triggered programmatically, i.e. via
+//! [`Coordinator::shutdown()`] or [`Coordinator::trigger()`] (for example
+//! from a Node.js `process.on("SIGINT")` handler).
+//!
+
+use crate::task::sleep;
+use crate::time::Duration;
+use crate::trigger::SingleTrigger;
+use futures::future::{join_all, BoxFuture};
+use futures::{select, FutureExt};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+/// Default per-phase timeout.
+pub const DEFAULT_PHASE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Error produced by the shutdown [`Coordinator`].
+#[derive(Error, Debug)]
+pub enum ShutdownError {
+    #[error("shutdown is already in progress")]
+    ShuttingDown,
+    #[error("unable to bind signal handler: {0}")]
+    Signal(String),
+}
+
+/// Shutdown phases, executed in the declaration order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Phase {
+    /// Stop accepting new work (listeners, servers)
+    AcceptStop,
+    /// Complete or cancel in-flight work (connections, task groups)
+    Drain,
+    /// Persist state (stores, caches, logs)
+    Flush,
+    /// Release remaining resources
+    Cleanup,
+}
+
+/// Cause of the shutdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShutdownReason {
+    /// Process signal (e.g. `SIGINT` or `SIGTERM`)
+    Signal(String),
+    /// Programmatic request
+    Requested(String),
+}
+
+impl fmt::Display for ShutdownReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShutdownReason::Signal(signal) => write!(f, "signal {signal}"),
+            ShutdownReason::Requested(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl From<&str> for ShutdownReason {
+    fn from(reason: &str) -> Self {
+        ShutdownReason::Requested(reason.to_string())
+    }
+}
+
+impl From<String> for ShutdownReason {
+    fn from(reason: String) -> Self {
+        ShutdownReason::Requested(reason)
+    }
+}
+
+/// Outcome of [`Coordinator::shutdown()`].
+#[derive(Debug, Clone)]
+pub struct ShutdownReport {
+    pub reason: ShutdownReason,
+    /// Hooks that did not complete within the timeout of their phase
+    pub timed_out: Vec<(Phase, String)>,
+}
+
+impl ShutdownReport {
+    /// Returns `true` if all hooks have completed.
+    pub fn is_complete(&self) -> bool {
+        self.timed_out.is_empty()
+    }
+}
+
+type HookFn = Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send>;
+
+struct Hook {
+    name: String,
+    hook: HookFn,
+}
+
+#[derive(Default)]
+struct State {
+    hooks: BTreeMap<Phase, Vec<Hook>>,
+    timeouts: BTreeMap<Phase, Duration>,
+    reason: Option<ShutdownReason>,
+    report: Option<ShutdownReport>,
+}
+
+#[derive(Default)]
+struct Inner {
+    state: Mutex<State>,
+    // fired once the shutdown is initiated
+    initiated: SingleTrigger,
+    // fired once all phases have completed
+    completed: SingleTrigger,
+    #[cfg(not(target_arch = "wasm32"))]
+    signals: AtomicBool,
+}
+
+/// Shutdown coordinator shared between components. Cloning produces
+/// a handle to the same coordinator.
+#[derive(Clone, Default)]
+pub struct Coordinator {
+    inner: Arc<Inner>,
+}
+
+impl Coordinator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the timeout of `phase` (default [`DEFAULT_PHASE_TIMEOUT`]).
+    pub fn with_phase_timeout(self, phase: Phase, timeout: Duration) -> Self {
+        self.set_phase_timeout(phase, timeout);
+        self
+    }
+
+    pub fn set_phase_timeout(&self, phase: Phase, timeout: Duration) {
+        self.inner
+            .state
+            .lock()
+            .unwrap()
+            .timeouts
+            .insert(phase, timeout);
+    }
+
+    /// Registers a shutdown hook invoked during `phase`. Fails
+    /// with [`ShutdownError::ShuttingDown`] once the shutdown is initiated.
+    pub fn register<N, F, Fut>(&self, name: N, phase: Phase, hook: F) -> Result<(), ShutdownError>
+    where
+        N: Into<String>,
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.reason.is_some() {
+            return Err(ShutdownError::ShuttingDown);
+        }
+        state.hooks.entry(phase).or_default().push(Hook {
+            name: name.into(),
+            hook: Box::new(move || hook().boxed()),
+        });
+        Ok(())
+    }
+
+    /// Returns `true` once the shutdown has been initiated.
+    pub fn is_shutting_down(&self) -> bool {
+        self.inner.state.lock().unwrap().reason.is_some()
+    }
+
+    /// Returns the reason of the shutdown once it has been initiated.
+    pub fn reason(&self) -> Option<ShutdownReason> {
+        self.inner.state.lock().unwrap().reason.clone()
+    }
+
+    /// Resolves with the shutdown reason once the shutdown is initiated.
+    pub async fn wait_for_shutdown(&self) -> ShutdownReason {
+        self.inner.initiated.listener.clone().await;
+        self.reason()
+            .expect("shutdown reason must be set when initiated")
+    }
+
+    /// Resolves once all shutdown phases have completed.
+    pub async fn wait_for_completion(&self) -> ShutdownReport {
+        self.inner.completed.listener.clone().await;
+        self.inner
+            .state
+            .lock()
+            .unwrap()
+            .report
+            .clone()
+            .expect("shutdown report must be set when completed")
+    }
+
+    /// Runs the registered hooks phase by phase. Subsequent (or concurrent)
+    /// calls do not run the hooks again and resolve with the report of the
+    /// first shutdown.
+    pub async fn shutdown<R: Into<ShutdownReason>>(&self, reason: R) -> ShutdownReport {
+        let initiated = {
+            let mut state = self.inner.state.lock().unwrap();
+            if state.reason.is_none() {
+                let reason = reason.into();
+                state.reason = Some(reason.clone());
+                Some((
+                    reason,
+                    std::mem::take(&mut state.hooks),
+                    state.timeouts.clone(),
+                ))
+            } else {
+                None
+            }
+        };
+        let Some((reason, hooks, timeouts)) = initiated else {
+            return self.wait_for_completion().await;
+        };
+        self.inner.initiated.trigger.trigger();
+
+        let mut timed_out = Vec::new();
+        for (phase, hooks) in hooks {
+            let timeout = timeouts
+                .get(&phase)
+                .copied()
+                .unwrap_or(DEFAULT_PHASE_TIMEOUT);
+            timed_out.extend(
+                run_phase(hooks, timeout)
+                    .await
+                    .into_iter()
+                    .map(|name| (phase, name)),
+            );
+        }
+
+        let report = ShutdownReport { reason, timed_out };
+        self.inner.state.lock().unwrap().report = Some(report.clone());
+        self.inner.completed.trigger.trigger();
+        report
+    }
+
+    /// Initiates the shutdown in a spawned task. Must be called
+    /// from within the async runtime (see [`task::spawn()`](crate::task::spawn)).
+    pub fn trigger<R: Into<ShutdownReason>>(&self, reason: R) {
+        let this = self.clone();
+        let reason = reason.into();
+        crate::task::spawn(async move {
+            this.shutdown(reason).await;
+        });
+    }
+
+    /// Binds `SIGINT` and `SIGTERM` to the coordinator. The first signal
+    /// initiates the shutdown; a signal received while the shutdown is
+    /// in progress terminates the process.
+    /// The shutdown runs in the tokio runtime this function is called from.
+    /// Signal handling can be bound only once per process.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn bind_signals(&self) -> Result<(), ShutdownError> {
+        if self.inner.signals.swap(true, Ordering::SeqCst) {
+            return Err(ShutdownError::Signal(
+                "signals are already bound".to_string(),
+            ));
+        }
+        let handle = tokio::runtime::Handle::try_current()
+            .map_err(|err| ShutdownError::Signal(err.to_string()))?;
+        let this = self.clone();
+        ctrlc::set_handler(move || {
+            if this.is_shutting_down() {
+                println!("^SIGTERM - halting");
+                std::process::exit(1);
+            }
+            println!("^SIGTERM - shutting down...");
+            let this = this.clone();
+            handle.spawn(async move {
+                this.shutdown(ShutdownReason::Signal("SIGTERM".to_string()))
+                    .await;
+            });
+        })
+        .map_err(|err| ShutdownError::Signal(err.to_string()))
+    }
+}
+
+/// Runs `hooks` concurrently, returning names of
+/// the hooks that have not completed within `timeout`.
+async fn run_phase(hooks: Vec<Hook>, timeout: Duration) -> Vec<String> {
+    let done = hooks
+        .iter()
+        .map(|_| AtomicBool::new(false))
+        .collect::<Vec<_>>();
+    let (names, futures): (Vec<_>, Vec<_>) = hooks
+        .into_iter()
+        .map(|Hook { name, hook }| (name, hook()))
+        .unzip();
+
+    let all = join_all(
+        futures
+            .into_iter()
+            .zip(done.iter())
+            .map(|(future, done)| async move {
+                future.await;
+                done.store(true, Ordering::SeqCst);
+            }),
+    )
+    .fuse();
+    let deadline = sleep(timeout).fuse();
+    futures::pin_mut!(all, deadline);
+    select! {
+        _ = all => {},
+        _ = deadline => {},
+    }
+
+    names
+        .into_iter()
+        .zip(done.iter())
+        .filter(|(_, done)| !done.load(Ordering::SeqCst))
+        .map(|(name, _)| name)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown_phases() {
+        let coordinator = Coordinator::new();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        // registered out of order, executed by phase
+        for (name, phase) in [
+            ("store", Phase::Flush),
+            ("server", Phase::AcceptStop),
+            ("tasks", Phase::Drain),
+            ("connections", Phase::Drain),
+        ] {
+            let log = log.clone();
+            coordinator
+                .register(name, phase, move || async move {
+                    log.lock().unwrap().push(format!("{name}:start"));
+                    sleep(Duration::from_millis(20)).await;
+                    log.lock().unwrap().push(format!("{name}:end"));
+                })
+                .unwrap();
+        }
+
+        let waiter = {
+            let coordinator = coordinator.clone();
+            tokio::spawn(async move { coordinator.wait_for_shutdown().await })
+        };
+
+        let report = coordinator.shutdown("test").await;
+        assert!(report.is_complete());
+        assert_eq!(report.reason, ShutdownReason::Requested("test".to_string()));
+        assert_eq!(waiter.await.unwrap(), report.reason);
+
+        let log = log.lock().unwrap().clone();
+        assert_eq!(log[..2], ["server:start", "server:end"]);
+        // hooks of the same phase run concurrently
+        assert_eq!(log[2..4], ["tasks:start", "connections:start"]);
+        assert!(log[4..6].contains(&"tasks:end".to_string()));
+        assert!(log[4..6].contains(&"connections:end".to_string()));
+        assert_eq!(log[6..], ["store:start", "store:end"]);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_timeout() {
+        let coordinator =
+            Coordinator::new().with_phase_timeout(Phase::Drain, Duration::from_millis(50));
+        let flushed = Arc::new(AtomicBool::new(false));
+        coordinator
+            .register("stuck", Phase::Drain, || async {
+                sleep(Duration::from_secs(60)).await;
+            })
+            .unwrap();
+        coordinator
+            .register("quick", Phase::Drain, || async {})
+            .unwrap();
+        let flushed_ = flushed.clone();
+        coordinator
+            .register("store", Phase::Flush, move || async move {
+                flushed_.store(true, Ordering::SeqCst);
+            })
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        let report = coordinator.shutdown("test").await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(report.timed_out, vec![(Phase::Drain, "stuck".to_string())]);
+        // the following phases run after the timeout
+        assert!(flushed.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_idempotent() {
+        let coordinator = Coordinator::new();
+        let runs = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let runs_ = runs.clone();
+        coordinator
+            .register("hook", Phase::Cleanup, move || async move {
+                sleep(Duration::from_millis(20)).await;
+                runs_.fetch_add(1, Ordering::SeqCst);
+            })
+            .unwrap();
+
+        let (first, second) = futures::join!(
+            coordinator.shutdown("first"),
+            coordinator.shutdown("second")
+        );
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(first.reason, ShutdownReason::Requested("first".to_string()));
+        assert_eq!(second.reason, first.reason);
+
+        let third = coordinator
+            .shutdown(ShutdownReason::Signal("SIGINT".into()))
+            .await;
+        assert_eq!(third.reason, first.reason);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert!(matches!(
+            coordinator.register("late", Phase::Cleanup, || async {}),
+            Err(ShutdownError::ShuttingDown)
+        ));
+    }
+}
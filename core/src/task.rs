@@ -5,20 +5,43 @@
 //!
 //! Following functions are are available:
 //! - [`spawn()`] - non-blocking spawn of the supplied async closure
+//! - [`spawn_local()`] - non-blocking spawn of a `!Send` future from a local task
+//! - [`spawn_local_with()`] - non-blocking spawn of a `!Send` future created by the supplied closure
 //! - [`sleep()`] - suspends the task for a given Duration
+//! - [`sleep_until()`] - suspends the task until a given Instant
 //! - [`yield_now()`] - yields rust executor
 //! - [`yield_executor()`] - yields to top-level executor (browser async loop)
+//! - [`dispatch_blocking()`] - executes CPU-bound work without stalling the executor
 //!
 //! <div class="example-wrap compile_fail"><pre class="compile_fail" style="white-space:normal;font:inherit;">
 //! Blocking spawn is not available as a part of this framework as WASM-browser environment can
 //! not block task execution due to a single-threaded async application environment.
 //! </pre></div>
 //!
+//! CPU-bound work (hashing, decompression) is supplied to [`dispatch_blocking()`] as an
+//! incremental closure returning [`ControlFlow::Continue`](std::ops::ControlFlow::Continue)
+//! until the result is available. The closure is executed on the tokio blocking thread pool
+//! natively, while in WASM it is executed on the executor, which is yielded between time
+//! slices (see [`BlockingMode`] and [`blocking_mode()`]).
+//!
 
 #[allow(unused_imports)]
 use cfg_if::cfg_if;
 use futures::Future;
 
+/// Execution mode of the work supplied to [`dispatch_blocking()`]
+/// on the current target (see [`blocking_mode()`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockingMode {
+    /// Steps are executed back-to-back on the tokio blocking
+    /// thread pool (native platforms).
+    ThreadPool,
+    /// Steps are executed on the async executor, yielding to the
+    /// browser event loop between time slices (`wasm32` target).
+    /// Each step stalls the executor for its duration.
+    Cooperative,
+}
+
 cfg_if! {
     if #[cfg(not(any(target_arch = "wasm32", target_arch = "bpf")))] {
 
@@ -31,8 +54,10 @@ cfg_if! {
             // for native platforms
             pub use tokio::task::yield_now as yield_executor;
             pub use tokio::task::yield_now;
-            pub use tokio::time::sleep;
+            pub use crate::clock::{sleep, sleep_until, Sleep};
             pub use crate::native::interval::{interval,Interval};
+            pub use crate::native::local::{spawn_local, spawn_local_with};
+            pub use crate::native::blocking::{blocking_mode, dispatch_blocking};
 
             pub fn spawn<F, T>(future: F)
             where
@@ -102,20 +127,41 @@ pub mod wasm {
         }
     }
 
+    /// Spawn a `!Send` future (same as [`dispatch()`] on the `wasm32` target).
+    pub fn spawn_local<F, T>(future: F)
+    where
+        F: Future<Output = T> + 'static,
+        T: 'static,
+    {
+        dispatch(future)
+    }
+
+    /// Spawn a `!Send` future created by the `factory` closure. On the `wasm32`
+    /// target, the closure is invoked immediately as the environment is single-threaded.
+    pub fn spawn_local_with<F, Fut>(factory: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future + 'static,
+    {
+        dispatch(factory())
+    }
+
     cfg_if! {
         if #[cfg(target_arch = "wasm32")] {
             pub use crate::wasm::{
                 overrides::disable_persistent_timer_overrides,
                 interval::{interval,Interval},
                 yield_executor::{yield_executor,Yield},
-                sleep::{sleep,Sleep}
+                blocking::{blocking_mode, dispatch_blocking},
             };
+            pub use crate::clock::{sleep, sleep_until, Sleep};
             pub use async_std::task::yield_now;
             pub use workflow_core_macros::call_async_no_send;
         } else {
             pub use crate::native::{
                 overrides::disable_persistent_timer_overrides,
                 interval::{interval,Interval},
+                blocking::{blocking_mode, dispatch_blocking},
             };
             pub use async_std::task::sleep;
             pub use async_std::task::yield_now;
@@ -126,3 +172,109 @@ pub mod wasm {
 
 #[cfg(target_arch = "wasm32")]
 pub use wasm::*;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use std::ops::ControlFlow;
+    use std::time::{Duration, Instant};
+
+    const BUFFER_SIZE: usize = 32 * 1024 * 1024;
+    const CHUNK_SIZE: usize = 64 * 1024;
+    const HEARTBEAT: Duration = Duration::from_millis(5);
+    const MAX_HEARTBEAT_GAP: Duration = Duration::from_millis(100);
+
+    /// Incremental FNV-1a hashing of a buffer, one chunk per step
+    struct Hashing {
+        buffer: Vec<u8>,
+        offset: usize,
+        hash: u64,
+    }
+
+    impl Hashing {
+        fn new(buffer: Vec<u8>) -> Self {
+            Self {
+                buffer,
+                offset: 0,
+                hash: 0xcbf29ce484222325,
+            }
+        }
+
+        fn step(&mut self) -> ControlFlow<u64> {
+            let end = (self.offset + CHUNK_SIZE).min(self.buffer.len());
+            for byte in &self.buffer[self.offset..end] {
+                self.hash = (self.hash ^ *byte as u64).wrapping_mul(0x100000001b3);
+            }
+            self.offset = end;
+            if self.offset == self.buffer.len() {
+                ControlFlow::Break(self.hash)
+            } else {
+                ControlFlow::Continue(())
+            }
+        }
+    }
+
+    fn buffer() -> Vec<u8> {
+        (0..BUFFER_SIZE).map(|n| (n % 251) as u8).collect()
+    }
+
+    /// Hashes a large buffer via `dispatch` while a heartbeat interval
+    /// is ticking on the same (single-threaded) executor.
+    async fn test_heartbeat<F, Fut>(dispatch: F)
+    where
+        F: FnOnce(Hashing) -> Fut,
+        Fut: Future<Output = u64>,
+    {
+        let expected = {
+            let mut hashing = Hashing::new(buffer());
+            loop {
+                if let ControlFlow::Break(hash) = hashing.step() {
+                    break hash;
+                }
+            }
+        };
+
+        let hashing = Hashing::new(buffer());
+        let mut ticks = vec![Instant::now()];
+        let heartbeat = async {
+            let mut interval = tokio::time::interval(HEARTBEAT);
+            loop {
+                interval.tick().await;
+                ticks.push(Instant::now());
+            }
+        };
+        let hash = tokio::select! {
+            hash = dispatch(hashing) => hash,
+            _ = heartbeat => unreachable!(),
+        };
+        ticks.push(Instant::now());
+
+        assert_eq!(hash, expected);
+        let gap = ticks
+            .windows(2)
+            .map(|ticks| ticks[1] - ticks[0])
+            .max()
+            .unwrap();
+        assert!(gap < MAX_HEARTBEAT_GAP, "heartbeat stalled for {gap:?}");
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_blocking() {
+        assert_eq!(blocking_mode(), BlockingMode::ThreadPool);
+        test_heartbeat(|mut hashing| dispatch_blocking(move || hashing.step())).await;
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_blocking_cooperative() {
+        use crate::wasm::blocking::{blocking_mode, dispatch_blocking};
+
+        assert_eq!(blocking_mode(), BlockingMode::Cooperative);
+        test_heartbeat(|mut hashing| dispatch_blocking(move || hashing.step())).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "step failure")]
+    async fn test_dispatch_blocking_panic() {
+        dispatch_blocking(|| -> ControlFlow<()> { panic!("step failure") }).await;
+    }
+}
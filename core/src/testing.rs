@@ -0,0 +1,282 @@
+//!
+//! Sandboxed test harness (available with the `test` feature).
+//!
+//! [`sandbox()`] returns a [`Sandbox`] guard that, for its lifetime,
+//! redirects [`dirs::home_dir()`](crate::dirs::home_dir) and
+//! [`dirs::data_dir()`](crate::dirs::data_dir) to a temporary directory
+//! (removed when the guard is dropped) and resolves [`env::var()`](crate::env::var)
+//! against an in-memory overlay before falling back to the real environment.
+//! Functions built on top of these (such as `workflow-store` path resolution
+//! or [`toggles`](crate::toggles)) observe the sandbox as well.
+//!
+//! ```ignore
+//! #[test]
+//! fn test_settings() {
+//!     let sandbox = workflow_core::testing::sandbox();
+//!     sandbox.set_var("APP_MODE", "test");
+//!     assert_eq!(workflow_core::env::var("APP_MODE").unwrap(), "test");
+//!     assert!(workflow_core::dirs::home_dir().unwrap().starts_with(sandbox.root()));
+//! }
+//! ```
+//!
+//! The sandbox is scoped to the current thread: tests running in parallel
+//! (each on its own thread) do not observe each other's sandboxes, and no
+//! global lock is held. As a consequence, the sandbox is not visible from
+//! other threads - threads spawned by the test and tasks executed by a
+//! multi-threaded async runtime see the real directories and environment.
+//! Async tests should use a single-threaded runtime (the default for
+//! `#[tokio::test]`). The guard is not `Send` to prevent it from being
+//! moved to another thread.
+//!
+//! Sandboxes can be nested; the innermost sandbox is in effect until dropped.
+//!
+//! [`manual_clock()`] returns a [`ManualClock`] guard offsetting the clock
+//! readings of [`clock`](crate::clock) (used by [`task::sleep()`](crate::task::sleep)
+//! and the clock jump watchdog) on the current thread, allowing tests to
+//! simulate a system suspend or wall-clock adjustments.
+//!
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::env::VarError;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+
+#[derive(Debug)]
+struct State {
+    root: PathBuf,
+    vars: RefCell<HashMap<String, Option<String>>>,
+}
+
+thread_local! {
+    static SANDBOXES: RefCell<Vec<Rc<State>>> = const { RefCell::new(Vec::new()) };
+}
+
+fn current() -> Option<Rc<State>> {
+    SANDBOXES.with(|sandboxes| sandboxes.borrow().last().cloned())
+}
+
+/// Creates a [`Sandbox`] for the current thread.
+///
+/// # Panics
+///
+/// Panics if the temporary directory can not be created.
+pub fn sandbox() -> Sandbox {
+    let root = std::env::temp_dir().join(format!(
+        "workflow-sandbox-{}-{:016x}",
+        std::process::id(),
+        rand::random::<u64>()
+    ));
+    std::fs::create_dir_all(root.join("home")).expect("unable to create sandbox home directory");
+    std::fs::create_dir_all(root.join("data")).expect("unable to create sandbox data directory");
+
+    let state = Rc::new(State {
+        root,
+        vars: RefCell::default(),
+    });
+    SANDBOXES.with(|sandboxes| sandboxes.borrow_mut().push(state.clone()));
+    Sandbox {
+        state,
+        _not_send: PhantomData,
+    }
+}
+
+/// Guard returned by [`sandbox()`] (see the [module](self) documentation).
+#[derive(Debug)]
+pub struct Sandbox {
+    state: Rc<State>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Sandbox {
+    /// Temporary directory containing the sandboxed directories
+    pub fn root(&self) -> &Path {
+        &self.state.root
+    }
+
+    /// Directory returned by [`dirs::home_dir()`](crate::dirs::home_dir)
+    pub fn home_dir(&self) -> PathBuf {
+        self.state.root.join("home")
+    }
+
+    /// Directory returned by [`dirs::data_dir()`](crate::dirs::data_dir)
+    pub fn data_dir(&self) -> PathBuf {
+        self.state.root.join("data")
+    }
+
+    /// Sets an environment variable visible within the sandbox
+    /// (the real environment is not modified).
+    pub fn set_var(&self, key: &str, value: &str) {
+        self.state
+            .vars
+            .borrow_mut()
+            .insert(key.to_string(), Some(value.to_string()));
+    }
+
+    /// Hides an environment variable within the sandbox, including
+    /// a variable present in the real environment.
+    pub fn remove_var(&self, key: &str) {
+        self.state.vars.borrow_mut().insert(key.to_string(), None);
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        SANDBOXES.with(|sandboxes| {
+            sandboxes
+                .borrow_mut()
+                .retain(|state| !Rc::ptr_eq(state, &self.state))
+        });
+        std::fs::remove_dir_all(&self.state.root).ok();
+    }
+}
+
+thread_local! {
+    static CLOCK: Cell<Option<(Duration, i64, Duration)>> = const { Cell::new(None) };
+}
+
+/// Creates a [`ManualClock`] for the current thread.
+///
+/// # Panics
+///
+/// Panics if a manual clock is already in effect on the current thread.
+pub fn manual_clock() -> ManualClock {
+    CLOCK.with(|clock| {
+        assert!(
+            clock.get().is_none(),
+            "a manual clock is already in effect on this thread"
+        );
+        clock.set(Some((Duration::ZERO, 0, Duration::ZERO)));
+    });
+    ManualClock {
+        _not_send: PhantomData,
+    }
+}
+
+/// Guard returned by [`manual_clock()`] (see the [module](self) documentation).
+#[derive(Debug)]
+pub struct ManualClock {
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ManualClock {
+    fn offset(&self, monotonic: Duration, wall: i64, suspended: Duration) {
+        CLOCK.with(|clock| {
+            let (m, w, s) = clock.get().unwrap_or_default();
+            clock.set(Some((m + monotonic, w + wall, s + suspended)));
+        });
+    }
+
+    /// Advances both the monotonic and the wall clock.
+    pub fn advance(&self, duration: Duration) {
+        self.offset(duration, duration.as_millis() as i64, Duration::ZERO);
+    }
+
+    /// Advances the wall clock and the suspended time (but not the
+    /// monotonic clock), as observed after a system suspend.
+    pub fn suspend(&self, duration: Duration) {
+        self.offset(Duration::ZERO, duration.as_millis() as i64, duration);
+    }
+
+    /// Advances the wall clock only, as observed after a wall-clock
+    /// adjustment (e.g. an NTP step).
+    pub fn advance_wall(&self, duration: Duration) {
+        self.offset(Duration::ZERO, duration.as_millis() as i64, Duration::ZERO);
+    }
+
+    /// Sets the wall clock back.
+    pub fn rewind(&self, duration: Duration) {
+        self.offset(
+            Duration::ZERO,
+            -(duration.as_millis() as i64),
+            Duration::ZERO,
+        );
+    }
+}
+
+impl Drop for ManualClock {
+    fn drop(&mut self) {
+        CLOCK.with(|clock| clock.set(None));
+    }
+}
+
+/// Monotonic, wall-clock (milliseconds) and suspended time offsets
+/// of the manual clock in effect on the current thread.
+pub(crate) fn clock_offsets() -> Option<(Duration, i64, Duration)> {
+    CLOCK.with(|clock| clock.get())
+}
+
+pub(crate) fn home_dir() -> Option<PathBuf> {
+    current().map(|state| state.root.join("home"))
+}
+
+pub(crate) fn data_dir() -> Option<PathBuf> {
+    current().map(|state| state.root.join("data"))
+}
+
+/// Returns `None` if the variable is not defined by the sandbox
+/// (or there is no sandbox), in which case the real environment applies.
+pub(crate) fn var(key: &str) -> Option<Result<String, VarError>> {
+    let state = current()?;
+    let vars = state.vars.borrow();
+    vars.get(key)
+        .map(|value| value.clone().ok_or(VarError::NotPresent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dirs, env};
+    use std::sync::Barrier;
+
+    #[test]
+    fn test_sandbox_redirects_dirs_and_env() {
+        let sandbox = sandbox();
+        let root = sandbox.root().to_path_buf();
+        assert_eq!(dirs::home_dir(), Some(sandbox.home_dir()));
+        assert_eq!(dirs::data_dir(), Some(sandbox.data_dir()));
+        assert!(sandbox.home_dir().is_dir());
+
+        sandbox.set_var("WORKFLOW_SANDBOX_TEST", "value");
+        assert_eq!(env::var("WORKFLOW_SANDBOX_TEST").unwrap(), "value");
+        sandbox.remove_var("PATH");
+        assert_eq!(env::var("PATH"), Err(VarError::NotPresent));
+
+        {
+            let nested = super::sandbox();
+            assert_eq!(dirs::home_dir(), Some(nested.home_dir()));
+            assert!(env::var("WORKFLOW_SANDBOX_TEST").is_err());
+        }
+        assert_eq!(dirs::home_dir(), Some(sandbox.home_dir()));
+
+        drop(sandbox);
+        assert!(!root.exists());
+        assert!(env::var("WORKFLOW_SANDBOX_TEST").is_err());
+        assert_ne!(dirs::home_dir(), Some(root.join("home")));
+    }
+
+    #[test]
+    fn test_parallel_sandboxes_are_isolated() {
+        // both sandboxes are active at the same time
+        let barrier = Barrier::new(2);
+        let roots = std::thread::scope(|scope| {
+            let threads = ["a", "b"].map(|name| {
+                let barrier = &barrier;
+                scope.spawn(move || {
+                    let sandbox = sandbox();
+                    sandbox.set_var("WORKFLOW_SANDBOX_NAME", name);
+                    barrier.wait();
+                    assert_eq!(env::var("WORKFLOW_SANDBOX_NAME").unwrap(), name);
+                    assert_eq!(dirs::home_dir(), Some(sandbox.home_dir()));
+                    barrier.wait();
+                    sandbox.root().to_path_buf()
+                })
+            });
+            threads.map(|thread| thread.join().unwrap())
+        });
+        assert_ne!(roots[0], roots[1]);
+        assert!(env::var("WORKFLOW_SANDBOX_NAME").is_err());
+    }
+}
@@ -9,6 +9,14 @@ use cfg_if::cfg_if;
 /// re-export of [`instant`] crate supporting native and WASM implementations
 pub use instant::*;
 
+/// `Instant` with checked and saturating arithmetic matching
+/// `std::time::Instant` on all platforms (a WASM shim, or
+/// `std::time::Instant` itself on native platforms).
+#[cfg(target_arch = "wasm32")]
+pub use crate::wasm::instant::Instant as SaturatingInstant;
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant as SaturatingInstant;
+
 pub const SECONDS: u64 = 1000;
 pub const MINUTES: u64 = SECONDS * 60;
 pub const HOURS: u64 = MINUTES * 60;
@@ -120,6 +128,504 @@ cfg_if! {
     }
 }
 
+/// Converts UNIX time in milliseconds to [`SystemTime`].
+pub fn unixtime_millis_as_systemtime(millis: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_millis(millis)
+}
+
+/// Converts [`SystemTime`] to UNIX time in milliseconds
+/// (times preceding the UNIX epoch result in `0`).
+pub fn systemtime_as_unixtime_millis(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Kind of the [`DurationParseError`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationErrorKind {
+    /// The duration string is empty
+    Empty,
+    /// Expected a number
+    InvalidNumber,
+    /// Number not followed by a unit (units can only be omitted
+    /// when the duration consists of a single number)
+    MissingUnit,
+    /// Unknown unit suffix
+    UnknownUnit,
+    /// The duration exceeds [`Duration::MAX`]
+    Overflow,
+}
+
+/// Error produced by [`parse_duration()`] carrying the offending
+/// token and its byte position in the parsed string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DurationParseError {
+    kind: DurationErrorKind,
+    token: String,
+    position: usize,
+}
+
+impl DurationParseError {
+    fn new(kind: DurationErrorKind, token: &str, position: usize) -> Self {
+        Self {
+            kind,
+            token: token.to_string(),
+            position,
+        }
+    }
+
+    pub fn kind(&self) -> DurationErrorKind {
+        self.kind
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl std::fmt::Display for DurationParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            token, position, ..
+        } = self;
+        match self.kind {
+            DurationErrorKind::Empty => write!(f, "empty duration"),
+            DurationErrorKind::InvalidNumber => {
+                write!(f, "invalid number `{token}` at position {position}")
+            }
+            DurationErrorKind::MissingUnit => {
+                write!(f, "missing unit after `{token}` at position {position}")
+            }
+            DurationErrorKind::UnknownUnit => write!(
+                f,
+                "unknown unit `{token}` at position {position} (expected ns, us, ms, s, m, h or d)"
+            ),
+            DurationErrorKind::Overflow => {
+                write!(f, "duration overflow at `{token}` (position {position})")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DurationParseError {}
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Duration units in the descending order used by [`format_duration()`]
+const UNITS: [(&str, u128); 7] = [
+    ("d", 86_400 * NANOS_PER_SEC),
+    ("h", 3_600 * NANOS_PER_SEC),
+    ("m", 60 * NANOS_PER_SEC),
+    ("s", NANOS_PER_SEC),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+fn unit_nanos(unit: &str) -> Option<u128> {
+    let unit = if unit == "µs" { "us" } else { unit };
+    UNITS
+        .iter()
+        .find(|(name, _)| *name == unit)
+        .map(|(_, nanos)| *nanos)
+}
+
+/// Parses a duration such as `"250ms"`, `"1.5s"` or `"1h30m15s"`.
+///
+/// A duration is a sequence of components (optionally separated by
+/// whitespace) each consisting of a number, optionally fractional,
+/// followed by one of the `ns`, `us` (or `µs`), `ms`, `s`, `m`, `h`
+/// and `d` units. A bare number is interpreted as seconds. Fractions
+/// below a nanosecond are truncated.
+pub fn parse_duration(text: &str) -> Result<Duration, DurationParseError> {
+    use DurationErrorKind::*;
+
+    let end_of = |from: usize, predicate: fn(char) -> bool| {
+        text[from..]
+            .find(|c: char| !predicate(c))
+            .map_or(text.len(), |offset| from + offset)
+    };
+
+    let mut nanos = 0u128;
+    let mut components = 0;
+    let mut position = end_of(0, char::is_whitespace);
+    if position == text.len() {
+        return Err(DurationParseError::new(Empty, "", 0));
+    }
+
+    while position < text.len() {
+        let number_end = end_of(position, |c| c.is_ascii_digit() || c == '.');
+        let number = &text[position..number_end];
+        let (int, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if (int.is_empty() && fraction.is_empty()) || fraction.contains('.') {
+            let end = if number.is_empty() {
+                end_of(position, |c| !c.is_ascii_digit() && !c.is_whitespace())
+            } else {
+                number_end
+            };
+            return Err(DurationParseError::new(
+                InvalidNumber,
+                &text[position..end],
+                position,
+            ));
+        }
+
+        let unit_end = end_of(number_end, |c| c.is_alphabetic());
+        let unit = &text[number_end..unit_end];
+        let scale = if unit.is_empty() {
+            // a bare number is only accepted as the entire duration
+            if components > 0 || !text[unit_end..].trim().is_empty() {
+                return Err(DurationParseError::new(MissingUnit, number, position));
+            }
+            NANOS_PER_SEC
+        } else {
+            unit_nanos(unit)
+                .ok_or_else(|| DurationParseError::new(UnknownUnit, unit, number_end))?
+        };
+
+        let overflow = || DurationParseError::new(Overflow, &text[position..unit_end], position);
+        let int = if int.is_empty() {
+            0
+        } else {
+            int.parse::<u128>().map_err(|_| overflow())?
+        };
+        // digits beyond nanosecond precision do not contribute
+        let fraction = &fraction[..fraction.len().min(18)];
+        let fraction_nanos = if fraction.is_empty() {
+            0
+        } else {
+            fraction.parse::<u128>().unwrap() * scale / 10u128.pow(fraction.len() as u32)
+        };
+        nanos = int
+            .checked_mul(scale)
+            .and_then(|component| component.checked_add(fraction_nanos))
+            .and_then(|component| nanos.checked_add(component))
+            .filter(|nanos| *nanos / NANOS_PER_SEC <= u64::MAX as u128)
+            .ok_or_else(overflow)?;
+
+        components += 1;
+        position = end_of(unit_end, char::is_whitespace);
+    }
+
+    Ok(Duration::new(
+        (nanos / NANOS_PER_SEC) as u64,
+        (nanos % NANOS_PER_SEC) as u32,
+    ))
+}
+
+/// Formats the duration in the compact canonical form parsed by
+/// [`parse_duration()`], e.g. `"1h30m"` or `"1s500ms"` (`"0s"` if zero).
+pub fn format_duration(duration: Duration) -> String {
+    let mut nanos = duration.as_nanos();
+    if nanos == 0 {
+        return "0s".to_string();
+    }
+
+    let mut text = String::new();
+    for (unit, scale) in UNITS {
+        let value = nanos / scale;
+        if value > 0 {
+            text.push_str(&format!("{value}{unit}"));
+            nanos %= scale;
+        }
+    }
+    text
+}
+
+/// [`Duration`] parsed from and displayed in the human readable form
+/// (see [`parse_duration()`] and [`format_duration()`]), for use
+/// with [`FromStr`](std::str::FromStr) based parsers such as
+/// command line arguments.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HumanDuration(pub Duration);
+
+impl std::str::FromStr for HumanDuration {
+    type Err = DurationParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        parse_duration(text).map(HumanDuration)
+    }
+}
+
+impl std::fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format_duration(self.0))
+    }
+}
+
+impl std::ops::Deref for HumanDuration {
+    type Target = Duration;
+
+    fn deref(&self) -> &Duration {
+        &self.0
+    }
+}
+
+impl From<Duration> for HumanDuration {
+    fn from(duration: Duration) -> Self {
+        HumanDuration(duration)
+    }
+}
+
+impl From<HumanDuration> for Duration {
+    fn from(duration: HumanDuration) -> Self {
+        duration.0
+    }
+}
+
+/// [`Duration`] serialization for use with `#[serde(with = "...")]`.
+///
+/// ```ignore
+/// #[derive(Serialize, Deserialize)]
+/// struct Settings {
+///     #[serde(with = "workflow_core::time::serde_duration::millis")]
+///     timeout: Duration,
+///     #[serde(with = "workflow_core::time::serde_duration::secs_nanos")]
+///     interval: Duration,
+///     #[serde(with = "workflow_core::time::serde_duration::human")]
+///     idle: Duration,
+/// }
+/// ```
+pub mod serde_duration {
+    /// Serializes [`Duration`](super::Duration) as the number of milliseconds
+    /// (sub-millisecond precision is truncated).
+    pub mod millis {
+        use super::super::Duration;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.serialize_u64(duration.as_millis().try_into().unwrap_or(u64::MAX))
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            Ok(Duration::from_millis(u64::deserialize(deserializer)?))
+        }
+    }
+
+    /// Serializes [`Duration`](super::Duration) as a human readable string
+    /// such as `"1h30m"` (see [`parse_duration()`](super::super::parse_duration)).
+    pub mod human {
+        use super::super::{format_duration, parse_duration, Duration};
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.serialize_str(&format_duration(*duration))
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let text = String::deserialize(deserializer)?;
+            parse_duration(&text).map_err(serde::de::Error::custom)
+        }
+    }
+
+    /// Serializes [`Duration`](super::Duration) as a `{ secs, nanos }` struct
+    /// (the default `serde` representation of `std::time::Duration`).
+    pub mod secs_nanos {
+        use super::super::Duration;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        #[derive(Serialize, Deserialize)]
+        struct SecsNanos {
+            secs: u64,
+            nanos: u32,
+        }
+
+        pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            SecsNanos {
+                secs: duration.as_secs(),
+                nanos: duration.subsec_nanos(),
+            }
+            .serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let SecsNanos { secs, nanos } = SecsNanos::deserialize(deserializer)?;
+            if nanos >= 1_000_000_000 {
+                return Err(serde::de::Error::custom("nanos must be less than 1e9"));
+            }
+            Ok(Duration::new(secs, nanos))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Settings {
+        #[serde(with = "serde_duration::millis")]
+        timeout: Duration,
+        #[serde(with = "serde_duration::secs_nanos")]
+        interval: Duration,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct HumanSettings {
+        #[serde(with = "serde_duration::human")]
+        idle: Duration,
+    }
+
+    #[test]
+    fn test_duration_serde() {
+        let settings = Settings {
+            timeout: Duration::from_millis(1500),
+            interval: Duration::new(2, 500),
+        };
+        let json = serde_json::to_string(&settings).unwrap();
+        assert_eq!(
+            json,
+            r#"{"timeout":1500,"interval":{"secs":2,"nanos":500}}"#
+        );
+        assert_eq!(serde_json::from_str::<Settings>(&json).unwrap(), settings);
+        assert!(serde_json::from_str::<Settings>(
+            r#"{"timeout":0,"interval":{"secs":0,"nanos":1000000000}}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        let secs = Duration::from_secs;
+        let millis = Duration::from_millis;
+        let cases = [
+            ("250ms", millis(250)),
+            ("1h30m", secs(5400)),
+            ("1h30m15s", secs(5415)),
+            ("1.5s", millis(1500)),
+            ("0.25m", secs(15)),
+            (".5h", secs(1800)),
+            ("30", secs(30)),
+            ("2.5", millis(2500)),
+            ("  7d ", secs(7 * 86400)),
+            ("1m 30s", secs(90)),
+            ("10us", Duration::from_micros(10)),
+            ("10µs", Duration::from_micros(10)),
+            ("42ns", Duration::from_nanos(42)),
+            ("1s1ms1us1ns", Duration::new(1, 1_001_001)),
+            ("1.0000000019s", Duration::new(1, 1)),
+            ("0s", Duration::ZERO),
+            ("18446744073709551615s", Duration::from_secs(u64::MAX)),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_duration(text), Ok(expected), "parsing `{text}`");
+        }
+    }
+
+    #[test]
+    fn test_parse_duration_errors() {
+        use DurationErrorKind::*;
+        let cases = [
+            ("", Empty, "", 0),
+            ("   ", Empty, "", 0),
+            ("abc", InvalidNumber, "abc", 0),
+            ("-5s", InvalidNumber, "-", 0),
+            ("1h 2x", UnknownUnit, "x", 4),
+            ("5sec", UnknownUnit, "sec", 1),
+            ("1..5s", InvalidNumber, "1..5", 0),
+            ("1h30", MissingUnit, "30", 2),
+            ("30 1h", MissingUnit, "30", 0),
+            (
+                "18446744073709551616s",
+                Overflow,
+                "18446744073709551616s",
+                0,
+            ),
+            ("1s213503982334602d", Overflow, "213503982334602d", 2),
+            (
+                "999999999999999999999999999999999999999999ns",
+                Overflow,
+                "999999999999999999999999999999999999999999ns",
+                0,
+            ),
+        ];
+        for (text, kind, token, position) in cases {
+            let err = parse_duration(text).unwrap_err();
+            assert_eq!(
+                (err.kind(), err.token(), err.position()),
+                (kind, token, position),
+                "parsing `{text}`"
+            );
+        }
+        assert_eq!(
+            parse_duration("1h 2x").unwrap_err().to_string(),
+            "unknown unit `x` at position 4 (expected ns, us, ms, s, m, h or d)"
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        let cases = [
+            (Duration::ZERO, "0s"),
+            (Duration::from_millis(250), "250ms"),
+            (Duration::from_secs(5400), "1h30m"),
+            (Duration::from_millis(1500), "1s500ms"),
+            (Duration::new(90061, 1_001_001), "1d1h1m1s1ms1us1ns"),
+        ];
+        for (duration, text) in cases {
+            assert_eq!(format_duration(duration), text);
+            assert_eq!(parse_duration(text), Ok(duration));
+        }
+        assert_eq!(
+            parse_duration(&format_duration(Duration::MAX)),
+            Ok(Duration::MAX)
+        );
+
+        let duration: HumanDuration = "1h30m".parse().unwrap();
+        assert_eq!(*duration, Duration::from_secs(5400));
+        assert_eq!(duration.to_string(), "1h30m");
+    }
+
+    #[test]
+    fn test_duration_serde_human() {
+        let settings = HumanSettings {
+            idle: Duration::from_secs(5415),
+        };
+        let json = serde_json::to_string(&settings).unwrap();
+        assert_eq!(json, r#"{"idle":"1h30m15s"}"#);
+        assert_eq!(
+            serde_json::from_str::<HumanSettings>(&json).unwrap(),
+            settings
+        );
+        assert!(serde_json::from_str::<HumanSettings>(r#"{"idle":"1h30"}"#).is_err());
+    }
+
+    #[test]
+    fn test_unixtime_systemtime() {
+        let millis = 1_700_000_000_123;
+        let time = unixtime_millis_as_systemtime(millis);
+        assert_eq!(systemtime_as_unixtime_millis(time), millis);
+        assert_eq!(systemtime_as_unixtime_millis(SystemTime::UNIX_EPOCH), 0);
+    }
+}
+
 /*
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,385 @@
+//!
+//! Feature toggles. Toggles are declared with a name, a default value
+//! and a description via [`declare_toggles!`](crate::declare_toggles)
+//! and queried via [`toggle!`](crate::toggle). The value of a toggle is
+//! resolved in the following order of precedence:
+//!
+//! - programmatic override ([`set_override()`])
+//! - environment variable (native and Node.js), named after the toggle
+//!   (see [`env_var_name()`]), accepting `1`, `true`, `on`, `yes` and
+//!   `0`, `false`, `off`, `no`; unrecognized values are ignored
+//! - JSON document (`{ "toggle-name" : true, ... }`) supplied via
+//!   [`load_json()`], typically loaded from an application settings
+//!   file using `workflow_store::fs::read_to_string()`
+//! - the declared default (`false` for toggles that were not declared)
+//!
+//! The resolved value is cached after the first read. Changing an
+//! override or loading a JSON document resets the cache; [`refresh()`]
+//! can be used to pick up changes to environment variables.
+//!
+//! ```ignore
+//! workflow_core::declare_toggles! {
+//!     "new-sync-engine" : false => "Use the new sync engine",
+//!     "compact-ui" : true => "Compact UI layout",
+//! }
+//!
+//! if workflow_core::toggle!("new-sync-engine") {
+//!     // ...
+//! }
+//!
+//! // debug console
+//! for info in workflow_core::toggles::list() {
+//!     println!("{info}");
+//! }
+//! ```
+//!
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
+use thiserror::Error;
+
+/// Prefix of the environment variables controlling toggles
+pub const ENV_PREFIX: &str = "TOGGLE_";
+
+/// Error produced when loading a toggle JSON document
+#[derive(Error, Debug)]
+pub enum ToggleError {
+    #[error("invalid toggle document: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid toggle document: expecting an object")]
+    NotAnObject,
+    #[error("toggle `{0}` must be a boolean")]
+    NotABool(String),
+}
+
+/// Toggle declaration
+#[derive(Debug, Clone, Copy)]
+pub struct Toggle {
+    pub name: &'static str,
+    pub default: bool,
+    pub description: &'static str,
+}
+
+impl Toggle {
+    pub const fn new(name: &'static str, default: bool, description: &'static str) -> Self {
+        Toggle {
+            name,
+            default,
+            description,
+        }
+    }
+}
+
+/// Origin of the toggle value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Source {
+    Override,
+    Env,
+    Store,
+    Default,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = match self {
+            Source::Override => "override",
+            Source::Env => "env",
+            Source::Store => "store",
+            Source::Default => "default",
+        };
+        f.write_str(source)
+    }
+}
+
+/// Toggle state returned by [`list()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToggleInfo {
+    pub name: String,
+    pub description: String,
+    pub default: bool,
+    pub value: bool,
+    pub source: Source,
+}
+
+impl fmt::Display for ToggleInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = if self.value { "on" } else { "off" };
+        write!(f, "{} = {value} ({})", self.name, self.source)?;
+        if !self.description.is_empty() {
+            write!(f, " - {}", self.description)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Entry {
+    default: bool,
+    description: &'static str,
+    value_override: Option<bool>,
+    cached: Option<(bool, Source)>,
+}
+
+struct Registry {
+    entries: BTreeMap<String, Entry>,
+    store: BTreeMap<String, bool>,
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    entries: BTreeMap::new(),
+    store: BTreeMap::new(),
+});
+
+fn registry() -> MutexGuard<'static, Registry> {
+    REGISTRY.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+impl Registry {
+    fn entry(&mut self, name: &str) -> &mut Entry {
+        self.entries.entry(name.to_string()).or_default()
+    }
+
+    fn resolve(&mut self, name: &str) -> (bool, Source) {
+        let stored = self.store.get(name).copied();
+        let entry = self.entry(name);
+        if let Some(cached) = entry.cached {
+            return cached;
+        }
+
+        let resolved = if let Some(value) = entry.value_override {
+            (value, Source::Override)
+        } else if let Some(value) = env_value(name) {
+            (value, Source::Env)
+        } else if let Some(value) = stored {
+            (value, Source::Store)
+        } else {
+            (entry.default, Source::Default)
+        };
+        entry.cached = Some(resolved);
+        resolved
+    }
+
+    fn invalidate(&mut self) {
+        self.entries
+            .values_mut()
+            .for_each(|entry| entry.cached = None);
+    }
+}
+
+/// Register toggle declarations (see [`declare_toggles!`](crate::declare_toggles))
+pub fn declare(toggles: &[Toggle]) {
+    let mut registry = registry();
+    for toggle in toggles {
+        let entry = registry.entry(toggle.name);
+        entry.default = toggle.default;
+        entry.description = toggle.description;
+        entry.cached = None;
+    }
+}
+
+/// Get the value of the toggle (see [`toggle!`](crate::toggle))
+pub fn enabled(name: &str) -> bool {
+    registry().resolve(name).0
+}
+
+/// Get the value of the toggle and its origin
+pub fn get(name: &str) -> (bool, Source) {
+    registry().resolve(name)
+}
+
+/// Override the toggle value, taking precedence over all other sources
+pub fn set_override(name: &str, value: bool) {
+    let mut registry = registry();
+    let entry = registry.entry(name);
+    entry.value_override = Some(value);
+    entry.cached = None;
+}
+
+/// Remove the toggle override
+pub fn clear_override(name: &str) {
+    let mut registry = registry();
+    let entry = registry.entry(name);
+    entry.value_override = None;
+    entry.cached = None;
+}
+
+/// Replace toggle values sourced from the JSON document
+/// (an object mapping toggle names to boolean values)
+pub fn load_json(text: &str) -> Result<(), ToggleError> {
+    let document = serde_json::from_str::<serde_json::Value>(text)?;
+    let object = document.as_object().ok_or(ToggleError::NotAnObject)?;
+    let store = object
+        .iter()
+        .map(|(name, value)| {
+            value
+                .as_bool()
+                .map(|value| (name.clone(), value))
+                .ok_or_else(|| ToggleError::NotABool(name.clone()))
+        })
+        .collect::<Result<BTreeMap<_, _>, _>>()?;
+
+    let mut registry = registry();
+    registry.store = store;
+    registry.invalidate();
+    Ok(())
+}
+
+/// Reset cached toggle values, re-reading the environment on next access
+pub fn refresh() {
+    registry().invalidate();
+}
+
+/// List all known toggles (declared or queried), sorted by name
+pub fn list() -> Vec<ToggleInfo> {
+    let mut registry = registry();
+    let names = registry.entries.keys().cloned().collect::<Vec<_>>();
+    names
+        .into_iter()
+        .map(|name| {
+            let (value, source) = registry.resolve(&name);
+            let entry = registry.entry(&name);
+            ToggleInfo {
+                description: entry.description.to_string(),
+                default: entry.default,
+                value,
+                source,
+                name,
+            }
+        })
+        .collect()
+}
+
+/// Name of the environment variable controlling the toggle:
+/// `new-sync-engine` is controlled by `TOGGLE_NEW_SYNC_ENGINE`
+pub fn env_var_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    format!("{ENV_PREFIX}{name}")
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "on" | "yes" => Some(true),
+        "0" | "false" | "off" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+fn env_value(name: &str) -> Option<bool> {
+    // environment is not available in the browser
+    if cfg!(target_arch = "wasm32") && !crate::runtime::is_node() {
+        return None;
+    }
+    crate::env::var(&env_var_name(name))
+        .ok()
+        .and_then(|value| parse_bool(&value))
+}
+
+/// Declare feature toggles with a name, default value and description:
+/// ```ignore
+/// workflow_core::declare_toggles! {
+///     "new-sync-engine" : false => "Use the new sync engine",
+/// }
+/// ```
+#[macro_export]
+macro_rules! declare_toggles {
+    ($($name:literal : $default:expr => $description:literal),* $(,)?) => {
+        $crate::toggles::declare(&[
+            $($crate::toggles::Toggle::new($name, $default, $description)),*
+        ])
+    };
+}
+
+/// Get the value of a feature toggle: `toggle!("new-sync-engine")`
+#[macro_export]
+macro_rules! toggle {
+    ($name:expr) => {
+        $crate::toggles::enabled($name)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle_precedence() {
+        let name = "test-precedence";
+        let env = env_var_name(name);
+        assert_eq!(env, "TOGGLE_TEST_PRECEDENCE");
+        std::env::remove_var(&env);
+
+        crate::declare_toggles! {
+            "test-precedence" : false => "precedence test",
+        }
+        assert_eq!(get(name), (false, Source::Default));
+
+        load_json(r#"{ "test-precedence" : true }"#).unwrap();
+        assert_eq!(get(name), (true, Source::Store));
+
+        std::env::set_var(&env, "off");
+        // cached until refreshed
+        assert_eq!(get(name), (true, Source::Store));
+        refresh();
+        assert_eq!(get(name), (false, Source::Env));
+
+        set_override(name, true);
+        assert_eq!(get(name), (true, Source::Override));
+        assert!(crate::toggle!(name));
+
+        clear_override(name);
+        assert_eq!(get(name), (false, Source::Env));
+        std::env::set_var(&env, "unknown");
+        refresh();
+        assert_eq!(get(name), (true, Source::Store));
+        std::env::remove_var(&env);
+
+        load_json("{}").unwrap();
+        assert_eq!(get(name), (false, Source::Default));
+    }
+
+    #[test]
+    fn test_toggle_list() {
+        crate::declare_toggles! {
+            "test-list-a" : true => "first",
+            "test-list-b" : false => "second",
+        }
+        set_override("test-list-b", true);
+        assert!(!crate::toggle!("test-list-undeclared"));
+
+        let list = list()
+            .into_iter()
+            .filter(|info| info.name.starts_with("test-list-"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            list.iter().map(|info| info.to_string()).collect::<Vec<_>>(),
+            vec![
+                "test-list-a = on (default) - first",
+                "test-list-b = on (override) - second",
+                "test-list-undeclared = off (default)",
+            ]
+        );
+        assert!(!list[1].default);
+    }
+
+    #[test]
+    fn test_toggle_document_errors() {
+        assert!(matches!(load_json("[]"), Err(ToggleError::NotAnObject)));
+        assert!(matches!(
+            load_json(r#"{ "a" : 1 }"#),
+            Err(ToggleError::NotABool(name)) if name == "a"
+        ));
+        assert!(matches!(load_json("{"), Err(ToggleError::Json(_))));
+        assert_eq!(parse_bool(" Yes "), Some(true));
+        assert_eq!(parse_bool("2"), None);
+    }
+}
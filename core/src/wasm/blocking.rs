@@ -0,0 +1,45 @@
+//!
+//! `dispatch_blocking()` executing CPU-bound work cooperatively
+//! on the single-threaded browser executor.
+//!
+
+use crate::task::BlockingMode;
+use crate::time::{Duration, Instant};
+use std::ops::ControlFlow;
+
+/// Time after which the executor is yielded to the
+/// browser event loop while steps are being executed.
+pub const TIME_SLICE: Duration = Duration::from_millis(8);
+
+/// Execution mode of [`dispatch_blocking()`] on the `wasm32` target.
+pub fn blocking_mode() -> BlockingMode {
+    BlockingMode::Cooperative
+}
+
+/// Executes the `step` closure until it returns [`ControlFlow::Break`]
+/// on the current executor, yielding to the browser event loop once
+/// [`TIME_SLICE`] has elapsed. As the execution of a step can not be
+/// interrupted, each step should complete well within the time slice.
+pub async fn dispatch_blocking<F, T>(mut step: F) -> T
+where
+    F: FnMut() -> ControlFlow<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let mut slice = Instant::now();
+    loop {
+        if let ControlFlow::Break(value) = step() {
+            return value;
+        }
+
+        if slice.elapsed() >= TIME_SLICE {
+            cfg_if::cfg_if! {
+                if #[cfg(target_arch = "wasm32")] {
+                    super::yield_executor::yield_executor().await;
+                } else {
+                    tokio::task::yield_now().await;
+                }
+            }
+            slice = Instant::now();
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//!
+//! [`Instant`] shim backed by `performance.now()` (or `Date.now()` if the
+//! Performance API is not available). Unlike `instant::Instant`, the
+//! arithmetic matches `std::time::Instant` semantics: durations between
+//! reversed instants (e.g. captured across a `performance.now()` clock
+//! reset after a bfcache restore) saturate to zero instead of panicking.
+//! Re-exported as [`SaturatingInstant`](crate::time::SaturatingInstant),
+//! leaving `time::Instant` as the `instant` crate re-export.
+//!
+
+use js_sys::{Function, Reflect};
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::time::Duration;
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    static PERFORMANCE: Option<(JsValue, Function)> = {
+        Reflect::get(&js_sys::global(), &"performance".into())
+            .ok()
+            .filter(|performance| !performance.is_undefined() && !performance.is_null())
+            .and_then(|performance| {
+                let now = Reflect::get(&performance, &"now".into()).ok()?;
+                Some((performance, now.dyn_into::<Function>().ok()?))
+            })
+    };
+}
+
+fn now_millis() -> f64 {
+    PERFORMANCE
+        .with(|performance| {
+            performance
+                .as_ref()
+                .and_then(|(performance, now)| now.call0(performance).ok()?.as_f64())
+        })
+        .unwrap_or_else(js_sys::Date::now)
+}
+
+fn duration_from_millis(millis: f64) -> Duration {
+    if millis.is_finite() && millis > 0.0 {
+        Duration::from_nanos((millis * 1_000_000.0) as u64)
+    } else {
+        Duration::ZERO
+    }
+}
+
+/// Monotonic clock measurement, represented as the
+/// duration since the `performance.timeOrigin`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(Duration);
+
+impl Instant {
+    pub fn now() -> Instant {
+        Instant(duration_from_millis(now_millis()))
+    }
+
+    /// Amount of time elapsed since `earlier`, or zero
+    /// if `earlier` is later than `self`.
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        self.saturating_duration_since(earlier)
+    }
+
+    /// Amount of time elapsed since `earlier`, or `None`
+    /// if `earlier` is later than `self`.
+    pub fn checked_duration_since(&self, earlier: Instant) -> Option<Duration> {
+        self.0.checked_sub(earlier.0)
+    }
+
+    /// Amount of time elapsed since `earlier`, or zero
+    /// if `earlier` is later than `self`.
+    pub fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+        self.checked_duration_since(earlier).unwrap_or_default()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().saturating_duration_since(*self)
+    }
+
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        self.0.checked_add(duration).map(Instant)
+    }
+
+    pub fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+        self.0.checked_sub(duration).map(Instant)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    /// # Panics
+    ///
+    /// Panics if the resulting point in time can not be represented.
+    fn add(self, other: Duration) -> Instant {
+        self.checked_add(other)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, other: Duration) {
+        *self = *self + other;
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    /// # Panics
+    ///
+    /// Panics if the resulting point in time precedes the time origin.
+    fn sub(self, other: Duration) -> Instant {
+        self.checked_sub(other)
+            .expect("overflow when subtracting duration from instant")
+    }
+}
+
+impl SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, other: Duration) {
+        *self = *self - other;
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    /// Saturates to zero if `other` is later than `self`.
+    fn sub(self, other: Instant) -> Duration {
+        self.saturating_duration_since(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn test_reversed_instants() {
+        let earlier = Instant(Duration::from_millis(1500));
+        let later = earlier + Duration::from_millis(250);
+
+        assert_eq!(
+            later.checked_duration_since(earlier),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(later - earlier, Duration::from_millis(250));
+
+        // a clock reset produces instants in reverse order
+        assert_eq!(earlier.checked_duration_since(later), None);
+        assert_eq!(earlier.saturating_duration_since(later), Duration::ZERO);
+        assert_eq!(earlier.duration_since(later), Duration::ZERO);
+        assert_eq!(earlier - later, Duration::ZERO);
+
+        assert_eq!(earlier.checked_sub(Duration::from_secs(2)), None);
+        assert_eq!(
+            earlier.checked_sub(Duration::from_millis(500)),
+            Some(Instant(Duration::from_secs(1)))
+        );
+        assert_eq!(
+            Instant(Duration::MAX).checked_add(Duration::from_nanos(1)),
+            None
+        );
+        assert_eq!(duration_from_millis(-1.0), Duration::ZERO);
+        assert_eq!(duration_from_millis(f64::NAN), Duration::ZERO);
+        assert_eq!(duration_from_millis(1.5), Duration::from_micros(1500));
+    }
+
+    #[test]
+    fn test_now() {
+        let start = Instant::now();
+        let now = Instant::now();
+        assert!(now >= start);
+        assert!(start.elapsed() >= now - start);
+    }
+}
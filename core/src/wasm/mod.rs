@@ -1,4 +1,12 @@
+#[cfg(all(feature = "time", target_arch = "wasm32"))]
+pub mod instant;
+#[cfg(all(feature = "task", any(test, target_arch = "wasm32")))]
+pub mod blocking;
+#[cfg(feature = "task")]
 pub mod interval;
+#[cfg(feature = "task")]
 pub mod overrides;
+#[cfg(feature = "task")]
 pub mod sleep;
+#[cfg(feature = "task")]
 pub mod yield_executor;
@@ -20,6 +20,15 @@ pub enum Error {
 
     #[error(transparent)]
     Wasm(#[from] workflow_wasm::error::Error),
+
+    #[error("D3 is not loaded (`window.d3` is not available)")]
+    NotLoaded,
+
+    #[error("Unsupported D3 version `{found}` (expected {expected}.x)")]
+    Version { expected: u32, found: String },
+
+    #[error("Unable to load D3 from `{0}`")]
+    ScriptLoad(String),
 }
 
 impl From<Error> for JsValue {
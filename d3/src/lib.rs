@@ -8,4 +8,6 @@ pub mod result;
 mod script;
 
 pub use d3::D3;
-pub use script::load;
+pub use script::{
+    check_version, load, load_from, LoadSource, D3_MAJOR_VERSION, D3_SCRIPT_ID, D3_VERSION,
+};
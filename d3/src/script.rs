@@ -1,17 +1,243 @@
+//!
+//! Loading of the D3 library from the embedded bundle, a pinned
+//! CDN version or a self-hosted URL.
+//!
+
+use crate::imports::*;
+use std::cell::RefCell;
+use workflow_core::channel::{oneshot, Sender};
 use workflow_dom::inject::*;
-use workflow_dom::result::Result;
 
-static mut D3_LOADED: bool = false;
+/// Version of the bundled D3 library.
+pub const D3_VERSION: &str = "7.8.5";
+/// Major D3 version supported by the bindings in this crate.
+pub const D3_MAJOR_VERSION: u32 = 7;
+/// Id of the `<script>` element injected by [`load()`].
+pub const D3_SCRIPT_ID: &str = "workflow-d3";
+
+/// Source of the D3 library.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum LoadSource {
+    /// Pinned version loaded from jsDelivr; `sri_hash` (e.g. `sha384-...`)
+    /// is set as the `integrity` attribute of the script element.
+    Cdn {
+        version: String,
+        sri_hash: Option<String>,
+    },
+    /// D3 bundle embedded in the WASM binary (works offline).
+    #[default]
+    Embedded,
+    /// Self-hosted D3 script.
+    Url(String),
+}
+
+impl LoadSource {
+    /// URL of the script; `None` for [`LoadSource::Embedded`].
+    pub fn url(&self) -> Option<String> {
+        match self {
+            LoadSource::Cdn { version, .. } => Some(format!(
+                "https://cdn.jsdelivr.net/npm/d3@{version}/dist/d3.min.js"
+            )),
+            LoadSource::Embedded => None,
+            LoadSource::Url(url) => Some(url.clone()),
+        }
+    }
+}
+
+enum State {
+    Idle,
+    Loading(Vec<Sender<Result<()>>>),
+    Ready,
+}
+
+thread_local! {
+    static STATE: RefCell<State> = const { RefCell::new(State::Idle) };
+}
 
+/// Loads D3 from the embedded bundle; see [`load_from()`].
 pub async fn load() -> Result<()> {
-    if unsafe { D3_LOADED } {
+    load_from(LoadSource::Embedded).await
+}
+
+/// Loads D3 from `source` and verifies it using [`check_version()`].
+/// Loading is idempotent: once D3 is available (including when loaded
+/// by the page itself) subsequent calls return immediately regardless
+/// of the `source`, and concurrent calls await the pending load.
+pub async fn load_from(source: LoadSource) -> Result<()> {
+    enum Action {
+        Ready,
+        Wait(workflow_core::channel::Receiver<Result<()>>),
+        Load,
+    }
+
+    let action = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        match &mut *state {
+            State::Ready => Action::Ready,
+            State::Loading(pending) => {
+                let (sender, receiver) = oneshot();
+                pending.push(sender);
+                Action::Wait(receiver)
+            }
+            State::Idle => {
+                *state = State::Loading(Vec::new());
+                Action::Load
+            }
+        }
+    });
+
+    match action {
+        Action::Ready => Ok(()),
+        Action::Wait(receiver) => receiver
+            .recv()
+            .await
+            .map_err(|err| Error::Custom(err.to_string()))?,
+        Action::Load => {
+            let result = load_impl(&source).await;
+            let pending = STATE.with(|state| {
+                let next = if result.is_ok() {
+                    State::Ready
+                } else {
+                    State::Idle
+                };
+                match state.replace(next) {
+                    State::Loading(pending) => pending,
+                    _ => Vec::new(),
+                }
+            });
+            for sender in pending {
+                sender.try_send(result.clone()).ok();
+            }
+            result
+        }
+    }
+}
+
+async fn load_impl(source: &LoadSource) -> Result<()> {
+    // D3 may have been loaded by the page
+    if check_version().is_ok() {
         return Ok(());
     }
 
-    let d3_js = include_bytes!("../extern/resources/d3.v7.min.js");
-    inject_blob(Content::Script(None, d3_js)).await?;
+    match source {
+        LoadSource::Embedded => {
+            let d3_js = include_bytes!("../extern/resources/d3.v7.min.js");
+            inject_blob(Content::Script(Some(D3_SCRIPT_ID), d3_js)).await?;
+        }
+        LoadSource::Cdn { sri_hash, .. } => {
+            inject_url(&source.url().unwrap(), sri_hash.as_deref()).await?;
+        }
+        LoadSource::Url(url) => {
+            inject_url(url, None).await?;
+        }
+    }
+
+    check_version().map(|_| ())
+}
+
+/// Injects a script element referencing `url`, resolving once the
+/// script has loaded or failed to load (including integrity failures).
+async fn inject_url(url: &str, integrity: Option<&str>) -> Result<()> {
+    let (sender, receiver) = oneshot();
+    let error_sender = sender.clone();
+    let onload = callback!(move |_event: web_sys::Event| {
+        sender.try_send(true).ok();
+    });
+    let onerror = callback!(move |_event: web_sys::Event| {
+        error_sender.try_send(false).ok();
+    });
+
+    let doc = document();
+    let script = doc.create_element("script")?;
+    script.set_attribute("id", D3_SCRIPT_ID)?;
+    script.set_attribute("type", "text/javascript")?;
+    script.set_attribute("crossorigin", "anonymous")?;
+    if let Some(integrity) = integrity {
+        script.set_attribute("integrity", integrity)?;
+    }
+    script.set_attribute("src", url)?;
+    script.add_event_listener_with_callback("load", onload.as_ref())?;
+    script.add_event_listener_with_callback("error", onerror.as_ref())?;
+    let root = match doc.query_selector("head")? {
+        Some(head) => head,
+        None => doc
+            .query_selector("body")?
+            .ok_or_else(|| Error::Custom("Unable to locate head element".to_string()))?,
+    };
+    root.append_child(&script)?;
+
+    let loaded = receiver
+        .recv()
+        .await
+        .map_err(|err| Error::Custom(err.to_string()))?;
+    if loaded {
+        Ok(())
+    } else {
+        script.remove();
+        Err(Error::ScriptLoad(url.to_string()))
+    }
+}
 
-    unsafe { D3_LOADED = true };
+/// Verifies that `window.d3` is available and matches [`D3_MAJOR_VERSION`],
+/// returning the D3 version.
+pub fn check_version() -> Result<String> {
+    let d3 = js_sys::Reflect::get(&js_sys::global(), &"d3".into())?;
+    if d3.is_undefined() || d3.is_null() {
+        return Err(Error::NotLoaded);
+    }
+    let version = js_sys::Reflect::get(&d3, &"version".into())?
+        .as_string()
+        .unwrap_or_default();
+    verify_version(&version)?;
+    Ok(version)
+}
 
-    Ok(())
+fn verify_version(version: &str) -> Result<()> {
+    let major = version
+        .split('.')
+        .next()
+        .and_then(|major| major.parse().ok());
+    if major == Some(D3_MAJOR_VERSION) {
+        Ok(())
+    } else {
+        Err(Error::Version {
+            expected: D3_MAJOR_VERSION,
+            found: version.to_string(),
+        })
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    async fn test_load_embedded() {
+        let (first, second) = futures::join!(load(), load());
+        first.unwrap();
+        second.unwrap();
+        load_from(LoadSource::Url("https://localhost/d3.js".to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(check_version().unwrap(), D3_VERSION);
+        let scripts = document()
+            .query_selector_all(&format!("script#{D3_SCRIPT_ID}"))
+            .unwrap();
+        assert_eq!(scripts.length(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_version_check() {
+        assert!(verify_version("7.8.5").is_ok());
+        for version in ["6.7.0", "8.0.0", ""] {
+            assert!(matches!(
+                verify_version(version),
+                Err(Error::Version { expected: 7, ref found }) if found == version
+            ));
+        }
+    }
 }
@@ -1,13 +1,66 @@
+//!
+//! Access to the system clipboard via the asynchronous
+//! [Clipboard API](https://developer.mozilla.org/en-US/docs/Web/API/Clipboard_API).
+//!
+//! The Clipboard API is only available in secure contexts (HTTPS or
+//! `localhost`); in other contexts [`write_text()`] and [`read_text()`]
+//! return [`Error::Unsupported`]. A denied clipboard permission is
+//! reported as [`Error::PermissionDenied`].
+//!
+
+use crate::error::Error;
+use crate::result::Result;
+use js_sys::Reflect;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
 extern "C" {
-    #[wasm_bindgen (js_namespace=["navigator", "clipboard"], js_name="readText")]
-    pub async fn read_text() -> JsValue;
+    #[wasm_bindgen (catch, js_namespace=["navigator", "clipboard"], js_name="readText")]
+    async fn read_text_impl() -> std::result::Result<JsValue, JsValue>;
     #[wasm_bindgen (js_namespace=["navigator", "clipboard"], js_name="read")]
     pub async fn read() -> JsValue;
     #[wasm_bindgen (catch, js_namespace=["navigator", "clipboard"], js_name="writeText")]
-    pub async fn write_text(text: &str) -> Result<(), JsValue>;
+    async fn write_text_impl(text: &str) -> std::result::Result<(), JsValue>;
     #[wasm_bindgen (catch, js_namespace=["navigator", "clipboard"], js_name="write")]
-    pub async fn write(data: JsValue) -> Result<(), JsValue>;
+    pub async fn write(data: JsValue) -> std::result::Result<(), JsValue>;
+}
+
+/// Returns `true` if the Clipboard API is available in the current context.
+pub fn is_supported() -> bool {
+    Reflect::get(&js_sys::global(), &"navigator".into())
+        .and_then(|navigator| Reflect::get(&navigator, &"clipboard".into()))
+        .map(|clipboard| !clipboard.is_undefined() && !clipboard.is_null())
+        .unwrap_or(false)
+}
+
+fn check_supported() -> Result<()> {
+    if is_supported() {
+        Ok(())
+    } else {
+        Err(Error::Unsupported("Clipboard API".to_string()))
+    }
+}
+
+fn clipboard_error(err: JsValue) -> Error {
+    let name = Reflect::get(&err, &"name".into())
+        .ok()
+        .and_then(|name| name.as_string());
+    if name.as_deref() == Some("NotAllowedError") {
+        Error::PermissionDenied("clipboard".to_string())
+    } else {
+        err.into()
+    }
+}
+
+/// Writes `text` to the clipboard.
+pub async fn write_text(text: &str) -> Result<()> {
+    check_supported()?;
+    write_text_impl(text).await.map_err(clipboard_error)
+}
+
+/// Reads text from the clipboard.
+pub async fn read_text() -> Result<String> {
+    check_supported()?;
+    let text = read_text_impl().await.map_err(clipboard_error)?;
+    Ok(text.as_string().unwrap_or_default())
 }
@@ -0,0 +1,316 @@
+//!
+//! [`ElementBuilder`] for constructing DOM elements without markup
+//! and [`DocumentFragmentBatch`] for appending large numbers of
+//! elements in a single DOM operation.
+//!
+//! ```ignore
+//! let (element, listeners) = ElementBuilder::tag("button")
+//!     .class("primary")
+//!     .attr("type", "button")
+//!     .text("Save")
+//!     .on("click", move |_event| save())
+//!     .append_to(&body()?)?;
+//! // removes the `click` listener when dropped
+//! drop(listeners);
+//! ```
+//!
+
+use crate::error::Error;
+use crate::result::Result;
+use crate::utils::document;
+use web_sys::{DocumentFragment, Element, EventTarget, Node};
+use workflow_wasm::callback::*;
+
+pub type EventCallback = Callback<CallbackClosureWithoutResult<web_sys::Event>>;
+
+/// Event listener registered by [`ElementBuilder::on()`].
+/// The listener is detached from its target when dropped.
+pub struct EventListener {
+    target: EventTarget,
+    event: String,
+    callback: EventCallback,
+}
+
+impl EventListener {
+    fn try_new<F>(target: &EventTarget, event: &str, mut callback: F) -> Result<Self>
+    where
+        F: FnMut(web_sys::Event) + 'static,
+    {
+        let callback = callback!(move |event: web_sys::Event| {
+            callback(event);
+        });
+        target.add_event_listener_with_callback(event, callback.as_ref())?;
+        Ok(Self {
+            target: target.clone(),
+            event: event.to_string(),
+            callback,
+        })
+    }
+
+    pub fn event(&self) -> &str {
+        &self.event
+    }
+}
+
+impl Drop for EventListener {
+    fn drop(&mut self) {
+        self.target
+            .remove_event_listener_with_callback(&self.event, self.callback.as_ref())
+            .ok();
+    }
+}
+
+/// Guard retaining event listeners registered while building elements.
+/// Dropping the guard (or calling [`Listeners::remove()`]) detaches
+/// all listeners; [`Listeners::forget()`] keeps them attached for the
+/// lifetime of the page.
+#[derive(Default)]
+pub struct Listeners {
+    listeners: Vec<EventListener>,
+}
+
+impl Listeners {
+    pub fn len(&self) -> usize {
+        self.listeners.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.listeners.is_empty()
+    }
+
+    /// Detaches all listeners.
+    pub fn remove(self) {
+        drop(self);
+    }
+
+    /// Keeps listeners attached permanently, leaking their closures.
+    pub fn forget(self) {
+        std::mem::forget(self);
+    }
+
+    fn extend(&mut self, mut other: Listeners) {
+        self.listeners.append(&mut other.listeners);
+    }
+}
+
+/// Builder for a DOM [`Element`]. Operations are applied to the element
+/// as they are chained; the first failure is retained and returned
+/// from [`ElementBuilder::build()`] or [`ElementBuilder::append_to()`].
+pub struct ElementBuilder {
+    element: Result<Element>,
+    listeners: Listeners,
+}
+
+impl ElementBuilder {
+    /// Creates a builder for a new element with the given tag name.
+    pub fn tag(name: &str) -> Self {
+        Self {
+            element: document().create_element(name).map_err(Error::from),
+            listeners: Listeners::default(),
+        }
+    }
+
+    fn apply<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&Element, &mut Listeners) -> Result<()>,
+    {
+        if let Ok(element) = &self.element {
+            if let Err(err) = f(element, &mut self.listeners) {
+                self.element = Err(err);
+            }
+        }
+        self
+    }
+
+    pub fn attr(self, name: &str, value: &str) -> Self {
+        self.apply(|element, _| Ok(element.set_attribute(name, value)?))
+    }
+
+    /// Adds a class to the element's `classList`.
+    pub fn class(self, name: &str) -> Self {
+        self.apply(|element, _| Ok(element.class_list().add_1(name)?))
+    }
+
+    /// Appends a text node.
+    pub fn text(self, text: &str) -> Self {
+        self.apply(|element, _| {
+            element.append_child(&document().create_text_node(text))?;
+            Ok(())
+        })
+    }
+
+    /// Appends a child element; its event listeners are
+    /// retained by this builder.
+    pub fn child(self, child: ElementBuilder) -> Self {
+        self.apply(|element, listeners| {
+            let (child, child_listeners) = child.build()?;
+            element.append_child(&child)?;
+            listeners.extend(child_listeners);
+            Ok(())
+        })
+    }
+
+    /// Appends an existing DOM node.
+    pub fn node(self, node: &Node) -> Self {
+        self.apply(|element, _| {
+            element.append_child(node)?;
+            Ok(())
+        })
+    }
+
+    /// Registers an event listener. The listener remains attached
+    /// while the [`Listeners`] guard returned by the builder is alive.
+    pub fn on<F>(self, event: &str, callback: F) -> Self
+    where
+        F: FnMut(web_sys::Event) + 'static,
+    {
+        self.apply(|element, listeners| {
+            listeners
+                .listeners
+                .push(EventListener::try_new(element, event, callback)?);
+            Ok(())
+        })
+    }
+
+    pub fn build(self) -> Result<(Element, Listeners)> {
+        Ok((self.element?, self.listeners))
+    }
+
+    /// Builds the element and appends it to `parent`.
+    pub fn append_to(self, parent: &Node) -> Result<(Element, Listeners)> {
+        let (element, listeners) = self.build()?;
+        parent.append_child(&element)?;
+        Ok((element, listeners))
+    }
+}
+
+/// Accumulates nodes in a [`DocumentFragment`] and appends all of them
+/// to the parent in a single operation, producing a single reflow.
+pub struct DocumentFragmentBatch {
+    fragment: DocumentFragment,
+    listeners: Listeners,
+    len: usize,
+}
+
+impl DocumentFragmentBatch {
+    pub fn new() -> Self {
+        Self {
+            fragment: document().create_document_fragment(),
+            listeners: Listeners::default(),
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, node: &Node) -> Result<()> {
+        self.fragment.append_child(node)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Builds the element and adds it to the batch, retaining its listeners.
+    pub fn push_element(&mut self, builder: ElementBuilder) -> Result<Element> {
+        let (element, listeners) = builder.build()?;
+        self.push(&element)?;
+        self.listeners.extend(listeners);
+        Ok(element)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends all accumulated nodes to `parent`.
+    pub fn append_to(self, parent: &Node) -> Result<Listeners> {
+        parent.append_child(&self.fragment)?;
+        Ok(self.listeners)
+    }
+}
+
+impl Default for DocumentFragmentBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use crate::utils::body;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::*;
+    use web_sys::{MutationObserver, MutationObserverInit};
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_fragment_batch() {
+        let (list, _) = ElementBuilder::tag("ul")
+            .attr("id", "batch-list")
+            .append_to(&body().unwrap())
+            .unwrap();
+
+        let observer = MutationObserver::new(&js_sys::Function::new_no_args("")).unwrap();
+        let init = MutationObserverInit::new();
+        init.set_child_list(true);
+        observer.observe_with_options(&list, &init).unwrap();
+
+        let mut batch = DocumentFragmentBatch::new();
+        for n in 0..1000 {
+            batch
+                .push_element(
+                    ElementBuilder::tag("li")
+                        .class("item")
+                        .text(&format!("item {n}")),
+                )
+                .unwrap();
+        }
+        assert_eq!(batch.len(), 1000);
+        batch.append_to(&list).unwrap();
+
+        let records = observer.take_records();
+        observer.disconnect();
+        assert_eq!(records.length(), 1);
+        let record: web_sys::MutationRecord = records.get(0).unchecked_into();
+        assert_eq!(record.added_nodes().length(), 1000);
+        assert_eq!(list.child_element_count(), 1000);
+        assert_eq!(
+            list.last_element_child().unwrap().text_content().as_deref(),
+            Some("item 999")
+        );
+        list.remove();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_listener_guard() {
+        let clicks = Arc::new(AtomicUsize::new(0));
+        let clicks_ = clicks.clone();
+        let (button, listeners) = ElementBuilder::tag("div")
+            .child(ElementBuilder::tag("button").attr("id", "guard-button").on(
+                "click",
+                move |_| {
+                    clicks_.fetch_add(1, Ordering::SeqCst);
+                },
+            ))
+            .build()
+            .unwrap();
+        let button = button.first_element_child().unwrap();
+        assert_eq!(listeners.len(), 1);
+
+        let click = || {
+            button
+                .dispatch_event(&web_sys::Event::new("click").unwrap())
+                .unwrap();
+        };
+        click();
+        assert_eq!(clicks.load(Ordering::SeqCst), 1);
+        listeners.remove();
+        click();
+        assert_eq!(clicks.load(Ordering::SeqCst), 1);
+    }
+}
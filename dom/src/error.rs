@@ -20,6 +20,36 @@ pub enum Error {
     JsValue(JsErrorData),
     #[error("{0}")]
     RecvError(RecvError), //#[from] workflow_core::channel::RecvError),
+    /// Resource injection failure
+    #[error("Unable to inject `{id}`: {message}")]
+    Inject { id: String, message: String },
+    /// Circular dependency between injected resources
+    #[error("Dependency cycle detected between: {}", .0.join(", "))]
+    DependencyCycle(Vec<String>),
+    /// Injected resource depends on an unknown resource
+    #[error("`{id}` depends on unknown resource `{dependency}`")]
+    MissingDependency { id: String, dependency: String },
+    /// Duplicate resource id
+    #[error("Duplicate resource id `{0}`")]
+    DuplicateId(String),
+    /// Resource fetch failure
+    #[error("Unable to fetch `{url}`: {message}")]
+    Fetch { url: String, message: String },
+    /// Resource load timeout
+    #[error("Timeout loading `{0}`")]
+    Timeout(String),
+    /// Operation aborted via [`Abortable`](workflow_core::abortable::Abortable)
+    #[error("Aborted")]
+    Aborted,
+    /// Resource load failures (`(url, error)` pairs)
+    #[error("Unable to load resources: {}", .0.iter().map(|(url, err)| format!("`{url}`: {err}")).collect::<Vec<_>>().join("; "))]
+    Load(Vec<(String, Error)>),
+    /// Browser API not available in the current context
+    #[error("{0} is not supported in this context")]
+    Unsupported(String),
+    /// Permission to use a browser API has been denied
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
 }
 
 unsafe impl Send for Error {}
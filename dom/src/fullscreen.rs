@@ -0,0 +1,62 @@
+//!
+//! Helpers for the [Fullscreen API](https://developer.mozilla.org/en-US/docs/Web/API/Fullscreen_API).
+//!
+//! Functions return [`Error::Unsupported`] if the Fullscreen API is not
+//! available or fullscreen is disabled for the document (for example
+//! within an `iframe` lacking the `allowfullscreen` attribute).
+//!
+
+use crate::error::Error;
+use crate::result::Result;
+use crate::utils::document;
+use js_sys::{Function, Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::Element;
+
+/// Returns `true` if the document allows switching to fullscreen.
+pub fn is_supported() -> bool {
+    Reflect::get(&document(), &"fullscreenEnabled".into())
+        .map(|enabled| enabled.is_truthy())
+        .unwrap_or(false)
+}
+
+/// Returns `true` if an element is currently displayed in fullscreen.
+pub fn is_fullscreen() -> bool {
+    document().fullscreen_element().is_some()
+}
+
+/// Returns the element currently displayed in fullscreen.
+pub fn element() -> Option<Element> {
+    document().fullscreen_element()
+}
+
+/// Invokes `target[method]()`, awaiting the returned promise if any.
+async fn invoke(target: &JsValue, method: &str) -> Result<()> {
+    if !is_supported() {
+        return Err(Error::Unsupported("Fullscreen API".to_string()));
+    }
+    let function = Reflect::get(target, &method.into())?;
+    let function = function
+        .dyn_into::<Function>()
+        .map_err(|_| Error::Unsupported("Fullscreen API".to_string()))?;
+    let result = function.call0(target)?;
+    if let Some(promise) = result.dyn_ref::<Promise>() {
+        JsFuture::from(promise.clone()).await?;
+    }
+    Ok(())
+}
+
+/// Displays `element` in fullscreen. Browsers only honor this request
+/// when made in response to a user gesture.
+pub async fn request(element: &Element) -> Result<()> {
+    invoke(element, "requestFullscreen").await
+}
+
+/// Exits fullscreen mode. Does nothing if the document is not in fullscreen.
+pub async fn exit() -> Result<()> {
+    if !is_fullscreen() {
+        return Ok(());
+    }
+    invoke(&document(), "exitFullscreen").await
+}
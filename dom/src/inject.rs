@@ -8,12 +8,16 @@
 //! binary.
 //!
 
+use crate::error::Error;
 use crate::result::*;
 use crate::utils::*;
 use js_sys::{Array, Function, Uint8Array};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use wasm_bindgen::JsCast;
 use web_sys::Element;
 use web_sys::{Blob, Url};
-use workflow_core::channel::oneshot;
+use workflow_core::channel::{oneshot, Receiver};
 use workflow_wasm::callback::*;
 
 pub type CustomEventCallback = Callback<CallbackClosureWithoutResult<web_sys::CustomEvent>>;
@@ -29,6 +33,24 @@ pub enum Content<'content> {
     Style(Option<&'content str>, &'content [u8]),
 }
 
+impl<'content> Content<'content> {
+    /// Returns the content `id` if present.
+    pub fn id(&self) -> Option<&'content str> {
+        match self {
+            Content::Script(id, _) | Content::Module(id, _) | Content::Style(id, _) => *id,
+        }
+    }
+
+    /// Creates an [`InjectItem`] that will be injected by [`inject_group()`]
+    /// only after the resources with the given ids have been loaded.
+    pub fn depends_on(self, ids: &[&'content str]) -> InjectItem<'content> {
+        InjectItem {
+            content: self,
+            depends_on: ids.to_vec(),
+        }
+    }
+}
+
 /// Inject CSS stylesheed directly into DOM as a
 /// [`<style>`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/style)
 /// element using [`Element::set_inner_html`]
@@ -95,6 +117,16 @@ pub fn inject_script<C>(
 where
     C: AsRef<Function>,
 {
+    let script = create_script_element(id, content, content_type)?;
+    if let Some(callback) = callback {
+        script.add_event_listener_with_callback("load", callback.as_ref())?;
+    }
+    root.append_child(&script)?;
+
+    Ok(())
+}
+
+fn create_script_element(id: Option<&str>, content: &[u8], content_type: &str) -> Result<Element> {
     let doc = document();
     let string = String::from_utf8_lossy(content);
     let regex = regex::Regex::new(r"//# sourceMappingURL.*$").unwrap();
@@ -108,17 +140,12 @@ where
     let url = Url::create_object_url_with_blob(&blob)?;
 
     let script = doc.create_element("script")?;
-    if let Some(callback) = callback {
-        script.add_event_listener_with_callback("load", callback.as_ref())?;
-    }
     if let Some(id) = id {
         script.set_attribute("id", id)?;
     }
     script.set_attribute("type", content_type)?;
     script.set_attribute("src", &url)?;
-    root.append_child(&script)?;
-
-    Ok(())
+    Ok(script)
 }
 
 pub fn inject_stylesheet<C>(
@@ -130,24 +157,29 @@ pub fn inject_stylesheet<C>(
 where
     C: AsRef<Function>,
 {
+    let style = create_stylesheet_element(id, content)?;
+    if let Some(callback) = callback {
+        style.add_event_listener_with_callback("load", callback.as_ref())?;
+        // closure.forget();
+    }
+    root.append_child(&style)?;
+    Ok(())
+}
+
+fn create_stylesheet_element(id: Option<&str>, content: &[u8]) -> Result<Element> {
     let args = Array::new_with_length(1);
     args.set(0, unsafe { Uint8Array::view(content).into() });
     let blob = Blob::new_with_u8_array_sequence(&args)?;
     let url = Url::create_object_url_with_blob(&blob)?;
 
     let style = document().create_element("link")?;
-    if let Some(callback) = callback {
-        style.add_event_listener_with_callback("load", callback.as_ref())?;
-        // closure.forget();
-    }
     if let Some(id) = id {
         style.set_attribute("id", id)?;
     }
     style.set_attribute("type", "text/css")?;
     style.set_attribute("rel", "stylesheet")?;
     style.set_attribute("href", &url)?;
-    root.append_child(&style)?;
-    Ok(())
+    Ok(style)
 }
 
 /// Inject data buffer contained in the [`Content`] struct as a [`Blob`](https://developer.mozilla.org/en-US/docs/Web/API/Blob)
@@ -158,16 +190,7 @@ pub fn inject_blob_with_callback<C>(content: Content, callback: Option<&C>) -> R
 where
     C: AsRef<Function>,
 {
-    let doc = document();
-    let root = {
-        let collection = doc.get_elements_by_tag_name("head");
-        if collection.length() > 0 {
-            collection.item(0).unwrap()
-        } else {
-            doc.get_elements_by_tag_name("body").item(0).unwrap()
-        }
-    };
-
+    let root = injection_root();
     match content {
         Content::Script(id, content) => {
             inject_script(root, id, content, "text/javascript", callback)?;
@@ -182,3 +205,350 @@ where
 
     Ok(())
 }
+
+/// Returns the `head` element of the document (or `body` if
+/// `head` is not present).
+pub(crate) fn injection_root() -> Element {
+    let doc = document();
+    let collection = doc.get_elements_by_tag_name("head");
+    if collection.length() > 0 {
+        collection.item(0).unwrap()
+    } else {
+        doc.get_elements_by_tag_name("body").item(0).unwrap()
+    }
+}
+
+/// Resource injected by [`inject_group()`] along with the
+/// ids of the resources it depends on.
+pub struct InjectItem<'content> {
+    pub content: Content<'content>,
+    pub depends_on: Vec<&'content str>,
+}
+
+impl<'content> From<Content<'content>> for InjectItem<'content> {
+    fn from(content: Content<'content>) -> Self {
+        Self {
+            content,
+            depends_on: Vec::new(),
+        }
+    }
+}
+
+/// Options for [`inject_group()`].
+#[derive(Clone)]
+pub struct InjectOptions {
+    /// Element receiving the injected resources
+    /// (defaults to the document `head`).
+    pub root: Option<Element>,
+    /// Inject stylesheets without waiting for the previous ones to load.
+    /// Resources depending on a stylesheet still wait for it to load.
+    pub parallel_styles: bool,
+}
+
+impl Default for InjectOptions {
+    fn default() -> Self {
+        Self {
+            root: None,
+            parallel_styles: true,
+        }
+    }
+}
+
+impl InjectOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_root(mut self, root: Element) -> Self {
+        self.root = Some(root);
+        self
+    }
+
+    pub fn with_parallel_styles(mut self, parallel_styles: bool) -> Self {
+        self.parallel_styles = parallel_styles;
+        self
+    }
+}
+
+/// Ids of the resources injected by [`inject_group()`].
+static REGISTRY: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+fn registry<R>(f: impl FnOnce(&mut HashSet<String>) -> R) -> R {
+    f(REGISTRY.lock().unwrap().get_or_insert_with(HashSet::new))
+}
+
+/// Returns `true` if a resource with the given `id` has been
+/// injected by [`inject_group()`].
+pub fn is_injected(id: &str) -> bool {
+    registry(|registry| registry.contains(id))
+}
+
+/// Resolves the injection order of `nodes` (`(id, dependencies)` pairs)
+/// returning node indices in a topological order. Nodes without mutual
+/// dependencies retain their relative order. Dependencies on ids that
+/// are not part of `nodes` must satisfy `injected`.
+fn resolve_order(
+    nodes: &[(Option<&str>, &[&str])],
+    injected: impl Fn(&str) -> bool,
+) -> Result<Vec<usize>> {
+    let mut index = HashMap::new();
+    for (idx, (id, _)) in nodes.iter().enumerate() {
+        if let Some(id) = id {
+            if index.insert(*id, idx).is_some() {
+                return Err(Error::DuplicateId(id.to_string()));
+            }
+        }
+    }
+
+    let mut dependents = vec![Vec::new(); nodes.len()];
+    let mut pending = vec![0usize; nodes.len()];
+    for (idx, (id, depends_on)) in nodes.iter().enumerate() {
+        for dependency in depends_on.iter() {
+            if let Some(&dep) = index.get(dependency) {
+                dependents[dep].push(idx);
+                pending[idx] += 1;
+            } else if !injected(dependency) {
+                return Err(Error::MissingDependency {
+                    id: id.unwrap_or_default().to_string(),
+                    dependency: dependency.to_string(),
+                });
+            }
+        }
+    }
+
+    let mut order = Vec::with_capacity(nodes.len());
+    let mut done = vec![false; nodes.len()];
+    while let Some(idx) = (0..nodes.len()).find(|&idx| !done[idx] && pending[idx] == 0) {
+        done[idx] = true;
+        order.push(idx);
+        for &dependent in dependents[idx].iter() {
+            pending[dependent] -= 1;
+        }
+    }
+
+    if order.len() < nodes.len() {
+        let cycle = (0..nodes.len())
+            .filter(|&idx| !done[idx])
+            .map(|idx| nodes[idx].0.unwrap_or_default().to_string())
+            .collect();
+        return Err(Error::DependencyCycle(cycle));
+    }
+
+    Ok(order)
+}
+
+/// Describes the failure reported by an `error` event.
+fn event_message(event: &web_sys::Event) -> String {
+    match event.dyn_ref::<web_sys::ErrorEvent>() {
+        Some(error) => error.message(),
+        None => format!("`{}` event", event.type_()),
+    }
+}
+
+/// Resource pending its `load` (or `error`) event.
+pub(crate) struct Injection {
+    id: Option<String>,
+    element: Element,
+    receiver: Receiver<std::result::Result<(), String>>,
+    _callbacks: [Callback<CallbackClosureWithoutResult<web_sys::Event>>; 2],
+}
+
+impl Injection {
+    pub(crate) fn try_new(root: &Element, content: &Content) -> Result<Self> {
+        let element = match content {
+            Content::Script(id, data) => create_script_element(*id, data, "text/javascript")?,
+            Content::Module(id, data) => create_script_element(*id, data, "module")?,
+            Content::Style(id, data) => create_stylesheet_element(*id, data)?,
+        };
+
+        let (sender, receiver) = oneshot();
+        let load_sender = sender.clone();
+        let load = callback!(move |_event: web_sys::Event| {
+            load_sender.try_send(Ok(())).ok();
+        });
+        let error = callback!(move |event: web_sys::Event| {
+            sender.try_send(Err(event_message(&event))).ok();
+        });
+        element.add_event_listener_with_callback("load", load.as_ref())?;
+        element.add_event_listener_with_callback("error", error.as_ref())?;
+
+        let id = content.id().map(String::from);
+        if let Some(id) = &id {
+            registry(|registry| registry.insert(id.clone()));
+        }
+        if let Err(err) = root.append_child(&element) {
+            if let Some(id) = &id {
+                registry(|registry| registry.remove(id));
+            }
+            return Err(err.into());
+        }
+
+        Ok(Self {
+            id,
+            element,
+            receiver,
+            _callbacks: [load, error],
+        })
+    }
+
+    pub(crate) async fn wait(&self) -> Result<()> {
+        match self.receiver.recv().await? {
+            Ok(()) => Ok(()),
+            Err(message) => {
+                self.unregister();
+                Err(Error::Inject {
+                    id: self.id.clone().unwrap_or_default(),
+                    message,
+                })
+            }
+        }
+    }
+
+    /// Removes the injected element from DOM.
+    pub(crate) fn remove(&self) {
+        self.element.remove();
+        self.unregister();
+    }
+
+    fn unregister(&self) {
+        if let Some(id) = &self.id {
+            registry(|registry| registry.remove(id));
+        }
+    }
+}
+
+/// Inject a group of resources into DOM respecting their dependencies
+/// (see [`Content::depends_on()`]).
+///
+/// Resources are injected in a topological order of their dependencies.
+/// Scripts and modules are injected sequentially, each one awaiting the
+/// `load` event of the previous one; stylesheets are loaded in parallel
+/// (see [`InjectOptions::parallel_styles`]). Resources with an id that
+/// has already been injected (by this or a previous call) are skipped.
+///
+/// ```ignore
+/// inject_group(vec![
+///     Content::Script(Some("app"), APP_JS).depends_on(&["base"]),
+///     Content::Script(Some("base"), BASE_JS).into(),
+///     Content::Style(Some("theme"), THEME_CSS).into(),
+/// ], InjectOptions::default()).await?;
+/// ```
+pub async fn inject_group<'content, I>(items: Vec<I>, options: InjectOptions) -> Result<()>
+where
+    I: Into<InjectItem<'content>>,
+{
+    let items = items.into_iter().map(Into::into).collect::<Vec<_>>();
+    let nodes = items
+        .iter()
+        .map(|item| (item.content.id(), item.depends_on.as_slice()))
+        .collect::<Vec<_>>();
+    let order = resolve_order(&nodes, is_injected)?;
+    let root = options.root.clone().unwrap_or_else(injection_root);
+
+    let mut styles: Vec<Injection> = Vec::new();
+    for idx in order {
+        let item = &items[idx];
+        if item.content.id().is_some_and(is_injected) {
+            continue;
+        }
+
+        // wait for the stylesheets this resource depends on
+        let (required, remaining): (Vec<_>, Vec<_>) = styles.into_iter().partition(|injection| {
+            injection
+                .id
+                .as_deref()
+                .is_some_and(|id| item.depends_on.contains(&id))
+        });
+        styles = remaining;
+        for injection in required {
+            injection.wait().await?;
+        }
+
+        let injection = Injection::try_new(&root, &item.content)?;
+        match item.content {
+            Content::Style(..) if options.parallel_styles => styles.push(injection),
+            _ => injection.wait().await?,
+        }
+    }
+
+    for injection in styles {
+        injection.wait().await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(nodes: &[(Option<&str>, &[&str])]) -> Result<Vec<usize>> {
+        resolve_order(nodes, |id| id == "external")
+    }
+
+    #[test]
+    fn test_resolve_order() {
+        assert_eq!(
+            order(&[
+                (Some("app"), &["base", "theme"]),
+                (Some("base"), &[]),
+                (None, &["external"]),
+                (Some("theme"), &["base"]),
+            ])
+            .unwrap(),
+            vec![1, 2, 3, 0]
+        );
+
+        assert!(matches!(
+            order(&[(Some("a"), &["b"]), (Some("b"), &["c"]), (Some("c"), &["a"]), (Some("d"), &[])]),
+            Err(Error::DependencyCycle(ids)) if ids == ["a", "b", "c"]
+        ));
+        assert!(matches!(
+            order(&[(Some("a"), &["missing"])]),
+            Err(Error::MissingDependency { id, dependency }) if id == "a" && dependency == "missing"
+        ));
+        assert!(matches!(
+            order(&[(Some("a"), &[]), (Some("a"), &[])]),
+            Err(Error::DuplicateId(id)) if id == "a"
+        ));
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    async fn test_inject_group() {
+        let base = b"window.__inject_group_base = 40;";
+        let app = b"window.__inject_group_app = window.__inject_group_base + 2;";
+        inject_group(
+            vec![
+                Content::Script(Some("inject-group-app"), app).depends_on(&["inject-group-base"]),
+                Content::Script(Some("inject-group-base"), base).into(),
+            ],
+            InjectOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let value = js_sys::Reflect::get(&window(), &"__inject_group_app".into()).unwrap();
+        assert_eq!(value.as_f64(), Some(42.0));
+        assert!(is_injected("inject-group-base") && is_injected("inject-group-app"));
+
+        // already injected resources are skipped
+        inject_group(
+            vec![Content::Script(Some("inject-group-base"), base)],
+            InjectOptions::default(),
+        )
+        .await
+        .unwrap();
+        let scripts = document()
+            .query_selector_all("script#inject-group-base")
+            .unwrap();
+        assert_eq!(scripts.length(), 1);
+    }
+}
@@ -25,9 +25,15 @@
 
 pub mod clipboard;
 pub mod download;
+pub mod element;
 pub mod error;
+pub mod fullscreen;
 pub mod inject;
 pub mod link;
 pub mod loader;
+pub mod observer;
 pub mod result;
 pub mod utils;
+pub mod visibility;
+
+pub use observer::{observe, wait_for_element, DomMutation, ObserveOptions};
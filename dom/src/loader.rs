@@ -1,17 +1,26 @@
 use crate::error::Error;
+use crate::inject::{self, Injection};
 use crate::result::Result;
-use futures::future::{join_all, BoxFuture, FutureExt};
-use js_sys::{Array, Uint8Array};
+use crate::utils::window;
+use futures::future::{join_all, pending, BoxFuture, FutureExt};
+use futures::select;
+use js_sys::{Array, Reflect, Uint8Array};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{AbortController, ReadableStreamDefaultReader, RequestInit};
 use web_sys::{Blob, Document, Url};
-use workflow_core::channel::oneshot;
+use workflow_core::abortable::Abortable;
+use workflow_core::channel::{oneshot, Channel, Receiver};
 use workflow_core::lookup::*;
+use workflow_core::task::sleep;
 use workflow_core::time::*;
 use workflow_log::*;
 use workflow_wasm::callback::*;
+use workflow_wasm::jserror::JsErrorData;
 
 pub type Id = u64;
 pub type ContentMap = HashMap<Id, Arc<Content>>;
@@ -388,3 +397,367 @@ pub fn declare(content: ContentList) -> Arc<Context> {
     ctx.declare(content);
     ctx
 }
+
+/// Remote resource loaded by [`Loader`].
+#[derive(Debug, Clone)]
+pub struct Resource {
+    pub url: String,
+    pub content_type: ContentType,
+    pub id: Option<String>,
+    pub optional: bool,
+}
+
+impl Resource {
+    pub fn new(content_type: ContentType, url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            content_type,
+            id: None,
+            optional: false,
+        }
+    }
+
+    pub fn script(url: impl Into<String>) -> Self {
+        Self::new(ContentType::Script, url)
+    }
+
+    pub fn module(url: impl Into<String>) -> Self {
+        Self::new(ContentType::Module, url)
+    }
+
+    pub fn style(url: impl Into<String>) -> Self {
+        Self::new(ContentType::Style, url)
+    }
+
+    /// Sets the `id` attribute of the injected element.
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Marks the resource as optional: a failure to load it is
+    /// reported but does not fail the [`Loader::load()`] call.
+    pub fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+}
+
+/// Events posted by [`Loader`] (see [`Loader::events()`]).
+#[derive(Debug, Clone)]
+pub enum LoaderEvent {
+    Started {
+        url: String,
+    },
+    Progress {
+        url: String,
+        received: u64,
+        total: Option<u64>,
+    },
+    Loaded {
+        url: String,
+    },
+    Failed {
+        url: String,
+        error: Error,
+    },
+    Completed {
+        loaded: usize,
+        failed: usize,
+    },
+}
+
+/// Result of a successful [`Loader::load()`] call.
+#[derive(Debug, Clone, Default)]
+pub struct LoadReport {
+    pub loaded: Vec<String>,
+    /// Failed optional resources
+    pub failed: Vec<(String, Error)>,
+}
+
+pub type ProgressFn = Arc<dyn Fn(&str, u64, Option<u64>) + 'static>;
+
+/// Fetches remote resources and injects them into DOM in the supplied
+/// order, awaiting the `load` event of each resource before proceeding
+/// to the next one.
+///
+/// Each resource is subject to an optional timeout covering both the
+/// fetch and the injection. Aborting the load via [`Abortable`] aborts
+/// the in-flight fetch (using an `AbortController`) and removes the
+/// element that is currently being injected. A failure of an optional
+/// resource (see [`Resource::optional()`]) is reported in the
+/// [`LoadReport`]; a failure of a required resource stops the load
+/// and produces [`Error::Load`] containing all failures.
+///
+/// ```ignore
+/// let loader = Loader::new(vec![
+///     Resource::style("/css/theme.css").optional(),
+///     Resource::script("/js/app.js"),
+/// ])
+/// .with_timeout(Duration::from_secs(10));
+/// let events = loader.events();
+/// let report = loader.load().await?;
+/// ```
+pub struct Loader {
+    resources: Vec<Resource>,
+    timeout: Option<Duration>,
+    abortable: Abortable,
+    progress: Option<ProgressFn>,
+    events: Channel<LoaderEvent>,
+}
+
+impl Loader {
+    pub fn new(resources: Vec<Resource>) -> Self {
+        Self {
+            resources,
+            timeout: None,
+            abortable: Abortable::new(),
+            progress: None,
+            events: Channel::unbounded(),
+        }
+    }
+
+    /// Per-resource timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_abortable(mut self, abortable: &Abortable) -> Self {
+        self.abortable = abortable.clone();
+        self
+    }
+
+    /// Callback receiving `(url, received, total)` as resource data
+    /// arrives. The `total` is available if the server supplies the
+    /// `Content-Length` header.
+    pub fn with_progress<F>(mut self, progress: F) -> Self
+    where
+        F: Fn(&str, u64, Option<u64>) + 'static,
+    {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// Receiver of the [`LoaderEvent`] notifications.
+    pub fn events(&self) -> Receiver<LoaderEvent> {
+        self.events.receiver.clone()
+    }
+
+    /// Aborts the load.
+    pub fn abort(&self) {
+        self.abortable.abort();
+    }
+
+    fn post(&self, event: LoaderEvent) {
+        self.events.try_send(event).ok();
+    }
+
+    pub async fn load(&self) -> Result<LoadReport> {
+        let mut report = LoadReport::default();
+        let mut failures = Vec::new();
+        for resource in self.resources.iter() {
+            self.post(LoaderEvent::Started {
+                url: resource.url.clone(),
+            });
+            match self.load_resource(resource).await {
+                Ok(()) => {
+                    self.post(LoaderEvent::Loaded {
+                        url: resource.url.clone(),
+                    });
+                    report.loaded.push(resource.url.clone());
+                }
+                Err(error) => {
+                    self.post(LoaderEvent::Failed {
+                        url: resource.url.clone(),
+                        error: error.clone(),
+                    });
+                    let aborted = matches!(error, Error::Aborted);
+                    failures.push((resource.url.clone(), error));
+                    if aborted || !resource.optional {
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.post(LoaderEvent::Completed {
+            loaded: report.loaded.len(),
+            failed: failures.len(),
+        });
+
+        let required_failed = failures.iter().any(|(url, _)| {
+            self.resources
+                .iter()
+                .any(|resource| &resource.url == url && !resource.optional)
+        });
+        if required_failed || matches!(failures.last(), Some((_, Error::Aborted))) {
+            Err(Error::Load(failures))
+        } else {
+            report.failed = failures;
+            Ok(report)
+        }
+    }
+
+    /// Resolves when the load is aborted or the resource deadline expires.
+    async fn watch(&self, url: &str, start: Instant) -> Error {
+        let deadline = async {
+            match self.timeout {
+                Some(timeout) => sleep(timeout.saturating_sub(start.elapsed())).await,
+                None => pending::<()>().await,
+            }
+        };
+        select! {
+            _ = self.abortable.cancelled().fuse() => Error::Aborted,
+            _ = deadline.fuse() => Error::Timeout(url.to_string()),
+        }
+    }
+
+    async fn load_resource(&self, resource: &Resource) -> Result<()> {
+        let start = Instant::now();
+        let controller = AbortController::new()?;
+
+        let data = select! {
+            result = self.fetch(resource, &controller).fuse() => result?,
+            error = self.watch(&resource.url, start).fuse() => {
+                controller.abort();
+                return Err(error);
+            }
+        };
+
+        let id = resource.id.as_deref();
+        let content = match resource.content_type {
+            ContentType::Script => inject::Content::Script(id, &data),
+            ContentType::Module => inject::Content::Module(id, &data),
+            ContentType::Style => inject::Content::Style(id, &data),
+        };
+        let injection = Injection::try_new(&root(), &content)?;
+        select! {
+            result = injection.wait().fuse() => result,
+            error = self.watch(&resource.url, start).fuse() => {
+                injection.remove();
+                Err(error)
+            }
+        }
+    }
+
+    async fn fetch(&self, resource: &Resource, controller: &AbortController) -> Result<Vec<u8>> {
+        let url = resource.url.as_str();
+        let fetch_error = |err: JsValue| Error::Fetch {
+            url: url.to_string(),
+            message: JsErrorData::from(err).to_string(),
+        };
+
+        let init = RequestInit::new();
+        init.set_signal(Some(&controller.signal()));
+        let response = JsFuture::from(window().fetch_with_str_and_init(url, &init))
+            .await
+            .map_err(fetch_error)?;
+        let response: web_sys::Response = response.dyn_into()?;
+        if !response.ok() {
+            return Err(Error::Fetch {
+                url: url.to_string(),
+                message: format!("HTTP status {}", response.status()),
+            });
+        }
+
+        let total = response
+            .headers()
+            .get("content-length")
+            .ok()
+            .flatten()
+            .and_then(|length| length.parse::<u64>().ok());
+        let notify = |received: u64| {
+            if let Some(progress) = &self.progress {
+                progress(url, received, total);
+            }
+            self.post(LoaderEvent::Progress {
+                url: url.to_string(),
+                received,
+                total,
+            });
+        };
+
+        let mut data = Vec::new();
+        match response.body() {
+            Some(body) => {
+                let reader: ReadableStreamDefaultReader = body.get_reader().unchecked_into();
+                loop {
+                    let chunk = JsFuture::from(reader.read()).await.map_err(fetch_error)?;
+                    if Reflect::get(&chunk, &"done".into())?.is_truthy() {
+                        break;
+                    }
+                    let value: Uint8Array = Reflect::get(&chunk, &"value".into())?.unchecked_into();
+                    data.extend_from_slice(&value.to_vec());
+                    notify(data.len() as u64);
+                }
+            }
+            None => {
+                // streaming is not available
+                let buffer = JsFuture::from(response.array_buffer()?)
+                    .await
+                    .map_err(fetch_error)?;
+                data = Uint8Array::new(&buffer).to_vec();
+                notify(data.len() as u64);
+            }
+        }
+
+        Ok(data)
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    const SCRIPT: &str = "data:text/javascript,window.__loader_test%20%3D%2042%3B";
+
+    #[wasm_bindgen_test]
+    async fn test_loader_optional_failure() {
+        let loader = Loader::new(vec![
+            Resource::script("http://127.0.0.1:9/unreachable.js")
+                .optional()
+                .with_id("loader-unreachable"),
+            Resource::script(SCRIPT).with_id("loader-data"),
+        ])
+        .with_timeout(Duration::from_secs(5));
+        let events = loader.events();
+        let report = loader.load().await.unwrap();
+        assert_eq!(report.loaded, vec![SCRIPT.to_string()]);
+        assert_eq!(report.failed.len(), 1);
+
+        let value = Reflect::get(&window(), &"__loader_test".into()).unwrap();
+        assert_eq!(value.as_f64(), Some(42.0));
+
+        let events = std::iter::from_fn(|| events.try_recv().ok()).collect::<Vec<_>>();
+        assert!(matches!(events.first(), Some(LoaderEvent::Started { .. })));
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, LoaderEvent::Failed { .. })));
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, LoaderEvent::Progress { .. })));
+        assert!(matches!(
+            events.last(),
+            Some(LoaderEvent::Completed {
+                loaded: 1,
+                failed: 1
+            })
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_loader_required_failure_and_abort() {
+        let loader = Loader::new(vec![Resource::script("http://127.0.0.1:9/unreachable.js")]);
+        assert!(matches!(loader.load().await, Err(Error::Load(failures)) if failures.len() == 1));
+
+        let loader = Loader::new(vec![Resource::script(SCRIPT).with_id("loader-aborted")]);
+        loader.abort();
+        assert!(matches!(loader.load().await, Err(Error::Load(failures))
+            if matches!(failures.as_slice(), [(_, Error::Aborted)])));
+        assert!(document().get_element_by_id("loader-aborted").is_none());
+    }
+}
@@ -0,0 +1,397 @@
+//!
+//! [`MutationObserver`](https://developer.mozilla.org/en-US/docs/Web/API/MutationObserver)
+//! wrapper delivering typed [`DomMutation`] events and a [`wait_for_element()`]
+//! helper resolving once an element matching a selector is present.
+//!
+//! ```ignore
+//! let receiver = observe(&container, ObserveOptions::default().with_child_list(true).with_subtree(true))?;
+//! while let Ok(mutation) = receiver.recv().await {
+//!     if let DomMutation::ChildList { added, .. } = mutation {
+//!         added.iter().for_each(apply_style);
+//!     }
+//! }
+//!
+//! let widget = wait_for_element(".widget", Duration::from_secs(5)).await?;
+//! ```
+//!
+
+use crate::error::Error;
+use crate::result::Result;
+use crate::utils::document;
+use futures::{select, FutureExt};
+use js_sys::Array;
+use std::ops::Deref;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, MutationObserver, MutationObserverInit, MutationRecord, Node, NodeList};
+use workflow_core::channel::{Channel, Receiver};
+use workflow_core::task::sleep;
+use workflow_core::time::Duration;
+use workflow_wasm::callback::*;
+
+/// Selection of changes reported by [`observe()`]. At least one of
+/// `child_list`, `attributes` or `character_data` must be enabled.
+/// Old values of attributes and character data are always recorded.
+#[derive(Debug, Clone, Default)]
+pub struct ObserveOptions {
+    pub child_list: bool,
+    pub attributes: bool,
+    pub character_data: bool,
+    pub subtree: bool,
+    pub attribute_filter: Option<Vec<String>>,
+}
+
+impl ObserveOptions {
+    /// Report addition and removal of child nodes.
+    pub fn with_child_list(mut self, child_list: bool) -> Self {
+        self.child_list = child_list;
+        self
+    }
+
+    /// Report attribute changes.
+    pub fn with_attributes(mut self, attributes: bool) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Report changes of text node content.
+    pub fn with_character_data(mut self, character_data: bool) -> Self {
+        self.character_data = character_data;
+        self
+    }
+
+    /// Extend observation to all descendants of the target.
+    pub fn with_subtree(mut self, subtree: bool) -> Self {
+        self.subtree = subtree;
+        self
+    }
+
+    /// Report changes of the listed attributes only (enables `attributes`).
+    pub fn with_attribute_filter<I, S>(mut self, attributes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.attributes = true;
+        self.attribute_filter = Some(attributes.into_iter().map(Into::into).collect());
+        self
+    }
+
+    fn init(&self) -> MutationObserverInit {
+        let init = MutationObserverInit::new();
+        init.set_child_list(self.child_list);
+        init.set_attributes(self.attributes);
+        init.set_attribute_old_value(self.attributes);
+        init.set_character_data(self.character_data);
+        init.set_character_data_old_value(self.character_data);
+        init.set_subtree(self.subtree);
+        if let Some(filter) = &self.attribute_filter {
+            let filter = filter
+                .iter()
+                .map(|name| js_sys::JsString::from(name.as_str()))
+                .collect::<Array>();
+            init.set_attribute_filter(&filter);
+        }
+        init
+    }
+}
+
+/// DOM change delivered by [`observe()`].
+#[derive(Debug, Clone)]
+pub enum DomMutation {
+    /// Child nodes of `target` were added or removed
+    ChildList {
+        target: Node,
+        added: Vec<Node>,
+        removed: Vec<Node>,
+    },
+    /// Attribute `name` of `target` has changed. `value` is the value
+    /// at the time the change is delivered (`None` if removed).
+    Attribute {
+        target: Element,
+        name: String,
+        namespace: Option<String>,
+        old_value: Option<String>,
+        value: Option<String>,
+    },
+    /// Content of the text (or comment) node `target` has changed
+    CharacterData {
+        target: Node,
+        old_value: Option<String>,
+        value: Option<String>,
+    },
+}
+
+impl DomMutation {
+    fn try_from_record(record: &MutationRecord) -> Option<Self> {
+        let target = record.target()?;
+        match record.type_().as_str() {
+            "childList" => Some(DomMutation::ChildList {
+                target,
+                added: nodes(&record.added_nodes()),
+                removed: nodes(&record.removed_nodes()),
+            }),
+            "attributes" => {
+                let target = target.dyn_into::<Element>().ok()?;
+                let name = record.attribute_name()?;
+                let namespace = record.attribute_namespace();
+                let value = match namespace.as_deref() {
+                    Some(namespace) => target.get_attribute_ns(Some(namespace), &name),
+                    None => target.get_attribute(&name),
+                };
+                Some(DomMutation::Attribute {
+                    target,
+                    name,
+                    namespace,
+                    old_value: record.old_value(),
+                    value,
+                })
+            }
+            "characterData" => Some(DomMutation::CharacterData {
+                value: target.node_value(),
+                target,
+                old_value: record.old_value(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Node the mutation applies to
+    pub fn target(&self) -> &Node {
+        match self {
+            DomMutation::ChildList { target, .. } => target,
+            DomMutation::Attribute { target, .. } => target,
+            DomMutation::CharacterData { target, .. } => target,
+        }
+    }
+}
+
+fn nodes(list: &NodeList) -> Vec<Node> {
+    (0..list.length()).filter_map(|n| list.get(n)).collect()
+}
+
+/// Receiver of [`DomMutation`] events created by [`observe()`].
+/// Dereferences to [`Receiver<DomMutation>`]; the observer is
+/// disconnected and its closure released when this receiver is dropped.
+pub struct MutationReceiver {
+    receiver: Receiver<DomMutation>,
+    observer: MutationObserver,
+    #[allow(dead_code)]
+    callback: Callback<CallbackClosureWithoutResult<Array>>,
+}
+
+impl Deref for MutationReceiver {
+    type Target = Receiver<DomMutation>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.receiver
+    }
+}
+
+impl Drop for MutationReceiver {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
+
+/// Observes changes of the `target` node selected by `options`.
+pub fn observe(target: &Node, options: ObserveOptions) -> Result<MutationReceiver> {
+    let channel = Channel::unbounded();
+    let sender = channel.sender.clone();
+    let callback = callback!(move |records: Array| {
+        for record in records.iter() {
+            let record = record.unchecked_into::<MutationRecord>();
+            if let Some(mutation) = DomMutation::try_from_record(&record) {
+                sender.try_send(mutation).ok();
+            }
+        }
+    });
+    let observer = MutationObserver::new(callback.as_ref())?;
+    observer.observe_with_options(target, &options.init())?;
+
+    Ok(MutationReceiver {
+        receiver: channel.receiver,
+        observer,
+        callback,
+    })
+}
+
+/// Resolves with the first element matching `selector`, waiting for it
+/// to be added to the document (or for an attribute change making an
+/// existing element match). Fails with [`Error::Timeout`] if no such
+/// element appears within `timeout`.
+pub async fn wait_for_element(selector: &str, timeout: Duration) -> Result<Element> {
+    let document = document();
+    if let Some(element) = document.query_selector(selector)? {
+        return Ok(element);
+    }
+
+    let root = document
+        .document_element()
+        .ok_or_else(|| Error::String("Unable to get document element".to_string()))?;
+    let receiver = observe(
+        &root,
+        ObserveOptions::default()
+            .with_child_list(true)
+            .with_attributes(true)
+            .with_subtree(true),
+    )?;
+
+    let deadline = sleep(timeout).fuse();
+    futures::pin_mut!(deadline);
+    loop {
+        select! {
+            mutation = receiver.recv().fuse() => {
+                mutation?;
+                // drain the batch delivered by the same callback
+                while receiver.try_recv().is_ok() {}
+                if let Some(element) = document.query_selector(selector)? {
+                    return Ok(element);
+                }
+            },
+            _ = deadline => {
+                return Err(Error::Timeout(selector.to_string()));
+            }
+        }
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use crate::utils::body;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn sandbox() -> Element {
+        let sandbox = document().create_element("div").unwrap();
+        body().unwrap().append_child(&sandbox).unwrap();
+        sandbox
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_observe_mutations() {
+        let sandbox = sandbox();
+        let receiver = observe(
+            &sandbox,
+            ObserveOptions::default()
+                .with_child_list(true)
+                .with_attributes(true)
+                .with_character_data(true)
+                .with_subtree(true),
+        )
+        .unwrap();
+
+        let child = document().create_element("span").unwrap();
+        sandbox.append_child(&child).unwrap();
+        let DomMutation::ChildList {
+            target,
+            added,
+            removed,
+        } = receiver.recv().await.unwrap()
+        else {
+            panic!("expected child list mutation");
+        };
+        assert!(target.is_same_node(Some(&sandbox)));
+        assert_eq!(added.len(), 1);
+        assert!(added[0].is_same_node(Some(&child)));
+        assert!(removed.is_empty());
+
+        child.set_attribute("data-state", "a").unwrap();
+        child.set_attribute("data-state", "b").unwrap();
+        for old in [None, Some("a")] {
+            let DomMutation::Attribute {
+                target,
+                name,
+                old_value,
+                value,
+                ..
+            } = receiver.recv().await.unwrap()
+            else {
+                panic!("expected attribute mutation");
+            };
+            assert!(target.is_same_node(Some(&child)));
+            assert_eq!(name, "data-state");
+            assert_eq!(old_value.as_deref(), old);
+            // the current value is reported for both records of the batch
+            assert_eq!(value.as_deref(), Some("b"));
+        }
+
+        let text = document().create_text_node("before");
+        child.append_child(&text).unwrap();
+        assert!(matches!(
+            receiver.recv().await.unwrap(),
+            DomMutation::ChildList { .. }
+        ));
+        text.set_data("after");
+        let DomMutation::CharacterData {
+            old_value, value, ..
+        } = receiver.recv().await.unwrap()
+        else {
+            panic!("expected character data mutation");
+        };
+        assert_eq!(old_value.as_deref(), Some("before"));
+        assert_eq!(value.as_deref(), Some("after"));
+
+        sandbox.remove_child(&child).unwrap();
+        let DomMutation::ChildList { removed, .. } = receiver.recv().await.unwrap() else {
+            panic!("expected child list mutation");
+        };
+        assert!(removed[0].is_same_node(Some(&child)));
+
+        let inner = receiver.deref().clone();
+        drop(receiver);
+        // the observer is disconnected and its closure (owning the sender) released
+        assert!(inner.is_closed());
+        sandbox
+            .append_child(&document().create_element("span").unwrap())
+            .unwrap();
+        sleep(Duration::from_millis(10)).await;
+        assert!(inner.try_recv().is_err());
+        sandbox.remove();
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_attribute_filter() {
+        let sandbox = sandbox();
+        let receiver = observe(
+            &sandbox,
+            ObserveOptions::default().with_attribute_filter(["class"]),
+        )
+        .unwrap();
+        sandbox.set_attribute("title", "ignored").unwrap();
+        sandbox.set_attribute("class", "styled").unwrap();
+        let DomMutation::Attribute { name, .. } = receiver.recv().await.unwrap() else {
+            panic!("expected attribute mutation");
+        };
+        assert_eq!(name, "class");
+        assert!(receiver.is_empty());
+        sandbox.remove();
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_wait_for_element() {
+        let sandbox = sandbox();
+        let sandbox_ = sandbox.clone();
+        let insert = async move {
+            sleep(Duration::from_millis(20)).await;
+            let widget = document().create_element("div").unwrap();
+            widget.set_attribute("class", "observer-widget").unwrap();
+            sandbox_.append_child(&widget).unwrap();
+        };
+        let (element, _) = futures::join!(
+            wait_for_element(".observer-widget", Duration::from_secs(5)),
+            insert
+        );
+        assert_eq!(element.unwrap().class_name(), "observer-widget");
+
+        // present elements resolve immediately
+        assert!(wait_for_element(".observer-widget", Duration::ZERO)
+            .await
+            .is_ok());
+
+        let result = wait_for_element(".observer-missing", Duration::from_millis(50)).await;
+        assert!(matches!(result, Err(Error::Timeout(_))));
+        sandbox.remove();
+    }
+}
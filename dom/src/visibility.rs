@@ -0,0 +1,106 @@
+//!
+//! Helpers for the [Page Visibility API](https://developer.mozilla.org/en-US/docs/Web/API/Page_Visibility_API).
+//!
+//! ```ignore
+//! let receiver = visibility::on_change()?;
+//! while let Ok(visible) = receiver.recv().await {
+//!     if !visible { pause(); }
+//! }
+//! ```
+//!
+
+use crate::error::Error;
+use crate::result::Result;
+use crate::utils::document;
+use js_sys::Reflect;
+use std::ops::Deref;
+use web_sys::Document;
+use workflow_core::channel::{Channel, Receiver};
+use workflow_wasm::callback::*;
+
+/// Returns `true` if the Page Visibility API is available.
+pub fn is_supported() -> bool {
+    Reflect::has(&document(), &"hidden".into()).unwrap_or(false)
+}
+
+/// Returns `true` if the page is currently visible.
+pub fn is_visible() -> bool {
+    !document().hidden()
+}
+
+/// Receiver of page visibility changes (`true` when the page becomes
+/// visible) created by [`on_change()`]. Dereferences to
+/// [`Receiver<bool>`]; the underlying `visibilitychange` listener is
+/// removed when this receiver is dropped.
+pub struct VisibilityReceiver {
+    receiver: Receiver<bool>,
+    document: Document,
+    callback: Callback<CallbackClosureWithoutResult<web_sys::Event>>,
+}
+
+impl Deref for VisibilityReceiver {
+    type Target = Receiver<bool>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.receiver
+    }
+}
+
+impl Drop for VisibilityReceiver {
+    fn drop(&mut self) {
+        self.document
+            .remove_event_listener_with_callback("visibilitychange", self.callback.as_ref())
+            .ok();
+    }
+}
+
+/// Subscribes to page visibility changes.
+pub fn on_change() -> Result<VisibilityReceiver> {
+    if !is_supported() {
+        return Err(Error::Unsupported("Page Visibility API".to_string()));
+    }
+
+    let document = document();
+    let channel = Channel::unbounded();
+    let sender = channel.sender.clone();
+    let callback = callback!(move |_event: web_sys::Event| {
+        sender.try_send(is_visible()).ok();
+    });
+    document.add_event_listener_with_callback("visibilitychange", callback.as_ref())?;
+
+    Ok(VisibilityReceiver {
+        receiver: channel.receiver,
+        document,
+        callback,
+    })
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn dispatch() {
+        document()
+            .dispatch_event(&web_sys::Event::new("visibilitychange").unwrap())
+            .unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_visibility_listener_lifecycle() {
+        let receiver = on_change().unwrap();
+        dispatch();
+        assert_eq!(receiver.recv().await.unwrap(), is_visible());
+        assert!(receiver.is_empty());
+
+        let inner = receiver.deref().clone();
+        assert!(!inner.is_closed());
+        drop(receiver);
+        // the closure owning the sender has been released
+        assert!(inner.is_closed());
+        dispatch();
+        assert!(inner.try_recv().is_err());
+    }
+}
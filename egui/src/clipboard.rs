@@ -0,0 +1,102 @@
+//!
+//! System clipboard access complementing egui's text output
+//! (`egui::Context::copy_text()`): reading the clipboard on demand
+//! and copying images. Uses the system clipboard on native platforms
+//! and the asynchronous Clipboard API in the browser (reading requires
+//! the user's permission and is not available in insecure contexts).
+//!
+
+use crate::imports::*;
+
+cfg_if! {
+    if #[cfg(not(target_arch = "wasm32"))] {
+
+        use std::borrow::Cow;
+
+        // the clipboard is retained so that its contents remain
+        // available after writing on platforms where the clipboard
+        // contents are served by the owning process (X11)
+        static CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+        fn with_clipboard<R>(f: impl FnOnce(&mut arboard::Clipboard) -> std::result::Result<R, arboard::Error>) -> Result<R> {
+            let mut clipboard = CLIPBOARD.lock().unwrap();
+            if clipboard.is_none() {
+                clipboard.replace(arboard::Clipboard::new().map_err(|err| Error::Clipboard(err.to_string()))?);
+            }
+            f(clipboard.as_mut().unwrap()).map_err(|err| Error::Clipboard(err.to_string()))
+        }
+
+        /// Reads text from the clipboard; resolves to `None`
+        /// if the clipboard does not contain text.
+        pub async fn read_text() -> Result<Option<String>> {
+            with_clipboard(|clipboard| match clipboard.get_text() {
+                Ok(text) => Ok(Some(text)),
+                Err(arboard::Error::ContentNotAvailable) => Ok(None),
+                Err(err) => Err(err),
+            })
+        }
+
+        /// Writes text to the clipboard.
+        pub async fn write_text(text: &str) -> Result<()> {
+            with_clipboard(|clipboard| clipboard.set_text(text))
+        }
+
+        /// Writes an RGBA image (`width * height * 4` bytes) to the clipboard.
+        pub async fn write_image(width: usize, height: usize, rgba: Vec<u8>) -> Result<()> {
+            with_clipboard(|clipboard| {
+                clipboard.set_image(arboard::ImageData {
+                    width,
+                    height,
+                    bytes: Cow::Owned(rgba),
+                })
+            })
+        }
+
+    } else {
+
+        use js_sys::{Function, Promise, Reflect};
+        use wasm_bindgen::{JsCast, JsValue};
+        use wasm_bindgen_futures::JsFuture;
+        use workflow_dom::utils::window;
+
+        fn js_error(err: JsValue) -> Error {
+            Error::Clipboard(format!("{err:?}"))
+        }
+
+        /// Invokes `method` of `navigator.clipboard`
+        async fn call(method: &str, args: &[JsValue]) -> Result<JsValue> {
+            let clipboard = Reflect::get(&window().navigator(), &"clipboard".into()).map_err(js_error)?;
+            if clipboard.is_undefined() {
+                return Err(Error::Unsupported("clipboard access"));
+            }
+            let function: Function = Reflect::get(&clipboard, &method.into())
+                .map_err(js_error)?
+                .dyn_into()
+                .map_err(js_error)?;
+            let args = args.iter().collect::<js_sys::Array>();
+            let promise: Promise = function
+                .apply(&clipboard, &args)
+                .map_err(js_error)?
+                .dyn_into()
+                .map_err(js_error)?;
+            JsFuture::from(promise).await.map_err(js_error)
+        }
+
+        /// Reads text from the clipboard; resolves to `None`
+        /// if the clipboard does not contain text.
+        pub async fn read_text() -> Result<Option<String>> {
+            Ok(call("readText", &[]).await?.as_string().filter(|text| !text.is_empty()))
+        }
+
+        /// Writes text to the clipboard.
+        pub async fn write_text(text: &str) -> Result<()> {
+            call("writeText", &[text.into()]).await?;
+            Ok(())
+        }
+
+        /// Copying images is not available in the browser.
+        pub async fn write_image(_width: usize, _height: usize, _rgba: Vec<u8>) -> Result<()> {
+            Err(Error::Unsupported("copying images to the clipboard"))
+        }
+    }
+}
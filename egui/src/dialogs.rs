@@ -0,0 +1,324 @@
+//!
+//! File dialogs working uniformly on native platforms (system dialogs)
+//! and in the browser (`<input type="file">` and download anchors).
+//!
+//! Dialog functions are async and resolve to `None` when the user
+//! dismisses the dialog. Within the UI, dialogs are typically started
+//! using [`Dialog`], which delivers the result back to the UI thread
+//! and requests an egui repaint once the dialog completes.
+//!
+//! Example module with "Open" and "Save As" buttons:
+//!
+//! ```ignore
+//! #[derive(Default)]
+//! pub struct Editor {
+//!     text: String,
+//!     open: Option<Dialog<Result<Option<FileHandle>>>>,
+//!     save: Option<Dialog<Result<Option<String>>>>,
+//! }
+//!
+//! impl ModuleT for Editor {
+//!     type Context = MyApp;
+//!
+//!     fn render(&mut self, _app: &mut MyApp, ctx: &egui::Context, _frame: &mut eframe::Frame, ui: &mut egui::Ui) {
+//!         ui.horizontal(|ui| {
+//!             if ui.add_enabled(self.open.is_none(), egui::Button::new("Open")).clicked() {
+//!                 let filters = [FileFilter::new("Text", &["txt", "md"])];
+//!                 self.open = Some(Dialog::open_file(ctx, &filters));
+//!             }
+//!             if ui.add_enabled(self.save.is_none(), egui::Button::new("Save As")).clicked() {
+//!                 self.save = Some(Dialog::save_file(ctx, "notes.txt", self.text.as_bytes().to_vec()));
+//!             }
+//!         });
+//!
+//!         if let Some(result) = self.open.as_ref().and_then(Dialog::take) {
+//!             self.open = None;
+//!             match result {
+//!                 Ok(Some(file)) => self.text = String::from_utf8_lossy(&file.bytes).to_string(),
+//!                 Ok(None) => {} // dismissed by the user
+//!                 Err(err) => log_error!("unable to open file: {err}"),
+//!             }
+//!         }
+//!         if let Some(result) = self.save.as_ref().and_then(Dialog::take) {
+//!             self.save = None;
+//!             if let Err(err) = result {
+//!                 log_error!("unable to save file: {err}");
+//!             }
+//!         }
+//!
+//!         ui.text_edit_multiline(&mut self.text);
+//!     }
+//! }
+//! ```
+//!
+
+use crate::imports::*;
+use std::path::PathBuf;
+
+/// File type filter of the file-open dialog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileFilter {
+    pub name: String,
+    /// Extensions without the leading dot
+    pub extensions: Vec<String>,
+}
+
+impl FileFilter {
+    pub fn new(name: &str, extensions: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+        }
+    }
+}
+
+/// Value of the `accept` attribute of `<input type="file">` matching `filters`
+pub fn accept(filters: &[FileFilter]) -> String {
+    filters
+        .iter()
+        .flat_map(|filter| filter.extensions.iter())
+        .map(|ext| format!(".{}", ext.trim_start_matches('.')))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// File selected by the user
+#[derive(Debug, Clone)]
+pub struct FileHandle {
+    /// File name (without the directory)
+    pub name: String,
+    /// File path (not available in the browser)
+    pub path: Option<PathBuf>,
+    pub bytes: Vec<u8>,
+}
+
+cfg_if! {
+    if #[cfg(not(target_arch = "wasm32"))] {
+
+        /// Prompts the user to select a file and reads its contents.
+        pub async fn open_file(filters: &[FileFilter]) -> Result<Option<FileHandle>> {
+            let mut dialog = rfd::AsyncFileDialog::new();
+            for filter in filters {
+                dialog = dialog.add_filter(&filter.name, &filter.extensions);
+            }
+            let Some(handle) = dialog.pick_file().await else {
+                return Ok(None);
+            };
+            Ok(Some(FileHandle {
+                name: handle.file_name(),
+                path: Some(handle.path().to_path_buf()),
+                bytes: handle.read().await,
+            }))
+        }
+
+        /// Prompts the user for the destination of `bytes`, suggesting
+        /// `suggested_name`. Resolves to the path of the saved file.
+        pub async fn save_file(suggested_name: &str, bytes: Vec<u8>) -> Result<Option<String>> {
+            let Some(handle) = rfd::AsyncFileDialog::new()
+                .set_file_name(suggested_name)
+                .save_file()
+                .await
+            else {
+                return Ok(None);
+            };
+            let path = handle.path().display().to_string();
+            handle
+                .write(&bytes)
+                .await
+                .map_err(|err| Error::custom(format!("unable to write `{path}`: {err}")))?;
+            Ok(Some(path))
+        }
+
+        /// Prompts the user to select a directory.
+        pub async fn pick_directory() -> Result<Option<PathBuf>> {
+            Ok(rfd::AsyncFileDialog::new()
+                .pick_folder()
+                .await
+                .map(|handle| handle.path().to_path_buf()))
+        }
+
+        fn spawn_dialog<T, F>(ctx: &egui::Context, future: F) -> Dialog<T>
+        where
+            T: Send + 'static,
+            F: Future<Output = T> + Send + 'static,
+        {
+            let (sender, receiver) = oneshot();
+            let ctx = ctx.clone();
+            task::spawn(async move {
+                sender.try_send(future.await).ok();
+                ctx.request_repaint();
+            });
+            Dialog { receiver }
+        }
+
+    } else {
+
+        use js_sys::{Array, Uint8Array};
+        use wasm_bindgen::{JsCast, JsValue};
+        use wasm_bindgen_futures::JsFuture;
+        use web_sys::{Blob, HtmlAnchorElement, HtmlInputElement, Url};
+        use workflow_dom::utils::{document, window};
+        use workflow_wasm::callback::callback;
+
+        /// Delay after the window regains focus before a file dialog
+        /// without a selection is considered dismissed (browsers may
+        /// dispatch `focus` before `change`)
+        const CANCEL_DELAY: Duration = Duration::from_millis(500);
+        /// Delay before releasing the object URL of a downloaded file
+        const REVOKE_DELAY: Duration = Duration::from_secs(5);
+
+        fn js_error(err: JsValue) -> Error {
+            Error::custom(format!("{err:?}"))
+        }
+
+        /// Prompts the user to select a file and reads its contents.
+        pub async fn open_file(filters: &[FileFilter]) -> Result<Option<FileHandle>> {
+            let input: HtmlInputElement = document()
+                .create_element("input")
+                .map_err(js_error)?
+                .dyn_into()
+                .map_err(js_error)?;
+            input.set_type("file");
+            let accept = accept(filters);
+            if !accept.is_empty() {
+                input.set_accept(&accept);
+            }
+
+            // resolves with `true` on selection; `cancel` is not dispatched by
+            // older browsers, in which case the dialog is considered dismissed
+            // when the window regains focus without a selection
+            let (sender, receiver) = oneshot::<bool>();
+            let change_sender = sender.clone();
+            let change = callback!(move |_event: web_sys::Event| {
+                change_sender.try_send(true).ok();
+            });
+            let cancel_sender = sender.clone();
+            let cancel = callback!(move |_event: web_sys::Event| {
+                cancel_sender.try_send(false).ok();
+            });
+            let focus = callback!(move |_event: web_sys::Event| {
+                let sender = sender.clone();
+                task::dispatch(async move {
+                    task::sleep(CANCEL_DELAY).await;
+                    sender.try_send(false).ok();
+                });
+            });
+            input.add_event_listener_with_callback("change", change.as_ref()).map_err(js_error)?;
+            input.add_event_listener_with_callback("cancel", cancel.as_ref()).map_err(js_error)?;
+            window().add_event_listener_with_callback("focus", focus.as_ref()).map_err(js_error)?;
+
+            input.click();
+            let selected = receiver.recv().await.unwrap_or(false);
+            window().remove_event_listener_with_callback("focus", focus.as_ref()).ok();
+
+            let file = match input.files().and_then(|files| files.get(0)) {
+                Some(file) if selected => file,
+                _ => return Ok(None),
+            };
+            let buffer = JsFuture::from(file.array_buffer()).await.map_err(js_error)?;
+            Ok(Some(FileHandle {
+                name: file.name(),
+                path: None,
+                bytes: Uint8Array::new(&buffer).to_vec(),
+            }))
+        }
+
+        /// Downloads `bytes` as `suggested_name`; the destination is
+        /// chosen by the browser. Resolves to the suggested name.
+        pub async fn save_file(suggested_name: &str, bytes: Vec<u8>) -> Result<Option<String>> {
+            let parts = Array::of1(&Uint8Array::from(bytes.as_slice()));
+            let blob = Blob::new_with_u8_array_sequence(&parts).map_err(js_error)?;
+            let url = Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+            let anchor: HtmlAnchorElement = document()
+                .create_element("a")
+                .map_err(js_error)?
+                .dyn_into()
+                .map_err(js_error)?;
+            anchor.set_href(&url);
+            anchor.set_download(suggested_name);
+            anchor.click();
+
+            // the download starts asynchronously
+            task::sleep(REVOKE_DELAY).await;
+            Url::revoke_object_url(&url).ok();
+            Ok(Some(suggested_name.to_string()))
+        }
+
+        /// Directory selection is not available in the browser.
+        pub async fn pick_directory() -> Result<Option<PathBuf>> {
+            Err(Error::Unsupported("directory selection"))
+        }
+
+        fn spawn_dialog<T, F>(ctx: &egui::Context, future: F) -> Dialog<T>
+        where
+            T: 'static,
+            F: Future<Output = T> + 'static,
+        {
+            let (sender, receiver) = oneshot();
+            let ctx = ctx.clone();
+            task::dispatch(async move {
+                sender.try_send(future.await).ok();
+                ctx.request_repaint();
+            });
+            Dialog { receiver }
+        }
+    }
+}
+
+/// Dialog running in the background; the result is retrieved by
+/// the UI using [`Dialog::take()`]. An egui repaint is requested
+/// when the dialog completes.
+pub struct Dialog<T> {
+    receiver: Receiver<T>,
+}
+
+impl Dialog<Result<Option<FileHandle>>> {
+    /// Starts [`open_file()`]
+    pub fn open_file(ctx: &egui::Context, filters: &[FileFilter]) -> Self {
+        let filters = filters.to_vec();
+        spawn_dialog(ctx, async move { open_file(&filters).await })
+    }
+}
+
+impl Dialog<Result<Option<String>>> {
+    /// Starts [`save_file()`]
+    pub fn save_file(ctx: &egui::Context, suggested_name: &str, bytes: Vec<u8>) -> Self {
+        let suggested_name = suggested_name.to_string();
+        spawn_dialog(ctx, async move { save_file(&suggested_name, bytes).await })
+    }
+}
+
+impl Dialog<Result<Option<PathBuf>>> {
+    /// Starts [`pick_directory()`]
+    pub fn pick_directory(ctx: &egui::Context) -> Self {
+        spawn_dialog(ctx, pick_directory())
+    }
+}
+
+impl<T> Dialog<T> {
+    /// Returns the result once the dialog has completed
+    /// (the result is returned only once).
+    pub fn take(&self) -> Option<T> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Returns `true` while the dialog is open
+    pub fn is_pending(&self) -> bool {
+        self.receiver.is_empty() && !self.receiver.is_closed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept() {
+        let filters = [
+            FileFilter::new("Images", &["png", "jpg"]),
+            FileFilter::new("Text", &[".txt"]),
+        ];
+        assert_eq!(accept(&filters), ".png,.jpg,.txt");
+        assert_eq!(accept(&[]), "");
+    }
+}
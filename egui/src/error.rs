@@ -22,6 +22,21 @@ pub enum Error {
 
     #[error("Channel recv() error")]
     RecvError,
+
+    #[error(transparent)]
+    Http(#[from] workflow_http::error::Error),
+
+    #[error(transparent)]
+    I18n(#[from] workflow_i18n::error::Error),
+
+    #[error("image decode error: {0}")]
+    Image(String),
+
+    #[error("{0} is not supported on this platform")]
+    Unsupported(&'static str),
+
+    #[error("clipboard error: {0}")]
+    Clipboard(String),
 }
 
 impl Error {
@@ -42,6 +57,12 @@ impl From<String> for Error {
     }
 }
 
+impl From<image::ImageError> for Error {
+    fn from(err: image::ImageError) -> Self {
+        Error::Image(err.to_string())
+    }
+}
+
 impl From<eframe::Error> for Error {
     fn from(err: eframe::Error) -> Self {
         Error::Eframe(err.to_string())
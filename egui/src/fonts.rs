@@ -1,3 +1,5 @@
+use crate::imports::*;
+use crate::runtime::try_runtime;
 use egui::{FontData, FontDefinitions, FontFamily};
 
 pub trait RegisterStaticFont {
@@ -15,3 +17,293 @@ impl RegisterStaticFont for FontDefinitions {
             .push(name.to_owned());
     }
 }
+
+/// Placement of a font installed via [`Fonts::install()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FontSlot {
+    /// Inserted as the first (preferred) font of the family.
+    Primary(FontFamily),
+    /// Appended as the last (fallback) font of the family.
+    Fallback(FontFamily),
+    /// Registered only as `FontFamily::Name(<font name>)`; use
+    /// [`Fonts::register_language_fallback()`] to make it part of
+    /// the proportional and monospace fallback chains.
+    Named,
+}
+
+/// Events produced by [`Fonts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FontEvent {
+    Installed {
+        name: String,
+    },
+    /// The activated language has no installed fallback font.
+    MissingLanguageFont {
+        language: String,
+        font: Option<String>,
+    },
+}
+
+#[derive(Default)]
+struct FontState {
+    base: FontDefinitions,
+    installed: Vec<(String, FontData, FontSlot)>,
+    languages: HashMap<String, String>,
+    language: Option<String>,
+}
+
+/// Runtime font management. Fonts can be installed after startup;
+/// each change rebuilds [`FontDefinitions`] and applies them via
+/// [`egui::Context::set_fonts()`]. Language fallback chains append
+/// a font registered for the active language (e.g. a CJK font for
+/// `ja`) to the proportional and monospace families.
+///
+/// ```ignore
+/// let fonts = Fonts::new(ctx);
+/// fonts.install_from_url("NotoSansJP", "https://example.com/NotoSansJP.otf", FontSlot::Named).await?;
+/// fonts.register_language_fallback("ja", "NotoSansJP");
+/// fonts::activate_language("ja")?;
+/// ```
+#[derive(Clone)]
+pub struct Fonts {
+    ctx: egui::Context,
+    state: Arc<Mutex<FontState>>,
+    events: Channel<FontEvent>,
+}
+
+impl Fonts {
+    pub fn new(ctx: &egui::Context) -> Self {
+        Self {
+            ctx: ctx.clone(),
+            state: Default::default(),
+            events: Channel::unbounded(),
+        }
+    }
+
+    /// Replaces the definitions the installed fonts are applied to
+    /// (defaults to [`FontDefinitions::default()`]).
+    pub fn with_base(self, base: FontDefinitions) -> Self {
+        self.state.lock().unwrap().base = base;
+        self
+    }
+
+    pub fn events(&self) -> Receiver<FontEvent> {
+        self.events.receiver.clone()
+    }
+
+    /// Installs (or replaces) the font `name` and applies the fonts.
+    pub fn install(&self, name: &str, bytes: Vec<u8>, slot: FontSlot) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state
+                .installed
+                .retain(|(installed, _, _)| installed != name);
+            state
+                .installed
+                .push((name.to_string(), FontData::from_owned(bytes), slot));
+        }
+        self.apply();
+        self.post(FontEvent::Installed {
+            name: name.to_string(),
+        });
+    }
+
+    /// Fetches the font from `url` and installs it.
+    pub async fn install_from_url(&self, name: &str, url: &str, slot: FontSlot) -> Result<()> {
+        let bytes = workflow_http::get_bytes(url).await?;
+        self.install(name, bytes, slot);
+        Ok(())
+    }
+
+    /// Associates the installed font `name` with the language `code`.
+    pub fn register_language_fallback(&self, code: &str, name: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .languages
+            .insert(code.to_string(), name.to_string());
+        self.apply();
+    }
+
+    /// Applies the fallback chain of the language `code`. Posts
+    /// [`FontEvent::MissingLanguageFont`] if the language has a
+    /// registered font that is not installed (or no font at all for
+    /// languages requiring one).
+    pub fn set_language(&self, code: &str) {
+        let missing = {
+            let mut state = self.state.lock().unwrap();
+            state.language = Some(code.to_string());
+            match state.languages.get(code) {
+                Some(font) if state.installed.iter().all(|(name, _, _)| name != font) => {
+                    Some(FontEvent::MissingLanguageFont {
+                        language: code.to_string(),
+                        font: Some(font.clone()),
+                    })
+                }
+                None if requires_font(code) => Some(FontEvent::MissingLanguageFont {
+                    language: code.to_string(),
+                    font: None,
+                }),
+                _ => None,
+            }
+        };
+
+        self.apply();
+        if let Some(event) = missing {
+            log_warn!("fonts: no font available for language `{code}`");
+            self.post(event);
+        }
+    }
+
+    /// Font definitions resulting from the installed fonts
+    /// and the active language.
+    pub fn definitions(&self) -> FontDefinitions {
+        let state = self.state.lock().unwrap();
+        let mut definitions = state.base.clone();
+
+        for (name, data, slot) in state.installed.iter() {
+            definitions.font_data.insert(name.clone(), data.clone());
+            definitions
+                .families
+                .entry(FontFamily::Name(name.as_str().into()))
+                .or_default()
+                .push(name.clone());
+            match slot {
+                FontSlot::Primary(family) => {
+                    definitions
+                        .families
+                        .entry(family.clone())
+                        .or_default()
+                        .insert(0, name.clone());
+                }
+                FontSlot::Fallback(family) => {
+                    definitions
+                        .families
+                        .entry(family.clone())
+                        .or_default()
+                        .push(name.clone());
+                }
+                FontSlot::Named => {}
+            }
+        }
+
+        let fallback = state
+            .language
+            .as_ref()
+            .and_then(|code| state.languages.get(code))
+            .filter(|font| definitions.font_data.contains_key(*font));
+        if let Some(font) = fallback {
+            for family in [FontFamily::Proportional, FontFamily::Monospace] {
+                let fonts = definitions.families.entry(family).or_default();
+                if !fonts.contains(font) {
+                    fonts.push(font.clone());
+                }
+            }
+        }
+
+        definitions
+    }
+
+    fn apply(&self) {
+        self.ctx.set_fonts(self.definitions());
+    }
+
+    fn post(&self, event: FontEvent) {
+        self.events.try_send(event.clone()).ok();
+        if let Some(runtime) = try_runtime() {
+            runtime.try_send(event).ok();
+        }
+    }
+}
+
+/// Languages whose scripts are not covered by the default egui fonts.
+fn requires_font(code: &str) -> bool {
+    let language = code.split(['-', '_']).next().unwrap_or(code);
+    matches!(
+        language,
+        "ja" | "zh" | "ko" | "th" | "hi" | "bn" | "ta" | "te" | "my" | "km" | "ka" | "am"
+    )
+}
+
+static FONTS: OnceLock<Fonts> = OnceLock::new();
+
+/// Returns the global [`Fonts`] instance bound to the runtime egui context.
+pub fn fonts() -> &'static Fonts {
+    FONTS.get_or_init(|| Fonts::new(runtime().egui_ctx()))
+}
+
+/// Installs a font using the global [`Fonts`] instance.
+pub fn install(name: &str, bytes: Vec<u8>, slot: FontSlot) {
+    fonts().install(name, bytes, slot)
+}
+
+/// Fetches and installs a font using the global [`Fonts`] instance.
+pub async fn install_from_url(name: &str, url: &str, slot: FontSlot) -> Result<()> {
+    fonts().install_from_url(name, url, slot).await
+}
+
+/// Activates the workflow-i18n language `code` and applies
+/// its font fallback chain.
+pub fn activate_language(code: &str) -> Result<()> {
+    workflow_i18n::i18n::dictionary().activate_language_code(code)?;
+    let code = workflow_i18n::i18n::dictionary().current_code();
+    fonts().set_language(code.as_str());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font_bytes(name: &str) -> Vec<u8> {
+        FontDefinitions::default().font_data[name].font.to_vec()
+    }
+
+    #[test]
+    fn test_fonts_install() {
+        let ctx = egui::Context::default();
+        let fonts = Fonts::new(&ctx);
+        let events = fonts.events();
+
+        fonts.install(
+            "Primary",
+            font_bytes("Hack"),
+            FontSlot::Primary(FontFamily::Proportional),
+        );
+        fonts.install("CJK", font_bytes("Ubuntu-Light"), FontSlot::Named);
+        let definitions = fonts.definitions();
+        let proportional = &definitions.families[&FontFamily::Proportional];
+        assert_eq!(proportional.first().map(String::as_str), Some("Primary"));
+        assert!(!proportional.contains(&"CJK".to_string()));
+        assert_eq!(
+            definitions.families[&FontFamily::Name("CJK".into())],
+            vec!["CJK"]
+        );
+
+        fonts.set_language("ja");
+        assert!(matches!(
+            std::iter::from_fn(|| events.try_recv().ok()).last(),
+            Some(FontEvent::MissingLanguageFont { font: None, .. })
+        ));
+
+        fonts.register_language_fallback("ja", "CJK");
+        fonts.set_language("ja");
+        let definitions = fonts.definitions();
+        for family in [FontFamily::Proportional, FontFamily::Monospace] {
+            assert_eq!(
+                definitions.families[&family].last().map(String::as_str),
+                Some("CJK")
+            );
+        }
+
+        fonts.set_language("en");
+        let proportional = fonts.definitions().families[&FontFamily::Proportional].clone();
+        assert!(!proportional.contains(&"CJK".to_string()));
+
+        // fonts are loaded at the beginning of the frame
+        let _ = ctx.run(egui::RawInput::default(), |_| {});
+        ctx.fonts(|fonts| {
+            assert!(fonts.families().contains(&FontFamily::Name("CJK".into())));
+        });
+    }
+}
@@ -11,7 +11,6 @@ where
     _settings_storage_requested: bool,
     _last_settings_storage_request: Instant,
 
-    #[allow(dead_code)]
     runtime: Runtime,
     events: ApplicationEventsChannel,
 }
@@ -62,6 +61,8 @@ where
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         log_info!("--- update ---");
 
+        self.runtime.bus().dispatch();
+
         for event in self.events.iter() {
             if let Err(err) = self.handle_events(event.clone(), ctx, frame) {
                 log_error!("error processing wallet runtime event: {}", err);
@@ -0,0 +1,385 @@
+//!
+//! Persistence of the application layout (window geometry, open/closed
+//! flags, selected tabs) across application restarts.
+//!
+//! Modules opt in by implementing [`PersistentState`] and returning it
+//! from [`ModuleT::persistent_state()`](crate::module::ModuleT::persistent_state).
+//! [`LayoutPersistence`] collects the state of all opted-in modules into
+//! a versioned [`Layout`] stored via [`workflow_store::fs`].
+//!
+//! ```ignore
+//! impl PersistentState for Settings {
+//!     fn save(&self) -> Value {
+//!         serde_json::to_value(&self.window).unwrap_or_default()
+//!     }
+//!
+//!     fn load(&mut self, state: Value) {
+//!         if let Ok(window) = serde_json::from_value(state) {
+//!             self.window = window;
+//!         }
+//!     }
+//! }
+//!
+//! // before the first frame
+//! let mut layout = LayoutPersistence::new("~/.myapp/layout.json");
+//! layout.restore(&modules);
+//! // on each frame
+//! layout.update(&modules);
+//! ```
+//!
+
+use crate::frame::app::App;
+use crate::imports::*;
+use crate::module::ModuleManager;
+use serde::{Deserialize, Serialize};
+pub use serde_json::Value;
+use std::path::PathBuf;
+use workflow_store::fs;
+
+/// Default delay between a save request and the actual save.
+pub const DEFAULT_SAVE_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Layout state of a module.
+pub trait PersistentState {
+    fn save(&self) -> Value;
+    fn load(&mut self, state: Value);
+}
+
+/// Window geometry and state commonly persisted by modules.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowState {
+    /// Window rectangle as `[min.x, min.y, max.x, max.y]`.
+    pub rect: Option<[f32; 4]>,
+    pub open: bool,
+    /// Selected tab.
+    pub tab: Option<String>,
+}
+
+impl WindowState {
+    pub fn new(rect: Option<Rect>, open: bool) -> Self {
+        Self {
+            rect: rect.map(|rect| [rect.min.x, rect.min.y, rect.max.x, rect.max.y]),
+            open,
+            tab: None,
+        }
+    }
+
+    pub fn with_tab(mut self, tab: impl Into<String>) -> Self {
+        self.tab = Some(tab.into());
+        self
+    }
+
+    pub fn rect(&self) -> Option<Rect> {
+        self.rect.map(|[min_x, min_y, max_x, max_y]| {
+            Rect::from_min_max(egui::pos2(min_x, min_y), egui::pos2(max_x, max_y))
+        })
+    }
+}
+
+/// Serialized layout: module states keyed by module name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Layout {
+    pub version: u32,
+    pub modules: BTreeMap<String, Value>,
+}
+
+impl Layout {
+    pub fn new(version: u32) -> Self {
+        Self {
+            version,
+            modules: BTreeMap::new(),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|err| Error::custom(err.to_string()))
+    }
+
+    /// Parses a stored layout. Returns `None` if the data is corrupted
+    /// or was produced by a different schema `version`.
+    pub fn from_json(json: &str, version: u32) -> Option<Self> {
+        let layout = match serde_json::from_str::<Layout>(json) {
+            Ok(layout) => layout,
+            Err(err) => {
+                log_warn!("layout: discarding corrupted layout state: {err}");
+                return None;
+            }
+        };
+
+        if layout.version != version {
+            log_warn!(
+                "layout: discarding layout state version {} (expected {version})",
+                layout.version
+            );
+            return None;
+        }
+
+        Some(layout)
+    }
+}
+
+/// Snapshots and restores the state of modules implementing
+/// [`PersistentState`]. Saves are debounced: [`LayoutPersistence::request_save()`]
+/// marks the layout as modified and [`LayoutPersistence::update()`]
+/// (invoked on each frame) stores it once the debounce delay elapses.
+pub struct LayoutPersistence {
+    path: PathBuf,
+    version: u32,
+    debounce: Duration,
+    requested: Option<Instant>,
+    stored: Option<Layout>,
+}
+
+impl LayoutPersistence {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            version: 1,
+            debounce: DEFAULT_SAVE_DEBOUNCE,
+            requested: None,
+            stored: None,
+        }
+    }
+
+    /// Schema version of the layout; stored layouts with
+    /// a different version are discarded.
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Collects the state of all modules implementing [`PersistentState`].
+    pub fn snapshot<T: App>(&self, manager: &ModuleManager<T>) -> Layout {
+        let mut layout = Layout::new(self.version);
+        for module in manager.modules() {
+            let mut module_ref = module.inner.module.borrow_mut();
+            if let Some(state) = module_ref.persistent_state() {
+                layout
+                    .modules
+                    .insert(module.inner.name.clone(), state.save());
+            }
+        }
+        layout
+    }
+
+    /// Applies `layout` to the registered modules.
+    pub fn apply<T: App>(&self, manager: &ModuleManager<T>, layout: &Layout) {
+        for module in manager.modules() {
+            if let Some(value) = layout.modules.get(&module.inner.name) {
+                let mut module_ref = module.inner.module.borrow_mut();
+                if let Some(state) = module_ref.persistent_state() {
+                    state.load(value.clone());
+                }
+            }
+        }
+    }
+
+    /// Reads the stored layout. Missing, corrupted or incompatible
+    /// layouts result in `None`.
+    pub fn load(&self) -> Option<Layout> {
+        if !fs::exists_sync(&self.path).unwrap_or(false) {
+            return None;
+        }
+
+        match fs::read_to_string_sync(&self.path) {
+            Ok(json) => Layout::from_json(&json, self.version),
+            Err(err) => {
+                log_warn!("layout: unable to read `{}`: {err}", self.path.display());
+                None
+            }
+        }
+    }
+
+    /// Restores the stored layout; should be called before the first
+    /// frame. Returns `true` if a layout has been applied.
+    pub fn restore<T: App>(&mut self, manager: &ModuleManager<T>) -> bool {
+        match self.load() {
+            Some(layout) => {
+                self.apply(manager, &layout);
+                self.stored = Some(layout);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Marks the layout as modified; each request restarts the debounce delay.
+    pub fn request_save(&mut self) {
+        self.requested = Some(Instant::now());
+    }
+
+    /// Saves the layout if a save has been requested and the
+    /// debounce delay has elapsed.
+    pub fn update<T: App>(&mut self, manager: &ModuleManager<T>) {
+        if let Some(requested) = self.requested {
+            if requested.elapsed() >= self.debounce {
+                self.requested = None;
+                if let Some((path, json)) = self.prepare(manager) {
+                    task::spawn(async move {
+                        if let Err(err) = fs::write_string(&path, &json).await {
+                            log_error!("layout: unable to store `{}`: {err}", path.display());
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    /// Saves the layout immediately (e.g. on application exit).
+    pub fn save<T: App>(&mut self, manager: &ModuleManager<T>) -> Result<()> {
+        self.requested = None;
+        if let Some((path, json)) = self.prepare(manager) {
+            fs::write_string_sync(&path, &json).map_err(|err| Error::custom(err.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Produces the serialized layout if it differs from the stored one.
+    fn prepare<T: App>(&mut self, manager: &ModuleManager<T>) -> Option<(PathBuf, String)> {
+        let layout = self.snapshot(manager);
+        if self.stored.as_ref() == Some(&layout) {
+            return None;
+        }
+
+        match layout.to_json() {
+            Ok(json) => {
+                self.stored = Some(layout);
+                Some((self.path.clone(), json))
+            }
+            Err(err) => {
+                log_error!("layout: unable to serialize layout: {err}");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module::{HashMapModuleExtension, ModuleT};
+
+    struct TestApp;
+
+    impl App for TestApp {
+        fn render(&mut self, _ctx: &egui::Context, _frame: &mut eframe::Frame) {}
+    }
+
+    #[derive(Default)]
+    struct Settings {
+        window: WindowState,
+    }
+
+    impl PersistentState for Settings {
+        fn save(&self) -> Value {
+            serde_json::to_value(&self.window).unwrap()
+        }
+
+        fn load(&mut self, state: Value) {
+            if let Ok(window) = serde_json::from_value(state) {
+                self.window = window;
+            }
+        }
+    }
+
+    impl ModuleT for Settings {
+        type Context = TestApp;
+
+        fn render(
+            &mut self,
+            _app: &mut TestApp,
+            _ctx: &egui::Context,
+            _frame: &mut eframe::Frame,
+            _ui: &mut egui::Ui,
+        ) {
+        }
+
+        fn persistent_state(&mut self) -> Option<&mut dyn PersistentState> {
+            Some(self)
+        }
+    }
+
+    #[derive(Default)]
+    struct Overview;
+
+    impl ModuleT for Overview {
+        type Context = TestApp;
+
+        fn render(
+            &mut self,
+            _app: &mut TestApp,
+            _ctx: &egui::Context,
+            _frame: &mut eframe::Frame,
+            _ui: &mut egui::Ui,
+        ) {
+        }
+    }
+
+    fn manager() -> ModuleManager<TestApp> {
+        let mut modules = AHashMap::new();
+        modules.insert_typeid(Settings::default());
+        modules.insert_typeid(Overview);
+        ModuleManager::new(TypeId::of::<Overview>(), modules)
+    }
+
+    #[test]
+    fn test_layout_round_trip() {
+        let window = WindowState::new(
+            Some(Rect::from_min_size(
+                egui::pos2(10.0, 20.0),
+                Vec2::new(300.0, 200.0),
+            )),
+            true,
+        )
+        .with_tab("network");
+        assert_eq!(window.rect().unwrap().width(), 300.0);
+
+        let source = manager();
+        source.get::<Settings>().as_mut().window = window.clone();
+        let persistence = LayoutPersistence::new("layout.json").with_version(3);
+        let layout = persistence.snapshot(&source);
+        assert_eq!(layout.modules.len(), 1);
+
+        let json = layout.to_json().unwrap();
+        let restored = Layout::from_json(&json, 3).unwrap();
+        assert_eq!(restored, layout);
+
+        let target = manager();
+        persistence.apply(&target, &restored);
+        assert_eq!(target.get::<Settings>().as_ref().window, window);
+    }
+
+    #[test]
+    fn test_layout_corrupted_fallback() {
+        assert!(Layout::from_json("{\"version\":1,\"modules\":", 1).is_none());
+        assert!(Layout::from_json("[1,2,3]", 1).is_none());
+        let json = Layout::new(1).to_json().unwrap();
+        assert!(Layout::from_json(&json, 2).is_none());
+
+        // a module ignores state it is unable to interpret
+        let mut layout = Layout::new(1);
+        layout
+            .modules
+            .insert("Settings".to_string(), Value::String("garbage".to_string()));
+        let manager = manager();
+        LayoutPersistence::new("layout.json").apply(&manager, &layout);
+        assert_eq!(
+            manager.get::<Settings>().as_ref().window,
+            WindowState::default()
+        );
+
+        let path =
+            std::env::temp_dir().join(format!("workflow-egui-layout-{}.json", std::process::id()));
+        std::fs::write(&path, "not json").unwrap();
+        let mut persistence = LayoutPersistence::new(&path);
+        assert!(!persistence.restore(&manager));
+        persistence.save(&manager).unwrap();
+        assert!(persistence.load().is_some());
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -1,12 +1,16 @@
+pub mod clipboard;
 pub mod device;
+pub mod dialogs;
 pub mod error;
 pub mod fonts;
 pub mod frame;
 pub mod imports;
+pub mod layout;
 pub mod module;
 pub mod prelude;
 pub mod result;
 pub mod runtime;
+pub mod texture;
 
 pub use ahash;
 pub use eframe;
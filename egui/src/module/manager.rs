@@ -86,6 +86,16 @@ where
         }
     }
 
+    /// Returns all registered modules.
+    pub fn modules(&self) -> Vec<Module<T>> {
+        self.inner().modules.values().cloned().collect()
+    }
+
+    /// Invokes the shutdown hooks of all registered modules.
+    pub fn shutdown(&self) {
+        self.modules().iter().for_each(|module| module.shutdown());
+    }
+
     pub fn has_stack(&self) -> bool {
         !self.inner().stack.is_empty()
     }
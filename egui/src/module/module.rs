@@ -49,6 +49,20 @@ pub trait ModuleT: Downcast {
     );
 
     fn shutdown(&mut self) {}
+
+    /// Layout state persisted by [`LayoutPersistence`](crate::layout::LayoutPersistence).
+    fn persistent_state(&mut self) -> Option<&mut dyn crate::layout::PersistentState> {
+        None
+    }
+
+    /// Lifecycle hook invoked when the module becomes the active module.
+    fn on_activate(&mut self) {}
+
+    /// Lifecycle hook invoked when the module is replaced by another module.
+    fn on_deactivate(&mut self) {}
+
+    /// Lifecycle hook invoked by [`ModuleManager::shutdown()`](crate::module::ModuleManager::shutdown).
+    fn on_shutdown(&mut self) {}
 }
 
 impl_downcast!(ModuleT assoc Context);
@@ -85,11 +99,21 @@ where
     }
 
     pub fn activate(&self, core: &mut T) {
-        self.inner.module.borrow_mut().activate(core)
+        let mut module = self.inner.module.borrow_mut();
+        module.on_activate();
+        module.activate(core)
     }
 
     pub fn deactivate(&self, core: &mut T) {
-        self.inner.module.borrow_mut().deactivate(core)
+        let mut module = self.inner.module.borrow_mut();
+        module.on_deactivate();
+        module.deactivate(core)
+    }
+
+    pub fn shutdown(&self) {
+        let mut module = self.inner.module.borrow_mut();
+        module.on_shutdown();
+        module.shutdown()
     }
 
     pub fn render(
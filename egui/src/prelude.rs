@@ -1,10 +1,16 @@
 pub use crate::device::Device;
+pub use crate::dialogs::{Dialog, FileFilter, FileHandle};
 pub use crate::runtime::events::{ApplicationEvent, ApplicationEventsChannel, RuntimeEvent};
-pub use crate::runtime::{Payload, Runtime, Service, ServiceResult};
+pub use crate::runtime::{
+    spawn_ui_task, AsyncBinding, MessageBus, Payload, Runtime, Service, ServiceResult,
+    Subscription, UiTask,
+};
 pub use workflow_egui_macros::register_modules;
 
-pub use crate::fonts::*;
+pub use crate::fonts::{FontEvent, FontSlot, Fonts, RegisterStaticFont};
 pub use crate::frame::app::App;
 pub use crate::frame::options::Options;
+pub use crate::layout::{Layout, LayoutPersistence, PersistentState, WindowState};
 pub use crate::module::*;
+pub use crate::texture::{TextureLoader, TextureSource, TextureState, TextureTicket};
 pub use web_sys::VisibilityState;
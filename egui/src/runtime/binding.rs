@@ -0,0 +1,233 @@
+//!
+//! [`AsyncBinding`] connecting values produced by background tasks
+//! to the UI, triggering an egui repaint when a new value arrives.
+//!
+//! Example module streaming a counter from a background task:
+//!
+//! ```ignore
+//! pub struct Counter {
+//!     counter: UiTask<u64>,
+//! }
+//!
+//! impl Counter {
+//!     pub fn new(runtime: &Runtime) -> Self {
+//!         let counter = spawn_ui_task(runtime.egui_ctx(), |sender, stop| async move {
+//!             let mut count = 0;
+//!             let mut interval = task::interval(Duration::from_secs(1));
+//!             loop {
+//!                 select! {
+//!                     _ = interval.next().fuse() => {
+//!                         count += 1;
+//!                         sender.send(count).await?;
+//!                     },
+//!                     _ = stop.recv().fuse() => break,
+//!                 }
+//!             }
+//!             Ok(())
+//!         });
+//!         Self { counter }
+//!     }
+//! }
+//!
+//! impl ModuleT for Counter {
+//!     type Context = MyApp;
+//!
+//!     fn render(&mut self, _app: &mut MyApp, _ctx: &egui::Context, _frame: &mut eframe::Frame, ui: &mut egui::Ui) {
+//!         let text = self.counter.map(|count| format!("Count: {count}"));
+//!         ui.label(text.unwrap_or_else(|| "Waiting...".to_string()));
+//!     }
+//! }
+//! ```
+//!
+
+use crate::imports::*;
+use crate::runtime::try_runtime;
+use std::ops::Deref;
+
+/// Holds the latest value received from a channel. Values are received
+/// by a forwarding task that requests an egui repaint for each new value.
+/// The forwarding task terminates when the channel is closed, when
+/// [`AsyncBinding::close()`] is called or when the last clone of the
+/// binding is dropped.
+pub struct AsyncBinding<T> {
+    value: Arc<Mutex<Option<T>>>,
+    version: Arc<AtomicU64>,
+    shutdown: Arc<Sender<()>>,
+}
+
+impl<T> Clone for AsyncBinding<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            version: self.version.clone(),
+            shutdown: self.shutdown.clone(),
+        }
+    }
+}
+
+impl<T> AsyncBinding<T>
+where
+    T: Send + 'static,
+{
+    pub fn new(ctx: &egui::Context, receiver: Receiver<T>) -> Self {
+        let value = Arc::new(Mutex::new(None));
+        let version = Arc::new(AtomicU64::new(0));
+        let (shutdown, shutdown_receiver) = oneshot::<()>();
+
+        let ctx = ctx.clone();
+        let value_ = value.clone();
+        let version_ = version.clone();
+        task::spawn(async move {
+            loop {
+                select! {
+                    msg = receiver.recv().fuse() => {
+                        let Ok(msg) = msg else {
+                            break;
+                        };
+                        value_.lock().unwrap().replace(msg);
+                        version_.fetch_add(1, Ordering::SeqCst);
+                        ctx.request_repaint();
+                    },
+                    _ = shutdown_receiver.recv().fuse() => {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Self {
+            value,
+            version,
+            shutdown: Arc::new(shutdown),
+        }
+    }
+
+    /// Creates a binding holding `value` until the first value is received.
+    pub fn with_initial(ctx: &egui::Context, receiver: Receiver<T>, value: T) -> Self {
+        let binding = Self::new(ctx, receiver);
+        binding.value.lock().unwrap().get_or_insert(value);
+        binding
+    }
+
+    /// Returns a clone of the latest value.
+    pub fn get(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.value.lock().unwrap().clone()
+    }
+
+    /// Maps the latest value using `f`.
+    pub fn map<R, F>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.value.lock().unwrap().as_ref().map(f)
+    }
+
+    /// Number of values received so far; can be used to detect changes
+    /// between frames.
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::SeqCst)
+    }
+
+    /// Stops the forwarding task. The latest value remains available.
+    pub fn close(&self) {
+        self.shutdown.try_send(()).ok();
+    }
+}
+
+/// Background task paired with an [`AsyncBinding`] receiving its output,
+/// created by [`spawn_ui_task()`]. The task is signaled to stop and the
+/// binding is closed when the `UiTask` is dropped (typically together
+/// with the module owning it).
+pub struct UiTask<T> {
+    binding: AsyncBinding<T>,
+    stop: Sender<()>,
+}
+
+impl<T> Deref for UiTask<T> {
+    type Target = AsyncBinding<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.binding
+    }
+}
+
+impl<T> UiTask<T> {
+    pub fn binding(&self) -> &AsyncBinding<T> {
+        &self.binding
+    }
+
+    /// Signals the task to stop.
+    pub fn stop(&self) {
+        self.stop.try_send(()).ok();
+    }
+}
+
+impl<T> Drop for UiTask<T> {
+    fn drop(&mut self) {
+        self.stop.try_send(()).ok();
+        self.binding.shutdown.try_send(()).ok();
+    }
+}
+
+/// Spawns `task_fn` supplying it with a [`Sender`] for values displayed
+/// by the UI and a stop signal [`Receiver`]. Task errors are posted
+/// to the runtime as [`RuntimeEvent::Error`] if the runtime is available.
+pub fn spawn_ui_task<T, F, Fut>(ctx: &egui::Context, task_fn: F) -> UiTask<T>
+where
+    T: Send + 'static,
+    F: FnOnce(Sender<T>, Receiver<()>) -> Fut,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    let channel = Channel::unbounded();
+    let (stop, stop_receiver) = oneshot();
+    let binding = AsyncBinding::new(ctx, channel.receiver);
+    let future = task_fn(channel.sender, stop_receiver);
+    task::spawn(async move {
+        if let Err(err) = future.await {
+            match try_runtime() {
+                Some(runtime) => {
+                    runtime
+                        .try_send_runtime_event(RuntimeEvent::Error(err.to_string()))
+                        .ok();
+                }
+                None => {
+                    log_error!("ui task error: {err}");
+                }
+            }
+        }
+    });
+
+    UiTask { binding, stop }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_async_binding() {
+        let ctx = egui::Context::default();
+        let counter = spawn_ui_task(&ctx, |sender, stop| async move {
+            for n in 1..=3u64 {
+                sender.send(n).await?;
+            }
+            stop.recv().await?;
+            Ok(())
+        });
+
+        while counter.version() < 3 {
+            task::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(counter.get(), Some(3));
+        assert_eq!(counter.map(|n| n * 2), Some(6));
+        assert!(ctx.has_requested_repaint());
+
+        let binding = counter.binding().clone();
+        drop(counter);
+        assert_eq!(binding.get(), Some(3));
+    }
+}
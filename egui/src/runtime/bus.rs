@@ -0,0 +1,348 @@
+//!
+//! Typed message bus allowing modules and background tasks to
+//! communicate without globals.
+//!
+//! Messages are posted from any thread via [`MessageBus::post()`] (or
+//! the [`post()`] function using the global [`Runtime`]) and are
+//! delivered to subscribers on the UI thread at the beginning of the
+//! next frame. Each subscription buffers up to the bus capacity; when
+//! a subscriber does not drain its messages (e.g. while its module is
+//! inactive) the oldest messages are discarded.
+//!
+//! ```ignore
+//! struct Pong;
+//!
+//! pub struct Status {
+//!     pongs: Subscription<Pong>,
+//! }
+//!
+//! impl ModuleT for Status {
+//!     fn render(..) {
+//!         for _pong in self.pongs.iter() {
+//!             // ...
+//!         }
+//!     }
+//! }
+//!
+//! // from a background task
+//! bus::post(Pong);
+//! ```
+//!
+
+use crate::imports::*;
+use workflow_core::channel::bounded;
+
+pub const DEFAULT_BUS_CAPACITY: usize = 256;
+
+type Envelope = (TypeId, Box<dyn Any + Send>);
+
+trait Deliver: Send + Sync {
+    /// Delivers the message, returning `false` if the
+    /// subscription has been dropped.
+    fn deliver(&self, msg: &(dyn Any + Send)) -> bool;
+}
+
+struct Subscriber<M> {
+    sender: Sender<M>,
+    receiver: Receiver<M>,
+}
+
+impl<M> Deliver for Subscriber<M>
+where
+    M: Clone + Send + 'static,
+{
+    fn deliver(&self, msg: &(dyn Any + Send)) -> bool {
+        // the subscriber retains a receiver clone to discard the
+        // oldest messages; a count of 1 means the subscription is gone
+        if self.sender.receiver_count() <= 1 {
+            return false;
+        }
+
+        if let Some(msg) = msg.downcast_ref::<M>() {
+            if self.sender.is_full() {
+                self.receiver.try_recv().ok();
+            }
+            self.sender.try_send(msg.clone()).ok();
+        }
+        true
+    }
+}
+
+/// Receiver of messages of type `M` created by [`MessageBus::subscribe()`].
+/// Dropping the subscription unsubscribes from the bus.
+pub struct Subscription<M> {
+    receiver: Receiver<M>,
+}
+
+impl<M> Subscription<M> {
+    pub fn try_recv(&self) -> Option<M> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Iterates over all pending messages.
+    pub fn iter(&self) -> impl Iterator<Item = M> + '_ {
+        std::iter::from_fn(|| self.try_recv())
+    }
+
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+
+    pub fn receiver(&self) -> &Receiver<M> {
+        &self.receiver
+    }
+}
+
+struct Inner {
+    capacity: usize,
+    pending: Mutex<Vec<Envelope>>,
+    subscribers: Mutex<AHashMap<TypeId, Vec<Box<dyn Deliver>>>>,
+    egui_ctx: Option<egui::Context>,
+}
+
+#[derive(Clone)]
+pub struct MessageBus {
+    inner: Arc<Inner>,
+}
+
+impl Default for MessageBus {
+    fn default() -> Self {
+        Self::new(None, DEFAULT_BUS_CAPACITY)
+    }
+}
+
+impl MessageBus {
+    /// Creates a new bus; if `egui_ctx` is supplied, posting a
+    /// message requests a repaint so that it is delivered promptly.
+    pub fn new(egui_ctx: Option<&egui::Context>, capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                capacity: capacity.max(1),
+                pending: Mutex::new(Vec::new()),
+                subscribers: Mutex::new(AHashMap::new()),
+                egui_ctx: egui_ctx.cloned(),
+            }),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+
+    pub fn subscribe<M>(&self) -> Subscription<M>
+    where
+        M: Clone + Send + 'static,
+    {
+        let (sender, receiver) = bounded(self.inner.capacity);
+        self.inner
+            .subscribers
+            .lock()
+            .unwrap()
+            .entry(TypeId::of::<M>())
+            .or_default()
+            .push(Box::new(Subscriber {
+                sender,
+                receiver: receiver.clone(),
+            }));
+        Subscription { receiver }
+    }
+
+    /// Queues a message for delivery before the next frame.
+    pub fn post<M>(&self, msg: M)
+    where
+        M: Clone + Send + 'static,
+    {
+        self.inner
+            .pending
+            .lock()
+            .unwrap()
+            .push((TypeId::of::<M>(), Box::new(msg)));
+        if let Some(ctx) = &self.inner.egui_ctx {
+            ctx.request_repaint();
+        }
+    }
+
+    /// Delivers pending messages to subscribers. Invoked on the UI
+    /// thread at the beginning of each frame.
+    pub fn dispatch(&self) {
+        let pending = std::mem::take(&mut *self.inner.pending.lock().unwrap());
+        if pending.is_empty() {
+            return;
+        }
+
+        let mut subscribers = self.inner.subscribers.lock().unwrap();
+        for (type_id, msg) in pending {
+            if let Some(list) = subscribers.get_mut(&type_id) {
+                list.retain(|subscriber| subscriber.deliver(msg.as_ref()));
+            }
+        }
+        subscribers.retain(|_, list| !list.is_empty());
+    }
+}
+
+/// Subscribes to messages of type `M` on the global [`Runtime`] bus.
+pub fn subscribe<M>() -> Subscription<M>
+where
+    M: Clone + Send + 'static,
+{
+    runtime().bus().subscribe::<M>()
+}
+
+/// Posts a message on the global [`Runtime`] bus.
+pub fn post<M>(msg: M)
+where
+    M: Clone + Send + 'static,
+{
+    runtime().bus().post(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::app::App;
+    use crate::module::{HashMapModuleExtension, ModuleManager, ModuleT};
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Ping(u32);
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Pong(u32);
+
+    struct TestApp;
+
+    impl App for TestApp {
+        fn render(&mut self, _ctx: &egui::Context, _frame: &mut eframe::Frame) {}
+    }
+
+    struct Pinger {
+        bus: MessageBus,
+        pongs: Subscription<Pong>,
+        received: Vec<u32>,
+        shutdown: bool,
+    }
+
+    impl ModuleT for Pinger {
+        type Context = TestApp;
+
+        fn main(&mut self, _app: &mut TestApp) {
+            for Pong(n) in self.pongs.iter() {
+                self.received.push(n);
+                if n < 3 {
+                    self.bus.post(Ping(n + 1));
+                }
+            }
+        }
+
+        fn render(
+            &mut self,
+            _app: &mut TestApp,
+            _ctx: &egui::Context,
+            _frame: &mut eframe::Frame,
+            _ui: &mut egui::Ui,
+        ) {
+        }
+
+        fn on_shutdown(&mut self) {
+            self.shutdown = true;
+        }
+    }
+
+    struct Ponger {
+        bus: MessageBus,
+        pings: Subscription<Ping>,
+        active: bool,
+    }
+
+    impl ModuleT for Ponger {
+        type Context = TestApp;
+
+        fn main(&mut self, _app: &mut TestApp) {
+            if self.active {
+                for Ping(n) in self.pings.iter() {
+                    self.bus.post(Pong(n));
+                }
+            }
+        }
+
+        fn render(
+            &mut self,
+            _app: &mut TestApp,
+            _ctx: &egui::Context,
+            _frame: &mut eframe::Frame,
+            _ui: &mut egui::Ui,
+        ) {
+        }
+
+        fn on_activate(&mut self) {
+            self.active = true;
+        }
+    }
+
+    fn manager(bus: &MessageBus) -> ModuleManager<TestApp> {
+        let mut modules = AHashMap::new();
+        modules.insert_typeid(Pinger {
+            bus: bus.clone(),
+            pongs: bus.subscribe(),
+            received: vec![],
+            shutdown: false,
+        });
+        modules.insert_typeid(Ponger {
+            bus: bus.clone(),
+            pings: bus.subscribe(),
+            active: false,
+        });
+        ModuleManager::new(TypeId::of::<Pinger>(), modules)
+    }
+
+    fn frame(bus: &MessageBus, manager: &ModuleManager<TestApp>, app: &mut TestApp) {
+        bus.dispatch();
+        manager.get::<Pinger>().as_mut().main(app);
+        manager.get::<Ponger>().as_mut().main(app);
+    }
+
+    #[test]
+    fn test_bus_ping_pong() {
+        let bus = MessageBus::new(None, 4);
+        let mut manager = manager(&bus);
+        let mut app = TestApp;
+
+        bus.post(Ping(1));
+        for _ in 0..3 {
+            frame(&bus, &manager, &mut app);
+        }
+        // pings are queued while the ponger is inactive
+        assert_eq!(manager.get::<Ponger>().as_ref().pings.len(), 1);
+        assert!(manager.get::<Pinger>().as_ref().received.is_empty());
+
+        manager.select::<Ponger>(&mut app);
+        assert!(manager.get::<Ponger>().as_ref().active);
+        for _ in 0..8 {
+            frame(&bus, &manager, &mut app);
+        }
+        assert_eq!(manager.get::<Pinger>().as_ref().received, vec![1, 2, 3]);
+
+        manager.shutdown();
+        assert!(manager.get::<Pinger>().as_ref().shutdown);
+    }
+
+    #[test]
+    fn test_bus_bound_and_unsubscribe() {
+        let bus = MessageBus::new(None, 2);
+        let pings = bus.subscribe::<Ping>();
+        let dropped = bus.subscribe::<Ping>();
+        drop(dropped);
+        for n in 0..5 {
+            bus.post(Ping(n));
+        }
+        bus.dispatch();
+        assert_eq!(pings.iter().collect::<Vec<_>>(), vec![Ping(3), Ping(4)]);
+        assert_eq!(
+            bus.inner.subscribers.lock().unwrap()[&TypeId::of::<Ping>()].len(),
+            1
+        );
+    }
+}
@@ -2,11 +2,17 @@ cfg_if::cfg_if! {
     if #[cfg(not(target_arch = "wasm32"))] {
         pub mod signals;
         pub mod panic;
+        #[cfg(feature = "service")]
+        pub mod services;
+        #[cfg(feature = "service")]
+        pub use services::{attach_services, ServiceBridge};
     } else {
         // ...
     }
 }
 
+pub mod binding;
+pub mod bus;
 pub mod channel;
 pub mod events;
 pub mod payload;
@@ -15,4 +21,6 @@ pub mod service;
 
 #[allow(clippy::module_inception)]
 mod runtime;
+pub use binding::{spawn_ui_task, AsyncBinding, UiTask};
+pub use bus::{MessageBus, Subscription};
 pub use runtime::*;
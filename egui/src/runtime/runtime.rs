@@ -9,6 +9,7 @@ pub use service::{Service, ServiceResult};
 pub struct Inner {
     egui_ctx: egui::Context,
     events: ApplicationEventsChannel,
+    bus: MessageBus,
     services: RwLock<AHashMap<String, Arc<dyn Service>>>,
     repaint_service: Arc<RepaintService>,
     is_running: Arc<AtomicBool>,
@@ -32,6 +33,7 @@ impl Runtime {
             inner: Arc::new(Inner {
                 services: Default::default(),
                 events,
+                bus: MessageBus::new(Some(egui_ctx), bus::DEFAULT_BUS_CAPACITY),
                 repaint_service: repaint_service.clone(),
                 egui_ctx: egui_ctx.clone(),
                 is_running: Arc::new(AtomicBool::new(false)),
@@ -44,7 +46,10 @@ impl Runtime {
         runtime.bind(repaint_service);
 
         #[cfg(target_arch = "wasm32")]
-        runtime.register_visibility_handler();
+        {
+            runtime.register_visibility_handler();
+            runtime.register_unload_handler();
+        }
 
         runtime
     }
@@ -133,6 +138,11 @@ impl Runtime {
         &self.inner.events
     }
 
+    /// Returns the reference to the inter-module [`MessageBus`].
+    pub fn bus(&self) -> &MessageBus {
+        &self.inner.bus
+    }
+
     /// Send an application even to the UI asynchronously.
     pub async fn send<T>(&self, msg: T) -> Result<()>
     where
@@ -222,6 +232,29 @@ impl Runtime {
         document().set_onvisibilitychange(Some(callback.as_ref()));
         self.inner.callbacks.retain(callback).unwrap();
     }
+
+    /// Post [`RuntimeEvent::Exit`] and signal the termination of the
+    /// services when the page is unloaded. The services can not be
+    /// joined as the page does not wait for async tasks on unload.
+    #[cfg(target_arch = "wasm32")]
+    pub fn register_unload_handler(&self) {
+        use workflow_dom::utils::*;
+        use workflow_wasm::callback::*;
+
+        let callback = callback!(move |_event: web_sys::Event| {
+            if let Some(runtime) = try_runtime() {
+                runtime.try_send_runtime_event(RuntimeEvent::Exit).ok();
+                if runtime.inner.is_running.swap(false, Ordering::SeqCst) {
+                    runtime.stop_services();
+                }
+            }
+        });
+
+        window()
+            .add_event_listener_with_callback("pagehide", callback.as_ref())
+            .unwrap();
+        self.inner.callbacks.retain(callback).unwrap();
+    }
 }
 
 static RUNTIME: Mutex<Option<Runtime>> = Mutex::new(None);
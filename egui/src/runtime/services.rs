@@ -0,0 +1,199 @@
+//!
+//! Bridge driving a [`workflow_service`] runtime (background services such
+//! as RPC clients or sync engines) from an eframe application. Requires the
+//! `service` feature (native only, as [`workflow_service`] is not available
+//! in wasm builds, where the [`Runtime`](super::Runtime) services are
+//! terminated on page unload instead).
+//!
+//! - the service runtime is started by [`attach_services()`] when the
+//!   application is created
+//! - window close requests are vetoed until the services have terminated
+//!   (in the reverse order of their binding) or the shutdown timeout elapses
+//!   (see [`ServiceBridge::handle_close_request()`])
+//! - state and health changes recorded by the service runtime trigger a repaint
+//! - [`ServiceBridge::status_ui()`] renders the state and health of the services
+//!
+//! ```ignore
+//! impl eframe::App for MyApp {
+//!     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+//!         self.services.handle_close_request(ctx);
+//!         egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
+//!             self.services.status_ui(ui);
+//!         });
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+
+use crate::imports::*;
+use workflow_service::prelude::{Runtime as ServiceRuntime, ServiceState, ServiceStatus};
+
+/// Default time allowed for the services to terminate
+/// before the application window is closed
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Lifecycle state of the [`ServiceBridge`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BridgeState {
+    Running,
+    /// Services are terminating; window close requests are vetoed
+    ShuttingDown,
+    /// Services have terminated (or the shutdown timed out)
+    Terminated,
+}
+
+struct Inner {
+    egui_ctx: egui::Context,
+    runtime: ServiceRuntime,
+    shutdown_timeout: Duration,
+    state: Mutex<BridgeState>,
+    /// Signaled once the service runtime has terminated
+    terminated: Channel<()>,
+}
+
+/// Handle of a service runtime attached to the application
+/// (see the [module](self) documentation)
+#[derive(Clone)]
+pub struct ServiceBridge {
+    inner: Arc<Inner>,
+}
+
+/// Start the service `runtime` and attach it to the application
+/// created with `cc`, allowing up to [`DEFAULT_SHUTDOWN_TIMEOUT`]
+/// for the services to terminate once the window is closed.
+/// Must be called within the async (tokio) runtime context.
+pub fn attach_services(
+    cc: &eframe::CreationContext<'_>,
+    runtime: &ServiceRuntime,
+) -> ServiceBridge {
+    ServiceBridge::new(&cc.egui_ctx, runtime, DEFAULT_SHUTDOWN_TIMEOUT)
+}
+
+impl ServiceBridge {
+    /// Start the service `runtime`, allowing up to `shutdown_timeout`
+    /// for the services to terminate once the window is closed.
+    pub fn new(
+        egui_ctx: &egui::Context,
+        runtime: &ServiceRuntime,
+        shutdown_timeout: Duration,
+    ) -> Self {
+        let bridge = Self {
+            inner: Arc::new(Inner {
+                egui_ctx: egui_ctx.clone(),
+                runtime: runtime.clone(),
+                shutdown_timeout,
+                state: Mutex::new(BridgeState::Running),
+                terminated: Channel::oneshot(),
+            }),
+        };
+
+        let events = runtime.subscribe();
+        let egui_ctx = egui_ctx.clone();
+        task::spawn(async move {
+            while events.recv().await.is_ok() {
+                egui_ctx.request_repaint();
+            }
+        });
+
+        let this = bridge.clone();
+        task::spawn(async move {
+            if let Err(err) = this.inner.runtime.run().await {
+                log_error!("Service runtime error: {err}");
+            }
+            *this.inner.state.lock().unwrap() = BridgeState::Terminated;
+            this.inner.terminated.try_send(()).ok();
+            this.inner.egui_ctx.request_repaint();
+        });
+
+        bridge
+    }
+
+    pub fn runtime(&self) -> &ServiceRuntime {
+        &self.inner.runtime
+    }
+
+    pub fn state(&self) -> BridgeState {
+        *self.inner.state.lock().unwrap()
+    }
+
+    /// Lifecycle state and health of the services
+    pub fn status(&self) -> Vec<ServiceStatus> {
+        self.inner.runtime.status()
+    }
+
+    /// Veto the window close requested during the current frame until
+    /// the services have terminated, initiating the shutdown if the
+    /// services are running. Must be called on every frame (typically
+    /// at the start of [`eframe::App::update()`]).
+    pub fn handle_close_request(&self, ctx: &egui::Context) {
+        if !ctx.input(|input| input.viewport().close_requested()) {
+            return;
+        }
+
+        match self.state() {
+            BridgeState::Terminated => {}
+            BridgeState::ShuttingDown => ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose),
+            BridgeState::Running => {
+                ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+                self.shutdown();
+            }
+        }
+    }
+
+    /// Terminate the services and close the application window once
+    /// they have terminated or the shutdown timeout has elapsed.
+    pub fn shutdown(&self) {
+        {
+            let mut state = self.inner.state.lock().unwrap();
+            if *state != BridgeState::Running {
+                return;
+            }
+            *state = BridgeState::ShuttingDown;
+        }
+
+        self.inner.runtime.terminate();
+        self.inner.egui_ctx.request_repaint();
+
+        let this = self.clone();
+        task::spawn(async move {
+            select! {
+                _ = this.inner.terminated.recv().fuse() => {},
+                _ = task::sleep(this.inner.shutdown_timeout).fuse() => {
+                    log_warn!(
+                        "Service shutdown timed out after {:?}, closing the application",
+                        this.inner.shutdown_timeout
+                    );
+                    *this.inner.state.lock().unwrap() = BridgeState::Terminated;
+                }
+            }
+            this.inner
+                .egui_ctx
+                .send_viewport_cmd(egui::ViewportCommand::Close);
+            this.inner.egui_ctx.request_repaint();
+        });
+    }
+
+    /// Render the state and health of the services
+    pub fn status_ui(&self, ui: &mut egui::Ui) {
+        for status in self.status() {
+            ui.horizontal(|ui| {
+                let color = match (status.state, status.healthy) {
+                    (Some(ServiceState::Failed), _) | (_, Some(false)) => egui::Color32::RED,
+                    (Some(ServiceState::Running), _) => egui::Color32::GREEN,
+                    _ => egui::Color32::GRAY,
+                };
+                ui.colored_label(color, "●");
+                ui.label(status.name);
+                let state = status
+                    .state
+                    .map(|state| state.to_string())
+                    .unwrap_or_else(|| "pending".to_string());
+                match status.reason {
+                    Some(reason) => ui.weak(format!("{state} ({reason})")),
+                    None => ui.weak(state),
+                };
+            });
+        }
+    }
+}
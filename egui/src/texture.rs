@@ -0,0 +1,462 @@
+//!
+//! [`TextureLoader`] fetching and decoding images in the background
+//! and uploading them to the egui texture manager on the next frame.
+//!
+//! Images are downloaded using [`workflow_http`] and decoded off the UI
+//! thread (using `createImageBitmap()` in the browser, falling back to
+//! the `image` crate where it is not available). Loaded textures are
+//! retained in an LRU cache keyed by the source URL, limited by the
+//! size of the decoded images (see [`TextureLoader::with_budget()`]).
+//!
+//! Example module displaying a remote image with a placeholder while loading:
+//!
+//! ```ignore
+//! pub struct Gallery {
+//!     image: TextureTicket,
+//! }
+//!
+//! impl Gallery {
+//!     pub fn new(runtime: &Runtime) -> Self {
+//!         let loader = TextureLoader::new(runtime.egui_ctx());
+//!         Self {
+//!             image: loader.load("https://example.com/image.png"),
+//!         }
+//!     }
+//! }
+//!
+//! impl ModuleT for Gallery {
+//!     type Context = MyApp;
+//!
+//!     fn render(&mut self, _app: &mut MyApp, _ctx: &egui::Context, _frame: &mut eframe::Frame, ui: &mut egui::Ui) {
+//!         match self.image.poll() {
+//!             TextureState::Loading => {
+//!                 ui.spinner();
+//!             }
+//!             TextureState::Ready(texture) => {
+//!                 ui.image((texture.id(), texture.size_vec2()));
+//!             }
+//!             TextureState::Failed(err) => {
+//!                 ui.label(format!("Unable to load image: {err}"));
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+//!
+
+use crate::imports::*;
+use egui::{ColorImage, TextureHandle, TextureOptions};
+
+/// Default size limit of the decoded images retained by the [`TextureLoader`] cache.
+pub const DEFAULT_TEXTURE_BUDGET: usize = 64 * 1024 * 1024;
+
+/// Image source supplied to [`TextureLoader::load()`].
+#[derive(Debug, Clone)]
+pub enum TextureSource {
+    /// Image fetched from the URL (also used as the cache key)
+    Url(String),
+    /// Encoded image data cached under the `name`
+    Bytes { name: String, bytes: Vec<u8> },
+}
+
+impl TextureSource {
+    pub fn bytes(name: impl Into<String>, bytes: impl Into<Vec<u8>>) -> Self {
+        TextureSource::Bytes {
+            name: name.into(),
+            bytes: bytes.into(),
+        }
+    }
+
+    /// Cache key of the source
+    pub fn key(&self) -> &str {
+        match self {
+            TextureSource::Url(url) => url,
+            TextureSource::Bytes { name, .. } => name,
+        }
+    }
+}
+
+impl From<&str> for TextureSource {
+    fn from(url: &str) -> Self {
+        TextureSource::Url(url.to_string())
+    }
+}
+
+impl From<String> for TextureSource {
+    fn from(url: String) -> Self {
+        TextureSource::Url(url)
+    }
+}
+
+/// State of a texture returned by [`TextureTicket::poll()`].
+#[derive(Clone)]
+pub enum TextureState {
+    Loading,
+    Ready(TextureHandle),
+    Failed(Arc<Error>),
+}
+
+enum Slot {
+    Loading,
+    Decoded(ColorImage),
+    Ready(TextureHandle),
+    Failed(Arc<Error>),
+}
+
+struct CacheEntry {
+    slot: Arc<Mutex<Slot>>,
+    /// Size of the uploaded texture (0 while loading)
+    bytes: usize,
+    /// Last use of the entry, used for LRU eviction
+    tick: u64,
+}
+
+#[derive(Default)]
+struct Cache {
+    entries: AHashMap<String, CacheEntry>,
+    bytes: usize,
+    tick: u64,
+}
+
+impl Cache {
+    fn touch(&mut self, key: &str) -> Option<Arc<Mutex<Slot>>> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(key).map(|entry| {
+            entry.tick = tick;
+            entry.slot.clone()
+        })
+    }
+
+    fn insert(&mut self, key: &str, slot: Arc<Mutex<Slot>>) {
+        self.tick += 1;
+        let entry = CacheEntry {
+            slot,
+            bytes: 0,
+            tick: self.tick,
+        };
+        if let Some(previous) = self.entries.insert(key.to_string(), entry) {
+            self.bytes -= previous.bytes;
+        }
+    }
+
+    fn remove(&mut self, key: &str, slot: &Arc<Mutex<Slot>>) {
+        // the entry may have been evicted and replaced by a new load
+        if matches!(self.entries.get(key), Some(entry) if Arc::ptr_eq(&entry.slot, slot)) {
+            let entry = self.entries.remove(key).unwrap();
+            self.bytes -= entry.bytes;
+        }
+    }
+
+    /// Accounts for the uploaded texture, evicting the least recently
+    /// used textures (other than `key`) exceeding the `budget`.
+    fn account(&mut self, key: &str, slot: &Arc<Mutex<Slot>>, bytes: usize, budget: usize) {
+        match self.entries.get_mut(key) {
+            Some(entry) if Arc::ptr_eq(&entry.slot, slot) => {
+                self.bytes = self.bytes - entry.bytes + bytes;
+                entry.bytes = bytes;
+            }
+            _ => return,
+        }
+
+        while self.bytes > budget {
+            let lru = self
+                .entries
+                .iter()
+                .filter(|(k, entry)| entry.bytes > 0 && k.as_str() != key)
+                .min_by_key(|(_, entry)| entry.tick)
+                .map(|(k, _)| k.clone());
+            let Some(lru) = lru else {
+                break;
+            };
+            let entry = self.entries.remove(&lru).unwrap();
+            self.bytes -= entry.bytes;
+        }
+    }
+}
+
+struct Inner {
+    ctx: egui::Context,
+    options: TextureOptions,
+    budget: usize,
+    cache: Mutex<Cache>,
+}
+
+/// Loads textures in the background, see the [module](self) documentation.
+/// Clones of the loader share the cache.
+#[derive(Clone)]
+pub struct TextureLoader {
+    inner: Arc<Inner>,
+}
+
+impl TextureLoader {
+    pub fn new(ctx: &egui::Context) -> Self {
+        Self::with_settings(ctx, TextureOptions::default(), DEFAULT_TEXTURE_BUDGET)
+    }
+
+    /// Creates a loader retaining up to `budget` bytes of decoded images
+    /// (4 bytes per pixel). Textures in use by the UI remain valid after
+    /// the eviction, while their next load results in a new download.
+    pub fn with_budget(ctx: &egui::Context, budget: usize) -> Self {
+        Self::with_settings(ctx, TextureOptions::default(), budget)
+    }
+
+    /// Creates a loader uploading textures with the supplied [`TextureOptions`].
+    pub fn with_settings(ctx: &egui::Context, options: TextureOptions, budget: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                ctx: ctx.clone(),
+                options,
+                budget,
+                cache: Mutex::new(Cache::default()),
+            }),
+        }
+    }
+
+    /// Starts loading the texture unless it is cached or already loading.
+    pub fn load(&self, source: impl Into<TextureSource>) -> TextureTicket {
+        let source = source.into();
+        let key = source.key().to_string();
+
+        let mut cache = self.inner.cache.lock().unwrap();
+        if let Some(slot) = cache.touch(&key) {
+            return TextureTicket {
+                loader: self.clone(),
+                key,
+                slot,
+            };
+        }
+
+        let slot = Arc::new(Mutex::new(Slot::Loading));
+        cache.insert(&key, slot.clone());
+        drop(cache);
+
+        let loader = self.clone();
+        let key_ = key.clone();
+        let slot_ = slot.clone();
+        let task = async move {
+            let result = match source {
+                TextureSource::Url(url) => workflow_http::get_bytes(url).await.map_err(Error::from),
+                TextureSource::Bytes { bytes, .. } => Ok(bytes),
+            };
+            let result = match result {
+                Ok(bytes) => decode(bytes).await,
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(image) => *slot_.lock().unwrap() = Slot::Decoded(image),
+                Err(err) => {
+                    log_error!("unable to load texture `{key_}`: {err}");
+                    // failed loads are not cached so that they can be retried
+                    loader.inner.cache.lock().unwrap().remove(&key_, &slot_);
+                    *slot_.lock().unwrap() = Slot::Failed(Arc::new(err));
+                }
+            }
+            loader.inner.ctx.request_repaint();
+        };
+
+        cfg_if! {
+            if #[cfg(target_arch = "wasm32")] {
+                task::dispatch(task);
+            } else {
+                task::spawn(task);
+            }
+        }
+
+        TextureTicket {
+            loader: self.clone(),
+            key,
+            slot,
+        }
+    }
+
+    /// Total size of the cached textures
+    pub fn cached_bytes(&self) -> usize {
+        self.inner.cache.lock().unwrap().bytes
+    }
+
+    /// Returns `true` if the texture of the source `key` is cached (or loading).
+    pub fn contains(&self, key: &str) -> bool {
+        self.inner.cache.lock().unwrap().entries.contains_key(key)
+    }
+
+    /// Removes all textures from the cache.
+    pub fn clear(&self) {
+        let mut cache = self.inner.cache.lock().unwrap();
+        cache.entries.clear();
+        cache.bytes = 0;
+    }
+}
+
+/// Handle of a texture requested via [`TextureLoader::load()`].
+#[derive(Clone)]
+pub struct TextureTicket {
+    loader: TextureLoader,
+    key: String,
+    slot: Arc<Mutex<Slot>>,
+}
+
+impl TextureTicket {
+    /// Cache key of the texture source
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns the state of the texture. Meant to be called from
+    /// `update()`, uploads the decoded image to the egui texture
+    /// manager once available.
+    pub fn poll(&self) -> TextureState {
+        let mut slot = self.slot.lock().unwrap();
+        if matches!(*slot, Slot::Decoded(_)) {
+            let Slot::Decoded(image) = std::mem::replace(&mut *slot, Slot::Loading) else {
+                unreachable!()
+            };
+            let bytes = image.pixels.len() * 4;
+            let inner = &self.loader.inner;
+            *slot = Slot::Ready(inner.ctx.load_texture(&self.key, image, inner.options));
+            drop(slot);
+            inner
+                .cache
+                .lock()
+                .unwrap()
+                .account(&self.key, &self.slot, bytes, inner.budget);
+            return self.poll();
+        }
+
+        match &*slot {
+            Slot::Loading | Slot::Decoded(_) => TextureState::Loading,
+            Slot::Ready(texture) => TextureState::Ready(texture.clone()),
+            Slot::Failed(err) => TextureState::Failed(err.clone()),
+        }
+    }
+
+    /// Returns the texture if loaded.
+    pub fn texture(&self) -> Option<TextureHandle> {
+        match self.poll() {
+            TextureState::Ready(texture) => Some(texture),
+            _ => None,
+        }
+    }
+}
+
+fn decode_image(bytes: &[u8]) -> Result<ColorImage> {
+    let image = image::load_from_memory(bytes)?.to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Ok(ColorImage::from_rgba_unmultiplied(size, image.as_raw()))
+}
+
+cfg_if! {
+    if #[cfg(target_arch = "wasm32")] {
+        use wasm_bindgen::JsCast;
+        use wasm_bindgen::JsValue;
+        use wasm_bindgen_futures::JsFuture;
+        use web_sys::{Blob, ImageBitmap, OffscreenCanvas, OffscreenCanvasRenderingContext2d};
+
+        fn js_error(err: JsValue) -> Error {
+            Error::Image(format!("{err:?}"))
+        }
+
+        /// Decodes the image using `createImageBitmap()` and reads the
+        /// pixels back via an `OffscreenCanvas`.
+        async fn decode_image_bitmap(bytes: &[u8]) -> Result<ColorImage> {
+            let window = web_sys::window().ok_or_else(|| Error::Image("window is not available".to_string()))?;
+            let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+            let blob = Blob::new_with_u8_array_sequence(&parts).map_err(js_error)?;
+            let bitmap: ImageBitmap = JsFuture::from(window.create_image_bitmap_with_blob(&blob).map_err(js_error)?)
+                .await
+                .map_err(js_error)?
+                .dyn_into()
+                .map_err(js_error)?;
+            let (width, height) = (bitmap.width(), bitmap.height());
+            let canvas = OffscreenCanvas::new(width, height).map_err(js_error)?;
+            let context: OffscreenCanvasRenderingContext2d = canvas
+                .get_context("2d")
+                .map_err(js_error)?
+                .ok_or_else(|| Error::Image("2d context is not available".to_string()))?
+                .dyn_into()
+                .map_err(js_error)?;
+            context.draw_image_with_image_bitmap(&bitmap, 0.0, 0.0).map_err(js_error)?;
+            let data = context
+                .get_image_data(0.0, 0.0, width as f64, height as f64)
+                .map_err(js_error)?
+                .data();
+            bitmap.close();
+            Ok(ColorImage::from_rgba_unmultiplied([width as usize, height as usize], &data.0))
+        }
+
+        async fn decode(bytes: Vec<u8>) -> Result<ColorImage> {
+            match decode_image_bitmap(&bytes).await {
+                Ok(image) => Ok(image),
+                Err(err) => {
+                    log_trace!("createImageBitmap() decode failure: {err}, using fallback decoder");
+                    decode_image(&bytes)
+                }
+            }
+        }
+    } else {
+        async fn decode(bytes: Vec<u8>) -> Result<ColorImage> {
+            tokio::task::spawn_blocking(move || decode_image(&bytes))
+                .await
+                .map_err(|err| Error::custom(err.to_string()))?
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgba, RgbaImage};
+    use std::io::Cursor;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbaImage::from_pixel(width, height, Rgba([255, 0, 0, 255]));
+        let mut data = Cursor::new(Vec::new());
+        image.write_to(&mut data, ImageFormat::Png).unwrap();
+        data.into_inner()
+    }
+
+    async fn ready(ticket: &TextureTicket) -> TextureState {
+        loop {
+            match ticket.poll() {
+                TextureState::Loading => task::sleep(Duration::from_millis(10)).await,
+                state => break state,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_texture_loader() {
+        let ctx = egui::Context::default();
+        // retains two 4x4 textures (64 bytes each)
+        let loader = TextureLoader::with_budget(&ctx, 128);
+
+        let first = loader.load(TextureSource::bytes("first", png(4, 4)));
+        let TextureState::Ready(texture) = ready(&first).await else {
+            panic!("texture is not loaded");
+        };
+        assert_eq!(texture.size(), [4, 4]);
+        assert_eq!(loader.cached_bytes(), 64);
+        assert!(ctx.has_requested_repaint());
+
+        // cached textures are shared
+        let again = loader.load(TextureSource::bytes("first", Vec::new()));
+        assert!(matches!(again.poll(), TextureState::Ready(t) if t.id() == texture.id()));
+
+        let second = loader.load(TextureSource::bytes("second", png(4, 4)));
+        ready(&second).await;
+        // `first` was used more recently than `second` was loaded
+        loader.load(TextureSource::bytes("first", Vec::new()));
+        let third = loader.load(TextureSource::bytes("third", png(4, 4)));
+        ready(&third).await;
+        assert_eq!(loader.cached_bytes(), 128);
+        assert!(loader.contains("first") && loader.contains("third"));
+        assert!(!loader.contains("second"));
+        // evicted textures remain usable
+        assert!(second.texture().is_some());
+
+        let invalid = loader.load(TextureSource::bytes("invalid", vec![0, 1, 2, 3]));
+        assert!(matches!(ready(&invalid).await, TextureState::Failed(_)));
+        assert!(!loader.contains("invalid"));
+    }
+}
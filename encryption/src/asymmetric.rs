@@ -0,0 +1,273 @@
+//!
+//! `X25519` key exchange and sealed boxes (public key encryption).
+//!
+//! A sealed box is produced for a recipient public key using an ephemeral
+//! keypair; only the holder of the recipient private key can open it.
+//! The encryption key is derived from the `X25519` shared secret and both
+//! public keys, and the data is encrypted with `XChaCha20Poly1305`:
+//!
+//! ```text
+//! ephemeral_public_key: [u8; 32] | nonce: [u8; 24] | ciphertext
+//! ```
+//!
+//! [`Keypair`] and [`PublicKey`] support serde and Borsh serialization,
+//! allowing them to be persisted via `workflow-store` (for example using
+//! `workflow_store::fs::write_json()`).
+//!
+
+use crate::error::Error;
+use crate::imports::*;
+use crate::secret::sensitive_buffer;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, AeadCore, AeadInPlace, KeyInit, OsRng},
+    Key, XChaCha20Poly1305,
+};
+use sha2::{Digest, Sha256};
+use workflow_core::hex::{FromHex, ToHex};
+use x25519_dalek::StaticSecret;
+
+pub const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 24;
+const TAG_LENGTH: usize = 16;
+const SEALED_BOX_DOMAIN: &[u8] = b"workflow-encryption/sealed-box/v1";
+
+/// `X25519` public key.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct PublicKey([u8; KEY_LENGTH]);
+
+impl PublicKey {
+    pub fn as_bytes(&self) -> &[u8; KEY_LENGTH] {
+        &self.0
+    }
+}
+
+impl From<[u8; KEY_LENGTH]> for PublicKey {
+    fn from(bytes: [u8; KEY_LENGTH]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for PublicKey {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self> {
+        Ok(Self(bytes.try_into().map_err(|_| Error::InvalidKey)?))
+    }
+}
+
+impl std::fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.as_slice().to_hex())
+    }
+}
+
+impl std::str::FromStr for PublicKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let bytes = Vec::<u8>::from_hex(s).map_err(|_| Error::InvalidKey)?;
+        PublicKey::try_from(bytes.as_slice())
+    }
+}
+
+impl From<&x25519_dalek::PublicKey> for PublicKey {
+    fn from(key: &x25519_dalek::PublicKey) -> Self {
+        Self(key.to_bytes())
+    }
+}
+
+impl From<&PublicKey> for x25519_dalek::PublicKey {
+    fn from(key: &PublicKey) -> Self {
+        x25519_dalek::PublicKey::from(key.0)
+    }
+}
+
+/// `X25519` keypair. The private half is retained as a [`Secret`].
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct Keypair {
+    public: PublicKey,
+    secret: Secret,
+}
+
+impl Keypair {
+    /// Generates a new random keypair.
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; KEY_LENGTH];
+        OsRng.fill_bytes(&mut bytes);
+        let keypair = Self::from_secret_bytes(&bytes);
+        bytes.zeroize();
+        keypair
+    }
+
+    /// Restores a keypair from its private key.
+    pub fn from_secret(secret: Secret) -> Result<Self> {
+        let mut bytes: [u8; KEY_LENGTH] =
+            secret.as_ref().try_into().map_err(|_| Error::InvalidKey)?;
+        let keypair = Self::from_secret_bytes(&bytes);
+        bytes.zeroize();
+        Ok(keypair)
+    }
+
+    fn from_secret_bytes(bytes: &[u8; KEY_LENGTH]) -> Self {
+        let static_secret = StaticSecret::from(*bytes);
+        let public = PublicKey::from(&x25519_dalek::PublicKey::from(&static_secret));
+        Self {
+            public,
+            secret: Secret::new(static_secret.to_bytes().to_vec()),
+        }
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public
+    }
+
+    pub fn secret(&self) -> &Secret {
+        &self.secret
+    }
+
+    fn static_secret(&self) -> Result<StaticSecret> {
+        let bytes: [u8; KEY_LENGTH] = self
+            .secret
+            .as_ref()
+            .try_into()
+            .map_err(|_| Error::InvalidKey)?;
+        Ok(StaticSecret::from(bytes))
+    }
+
+    /// Performs `X25519` Diffie-Hellman with the remote public key.
+    /// Fails if the remote key is a low-order point.
+    pub fn diffie_hellman(&self, remote: &PublicKey) -> Result<Secret> {
+        let shared = self
+            .static_secret()?
+            .diffie_hellman(&x25519_dalek::PublicKey::from(remote));
+        if !shared.was_contributory() {
+            return Err(Error::InvalidKey);
+        }
+        Ok(Secret::new(shared.as_bytes().to_vec()))
+    }
+}
+
+fn sealed_box_key(shared: &Secret, ephemeral: &PublicKey, recipient: &PublicKey) -> Secret {
+    let mut hash = Sha256::default();
+    hash.update(SEALED_BOX_DOMAIN);
+    hash.update(shared.as_ref());
+    hash.update(ephemeral.as_bytes());
+    hash.update(recipient.as_bytes());
+    Secret::new(hash.finalize().to_vec())
+}
+
+/// Encrypts `plaintext` for the owner of the `recipient` public key.
+pub fn seal(recipient: &PublicKey, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let ephemeral = Keypair::generate();
+    let shared = ephemeral.diffie_hellman(recipient)?;
+    let key = sealed_box_key(&shared, ephemeral.public_key(), recipient);
+
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_ref()));
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let mut buffer = sensitive_buffer(plaintext, TAG_LENGTH);
+    if let Err(err) =
+        cipher.encrypt_in_place(&nonce, ephemeral.public_key().as_bytes(), &mut buffer)
+    {
+        buffer.zeroize();
+        return Err(err.into());
+    }
+
+    let mut sealed = Vec::with_capacity(KEY_LENGTH + NONCE_LENGTH + buffer.len());
+    sealed.extend_from_slice(ephemeral.public_key().as_bytes());
+    sealed.extend_from_slice(&nonce);
+    sealed.extend(buffer);
+    Ok(sealed)
+}
+
+/// Opens a sealed box produced by [`seal()`] using the recipient keypair.
+/// Returns [`Error::SealedBoxAuthentication`] if the data has been
+/// tampered with or was not sealed for this keypair.
+pub fn open(keypair: &Keypair, sealed: &[u8]) -> Result<Secret> {
+    if sealed.len() < KEY_LENGTH + NONCE_LENGTH + TAG_LENGTH {
+        return Err(Error::DecryptionDataLength);
+    }
+    let (ephemeral, rest) = sealed.split_at(KEY_LENGTH);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LENGTH);
+    let ephemeral = PublicKey::try_from(ephemeral)?;
+
+    let shared = keypair
+        .diffie_hellman(&ephemeral)
+        .map_err(|_| Error::SealedBoxAuthentication)?;
+    let key = sealed_box_key(&shared, &ephemeral, keypair.public_key());
+
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_ref()));
+    let mut buffer = ciphertext.to_vec();
+    cipher
+        .decrypt_in_place(nonce.into(), ephemeral.as_bytes(), &mut buffer)
+        .map_err(|_| Error::SealedBoxAuthentication)?;
+    Ok(Secret::new(buffer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sealed_box_round_trip() -> Result<()> {
+        let recipient = Keypair::generate();
+        let plaintext = b"configuration blob";
+        let sealed = seal(recipient.public_key(), plaintext)?;
+        assert_eq!(open(&recipient, &sealed)?.as_ref(), plaintext);
+
+        // keys survive serialization
+        let json = serde_json::to_string(&recipient).unwrap();
+        let restored: Keypair = serde_json::from_str(&json).unwrap();
+        assert_eq!(open(&restored, &sealed)?.as_ref(), plaintext);
+
+        let borsh = borsh::to_vec(&recipient)?;
+        let restored = Keypair::try_from_slice(&borsh)?;
+        assert_eq!(restored.public_key(), recipient.public_key());
+
+        let restored = Keypair::from_secret(recipient.secret().clone())?;
+        assert_eq!(restored.public_key(), recipient.public_key());
+
+        let public_key = recipient.public_key().to_string().parse::<PublicKey>()?;
+        assert_eq!(&public_key, recipient.public_key());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sealed_box_tampering() -> Result<()> {
+        let recipient = Keypair::generate();
+        let sealed = seal(recipient.public_key(), b"configuration blob")?;
+
+        for idx in [0, KEY_LENGTH, KEY_LENGTH + NONCE_LENGTH, sealed.len() - 1] {
+            let mut tampered = sealed.clone();
+            tampered[idx] ^= 1;
+            assert!(matches!(
+                open(&recipient, &tampered),
+                Err(Error::SealedBoxAuthentication)
+            ));
+        }
+
+        let other = Keypair::generate();
+        assert!(matches!(
+            open(&other, &sealed),
+            Err(Error::SealedBoxAuthentication)
+        ));
+
+        assert!(matches!(
+            open(&recipient, &sealed[..KEY_LENGTH]),
+            Err(Error::DecryptionDataLength)
+        ));
+
+        Ok(())
+    }
+}
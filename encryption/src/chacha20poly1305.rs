@@ -1,10 +1,52 @@
+//!
+//! `XChaCha20Poly1305` encryption with a versioned envelope.
+//!
+//! Data produced by [`encrypt_slice()`] is prefixed with a header
+//! recording the envelope version and the key derivation function
+//! (and its parameters) used to derive the encryption key:
+//!
+//! ```text
+//! "wfe\0" | version: u8 | kdf: borsh(Kdf) | nonce: [u8; 24] | ciphertext
+//! ```
+//!
+//! The header is authenticated as associated data. Data without the
+//! header is treated as the legacy format (`ciphertext | nonce`, key
+//! derived via [`argon2_sha256()`]).
+//!
+
 use crate::error::Error;
 use crate::imports::*;
+use crate::kdf::{self, Params, SALT_LENGTH};
+use crate::secret::sensitive_buffer;
 use chacha20poly1305::{
-    aead::{AeadCore, AeadInPlace, KeyInit, OsRng},
+    aead::{rand_core::RngCore, AeadCore, AeadInPlace, KeyInit, OsRng},
     Key, XChaCha20Poly1305,
 };
 
+/// Magic bytes identifying a versioned envelope.
+pub const ENVELOPE_MAGIC: [u8; 4] = *b"wfe\0";
+/// Current envelope version.
+pub const ENVELOPE_VERSION: u8 = 1;
+
+const NONCE_LENGTH: usize = 24;
+
+/// Key derivation function recorded in the envelope header.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub enum Kdf {
+    Argon2id {
+        params: Params,
+        salt: [u8; SALT_LENGTH],
+    },
+}
+
+impl Kdf {
+    pub(crate) fn derive(&self, secret: &Secret) -> Result<Secret> {
+        match self {
+            Kdf::Argon2id { params, salt } => kdf::argon2id(secret.as_ref(), salt, params),
+        }
+    }
+}
+
 /// Encrypts the given data using `XChaCha20Poly1305` algorithm.
 pub fn encrypt<T>(data: &T, secret: &Secret) -> Result<Vec<u8>>
 where
@@ -12,17 +54,59 @@ where
 {
     let mut buffer = vec![];
     data.serialize(&mut buffer)?;
-    encrypt_slice(&buffer, secret)
+    let encrypted = encrypt_slice(&buffer, secret);
+    buffer.zeroize();
+    encrypted
 }
 
+/// Encrypts the given data using `XChaCha20Poly1305` algorithm
+/// with the key derived via `Argon2id` using default [`Params`].
 pub fn encrypt_slice(data: &[u8], secret: &Secret) -> Result<Vec<u8>> {
+    encrypt_slice_with_params(data, secret, &Params::default())
+}
+
+/// Encrypts the given data using `XChaCha20Poly1305` algorithm
+/// with the key derived via `Argon2id` using the supplied [`Params`].
+pub fn encrypt_slice_with_params(data: &[u8], secret: &Secret, params: &Params) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    let kdf = Kdf::Argon2id {
+        params: *params,
+        salt,
+    };
+    let private_key_bytes = kdf.derive(secret)?;
+
+    let mut header = ENVELOPE_MAGIC.to_vec();
+    header.push(ENVELOPE_VERSION);
+    BorshSerialize::serialize(&kdf, &mut header)?;
+
+    let key = Key::from_slice(private_key_bytes.as_ref());
+    let cipher = XChaCha20Poly1305::new(key);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let mut buffer = sensitive_buffer(data, 16);
+    if let Err(err) = cipher.encrypt_in_place(&nonce, &header, &mut buffer) {
+        buffer.zeroize();
+        return Err(err.into());
+    }
+
+    header.extend(nonce.iter().cloned());
+    header.extend(buffer);
+    Ok(header)
+}
+
+/// Encrypts the given data using the legacy (pre-envelope) format.
+/// Retained for interoperability with older releases; new data
+/// should be encrypted using [`encrypt_slice()`].
+pub fn encrypt_slice_legacy(data: &[u8], secret: &Secret) -> Result<Vec<u8>> {
     let private_key_bytes = argon2_sha256(secret.as_ref(), 32)?;
     let key = Key::from_slice(private_key_bytes.as_ref());
     let cipher = XChaCha20Poly1305::new(key);
     let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng); // 96-bits; unique per message
-    let mut buffer = data.to_vec();
-    buffer.reserve(24);
-    cipher.encrypt_in_place(&nonce, &[], &mut buffer)?;
+    let mut buffer = sensitive_buffer(data, 16 + 24);
+    if let Err(err) = cipher.encrypt_in_place(&nonce, &[], &mut buffer) {
+        buffer.zeroize();
+        return Err(err.into());
+    }
     buffer.extend(nonce.iter().cloned());
     Ok(buffer)
 }
@@ -36,14 +120,42 @@ where
 }
 
 /// Decrypts the given data using `XChaCha20Poly1305` algorithm.
+/// Both versioned envelopes and legacy data are supported.
 pub fn decrypt_slice(data: &[u8], secret: &Secret) -> Result<Secret> {
-    if data.len() < 24 {
+    match data.strip_prefix(&ENVELOPE_MAGIC) {
+        Some(envelope) => decrypt_envelope(data, envelope, secret),
+        None => decrypt_slice_legacy(data, secret),
+    }
+}
+
+fn decrypt_envelope(data: &[u8], envelope: &[u8], secret: &Secret) -> Result<Secret> {
+    let (&version, mut rest) = envelope.split_first().ok_or(Error::DecryptionDataLength)?;
+    if version != ENVELOPE_VERSION {
+        return Err(Error::UnknownEnvelopeVersion(version));
+    }
+    let kdf = Kdf::deserialize(&mut rest).map_err(|_| Error::InvalidEnvelopeHeader)?;
+    let header = &data[..data.len() - rest.len()];
+    if rest.len() < NONCE_LENGTH {
+        return Err(Error::DecryptionDataLength);
+    }
+    let (nonce, ciphertext) = rest.split_at(NONCE_LENGTH);
+
+    let private_key_bytes = kdf.derive(secret)?;
+    let key = Key::from_slice(private_key_bytes.as_ref());
+    let cipher = XChaCha20Poly1305::new(key);
+    let mut buffer = ciphertext.to_vec();
+    cipher.decrypt_in_place(nonce.into(), header, &mut buffer)?;
+    Ok(Secret::new(buffer))
+}
+
+fn decrypt_slice_legacy(data: &[u8], secret: &Secret) -> Result<Secret> {
+    if data.len() < NONCE_LENGTH {
         return Err(Error::DecryptionDataLength);
     }
     let private_key_bytes = argon2_sha256(secret.as_ref(), 32)?;
     let key = Key::from_slice(private_key_bytes.as_ref());
     let cipher = XChaCha20Poly1305::new(key);
-    let len = data.len() - 24;
+    let len = data.len() - NONCE_LENGTH;
     let nonce = &data[len..];
     let mut buffer = data[..len].to_vec();
     cipher.decrypt_in_place(nonce.into(), &[], &mut buffer)?;
@@ -72,4 +184,58 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_envelope_versions() -> Result<()> {
+        let password = Secret::from("password");
+        let original = b"hello world".to_vec();
+        let params = Params::new(256, 1, 1);
+
+        // new writes are versioned and round-trip
+        let encrypted = encrypt_slice_with_params(&original, &password, &params)?;
+        assert!(encrypted.starts_with(&ENVELOPE_MAGIC));
+        assert_eq!(encrypted[ENVELOPE_MAGIC.len()], ENVELOPE_VERSION);
+        assert_eq!(decrypt_slice(&encrypted, &password)?.as_ref(), original);
+        assert!(decrypt_slice(&encrypted, &Secret::from("wrong")).is_err());
+
+        // parameters of a crafted envelope are bounded before the key is derived
+        let mut crafted = ENVELOPE_MAGIC.to_vec();
+        crafted.push(ENVELOPE_VERSION);
+        let kdf = Kdf::Argon2id {
+            params: Params::new(u32::MAX, u32::MAX, 1),
+            salt: [0; SALT_LENGTH],
+        };
+        BorshSerialize::serialize(&kdf, &mut crafted)?;
+        crafted.extend([0; NONCE_LENGTH + 16]);
+        assert!(matches!(
+            decrypt_slice(&crafted, &password),
+            Err(Error::KdfParamsLimit)
+        ));
+
+        // legacy blobs still decrypt
+        let legacy = encrypt_slice_legacy(&original, &password)?;
+        assert_eq!(decrypt_slice(&legacy, &password)?.as_ref(), original);
+
+        // unknown versions are rejected
+        let mut unknown = encrypted.clone();
+        unknown[ENVELOPE_MAGIC.len()] = ENVELOPE_VERSION + 1;
+        assert!(matches!(
+            decrypt_slice(&unknown, &password),
+            Err(Error::UnknownEnvelopeVersion(v)) if v == ENVELOPE_VERSION + 1
+        ));
+
+        // header is authenticated (salt tampering)
+        let mut tampered = encrypted.clone();
+        let len = tampered.len();
+        tampered[len - original.len() - 16 - NONCE_LENGTH - 1] ^= 1;
+        assert!(decrypt_slice(&tampered, &password).is_err());
+
+        // truncated header
+        assert!(matches!(
+            decrypt_slice(&encrypted[..ENVELOPE_MAGIC.len() + 3], &password),
+            Err(Error::InvalidEnvelopeHeader)
+        ));
+
+        Ok(())
+    }
 }
@@ -29,6 +29,27 @@ pub enum Error {
 
     #[error("Decryption failed (invalid data length)")]
     DecryptionDataLength,
+
+    #[error("Decryption failed (unknown envelope version {0})")]
+    UnknownEnvelopeVersion(u8),
+
+    #[error("Decryption failed (invalid envelope header)")]
+    InvalidEnvelopeHeader,
+
+    #[error("Key derivation parameters exceed the allowed maximum")]
+    KdfParamsLimit,
+
+    #[error("Stream has already been finished")]
+    StreamFinished,
+
+    #[error("Invalid key")]
+    InvalidKey,
+
+    #[error("Sealed box authentication failed")]
+    SealedBoxAuthentication,
+
+    #[error("Manifest entries do not match the root hash")]
+    ManifestRootHash,
 }
 
 impl From<String> for Error {
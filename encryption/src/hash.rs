@@ -1,6 +1,10 @@
 use crate::imports::*;
 use argon2::Argon2;
-use sha2::{Digest, Sha256};
+use base64::Engine;
+use hmac::Mac;
+use sha2::{Digest, Sha256, Sha512};
+use subtle::ConstantTimeEq;
+use workflow_core::hex::ToHex;
 
 /// Produces `SHA256` hash of the given data.
 #[inline]
@@ -26,6 +30,212 @@ pub fn argon2_sha256(data: &[u8], byte_length: usize) -> Result<Secret> {
     Ok(key.into())
 }
 
+/// Fixed-size authentication tag (or keyed hash) produced by [`Hmac`]
+/// and the BLAKE3 keyed hash. Equality comparison is performed in constant time.
+#[derive(Clone, Copy)]
+pub struct Tag<const N: usize>([u8; N]);
+
+/// `HMAC-SHA256` tag
+pub type Tag256 = Tag<32>;
+/// `HMAC-SHA512` tag
+pub type Tag512 = Tag<64>;
+
+impl<const N: usize> Tag<N> {
+    pub fn as_bytes(&self) -> &[u8; N] {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> [u8; N] {
+        self.0
+    }
+
+    pub fn to_hex(&self) -> String {
+        self.0.as_slice().to_hex()
+    }
+
+    /// Standard (padded) base64 encoding of the tag
+    pub fn to_base64(&self) -> String {
+        base64::engine::general_purpose::STANDARD.encode(self.0)
+    }
+
+    /// Constant-time comparison against a tag received from a peer.
+    /// Tags of a different length do not match.
+    pub fn ct_eq(&self, other: &[u8]) -> bool {
+        self.0.as_slice().ct_eq(other).into()
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for Tag<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for Tag<N> {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> PartialEq for Tag<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(&other.0)
+    }
+}
+
+impl<const N: usize> Eq for Tag<N> {}
+
+impl<const N: usize> std::fmt::Display for Tag<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl<const N: usize> std::fmt::Debug for Tag<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Tag").field(&self.to_hex()).finish()
+    }
+}
+
+enum HmacInner {
+    Sha256(hmac::Hmac<Sha256>),
+    Sha512(hmac::Hmac<Sha512>),
+}
+
+/// Streaming HMAC computation producing a `N`-byte [`Tag`]; use
+/// [`HmacSha256`] or [`HmacSha512`].
+///
+/// ```ignore
+/// let mut hmac = HmacSha256::new(key);
+/// for chunk in chunks {
+///     hmac.update(chunk);
+/// }
+/// let tag = hmac.finalize();
+/// ```
+pub struct Hmac<const N: usize> {
+    inner: HmacInner,
+}
+
+pub type HmacSha256 = Hmac<32>;
+pub type HmacSha512 = Hmac<64>;
+
+impl HmacSha256 {
+    pub fn new(key: &[u8]) -> Self {
+        Self {
+            // HMAC accepts keys of any length
+            inner: HmacInner::Sha256(hmac::Hmac::new_from_slice(key).unwrap()),
+        }
+    }
+}
+
+impl HmacSha512 {
+    pub fn new(key: &[u8]) -> Self {
+        Self {
+            inner: HmacInner::Sha512(hmac::Hmac::new_from_slice(key).unwrap()),
+        }
+    }
+}
+
+impl<const N: usize> Hmac<N> {
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.inner {
+            HmacInner::Sha256(mac) => mac.update(data),
+            HmacInner::Sha512(mac) => mac.update(data),
+        }
+    }
+
+    /// Builder variant of [`Hmac::update()`]
+    pub fn chain(mut self, data: &[u8]) -> Self {
+        self.update(data);
+        self
+    }
+
+    pub fn finalize(self) -> Tag<N> {
+        let bytes = match self.inner {
+            HmacInner::Sha256(mac) => mac.finalize().into_bytes().to_vec(),
+            HmacInner::Sha512(mac) => mac.finalize().into_bytes().to_vec(),
+        };
+        Tag(bytes
+            .try_into()
+            .expect("HMAC output length matches the tag length"))
+    }
+
+    /// Verifies the `tag` against the HMAC of the data supplied so far
+    /// in constant time.
+    pub fn verify(self, tag: &[u8]) -> bool {
+        self.finalize().ct_eq(tag)
+    }
+}
+
+/// Produces `HMAC-SHA256` tag of the given data.
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Tag256 {
+    HmacSha256::new(key).chain(data).finalize()
+}
+
+/// Produces `HMAC-SHA512` tag of the given data.
+pub fn hmac_sha512(key: &[u8], data: &[u8]) -> Tag512 {
+    HmacSha512::new(key).chain(data).finalize()
+}
+
+/// Verifies the HMAC `tag` of the given data in constant time. The algorithm
+/// is selected by the tag length: 32 bytes for `HMAC-SHA256`, 64 bytes for
+/// `HMAC-SHA512`. Tags of any other length (including truncated tags) are rejected.
+pub fn verify(key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+    match tag.len() {
+        32 => HmacSha256::new(key).chain(data).verify(tag),
+        64 => HmacSha512::new(key).chain(data).verify(tag),
+        _ => false,
+    }
+}
+
+/// Streaming BLAKE3 keyed hash, a faster alternative to [`Hmac`].
+#[cfg(feature = "blake3")]
+#[derive(Clone)]
+pub struct Blake3Keyed {
+    hasher: blake3::Hasher,
+}
+
+#[cfg(feature = "blake3")]
+impl Blake3Keyed {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            hasher: blake3::Hasher::new_keyed(key),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+
+    /// Builder variant of [`Blake3Keyed::update()`]
+    pub fn chain(mut self, data: &[u8]) -> Self {
+        self.update(data);
+        self
+    }
+
+    pub fn finalize(self) -> Tag256 {
+        Tag(*self.hasher.finalize().as_bytes())
+    }
+
+    /// Verifies the `tag` against the keyed hash of the data
+    /// supplied so far in constant time.
+    pub fn verify(self, tag: &[u8]) -> bool {
+        self.finalize().ct_eq(tag)
+    }
+}
+
+/// Produces BLAKE3 keyed hash of the given data.
+#[cfg(feature = "blake3")]
+pub fn blake3_keyed(key: &[u8; 32], data: &[u8]) -> Tag256 {
+    Blake3Keyed::new(key).chain(data).finalize()
+}
+
+/// Verifies the BLAKE3 keyed hash `tag` of the given data in constant time.
+#[cfg(feature = "blake3")]
+pub fn verify_blake3(key: &[u8; 32], data: &[u8], tag: &[u8]) -> bool {
+    Blake3Keyed::new(key).chain(data).verify(tag)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -43,4 +253,130 @@ mod tests {
             "a79b661f0defd1960a4770889e19da0ce2fde1e98ca040f84ab9b2519ca46234"
         );
     }
+
+    // RFC 4231 test cases: (key, data, HMAC-SHA256, HMAC-SHA512)
+    fn rfc4231_vectors() -> Vec<(Vec<u8>, Vec<u8>, &'static str, &'static str)> {
+        vec![
+            (
+                vec![0x0b; 20],
+                b"Hi There".to_vec(),
+                "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+                "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854",
+            ),
+            (
+                b"Jefe".to_vec(),
+                b"what do ya want for nothing?".to_vec(),
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+                "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+            ),
+            (
+                vec![0xaa; 20],
+                vec![0xdd; 50],
+                "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+                "fa73b0089d56a284efb0f0756c890be9b1b5dbdd8ee81a3655f83e33b2279d39bf3e848279a722c806b485a47e67c807b946a337bee8942674278859e13292fb",
+            ),
+            (
+                (0x01..=0x19).collect(),
+                vec![0xcd; 50],
+                "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+                "b0ba465637458c6990e5a8c5f61d4af7e576d97ff94b872de76f8050361ee3dba91ca5c11aa25eb4d679275cc5788063a5f19741120c4f2de2adebeb10a298dd",
+            ),
+            (
+                vec![0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First".to_vec(),
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+                "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f3526b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598",
+            ),
+            (
+                vec![0xaa; 131],
+                b"This is a test using a larger than block-size key and a larger than block-size data. The key needs to be hashed before being used by the HMAC algorithm.".to_vec(),
+                "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+                "e37b6a775dc87dbaa4dfa9f96e5e3ffddebd71f8867289865df5a32d20cdc944b6022cac3c4982b10d5eeb55c3e4de15134676fb6de0446065c97440fa8c6a58",
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_hmac_rfc4231() {
+        for (key, data, sha256, sha512) in rfc4231_vectors() {
+            let tag = hmac_sha256(&key, &data);
+            assert_eq!(tag.to_hex(), sha256);
+            assert!(verify(&key, &data, tag.as_bytes()));
+            let tag = hmac_sha512(&key, &data);
+            assert_eq!(tag.to_string(), sha512);
+            assert!(verify(&key, &data, tag.as_bytes()));
+        }
+
+        // test case 5 (truncation to 128 bits)
+        let key = [0x0c; 20];
+        let data = b"Test With Truncation";
+        assert!(hmac_sha256(&key, data)
+            .to_hex()
+            .starts_with("a3b6167473100ee06e0c796c2955552b"));
+        assert!(hmac_sha512(&key, data)
+            .to_hex()
+            .starts_with("415fad6271580a531d4179bc891d87a6"));
+    }
+
+    #[test]
+    fn test_hmac_incremental() {
+        let key = b"incremental key";
+        let data = (0..1000u32).map(|n| (n % 251) as u8).collect::<Vec<_>>();
+
+        for chunk_size in [1, 7, 64, 128, 333, 1000] {
+            let mut hmac = HmacSha256::new(key);
+            data.chunks(chunk_size).for_each(|chunk| hmac.update(chunk));
+            assert_eq!(hmac.finalize(), hmac_sha256(key, &data));
+
+            let hmac = data
+                .chunks(chunk_size)
+                .fold(HmacSha512::new(key), |hmac, chunk| hmac.chain(chunk));
+            assert_eq!(hmac.finalize(), hmac_sha512(key, &data));
+        }
+    }
+
+    #[test]
+    fn test_hmac_verify() {
+        let key = b"key";
+        let tag = hmac_sha256(key, b"message");
+        assert!(verify(key, b"message", tag.as_ref()));
+        assert!(!verify(key, b"messagE", tag.as_ref()));
+        assert!(!verify(b"kez", b"message", tag.as_ref()));
+        assert!(!verify(key, b"message", &tag.as_ref()[..16]));
+        let mut forged = tag.into_bytes();
+        forged[31] ^= 1;
+        assert!(!verify(key, b"message", &forged));
+        assert!(HmacSha256::new(key)
+            .chain(b"mess")
+            .chain(b"age")
+            .verify(tag.as_ref()));
+    }
+
+    #[test]
+    fn test_tag_encoding() {
+        let tag = Tag::from([0xfb, 0xff, 0x00, 0x10]);
+        assert_eq!(tag.to_hex(), "fbff0010");
+        assert_eq!(tag.to_string(), "fbff0010");
+        assert_eq!(tag.to_base64(), "+/8AEA==");
+        assert_eq!(format!("{tag:?}"), "Tag(\"fbff0010\")");
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn test_blake3_keyed() {
+        let key = [7u8; 32];
+        let data = (0..5000u32).map(|n| n as u8).collect::<Vec<_>>();
+        let tag = blake3_keyed(&key, &data);
+        assert_eq!(tag.as_bytes(), blake3::keyed_hash(&key, &data).as_bytes());
+        assert_ne!(tag, blake3_keyed(&[8u8; 32], &data));
+
+        for chunk_size in [1, 100, 1024, 4096] {
+            let mut hasher = Blake3Keyed::new(&key);
+            data.chunks(chunk_size)
+                .for_each(|chunk| hasher.update(chunk));
+            assert_eq!(hasher.finalize(), tag);
+        }
+        assert!(verify_blake3(&key, &data, tag.as_ref()));
+        assert!(!verify_blake3(&key, &data[1..], tag.as_ref()));
+    }
 }
@@ -0,0 +1,137 @@
+//!
+//! Password-based key derivation using `Argon2id` with tunable parameters.
+//!
+
+use crate::error::Error;
+use crate::imports::*;
+use argon2::{Algorithm, Argon2, Version};
+
+/// Length of the keys produced by [`argon2id()`].
+pub const KEY_LENGTH: usize = 32;
+
+/// Length of the random salt generated for new encryption envelopes.
+pub const SALT_LENGTH: usize = 16;
+
+/// Maximum memory size (256 MiB) accepted by [`argon2id()`].
+pub const MAX_M_COST: u32 = 256 * 1024;
+/// Maximum number of iterations accepted by [`argon2id()`].
+pub const MAX_T_COST: u32 = 16;
+/// Maximum degree of parallelism accepted by [`argon2id()`].
+pub const MAX_P_COST: u32 = 16;
+
+/// `Argon2id` cost parameters.
+///
+/// The [`Default`] values follow the OWASP recommendation for
+/// interactive logins (19 MiB of memory, 2 iterations, 1 lane).
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+pub struct Params {
+    /// Memory size in KiB
+    pub m_cost: u32,
+    /// Number of iterations
+    pub t_cost: u32,
+    /// Degree of parallelism
+    pub p_cost: u32,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            m_cost: argon2::Params::DEFAULT_M_COST,
+            t_cost: argon2::Params::DEFAULT_T_COST,
+            p_cost: argon2::Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl Params {
+    pub fn new(m_cost: u32, t_cost: u32, p_cost: u32) -> Self {
+        Self {
+            m_cost,
+            t_cost,
+            p_cost,
+        }
+    }
+
+    /// Rejects parameters exceeding [`MAX_M_COST`], [`MAX_T_COST`] or [`MAX_P_COST`].
+    /// Parameters are read from the envelope of the data being decrypted,
+    /// allowing crafted data to request an arbitrary amount of memory and CPU.
+    pub fn validate(&self) -> Result<()> {
+        if self.m_cost > MAX_M_COST || self.t_cost > MAX_T_COST || self.p_cost > MAX_P_COST {
+            Err(Error::KdfParamsLimit)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Derives a [`KEY_LENGTH`] byte key from the password and salt using `Argon2id`.
+/// Fails if the parameters exceed the maximum values (see [`Params::validate()`]).
+pub fn argon2id(password: &[u8], salt: &[u8], params: &Params) -> Result<Secret> {
+    params.validate()?;
+    let params = argon2::Params::new(
+        params.m_cost,
+        params.t_cost,
+        params.p_cost,
+        Some(KEY_LENGTH),
+    )?;
+    let mut key = vec![0u8; KEY_LENGTH];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password, salt, &mut key)?;
+    Ok(key.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_argon2id_derivation() -> Result<()> {
+        let password = Secret::from("password");
+        let params = Params::new(256, 1, 1);
+        let a = Secret::derive_key_argon2(&password, b"salt-0123456789a", &params)?;
+        let b = Secret::derive_key_argon2(&password, b"salt-0123456789a", &params)?;
+        let c = Secret::derive_key_argon2(&password, b"salt-0123456789b", &params)?;
+        assert_eq!(a.as_slice(), b.as_slice());
+        assert_ne!(a.as_slice(), c.as_slice());
+        assert_eq!(a.as_slice().len(), KEY_LENGTH);
+        assert!(argon2id(b"password", b"salt-0123456789a", &Params::new(0, 0, 0)).is_err());
+        for params in [
+            Params::new(MAX_M_COST + 1, 1, 1),
+            Params::new(256, MAX_T_COST + 1, 1),
+            Params::new(256, 1, MAX_P_COST + 1),
+        ] {
+            assert!(matches!(
+                argon2id(b"password", b"salt-0123456789a", &params),
+                Err(Error::KdfParamsLimit)
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "slow-tests")]
+    #[test]
+    fn test_argon2id_params_sanity() -> Result<()> {
+        use std::time::Instant;
+
+        let password = b"user_password";
+        let salt = b"0123456789abcdef";
+
+        let start = Instant::now();
+        let weak = argon2id(password, salt, &Params::new(256, 1, 1))?;
+        let weak_elapsed = start.elapsed();
+
+        let start = Instant::now();
+        let default = argon2id(password, salt, &Params::default())?;
+        let default_elapsed = start.elapsed();
+
+        let strong = argon2id(password, salt, &Params::new(64 * 1024, 3, 4))?;
+
+        assert_ne!(weak.as_slice(), default.as_slice());
+        assert_ne!(default.as_slice(), strong.as_slice());
+        // the default parameters must impose a measurable memory-hard cost
+        assert!(default_elapsed > weak_elapsed);
+        Ok(())
+    }
+}
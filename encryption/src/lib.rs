@@ -1,13 +1,21 @@
 mod imports;
 
+pub mod asymmetric;
 pub mod chacha20poly1305;
 pub mod error;
 pub mod hash;
+pub mod kdf;
+pub mod manifest;
 pub mod result;
 pub mod secret;
+pub mod stream;
 
 pub mod prelude {
+    pub use crate::asymmetric::{self, Keypair, PublicKey};
     pub use crate::chacha20poly1305;
     pub use crate::hash::*;
+    pub use crate::kdf::Params;
+    pub use crate::manifest::{Manifest, VerificationReport};
     pub use crate::secret::Secret;
+    pub use crate::stream::{DecryptStream, EncryptStream, StreamOptions};
 }
@@ -0,0 +1,563 @@
+//!
+//! File integrity manifests for directory verification.
+//!
+//! A [`Manifest`] records the relative path, size and hash of every file
+//! within a directory tree, as well as a root hash computed over the entries
+//! sorted by path. The manifest can be persisted using serde or Borsh and
+//! later used to [`verify`](Manifest::verify) a copy of the tree, producing
+//! a [`VerificationReport`] listing missing, extra and mismatched files.
+//!
+//! The root hash can be signed using a shared key ([`Manifest::sign()`]),
+//! producing an `HMAC-SHA256` tag that authenticates the entire tree.
+//!
+//! Files are read in chunks of [`CHUNK_SIZE`] bytes, so the size of the
+//! files does not affect memory usage. Symbolic links to directories are
+//! not followed. On WASM, the manifest is available only under Node.js.
+//!
+
+use crate::error::Error;
+use crate::imports::*;
+use sha2::{Digest as _, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use workflow_core::hex::{FromHex, ToHex};
+
+/// Size of the chunks in which the files are read and hashed.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+const ROOT_DOMAIN: &[u8] = b"workflow-encryption/manifest/v1";
+
+/// Hash algorithm used for the manifest entries and the root hash.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
+/// 32-byte hash of a file or of the manifest root. Serialized
+/// as a hex string in human-readable formats (such as JSON).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, BorshSerialize, BorshDeserialize)]
+pub struct Digest([u8; 32]);
+
+impl Digest {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for Digest {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl AsRef<[u8]> for Digest {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Digest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0.as_slice().to_hex())
+    }
+}
+
+impl std::fmt::Debug for Digest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Digest").field(&self.to_string()).finish()
+    }
+}
+
+impl std::str::FromStr for Digest {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let bytes = Vec::<u8>::from_hex(s).map_err(|err| Error::custom(err.to_string()))?;
+        Ok(Self(bytes.try_into().map_err(|_| {
+            Error::custom(format!("invalid digest length: {s}"))
+        })?))
+    }
+}
+
+impl Serialize for Digest {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string())
+        } else {
+            Serialize::serialize(&self.0, serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Digest {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let hex = <String as Deserialize>::deserialize(deserializer)?;
+            hex.parse().map_err(serde::de::Error::custom)
+        } else {
+            Ok(Self(<[u8; 32] as Deserialize>::deserialize(deserializer)?))
+        }
+    }
+}
+
+enum Hasher {
+    Sha256(Sha256),
+    #[cfg(feature = "blake3")]
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::default()),
+            #[cfg(feature = "blake3")]
+            HashAlgorithm::Blake3 => Hasher::Blake3(Box::default()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha256(hasher) => hasher.update(data),
+            #[cfg(feature = "blake3")]
+            Hasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    fn finalize(self) -> Digest {
+        match self {
+            Hasher::Sha256(hasher) => Digest(hasher.finalize().into()),
+            #[cfg(feature = "blake3")]
+            Hasher::Blake3(hasher) => Digest(*hasher.finalize().as_bytes()),
+        }
+    }
+}
+
+/// Manifest entry describing a single file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct ManifestEntry {
+    /// Path relative to the manifest root, using `/` as the separator
+    pub path: String,
+    /// File size in bytes
+    pub size: u64,
+    /// Hash of the file contents
+    pub hash: Digest,
+}
+
+/// Result of [`Manifest::verify()`]. Each list contains relative
+/// paths (see [`ManifestEntry::path`]) sorted in ascending order.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct VerificationReport {
+    /// Files listed in the manifest that are not present in the tree
+    pub missing: Vec<String>,
+    /// Files present in the tree that are not listed in the manifest
+    pub extra: Vec<String>,
+    /// Files whose size or hash does not match the manifest
+    pub mismatched: Vec<String>,
+}
+
+impl VerificationReport {
+    /// Returns `true` if the tree matches the manifest exactly.
+    pub fn is_valid(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+/// File integrity manifest of a directory tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct Manifest {
+    /// Hash algorithm used for the entries and the root hash
+    pub algorithm: HashAlgorithm,
+    /// Entries sorted by path
+    pub entries: Vec<ManifestEntry>,
+    /// Hash over the sorted entries
+    pub root: Digest,
+    /// `HMAC-SHA256` tag of the root hash (see [`Manifest::sign()`])
+    pub signature: Option<Digest>,
+}
+
+impl Manifest {
+    /// Creates a manifest of all files within `root_path` using [`HashAlgorithm::Sha256`].
+    pub fn create(root_path: impl AsRef<Path>) -> Result<Self> {
+        Self::create_with_algorithm(root_path, HashAlgorithm::default())
+    }
+
+    /// Creates a manifest of all files within `root_path` using the given [`HashAlgorithm`].
+    pub fn create_with_algorithm(
+        root_path: impl AsRef<Path>,
+        algorithm: HashAlgorithm,
+    ) -> Result<Self> {
+        let entries = fs::walk(root_path.as_ref())?
+            .into_iter()
+            .map(|(path, file)| {
+                let (size, hash) = fs::hash_file(&file, algorithm)?;
+                Ok(ManifestEntry { path, size, hash })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let root = root_hash(algorithm, &entries);
+        Ok(Self {
+            algorithm,
+            entries,
+            root,
+            signature: None,
+        })
+    }
+
+    /// Verifies the files within `root_path` against the manifest. Fails
+    /// with [`Error::ManifestRootHash`] if the manifest entries do not
+    /// match the root hash (i.e. the manifest itself has been modified).
+    pub fn verify(&self, root_path: impl AsRef<Path>) -> Result<VerificationReport> {
+        if root_hash(self.algorithm, &self.entries) != self.root {
+            return Err(Error::ManifestRootHash);
+        }
+
+        let mut files = fs::walk(root_path.as_ref())?;
+        let mut report = VerificationReport::default();
+        for entry in self.entries.iter() {
+            match files.remove(&entry.path) {
+                None => report.missing.push(entry.path.clone()),
+                Some(file) => {
+                    let (size, hash) = fs::hash_file(&file, self.algorithm)?;
+                    if size != entry.size || hash != entry.hash {
+                        report.mismatched.push(entry.path.clone());
+                    }
+                }
+            }
+        }
+        report.extra = files.into_keys().collect();
+        report.missing.sort();
+        report.mismatched.sort();
+        Ok(report)
+    }
+
+    /// Signs the root hash with the given key (`HMAC-SHA256`).
+    pub fn sign(&mut self, key: &[u8]) {
+        self.signature = Some(Digest(hmac_sha256(key, self.root.as_ref()).into_bytes()));
+    }
+
+    /// Verifies the signature of the root hash in constant time.
+    /// Returns `false` if the manifest is not signed.
+    pub fn verify_signature(&self, key: &[u8]) -> bool {
+        self.signature.as_ref().is_some_and(|signature| {
+            crate::hash::verify(key, self.root.as_ref(), signature.as_ref())
+        })
+    }
+}
+
+fn root_hash(algorithm: HashAlgorithm, entries: &[ManifestEntry]) -> Digest {
+    let mut sorted = entries.iter().collect::<Vec<_>>();
+    sorted.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut hasher = Hasher::new(algorithm);
+    hasher.update(ROOT_DOMAIN);
+    for entry in sorted {
+        hasher.update(&(entry.path.len() as u64).to_le_bytes());
+        hasher.update(entry.path.as_bytes());
+        hasher.update(&entry.size.to_le_bytes());
+        hasher.update(entry.hash.as_ref());
+    }
+    hasher.finalize()
+}
+
+fn relative_path(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{parent}/{name}")
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod fs {
+    use super::*;
+    use std::fs::File;
+    use std::io::Read;
+    use std::path::PathBuf;
+
+    /// Returns all files within `root` keyed (and sorted) by the relative path.
+    pub fn walk(root: &Path) -> Result<BTreeMap<String, PathBuf>> {
+        let mut files = BTreeMap::new();
+        walk_dir(root, "", &mut files)?;
+        Ok(files)
+    }
+
+    fn walk_dir(dir: &Path, relative: &str, files: &mut BTreeMap<String, PathBuf>) -> Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().into_string().map_err(|name| {
+                Error::custom(format!("invalid file name: {}", name.to_string_lossy()))
+            })?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                walk_dir(&path, &relative_path(relative, &name), files)?;
+            } else if file_type.is_file() || (file_type.is_symlink() && path.is_file()) {
+                files.insert(relative_path(relative, &name), path);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn hash_file(path: &Path, algorithm: HashAlgorithm) -> Result<(u64, Digest)> {
+        let mut file = File::open(path)?;
+        let mut hasher = Hasher::new(algorithm);
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        let mut size = 0u64;
+        loop {
+            let len = file.read(&mut buffer)?;
+            if len == 0 {
+                break;
+            }
+            hasher.update(&buffer[..len]);
+            size += len as u64;
+        }
+        Ok((size, hasher.finalize()))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod fs {
+    use super::*;
+    use js_sys::{Array, Object, Uint8Array};
+    use wasm_bindgen::{JsCast, JsValue};
+    use workflow_node::fs as node;
+
+    fn js_error(err: JsValue) -> Error {
+        Error::custom(format!("{err:?}"))
+    }
+
+    fn join(dir: &str, name: &str) -> String {
+        format!("{}/{name}", dir.trim_end_matches('/'))
+    }
+
+    /// Returns all files within `root` keyed (and sorted) by the relative path.
+    pub fn walk(root: &Path) -> Result<BTreeMap<String, String>> {
+        if !workflow_core::runtime::is_node() {
+            return Err(Error::custom("file manifests require Node.js"));
+        }
+        let mut files = BTreeMap::new();
+        walk_dir(&root.to_string_lossy(), "", &mut files)?;
+        Ok(files)
+    }
+
+    fn walk_dir(dir: &str, relative: &str, files: &mut BTreeMap<String, String>) -> Result<()> {
+        let names: Array = node::readdir_sync_with_options(dir, Object::new()).map_err(js_error)?;
+        for name in names.iter() {
+            let name = name
+                .as_string()
+                .ok_or_else(|| Error::custom("readdirSync: expecting file names"))?;
+            let path = join(dir, &name);
+            let stats = node::stat_sync(&path)
+                .map_err(js_error)?
+                .unchecked_into::<node::Stats>();
+            if stats.is_directory() {
+                // `statSync` follows symbolic links
+                let lstats = node::lstat_sync(&path)
+                    .map_err(js_error)?
+                    .unchecked_into::<node::Stats>();
+                if !lstats.is_symbolic_link() {
+                    walk_dir(&path, &relative_path(relative, &name), files)?;
+                }
+            } else if stats.is_file() {
+                files.insert(relative_path(relative, &name), path);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn hash_file(path: &str, algorithm: HashAlgorithm) -> Result<(u64, Digest)> {
+        let fd = node::open_sync(path, "r").map_err(js_error)?;
+        let result = hash_fd(fd, algorithm);
+        node::close_sync(fd).map_err(js_error)?;
+        result
+    }
+
+    fn hash_fd(fd: u32, algorithm: HashAlgorithm) -> Result<(u64, Digest)> {
+        let mut hasher = Hasher::new(algorithm);
+        let buffer = Uint8Array::new_with_length(CHUNK_SIZE as u32);
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+        let mut size = 0u64;
+        loop {
+            let len = node::read_sync(fd, &buffer, 0, CHUNK_SIZE as u32, None).map_err(js_error)?
+                as usize;
+            if len == 0 {
+                break;
+            }
+            buffer.subarray(0, len as u32).copy_to(&mut chunk[..len]);
+            hasher.update(&chunk[..len]);
+            size += len as u64;
+        }
+        Ok((size, hasher.finalize()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    struct TempTree(PathBuf);
+
+    impl TempTree {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!(
+                "workflow-encryption-manifest-{name}-{}",
+                std::process::id()
+            ));
+            let _ = std::fs::remove_dir_all(&root);
+            std::fs::create_dir_all(root.join("assets/icons")).unwrap();
+            std::fs::write(root.join("index.html"), b"<html></html>").unwrap();
+            std::fs::write(root.join("assets/app.js"), b"console.log('app');").unwrap();
+            std::fs::write(root.join("assets/icons/logo.svg"), b"<svg/>").unwrap();
+            // spans multiple chunks
+            let large = (0..CHUNK_SIZE * 3 + 17)
+                .map(|n| (n % 251) as u8)
+                .collect::<Vec<_>>();
+            std::fs::write(root.join("assets/app.wasm"), large).unwrap();
+            Self(root)
+        }
+
+        fn path(&self, relative: &str) -> PathBuf {
+            self.0.join(relative)
+        }
+    }
+
+    impl Drop for TempTree {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_manifest_create() -> Result<()> {
+        let tree = TempTree::new("create");
+        let manifest = Manifest::create(&tree.0)?;
+
+        let paths = manifest
+            .entries
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "assets/app.js",
+                "assets/app.wasm",
+                "assets/icons/logo.svg",
+                "index.html"
+            ]
+        );
+
+        let wasm = std::fs::read(tree.path("assets/app.wasm"))?;
+        let entry = &manifest.entries[1];
+        assert_eq!(entry.size, wasm.len() as u64);
+        assert_eq!(entry.hash.as_ref(), crate::hash::sha256(&wasm).as_ref());
+
+        // the root hash does not depend on the order of the entries
+        let mut reversed = manifest.entries.clone();
+        reversed.reverse();
+        assert_eq!(root_hash(manifest.algorithm, &reversed), manifest.root);
+        assert!(Manifest::create(&tree.0)?.verify(&tree.0)?.is_valid());
+
+        // serialization
+        let json = serde_json::to_string(&manifest).unwrap();
+        assert!(json.contains(&format!("\"root\":\"{}\"", manifest.root)));
+        assert_eq!(serde_json::from_str::<Manifest>(&json).unwrap(), manifest);
+        let borsh = borsh::to_vec(&manifest)?;
+        assert_eq!(Manifest::try_from_slice(&borsh)?, manifest);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_manifest_verify() -> Result<()> {
+        let tree = TempTree::new("verify");
+        let manifest = Manifest::create(&tree.0)?;
+        assert_eq!(manifest.verify(&tree.0)?, VerificationReport::default());
+
+        // tamper with a single byte of the large file, keeping the size
+        let mut wasm = std::fs::read(tree.path("assets/app.wasm"))?;
+        wasm[CHUNK_SIZE * 2 + 5] ^= 1;
+        std::fs::write(tree.path("assets/app.wasm"), wasm)?;
+        std::fs::remove_file(tree.path("assets/icons/logo.svg"))?;
+        std::fs::write(tree.path("assets/icons/extra.svg"), b"<svg/>")?;
+
+        let report = manifest.verify(&tree.0)?;
+        assert_eq!(
+            report,
+            VerificationReport {
+                missing: vec!["assets/icons/logo.svg".to_string()],
+                extra: vec!["assets/icons/extra.svg".to_string()],
+                mismatched: vec!["assets/app.wasm".to_string()],
+            }
+        );
+        assert!(!report.is_valid());
+
+        // a modified manifest is rejected
+        let mut modified = manifest.clone();
+        modified.entries[1].hash = Manifest::create(&tree.0)?.entries[1].hash;
+        assert!(matches!(
+            modified.verify(&tree.0),
+            Err(Error::ManifestRootHash)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_manifest_signature() -> Result<()> {
+        let tree = TempTree::new("signature");
+        let mut manifest = Manifest::create(&tree.0)?;
+        assert!(!manifest.verify_signature(b"key"));
+
+        manifest.sign(b"key");
+        assert!(manifest.verify_signature(b"key"));
+        assert!(!manifest.verify_signature(b"kez"));
+
+        let json = serde_json::to_string(&manifest).unwrap();
+        let restored = serde_json::from_str::<Manifest>(&json).unwrap();
+        assert!(restored.verify_signature(b"key"));
+
+        let mut forged = manifest.clone();
+        forged.root = Digest::from([0u8; 32]);
+        assert!(!forged.verify_signature(b"key"));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn test_manifest_blake3() -> Result<()> {
+        let tree = TempTree::new("blake3");
+        let manifest = Manifest::create_with_algorithm(&tree.0, HashAlgorithm::Blake3)?;
+        let html = std::fs::read(tree.path("index.html"))?;
+        assert_eq!(
+            manifest.entries[3].hash.as_bytes(),
+            blake3::hash(&html).as_bytes()
+        );
+        assert_ne!(manifest.root, Manifest::create(&tree.0)?.root);
+        assert!(manifest.verify(&tree.0)?.is_valid());
+        Ok(())
+    }
+}
@@ -3,8 +3,13 @@
 //!
 
 use crate::imports::*;
+use subtle::ConstantTimeEq;
 
 /// Secret container for sensitive data. Performs memory zeroization on drop.
+///
+/// Equality comparison is performed in constant time (with respect to the
+/// contents; the length is not considered secret) and the [`Debug`](std::fmt::Debug)
+/// output never includes the secret contents.
 #[derive(Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct Secret(Vec<u8>);
 
@@ -24,8 +29,62 @@ impl Secret {
     pub fn as_slice_mut(&mut self) -> &mut [u8] {
         &mut self.0
     }
+
+    /// Derives a 32-byte key from the password and salt using `Argon2id`
+    /// with the supplied cost [`Params`](crate::kdf::Params).
+    pub fn derive_key_argon2(
+        password: &Secret,
+        salt: &[u8],
+        params: &crate::kdf::Params,
+    ) -> Result<Secret> {
+        crate::kdf::argon2id(password.as_ref(), salt, params)
+    }
+
+    /// Constant-time comparison of the secret contents.
+    pub fn ct_eq(&self, other: &Secret) -> bool {
+        self.0.as_slice().ct_eq(other.0.as_slice()).into()
+    }
+
+    /// Returns `true` if the secret holds no data (for example after
+    /// an explicit [`Zeroize::zeroize()`] call).
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Redacted representation of the secret suitable for logging.
+    pub fn redacted_debug(&self) -> String {
+        format!("{self:?}")
+    }
+
+    /// Best-effort locking of the secret memory into RAM, preventing it
+    /// from being swapped to disk. The lock is released when the returned
+    /// [`LockedSecret`] is dropped (after the contents have been zeroized).
+    #[cfg(all(feature = "mlock", not(target_arch = "wasm32")))]
+    pub fn into_locked(self) -> Result<LockedSecret> {
+        LockedSecret::try_new(self)
+    }
+}
+
+/// Copies `data` into a buffer with capacity for `extra` bytes so that
+/// in-place operations do not reallocate (leaving unwiped copies behind).
+pub(crate) fn sensitive_buffer(data: &[u8], extra: usize) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(data.len() + extra);
+    buffer.extend_from_slice(data);
+    buffer
+}
+
+impl PartialEq for Secret {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other)
+    }
 }
 
+impl Eq for Secret {}
+
 impl AsRef<[u8]> for Secret {
     fn as_ref(&self) -> &[u8] {
         &self.0
@@ -77,3 +136,83 @@ impl std::fmt::Debug for Secret {
             .finish()
     }
 }
+
+/// [`Secret`] whose memory is locked into RAM (`mlock`). Derefs to [`Secret`].
+#[cfg(all(feature = "mlock", not(target_arch = "wasm32")))]
+pub struct LockedSecret {
+    // declared before the guard so that the secret is
+    // zeroized prior to the memory being unlocked
+    secret: Secret,
+    _guard: Option<region::LockGuard>,
+}
+
+#[cfg(all(feature = "mlock", not(target_arch = "wasm32")))]
+impl LockedSecret {
+    fn try_new(secret: Secret) -> Result<Self> {
+        let guard = if secret.is_empty() {
+            None
+        } else {
+            Some(
+                region::lock(secret.0.as_ptr(), secret.0.len())
+                    .map_err(|err| crate::error::Error::custom(format!("mlock: {err}")))?,
+            )
+        };
+        Ok(Self {
+            secret,
+            _guard: guard,
+        })
+    }
+}
+
+#[cfg(all(feature = "mlock", not(target_arch = "wasm32")))]
+impl std::ops::Deref for LockedSecret {
+    type Target = Secret;
+
+    fn deref(&self) -> &Secret {
+        &self.secret
+    }
+}
+
+#[cfg(all(feature = "mlock", not(target_arch = "wasm32")))]
+impl std::fmt::Debug for LockedSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&self.secret, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_debug_redaction() {
+        let secret = Secret::from("hunter2");
+        let debug = format!("{:?}", secret);
+        assert!(!debug.contains("hunter2"));
+        assert_eq!(debug, secret.redacted_debug());
+        assert!(!format!("{:?}", Some(secret.clone())).contains("hunter2"));
+    }
+
+    #[test]
+    fn test_secret_ct_eq() {
+        let a = Secret::from("password");
+        let b = Secret::new(b"password".to_vec());
+        let c = Secret::from("passwore");
+        let d = Secret::from("pass");
+        assert!(a.ct_eq(&b));
+        assert!(!a.ct_eq(&c));
+        assert!(!a.ct_eq(&d));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(a, d);
+    }
+
+    #[test]
+    fn test_secret_zeroize() {
+        let mut secret = Secret::from("password");
+        let clone = secret.clone();
+        secret.zeroize();
+        assert!(secret.is_empty());
+        assert_eq!(clone.as_slice(), b"password");
+    }
+}
@@ -0,0 +1,578 @@
+//!
+//! Streaming `XChaCha20Poly1305` encryption for data that does not fit in memory.
+//!
+//! Data is split into fixed-size chunks, each encrypted with a nonce derived
+//! from a per-stream random prefix and a chunk counter (the STREAM construction).
+//! The final chunk is flagged so that truncation, reordering or removal of
+//! chunks is detected during decryption. The stream layout is:
+//!
+//! ```text
+//! "wfs\0" | version: u8 | kdf: borsh(Kdf) | chunk_size: u32 | nonce: [u8; 19] | chunk* | last chunk
+//! ```
+//!
+//! The header is authenticated as associated data of every chunk.
+//!
+
+use crate::chacha20poly1305::Kdf;
+use crate::error::Error;
+use crate::imports::*;
+use crate::kdf::{Params, SALT_LENGTH};
+use crate::secret::sensitive_buffer;
+use chacha20poly1305::{
+    aead::{
+        rand_core::RngCore,
+        stream::{DecryptorBE32, EncryptorBE32},
+        KeyInit, OsRng,
+    },
+    Key, XChaCha20Poly1305,
+};
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use std::io::{Read, Write};
+
+/// Magic bytes identifying an encrypted stream.
+pub const STREAM_MAGIC: [u8; 4] = *b"wfs\0";
+/// Current stream format version.
+pub const STREAM_VERSION: u8 = 1;
+/// Default plaintext chunk size (1 MiB).
+pub const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
+/// Maximum plaintext chunk size (64 MiB). Each chunk is buffered in memory,
+/// so the chunk size read from the stream header is bounded by this value.
+pub const MAX_CHUNK_SIZE: usize = 64 * 1024 * 1024;
+
+const TAG_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 19;
+// magic | version | kdf (tag | params | salt) | chunk_size | nonce
+const HEADER_LENGTH: usize = 4 + 1 + (1 + 12 + SALT_LENGTH) + 4 + NONCE_LENGTH;
+
+/// Options for [`EncryptStream`] and [`encrypt_stream_async()`].
+#[derive(Debug, Clone)]
+pub struct StreamOptions {
+    /// Plaintext chunk size (at most [`MAX_CHUNK_SIZE`])
+    pub chunk_size: usize,
+    /// Key derivation parameters
+    pub params: Params,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            params: Params::default(),
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct StreamHeader {
+    kdf: Kdf,
+    chunk_size: u32,
+    nonce: [u8; NONCE_LENGTH],
+}
+
+impl StreamHeader {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = STREAM_MAGIC.to_vec();
+        bytes.push(STREAM_VERSION);
+        BorshSerialize::serialize(self, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn from_bytes(bytes: &[u8; HEADER_LENGTH]) -> Result<Self> {
+        let rest = bytes
+            .strip_prefix(&STREAM_MAGIC)
+            .ok_or(Error::InvalidEnvelopeHeader)?;
+        let (&version, mut rest) = rest.split_first().ok_or(Error::InvalidEnvelopeHeader)?;
+        if version != STREAM_VERSION {
+            return Err(Error::UnknownEnvelopeVersion(version));
+        }
+        let header =
+            StreamHeader::deserialize(&mut rest).map_err(|_| Error::InvalidEnvelopeHeader)?;
+        if header.chunk_size == 0 || header.chunk_size as usize > MAX_CHUNK_SIZE {
+            return Err(Error::InvalidEnvelopeHeader);
+        }
+        // reject untrusted key derivation parameters before deriving the key
+        let Kdf::Argon2id { params, .. } = &header.kdf;
+        params.validate()?;
+        Ok(header)
+    }
+
+    fn cipher(&self, secret: &Secret) -> Result<XChaCha20Poly1305> {
+        let private_key_bytes = self.kdf.derive(secret)?;
+        Ok(XChaCha20Poly1305::new(Key::from_slice(
+            private_key_bytes.as_ref(),
+        )))
+    }
+}
+
+/// IO-independent chunk encryptor used by [`EncryptStream`]
+/// and [`encrypt_stream_async()`].
+struct ChunkEncryptor {
+    encryptor: Option<EncryptorBE32<XChaCha20Poly1305>>,
+    header: Vec<u8>,
+    chunk_size: usize,
+}
+
+impl ChunkEncryptor {
+    fn new(secret: &Secret, options: &StreamOptions) -> Result<Self> {
+        let chunk_size = u32::try_from(options.chunk_size)
+            .ok()
+            .filter(|size| *size > 0 && *size as usize <= MAX_CHUNK_SIZE)
+            .ok_or_else(|| Error::custom("invalid stream chunk size"))?;
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; NONCE_LENGTH];
+        OsRng.fill_bytes(&mut nonce);
+
+        let header = StreamHeader {
+            kdf: Kdf::Argon2id {
+                params: options.params,
+                salt,
+            },
+            chunk_size,
+            nonce,
+        };
+        let cipher = header.cipher(secret)?;
+        Ok(Self {
+            encryptor: Some(EncryptorBE32::from_aead(cipher, nonce.as_slice().into())),
+            header: header.to_bytes()?,
+            chunk_size: options.chunk_size,
+        })
+    }
+
+    fn encrypt(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>> {
+        let mut buffer = sensitive_buffer(chunk, TAG_LENGTH);
+        let result = if last {
+            let encryptor = self.encryptor.take().ok_or(Error::StreamFinished)?;
+            encryptor.encrypt_last_in_place(&self.header, &mut buffer)
+        } else {
+            let encryptor = self.encryptor.as_mut().ok_or(Error::StreamFinished)?;
+            encryptor.encrypt_next_in_place(&self.header, &mut buffer)
+        };
+        if let Err(err) = result {
+            buffer.zeroize();
+            return Err(err.into());
+        }
+        Ok(buffer)
+    }
+}
+
+/// IO-independent chunk decryptor used by [`DecryptStream`]
+/// and [`decrypt_stream_async()`].
+struct ChunkDecryptor {
+    decryptor: Option<DecryptorBE32<XChaCha20Poly1305>>,
+    header: Vec<u8>,
+    chunk_size: usize,
+}
+
+impl ChunkDecryptor {
+    fn new(secret: &Secret, header_bytes: &[u8; HEADER_LENGTH]) -> Result<Self> {
+        let header = StreamHeader::from_bytes(header_bytes)?;
+        let cipher = header.cipher(secret)?;
+        Ok(Self {
+            decryptor: Some(DecryptorBE32::from_aead(
+                cipher,
+                header.nonce.as_slice().into(),
+            )),
+            header: header_bytes.to_vec(),
+            chunk_size: header.chunk_size as usize,
+        })
+    }
+
+    /// Size of an encrypted chunk (including the authentication tag).
+    fn encrypted_chunk_size(&self) -> usize {
+        self.chunk_size + TAG_LENGTH
+    }
+
+    fn decrypt(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>> {
+        let mut buffer = chunk.to_vec();
+        if last {
+            let decryptor = self.decryptor.take().ok_or(Error::StreamFinished)?;
+            decryptor.decrypt_last_in_place(&self.header, &mut buffer)?;
+        } else {
+            let decryptor = self.decryptor.as_mut().ok_or(Error::StreamFinished)?;
+            decryptor.decrypt_next_in_place(&self.header, &mut buffer)?;
+        }
+        Ok(buffer)
+    }
+}
+
+/// Encrypting [`Write`] adapter. Plaintext written to this stream is
+/// encrypted in chunks and forwarded to the underlying writer.
+///
+/// [`EncryptStream::finish()`] must be called once all data has been
+/// written; a stream dropped without finishing is detected as truncated
+/// during decryption.
+pub struct EncryptStream<W: Write> {
+    writer: Option<W>,
+    encryptor: ChunkEncryptor,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> EncryptStream<W> {
+    pub fn new(secret: &Secret, writer: W) -> Result<Self> {
+        Self::with_options(secret, writer, &StreamOptions::default())
+    }
+
+    pub fn with_options(secret: &Secret, mut writer: W, options: &StreamOptions) -> Result<Self> {
+        let encryptor = ChunkEncryptor::new(secret, options)?;
+        writer.write_all(&encryptor.header)?;
+        Ok(Self {
+            writer: Some(writer),
+            buffer: Vec::with_capacity(options.chunk_size + 1),
+            encryptor,
+        })
+    }
+
+    fn writer(&mut self) -> Result<&mut W> {
+        self.writer.as_mut().ok_or(Error::StreamFinished)
+    }
+
+    /// Encrypts all buffered full chunks while retaining at least one
+    /// byte so that the last chunk can be flagged in [`finish()`](Self::finish).
+    fn flush_chunks(&mut self) -> Result<()> {
+        let chunk_size = self.encryptor.chunk_size;
+        while self.buffer.len() > chunk_size {
+            let encrypted = self.encryptor.encrypt(&self.buffer[..chunk_size], false)?;
+            self.buffer.drain(..chunk_size);
+            self.writer()?.write_all(&encrypted)?;
+        }
+        Ok(())
+    }
+
+    /// Encrypts the remaining data as the final chunk and returns the writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.flush_chunks()?;
+        let encrypted = self.encryptor.encrypt(&self.buffer, true)?;
+        self.buffer.zeroize();
+        let mut writer = self.writer.take().ok_or(Error::StreamFinished)?;
+        writer.write_all(&encrypted)?;
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+impl<W: Write> Write for EncryptStream<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // never grow the buffer past its initial capacity so that
+        // plaintext is not left behind in reallocated memory
+        let capacity = self.encryptor.chunk_size + 1;
+        let mut written = 0;
+        while written < buf.len() {
+            let len = (capacity - self.buffer.len()).min(buf.len() - written);
+            self.buffer.extend_from_slice(&buf[written..written + len]);
+            written += len;
+            self.flush_chunks().map_err(std::io::Error::other)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer().map_err(std::io::Error::other)?.flush()
+    }
+}
+
+impl<W: Write> Drop for EncryptStream<W> {
+    fn drop(&mut self) {
+        self.buffer.zeroize();
+    }
+}
+
+/// Decrypting [`Read`] adapter over a stream produced by [`EncryptStream`].
+/// Any chunk authentication failure (including truncated, reordered or
+/// missing chunks) results in an [`std::io::ErrorKind::InvalidData`] error.
+pub struct DecryptStream<R: Read> {
+    reader: R,
+    decryptor: ChunkDecryptor,
+    pending: Vec<u8>,
+    plaintext: Vec<u8>,
+    position: usize,
+    finished: bool,
+}
+
+impl<R: Read> DecryptStream<R> {
+    pub fn new(secret: &Secret, mut reader: R) -> Result<Self> {
+        let mut header = [0u8; HEADER_LENGTH];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| Error::InvalidEnvelopeHeader)?;
+        let decryptor = ChunkDecryptor::new(secret, &header)?;
+        Ok(Self {
+            reader,
+            pending: Vec::with_capacity(decryptor.encrypted_chunk_size() + 1),
+            decryptor,
+            plaintext: Vec::new(),
+            position: 0,
+            finished: false,
+        })
+    }
+
+    /// Reads the next encrypted chunk plus one byte of look-ahead
+    /// to determine whether the chunk is the last one.
+    fn next_chunk(&mut self) -> Result<()> {
+        let full = self.decryptor.encrypted_chunk_size();
+        let mut buf = [0u8; 8192];
+        while self.pending.len() <= full {
+            let want = (full + 1 - self.pending.len()).min(buf.len());
+            match self.reader.read(&mut buf[..want]) {
+                Ok(0) => break,
+                Ok(n) => self.pending.extend_from_slice(&buf[..n]),
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        self.plaintext.zeroize();
+        self.position = 0;
+        if self.pending.len() > full {
+            self.plaintext = self.decryptor.decrypt(&self.pending[..full], false)?;
+            self.pending.drain(..full);
+        } else {
+            self.plaintext = self.decryptor.decrypt(&self.pending, true)?;
+            self.pending.clear();
+            self.finished = true;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecryptStream<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.position >= self.plaintext.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.next_chunk()
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        }
+        let len = buf.len().min(self.plaintext.len() - self.position);
+        buf[..len].copy_from_slice(&self.plaintext[self.position..self.position + len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+impl<R: Read> Drop for DecryptStream<R> {
+    fn drop(&mut self) {
+        self.plaintext.zeroize();
+    }
+}
+
+/// Reads until `buffer` holds `len` bytes or the reader is exhausted.
+async fn fill_async<R>(reader: &mut R, buffer: &mut Vec<u8>, len: usize) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut buf = [0u8; 8192];
+    while buffer.len() < len {
+        let want = (len - buffer.len()).min(buf.len());
+        match reader.read(&mut buf[..want]).await? {
+            0 => break,
+            n => buffer.extend_from_slice(&buf[..n]),
+        }
+    }
+    Ok(())
+}
+
+/// Encrypts all data from the async `reader` into the async `writer`.
+/// Suitable for use with async file handles such as `async_std::fs::File`.
+pub async fn encrypt_stream_async<R, W>(
+    secret: &Secret,
+    reader: &mut R,
+    writer: &mut W,
+    options: &StreamOptions,
+) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut encryptor = ChunkEncryptor::new(secret, options)?;
+    writer.write_all(&encryptor.header).await?;
+
+    let chunk_size = encryptor.chunk_size;
+    let mut buffer = Vec::with_capacity(chunk_size + 1);
+    loop {
+        fill_async(reader, &mut buffer, chunk_size + 1).await?;
+        if buffer.len() > chunk_size {
+            let encrypted = encryptor.encrypt(&buffer[..chunk_size], false)?;
+            buffer.drain(..chunk_size);
+            writer.write_all(&encrypted).await?;
+        } else {
+            let encrypted = encryptor.encrypt(&buffer, true)?;
+            buffer.zeroize();
+            writer.write_all(&encrypted).await?;
+            break;
+        }
+    }
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Decrypts a stream produced by [`EncryptStream`] or [`encrypt_stream_async()`]
+/// from the async `reader` into the async `writer`.
+pub async fn decrypt_stream_async<R, W>(
+    secret: &Secret,
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut header = [0u8; HEADER_LENGTH];
+    reader
+        .read_exact(&mut header)
+        .await
+        .map_err(|_| Error::InvalidEnvelopeHeader)?;
+    let mut decryptor = ChunkDecryptor::new(secret, &header)?;
+
+    let full = decryptor.encrypted_chunk_size();
+    let mut buffer = Vec::with_capacity(full + 1);
+    loop {
+        fill_async(reader, &mut buffer, full + 1).await?;
+        let last = buffer.len() <= full;
+        let len = buffer.len().min(full);
+        let mut plaintext = decryptor.decrypt(&buffer[..len], last)?;
+        buffer.drain(..len);
+        writer.write_all(&plaintext).await?;
+        plaintext.zeroize();
+        if last {
+            break;
+        }
+    }
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHUNK_SIZE: usize = 1024;
+
+    fn options() -> StreamOptions {
+        StreamOptions {
+            chunk_size: CHUNK_SIZE,
+            params: Params::new(256, 1, 1),
+        }
+    }
+
+    fn payload() -> Vec<u8> {
+        (0..CHUNK_SIZE * 5 + 123).map(|v| (v % 251) as u8).collect()
+    }
+
+    fn encrypt(secret: &Secret, data: &[u8]) -> Result<Vec<u8>> {
+        let mut stream = EncryptStream::with_options(secret, Vec::new(), &options())?;
+        // write in uneven slices to exercise buffering
+        for slice in data.chunks(700) {
+            stream.write_all(slice)?;
+        }
+        stream.finish()
+    }
+
+    fn decrypt(secret: &Secret, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        let mut stream = DecryptStream::new(secret, data).map_err(std::io::Error::other)?;
+        let mut plaintext = Vec::new();
+        stream.read_to_end(&mut plaintext)?;
+        Ok(plaintext)
+    }
+
+    #[test]
+    fn test_stream_round_trip() -> Result<()> {
+        let secret = Secret::from("password");
+        for len in [0, 1, CHUNK_SIZE, CHUNK_SIZE + 1, payload().len()] {
+            let data = &payload()[..len];
+            let encrypted = encrypt(&secret, data)?;
+            assert_eq!(decrypt(&secret, &encrypted)?, data);
+        }
+        assert!(decrypt(&Secret::from("wrong"), &encrypt(&secret, &payload())?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_tampering() -> Result<()> {
+        let secret = Secret::from("password");
+        let encrypted = encrypt(&secret, &payload())?;
+        let chunk = CHUNK_SIZE + TAG_LENGTH;
+        let chunk_at = |idx: usize| HEADER_LENGTH + idx * chunk..HEADER_LENGTH + (idx + 1) * chunk;
+
+        // truncated at a chunk boundary (final chunk missing)
+        let truncated = &encrypted[..HEADER_LENGTH + 5 * chunk];
+        assert!(decrypt(&secret, truncated).is_err());
+
+        // truncated mid-chunk
+        assert!(decrypt(&secret, &encrypted[..encrypted.len() - 1]).is_err());
+
+        // reordered chunks
+        let mut reordered = encrypted[..HEADER_LENGTH].to_vec();
+        reordered.extend_from_slice(&encrypted[chunk_at(1)]);
+        reordered.extend_from_slice(&encrypted[chunk_at(0)]);
+        reordered.extend_from_slice(&encrypted[chunk_at(2).start..]);
+        assert!(decrypt(&secret, &reordered).is_err());
+
+        // missing chunk
+        let mut missing = encrypted[..HEADER_LENGTH].to_vec();
+        missing.extend_from_slice(&encrypted[chunk_at(1).start..]);
+        assert!(decrypt(&secret, &missing).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_header_limits() -> Result<()> {
+        let secret = Secret::from("password");
+        let encrypted = encrypt(&secret, &payload())?;
+        let chunk_size_at = HEADER_LENGTH - NONCE_LENGTH - 4..HEADER_LENGTH - NONCE_LENGTH;
+        let m_cost_at = 4 + 1 + 1..4 + 1 + 1 + 4;
+
+        // oversized chunk size is rejected before any buffer is allocated
+        let mut crafted = encrypted.clone();
+        crafted[chunk_size_at].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            DecryptStream::new(&secret, crafted.as_slice()),
+            Err(Error::InvalidEnvelopeHeader)
+        ));
+
+        // oversized key derivation parameters are rejected before deriving
+        let mut crafted = encrypted.clone();
+        crafted[m_cost_at].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            DecryptStream::new(&secret, crafted.as_slice()),
+            Err(Error::KdfParamsLimit)
+        ));
+
+        let options = StreamOptions {
+            chunk_size: MAX_CHUNK_SIZE + 1,
+            ..options()
+        };
+        assert!(EncryptStream::with_options(&secret, Vec::new(), &options).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_async_round_trip() -> Result<()> {
+        futures::executor::block_on(async {
+            let secret = Secret::from("password");
+            let data = payload();
+
+            let mut encrypted = futures::io::Cursor::new(Vec::new());
+            encrypt_stream_async(&secret, &mut data.as_slice(), &mut encrypted, &options()).await?;
+            let encrypted = encrypted.into_inner();
+
+            // interoperable with the blocking adapters
+            assert_eq!(decrypt(&secret, &encrypted)?, data);
+
+            let mut decrypted = futures::io::Cursor::new(Vec::new());
+            decrypt_stream_async(&secret, &mut encrypted.as_slice(), &mut decrypted).await?;
+            assert_eq!(decrypted.into_inner(), data);
+
+            let truncated = &encrypted[..encrypted.len() - 1];
+            let mut sink = futures::io::Cursor::new(Vec::new());
+            assert!(
+                decrypt_stream_async(&secret, &mut &truncated[..], &mut sink)
+                    .await
+                    .is_err()
+            );
+            Ok::<(), Error>(())
+        })
+    }
+}
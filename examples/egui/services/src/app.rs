@@ -0,0 +1,110 @@
+use async_trait::async_trait;
+use futures::{select, FutureExt};
+use std::sync::Arc;
+use std::time::Duration;
+use workflow_core::channel::DuplexChannel;
+use workflow_core::task::{sleep, spawn};
+use workflow_egui::eframe;
+use workflow_egui::egui;
+use workflow_egui::runtime::services::BridgeState;
+use workflow_egui::runtime::{attach_services, ServiceBridge};
+use workflow_log::*;
+use workflow_service::prelude::*;
+
+// Mock sync engine: reports its health periodically
+// and flushes its state before terminating
+pub struct SyncService {
+    shutdown: DuplexChannel,
+}
+
+impl SyncService {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            shutdown: DuplexChannel::oneshot(),
+        })
+    }
+}
+
+#[async_trait]
+impl Service for SyncService {
+    fn name(&self) -> &'static str {
+        "sync"
+    }
+
+    async fn spawn(self: Arc<Self>, runtime: Runtime) -> ServiceResult<()> {
+        spawn(async move {
+            let mut healthy = true;
+            loop {
+                select! {
+                    _ = sleep(Duration::from_secs(3)).fuse() => {
+                        healthy = !healthy;
+                        let reason = (!healthy).then(|| "peer unreachable".to_string());
+                        runtime.record_event(self.name(), EventKind::Health { healthy, reason });
+                    },
+                    _ = self.shutdown.request.receiver.recv().fuse() => break,
+                }
+            }
+
+            log_info!("sync: flushing state...");
+            sleep(Duration::from_secs(1)).await;
+            log_info!("sync: terminated");
+            self.shutdown.response.sender.send(()).await.unwrap();
+        });
+        Ok(())
+    }
+
+    fn terminate(self: Arc<Self>) {
+        self.shutdown.request.sender.try_send(()).unwrap();
+    }
+
+    async fn join(self: Arc<Self>) -> ServiceResult<()> {
+        self.shutdown.response.receiver.recv().await.unwrap();
+        Ok(())
+    }
+}
+
+pub struct ExampleApp {
+    services: ServiceBridge,
+}
+
+impl eframe::App for ExampleApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // veto the window close until the services have terminated
+        self.services.handle_close_request(ctx);
+
+        egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
+            self.services.status_ui(ui);
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("workflow-egui services");
+            match self.services.state() {
+                BridgeState::Running => ui.label("Close the window to shut down the services."),
+                BridgeState::ShuttingDown => ui.label("Shutting down services..."),
+                BridgeState::Terminated => ui.label("Services terminated."),
+            };
+        });
+    }
+}
+
+pub fn main() -> eframe::Result<()> {
+    // log service lifecycle transitions to stdout
+    workflow_service::debug::enable(true);
+
+    let runtime = Runtime::default();
+    runtime.bind(SyncService::new());
+
+    // the window is closed only after the services have terminated,
+    // so `run_native()` returns once the shutdown is complete
+    eframe::run_native(
+        "workflow-egui services",
+        eframe::NativeOptions::default(),
+        Box::new(move |cc| {
+            let services = attach_services(cc, &runtime);
+            Ok(Box::new(ExampleApp { services }))
+        }),
+    )?;
+
+    log_info!("bye!");
+    Ok(())
+}
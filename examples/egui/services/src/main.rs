@@ -0,0 +1,14 @@
+#[cfg(not(target_arch = "wasm32"))]
+mod app;
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::main]
+async fn main() {
+    if let Err(err) = app::main() {
+        workflow_log::log_error!("{err}");
+    }
+}
+
+// suppress build errors for wasm32
+#[cfg(target_arch = "wasm32")]
+fn main() {}
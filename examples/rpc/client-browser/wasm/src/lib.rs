@@ -1,8 +1,12 @@
 use rpc_example_client_common::client_example;
+use rpc_example_messages::TestOps;
 use std::{sync::Arc, time::Duration};
 use wasm_bindgen::prelude::*;
 use workflow_log::*;
 
+// JS facade of the wRPC client (`new TestClient({ url, encoding })`)
+workflow_rpc::rpc_client_bindings!(TestClient, TestOps);
+
 // A log sink to dump logs to screen
 struct Sink;
 impl workflow_log::Sink for Sink {
@@ -36,3 +40,59 @@ pub async fn main() -> Result<(), String> {
 
     Ok(())
 }
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::TestClient;
+    use js_sys::{Function, Promise, JSON};
+    use wasm_bindgen::prelude::*;
+    use wasm_bindgen_futures::JsFuture;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    /// Exercises the JS API of the `TestClient` instance passed as `rpc`
+    const SCRIPT: &str = r#"
+        return (async () => {
+            const connected = new Promise((resolve) => rpc.addEventListener("connect", resolve));
+            const notification = new Promise((resolve) => rpc.subscribe("Notify", resolve));
+            await rpc.connect({ blockAsyncConnect : true, strategy : "fallback" });
+            await connected;
+            const even = await rpc.call("EvenOdd", { v : 42 });
+            const increase = await rpc.call("Increase", { v : 1 });
+            let unknown;
+            try {
+                await rpc.call("Unknown", { v : 1 });
+            } catch (err) {
+                unknown = err;
+            }
+            const notify = await notification;
+            const isConnected = rpc.isConnected;
+            await rpc.disconnect();
+            return {
+                even,
+                increase,
+                unknown : unknown instanceof Error ? unknown.name : null,
+                notify : "Seq" in notify,
+                isConnected,
+            };
+        })();
+    "#;
+
+    #[wasm_bindgen_test]
+    #[ignore = "requires the example server (`cargo run -p rpc-example-server -- --json`)"]
+    async fn test_js_facade() {
+        let args = js_sys::Object::new();
+        js_sys::Reflect::set(&args, &"url".into(), &"ws://127.0.0.1:9292".into()).unwrap();
+        js_sys::Reflect::set(&args, &"encoding".into(), &"json".into()).unwrap();
+        let rpc: JsValue = TestClient::new(args.unchecked_into()).unwrap().into();
+
+        let script = Function::new_with_args("rpc", SCRIPT);
+        let promise: Promise = script.call1(&JsValue::UNDEFINED, &rpc).unwrap().into();
+        let result = JsFuture::from(promise).await.unwrap();
+        assert_eq!(
+            JSON::stringify(&result).unwrap().as_string().unwrap(),
+            r#"{"even":{"Even":42},"increase":{"Increase":101},"unknown":"UnknownOp","notify":true,"isConnected":true}"#
+        );
+    }
+}
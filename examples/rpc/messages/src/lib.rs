@@ -1,9 +1,19 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
+use workflow_core::enums::Describe;
 
 /// RPC operations (methods and notifications)
 #[derive(
-    Clone, Debug, Eq, PartialEq, Hash, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+    Describe,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
 )]
 pub enum TestOps {
     Notify,
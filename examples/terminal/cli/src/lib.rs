@@ -2,21 +2,167 @@
 use async_trait::async_trait;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use workflow_core::time::HumanDuration;
 use workflow_terminal::Terminal;
 // use workflow_terminal::Options;
 use workflow_log::*;
 use workflow_terminal::parse;
 use workflow_terminal::Cli;
+use workflow_terminal::CliArgs;
 use workflow_terminal::Result;
+use workflow_terminal::{Context, Handler, HandlerCli};
+
+#[derive(CliArgs)]
+struct SleepArgs {
+    #[arg(help = "sleep duration, e.g. 250ms or 1m30s (default 5s)")]
+    duration: Option<HumanDuration>,
+}
+
+#[derive(CliArgs)]
+struct AskArgs {
+    #[arg(short, long, help = "do not echo the input")]
+    secret: bool,
+    #[arg(help = "prompt text")]
+    prompt: Vec<String>,
+}
+
+#[derive(Default, Handler)]
+#[help(summary = "simple text output", aliases = ["hi"])]
+struct Hello;
+
+impl Hello {
+    async fn main(
+        self: Arc<Self>,
+        ctx: &Arc<dyn Context>,
+        _argv: Vec<String>,
+        _cmd: &str,
+    ) -> Result<()> {
+        ctx.term().writeln("hello back to you!");
+        Ok(())
+    }
+}
+
+#[derive(Default, Handler)]
+#[help("list command history")]
+struct History;
+
+impl History {
+    async fn main(
+        self: Arc<Self>,
+        ctx: &Arc<dyn Context>,
+        _argv: Vec<String>,
+        _cmd: &str,
+    ) -> Result<()> {
+        let term = ctx.term();
+        for line in term.history().iter() {
+            term.writeln(line);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default, Handler)]
+#[help("log_trace!() macro output")]
+struct Test;
+
+impl Test {
+    async fn main(
+        self: Arc<Self>,
+        _ctx: &Arc<dyn Context>,
+        _argv: Vec<String>,
+        _cmd: &str,
+    ) -> Result<()> {
+        log_trace!("log_trace!() macro test");
+        Ok(())
+    }
+}
+
+#[derive(Default, Handler)]
+#[help(
+    summary = "sleep (5s by default)",
+    description = "Suspends the command processing for the given duration. Press Ctrl+C to abort."
+)]
+#[args(SleepArgs)]
+struct Sleep;
+
+impl Sleep {
+    async fn main(self: Arc<Self>, _ctx: &Arc<dyn Context>, args: SleepArgs) -> Result<()> {
+        let duration = args
+            .duration
+            .unwrap_or(HumanDuration(Duration::from_secs(5)));
+        log_trace!("start sleep ({duration})");
+        workflow_core::task::sleep(*duration).await;
+        log_trace!("finish sleep");
+        Ok(())
+    }
+}
+
+#[derive(Default, Handler)]
+#[help(
+    summary = "ask user for text input",
+    description = "Displays the prompt (\"Enter something:\" by default) and logs the text entered by the user."
+)]
+#[args(AskArgs)]
+struct Ask;
+
+impl Ask {
+    async fn main(self: Arc<Self>, ctx: &Arc<dyn Context>, args: AskArgs) -> Result<()> {
+        let prompt = if args.prompt.is_empty() {
+            "Enter something:".to_string()
+        } else {
+            args.prompt.join(" ")
+        };
+        let text = ctx.term().ask(args.secret, &prompt).await?;
+        log_info!("You have entered something: {}", text);
+        Ok(())
+    }
+}
+
+#[derive(Default, Handler)]
+#[help("ask user for password text input (no echo)")]
+struct Pass;
+
+impl Pass {
+    async fn main(
+        self: Arc<Self>,
+        ctx: &Arc<dyn Context>,
+        _argv: Vec<String>,
+        _cmd: &str,
+    ) -> Result<()> {
+        let text = ctx.term().ask(true, "Enter something:").await?;
+        log_info!("You have entered something: {}", text);
+        Ok(())
+    }
+}
+
+#[derive(Default, Handler)]
+#[help(summary = "exit terminal", aliases = ["quit"])]
+struct Exit;
+
+impl Exit {
+    async fn main(
+        self: Arc<Self>,
+        ctx: &Arc<dyn Context>,
+        _argv: Vec<String>,
+        _cmd: &str,
+    ) -> Result<()> {
+        let term = ctx.term();
+        term.writeln("bye!");
+        term.exit().await;
+        Ok(())
+    }
+}
 
 struct ExampleCli {
     term: Arc<Mutex<Option<Arc<Terminal>>>>,
+    handlers: HandlerCli,
 }
 
 impl ExampleCli {
     fn new() -> Self {
         ExampleCli {
             term: Arc::new(Mutex::new(None)),
+            handlers: HandlerCli::new(),
         }
     }
 
@@ -25,6 +171,12 @@ impl ExampleCli {
     }
 }
 
+impl Context for ExampleCli {
+    fn term(&self) -> Arc<Terminal> {
+        self.term().expect("terminal is not initialized")
+    }
+}
+
 impl workflow_log::Sink for ExampleCli {
     fn write(&self, _target: Option<&str>, _level: Level, args: &std::fmt::Arguments<'_>) -> bool {
         // note, the terminal may not be initialized
@@ -45,61 +197,24 @@ impl workflow_log::Sink for ExampleCli {
 impl Cli for ExampleCli {
     fn init(self: Arc<Self>, term: &Arc<Terminal>) -> Result<()> {
         *self.term.lock().unwrap() = Some(term.clone());
-        Ok(())
-    }
 
-    async fn digest(self: Arc<Self>, term: Arc<Terminal>, cmd: String) -> Result<()> {
-        let argv = parse(&cmd);
-        match argv[0].as_str() {
-            "help" => {
-                let commands = [
-                    "help - this list",
-                    "hello - simple text output",
-                    "test - log_trace!() macro output",
-                    "history - list command history",
-                    "sleep - sleep for 5 seconds",
-                    "ask - ask user for text input (with echo)",
-                    "pass - ask user for password text input (no echo)",
-                    "exit - exit terminal",
-                ];
-                term.writeln("\n\rCommands:\n\r");
-                term.writeln("\t".to_string() + &commands.join("\n\r\t") + "\n\r");
-            }
-            "hello" => {
-                term.writeln("hello back to you!");
-            }
-            "history" => {
-                let history = term.history();
-                for line in history.iter() {
-                    term.writeln(line);
-                }
-            }
-            "test" => {
-                log_trace!("log_trace!() macro test");
-            }
-            "sleep" => {
-                log_trace!("start sleep (5 sec)");
-                workflow_core::task::sleep(Duration::from_millis(5000)).await;
-                log_trace!("finish sleep");
-            }
-            "ask" => {
-                let text = term.ask(false, "Enter something:").await?;
-                log_info!("You have entered something: {}", text);
-            }
-            "pass" => {
-                let text = term.ask(true, "Enter something:").await?;
-                log_info!("You have entered something: {}", text);
-            }
-            "exit" => {
-                term.writeln("bye!");
-                term.exit().await;
-            }
-            _ => return Err(format!("command not found: {cmd}").into()),
-        }
+        // `help` is provided by the HandlerCli, generated
+        // from the metadata of the registered handlers
+        self.handlers.register(&self, Hello);
+        self.handlers.register(&self, History);
+        self.handlers.register(&self, Test);
+        self.handlers.register(&self, Sleep);
+        self.handlers.register(&self, Ask);
+        self.handlers.register(&self, Pass);
+        self.handlers.register(&self, Exit);
 
         Ok(())
     }
 
+    async fn digest(self: Arc<Self>, _term: Arc<Terminal>, cmd: String) -> Result<()> {
+        self.handlers.execute(&self, &cmd).await
+    }
+
     async fn complete(
         self: Arc<Self>,
         _term: Arc<Terminal>,
@@ -141,3 +256,104 @@ pub async fn example_terminal() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::Ordering;
+    use workflow_terminal::clear::ClearLine;
+    use workflow_terminal::Options;
+
+    async fn headless(cols: usize) -> Arc<Terminal> {
+        let cli = Arc::new(ExampleCli::new());
+        let term = Arc::new(
+            Terminal::try_new_with_options(cli, Options::new().with_headless(true)).unwrap(),
+        );
+        term.para_width.store(cols, Ordering::SeqCst);
+        term.init().await.unwrap();
+        term
+    }
+
+    /// Executes `cmd`, returning the lines written by the command
+    /// (without the prompt re-rendered after each line)
+    async fn run(term: &Arc<Terminal>, cmd: &str) -> Vec<String> {
+        let start = term.headless_output().unwrap().len();
+        term.exec(cmd).await.unwrap();
+        let output = term.headless_output().unwrap()[start..].to_string();
+        let clear = ClearLine.to_string();
+        let mut lines = output
+            .split("\n\r")
+            .map(|line| line.rsplit(clear.as_str()).next().unwrap().to_string())
+            .collect::<Vec<_>>();
+        // prompt rendered after the command
+        lines.pop();
+        lines
+    }
+
+    #[tokio::test]
+    async fn test_help() {
+        let term = headless(80).await;
+        assert_eq!(
+            run(&term, "help").await,
+            [
+                "",
+                "    ask       ask user for text input",
+                "    exit      exit terminal",
+                "    hello     simple text output",
+                "    help      display commands or `help <command>` for details",
+                "    history   list command history",
+                "    pass      ask user for password text input (no echo)",
+                "    sleep     sleep (5s by default)",
+                "    test      log_trace!() macro output",
+                "",
+            ]
+        );
+
+        assert_eq!(
+            run(&term, "help hi").await,
+            [
+                "",
+                "    hello - simple text output",
+                "",
+                "    Usage: hello",
+                "    Aliases: hi",
+                "",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_help_command() {
+        let term = headless(48).await;
+        assert_eq!(
+            run(&term, "help ask").await,
+            [
+                "",
+                "    ask - ask user for text input",
+                "",
+                "    Usage: ask [-s|--secret] [<prompt>...]",
+                "      -s, --secret    do not echo the input",
+                "      <prompt>        prompt text",
+                "",
+                "    Displays the prompt (\"Enter something:\" by",
+                "    default) and logs the text entered by the",
+                "    user.",
+                "",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_command_not_found() {
+        let term = headless(80).await;
+        assert_eq!(
+            run(&term, "helo").await,
+            ["command not found: helo (did you mean `hello`?)"]
+        );
+        assert_eq!(run(&term, "xyz").await, ["command not found: xyz"]);
+        assert_eq!(
+            run(&term, "help slep").await,
+            ["command not found: slep (did you mean `sleep`?)"]
+        );
+    }
+}
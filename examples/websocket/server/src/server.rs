@@ -5,7 +5,7 @@ use std::sync::Arc;
 // use tungstenite::Message;
 use workflow_log::*;
 use workflow_websocket::server::{
-    Message, Result, WebSocketHandler, WebSocketReceiver, WebSocketSender, WebSocketServer,
+    Message, Result, Router, WebSocketHandler, WebSocketReceiver, WebSocketSender, WebSocketServer,
     WebSocketSink,
 };
 
@@ -15,7 +15,37 @@ pub struct MyContext {
 }
 
 // A simple WebSocket handler struct
-pub struct MyWsHandler;
+// dispatching messages using the router
+pub struct MyWsHandler {
+    router: Router<Arc<MyContext>>,
+}
+
+impl MyWsHandler {
+    // echo text and binary messages
+    // while logging the ip address and received data
+    pub fn new() -> Self {
+        let router = Router::new()
+            .on_text(
+                "",
+                |ctx: Arc<MyContext>, text, sink: WebSocketSink| async move {
+                    log_trace!("[{}] {}", ctx.peer, text);
+                    sink.send(Message::Text(text))?;
+                    Ok(())
+                },
+            )
+            .on_binary(
+                [],
+                |ctx: Arc<MyContext>, data, sink: WebSocketSink| async move {
+                    log_trace!("[{}] {:?}", ctx.peer, data);
+                    sink.send(Message::Binary(data))?;
+                    Ok(())
+                },
+            )
+            .with_unhandled_logging(true);
+
+        MyWsHandler { router }
+    }
+}
 
 #[async_trait]
 impl WebSocketHandler for MyWsHandler {
@@ -39,26 +69,14 @@ impl WebSocketHandler for MyWsHandler {
         Ok(Arc::new(ctx))
     }
 
-    // receive and echo text and binary messages
-    // while logging the ip address and received data
+    // dispatch text and binary messages to the router
     async fn message(
         self: &Arc<Self>,
         ctx: &Self::Context,
         msg: Message,
         sink: &WebSocketSink,
     ) -> Result<()> {
-        match &msg {
-            Message::Binary(data) => {
-                log_trace!("[{}] {:?}", ctx.peer, data);
-                sink.send(msg)?;
-            }
-            Message::Text(text) => {
-                log_trace!("[{}] {}", ctx.peer, text);
-                sink.send(msg)?;
-            }
-            _ => {}
-        }
-        Ok(())
+        self.router.route(ctx, msg, sink).await
     }
 }
 
@@ -80,7 +98,7 @@ pub async fn server_example() -> Result<()> {
     log_info!("WebSocket server is listening on {}", addr);
 
     // create our handler instance
-    let handler = Arc::new(MyWsHandler {});
+    let handler = Arc::new(MyWsHandler::new());
     // create websocket server and install our handler in it
     let ws = WebSocketServer::<MyWsHandler>::new(handler, None);
     // listen for incoming connections
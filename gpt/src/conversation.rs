@@ -0,0 +1,358 @@
+//!
+//! [`Conversation`] tracking chat history within a token budget.
+//!
+
+use crate::imports::*;
+use std::path::Path;
+
+/// Default context budget in tokens.
+pub const DEFAULT_CONTEXT_BUDGET: usize = 4096;
+
+/// Estimates the number of tokens consumed by text.
+pub trait TokenEstimator: Send + Sync {
+    fn estimate(&self, text: &str) -> usize;
+
+    /// Tokens consumed by a message, including the per-message overhead.
+    fn estimate_message(&self, message: &ChatMessage) -> usize {
+        self.estimate(&message.content) + 4
+    }
+}
+
+/// Heuristic estimator assuming ~4 characters per token,
+/// which approximates OpenAI tokenizers for English text.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HeuristicEstimator;
+
+impl TokenEstimator for HeuristicEstimator {
+    fn estimate(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(4)
+    }
+}
+
+/// Policy applied when the conversation exceeds its context budget.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TruncationPolicy {
+    /// Discard the oldest messages.
+    #[default]
+    DropOldest,
+    /// Replace the oldest messages with a model-generated summary.
+    /// Falls back to [`TruncationPolicy::DropOldest`] if the summary
+    /// request fails or the summary does not fit the budget.
+    Summarize,
+}
+
+fn default_estimator() -> Arc<dyn TokenEstimator> {
+    Arc::new(HeuristicEstimator)
+}
+
+/// Chat history consisting of an optional system prompt, an optional
+/// summary of truncated messages and the conversation turns. Before each
+/// request the oldest turns are truncated (or summarized) so that the
+/// request fits the context budget; the system prompt and the most
+/// recent message are always retained.
+///
+/// ```ignore
+/// let mut conversation = Conversation::new(&gpt)
+///     .with_system_prompt("You are a helpful assistant.")
+///     .with_budget(8192);
+/// let reply = conversation.send("Hello!").await?;
+/// conversation.save("~/.myapp/conversation.json").await?;
+/// ```
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    system_prompt: Option<String>,
+    summary: Option<String>,
+    turns: Vec<ChatMessage>,
+    budget: usize,
+    policy: TruncationPolicy,
+    usage: Usage,
+    #[serde(skip, default = "default_estimator")]
+    estimator: Arc<dyn TokenEstimator>,
+    #[serde(skip)]
+    client: Option<ChatGPT>,
+}
+
+impl Default for Conversation {
+    fn default() -> Self {
+        Self {
+            system_prompt: None,
+            summary: None,
+            turns: Vec::new(),
+            budget: DEFAULT_CONTEXT_BUDGET,
+            policy: TruncationPolicy::default(),
+            usage: Usage::default(),
+            estimator: default_estimator(),
+            client: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for Conversation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Conversation")
+            .field("system_prompt", &self.system_prompt)
+            .field("summary", &self.summary)
+            .field("turns", &self.turns)
+            .field("budget", &self.budget)
+            .field("policy", &self.policy)
+            .field("usage", &self.usage)
+            .finish()
+    }
+}
+
+impl Conversation {
+    pub fn new(client: &ChatGPT) -> Self {
+        Self::default().with_client(client)
+    }
+
+    /// Sets the client; required after deserialization.
+    pub fn with_client(mut self, client: &ChatGPT) -> Self {
+        self.client = Some(client.clone());
+        self
+    }
+
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Context budget in tokens, including the system prompt.
+    pub fn with_budget(mut self, budget: usize) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: TruncationPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_estimator(mut self, estimator: Arc<dyn TokenEstimator>) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    pub fn system_prompt(&self) -> Option<&str> {
+        self.system_prompt.as_deref()
+    }
+
+    pub fn summary(&self) -> Option<&str> {
+        self.summary.as_deref()
+    }
+
+    pub fn turns(&self) -> &[ChatMessage] {
+        &self.turns
+    }
+
+    /// Accumulated token usage reported by the API.
+    pub fn usage(&self) -> Usage {
+        self.usage
+    }
+
+    pub fn push(&mut self, message: ChatMessage) {
+        self.turns.push(message);
+    }
+
+    pub fn clear(&mut self) {
+        self.summary = None;
+        self.turns.clear();
+    }
+
+    /// Messages sent with the next request.
+    pub fn messages(&self) -> Vec<ChatMessage> {
+        self.system_prompt
+            .iter()
+            .map(ChatMessage::system)
+            .chain(self.summary.iter().map(|summary| {
+                ChatMessage::system(format!("Summary of the earlier conversation: {summary}"))
+            }))
+            .chain(self.turns.iter().cloned())
+            .collect()
+    }
+
+    /// Estimated number of tokens of [`Conversation::messages()`].
+    pub fn estimate_tokens(&self) -> usize {
+        self.messages()
+            .iter()
+            .map(|message| self.estimator.estimate_message(message))
+            .sum()
+    }
+
+    /// Removes the oldest turns until the conversation fits the budget,
+    /// retaining the most recent message. Returns the removed messages.
+    pub fn truncate(&mut self) -> Vec<ChatMessage> {
+        let mut removed = Vec::new();
+        while self.turns.len() > 1 && self.estimate_tokens() > self.budget {
+            removed.push(self.turns.remove(0));
+        }
+        removed
+    }
+
+    async fn fit(&mut self) -> Result<()> {
+        let removed = self.truncate();
+        if removed.is_empty() || self.policy != TruncationPolicy::Summarize {
+            return Ok(());
+        }
+
+        let previous = self.summary.take();
+        let transcript = previous
+            .iter()
+            .map(|summary| format!("Earlier summary: {summary}"))
+            .chain(removed.iter().map(|message| {
+                let role = match message.role {
+                    Role::System => "system",
+                    Role::User => "user",
+                    Role::Assistant => "assistant",
+                };
+                format!("{role}: {}", message.content)
+            }))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let request = ChatRequest::new(vec![
+            ChatMessage::system(
+                "Summarize the following conversation concisely, preserving facts and decisions.",
+            ),
+            ChatMessage::user(transcript),
+        ]);
+
+        match self.client()?.complete(&request).await {
+            Ok(completion) => {
+                self.usage += completion.usage;
+                self.summary = Some(completion.text);
+                // the summary must fit as well
+                self.truncate();
+                if self.estimate_tokens() > self.budget {
+                    self.summary = None;
+                }
+            }
+            Err(_) => {
+                self.summary = previous;
+                self.truncate();
+            }
+        }
+        Ok(())
+    }
+
+    fn client(&self) -> Result<ChatGPT> {
+        self.client
+            .clone()
+            .ok_or_else(|| Error::Custom("Conversation client is not set".to_string()))
+    }
+
+    /// Sends `text` as a user message and appends the reply. If the
+    /// request fails, the user message is removed from the conversation.
+    pub async fn send(&mut self, text: impl Into<String>) -> Result<String> {
+        let client = self.client()?;
+        let snapshot = (self.summary.clone(), self.turns.clone());
+        self.turns.push(ChatMessage::user(text));
+        if let Err(err) = self.fit().await {
+            (self.summary, self.turns) = snapshot;
+            return Err(err);
+        }
+
+        match client.complete(&ChatRequest::new(self.messages())).await {
+            Ok(completion) => {
+                self.usage += completion.usage;
+                self.turns
+                    .push(ChatMessage::assistant(completion.text.clone()));
+                Ok(completion.text)
+            }
+            Err(err) => {
+                self.turns.pop();
+                Err(err)
+            }
+        }
+    }
+
+    /// Stores the conversation via [`workflow_store::fs`].
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        workflow_store::fs::write_json(path.as_ref(), self).await?;
+        Ok(())
+    }
+
+    /// Loads a conversation stored by [`Conversation::save()`].
+    pub async fn load(path: impl AsRef<Path>, client: &ChatGPT) -> Result<Self> {
+        let conversation: Conversation = workflow_store::fs::read_json(path.as_ref()).await?;
+        Ok(conversation.with_client(client))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every message consumes 10 tokens.
+    struct FixedEstimator;
+
+    impl TokenEstimator for FixedEstimator {
+        fn estimate(&self, _text: &str) -> usize {
+            10
+        }
+
+        fn estimate_message(&self, _message: &ChatMessage) -> usize {
+            10
+        }
+    }
+
+    fn conversation(budget: usize) -> Conversation {
+        let mut conversation = Conversation::default()
+            .with_system_prompt("system")
+            .with_budget(budget)
+            .with_estimator(Arc::new(FixedEstimator));
+        for n in 0..5 {
+            conversation.push(ChatMessage::user(format!("question {n}")));
+            conversation.push(ChatMessage::assistant(format!("answer {n}")));
+        }
+        conversation.push(ChatMessage::user("question 5"));
+        conversation
+    }
+
+    #[test]
+    fn test_truncation_keeps_system_prompt_and_recent_turns() {
+        let mut conversation = conversation(50);
+        assert_eq!(conversation.estimate_tokens(), 120);
+
+        let removed = conversation.truncate();
+        assert_eq!(removed.len(), 7);
+        assert_eq!(removed[0].content, "question 0");
+        assert_eq!(conversation.estimate_tokens(), 50);
+
+        let messages = conversation.messages();
+        assert_eq!(messages[0], ChatMessage::system("system"));
+        let recent = messages[1..]
+            .iter()
+            .map(|message| message.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            recent,
+            vec!["answer 3", "question 4", "answer 4", "question 5"]
+        );
+    }
+
+    #[test]
+    fn test_truncation_retains_last_message() {
+        let mut conversation = conversation(5);
+        conversation.truncate();
+        assert_eq!(conversation.system_prompt(), Some("system"));
+        assert_eq!(conversation.turns(), &[ChatMessage::user("question 5")]);
+    }
+
+    #[test]
+    fn test_heuristic_estimator() {
+        let estimator = HeuristicEstimator;
+        assert_eq!(estimator.estimate(""), 0);
+        assert_eq!(estimator.estimate("abcde"), 2);
+        assert_eq!(estimator.estimate_message(&ChatMessage::user("abcd")), 5);
+    }
+
+    #[test]
+    fn test_conversation_serde() {
+        let conversation = conversation(50).with_policy(TruncationPolicy::Summarize);
+        let json = serde_json::to_string(&conversation).unwrap();
+        let restored: Conversation = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.messages(), conversation.messages());
+        assert_eq!(restored.policy, TruncationPolicy::Summarize);
+        assert_eq!(restored.budget, 50);
+        assert!(restored.client.is_none());
+    }
+}
@@ -1,3 +1,5 @@
+use serde::Deserialize;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,4 +8,68 @@ pub enum Error {
     Reqwest(#[from] reqwest::Error),
     #[error("Failure after {0} retries: {1}")]
     RetryFailure(usize, String),
+    #[error("HTTP status {0}: {1}")]
+    Status(u16, String),
+    #[error("Rate limit exceeded: {message}")]
+    RateLimit {
+        /// Delay requested by the server before retrying.
+        retry_after: Option<Duration>,
+        message: String,
+    },
+    #[error("Authentication failure: {0}")]
+    Unauthorized(String),
+    #[error("Context length exceeded: {0}")]
+    ContextLengthExceeded(String),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Aborted")]
+    Aborted,
+    #[error(transparent)]
+    Store(#[from] workflow_store::error::Error),
+    #[error("{0}")]
+    Custom(String),
+}
+
+#[derive(Deserialize)]
+struct ApiErrorResponse {
+    error: ApiError,
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    message: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    code: Option<serde_json::Value>,
+}
+
+impl Error {
+    /// Maps an unsuccessful API response to a typed error. The body is
+    /// expected in the OpenAI `{ "error": { "message", "type", "code" } }`
+    /// format, which is also used by Azure OpenAI and llama.cpp.
+    pub(crate) fn from_response(status: u16, retry_after: Option<Duration>, body: String) -> Self {
+        let error = serde_json::from_str::<ApiErrorResponse>(&body)
+            .ok()
+            .map(|response| response.error);
+        let is_context_length = error.as_ref().is_some_and(|error| {
+            [
+                error.code.as_ref().and_then(|code| code.as_str()),
+                error.kind.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .any(|kind| kind == "context_length_exceeded" || kind == "exceed_context_size_error")
+        });
+        let message = error.map(|error| error.message).unwrap_or(body);
+
+        match status {
+            429 => Error::RateLimit {
+                retry_after,
+                message,
+            },
+            401 | 403 => Error::Unauthorized(message),
+            _ if is_context_length => Error::ContextLengthExceeded(message),
+            _ => Error::Status(status, message),
+        }
+    }
 }
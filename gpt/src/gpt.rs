@@ -1,6 +1,6 @@
 use crate::imports::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Model {
     CushmanCodex,
     DavinciCodex,
@@ -34,8 +34,8 @@ impl std::fmt::Display for Model {
 }
 
 struct Inner {
-    api_key: String,
-    model: Model,
+    provider: ProviderConfig,
+    url: String,
     client: Client,
 }
 
@@ -46,15 +46,38 @@ pub struct ChatGPT {
 
 impl ChatGPT {
     pub fn new(api_key: String, model: Model) -> Self {
+        Self::custom(ProviderConfig::openai(api_key, model))
+    }
+
+    /// OpenAI API using the [`Model::Gpt4o`] model.
+    pub fn openai(api_key: impl Into<String>) -> Self {
+        Self::custom(ProviderConfig::openai(api_key, Model::Gpt4o))
+    }
+
+    /// Azure OpenAI deployment; see [`ProviderConfig::azure()`].
+    pub fn azure(
+        endpoint: impl Into<String>,
+        api_key: impl Into<String>,
+        deployment: impl Into<String>,
+    ) -> Self {
+        Self::custom(ProviderConfig::azure(endpoint, api_key, deployment))
+    }
+
+    /// Any OpenAI-compatible provider (e.g. a local llama.cpp server).
+    pub fn custom(provider: ProviderConfig) -> Self {
         ChatGPT {
             inner: Arc::new(Inner {
-                api_key,
-                model,
+                url: provider.chat_completions_url(),
+                provider,
                 client: Client::new(),
             }),
         }
     }
 
+    pub fn provider(&self) -> &ProviderConfig {
+        &self.inner.provider
+    }
+
     pub async fn query_with_retries(
         &self,
         text: String,
@@ -68,39 +91,151 @@ impl ChatGPT {
                     return Ok(response);
                 }
                 Err(err) => {
-                    workflow_core::task::sleep(delay).await;
                     attempt += 1;
                     if attempt >= retries {
                         return Err(Error::RetryFailure(retries, err.to_string()));
                     }
+                    let delay = match &err {
+                        Error::RateLimit {
+                            retry_after: Some(retry_after),
+                            ..
+                        } => *retry_after,
+                        _ => delay,
+                    };
+                    workflow_core::task::sleep(delay).await;
                 }
             }
         }
     }
 
-    pub async fn query(&self, text: String) -> Result<String> {
+    fn body<'r>(&self, request: &'r ChatRequest, stream: bool) -> RequestBody<'r> {
+        RequestBody::new(self.inner.provider.model.to_string(), request, stream)
+    }
+
+    fn post(&self, body: &RequestBody<'_>) -> reqwest::RequestBuilder {
+        let provider = &self.inner.provider;
+        let mut builder = self.inner.client.post(&self.inner.url);
+        builder = match &provider.auth {
+            Auth::Bearer(key) => builder.header("Authorization", format!("Bearer {key}")),
+            Auth::ApiKey(key) => builder.header("api-key", key),
+            Auth::None => builder,
+        };
+        for (name, value) in provider.headers.iter() {
+            builder = builder.header(name, value);
+        }
+        builder.json(body)
+    }
+
+    async fn send(&self, body: &RequestBody<'_>) -> Result<reqwest::Response> {
+        let response = self.post(body).send().await?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(response)
+        } else {
+            let retry_after = retry_after(response.headers());
+            Err(Error::from_response(
+                status.as_u16(),
+                retry_after,
+                response.text().await?,
+            ))
+        }
+    }
+
+    /// Completes `request`, returning the first choice and the token usage.
+    pub async fn complete(&self, request: &ChatRequest) -> Result<Completion> {
         let response = self
-            .inner
-            .client
-            .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", self.inner.api_key))
-            .json(&Request {
-                model: self.inner.model.to_string(),
-                messages: vec![Message {
-                    role: "user".to_string(),
-                    content: text,
-                }],
-            })
-            .send()
+            .send(&self.body(request, false))
             .await?
             .json::<Response>()
             .await?;
 
-        Ok(response
-            .choices
-            .first()
-            .map(|choice| choice.message.content.clone())
-            .unwrap_or_default())
+        Ok(Completion {
+            text: response
+                .choices
+                .first()
+                .map(|choice| choice.message.content.clone())
+                .unwrap_or_default(),
+            usage: response.usage.unwrap_or_default(),
+        })
+    }
+
+    pub async fn query(&self, text: String) -> Result<String> {
+        let request = ChatRequest::new(vec![ChatMessage::user(text)]);
+        Ok(self.complete(&request).await?.text)
+    }
+
+    /// Streams the completion of `request`. Tokens are delivered as
+    /// [`StreamEvent::Token`] as they arrive, followed by a single
+    /// [`StreamEvent::Done`] or [`StreamEvent::Error`]. Dropping the
+    /// receiver stops the stream.
+    pub fn complete_stream(&self, request: ChatRequest) -> Receiver<StreamEvent> {
+        self.complete_stream_with_abortable(request, &Abortable::new())
+    }
+
+    /// Same as [`ChatGPT::complete_stream()`]; aborting `abortable`
+    /// terminates the HTTP request and produces [`StreamEvent::Error`].
+    pub fn complete_stream_with_abortable(
+        &self,
+        request: ChatRequest,
+        abortable: &Abortable,
+    ) -> Receiver<StreamEvent> {
+        let (sender, receiver) = unbounded();
+        let this = self.clone();
+        let abortable = abortable.clone();
+        let future = async move {
+            if let Err(err) = this.stream(request, &abortable, &sender).await {
+                sender.try_send(StreamEvent::Error(err.to_string())).ok();
+            }
+        };
+
+        cfg_if::cfg_if! {
+            if #[cfg(target_arch = "wasm32")] {
+                // the fetch() response stream is not `Send`
+                task::dispatch(future);
+            } else {
+                task::spawn(future);
+            }
+        }
+
+        receiver
+    }
+
+    async fn stream(
+        &self,
+        request: ChatRequest,
+        abortable: &Abortable,
+        sender: &Sender<StreamEvent>,
+    ) -> Result<()> {
+        let body = self.body(&request, true);
+        // dropping the response (or the pending request) aborts
+        // the underlying connection or fetch() request
+        let response = select! {
+            response = self.send(&body).fuse() => response?,
+            _ = aborted(abortable).fuse() => return Err(Error::Aborted),
+        };
+
+        let mut stream = Box::pin(response.bytes_stream());
+        let mut decoder = StreamDecoder::default();
+        while !decoder.is_done() {
+            let chunk = select! {
+                chunk = stream.next().fuse() => chunk,
+                _ = aborted(abortable).fuse() => return Err(Error::Aborted),
+            };
+            let Some(chunk) = chunk else {
+                break;
+            };
+            for event in decoder.push(&chunk?)? {
+                if sender.try_send(event).is_err() {
+                    // receiver has been dropped
+                    return Ok(());
+                }
+            }
+        }
+
+        if let Some(event) = decoder.finish() {
+            sender.try_send(event).ok();
+        }
+        Ok(())
     }
 
     pub async fn translate(
@@ -115,19 +250,9 @@ impl ChatGPT {
             target_language, message_content
         );
 
+        let request = ChatRequest::new(vec![ChatMessage::user(message_content)]);
         let response = self
-            .inner
-            .client
-            .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", self.inner.api_key))
-            .json(&Request {
-                model: self.inner.model.to_string(),
-                messages: vec![Message {
-                    role: "user".to_string(),
-                    content: message_content,
-                }],
-            })
-            .send()
+            .send(&self.body(&request, false))
             .await?
             .json::<Response>()
             .await?;
@@ -153,21 +278,32 @@ impl ChatGPT {
     }
 }
 
-#[derive(Serialize)]
-struct Request {
-    model: String,
-    messages: Vec<Message>,
+/// Delay requested via the `retry-after-ms` (OpenAI, Azure)
+/// or `retry-after` (seconds) response headers.
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .filter(|value| value.is_finite() && *value >= 0.0)
+    };
+    header("retry-after-ms")
+        .map(|ms| Duration::from_secs_f64(ms / 1000.0))
+        .or_else(|| header("retry-after").map(Duration::from_secs_f64))
 }
 
-#[derive(Serialize)]
-struct Message {
-    role: String,
-    content: String,
+/// Resolves when `abortable` is aborted.
+async fn aborted(abortable: &Abortable) {
+    while !abortable.is_aborted() {
+        task::sleep(Duration::from_millis(50)).await;
+    }
 }
 
 #[derive(Deserialize)]
 struct Response {
     choices: Vec<Choice>,
+    usage: Option<Usage>,
 }
 
 #[derive(Deserialize)]
@@ -179,3 +315,238 @@ struct Choice {
 struct MessageResponse {
     content: String,
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::mpsc;
+    use workflow_http::test_server::{self, TestRequest};
+
+    const CAPTURED: &str = concat!(
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"\"},\"finish_reason\":null}],\"usage\":null}\n\n",
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hello\"},\"finish_reason\":null}],\"usage\":null}\n\n",
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\", wörld\"},\"finish_reason\":null}],\"usage\":null}\n\n",
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"!\"},\"finish_reason\":null}],\"usage\":null}\n\n",
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}],\"usage\":null}\n\n",
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":4,\"total_tokens\":13}}\n\n",
+        "data: [DONE]\n\n",
+    );
+
+    /// Server replying to a single request with `status`, extra `headers`
+    /// and a JSON `body`; returns the base URL and the captured request.
+    fn mock_server(
+        status: u16,
+        headers: &str,
+        body: &str,
+    ) -> (String, mpsc::Receiver<TestRequest>) {
+        let headers = format!("Content-Type: application/json\r\n{headers}");
+        let body = body.to_string();
+        let (sender, receiver) = mpsc::channel();
+        let (url, _) = test_server::serve_once(move |request, stream| {
+            test_server::respond(stream, status, &headers, body.as_bytes());
+            sender.send(request).ok();
+        });
+        (format!("{url}/v1"), receiver)
+    }
+
+    const COMPLETION: &str = r#"{"choices":[{"index":0,"message":{"role":"assistant","content":"Hi!"}}],"usage":{"prompt_tokens":3,"completion_tokens":2,"total_tokens":5}}"#;
+
+    /// SSE server replaying `CAPTURED` in small chunks. If `stall` is set,
+    /// the server stops after the first token and reports (via the returned
+    /// channel) when the client has closed the connection.
+    fn sse_server(stall: bool) -> (String, mpsc::Receiver<String>) {
+        let (sender, receiver) = mpsc::channel();
+        let (url, _) = test_server::serve_once(move |request, stream| {
+            sender.send(request.text()).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n")
+                .unwrap();
+            let data = if stall {
+                // up to the end of the first token event
+                let start = CAPTURED.find("Hello").unwrap();
+                &CAPTURED[..start + CAPTURED[start..].find("\n\n").unwrap() + 2]
+            } else {
+                CAPTURED
+            };
+            for chunk in data.as_bytes().chunks(7) {
+                stream.write_all(chunk).unwrap();
+                stream.flush().unwrap();
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            if stall {
+                while stream.write_all(b": keep-alive\n\n").is_ok() {
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+                sender.send("closed".to_string()).unwrap();
+            }
+        });
+        (format!("{url}/v1"), receiver)
+    }
+
+    #[tokio::test]
+    async fn test_complete_stream() {
+        let (url, server) = sse_server(false);
+        let gpt = ChatGPT::custom(ProviderConfig::new(url, Model::Gpt4o));
+        let receiver = gpt.complete_stream("Say hello".into());
+
+        let mut text = String::new();
+        let mut usage = None;
+        while let Ok(event) = receiver.recv().await {
+            match event {
+                StreamEvent::Token(token) => text.push_str(&token),
+                StreamEvent::Done(done) => usage = Some(done),
+                StreamEvent::Error(err) => panic!("stream error: {err}"),
+            }
+        }
+        assert_eq!(text, "Hello, wörld!");
+        assert_eq!(
+            usage,
+            Some(Usage {
+                prompt_tokens: 9,
+                completion_tokens: 4,
+                total_tokens: 13
+            })
+        );
+
+        let body: serde_json::Value = serde_json::from_str(&server.recv().unwrap()).unwrap();
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["model"], "gpt-4o");
+        assert_eq!(body["messages"][0]["role"], "user");
+    }
+
+    #[tokio::test]
+    async fn test_complete_stream_abort() {
+        let (url, server) = sse_server(true);
+        let gpt = ChatGPT::custom(ProviderConfig::new(url, Model::Gpt4o));
+        let abortable = Abortable::new();
+        let receiver = gpt.complete_stream_with_abortable("Say hello".into(), &abortable);
+
+        assert_eq!(
+            receiver.recv().await.unwrap(),
+            StreamEvent::Token("Hello".to_string())
+        );
+        abortable.abort();
+        assert_eq!(
+            receiver.recv().await.unwrap(),
+            StreamEvent::Error(Error::Aborted.to_string())
+        );
+        assert!(receiver.recv().await.is_err());
+
+        server.recv().unwrap();
+        assert_eq!(
+            server.recv_timeout(Duration::from_secs(5)).unwrap(),
+            "closed"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_provider_request_shape() {
+        // OpenAI
+        let (url, server) = mock_server(200, "", COMPLETION);
+        let gpt = ChatGPT::custom(
+            ProviderConfig::new(url, Model::Gpt4o).with_auth(Auth::Bearer("sk-test".into())),
+        );
+        let completion = gpt.complete(&"Hello".into()).await.unwrap();
+        assert_eq!(completion.text, "Hi!");
+        assert_eq!(completion.usage.total_tokens, 5);
+        let request = server.recv().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.target, "/v1/chat/completions");
+        assert_eq!(request.header("authorization"), Some("Bearer sk-test"));
+        assert_eq!(request.header("api-key"), None);
+        assert_eq!(request.json()["model"], "gpt-4o");
+        assert_eq!(request.json()["messages"][0]["content"], "Hello");
+
+        // Azure OpenAI
+        let (url, server) = mock_server(200, "", COMPLETION);
+        let base_url = url.trim_end_matches("/v1").to_string();
+        let gpt = ChatGPT::custom(
+            ProviderConfig::azure(base_url, "azure-key", "my-gpt4").with_api_version("2024-02-01"),
+        );
+        gpt.complete(&"Hello".into()).await.unwrap();
+        let request = server.recv().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(
+            request.target,
+            "/openai/deployments/my-gpt4/chat/completions?api-version=2024-02-01"
+        );
+        assert_eq!(request.header("api-key"), Some("azure-key"));
+        assert_eq!(request.header("authorization"), None);
+        assert_eq!(request.json()["model"], "my-gpt4");
+
+        // local llama.cpp server
+        let (url, server) = mock_server(200, "", COMPLETION);
+        let gpt = ChatGPT::custom(
+            ProviderConfig::new(format!("{url}/"), Model::Custom("llama-3".into()))
+                .with_header("x-client", "workflow"),
+        );
+        gpt.complete(&"Hello".into()).await.unwrap();
+        let request = server.recv().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.target, "/v1/chat/completions");
+        assert_eq!(request.header("authorization"), None);
+        assert_eq!(request.header("x-client"), Some("workflow"));
+        assert_eq!(request.json()["model"], "llama-3");
+    }
+
+    #[tokio::test]
+    async fn test_typed_errors() {
+        async fn complete(status: u16, headers: &str, body: &str) -> Error {
+            let (url, _server) = mock_server(status, headers, body);
+            ChatGPT::custom(ProviderConfig::new(url, Model::Gpt4o))
+                .complete(&"Hello".into())
+                .await
+                .unwrap_err()
+        }
+
+        let err = complete(
+            429,
+            "retry-after-ms: 1500\r\n",
+            r#"{"error":{"message":"Rate limit reached","type":"requests","code":"rate_limit_exceeded"}}"#,
+        )
+        .await;
+        assert!(matches!(
+            err,
+            Error::RateLimit { retry_after: Some(retry_after), ref message }
+                if retry_after == Duration::from_millis(1500) && message == "Rate limit reached"
+        ));
+
+        let err = complete(429, "Retry-After: 2\r\n", "{}").await;
+        assert!(matches!(
+            err,
+            Error::RateLimit { retry_after: Some(retry_after), .. } if retry_after == Duration::from_secs(2)
+        ));
+
+        let err = complete(
+            401,
+            "",
+            r#"{"error":{"message":"Incorrect API key provided","type":"invalid_request_error","code":"invalid_api_key"}}"#,
+        )
+        .await;
+        assert!(
+            matches!(err, Error::Unauthorized(ref message) if message == "Incorrect API key provided")
+        );
+
+        let err = complete(
+            400,
+            "",
+            r#"{"error":{"message":"This model's maximum context length is 8192 tokens","type":"invalid_request_error","param":"messages","code":"context_length_exceeded"}}"#,
+        )
+        .await;
+        assert!(matches!(err, Error::ContextLengthExceeded(_)));
+
+        // llama.cpp
+        let err = complete(
+            400,
+            "",
+            r#"{"error":{"code":400,"message":"the request exceeds the available context size","type":"exceed_context_size_error"}}"#,
+        )
+        .await;
+        assert!(matches!(err, Error::ContextLengthExceeded(_)));
+
+        let err = complete(500, "", "upstream failure").await;
+        assert!(matches!(err, Error::Status(500, ref message) if message == "upstream failure"));
+    }
+}
@@ -1,6 +1,16 @@
+pub use crate::chat::{ChatMessage, ChatRequest, Completion, Role, Usage};
+pub(crate) use crate::chat::RequestBody;
 pub use crate::error::Error;
+pub use crate::gpt::ChatGPT;
+pub use crate::provider::{Auth, ProviderConfig};
 pub use crate::result::Result;
+pub use crate::stream::StreamEvent;
+pub(crate) use crate::stream::StreamDecoder;
+pub use futures::{select, FutureExt, StreamExt};
 pub use reqwest::Client;
 pub use serde::{Deserialize, Serialize};
 pub use std::sync::Arc;
 pub use std::time::Duration;
+pub use workflow_core::abortable::Abortable;
+pub use workflow_core::channel::{unbounded, Receiver, Sender};
+pub use workflow_core::task;
@@ -1,8 +1,16 @@
+pub mod chat;
+pub mod conversation;
 pub mod error;
 pub mod gpt;
 mod imports;
+pub mod provider;
 pub mod result;
+pub mod stream;
 
 pub mod prelude {
-    pub use crate::gpt::ChatGPT;
+    pub use crate::chat::{ChatMessage, ChatRequest, Completion, Role, Usage};
+    pub use crate::conversation::{Conversation, TokenEstimator, TruncationPolicy};
+    pub use crate::gpt::{ChatGPT, Model};
+    pub use crate::provider::{Auth, ProviderConfig};
+    pub use crate::stream::StreamEvent;
 }
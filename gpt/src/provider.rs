@@ -0,0 +1,125 @@
+//!
+//! [`ProviderConfig`] describing OpenAI-compatible chat completion
+//! endpoints (OpenAI, Azure OpenAI, local llama.cpp servers etc.)
+//!
+
+use crate::gpt::Model;
+
+pub const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+pub const AZURE_DEFAULT_API_VERSION: &str = "2024-06-01";
+
+/// Authentication applied to each request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Auth {
+    /// `Authorization: Bearer <key>` (OpenAI and most compatible servers)
+    Bearer(String),
+    /// `api-key: <key>` (Azure OpenAI)
+    ApiKey(String),
+    /// No authentication (e.g. local servers)
+    None,
+}
+
+/// Provider specific URL layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Flavor {
+    /// `<base_url>/chat/completions`
+    OpenAI,
+    /// `<base_url>/openai/deployments/<deployment>/chat/completions?api-version=<api_version>`
+    Azure {
+        deployment: String,
+        api_version: String,
+    },
+}
+
+/// Configuration of an OpenAI-compatible provider.
+///
+/// ```ignore
+/// // local llama.cpp server
+/// let gpt = ChatGPT::custom(
+///     ProviderConfig::new("http://localhost:8080/v1", Model::Custom("llama-3".into()))
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProviderConfig {
+    pub base_url: String,
+    pub auth: Auth,
+    pub model: Model,
+    /// Additional headers sent with each request.
+    pub headers: Vec<(String, String)>,
+    pub flavor: Flavor,
+}
+
+impl ProviderConfig {
+    /// Creates an unauthenticated OpenAI-compatible configuration.
+    pub fn new(base_url: impl Into<String>, model: Model) -> Self {
+        Self {
+            base_url: base_url.into(),
+            auth: Auth::None,
+            model,
+            headers: Vec::new(),
+            flavor: Flavor::OpenAI,
+        }
+    }
+
+    pub fn openai(api_key: impl Into<String>, model: Model) -> Self {
+        Self::new(OPENAI_BASE_URL, model).with_auth(Auth::Bearer(api_key.into()))
+    }
+
+    /// Azure OpenAI configuration; `endpoint` is the resource endpoint
+    /// (e.g. `https://<resource>.openai.azure.com`).
+    pub fn azure(
+        endpoint: impl Into<String>,
+        api_key: impl Into<String>,
+        deployment: impl Into<String>,
+    ) -> Self {
+        let deployment = deployment.into();
+        Self {
+            base_url: endpoint.into(),
+            auth: Auth::ApiKey(api_key.into()),
+            model: Model::Custom(deployment.clone()),
+            headers: Vec::new(),
+            flavor: Flavor::Azure {
+                deployment,
+                api_version: AZURE_DEFAULT_API_VERSION.to_string(),
+            },
+        }
+    }
+
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    pub fn with_model(mut self, model: Model) -> Self {
+        self.model = model;
+        self
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Overrides the Azure `api-version` query parameter;
+    /// ignored by other flavors.
+    pub fn with_api_version(mut self, version: impl Into<String>) -> Self {
+        if let Flavor::Azure { api_version, .. } = &mut self.flavor {
+            *api_version = version.into();
+        }
+        self
+    }
+
+    /// URL of the chat completions endpoint.
+    pub fn chat_completions_url(&self) -> String {
+        let base_url = self.base_url.trim_end_matches('/');
+        match &self.flavor {
+            Flavor::OpenAI => format!("{base_url}/chat/completions"),
+            Flavor::Azure {
+                deployment,
+                api_version,
+            } => format!(
+                "{base_url}/openai/deployments/{deployment}/chat/completions?api-version={api_version}"
+            ),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//!
+//! Streaming chat completions delivered as server-sent events (SSE).
+//!
+
+use crate::imports::*;
+
+/// Event produced by [`ChatGPT::complete_stream()`](crate::gpt::ChatGPT::complete_stream).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamEvent {
+    /// Incremental completion text.
+    Token(String),
+    /// Completion finished; usage is zero if not reported by the server.
+    Done(Usage),
+    /// Completion failed or was aborted; no further events follow.
+    Error(String),
+}
+
+/// Incremental SSE parser. Bytes are buffered until a complete event
+/// (terminated by an empty line) is available, so events, JSON payloads
+/// and UTF-8 sequences may be split across chunk boundaries.
+#[derive(Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds a chunk, returning the `data` payloads of completed events.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some((end, separator)) = find_event_end(&self.buffer) {
+            let event = self.buffer.drain(..end + separator).collect::<Vec<_>>();
+            let event = String::from_utf8_lossy(&event[..end]);
+            let data = event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|data| data.strip_prefix(' ').unwrap_or(data))
+                .collect::<Vec<_>>();
+            if !data.is_empty() {
+                events.push(data.join("\n"));
+            }
+        }
+        events
+    }
+}
+
+/// Returns the position of the first blank line and the separator length.
+fn find_event_end(buffer: &[u8]) -> Option<(usize, usize)> {
+    (0..buffer.len()).find_map(|n| {
+        if buffer[n..].starts_with(b"\r\n\r\n") {
+            Some((n, 4))
+        } else if buffer[n..].starts_with(b"\n\n") {
+            Some((n, 2))
+        } else {
+            None
+        }
+    })
+}
+
+#[derive(Deserialize)]
+struct Chunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct ChunkChoice {
+    #[serde(default)]
+    delta: Delta,
+}
+
+#[derive(Default, Deserialize)]
+struct Delta {
+    content: Option<String>,
+}
+
+/// Converts SSE payloads into [`StreamEvent`]s.
+#[derive(Default)]
+pub(crate) struct StreamDecoder {
+    parser: SseParser,
+    usage: Usage,
+    done: bool,
+}
+
+impl StreamDecoder {
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<StreamEvent>> {
+        let mut events = Vec::new();
+        for data in self.parser.push(chunk) {
+            if self.done {
+                break;
+            }
+            if data.trim() == "[DONE]" {
+                self.done = true;
+                events.push(StreamEvent::Done(self.usage));
+                break;
+            }
+
+            let chunk: Chunk = serde_json::from_str(&data)?;
+            if let Some(usage) = chunk.usage {
+                self.usage = usage;
+            }
+            for choice in chunk.choices {
+                if let Some(content) = choice.delta.content.filter(|text| !text.is_empty()) {
+                    events.push(StreamEvent::Token(content));
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Event emitted if the stream ends without the `[DONE]` marker.
+    pub fn finish(&mut self) -> Option<StreamEvent> {
+        (!self.done).then(|| {
+            self.done = true;
+            StreamEvent::Done(self.usage)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_parser_chunk_boundaries() {
+        let stream =
+            "data: {\"a\":\"é\"}\n\n: comment\n\ndata: one\ndata: two\r\n\r\ndata: [DONE]\n\n";
+        for size in 1..stream.len() {
+            let mut parser = SseParser::new();
+            let events = stream
+                .as_bytes()
+                .chunks(size)
+                .flat_map(|chunk| parser.push(chunk))
+                .collect::<Vec<_>>();
+            assert_eq!(events, vec!["{\"a\":\"é\"}", "one\ntwo", "[DONE]"]);
+        }
+    }
+}
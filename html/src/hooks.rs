@@ -0,0 +1,229 @@
+//!
+//! [`Hooks`] - elements marked with `@name` attributes collected
+//! during rendering of the [`Html`](crate::Html) tree.
+//!
+
+use crate::utils::{Element, JsValue};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use wasm_bindgen::JsCast;
+
+/// Errors produced by [`Hooks`] lookups and validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HooksError {
+    /// Refs not present in the rendered tree
+    Missing {
+        names: Vec<String>,
+        available: Vec<String>,
+    },
+    /// The element does not match the requested type
+    WrongType {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+    /// The ref name is used by multiple elements of the same tree
+    Duplicate(String),
+}
+
+impl fmt::Display for HooksError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quote = |names: &[String]| {
+            names
+                .iter()
+                .map(|name| format!("`@{name}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            HooksError::Missing { names, available } if available.is_empty() => {
+                write!(f, "missing ref {} (no refs available)", quote(names))
+            }
+            HooksError::Missing { names, available } => write!(
+                f,
+                "missing ref {} (available refs: {})",
+                quote(names),
+                quote(available)
+            ),
+            HooksError::WrongType {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "ref `@{name}` is a <{actual}> element, expected {expected}"
+            ),
+            HooksError::Duplicate(name) => write!(f, "duplicate ref `@{name}`"),
+        }
+    }
+}
+
+impl std::error::Error for HooksError {}
+
+impl From<HooksError> for JsValue {
+    fn from(err: HooksError) -> JsValue {
+        JsValue::from(err.to_string())
+    }
+}
+
+/// Elements marked with `@name` attributes, keyed by the name.
+#[derive(Debug, Default, Clone)]
+pub struct Hooks(BTreeMap<String, Element>);
+
+impl Hooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the element under `name`, failing if
+    /// the name is already used by another element.
+    pub fn insert_unique(&mut self, name: String, element: Element) -> Result<(), HooksError> {
+        if self.0.contains_key(&name) {
+            return Err(HooksError::Duplicate(name));
+        }
+        self.0.insert(name, element);
+        Ok(())
+    }
+
+    /// Obtain the element registered under `name` cast to the type `T`
+    /// (such as `web_sys::HtmlInputElement`).
+    pub fn get_as<T: JsCast>(&self, name: &str) -> Result<T, HooksError> {
+        let element = self.0.get(name).ok_or_else(|| self.missing(&[name]))?;
+        element
+            .clone()
+            .dyn_into::<T>()
+            .map_err(|element| HooksError::WrongType {
+                name: name.to_string(),
+                expected: std::any::type_name::<T>()
+                    .rsplit("::")
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                actual: element.tag_name().to_lowercase(),
+            })
+    }
+
+    /// Check that all `names` are present, allowing to detect mismatches
+    /// between the template and the code right after rendering.
+    pub fn require(&self, names: &[&str]) -> Result<(), HooksError> {
+        let missing = names
+            .iter()
+            .copied()
+            .filter(|name| !self.0.contains_key(*name))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(self.missing(&missing))
+        }
+    }
+
+    fn missing(&self, names: &[&str]) -> HooksError {
+        HooksError::Missing {
+            names: names.iter().map(|name| name.to_string()).collect(),
+            available: self.0.keys().cloned().collect(),
+        }
+    }
+
+    pub fn into_inner(self) -> BTreeMap<String, Element> {
+        self.0
+    }
+}
+
+impl Deref for Hooks {
+    type Target = BTreeMap<String, Element>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Hooks {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<BTreeMap<String, Element>> for Hooks {
+    fn from(map: BTreeMap<String, Element>) -> Self {
+        Hooks(map)
+    }
+}
+
+impl IntoIterator for Hooks {
+    type Item = (String, Element);
+    type IntoIter = std::collections::btree_map::IntoIter<String, Element>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use crate as workflow_html;
+    use crate::*;
+    use wasm_bindgen_test::*;
+    use web_sys::HtmlInputElement;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn render() -> Html {
+        tree! {
+            <div @form>
+                <input @name type="text" value="abc" />
+                <span @label>"name"</span>
+            </div>
+        }
+        .render_tree()
+        .unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_hooks_get_as() {
+        let html = render();
+        let input = html.hooks().get_as::<HtmlInputElement>("name").unwrap();
+        assert_eq!(input.value(), "abc");
+        assert_eq!(
+            html.hooks().get_as::<HtmlInputElement>("label"),
+            Err(HooksError::WrongType {
+                name: "label".to_string(),
+                expected: "HtmlInputElement".to_string(),
+                actual: "span".to_string(),
+            })
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_hooks_missing() {
+        let html = render();
+        let err = html.hooks().get_as::<WebElement>("email").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "missing ref `@email` (available refs: `@form`, `@label`, `@name`)"
+        );
+
+        html.hooks().require(&["form", "name"]).unwrap();
+        assert_eq!(
+            html.hooks().require(&["form", "email", "submit"]),
+            Err(HooksError::Missing {
+                names: vec!["email".to_string(), "submit".to_string()],
+                available: vec!["form".to_string(), "label".to_string(), "name".to_string()],
+            })
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_hooks_duplicate() {
+        let result = tree! {
+            <div>
+                <span @item>"a"</span>
+                <span @item>"b"</span>
+            </div>
+        }
+        .render_tree();
+        let err = result.err().expect("duplicate refs must fail the render");
+        assert_eq!(err.as_string().unwrap(), "duplicate ref `@item`");
+    }
+}
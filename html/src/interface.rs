@@ -1,8 +1,8 @@
+pub use crate::hooks::Hooks;
 use crate::render::{Render, Renderables};
 pub use crate::utils::{document, Element, ElementResult};
 use crate::WebElement;
-use std::collections::BTreeMap;
-pub type Hooks = BTreeMap<String, Element>;
+use web_sys::Node;
 //use workflow_log::log_trace;
 
 #[derive(Clone)]
@@ -36,6 +36,32 @@ impl Html {
         }
         Ok(())
     }
+
+    /// Remove the root elements from their parent. The retained renderables,
+    /// hooks and event listeners are kept alive, allowing the roots to be
+    /// re-inserted using [`Html::attach_to()`].
+    pub fn detach(&self) -> ElementResult<()> {
+        for root in self.roots.iter() {
+            root.remove();
+        }
+        Ok(())
+    }
+
+    /// Insert the root elements into `parent` (in their original order) before
+    /// the `before` node, or append them if `before` is `None`. If the roots are
+    /// attached to a different parent, they are moved without being re-rendered.
+    pub fn attach_to(&self, parent: &WebElement, before: Option<&Node>) -> ElementResult<()> {
+        for root in self.roots.iter() {
+            parent.insert_before(root, before)?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if all root elements are attached to a parent node
+    pub fn is_attached(&self) -> bool {
+        !self.roots.is_empty() && self.roots.iter().all(|root| root.parent_node().is_some())
+    }
+
     pub fn remove_event_listeners(&self) -> ElementResult<()> {
         for root in &self.renderables {
             root.remove_event_listeners()?;
@@ -52,8 +78,9 @@ impl Render for Html {
         renderables: &mut Renderables,
     ) -> ElementResult<()> {
         renderables.append(self.renderables.as_mut());
-        let mut hooks = self.hooks().clone();
-        map.append(&mut hooks);
+        for (name, element) in self.hooks().clone() {
+            map.insert_unique(name, element)?;
+        }
         self.inject_into(parent)?;
         Ok(())
     }
@@ -70,6 +97,63 @@ impl Render for Html {
     }
 }
 
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use crate as workflow_html;
+    use crate::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use wasm_bindgen_test::*;
+    use web_sys::MouseEvent;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_html_detach_attach() {
+        let clicks = Rc::new(Cell::new(0));
+        let counter = clicks.clone();
+        let html = tree! {
+            <span>"a"</span>
+            <button @button !click={counter.set(counter.get() + 1)}>"b"</button>
+            <span>"c"</span>
+        }
+        .render_tree()
+        .unwrap();
+        let button = html.hooks().get_as::<WebElement>("button").unwrap();
+        let click = || {
+            button
+                .dispatch_event(&MouseEvent::new("click").unwrap())
+                .unwrap();
+        };
+
+        let a = document().create_element("div").unwrap();
+        let b = document().create_element("div").unwrap();
+        b.set_inner_html("<p>x</p>");
+        assert!(!html.is_attached());
+        html.attach_to(&a, None).unwrap();
+        assert!(html.is_attached());
+        click();
+        assert_eq!(clicks.get(), 1);
+
+        html.detach().unwrap();
+        assert!(!html.is_attached());
+        assert_eq!(a.children().length(), 0);
+
+        // the fragment moves as a unit, before the reference node
+        html.attach_to(&b, b.first_child().as_ref()).unwrap();
+        assert_eq!(b.text_content().unwrap(), "abcx");
+        click();
+        assert_eq!(clicks.get(), 2);
+
+        // moving between parents does not require detaching
+        html.attach_to(&a, None).unwrap();
+        assert_eq!(a.text_content().unwrap(), "abc");
+        assert_eq!(b.text_content().unwrap(), "x");
+        click();
+        assert_eq!(clicks.get(), 3);
+    }
+}
+
 /*
 impl Drop for Html{
     fn drop(&mut self) {
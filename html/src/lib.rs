@@ -24,10 +24,14 @@
 //!
 
 pub mod escape;
+pub mod hooks;
 pub mod interface;
+pub mod list;
 pub mod render;
 pub mod utils;
-pub use interface::{Hooks, Html};
+pub use hooks::{Hooks, HooksError};
+pub use interface::Html;
+pub use list::{ListBinding, ListError};
 
 pub use escape::{escape_attr, escape_html};
 pub use render::{Render, Renderables, Result, Write};
@@ -113,7 +117,7 @@ impl<T: Render + Clone + 'static> Render for Element<T> {
         }
         if let Some((key, value)) = self.reff {
             el.set_attribute("data-ref", &value)?;
-            map.insert(key, el.clone());
+            map.insert_unique(key, el.clone())?;
         }
         if let Some(children) = self.children {
             children.render_node(&mut el, map, renderables)?;
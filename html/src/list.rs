@@ -0,0 +1,220 @@
+//!
+//! [`ListBinding`] - keyed rendering of collections into a container
+//! element. Updating the list re-renders only the items with new keys,
+//! removes the items whose keys are no longer present and moves the
+//! retained items into place, preserving their DOM nodes (along with
+//! focus and scroll state), [`Hooks`](crate::Hooks) and retained
+//! [`Renderables`](crate::Renderables).
+//!
+
+use crate::interface::Html;
+use crate::utils::{Element, ElementResult, JsValue};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+use web_sys::Node;
+
+/// Errors produced by [`ListBinding::update()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListError {
+    /// Multiple items of the list produce the same key
+    DuplicateKey(String),
+}
+
+impl fmt::Display for ListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListError::DuplicateKey(key) => write!(f, "duplicate list key `{key}`"),
+        }
+    }
+}
+
+impl std::error::Error for ListError {}
+
+impl From<ListError> for JsValue {
+    fn from(err: ListError) -> JsValue {
+        JsValue::from(err.to_string())
+    }
+}
+
+/// Keyed list rendered into a container element. The container is
+/// expected to be managed exclusively by the binding.
+///
+/// ```ignore
+/// let mut list = ListBinding::new(html.hooks().get_as::<Element>("list")?);
+/// list.update(&items, |item| item.id, |item| {
+///     let name = item.name.clone();
+///     tree! { <li>{name}</li> }.render_tree()
+/// })?;
+/// ```
+pub struct ListBinding<K> {
+    container: Element,
+    items: Vec<(K, Html)>,
+}
+
+impl<K> ListBinding<K>
+where
+    K: Eq + Hash + Clone + fmt::Debug,
+{
+    pub fn new(container: Element) -> Self {
+        Self {
+            container,
+            items: Vec::new(),
+        }
+    }
+
+    pub fn container(&self) -> &Element {
+        &self.container
+    }
+
+    /// Keys of the rendered items, in the rendered order
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.items.iter().map(|(key, _)| key)
+    }
+
+    /// Rendered [`Html`] of the item with the `key`
+    pub fn get(&self, key: &K) -> Option<&Html> {
+        self.items
+            .iter()
+            .find(|(item, _)| item == key)
+            .map(|(_, html)| html)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Render `items` into the container. `render_fn` is called only for
+    /// items whose key (produced by `key_fn`) was not present in the
+    /// previous update; the items of the previous update are retained,
+    /// moved into the new order (via `insertBefore`) or removed. Fails
+    /// with [`ListError::DuplicateKey`] (before modifying the DOM) if
+    /// multiple items produce the same key.
+    pub fn update<T, KF, RF>(&mut self, items: &[T], key_fn: KF, render_fn: RF) -> ElementResult<()>
+    where
+        KF: Fn(&T) -> K,
+        RF: Fn(&T) -> ElementResult<Html>,
+    {
+        let keys = items.iter().map(&key_fn).collect::<Vec<_>>();
+        let mut unique = HashSet::with_capacity(keys.len());
+        if let Some(key) = keys.iter().find(|key| !unique.insert(*key)) {
+            return Err(ListError::DuplicateKey(format!("{key:?}")).into());
+        }
+
+        let mut retained = HashMap::with_capacity(self.items.len());
+        for (key, html) in self.items.drain(..) {
+            if unique.contains(&key) {
+                retained.insert(key, html);
+            } else {
+                Self::remove(&self.container, &html)?;
+            }
+        }
+
+        // walk the container placing the roots of each item at the
+        // expected position; nodes already in place are left untouched
+        let mut expected: Option<Node> = self.container.first_child();
+        for (item, key) in items.iter().zip(keys) {
+            let html = match retained.remove(&key) {
+                Some(html) => html,
+                None => render_fn(item)?,
+            };
+            for root in html.roots() {
+                let node: &Node = root;
+                if expected
+                    .as_ref()
+                    .is_some_and(|expected| expected.is_same_node(Some(node)))
+                {
+                    expected = node.next_sibling();
+                } else {
+                    self.container.insert_before(node, expected.as_ref())?;
+                }
+            }
+            self.items.push((key, html));
+        }
+
+        Ok(())
+    }
+
+    /// Remove all items from the container
+    pub fn clear(&mut self) -> ElementResult<()> {
+        for (_, html) in self.items.drain(..) {
+            Self::remove(&self.container, &html)?;
+        }
+        Ok(())
+    }
+
+    fn remove(container: &Element, html: &Html) -> ElementResult<()> {
+        html.remove_event_listeners()?;
+        for root in html.roots() {
+            container.remove_child(root)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use crate as workflow_html;
+    use crate::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn render(binding: &mut ListBinding<String>, items: &[&str]) -> ElementResult<()> {
+        binding.update(
+            items,
+            |item| item.to_string(),
+            |item| {
+                let text = item.to_string();
+                tree! { <li>{text}</li> }.render_tree()
+            },
+        )
+    }
+
+    fn node(binding: &ListBinding<String>, key: &str) -> WebElement {
+        binding.get(&key.to_string()).unwrap().roots()[0].clone()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_list_binding_update() {
+        let container = document().create_element("ul").unwrap();
+        let mut binding = ListBinding::new(container.clone());
+
+        render(&mut binding, &["a", "b", "c"]).unwrap();
+        assert_eq!(container.text_content().unwrap(), "abc");
+        let a = node(&binding, "a");
+        let c = node(&binding, "c");
+
+        render(&mut binding, &["c", "a", "d"]).unwrap();
+        assert_eq!(container.text_content().unwrap(), "cad");
+        assert_eq!(container.children().length(), 3);
+        assert!(a.is_same_node(Some(&node(&binding, "a"))));
+        assert!(c.is_same_node(Some(&node(&binding, "c"))));
+        assert!(binding.get(&"b".to_string()).is_none());
+        assert_eq!(
+            binding.keys().cloned().collect::<Vec<_>>(),
+            vec!["c", "a", "d"]
+        );
+
+        binding.clear().unwrap();
+        assert!(binding.is_empty());
+        assert_eq!(container.children().length(), 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_list_binding_duplicate_key() {
+        let container = document().create_element("ul").unwrap();
+        let mut binding = ListBinding::new(container.clone());
+        render(&mut binding, &["a"]).unwrap();
+
+        let err = render(&mut binding, &["b", "a", "b"]).unwrap_err();
+        assert_eq!(err.as_string().unwrap(), "duplicate list key `\"b\"`");
+        // the previous rendering is left intact
+        assert_eq!(container.text_content().unwrap(), "a");
+        assert_eq!(binding.len(), 1);
+    }
+}
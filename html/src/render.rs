@@ -1,7 +1,6 @@
 use crate::interface::Hooks;
 use crate::utils::{document, Element, ElementResult};
 use crate::Html;
-use std::collections::BTreeMap;
 pub use std::fmt::{Result, Write};
 pub use std::sync::Arc;
 
@@ -44,11 +43,11 @@ pub trait Render {
         self,
         parent: &mut Element,
         renderables: &mut Renderables,
-    ) -> ElementResult<BTreeMap<String, Element>>
+    ) -> ElementResult<Hooks>
     where
         Self: Sized,
     {
-        let mut map = BTreeMap::new();
+        let mut map = Hooks::new();
         self.render_node(parent, &mut map, renderables)?;
         Ok(map)
     }
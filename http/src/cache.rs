@@ -0,0 +1,694 @@
+//!
+//! Opt-in HTTP response cache backed by `workflow-store`.
+//!
+//! [`CachedClient`] stores successful `GET` responses (body and a selected
+//! set of headers) keyed by the request URL. Cached entries are served
+//! while fresh according to the `Cache-Control: max-age` directive and
+//! revalidated using `ETag` / `If-None-Match` once stale (a `304 Not
+//! Modified` response refreshes the stored entry). Responses marked with
+//! `Cache-Control: no-store` or `Vary: *` are never stored.
+//!
+//! A cached entry is only served to requests carrying the same values of
+//! the request headers listed by the `Vary` response header. Requests with
+//! an `Authorization` header (including a client default) bypass the cache.
+//!
+//! Entry metadata is kept in an index file within the cache folder and
+//! response bodies are stored in separate files. When the total size of
+//! the stored bodies exceeds the cache budget, the least recently used
+//! entries are evicted. Access times updated by cache hits are kept in
+//! memory and persisted with the next index write (a store, revalidation
+//! or eviction) or by [`CachedClient::flush()`]. In the browser, `workflow-store` maps the cache
+//! files onto local storage keys.
+//!
+
+use crate::client::Client;
+use crate::request::{Method, Request};
+use crate::response::Response;
+use crate::result::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use workflow_core::hex::ToHex;
+use workflow_core::time::unixtime_as_millis_u64;
+use workflow_store::fs;
+
+/// Default cache budget (total size of stored response bodies).
+pub const DEFAULT_CACHE_BUDGET: u64 = 32 * 1024 * 1024;
+
+const INDEX_FILENAME: &str = "http-cache-index.json";
+
+/// Response headers retained in the cache.
+const CACHED_HEADERS: &[&str] = &[
+    "cache-control",
+    "content-type",
+    "content-language",
+    "etag",
+    "last-modified",
+    "vary",
+];
+
+/// Per-request cache policy (see [`Request::with_cache_policy()`]).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CachePolicy {
+    /// Serve fresh entries from the cache, revalidate stale
+    /// entries and fetch missing ones.
+    #[default]
+    Default,
+    /// Serve any cached entry regardless of its age; fetch if missing.
+    ForceCache,
+    /// Always contact the server (revalidating the cached entry if possible).
+    NoCache,
+    /// Same as [`CachePolicy::Default`], but if the server can not be
+    /// reached, serve the cached entry regardless of its age.
+    OfflineFirst,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    etag: Option<String>,
+    /// Time the entry was stored or last revalidated (unix time in msec)
+    stored: u64,
+    /// Freshness lifetime in seconds
+    max_age: u64,
+    /// Last access (unix time in msec)
+    accessed: u64,
+    size: u64,
+    /// Request header values selected by the `Vary` response header
+    #[serde(default)]
+    vary: Vec<(String, Option<String>)>,
+}
+
+impl Entry {
+    fn is_fresh(&self, now: u64) -> bool {
+        now.saturating_sub(self.stored) < self.max_age.saturating_mul(1000)
+    }
+
+    /// Checks if the request headers match the values the entry varies on.
+    fn matches(&self, headers: &[(String, String)]) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| header_value(headers, name) == *value)
+    }
+
+    fn response(&self, body: Vec<u8>) -> Response {
+        Response::new(self.status, self.headers.clone(), body)
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct Index {
+    entries: HashMap<String, Entry>,
+    /// Access times have been updated since the index was last written
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl Index {
+    fn size(&self) -> u64 {
+        self.entries.values().map(|entry| entry.size).sum()
+    }
+}
+
+/// Combined value of the request header `name` (case-insensitive).
+fn header_value(headers: &[(String, String)], name: &str) -> Option<String> {
+    let values = headers
+        .iter()
+        .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+        .collect::<Vec<_>>();
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+/// Parsed `Cache-Control` response directives.
+struct CacheControl {
+    no_store: bool,
+    max_age: Option<u64>,
+}
+
+impl CacheControl {
+    fn parse(response: &Response) -> Self {
+        let mut no_store = false;
+        let mut max_age = None;
+        if let Some(value) = response.header("cache-control") {
+            for directive in value.split(',').map(|directive| directive.trim()) {
+                let lowercase = directive.to_ascii_lowercase();
+                if lowercase == "no-store" {
+                    no_store = true;
+                } else if lowercase == "no-cache" {
+                    max_age = Some(0);
+                } else if let Some(seconds) = lowercase.strip_prefix("max-age=") {
+                    max_age = max_age.or_else(|| seconds.trim_matches('"').parse().ok());
+                }
+            }
+        }
+        Self { no_store, max_age }
+    }
+}
+
+/// [`Client`] wrapper caching `GET` responses.
+///
+/// ```ignore
+/// let cache = CachedClient::new(Client::new()?, "~/.myapp/http-cache");
+/// let fonts = cache
+///     .send(Request::new("https://cdn.example.com/fonts.json"))
+///     .await?
+///     .error_for_status()?;
+/// ```
+#[derive(Clone)]
+pub struct CachedClient {
+    client: Client,
+    folder: PathBuf,
+    budget: u64,
+    index: Arc<Mutex<Option<Index>>>,
+}
+
+impl CachedClient {
+    pub fn new(client: Client, folder: impl AsRef<Path>) -> Self {
+        Self {
+            client,
+            folder: folder.as_ref().to_path_buf(),
+            budget: DEFAULT_CACHE_BUDGET,
+            index: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Maximum total size (in bytes) of the cached response bodies.
+    pub fn with_budget(mut self, budget: u64) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Total size of the cached response bodies.
+    pub async fn size(&self) -> Result<u64> {
+        Ok(self.index().await?.size())
+    }
+
+    /// Executes the request according to its [`CachePolicy`].
+    /// Requests other than `GET` and requests with an `Authorization`
+    /// header are passed through to the client.
+    pub async fn send(&self, request: Request) -> Result<Response> {
+        let headers = self
+            .client
+            .request_headers(&request)
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>();
+        if request.method != Method::Get || header_value(&headers, "authorization").is_some() {
+            return self.client.send(request).await;
+        }
+
+        let policy = request.cache;
+        let url = self.client.request_url(&request);
+        let key = Sha256::digest(url.as_bytes()).as_slice().to_hex();
+        let cached = self
+            .lookup(&key)
+            .await?
+            .filter(|(entry, _)| entry.matches(&headers));
+
+        if let Some((entry, body)) = &cached {
+            let now = unixtime_as_millis_u64();
+            let serve = match policy {
+                CachePolicy::ForceCache => true,
+                CachePolicy::Default | CachePolicy::OfflineFirst => entry.is_fresh(now),
+                CachePolicy::NoCache => false,
+            };
+            if serve {
+                return Ok(entry.response(body.clone()));
+            }
+        }
+
+        let mut request = request;
+        if let Some((
+            Entry {
+                etag: Some(etag), ..
+            },
+            _,
+        )) = &cached
+        {
+            if request.header("if-none-match").is_none() {
+                request = request.with_header("If-None-Match", etag);
+            }
+        }
+
+        let response = match self.client.send(request).await {
+            Ok(response) => response,
+            Err(err) => {
+                return match (policy, cached) {
+                    (CachePolicy::OfflineFirst, Some((entry, body))) => Ok(entry.response(body)),
+                    _ => Err(err),
+                };
+            }
+        };
+
+        match cached {
+            Some((entry, body)) if response.status() == 304 => {
+                let entry = self.revalidated(&key, entry, &response).await?;
+                Ok(entry.response(body))
+            }
+            _ => {
+                if response.status() == 200 {
+                    self.store(&key, url, &response, &headers).await?;
+                }
+                Ok(response)
+            }
+        }
+    }
+
+    /// Removes all cached entries.
+    pub async fn clear(&self) -> Result<()> {
+        let index = self.index().await?;
+        for key in index.entries.keys() {
+            fs::remove(&self.body_path(key)).await.ok();
+        }
+        self.update_index(|index| index.entries.clear()).await
+    }
+
+    /// Persists the access times updated by cache hits since the last
+    /// index write, so that the LRU order survives a restart.
+    pub async fn flush(&self) -> Result<()> {
+        self.index().await?;
+        let dirty = self
+            .index
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|index| index.dirty);
+        if dirty {
+            self.update_index(|_| {}).await?;
+        }
+        Ok(())
+    }
+
+    fn body_path(&self, key: &str) -> PathBuf {
+        self.folder.join(format!("{key}.bin"))
+    }
+
+    async fn index(&self) -> Result<Index> {
+        if let Some(index) = self.index.lock().unwrap().as_ref() {
+            return Ok(index.clone());
+        }
+
+        fs::create_dir_all(&self.folder).await?;
+        let filename = self.folder.join(INDEX_FILENAME);
+        let index = if fs::exists(&filename).await? {
+            // a corrupted index is discarded
+            fs::read_json::<Index>(&filename).await.unwrap_or_default()
+        } else {
+            Index::default()
+        };
+        Ok(self.index.lock().unwrap().get_or_insert(index).clone())
+    }
+
+    async fn update_index<F>(&self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Index),
+    {
+        self.index().await?;
+        let index = {
+            let mut guard = self.index.lock().unwrap();
+            let index = guard.as_mut().expect("cache index");
+            f(index);
+            index.dirty = false;
+            index.clone()
+        };
+        fs::write_json(&self.folder.join(INDEX_FILENAME), &index).await?;
+        Ok(())
+    }
+
+    async fn lookup(&self, key: &str) -> Result<Option<(Entry, Vec<u8>)>> {
+        self.index().await?;
+        let entry = self
+            .index
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|index| index.entries.get(key).cloned());
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+        match fs::read(&self.body_path(key)).await {
+            Ok(body) => {
+                // the access time is persisted lazily (see `flush()`)
+                let accessed = unixtime_as_millis_u64();
+                if let Some(index) = self.index.lock().unwrap().as_mut() {
+                    if let Some(entry) = index.entries.get_mut(key) {
+                        entry.accessed = accessed;
+                        index.dirty = true;
+                    }
+                }
+                Ok(Some((entry, body)))
+            }
+            Err(_) => {
+                // the body has been removed externally
+                self.update_index(|index| {
+                    index.entries.remove(key);
+                })
+                .await?;
+                Ok(None)
+            }
+        }
+    }
+
+    async fn revalidated(&self, key: &str, mut entry: Entry, response: &Response) -> Result<Entry> {
+        let control = CacheControl::parse(response);
+        let now = unixtime_as_millis_u64();
+        entry.stored = now;
+        entry.accessed = now;
+        if let Some(max_age) = control.max_age {
+            entry.max_age = max_age;
+        }
+        if let Some(etag) = response.header("etag") {
+            entry.etag = Some(etag.to_string());
+        }
+        let updated = entry.clone();
+        self.update_index(|index| {
+            index.entries.insert(key.to_string(), updated);
+        })
+        .await?;
+        Ok(entry)
+    }
+
+    async fn store(
+        &self,
+        key: &str,
+        url: String,
+        response: &Response,
+        headers: &[(String, String)],
+    ) -> Result<()> {
+        let control = CacheControl::parse(response);
+        let size = response.bytes().len() as u64;
+        let vary = response
+            .header("vary")
+            .map(|vary| {
+                vary.split(',')
+                    .map(|name| name.trim().to_ascii_lowercase())
+                    .filter(|name| !name.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if control.no_store || size > self.budget || vary.iter().any(|name| name == "*") {
+            if self.index().await?.entries.contains_key(key) {
+                fs::remove(&self.body_path(key)).await.ok();
+                self.update_index(|index| {
+                    index.entries.remove(key);
+                })
+                .await?;
+            }
+            return Ok(());
+        }
+
+        fs::write(&self.body_path(key), response.bytes()).await?;
+
+        let now = unixtime_as_millis_u64();
+        let entry = Entry {
+            url,
+            status: response.status(),
+            headers: response
+                .headers()
+                .iter()
+                .filter(|(name, _)| CACHED_HEADERS.contains(&name.to_ascii_lowercase().as_str()))
+                .cloned()
+                .collect(),
+            etag: response.header("etag").map(String::from),
+            stored: now,
+            max_age: control.max_age.unwrap_or_default(),
+            accessed: now,
+            size,
+            vary: vary
+                .into_iter()
+                .map(|name| {
+                    let value = header_value(headers, &name);
+                    (name, value)
+                })
+                .collect(),
+        };
+
+        let budget = self.budget;
+        let mut evicted = Vec::new();
+        self.update_index(|index| {
+            index.entries.insert(key.to_string(), entry);
+            while index.size() > budget {
+                let Some(lru) = index
+                    .entries
+                    .iter()
+                    .filter(|(candidate, _)| candidate.as_str() != key)
+                    .min_by_key(|(_, entry)| entry.accessed)
+                    .map(|(key, _)| key.clone())
+                else {
+                    break;
+                };
+                index.entries.remove(&lru);
+                evicted.push(lru);
+            }
+        })
+        .await?;
+
+        for key in evicted {
+            fs::remove(&self.body_path(&key)).await.ok();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::RetryPolicy;
+    use crate::test_server;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Server counting requests:
+    /// - `/static` responds with `max-age=60`
+    /// - `/etag` responds with `no-cache` and `ETag: "v1"`; requests with
+    ///   `If-None-Match: "v1"` receive `304` and `max-age=60`
+    /// - `/vary` responds with `Vary: Accept-Language` and the
+    ///   `Accept-Language` request header as the body
+    /// - `/vary-any` responds with `Vary: *`
+    /// - any other path responds with its name as the body
+    fn counting_server() -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let url = test_server::serve(move |request, stream| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let path = request.target.as_str();
+            let (status, headers, body) = match path {
+                "/static" => (200, "Cache-Control: max-age=60\r\n", "static"),
+                "/etag" if request.header("if-none-match") == Some("\"v1\"") => {
+                    (304, "Cache-Control: max-age=60\r\nETag: \"v1\"\r\n", "")
+                }
+                "/etag" => (200, "Cache-Control: no-cache\r\nETag: \"v1\"\r\n", "etag"),
+                "/vary" => (
+                    200,
+                    "Cache-Control: max-age=60\r\nVary: Accept-Language\r\n",
+                    request.header("accept-language").unwrap_or("none"),
+                ),
+                "/vary-any" => (200, "Cache-Control: max-age=60\r\nVary: *\r\n", "any"),
+                _ => (200, "Cache-Control: max-age=60\r\n", &path[1..]),
+            };
+            test_server::respond(stream, status, headers, body.as_bytes());
+        });
+        (url, requests)
+    }
+
+    fn cache_folder(name: &str) -> PathBuf {
+        let folder =
+            std::env::temp_dir().join(format!("workflow-http-cache-{}-{name}", std::process::id()));
+        std::fs::remove_dir_all(&folder).ok();
+        folder
+    }
+
+    #[tokio::test]
+    async fn test_cache_max_age() -> Result<()> {
+        let (url, requests) = counting_server();
+        let folder = cache_folder("max-age");
+        let client = Client::builder().with_base_url(url).build()?;
+        let cache = CachedClient::new(client.clone(), &folder);
+
+        let response = cache.send(Request::new("/static")).await?;
+        assert_eq!(response.text()?, "static");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let response = cache.send(Request::new("/static")).await?;
+        assert_eq!(response.header("cache-control"), Some("max-age=60"));
+        assert_eq!(response.text()?, "static");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // the index persists across instances
+        let cache = CachedClient::new(client, &folder);
+        assert_eq!(cache.send(Request::new("/static")).await?.text()?, "static");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let request = Request::new("/static").with_cache_policy(CachePolicy::NoCache);
+        assert_eq!(cache.send(request).await?.text()?, "static");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        cache.clear().await?;
+        assert_eq!(cache.size().await?, 0);
+        cache.send(Request::new("/static")).await?;
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        std::fs::remove_dir_all(&folder).ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_etag_revalidation() -> Result<()> {
+        let (url, requests) = counting_server();
+        let folder = cache_folder("etag");
+        let client = Client::builder().with_base_url(url).build()?;
+        let cache = CachedClient::new(client, &folder);
+
+        assert_eq!(cache.send(Request::new("/etag")).await?.text()?, "etag");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // stale entry (no-cache), revalidated with 304
+        let response = cache.send(Request::new("/etag")).await?;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text()?, "etag");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // the revalidation refreshed the entry with max-age=60
+        assert_eq!(cache.send(Request::new("/etag")).await?.text()?, "etag");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        std::fs::remove_dir_all(&folder).ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_vary() -> Result<()> {
+        let (url, requests) = counting_server();
+        let folder = cache_folder("vary");
+        let client = Client::builder().with_base_url(&url).build()?;
+        let cache = CachedClient::new(client, &folder);
+        let request =
+            |language: &str| Request::new("/vary").with_header("Accept-Language", language);
+
+        assert_eq!(cache.send(request("en")).await?.text()?, "en");
+        assert_eq!(cache.send(request("en")).await?.text()?, "en");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // a different value of a header named by `Vary` is a cache miss
+        assert_eq!(cache.send(request("de")).await?.text()?, "de");
+        assert_eq!(cache.send(Request::new("/vary")).await?.text()?, "none");
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+        assert_eq!(cache.send(Request::new("/vary")).await?.text()?, "none");
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        // `Vary: *` responses are not stored
+        cache.send(Request::new("/vary-any")).await?;
+        cache.send(Request::new("/vary-any")).await?;
+        assert_eq!(requests.load(Ordering::SeqCst), 5);
+
+        std::fs::remove_dir_all(&folder).ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_authorization() -> Result<()> {
+        let (url, requests) = counting_server();
+        let folder = cache_folder("authorization");
+        let cache = CachedClient::new(Client::builder().with_base_url(&url).build()?, &folder);
+
+        let request = || Request::new("/private").with_header("Authorization", "Bearer token");
+        assert_eq!(cache.send(request()).await?.text()?, "private");
+        assert_eq!(cache.send(request()).await?.text()?, "private");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert_eq!(cache.size().await?, 0);
+
+        // client default headers are taken into account
+        let client = Client::builder()
+            .with_base_url(&url)
+            .with_header("Authorization", "Bearer token")
+            .build()?;
+        let cache = CachedClient::new(client, &folder);
+        cache.send(Request::new("/private")).await?;
+        cache.send(Request::new("/private")).await?;
+        assert_eq!(requests.load(Ordering::SeqCst), 4);
+        assert_eq!(cache.size().await?, 0);
+
+        std::fs::remove_dir_all(&folder).ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_eviction() -> Result<()> {
+        let (url, requests) = counting_server();
+        let folder = cache_folder("eviction");
+        let cache = CachedClient::new(Client::new()?, &folder).with_budget(10);
+
+        // LRU eviction
+        cache.send(Request::new(format!("{url}/first"))).await?;
+        cache.send(Request::new(format!("{url}/second"))).await?;
+        assert_eq!(cache.size().await?, 6);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        cache.send(Request::new(format!("{url}/second"))).await?;
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        cache.send(Request::new(format!("{url}/first"))).await?;
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        std::fs::remove_dir_all(&folder).ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_hit_does_not_write_index() -> Result<()> {
+        let (url, requests) = counting_server();
+        let folder = cache_folder("hit");
+        let client = Client::builder().with_base_url(url).build()?;
+        let cache = CachedClient::new(client, &folder);
+        let index = || std::fs::read_to_string(folder.join(INDEX_FILENAME)).unwrap();
+
+        cache.send(Request::new("/static")).await?;
+        let stored = index();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        cache.send(Request::new("/static")).await?;
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert_eq!(index(), stored);
+
+        // the updated access time is persisted by `flush()`
+        cache.flush().await?;
+        assert_ne!(index(), stored);
+        let flushed = index();
+        cache.flush().await?;
+        assert_eq!(index(), flushed);
+
+        std::fs::remove_dir_all(&folder).ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_offline() -> Result<()> {
+        // serve a single (stale) response, then stop listening
+        let (url, server) = test_server::serve_once(|_, stream| {
+            test_server::respond(stream, 200, "Cache-Control: no-cache\r\n", b"offline");
+        });
+        let url = format!("{url}/offline");
+
+        let folder = cache_folder("offline");
+        let client = Client::builder()
+            .with_retry_policy(RetryPolicy::none())
+            .build()?;
+        let cache = CachedClient::new(client, &folder);
+        assert_eq!(cache.send(Request::new(&url)).await?.text()?, "offline");
+        server.join().unwrap();
+
+        assert!(cache.send(Request::new(&url)).await.is_err());
+        let request = Request::new(&url).with_cache_policy(CachePolicy::ForceCache);
+        assert_eq!(cache.send(request).await?.text()?, "offline");
+        let request = Request::new(&url).with_cache_policy(CachePolicy::OfflineFirst);
+        assert_eq!(cache.send(request).await?.text()?, "offline");
+
+        std::fs::remove_dir_all(&folder).ok();
+        Ok(())
+    }
+}
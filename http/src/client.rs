@@ -0,0 +1,529 @@
+//!
+//! HTTP [`Client`] holding request defaults (base URL, headers, timeouts
+//! and a [`RetryPolicy`]).
+//!
+//! On native targets the client owns a `reqwest` connection pool that
+//! is reused across requests; cloning the client shares the pool.
+//! On WASM32 requests are executed using the browser `fetch` API.
+//! On both targets the total request timeout is applied by `reqwest`
+//! (`RequestBuilder::timeout()`); the connection timeout is native only.
+//!
+
+use crate::error::Error;
+use crate::request::Request;
+use crate::response::Response;
+use crate::result::Result;
+use rand::Rng;
+use std::sync::Arc;
+use std::time::Duration;
+use workflow_core::task::sleep;
+
+/// Retry policy applied to transient failures: connection errors
+/// as well as `429 Too Many Requests` and `5xx` responses.
+///
+/// Only idempotent requests (see [`Method::is_idempotent()`](crate::Method::is_idempotent))
+/// are retried unless [`RetryPolicy::with_non_idempotent()`] is enabled.
+/// Requests with a streamed body (see [`Request::is_repeatable()`]) are
+/// never retried.
+/// The delay between attempts grows exponentially from `initial_backoff`
+/// up to `max_backoff`, randomized using "equal jitter". A `Retry-After`
+/// response header (in seconds) takes precedence over the backoff delay
+/// (capped by `max_backoff`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the initial request
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub jitter: bool,
+    pub non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(10),
+            jitter: true,
+            non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Policy performing a single attempt.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Allow retrying non-idempotent requests (such as `POST`).
+    pub fn with_non_idempotent(mut self, non_idempotent: bool) -> Self {
+        self.non_idempotent = non_idempotent;
+        self
+    }
+
+    /// Delay preceding the attempt following the failed `attempt` (1-based).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .initial_backoff
+            .saturating_mul(1 << exp)
+            .min(self.max_backoff);
+        if self.jitter && !delay.is_zero() {
+            let half = delay / 2;
+            half + half.mul_f64(rand::thread_rng().gen::<f64>())
+        } else {
+            delay
+        }
+    }
+
+    fn is_retryable_status(status: u16) -> bool {
+        status == 429 || (500..600).contains(&status)
+    }
+
+    fn is_retryable_error(err: &Error) -> bool {
+        match err {
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::Reqwest(err) => err.is_connect(),
+            // `fetch` network failures are reported as request errors
+            #[cfg(target_arch = "wasm32")]
+            Error::Reqwest(err) => err.is_request() && !err.is_timeout(),
+            _ => false,
+        }
+    }
+}
+
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .header("retry-after")
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Builder for [`Client`].
+#[derive(Debug, Default, Clone)]
+pub struct ClientBuilder {
+    base_url: Option<String>,
+    user_agent: Option<String>,
+    headers: Vec<(String, String)>,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    retry: RetryPolicy,
+}
+
+impl ClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Base URL prepended to request URLs that are not absolute.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Default header sent with every request unless
+    /// the request supplies a header with the same name.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Connection timeout (not supported by `fetch`, ignored on WASM32).
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Default total request timeout, covering the connection
+    /// as well as reading of the response body.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let mut builder = reqwest::Client::builder();
+        cfg_if::cfg_if! {
+            if #[cfg(not(target_arch = "wasm32"))] {
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+            }
+        }
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        Ok(Client {
+            inner: Arc::new(Inner {
+                client: builder.build()?,
+                base_url: self.base_url,
+                headers: self.headers,
+                timeout: self.timeout,
+                retry: self.retry,
+            }),
+        })
+    }
+}
+
+struct Inner {
+    client: reqwest::Client,
+    base_url: Option<String>,
+    headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    retry: RetryPolicy,
+}
+
+/// HTTP client executing [`Request`]s with the configured defaults.
+///
+/// ```ignore
+/// let client = Client::builder()
+///     .with_base_url("https://cdn.example.com/v1")
+///     .with_header("X-Api-Key", key)
+///     .with_timeout(Duration::from_secs(30))
+///     .with_retry_policy(RetryPolicy::default().with_max_attempts(5))
+///     .build()?;
+/// let manifest: Manifest = client
+///     .send(Request::new("manifest.json"))
+///     .await?
+///     .error_for_status()?
+///     .json()?;
+/// ```
+#[derive(Clone)]
+pub struct Client {
+    inner: Arc<Inner>,
+}
+
+impl Client {
+    /// Creates a client with the default [`RetryPolicy`] and no timeouts.
+    pub fn new() -> Result<Self> {
+        ClientBuilder::new().build()
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// Client used by [`Request::send()`]: no defaults and no retries.
+    pub(crate) fn shared() -> Self {
+        let build = || {
+            ClientBuilder::new()
+                .with_retry_policy(RetryPolicy::none())
+                .build()
+                .expect("default http client")
+        };
+        cfg_if::cfg_if! {
+            if #[cfg(target_arch = "wasm32")] {
+                build()
+            } else {
+                static SHARED: std::sync::OnceLock<Client> = std::sync::OnceLock::new();
+                SHARED.get_or_init(build).clone()
+            }
+        }
+    }
+
+    pub(crate) fn reqwest(&self) -> &reqwest::Client {
+        &self.inner.client
+    }
+
+    pub fn base_url(&self) -> Option<&str> {
+        self.inner.base_url.as_deref()
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.inner.retry
+    }
+
+    fn url(&self, url: &str) -> String {
+        match &self.inner.base_url {
+            Some(base_url) if !url.contains("://") => format!(
+                "{}/{}",
+                base_url.trim_end_matches('/'),
+                url.trim_start_matches('/')
+            ),
+            _ => url.to_string(),
+        }
+    }
+
+    /// Absolute request URL including the query string.
+    pub(crate) fn request_url(&self, request: &Request) -> String {
+        let url = self.url(&request.url);
+        if request.query.is_empty() {
+            url
+        } else {
+            let query = request
+                .query
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect::<Vec<_>>()
+                .join("&");
+            let separator = if url.contains('?') { '&' } else { '?' };
+            format!("{url}{separator}{query}")
+        }
+    }
+
+    /// Headers sent with the request: client defaults not overridden by
+    /// the request, followed by the user agent and the request headers.
+    pub(crate) fn request_headers<'r>(&'r self, request: &'r Request) -> Vec<(&'r str, &'r str)> {
+        let defaults = self
+            .inner
+            .headers
+            .iter()
+            .filter(|(name, _)| request.header(name).is_none());
+        let user_agent = request
+            .user_agent
+            .iter()
+            .map(|user_agent| ("User-Agent", user_agent.as_str()));
+        defaults
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .chain(user_agent)
+            .chain(
+                request
+                    .headers
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str())),
+            )
+            .collect()
+    }
+
+    async fn attempt(&self, request: &Request) -> Result<Response> {
+        let mut req = self
+            .inner
+            .client
+            .request(request.method.into(), self.url(&request.url));
+        if !request.query.is_empty() {
+            req = req.query(&request.query);
+        }
+        for (name, value) in self.request_headers(request) {
+            req = req.header(name, value);
+        }
+        if let Some(timeout) = request.timeout.or(self.inner.timeout) {
+            req = req.timeout(timeout);
+        }
+        if let Some(multipart) = &request.multipart {
+            req = req.multipart(multipart.to_form()?);
+        } else if let Some(body) = &request.body {
+            req = req.body(body.clone());
+        }
+
+        let resp = req.send().await?;
+        let status = resp.status().as_u16();
+        let headers = resp
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).to_string(),
+                )
+            })
+            .collect();
+        let body = resp.bytes().await?.to_vec();
+        Ok(Response::new(status, headers, body))
+    }
+
+    pub(crate) async fn execute(&self, request: Request) -> Result<Response> {
+        let policy = request.retry.as_ref().unwrap_or(&self.inner.retry);
+        let retryable =
+            (policy.non_idempotent || request.method.is_idempotent()) && request.is_repeatable();
+
+        let mut attempt = 1;
+        loop {
+            let result = self.attempt(&request).await;
+            if !retryable || attempt >= policy.max_attempts {
+                return result;
+            }
+            let delay = match &result {
+                Ok(resp) if RetryPolicy::is_retryable_status(resp.status()) => retry_after(resp)
+                    .map(|delay| delay.min(policy.max_backoff))
+                    .unwrap_or_else(|| policy.backoff(attempt)),
+                Err(err) if RetryPolicy::is_retryable_error(err) => policy.backoff(attempt),
+                _ => return result,
+            };
+            sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{self, TestRequest};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::time::Instant;
+
+    /// Server responding with the supplied sequence of
+    /// `(status, extra headers)` (the last entry is repeated).
+    /// Returns the url and the log of received requests.
+    fn flaky_server(script: Vec<(u16, &'static str)>) -> (String, Arc<Mutex<Vec<TestRequest>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        let next = AtomicUsize::new(0);
+        let url = test_server::serve(move |request, stream| {
+            log.lock().unwrap().push(request);
+            let idx = next.fetch_add(1, Ordering::SeqCst);
+            let (status, headers) = script[idx.min(script.len() - 1)];
+            test_server::respond(stream, status, headers, b"ok");
+        });
+        (url, requests)
+    }
+
+    fn fast_retries() -> RetryPolicy {
+        RetryPolicy::default().with_backoff(Duration::from_millis(1), Duration::from_millis(10))
+    }
+
+    #[tokio::test]
+    async fn test_client_retry() -> Result<()> {
+        let (url, requests) = flaky_server(vec![(502, ""), (503, ""), (200, "")]);
+        let client = Client::builder()
+            .with_base_url(format!("{url}/api/"))
+            .with_header("X-Api-Key", "key")
+            .with_header("Accept", "text/plain")
+            .with_retry_policy(fast_retries())
+            .build()?;
+        let response = client
+            .send(Request::new("/items").with_header("Accept", "application/json"))
+            .await?;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text()?, "ok");
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].target, "/api/items");
+        assert_eq!(requests[0].header("x-api-key"), Some("key"));
+        assert_eq!(
+            requests[0].header_values("accept"),
+            vec!["application/json"]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_retry_limits() -> Result<()> {
+        // non-idempotent requests are not retried by default
+        let (url, requests) = flaky_server(vec![(502, ""), (502, ""), (200, "")]);
+        let client = Client::builder()
+            .with_base_url(url)
+            .with_retry_policy(fast_retries())
+            .build()?;
+        let response = client
+            .send(Request::post("/items").with_text("item"))
+            .await?;
+        assert_eq!(response.status(), 502);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        // ... unless enabled by the per-request policy
+        let response = client
+            .send(
+                Request::post("/items")
+                    .with_text("item")
+                    .with_retry_policy(fast_retries().with_non_idempotent(true)),
+            )
+            .await?;
+        assert_eq!(response.status(), 200);
+        assert_eq!(requests.lock().unwrap().len(), 3);
+
+        // the last failure is returned once the attempts are exhausted
+        let (url, requests) = flaky_server(vec![(500, "")]);
+        let response = client.send(Request::new(format!("{url}/items"))).await?;
+        assert_eq!(response.status(), 500);
+        assert_eq!(requests.lock().unwrap().len(), 3);
+
+        // client errors are not retried
+        let (url, requests) = flaky_server(vec![(404, ""), (200, "")]);
+        let response = client.send(Request::new(format!("{url}/items"))).await?;
+        assert_eq!(response.status(), 404);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_retry_after() -> Result<()> {
+        let (url, requests) = flaky_server(vec![(429, "Retry-After: 1\r\n"), (200, "")]);
+        let client = Client::builder()
+            .with_retry_policy(
+                RetryPolicy::default()
+                    .with_backoff(Duration::from_millis(1), Duration::from_secs(5)),
+            )
+            .build()?;
+        let start = Instant::now();
+        let response = client.send(Request::new(url)).await?;
+        assert_eq!(response.status(), 200);
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(requests.lock().unwrap().len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_connect_error() -> Result<()> {
+        // bind and release a port to obtain an address that refuses connections
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let client = Client::builder()
+            .with_connect_timeout(Duration::from_secs(1))
+            .with_retry_policy(fast_retries())
+            .build()?;
+        let result = client.send(Request::new(format!("http://{addr}"))).await;
+        assert!(matches!(result, Err(Error::Reqwest(err)) if err.is_connect()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let policy = RetryPolicy::default()
+            .with_backoff(Duration::from_millis(100), Duration::from_secs(1))
+            .with_jitter(false);
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(5), Duration::from_secs(1));
+        assert_eq!(policy.backoff(100), Duration::from_secs(1));
+
+        let policy = policy.with_jitter(true);
+        for attempt in 1..8 {
+            let delay = policy.backoff(attempt);
+            let max = policy.clone().with_jitter(false).backoff(attempt);
+            assert!(delay >= max / 2 && delay <= max);
+        }
+    }
+}
@@ -0,0 +1,538 @@
+//!
+//! Streaming downloads with progress reporting, resume support
+//! and optional `SHA-256` verification.
+//!
+//! On native targets [`download()`] writes the response body into
+//! `<destination>.part` and renames it to the destination once the
+//! transfer has completed (and the checksum has been verified).
+//! If a partial file is present, the download is resumed using
+//! an HTTP `Range` request, provided the server advertises
+//! `Accept-Ranges: bytes`. Aborting a download leaves the partial
+//! file in place, allowing a subsequent call to resume it.
+//!
+//! [`download_with_writer()`] is available on all targets (including
+//! WASM32, where there is no file system access) and delivers the
+//! response body in chunks to the supplied callback.
+//!
+
+use crate::client::Client;
+use crate::error::Error;
+use crate::result::Result;
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+use workflow_core::abortable::Abortable;
+use workflow_core::channel::Sender;
+use workflow_core::hex::{FromHex, ToHex};
+
+/// Download progress notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes received so far (including bytes of a resumed partial file)
+    pub received: u64,
+    /// Total size if advertised by the server via `Content-Length`
+    pub total: Option<u64>,
+}
+
+/// Options for [`download()`] and [`download_with_writer()`].
+#[derive(Clone)]
+pub struct DownloadOptions {
+    pub user_agent: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub sha256: Option<Vec<u8>>,
+    pub progress: Option<Sender<Progress>>,
+    pub abortable: Option<Abortable>,
+    pub resume: bool,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            user_agent: None,
+            headers: Vec::new(),
+            sha256: None,
+            progress: None,
+            abortable: None,
+            resume: true,
+        }
+    }
+}
+
+impl DownloadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Expected `SHA-256` digest of the complete file (hex encoded).
+    pub fn with_sha256(mut self, hex: &str) -> Result<Self> {
+        let digest = Vec::<u8>::from_hex(hex).map_err(Error::custom)?;
+        if digest.len() != 32 {
+            return Err(Error::custom(format!("invalid sha256 digest: {hex}")));
+        }
+        self.sha256 = Some(digest);
+        Ok(self)
+    }
+
+    /// Channel sender receiving a [`Progress`] notification after each
+    /// received chunk. The notifications are posted using `try_send()`,
+    /// as such an unbounded channel should be used.
+    pub fn with_progress(mut self, sender: Sender<Progress>) -> Self {
+        self.progress = Some(sender);
+        self
+    }
+
+    pub fn with_abortable(mut self, abortable: &Abortable) -> Self {
+        self.abortable = Some(abortable.clone());
+        self
+    }
+
+    /// Enables or disables resuming from a partial file (enabled by default).
+    pub fn with_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    fn check_aborted(&self) -> Result<()> {
+        match &self.abortable {
+            Some(abortable) if abortable.is_aborted() => Err(Error::Aborted),
+            _ => Ok(()),
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        let mut req = Client::shared().reqwest().request(method, url);
+        if let Some(user_agent) = &self.user_agent {
+            req = req.header("User-Agent", user_agent);
+        }
+        for (name, value) in &self.headers {
+            req = req.header(name, value);
+        }
+        req
+    }
+}
+
+/// Tracks the transfer state: received bytes, progress
+/// notifications and the running `SHA-256` digest.
+struct Transfer<'options> {
+    options: &'options DownloadOptions,
+    hasher: Sha256,
+    received: u64,
+    total: Option<u64>,
+}
+
+impl<'options> Transfer<'options> {
+    fn new(options: &'options DownloadOptions) -> Self {
+        Self {
+            options,
+            hasher: Sha256::new(),
+            received: 0,
+            total: None,
+        }
+    }
+
+    fn restart(&mut self, total: Option<u64>) {
+        self.hasher = Sha256::new();
+        self.received = 0;
+        self.total = total;
+    }
+
+    fn update(&mut self, chunk: &[u8]) {
+        if self.options.sha256.is_some() {
+            self.hasher.update(chunk);
+        }
+        self.received += chunk.len() as u64;
+    }
+
+    fn notify(&self) {
+        if let Some(sender) = &self.options.progress {
+            sender
+                .try_send(Progress {
+                    received: self.received,
+                    total: self.total,
+                })
+                .ok();
+        }
+    }
+
+    fn verify(self) -> Result<()> {
+        if let Some(expected) = &self.options.sha256 {
+            let actual = self.hasher.finalize();
+            if actual.as_slice() != expected.as_slice() {
+                return Err(Error::Checksum {
+                    expected: expected.to_hex(),
+                    actual: actual.as_slice().to_hex(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Downloads `url` passing each received chunk to `writer`.
+pub async fn download_with_writer<W>(
+    url: impl Into<String>,
+    options: DownloadOptions,
+    writer: W,
+) -> Result<()>
+where
+    W: FnMut(&[u8]) -> Result<()> + 'static,
+{
+    let url = url.into();
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            use workflow_core::task::call_async_no_send;
+            call_async_no_send!(download_with_writer_impl(url, options, writer).await)
+        } else {
+            download_with_writer_impl(url, options, writer).await
+        }
+    }
+}
+
+async fn download_with_writer_impl<W>(
+    url: String,
+    options: DownloadOptions,
+    mut writer: W,
+) -> Result<()>
+where
+    W: FnMut(&[u8]) -> Result<()> + 'static,
+{
+    options.check_aborted()?;
+    let resp = options.request(reqwest::Method::GET, &url).send().await?;
+    if !resp.status().is_success() {
+        return Err(Error::Status(resp.status().as_u16(), resp.text().await?));
+    }
+
+    let mut transfer = Transfer::new(&options);
+    transfer.restart(resp.content_length());
+    transfer.notify();
+    let mut stream = resp.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        writer(&chunk)?;
+        transfer.update(&chunk);
+        transfer.notify();
+        options.check_aborted()?;
+    }
+    transfer.verify()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use native::download;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use super::*;
+    use async_std::fs::{File, OpenOptions};
+    use async_std::io::{ReadExt, WriteExt};
+    use std::path::{Path, PathBuf};
+    use workflow_store::fs;
+
+    fn partial_path(destination: &Path) -> PathBuf {
+        let mut partial = destination.as_os_str().to_owned();
+        partial.push(".part");
+        PathBuf::from(partial)
+    }
+
+    async fn partial_len(partial: &Path) -> u64 {
+        async_std::fs::metadata(partial)
+            .await
+            .map(|metadata| metadata.len())
+            .unwrap_or_default()
+    }
+
+    /// Returns the resource size if the server supports byte range requests.
+    async fn probe_ranges(url: &str, options: &DownloadOptions) -> Result<Option<u64>> {
+        let resp = options.request(reqwest::Method::HEAD, url).send().await?;
+        let accepts_ranges = resp
+            .headers()
+            .get(reqwest::header::ACCEPT_RANGES)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.eq_ignore_ascii_case("bytes"));
+        if resp.status().is_success() && accepts_ranges {
+            // `Response::content_length()` reflects the (empty) body of a HEAD response
+            Ok(resp
+                .headers()
+                .get(reqwest::header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Feeds the contents of an existing partial file to the transfer digest.
+    async fn hash_partial(partial: &Path, transfer: &mut Transfer<'_>) -> Result<()> {
+        let mut file = File::open(partial).await?;
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            let len = file.read(&mut buffer).await?;
+            if len == 0 {
+                break;
+            }
+            transfer.update(&buffer[..len]);
+        }
+        Ok(())
+    }
+
+    /// Downloads `url` into the `destination` file, resuming from a
+    /// previously interrupted download if possible.
+    pub async fn download(
+        url: impl Into<String>,
+        destination: impl AsRef<Path>,
+        options: DownloadOptions,
+    ) -> Result<()> {
+        let url = url.into();
+        let destination = destination.as_ref();
+        let partial = partial_path(destination);
+        if let Some(parent) = destination.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent).await?;
+            }
+        }
+
+        options.check_aborted()?;
+        let mut transfer = Transfer::new(&options);
+
+        let mut offset = if options.resume {
+            partial_len(&partial).await
+        } else {
+            0
+        };
+        let mut complete = false;
+        if offset > 0 {
+            match probe_ranges(&url, &options).await? {
+                Some(total) if offset <= total => {
+                    complete = offset == total;
+                    transfer.restart(Some(total));
+                    if options.sha256.is_some() {
+                        hash_partial(&partial, &mut transfer).await?;
+                    } else {
+                        transfer.received = offset;
+                    }
+                }
+                _ => offset = 0,
+            }
+        }
+
+        if !complete {
+            let mut req = options.request(reqwest::Method::GET, &url);
+            if offset > 0 {
+                req = req.header(reqwest::header::RANGE, format!("bytes={offset}-"));
+            }
+            let resp = req.send().await?;
+            if resp.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+                if !resp.status().is_success() {
+                    return Err(Error::Status(resp.status().as_u16(), resp.text().await?));
+                }
+                // the range has not been honored, start over
+                offset = 0;
+            }
+
+            if offset == 0 {
+                transfer.restart(resp.content_length());
+            }
+            transfer.notify();
+
+            let mut file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(offset > 0)
+                .truncate(offset == 0)
+                .open(&partial)
+                .await?;
+
+            let mut stream = resp.bytes_stream();
+            let result: Result<()> = async {
+                while let Some(chunk) = stream.next().await {
+                    let chunk = chunk?;
+                    file.write_all(&chunk).await?;
+                    transfer.update(&chunk);
+                    transfer.notify();
+                    options.check_aborted()?;
+                }
+                Ok(())
+            }
+            .await;
+
+            // keep the partial file consistent with the received data
+            // so that an interrupted download can be resumed
+            file.flush().await?;
+            file.sync_all().await?;
+            drop(file);
+            result?;
+        } else {
+            transfer.notify();
+        }
+
+        if let Err(err) = transfer.verify() {
+            fs::remove(&partial).await?;
+            return Err(err);
+        }
+
+        fs::rename(&partial, &destination.to_path_buf()).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use workflow_core::channel::Channel;
+
+    const SIZE: usize = 4 * 1024 * 1024;
+    const PIECE: usize = 64 * 1024;
+
+    fn content() -> Vec<u8> {
+        (0..SIZE).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// Server serving [`content()`] with `Range` request support.
+    /// The body is sent in pieces with a small delay to allow aborting
+    /// a download mid-transfer. Returns the url and the log of received
+    /// `Range` headers.
+    fn file_server() -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let log = ranges.clone();
+        let content = Arc::new(content());
+        let url = test_server::serve(move |request, stream| {
+            if request.method == "HEAD" {
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nAccept-Ranges: bytes\r\nContent-Length: {SIZE}\r\nConnection: close\r\n\r\n"
+                )
+                .unwrap();
+                return;
+            }
+
+            let range = request.header("range").map(String::from);
+            log.lock().unwrap().push(range.clone());
+            let start = range
+                .as_deref()
+                .and_then(|range| range.strip_prefix("bytes="))
+                .and_then(|range| range.strip_suffix('-'))
+                .map(|start| start.parse::<usize>().unwrap())
+                .unwrap_or(0);
+            if start > 0 {
+                write!(
+                    stream,
+                    "HTTP/1.1 206 Partial Content\r\nAccept-Ranges: bytes\r\nContent-Range: bytes {start}-{}/{SIZE}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    SIZE - 1,
+                    SIZE - start
+                )
+                .unwrap();
+            } else {
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nAccept-Ranges: bytes\r\nContent-Length: {SIZE}\r\nConnection: close\r\n\r\n"
+                )
+                .unwrap();
+            }
+            for piece in content[start..].chunks(PIECE) {
+                if stream.write_all(piece).is_err() {
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        });
+        (format!("{url}/archive.bin"), ranges)
+    }
+
+    fn sha256_hex(data: &[u8]) -> String {
+        Sha256::digest(data).as_slice().to_hex()
+    }
+
+    #[tokio::test]
+    async fn test_download_resume() -> Result<()> {
+        let (url, ranges) = file_server();
+        let dir =
+            std::env::temp_dir().join(format!("workflow-http-download-{}", std::process::id()));
+        let destination = dir.join("archive.bin");
+        let content = content();
+
+        // abort the transfer once the first megabyte has been received
+        let abortable = Abortable::new();
+        let progress = Channel::<Progress>::unbounded();
+        let options = DownloadOptions::new()
+            .with_sha256(&sha256_hex(&content))?
+            .with_progress(progress.sender.clone())
+            .with_abortable(&abortable);
+        let receiver = progress.receiver.clone();
+        let trigger = abortable.clone();
+        tokio::spawn(async move {
+            while let Ok(progress) = receiver.recv().await {
+                if progress.received >= 1024 * 1024 {
+                    trigger.abort();
+                    break;
+                }
+            }
+        });
+
+        let result = download(&url, &destination, options.clone()).await;
+        assert!(matches!(result, Err(Error::Aborted)));
+        let partial = std::fs::read(dir.join("archive.bin.part"))?;
+        assert!(!partial.is_empty() && partial.len() < SIZE);
+        assert_eq!(partial.as_slice(), &content[..partial.len()]);
+        assert!(!destination.exists());
+
+        // resume
+        abortable.reset();
+        progress.drain().ok();
+        download(&url, &destination, options).await?;
+        assert_eq!(std::fs::read(&destination)?, content);
+        assert!(!dir.join("archive.bin.part").exists());
+        let last = progress.iter().last().unwrap();
+        assert_eq!(
+            last,
+            Progress {
+                received: SIZE as u64,
+                total: Some(SIZE as u64)
+            }
+        );
+        assert_eq!(
+            ranges.lock().unwrap().as_slice(),
+            &[None, Some(format!("bytes={}-", partial.len()))]
+        );
+
+        // checksum mismatch discards the download
+        std::fs::remove_file(&destination)?;
+        let options = DownloadOptions::new().with_sha256(&sha256_hex(b"other"))?;
+        let result = download(&url, &destination, options).await;
+        assert!(matches!(result, Err(Error::Checksum { .. })));
+        assert!(!destination.exists());
+        assert!(!dir.join("archive.bin.part").exists());
+
+        std::fs::remove_dir_all(&dir).ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_download_with_writer() -> Result<()> {
+        let (url, _) = file_server();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        download_with_writer(
+            url,
+            DownloadOptions::new().with_sha256(&sha256_hex(&content()))?,
+            move |chunk| {
+                sink.lock().unwrap().extend_from_slice(chunk);
+                Ok(())
+            },
+        )
+        .await?;
+        assert_eq!(*received.lock().unwrap(), content());
+        Ok(())
+    }
+}
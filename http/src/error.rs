@@ -11,6 +11,24 @@ pub enum Error {
     #[error("JSON: {0}")]
     Json(#[from] serde_json::Error),
 
+    #[error("HTTP status {0}: {1}")]
+    Status(u16, String),
+
+    #[error("UTF-8: {0}")]
+    Utf8(#[from] std::string::FromUtf8Error),
+
+    #[error("IO: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Store: {0}")]
+    Store(#[from] workflow_store::error::Error),
+
+    #[error("Checksum mismatch: expected {expected}, got {actual}")]
+    Checksum { expected: String, actual: String },
+
+    #[error("Aborted")]
+    Aborted,
+
     #[error("Not implemented")]
     NotImplemented,
 }
@@ -1,16 +1,31 @@
+pub mod cache;
+pub mod client;
+pub mod download;
 pub mod error;
+pub mod multipart;
+pub mod request;
+pub mod response;
 pub mod result;
+#[cfg(all(not(target_arch = "wasm32"), any(test, feature = "test")))]
+pub mod test_server;
 
 cfg_if::cfg_if! {
     if #[cfg(target_arch = "wasm32")] {
         mod wasm;
-        pub use wasm::*;
     } else {
         mod native;
-        pub use native::*;
     }
 }
 
+pub use cache::{CachePolicy, CachedClient};
+pub use client::{Client, ClientBuilder, RetryPolicy};
+#[cfg(not(target_arch = "wasm32"))]
+pub use download::download;
+pub use download::{download_with_writer, DownloadOptions, Progress};
+pub use multipart::Multipart;
+pub use request::{get, get_bytes, get_json, Method, Request};
+pub use response::Response;
+
 pub mod prelude {
     pub use super::*;
 }
@@ -0,0 +1,353 @@
+//!
+//! `multipart/form-data` request body ([`Multipart`]) supplied via
+//! [`Request::with_multipart()`](crate::Request::with_multipart).
+//!
+//! On native targets the body is serialized with a generated boundary;
+//! it is sent with a `Content-Length` header if the length of all parts
+//! is known, otherwise using chunked transfer encoding. On WASM32 the
+//! parts are mapped onto the browser `FormData` and `Blob` APIs and the
+//! encoding is performed by the browser.
+//!
+
+use crate::result::Result;
+use reqwest::multipart::{Form, Part};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Arc, Mutex};
+
+#[cfg(not(target_arch = "wasm32"))]
+type StreamBody = Arc<Mutex<Option<reqwest::Body>>>;
+
+#[derive(Clone)]
+enum PartBody {
+    Text(String),
+    Bytes(Vec<u8>),
+    #[cfg(not(target_arch = "wasm32"))]
+    Stream {
+        body: StreamBody,
+        length: Option<u64>,
+    },
+}
+
+impl std::fmt::Debug for PartBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PartBody::Text(text) => f.debug_tuple("Text").field(text).finish(),
+            PartBody::Bytes(bytes) => write!(f, "Bytes({} bytes)", bytes.len()),
+            #[cfg(not(target_arch = "wasm32"))]
+            PartBody::Stream { length, .. } => f
+                .debug_struct("Stream")
+                .field("length", length)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MultipartPart {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+    body: PartBody,
+}
+
+/// `multipart/form-data` body builder.
+///
+/// ```ignore
+/// let multipart = Multipart::new()
+///     .part_text("description", "monthly report")
+///     .part_bytes("file", "report.pdf", "application/pdf", data);
+/// let response = Request::post("https://example.com/upload")
+///     .with_multipart(multipart)
+///     .send()
+///     .await?
+///     .error_for_status()?;
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Multipart {
+    parts: Vec<MultipartPart>,
+}
+
+impl Multipart {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a text field.
+    pub fn part_text(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.parts.push(MultipartPart {
+            name: name.into(),
+            filename: None,
+            content_type: None,
+            body: PartBody::Text(value.into()),
+        });
+        self
+    }
+
+    /// Appends a file part. An invalid `content_type` is reported
+    /// when the request is sent.
+    pub fn part_bytes(
+        mut self,
+        name: impl Into<String>,
+        filename: impl Into<String>,
+        content_type: impl Into<String>,
+        bytes: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.parts.push(MultipartPart {
+            name: name.into(),
+            filename: Some(filename.into()),
+            content_type: Some(content_type.into()),
+            body: PartBody::Bytes(bytes.into()),
+        });
+        self
+    }
+
+    /// Appends a file part read from `stream` while the request is sent
+    /// (native only), allowing upload of large files without buffering
+    /// them in memory. If `length` is not supplied the request is sent
+    /// using chunked transfer encoding.
+    ///
+    /// A stream can be sent only once: requests containing stream parts
+    /// are not retried and sending a clone of the request fails.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn part_stream<S>(
+        mut self,
+        name: impl Into<String>,
+        filename: impl Into<String>,
+        content_type: impl Into<String>,
+        length: Option<u64>,
+        stream: S,
+    ) -> Self
+    where
+        S: futures::Stream<Item = std::io::Result<Vec<u8>>> + Send + 'static,
+    {
+        self.parts.push(MultipartPart {
+            name: name.into(),
+            filename: Some(filename.into()),
+            content_type: Some(content_type.into()),
+            body: PartBody::Stream {
+                body: Arc::new(Mutex::new(Some(reqwest::Body::wrap_stream(stream)))),
+                length,
+            },
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// Returns `true` if the body can be sent more than once
+    /// (the body contains no stream parts).
+    pub fn is_repeatable(&self) -> bool {
+        cfg_if::cfg_if! {
+            if #[cfg(target_arch = "wasm32")] {
+                true
+            } else {
+                !self
+                    .parts
+                    .iter()
+                    .any(|part| matches!(part.body, PartBody::Stream { .. }))
+            }
+        }
+    }
+
+    pub(crate) fn to_form(&self) -> Result<Form> {
+        #[cfg(target_arch = "wasm32")]
+        let mut form = Form::new();
+        // names are escaped as performed by browsers (see `escape()`)
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut form = Form::new().percent_encode_noop();
+        for part in &self.parts {
+            let mut body = match &part.body {
+                PartBody::Text(text) => Part::text(text.clone()),
+                PartBody::Bytes(bytes) => Part::bytes(bytes.clone()),
+                #[cfg(not(target_arch = "wasm32"))]
+                PartBody::Stream { body, length } => {
+                    let body = body
+                        .lock()
+                        .unwrap()
+                        .take()
+                        .ok_or("multipart: stream part has already been sent")?;
+                    match length {
+                        Some(length) => Part::stream_with_length(body, *length),
+                        None => Part::stream(body),
+                    }
+                }
+            };
+            if let Some(filename) = &part.filename {
+                body = body.file_name(escape(filename));
+            }
+            if let Some(content_type) = &part.content_type {
+                body = body.mime_str(content_type)?;
+            }
+            form = form.part(escape(&part.name), body);
+        }
+        Ok(form)
+    }
+}
+
+/// Escapes a field name or filename following the HTML `multipart/form-data`
+/// encoding algorithm (`"`, CR and LF are percent-encoded). Browsers apply
+/// the same escaping to `FormData`, so it is not performed on WASM32.
+fn escape(value: &str) -> String {
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            value.to_string()
+        } else {
+            value
+                .replace('"', "%22")
+                .replace('\r', "%0D")
+                .replace('\n', "%0A")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::request::Request;
+    use crate::test_server;
+
+    /// Splits a `multipart/form-data` body into JSON objects
+    /// holding the part headers and the part body.
+    fn parse_parts(body: &[u8], boundary: &str) -> Vec<serde_json::Value> {
+        let body = String::from_utf8_lossy(body);
+        body.split(&format!("--{boundary}"))
+            .skip(1)
+            .take_while(|part| !part.starts_with("--"))
+            .map(|part| {
+                let part = part.strip_prefix("\r\n").unwrap();
+                let part = part.strip_suffix("\r\n").unwrap();
+                let (head, content) = part.split_once("\r\n\r\n").unwrap();
+                let mut headers = serde_json::Map::new();
+                for line in head.split("\r\n") {
+                    let (name, value) = line.split_once(':').unwrap();
+                    headers.insert(name.trim().to_lowercase(), value.trim().into());
+                }
+                serde_json::json!({ "headers": headers, "body": content })
+            })
+            .collect()
+    }
+
+    /// Server replying with the request headers
+    /// and the parsed multipart parts as JSON.
+    fn multipart_server() -> String {
+        test_server::serve(|request, stream| {
+            let headers = request
+                .headers
+                .iter()
+                .map(|(name, value)| (name.clone(), value.as_str().into()))
+                .collect::<serde_json::Map<_, _>>();
+            let boundary = request
+                .header("content-type")
+                .unwrap()
+                .split_once("boundary=")
+                .unwrap()
+                .1;
+            let reply = serde_json::json!({
+                "headers": headers,
+                "parts": parse_parts(&request.body, boundary),
+            })
+            .to_string();
+            test_server::respond(
+                stream,
+                200,
+                "Content-Type: application/json\r\n",
+                reply.as_bytes(),
+            );
+        })
+    }
+
+    #[tokio::test]
+    async fn test_multipart_round_trip() -> Result<()> {
+        let url = multipart_server();
+
+        let multipart = Multipart::new()
+            .part_text("title", "quarterly report")
+            .part_bytes("file", "report \"q1\".txt", "text/plain", "line 1\nline 2");
+        let echo: serde_json::Value = Request::post(url.as_str())
+            .with_multipart(multipart)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()?;
+
+        assert!(echo["headers"]["content-type"]
+            .as_str()
+            .unwrap()
+            .starts_with("multipart/form-data; boundary="));
+        assert!(echo["headers"]["content-length"].is_string());
+        let parts = echo["parts"].as_array().unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(
+            parts[0]["headers"]["content-disposition"],
+            r#"form-data; name="title""#
+        );
+        assert_eq!(parts[0]["body"], "quarterly report");
+        assert_eq!(
+            parts[1]["headers"]["content-disposition"],
+            r#"form-data; name="file"; filename="report %22q1%22.txt""#
+        );
+        assert_eq!(parts[1]["headers"]["content-type"], "text/plain");
+        assert_eq!(parts[1]["body"], "line 1\nline 2");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_multipart_stream() -> Result<()> {
+        let url = multipart_server();
+
+        let chunks = || {
+            futures::stream::iter(
+                ["first ", "second ", "third"].map(|chunk| Ok(chunk.as_bytes().to_vec())),
+            )
+        };
+
+        // unknown length: sent using chunked transfer encoding
+        let multipart = Multipart::new().part_text("id", "1").part_stream(
+            "data",
+            "data.bin",
+            "application/octet-stream",
+            None,
+            chunks(),
+        );
+        assert!(!multipart.is_repeatable());
+        let request = Request::post(url.as_str()).with_multipart(multipart);
+        let echo: serde_json::Value = request.clone().send().await?.json()?;
+        assert_eq!(echo["headers"]["transfer-encoding"], "chunked");
+        assert_eq!(echo["parts"][1]["body"], "first second third");
+        assert_eq!(
+            echo["parts"][1]["headers"]["content-type"],
+            "application/octet-stream"
+        );
+
+        // the stream has been consumed by the first request
+        assert!(matches!(request.send().await, Err(Error::Custom(_))));
+
+        // known length: sent with `Content-Length`
+        let multipart =
+            Multipart::new().part_stream("data", "data.bin", "text/plain", Some(18), chunks());
+        let echo: serde_json::Value = Request::put(url.as_str())
+            .with_multipart(multipart)
+            .send()
+            .await?
+            .json()?;
+        assert!(echo["headers"]["content-length"].is_string());
+        assert!(echo["headers"]["transfer-encoding"].is_null());
+        assert_eq!(echo["parts"][0]["body"], "first second third");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_multipart_invalid_content_type() {
+        let multipart = Multipart::new().part_bytes("file", "a.bin", "not a mime type", vec![0]);
+        let result = Request::post("http://127.0.0.1:1")
+            .with_multipart(multipart)
+            .send()
+            .await;
+        assert!(matches!(result, Err(Error::Reqwest(err)) if err.is_builder()));
+    }
+}
@@ -1,80 +1,127 @@
-use crate::error::Error;
+use crate::client::Client;
+use crate::request::Request;
+use crate::response::Response;
 use crate::result::Result;
 
-pub async fn get(url: impl Into<String>) -> Result<String> {
-    Request::new(url).get().await
-}
-
-pub async fn get_bytes(url: impl Into<String>) -> Result<Vec<u8>> {
-    Request::new(url).get_bytes().await
+impl Request {
+    /// Executes the request. The response is returned for any HTTP status;
+    /// see [`Response::error_for_status()`].
+    pub async fn send(self) -> Result<Response> {
+        Client::shared().execute(self).await
+    }
 }
 
-pub async fn get_json<T: serde::de::DeserializeOwned + 'static>(
-    url: impl Into<String>,
-) -> Result<T> {
-    Request::new(url).get_json().await
+impl Client {
+    /// Executes the request applying the client defaults and retry policy.
+    /// The response is returned for any HTTP status (including the last
+    /// retried failure); see [`Response::error_for_status()`].
+    pub async fn send(&self, request: Request) -> Result<Response> {
+        self.execute(request).await
+    }
 }
 
-pub struct Request {
-    pub url: String,
-    pub user_agent: Option<String>,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::request::{get_json, Method};
+    use crate::test_server::{self, TestRequest};
 
-impl Request {
-    pub fn new(url: impl Into<String>) -> Self {
-        Self {
-            url: url.into(),
-            user_agent: None,
-        }
+    /// Server echoing the request back as JSON. Requests
+    /// to `/status/<code>` respond with the given status code.
+    fn echo_server() -> String {
+        test_server::serve(|request: TestRequest, stream| {
+            let headers = request
+                .headers
+                .iter()
+                .map(|(name, value)| (name.clone(), value.as_str().into()))
+                .collect::<serde_json::Map<_, _>>();
+            let status = request
+                .target
+                .strip_prefix("/status/")
+                .map(|code| code.parse::<u16>().unwrap())
+                .unwrap_or(200);
+            let reply = serde_json::json!({
+                "method": request.method,
+                "target": request.target,
+                "headers": headers,
+                "body": request.text(),
+            })
+            .to_string();
+            test_server::respond(
+                stream,
+                status,
+                "Content-Type: application/json\r\nX-Echo: yes\r\n",
+                reply.as_bytes(),
+            );
+        })
     }
 
-    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
-        self.user_agent = Some(user_agent.into());
-        self
-    }
+    #[tokio::test]
+    async fn test_request_builder() -> Result<()> {
+        let url = echo_server();
 
-    pub async fn get(self) -> Result<String> {
-        let mut req = reqwest::Client::new().get(&self.url);
-        if let Some(user_agent) = self.user_agent {
-            req = req.header("User-Agent", user_agent);
-        }
-        let resp = req.send().await?;
-        let status = resp.status();
-        let text = resp.text().await?;
-        if status.is_success() {
-            Ok(text)
-        } else {
-            Err(Error::Custom(format!("{}: {}", status, text)))
-        }
-    }
+        let echo: serde_json::Value = Request::post(format!("{url}/items"))
+            .with_header("X-Token", "abc")
+            .with_query("page", "2")
+            .with_query("q", "a b")
+            .with_json(&serde_json::json!({ "name": "item" }))?
+            .send()
+            .await?
+            .error_for_status()?
+            .json()?;
+        assert_eq!(echo["method"], "POST");
+        assert_eq!(echo["target"], "/items?page=2&q=a+b");
+        assert_eq!(echo["headers"]["x-token"], "abc");
+        assert_eq!(echo["headers"]["content-type"], "application/json");
+        assert_eq!(echo["body"], r#"{"name":"item"}"#);
+
+        let response = Request::new(format!("{url}/text"))
+            .with_method(Method::Put)
+            .with_text("hello")
+            .send()
+            .await?;
+        assert!(response.is_success());
+        assert_eq!(response.header("x-echo"), Some("yes"));
+        let echo: serde_json::Value = serde_json::from_str(&response.text()?)?;
+        assert_eq!(echo["method"], "PUT");
+        assert_eq!(echo["headers"]["content-type"], "text/plain; charset=utf-8");
+        assert_eq!(echo["body"], "hello");
+
+        let echo: serde_json::Value = get_json(format!("{url}/json")).await?;
+        assert_eq!(echo["method"], "GET");
+
+        // the legacy helpers always issue a GET request
+        let echo: serde_json::Value = Request::post(format!("{url}/json")).get_json().await?;
+        assert_eq!(echo["method"], "GET");
+        let echo = Request::new(format!("{url}/text")).get().await?;
+        assert_eq!(
+            Request::new(format!("{url}/text")).get_bytes().await?,
+            echo.as_bytes()
+        );
 
-    pub async fn get_bytes(self) -> Result<Vec<u8>> {
-        let mut req = reqwest::Client::new().get(&self.url);
-        if let Some(user_agent) = self.user_agent {
-            req = req.header("User-Agent", user_agent);
-        }
-        let resp = req.send().await?;
-        let status = resp.status();
-        let bytes = resp.bytes().await?;
-        if status.is_success() {
-            Ok(bytes.to_vec())
-        } else {
-            Err(Error::Custom(format!("{}: {:?}", status, bytes)))
-        }
+        Ok(())
     }
 
-    pub async fn get_json<T: serde::de::DeserializeOwned + 'static>(self) -> Result<T> {
-        let mut req = reqwest::Client::new().get(&self.url);
-        if let Some(user_agent) = self.user_agent {
-            req = req.header("User-Agent", user_agent);
-        }
-        let resp = req.send().await?;
-        let status = resp.status();
-        let text = resp.text().await?;
-        if status.is_success() {
-            Ok(serde_json::from_str(&text)?)
-        } else {
-            Err(Error::Custom(format!("{}: {}", status, text)))
-        }
+    #[tokio::test]
+    async fn test_response_status() -> Result<()> {
+        let url = echo_server();
+
+        let response = Request::delete(format!("{url}/status/404")).send().await?;
+        assert_eq!(response.status(), 404);
+        assert!(!response.is_success());
+        let echo: serde_json::Value = response.json()?;
+        assert_eq!(echo["method"], "DELETE");
+        assert!(matches!(
+            response.error_for_status(),
+            Err(Error::Status(404, _))
+        ));
+
+        assert!(matches!(
+            get_json::<serde_json::Value>(format!("{url}/status/503")).await,
+            Err(Error::Status(503, _))
+        ));
+
+        Ok(())
     }
 }
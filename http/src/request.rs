@@ -0,0 +1,267 @@
+//!
+//! HTTP [`Request`] builder shared by native and WASM32 targets.
+//!
+
+use crate::cache::CachePolicy;
+use crate::client::RetryPolicy;
+use crate::multipart::Multipart;
+use crate::result::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+
+/// HTTP request method.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Method {
+    #[default]
+    Get,
+    Post,
+    Put,
+    Delete,
+    Patch,
+    Head,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+            Method::Patch => "PATCH",
+            Method::Head => "HEAD",
+        }
+    }
+
+    /// Returns `true` for methods that can be safely repeated
+    /// (`GET`, `HEAD`, `PUT` and `DELETE`).
+    pub fn is_idempotent(&self) -> bool {
+        matches!(
+            self,
+            Method::Get | Method::Head | Method::Put | Method::Delete
+        )
+    }
+}
+
+impl std::fmt::Display for Method {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<Method> for reqwest::Method {
+    fn from(method: Method) -> Self {
+        match method {
+            Method::Get => reqwest::Method::GET,
+            Method::Post => reqwest::Method::POST,
+            Method::Put => reqwest::Method::PUT,
+            Method::Delete => reqwest::Method::DELETE,
+            Method::Patch => reqwest::Method::PATCH,
+            Method::Head => reqwest::Method::HEAD,
+        }
+    }
+}
+
+/// HTTP request builder. The request is executed using [`Request::send()`]
+/// or [`Client::send()`](crate::Client::send), in which case the request
+/// settings override the client defaults.
+///
+/// ```ignore
+/// let response = Request::post("https://example.com/api")
+///     .with_header("X-Api-Key", key)
+///     .with_query("page", "1")
+///     .with_json(&payload)?
+///     .send()
+///     .await?
+///     .error_for_status()?;
+/// let reply: Reply = response.json()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub url: String,
+    pub method: Method,
+    pub user_agent: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub query: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+    pub multipart: Option<Multipart>,
+    pub timeout: Option<Duration>,
+    pub retry: Option<RetryPolicy>,
+    pub cache: CachePolicy,
+}
+
+impl Request {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            method: Method::Get,
+            user_agent: None,
+            headers: Vec::new(),
+            query: Vec::new(),
+            body: None,
+            multipart: None,
+            timeout: None,
+            retry: None,
+            cache: CachePolicy::Default,
+        }
+    }
+
+    pub fn post(url: impl Into<String>) -> Self {
+        Self::new(url).with_method(Method::Post)
+    }
+
+    pub fn put(url: impl Into<String>) -> Self {
+        Self::new(url).with_method(Method::Put)
+    }
+
+    pub fn delete(url: impl Into<String>) -> Self {
+        Self::new(url).with_method(Method::Delete)
+    }
+
+    pub fn patch(url: impl Into<String>) -> Self {
+        Self::new(url).with_method(Method::Patch)
+    }
+
+    pub fn with_method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Appends a request header. Headers with the same name are sent
+    /// as multiple values.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Appends a query parameter to the request URL.
+    pub fn with_query(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self.multipart = None;
+        self
+    }
+
+    /// Sets a `multipart/form-data` body, replacing any previously
+    /// supplied body. The `Content-Type` header (including the boundary)
+    /// is set when the request is sent and should not be supplied.
+    pub fn with_multipart(mut self, multipart: Multipart) -> Self {
+        self.multipart = Some(multipart);
+        self.body = None;
+        self
+    }
+
+    /// Sets a text body with `Content-Type: text/plain; charset=utf-8`
+    /// (unless a content type has already been supplied).
+    pub fn with_text(self, text: impl Into<String>) -> Self {
+        self.with_body(text.into().into_bytes())
+            .with_default_content_type("text/plain; charset=utf-8")
+    }
+
+    /// Serializes `value` as the JSON request body and sets
+    /// `Content-Type: application/json` (unless already supplied).
+    pub fn with_json<T: Serialize + ?Sized>(self, value: &T) -> Result<Self> {
+        Ok(self
+            .with_body(serde_json::to_vec(value)?)
+            .with_default_content_type("application/json"))
+    }
+
+    /// Total request timeout (overrides the client default).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Retry policy (overrides the client default).
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Cache policy applied when the request is executed
+    /// via [`CachedClient`](crate::CachedClient).
+    pub fn with_cache_policy(mut self, cache: CachePolicy) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    fn with_default_content_type(self, content_type: &str) -> Self {
+        if self.header("content-type").is_some() {
+            self
+        } else {
+            self.with_header("Content-Type", content_type)
+        }
+    }
+
+    /// Returns `true` if the request can be sent more than once
+    /// (see [`Multipart::is_repeatable()`]).
+    pub fn is_repeatable(&self) -> bool {
+        !matches!(&self.multipart, Some(multipart) if !multipart.is_repeatable())
+    }
+
+    /// Returns the first value of the header `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sends a `GET` request and returns the response body as text.
+    /// Fails with [`Error::Status`](crate::error::Error::Status) on a non-2xx status.
+    pub async fn get(self) -> Result<String> {
+        self.with_method(Method::Get)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+    }
+
+    /// Sends a `GET` request and returns the response body as bytes.
+    /// Fails with [`Error::Status`](crate::error::Error::Status) on a non-2xx status.
+    pub async fn get_bytes(self) -> Result<Vec<u8>> {
+        Ok(self
+            .with_method(Method::Get)
+            .send()
+            .await?
+            .error_for_status()?
+            .into_bytes())
+    }
+
+    /// Sends a `GET` request and deserializes the JSON response body into `T`.
+    /// Fails with [`Error::Status`](crate::error::Error::Status) on a non-2xx status.
+    pub async fn get_json<T: DeserializeOwned>(self) -> Result<T> {
+        self.with_method(Method::Get)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+    }
+}
+
+/// Fetches `url` and returns the response body as text.
+/// Fails with [`Error::Status`](crate::error::Error::Status) on a non-2xx status.
+pub async fn get(url: impl Into<String>) -> Result<String> {
+    Request::new(url).get().await
+}
+
+/// Fetches `url` and returns the response body as bytes.
+/// Fails with [`Error::Status`](crate::error::Error::Status) on a non-2xx status.
+pub async fn get_bytes(url: impl Into<String>) -> Result<Vec<u8>> {
+    Request::new(url).get_bytes().await
+}
+
+/// Fetches `url` and deserializes the JSON response body into `T`.
+/// Fails with [`Error::Status`](crate::error::Error::Status) on a non-2xx status.
+pub async fn get_json<T: DeserializeOwned>(url: impl Into<String>) -> Result<T> {
+    Request::new(url).get_json().await
+}
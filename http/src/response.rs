@@ -0,0 +1,84 @@
+//!
+//! HTTP [`Response`] returned by [`Request::send()`](crate::Request::send).
+//!
+
+use crate::error::Error;
+use crate::result::Result;
+use serde::de::DeserializeOwned;
+
+/// HTTP response with a fully received body.
+///
+/// Unsuccessful (non-2xx) statuses are not treated as errors; use
+/// [`Response::error_for_status()`] to convert them into [`Error::Status`].
+#[derive(Debug, Clone)]
+pub struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    pub(crate) fn new(status: u16, headers: Vec<(String, String)>, body: Vec<u8>) -> Self {
+        Self {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Returns `true` if the status is within the `200..=299` range.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Response headers. Header names are lowercase.
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Returns the first value of the header `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn content_length(&self) -> Option<u64> {
+        self.header("content-length")
+            .and_then(|value| value.trim().parse().ok())
+    }
+
+    /// Returns [`Error::Status`] if the status is not successful,
+    /// otherwise returns the response unchanged.
+    pub fn error_for_status(self) -> Result<Self> {
+        if self.is_success() {
+            Ok(self)
+        } else {
+            Err(Error::Status(
+                self.status,
+                String::from_utf8_lossy(&self.body).to_string(),
+            ))
+        }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.body
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.body
+    }
+
+    pub fn text(self) -> Result<String> {
+        Ok(String::from_utf8(self.body)?)
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+}
@@ -0,0 +1,178 @@
+//!
+//! Minimal blocking HTTP/1.1 server for unit tests of this crate and,
+//! using the `test` feature, of dependent crates.
+//!
+//! ```ignore
+//! let url = test_server::serve(|request, stream| {
+//!     test_server::respond(stream, 200, "Cache-Control: no-cache\r\n", request.target.as_bytes());
+//! });
+//! ```
+//!
+//! Each connection carries a single request and is served on a dedicated
+//! thread; responses written by [`respond()`] close the connection.
+//!
+
+use reqwest::StatusCode;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// HTTP request received by the test server.
+#[derive(Debug, Clone, Default)]
+pub struct TestRequest {
+    /// Request method, e.g. `GET`
+    pub method: String,
+    /// Request target (path and query), e.g. `/items?page=2`
+    pub target: String,
+    /// Request headers (lowercase names) in the order received
+    pub headers: Vec<(String, String)>,
+    /// Request body (decoded if sent using chunked transfer encoding)
+    pub body: Vec<u8>,
+}
+
+impl TestRequest {
+    /// Returns the first value of the header `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns all values of the header `name` (case-insensitive).
+    pub fn header_values(&self, name: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+
+    /// Parses the body as JSON (panics if the body is not valid JSON).
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+}
+
+/// Reads the request head and body. Returns `None` if the connection
+/// has been closed before a request has been received.
+pub fn read_request(reader: &mut impl BufRead) -> Option<TestRequest> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).ok()? == 0 {
+        return None;
+    }
+    let mut parts = line.split_whitespace();
+    let mut request = TestRequest {
+        method: parts.next().unwrap_or_default().to_string(),
+        target: parts.next().unwrap_or_default().to_string(),
+        ..Default::default()
+    };
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            request
+                .headers
+                .push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    request.body = if request.header("transfer-encoding") == Some("chunked") {
+        read_chunked(reader)
+    } else {
+        let length = request
+            .header("content-length")
+            .map(|length| length.parse().unwrap())
+            .unwrap_or(0);
+        let mut body = vec![0u8; length];
+        reader.read_exact(&mut body).unwrap();
+        body
+    };
+    Some(request)
+}
+
+fn read_chunked(reader: &mut impl BufRead) -> Vec<u8> {
+    let mut body = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let size = usize::from_str_radix(line.trim(), 16).unwrap();
+        let mut chunk = vec![0u8; size + 2];
+        reader.read_exact(&mut chunk).unwrap();
+        if size == 0 {
+            return body;
+        }
+        body.extend_from_slice(&chunk[..size]);
+    }
+}
+
+/// Writes a response with the given `status`, extra `headers` (each
+/// terminated by `\r\n`) and `body`, and closes the connection.
+pub fn respond(stream: &mut TcpStream, status: u16, headers: &str, body: &[u8]) {
+    let reason = StatusCode::from_u16(status)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or("Status");
+    let head = format!(
+        "HTTP/1.1 {status} {reason}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    // the client may have given up on the request
+    stream.write_all(head.as_bytes()).ok();
+    stream.write_all(body).ok();
+}
+
+/// Serves connections on a local port until the process exits, calling
+/// `handler` for each received request. Returns the server base URL
+/// (e.g. `http://127.0.0.1:49152`).
+pub fn serve<F>(handler: F) -> String
+where
+    F: Fn(TestRequest, &mut TcpStream) + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handler = Arc::new(handler);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { break };
+            let handler = handler.clone();
+            std::thread::spawn(move || handle(stream, |request, stream| handler(request, stream)));
+        }
+    });
+    url
+}
+
+/// Accepts a single connection and serves its request using `handler`,
+/// closing the listener afterwards. Returns the server base URL and
+/// the handle of the server thread.
+pub fn serve_once<F>(handler: F) -> (String, JoinHandle<()>)
+where
+    F: FnOnce(TestRequest, &mut TcpStream) + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        handle(stream, handler);
+    });
+    (url, server)
+}
+
+fn handle<F>(stream: TcpStream, handler: F)
+where
+    F: FnOnce(TestRequest, &mut TcpStream),
+{
+    let mut reader = BufReader::new(stream);
+    if let Some(request) = read_request(&mut reader) {
+        handler(request, reader.get_mut());
+    }
+}
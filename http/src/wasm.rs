@@ -1,93 +1,81 @@
-use crate::error::Error;
+use crate::client::Client;
+use crate::request::Request;
+use crate::response::Response;
 use crate::result::Result;
 use workflow_core::task::call_async_no_send;
 
-pub async fn get(url: impl Into<String>) -> Result<String> {
-    Request::new(url).get().await
-}
-
-pub async fn get_bytes(url: impl Into<String>) -> Result<Vec<u8>> {
-    Request::new(url).get_bytes().await
-}
-
-pub async fn get_json<T: serde::de::DeserializeOwned + 'static>(
-    url: impl Into<String>,
-) -> Result<T> {
-    Request::new(url).get_json().await
+impl Request {
+    /// Executes the request using the browser `fetch` API. The response
+    /// is returned for any HTTP status; see [`Response::error_for_status()`].
+    pub async fn send(self) -> Result<Response> {
+        call_async_no_send!(Client::shared().execute(self).await)
+    }
 }
 
-pub struct Request {
-    pub url: String,
-    pub user_agent: Option<String>,
+impl Client {
+    /// Executes the request using the browser `fetch` API, applying the
+    /// client defaults and retry policy. The response is returned for any
+    /// HTTP status (including the last retried failure); see
+    /// [`Response::error_for_status()`].
+    pub async fn send(&self, request: Request) -> Result<Response> {
+        let client = self.clone();
+        call_async_no_send!(async move { client.execute(request).await })
+    }
 }
 
-impl Request {
-    pub fn new(url: impl Into<String>) -> Self {
-        Self {
-            url: url.into(),
-            user_agent: None,
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::request::{get_bytes, get_json, Method};
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::*;
+    use web_sys::{Blob, BlobPropertyBag, Url};
 
-    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
-        self.user_agent = Some(user_agent.into());
-        self
-    }
+    wasm_bindgen_test_configure!(run_in_browser);
 
-    async fn get_not_send_impl(self) -> Result<String> {
-        let mut req = reqwest::Client::new().get(&self.url);
-        if let Some(user_agent) = self.user_agent {
-            req = req.header("User-Agent", user_agent);
-        }
-        let resp = req.send().await?;
-        let status = resp.status();
-        let text = resp.text().await?;
-        if status.is_success() {
-            Ok(text)
-        } else {
-            Err(Error::Custom(format!("{}: {}", status, text)))
-        }
+    /// Creates a `blob:` URL serving `content` with the given content type.
+    fn blob_url(content: &str, content_type: &str) -> String {
+        let options = BlobPropertyBag::new();
+        options.set_type(content_type);
+        let parts = js_sys::Array::of1(&JsValue::from_str(content));
+        let blob = Blob::new_with_str_sequence_and_options(&parts, &options).unwrap();
+        Url::create_object_url_with_blob(&blob).unwrap()
     }
 
-    pub async fn get(self) -> Result<String> {
-        call_async_no_send!(self.get_not_send_impl().await)
-    }
+    #[wasm_bindgen_test]
+    async fn test_request_send() {
+        let url = blob_url(r#"{"name":"item"}"#, "application/json");
 
-    async fn get_bytes_not_send_impl(self) -> Result<Vec<u8>> {
-        let mut req = reqwest::Client::new().get(&self.url);
-        if let Some(user_agent) = self.user_agent {
-            req = req.header("User-Agent", user_agent);
-        }
-        let resp = req.send().await?;
-        let status = resp.status();
-        let bytes = resp.bytes().await?;
-        if status.is_success() {
-            Ok(bytes.to_vec())
-        } else {
-            Err(Error::Custom(format!("{}: {:?}", status, bytes)))
-        }
-    }
+        let response = Request::new(&url).send().await.unwrap();
+        assert!(response.is_success());
+        assert_eq!(response.header("content-type"), Some("application/json"));
+        let value: serde_json::Value = response.json().unwrap();
+        assert_eq!(value["name"], "item");
 
-    pub async fn get_bytes(self) -> Result<Vec<u8>> {
-        call_async_no_send!(self.get_bytes_not_send_impl().await)
-    }
+        let value: serde_json::Value = get_json(&url).await.unwrap();
+        assert_eq!(value["name"], "item");
+        assert_eq!(
+            get_bytes(&url).await.unwrap(),
+            br#"{"name":"item"}"#.to_vec()
+        );
 
-    async fn get_json_not_send_impl<T: serde::de::DeserializeOwned + 'static>(self) -> Result<T> {
-        let mut req = reqwest::Client::new().get(&self.url);
-        if let Some(user_agent) = self.user_agent {
-            req = req.header("User-Agent", user_agent);
-        }
-        let resp = req.send().await?;
-        let status = resp.status();
-        let text = resp.text().await?;
-        if status.is_success() {
-            Ok(serde_json::from_str(&text)?)
-        } else {
-            Err(Error::Custom(format!("{}: {}", status, text)))
-        }
-    }
+        // the legacy helpers always issue a GET request
+        let text = blob_url("hello", "text/plain");
+        assert_eq!(
+            Request::new(&text)
+                .with_method(Method::Post)
+                .get()
+                .await
+                .unwrap(),
+            "hello"
+        );
+        assert!(matches!(
+            Request::new(&text).get_json::<serde_json::Value>().await,
+            Err(Error::Json(_))
+        ));
 
-    pub async fn get_json<T: serde::de::DeserializeOwned + 'static>(self) -> Result<T> {
-        call_async_no_send!(self.get_json_not_send_impl().await)
+        Url::revoke_object_url(&url).unwrap();
+        Url::revoke_object_url(&text).unwrap();
     }
 }
@@ -0,0 +1,104 @@
+//!
+//! Initialization of a large dictionary (5 languages of 10,000 entries)
+//! from JSON data (`Builder::with_static_json_data()`) versus a compiled
+//! dictionary (`Builder::with_embedded_data()`). The heap memory retained
+//! by each dictionary is reported before running the benchmarks.
+//!
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use workflow_i18n::embed;
+use workflow_i18n::i18n::{dictionary, Builder};
+
+const LANGUAGES: [&str; 5] = ["en", "de", "fr", "ja", "zh"];
+const ENTRIES: usize = 10_000;
+
+struct CountingAllocator;
+
+static RESIDENT: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        RESIDENT.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        RESIDENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        RESIDENT.fetch_add(new_size, Ordering::Relaxed);
+        RESIDENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn json_data() -> &'static str {
+    let languages = LANGUAGES
+        .iter()
+        .map(|code| format!("\"{code}\": \"Language {code}\""))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let translations = LANGUAGES
+        .iter()
+        .map(|code| {
+            let entries = (0..ENTRIES)
+                .map(|n| {
+                    format!("\"Text number {n}\": \"Translation of text number {n} ({code})\"")
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("\"{code}\": {{ {entries} }}")
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let enabled = LANGUAGES
+        .iter()
+        .map(|code| format!("\"{code}\""))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let json = format!(
+        "{{ \"enabled\": [{enabled}], \"aliases\": {{}}, \"languages\": {{ {languages} }}, \"translations\": {{ {translations} }} }}"
+    );
+    Box::leak(json.into_boxed_str())
+}
+
+/// Heap bytes retained by the dictionary created by `builder`.
+fn resident(builder: impl Fn() -> Builder) -> usize {
+    // keep the active dictionary alive so that its release is not accounted for
+    let previous = dictionary();
+    let before = RESIDENT.load(Ordering::Relaxed);
+    builder().try_init().unwrap();
+    let after = RESIDENT.load(Ordering::Relaxed);
+    drop(previous);
+    after - before
+}
+
+fn dictionary_init(c: &mut Criterion) {
+    let json = json_data();
+    let embedded: &'static [u8] = Box::leak(embed::compile_str(json).unwrap().into_boxed_slice());
+    let from_json = || Builder::new("ja", "en").with_static_json_data(json);
+    let from_embedded = || Builder::new("ja", "en").with_embedded_data(embedded);
+
+    from_embedded().try_init().unwrap();
+    let json_resident = resident(from_json);
+    let embedded_resident = resident(from_embedded);
+    println!("resident memory: {json_resident} bytes (json), {embedded_resident} bytes (embedded)");
+    assert!(embedded_resident < json_resident);
+
+    c.bench_function("dictionary init (json)", |b| {
+        b.iter(|| from_json().try_init().unwrap())
+    });
+    c.bench_function("dictionary init (embedded)", |b| {
+        b.iter(|| from_embedded().try_init().unwrap())
+    });
+}
+
+criterion_group!(benches, dictionary_init);
+criterion_main!(benches);
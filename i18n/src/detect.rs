@@ -0,0 +1,296 @@
+//!
+//! Detection of the user language from the browser (`navigator.languages`),
+//! NW.js (UI locale) or the OS (`LC_ALL`, `LC_MESSAGES`, `LANG`) and
+//! persistence of the user-selected language in a [`Store`].
+//!
+//! ```ignore
+//! Builder::new("en", "en").with_static_json_data(I18N).try_init()?;
+//! let mut store = Store::new();
+//! store.with_generic("~/.app/settings.json");
+//! i18n::init_auto(store).await?;
+//! ...
+//! i18n::set_language("ja").await?;
+//! ```
+//!
+
+use crate::i18n::{dictionary, try_dictionary, Languages};
+use crate::result::Result;
+use arc_swap::ArcSwapOption;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+use workflow_store::json::JsonStore;
+use workflow_store::store::Store;
+
+/// Path of the user-selected language in the [`JsonStore`] document
+pub const LANGUAGE_STORE_PATH: &str = "i18n.language";
+
+static LANGUAGE_STORE: ArcSwapOption<JsonStore> = ArcSwapOption::const_empty();
+
+/// Language code of the language map (e.g. `en`, `zh`)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LanguageCode(String);
+
+impl LanguageCode {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for LanguageCode {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for LanguageCode {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for LanguageCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<LanguageCode> for String {
+    fn from(code: LanguageCode) -> String {
+        code.0
+    }
+}
+
+/// Normalizes the `locale` (such as `en-GB`, `en_GB.UTF-8` or `zh-Hant-TW`)
+/// to one of the language `codes`, matching aliases first and falling back
+/// on less specific subtags (`en-GB` → `en`). Returns `None` if the locale
+/// does not correspond to any of the codes.
+pub(crate) fn normalize<'a>(
+    locale: &str,
+    codes: &[&'a str],
+    aliases: &[(&str, &'a str)],
+) -> Option<&'a str> {
+    // strip the POSIX encoding and modifier (`en_GB.UTF-8@euro`)
+    let locale = locale
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .replace('_', "-");
+    if locale.is_empty() || locale == "C" || locale == "POSIX" {
+        return None;
+    }
+
+    let find = |tag: &str| {
+        aliases
+            .iter()
+            .find(|(alias, code)| alias.eq_ignore_ascii_case(tag) && codes.contains(code))
+            .map(|(_, code)| *code)
+            .or_else(|| {
+                codes
+                    .iter()
+                    .find(|code| code.eq_ignore_ascii_case(tag))
+                    .copied()
+            })
+    };
+
+    let mut subtags = locale.split('-').collect::<Vec<_>>();
+    while !subtags.is_empty() {
+        if let Some(code) = find(&subtags.join("-")) {
+            return Some(code);
+        }
+        subtags.pop();
+    }
+    None
+}
+
+/// Selects the `stored` (user-selected) language if valid, otherwise
+/// the first recognized detected `locale`, otherwise the `default`.
+fn select<'a>(
+    stored: Option<&str>,
+    locales: &[String],
+    normalize: impl Fn(&str) -> Option<&'a str>,
+    default: &'a str,
+) -> &'a str {
+    stored
+        .and_then(&normalize)
+        .or_else(|| locales.iter().find_map(|locale| normalize(locale)))
+        .unwrap_or(default)
+}
+
+/// Preferred locales of the user, in the order of preference
+pub fn system_locales() -> Vec<String> {
+    let mut locales = Vec::new();
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        use js_sys::{Array, Function, Reflect};
+        use wasm_bindgen::{JsCast, JsValue};
+
+        let get = |target: &JsValue, key: &str| {
+            Reflect::get(target, &JsValue::from_str(key))
+                .ok()
+                .filter(|value| !value.is_undefined() && !value.is_null())
+        };
+
+        // NW.js UI locale (reflects the `--lang` command line option)
+        if workflow_core::runtime::is_nw() {
+            let language = get(&js_sys::global(), "chrome")
+                .and_then(|chrome| get(&chrome, "i18n"))
+                .and_then(|i18n| {
+                    let get_ui_language =
+                        get(&i18n, "getUILanguage")?.dyn_into::<Function>().ok()?;
+                    get_ui_language.call0(&i18n).ok()?.as_string()
+                });
+            locales.extend(language);
+        }
+
+        if let Some(navigator) = get(&js_sys::global(), "navigator") {
+            match get(&navigator, "languages")
+                .and_then(|languages| languages.dyn_into::<Array>().ok())
+            {
+                Some(languages) => {
+                    locales.extend(languages.iter().filter_map(|language| language.as_string()))
+                }
+                None => locales
+                    .extend(get(&navigator, "language").and_then(|language| language.as_string())),
+            }
+        }
+    }
+
+    if !cfg!(target_arch = "wasm32") || workflow_core::runtime::is_node() {
+        locales.extend(
+            ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .into_iter()
+                .filter_map(|key| workflow_core::env::var(key).ok())
+                .filter(|locale| !locale.is_empty()),
+        );
+    }
+
+    locales
+}
+
+/// Detects the user language among the enabled languages of the dictionary
+/// (or the default language map if the dictionary is not initialized),
+/// falling back on the default language if none of the user locales is recognized.
+pub fn detect_language() -> LanguageCode {
+    let locales = system_locales();
+    let code = match try_dictionary() {
+        Some(dictionary) => select(
+            None,
+            &locales,
+            |locale| dictionary.normalize_language_code(locale),
+            dictionary.default_code(),
+        )
+        .to_string(),
+        None => {
+            let codes = Languages::default().codes();
+            select(
+                None,
+                &locales,
+                |locale| normalize(locale, &codes, &[]),
+                "en",
+            )
+            .to_string()
+        }
+    };
+    LanguageCode(code)
+}
+
+/// Activates the language stored in the `store` by [`set_language()`] if present
+/// or the detected language (see [`detect_language()`]) otherwise. Subsequent
+/// [`set_language()`] calls are persisted in the `store` (at [`LANGUAGE_STORE_PATH`]).
+/// The dictionary must be initialized (see [`Builder::try_init()`](crate::i18n::Builder::try_init)).
+pub async fn init_auto(store: Store) -> Result<LanguageCode> {
+    let store = JsonStore::new(store);
+    let stored = store
+        .get_path(LANGUAGE_STORE_PATH)
+        .await?
+        .and_then(|value| value.as_str().map(String::from));
+
+    let dictionary = dictionary();
+    let code = select(
+        stored.as_deref(),
+        &system_locales(),
+        |locale| dictionary.normalize_language_code(locale),
+        dictionary.default_code(),
+    )
+    .to_string();
+    dictionary.activate_language_code(code.as_str())?;
+    LANGUAGE_STORE.store(Some(Arc::new(store)));
+
+    Ok(LanguageCode(code))
+}
+
+/// Activates the language and persists it in the store supplied to [`init_auto()`] (if any).
+pub async fn set_language(code: &str) -> Result<()> {
+    let dictionary = dictionary();
+    dictionary.activate_language_code(code)?;
+    if let Some(store) = LANGUAGE_STORE.load_full() {
+        let code = dictionary.current_code();
+        store
+            .set_path(LANGUAGE_STORE_PATH, code.as_str().into())
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALIASES: &[(&str, &str)] = &[("zh-TW", "zh"), ("zh-Hant", "zh"), ("iw", "he")];
+
+    #[test]
+    fn test_normalize_language_code() {
+        let codes = Languages::default().codes();
+        let resolve = |locale: &str| normalize(locale, &codes, ALIASES);
+
+        for (locale, expected) in [
+            ("en", Some("en")),
+            ("en-GB", Some("en")),
+            ("EN-us", Some("en")),
+            ("en_GB.UTF-8", Some("en")),
+            ("de_DE@euro", Some("de")),
+            ("pt-BR", Some("pt")),
+            ("fil-PH", Some("fil")),
+            ("zh-Hant-TW", Some("zh")),
+            ("zh_TW.Big5", Some("zh")),
+            ("iw-IL", Some("he")),
+            ("ja_JP.eucJP", Some("ja")),
+            ("C", None),
+            ("POSIX", None),
+            ("C.UTF-8", None),
+            ("", None),
+            ("xx-YY", None),
+            ("klingon", None),
+        ] {
+            assert_eq!(resolve(locale), expected, "{locale}");
+        }
+
+        // aliases resolving to disabled languages are ignored
+        assert_eq!(normalize("iw", &["en"], ALIASES), None);
+    }
+
+    #[test]
+    fn test_select_language_precedence() {
+        let codes = ["en", "de", "ja"];
+        let resolve = |locale: &str| normalize(locale, &codes, &[]);
+        let locales = vec![
+            "fr-FR".to_string(),
+            "de_AT.UTF-8".to_string(),
+            "ja".to_string(),
+        ];
+
+        // user-selected language takes precedence over the detected locales
+        assert_eq!(select(Some("ja"), &locales, resolve, "en"), "ja");
+        // unknown stored languages are ignored
+        assert_eq!(select(Some("xx"), &locales, resolve, "en"), "de");
+        assert_eq!(select(None, &locales, resolve, "en"), "de");
+        // unknown locales fall back to the default
+        assert_eq!(select(None, &["fr".to_string()], resolve, "en"), "en");
+        assert_eq!(select(Some("fr"), &[], resolve, "en"), "en");
+    }
+}
@@ -0,0 +1,353 @@
+//!
+//! Compiled dictionaries embedded into the application binary.
+//!
+//! Parsing a large `i18n.json` data file at startup can take noticeable
+//! time on low-end devices. As an alternative, the data file can be
+//! compiled by the build script into a compact binary representation
+//! that is embedded using [`embed_dict!`](crate::embed_dict). Translations
+//! are then looked up directly within the embedded data (using a binary
+//! search over the sorted keys of each language), without parsing the
+//! data or building maps of the translations at startup.
+//!
+//! `build.rs` (with `workflow-i18n` listed in `[build-dependencies]`):
+//! ```ignore
+//! fn main() {
+//!     workflow_i18n::embed::compile("i18n/i18n.json").unwrap();
+//! }
+//! ```
+//!
+//! Application:
+//! ```ignore
+//! Builder::new("en", "en")
+//!     .with_embedded_data(workflow_i18n::embed_dict!("i18n/i18n.json"))
+//!     .try_init()?;
+//! ```
+//!
+//! Lookups ([`i18n()`](crate::i18n())) are unchanged. Dictionaries loaded
+//! at runtime (e.g. [`load()`](crate::i18n::load)) continue to use JSON.
+//!
+//! ## Format
+//!
+//! All integers are little-endian `u32`; strings are `(offset, length)`
+//! references into a string table of deduplicated UTF-8 strings.
+//! ```text
+//! magic, string table (offset, length)
+//! enabled:      count, [string]
+//! aliases:      count, [(string alias, string code)]
+//! languages:    count, [(string code, string title)]
+//! translations: count, [(string code, entries offset, entries count)]
+//! entries:      [(string key, string translation)] sorted by key, per language
+//! string table
+//! ```
+//!
+
+use crate::error::Error;
+use crate::i18n::FxHashMap;
+use crate::result::Result;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+const MAGIC: &[u8; 8] = b"wi18n\0\0\x01";
+const STRING: usize = 8;
+const ENTRY: usize = 2 * STRING;
+
+/// Includes the dictionary compiled from the JSON data file `path`
+/// by [`compile()`] in the build script, as `&'static [u8]`.
+#[macro_export]
+macro_rules! embed_dict {
+    ($path:literal) => {
+        include_bytes!(concat!(env!("OUT_DIR"), "/", $path, ".dict")) as &'static [u8]
+    };
+}
+
+/// `i18n.json` data file structure with owned strings
+/// (keys are kept sorted by [`BTreeMap`]).
+#[derive(serde::Deserialize)]
+struct Source {
+    enabled: Vec<String>,
+    aliases: BTreeMap<String, String>,
+    languages: BTreeMap<String, String>,
+    translations: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// Compiles the JSON data file (path relative to the crate directory) into
+/// `$OUT_DIR/<path>.dict` for [`embed_dict!`](crate::embed_dict). Intended
+/// to be called from the build script.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn compile(json_file: &str) -> Result<()> {
+    let out_dir = std::env::var("OUT_DIR").map_err(|_| Error::custom("OUT_DIR is not set"))?;
+    println!("cargo:rerun-if-changed={json_file}");
+    let json_data = std::fs::read_to_string(json_file)?;
+    let target = std::path::Path::new(&out_dir).join(format!("{json_file}.dict"));
+    if let Some(folder) = target.parent() {
+        std::fs::create_dir_all(folder)?;
+    }
+    std::fs::write(target, compile_str(&json_data)?)?;
+    Ok(())
+}
+
+/// Compiles JSON data (the `i18n.json` data file contents) into the embedded format.
+pub fn compile_str(json_data: &str) -> Result<Vec<u8>> {
+    let source: Source = serde_json::from_str(json_data)?;
+    let mut writer = Writer::default();
+
+    writer.u32(source.enabled.len());
+    for code in source.enabled.iter() {
+        writer.string(code);
+    }
+    for map in [&source.aliases, &source.languages] {
+        writer.u32(map.len());
+        for (key, value) in map.iter() {
+            writer.string(key);
+            writer.string(value);
+        }
+    }
+
+    // entries follow the language index
+    let mut offset = writer.index.len() + 4 + source.translations.len() * (STRING + 8);
+    writer.u32(source.translations.len());
+    for (code, entries) in source.translations.iter() {
+        writer.string(code);
+        writer.u32(offset);
+        writer.u32(entries.len());
+        offset += entries.len() * ENTRY;
+    }
+    for entries in source.translations.values() {
+        for (key, value) in entries.iter() {
+            writer.string(key);
+            writer.string(value);
+        }
+    }
+
+    let index_len = MAGIC.len() + 8 + writer.index.len();
+    let mut data = Vec::with_capacity(index_len + writer.strings.len());
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&to_u32(index_len).to_le_bytes());
+    data.extend_from_slice(&to_u32(writer.strings.len()).to_le_bytes());
+    data.extend_from_slice(&writer.index);
+    data.extend_from_slice(writer.strings.as_bytes());
+    Ok(data)
+}
+
+fn to_u32(value: usize) -> u32 {
+    u32::try_from(value).expect("i18n: embedded dictionary exceeds 4GiB")
+}
+
+#[derive(Default)]
+struct Writer {
+    index: Vec<u8>,
+    strings: String,
+    offsets: HashMap<String, (u32, u32)>,
+}
+
+impl Writer {
+    fn u32(&mut self, value: usize) {
+        self.index.extend_from_slice(&to_u32(value).to_le_bytes());
+    }
+
+    fn string(&mut self, text: &str) {
+        let (offset, len) = match self.offsets.get(text) {
+            Some(reference) => *reference,
+            None => {
+                let reference = (to_u32(self.strings.len()), to_u32(text.len()));
+                self.strings.push_str(text);
+                self.offsets.insert(text.to_string(), reference);
+                reference
+            }
+        };
+        self.index.extend_from_slice(&offset.to_le_bytes());
+        self.index.extend_from_slice(&len.to_le_bytes());
+    }
+}
+
+/// Embedded dictionary data with strings borrowed from the embedded bytes
+pub(crate) struct Embedded {
+    pub enabled: Vec<&'static str>,
+    pub aliases: FxHashMap<&'static str, &'static str>,
+    pub languages: FxHashMap<&'static str, &'static str>,
+    pub translations: FxHashMap<&'static str, EmbeddedTranslations>,
+}
+
+/// Parses the index of the embedded dictionary; the translation
+/// entries are not read until looked up.
+pub(crate) fn parse(data: &'static [u8]) -> Result<Embedded> {
+    if !data.starts_with(MAGIC) {
+        return Err(Error::Embedded("unknown format".to_string()));
+    }
+    let header = &data[MAGIC.len()..];
+    let index_len = read_u32(header, 0)?;
+    let strings_len = read_u32(header, 4)?;
+    let strings = index_len
+        .checked_add(strings_len)
+        .and_then(|end| data.get(index_len..end))
+        .ok_or_else(|| Error::Embedded("truncated string table".to_string()))?;
+    let strings = std::str::from_utf8(strings)
+        .map_err(|_| Error::Embedded("invalid string table".to_string()))?;
+
+    let body = data
+        .get(MAGIC.len() + 8..index_len)
+        .ok_or_else(|| Error::Embedded("truncated index".to_string()))?;
+    let mut reader = Reader {
+        index: body,
+        strings,
+        pos: 0,
+    };
+
+    let enabled = (0..reader.u32()?)
+        .map(|_| reader.string())
+        .collect::<Result<Vec<_>>>()?;
+    let mut maps = [FxHashMap::default(), FxHashMap::default()];
+    for map in maps.iter_mut() {
+        for _ in 0..reader.u32()? {
+            map.insert(reader.string()?, reader.string()?);
+        }
+    }
+    let [aliases, languages] = maps;
+
+    let mut translations = FxHashMap::default();
+    for _ in 0..reader.u32()? {
+        let code = reader.string()?;
+        let offset = reader.u32()?;
+        let len = reader.u32()?;
+        let entries = len
+            .checked_mul(ENTRY)
+            .and_then(|len| offset.checked_add(len))
+            .and_then(|end| body.get(offset..end))
+            .ok_or_else(|| Error::Embedded(format!("truncated translations for `{code}`")))?;
+        translations.insert(code, EmbeddedTranslations { entries, strings });
+    }
+
+    Ok(Embedded {
+        enabled,
+        aliases,
+        languages,
+        translations,
+    })
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Result<usize> {
+    data.get(pos..pos + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+        .ok_or_else(|| Error::Embedded("truncated index".to_string()))
+}
+
+fn read_string(data: &[u8], pos: usize, strings: &'static str) -> Option<&'static str> {
+    let offset = read_u32(data, pos).ok()?;
+    let len = read_u32(data, pos + 4).ok()?;
+    strings.get(offset..offset.checked_add(len)?)
+}
+
+struct Reader {
+    index: &'static [u8],
+    strings: &'static str,
+    pos: usize,
+}
+
+impl Reader {
+    fn u32(&mut self) -> Result<usize> {
+        let value = read_u32(self.index, self.pos)?;
+        self.pos += 4;
+        Ok(value)
+    }
+
+    fn string(&mut self) -> Result<&'static str> {
+        let string = read_string(self.index, self.pos, self.strings)
+            .ok_or_else(|| Error::Embedded("invalid string reference".to_string()))?;
+        self.pos += STRING;
+        Ok(string)
+    }
+}
+
+/// Translations of a language within an embedded dictionary
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedTranslations {
+    /// `(key, translation)` string references sorted by key
+    entries: &'static [u8],
+    strings: &'static str,
+}
+
+impl EmbeddedTranslations {
+    pub fn len(&self) -> usize {
+        self.entries.len() / ENTRY
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn key(&self, index: usize) -> Option<&'static str> {
+        read_string(self.entries, index * ENTRY, self.strings)
+    }
+
+    fn value(&self, index: usize) -> Option<&'static str> {
+        read_string(self.entries, index * ENTRY + STRING, self.strings)
+    }
+
+    pub fn get(&self, text: &str) -> Option<&'static str> {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let middle = low + (high - low) / 2;
+            match self.key(middle)?.as_bytes().cmp(text.as_bytes()) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => return self.value(middle),
+            }
+        }
+        None
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &'static str)> + '_ {
+        (0..self.len()).filter_map(|index| Some((self.key(index)?, self.value(index)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r#"{
+        "enabled": ["en", "ja"],
+        "aliases": { "en-GB": "en", "en-US": "en" },
+        "languages": { "en": "English", "ja": "日本語", "de": "Deutsch" },
+        "translations": {
+            "en": {},
+            "ja": { "Hello": "こんにちは", "Goodbye": "さようなら", "Line\nbreak": "改行\n" },
+            "de": { "Hello": "Hallo" }
+        }
+    }"#;
+
+    #[test]
+    fn test_embedded_dictionary() {
+        let data: &'static [u8] = Box::leak(compile_str(JSON).unwrap().into_boxed_slice());
+        let embedded = parse(data).unwrap();
+
+        assert_eq!(embedded.enabled, vec!["en", "ja"]);
+        assert_eq!(embedded.aliases.get("en-GB"), Some(&"en"));
+        assert_eq!(embedded.languages.get("ja"), Some(&"日本語"));
+        assert_eq!(embedded.languages.len(), 3);
+
+        let ja = embedded.translations.get("ja").unwrap();
+        assert_eq!(ja.len(), 3);
+        assert_eq!(ja.get("Hello"), Some("こんにちは"));
+        assert_eq!(ja.get("Goodbye"), Some("さようなら"));
+        assert_eq!(ja.get("Line\nbreak"), Some("改行\n"));
+        assert_eq!(ja.get("Hell"), None);
+        assert_eq!(ja.get("Hello!"), None);
+        let keys = ja.iter().map(|(key, _)| key).collect::<Vec<_>>();
+        assert_eq!(keys, vec!["Goodbye", "Hello", "Line\nbreak"]);
+        assert!(embedded.translations.get("en").unwrap().is_empty());
+        assert_eq!(
+            embedded.translations.get("de").unwrap().get("Hello"),
+            Some("Hallo")
+        );
+    }
+
+    #[test]
+    fn test_invalid_embedded_dictionary() {
+        let data = compile_str(JSON).unwrap();
+        assert!(parse(b"json").is_err());
+        let truncated: &'static [u8] =
+            Box::leak(data[..data.len() / 2].to_vec().into_boxed_slice());
+        assert!(parse(truncated).is_err());
+    }
+}
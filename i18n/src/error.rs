@@ -17,6 +17,9 @@ pub enum Error {
     #[error("i18n: received invalid language code '{0}'")]
     UnknownLanguageCode(String),
 
+    #[error("i18n: invalid embedded dictionary: {0}")]
+    Embedded(String),
+
     #[error("i18n: unable to get storage path")]
     StoragePath,
 
@@ -25,6 +28,9 @@ pub enum Error {
 
     #[error("i18n: io failure: {0}")]
     Io(#[from] std::io::Error),
+
+    #[error("i18n: store failure: {0}")]
+    Store(#[from] workflow_store::error::Error),
 }
 
 impl Error {
@@ -1,3 +1,4 @@
+use crate::embed::{self, EmbeddedTranslations};
 use crate::error::Error;
 use crate::result::Result;
 use arc_swap::*;
@@ -9,6 +10,10 @@ use std::sync::{Arc, Mutex, MutexGuard};
 pub type FxBuildHasher = BuildHasherDefault<FxHasher64>;
 pub type FxHashMap<K, V> = HashMap<K, V, FxBuildHasher>;
 
+pub use crate::detect::{
+    detect_language, init_auto, set_language, system_locales, LanguageCode, LANGUAGE_STORE_PATH,
+};
+
 static mut JSON_DATA: Option<String> = None;
 static mut JSON_DATA_GUARD: Option<Mutex<()>> = None;
 static DICTIONARY: ArcSwapOption<Dictionary> = ArcSwapOption::const_empty();
@@ -21,6 +26,7 @@ pub struct Builder {
     default_code: String,
     static_json_data: Option<&'static str>,
     string_json_data: Option<String>,
+    embedded_data: Option<&'static [u8]>,
     store_fn: Option<Arc<StoreFn>>,
 }
 
@@ -31,6 +37,7 @@ impl Builder {
             default_code: default_code.to_string(),
             static_json_data: None,
             string_json_data: None,
+            embedded_data: None,
             store_fn: None,
         }
     }
@@ -45,6 +52,14 @@ impl Builder {
         self
     }
 
+    /// Use a dictionary compiled by the build script and included
+    /// via [`embed_dict!`](crate::embed_dict) (see [`embed`](crate::embed)).
+    /// Takes precedence over JSON data.
+    pub fn with_embedded_data(mut self, data: &'static [u8]) -> Self {
+        self.embedded_data = Some(data);
+        self
+    }
+
     pub fn with_store(
         mut self,
         store_fn: impl Fn(&str) -> Result<()> + Send + Sync + 'static,
@@ -54,6 +69,17 @@ impl Builder {
     }
 
     pub fn try_init(self) -> Result<()> {
+        if let Some(data) = self.embedded_data {
+            let dictionary = Arc::new(Dictionary::try_from_embedded(
+                self.current_code,
+                self.default_code,
+                data,
+                self.store_fn,
+            )?);
+            DICTIONARY.swap(Some(dictionary));
+            return Ok(());
+        }
+
         let json_data = if let Some(json_data) = self.string_json_data {
             unsafe {
                 JSON_DATA = Some(json_data);
@@ -135,17 +161,15 @@ pub fn import_translation_files<P: AsRef<Path>>(source_folder_path: P, reload: b
 
     let translations = dictionary
         .translations
-        .clone()
-        .into_iter()
+        .iter()
+        .map(|(code, translation)| (*code, translation.iter().collect::<FxHashMap<_, _>>()))
         .chain(merged_translations.iter().map(|(code, translation)| {
             (
                 code.as_str(),
-                Arc::new(
-                    translation
-                        .iter()
-                        .map(|(k, v)| (k.as_str(), v.as_str()))
-                        .collect(),
-                ),
+                translation
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.as_str()))
+                    .collect(),
             )
         }))
         .collect();
@@ -199,6 +223,11 @@ pub fn dictionary() -> Arc<Dictionary> {
     DICTIONARY.load().as_ref().unwrap().clone()
 }
 
+/// Obtain the dictionary if it has been initialized.
+pub fn try_dictionary() -> Option<Arc<Dictionary>> {
+    DICTIONARY.load_full()
+}
+
 pub fn guard() -> MutexGuard<'static, ()> {
     unsafe { JSON_DATA_GUARD.as_ref().unwrap().lock().unwrap() }
 }
@@ -328,6 +357,49 @@ mod tests {
         // println!("{translated}");
         assert_eq!(translated, "Hello, John!");
     }
+
+    const JSON: &str = r#"{
+        "enabled": ["en", "ja", "de"],
+        "aliases": { "en-GB": "en", "ja-JP": "ja" },
+        "languages": { "en": "English", "ja": "日本語", "de": "Deutsch" },
+        "translations": {
+            "en": { "Hello": "Hello", "Settings": "Settings" },
+            "ja": { "Hello": "こんにちは", "Settings": "設定", "Hello, {name}!": "こんにちは、{name}!" },
+            "de": { "Hello": "Hallo" }
+        }
+    }"#;
+
+    #[test]
+    pub fn test_embedded_translations_match_json() {
+        let data = Box::leak(crate::embed::compile_str(JSON).unwrap().into_boxed_slice());
+        let json = Dictionary::try_new("ja-JP", "en", Some(JSON), None).unwrap();
+        let embedded = Dictionary::try_from_embedded("ja-JP", "en", data, None).unwrap();
+
+        assert_eq!(json.current_code(), embedded.current_code());
+        assert_eq!(json.current_title(), embedded.current_title());
+        let mut enabled = embedded.enabled_languages();
+        enabled.sort();
+        assert_eq!(
+            enabled,
+            [("de", "Deutsch"), ("en", "English"), ("ja", "日本語")]
+        );
+        assert_eq!(json.to_json().unwrap(), embedded.to_json().unwrap());
+
+        for (code, _) in json.enabled_languages() {
+            json.activate_language_code(code).unwrap();
+            embedded.activate_language_code(code).unwrap();
+            let translations = json.current_translations();
+            assert_eq!(translations.len(), embedded.current_translations().len());
+            for (text, translated) in translations.iter() {
+                assert_eq!(embedded.translate(text), Some(translated));
+            }
+            assert_eq!(embedded.translate("Missing"), None);
+        }
+        assert_eq!(
+            embedded.default_translations().get("Settings"),
+            Some("Settings")
+        );
+    }
 }
 
 /// Dictionary structure containing all translations and related data.
@@ -337,7 +409,7 @@ pub struct Dictionary {
     /// list of aliases ("en-GB": "en"), ("en-US": "en"), ("zh-CN": "zh_HANS"), ("zh-TW": "zh_HANT") etc.
     aliases: FxHashMap<&'static str, &'static str>,
     /// Map of translations {"ja": { "Hello" : "こんにちは" }}
-    translations: FxHashMap<&'static str, Arc<FxHashMap<&'static str, &'static str>>>,
+    translations: FxHashMap<&'static str, Arc<Translations>>,
     /// Missing translation entries (default language)
     missing: Mutex<FxHashMap<String, String>>,
     /// Enabled language codes ["en", "ja"]
@@ -347,11 +419,11 @@ pub struct Dictionary {
     /// Current language title
     current_title: ArcSwap<String>,
     /// Current language translations {"Hello" : "こんにちは", ...}
-    current_translations: ArcSwap<FxHashMap<&'static str, &'static str>>,
+    current_translations: ArcSwap<Translations>,
     /// Default language code (the language used in the source code)
     default_code: String,
     /// Default language translations {"Hello" : "Hello", ...}
-    default_translations: Arc<FxHashMap<&'static str, &'static str>>,
+    default_translations: Arc<Translations>,
     // / Full data file path
     // json_data_file_path: Option<PathBuf>,
     /// Storage callback function
@@ -377,7 +449,60 @@ impl Dictionary {
         } else {
             Data::default()
         };
+        let translations = translations
+            .into_iter()
+            .map(|(code, translations)| (code, Arc::new(Translations::Map(translations))))
+            .collect();
 
+        Self::try_from_parts(
+            current_code,
+            default_code,
+            enabled,
+            languages,
+            aliases,
+            translations,
+            store_fn,
+        )
+    }
+
+    /// Create a new dictionary from the embedded data (see [`embed`](crate::embed)).
+    fn try_from_embedded(
+        current_code: impl Into<String>,
+        default_code: impl Into<String>,
+        data: &'static [u8],
+        store_fn: Option<Arc<StoreFn>>,
+    ) -> Result<Self> {
+        let embed::Embedded {
+            enabled,
+            aliases,
+            languages,
+            translations,
+        } = embed::parse(data)?;
+        let translations = translations
+            .into_iter()
+            .map(|(code, translations)| (code, Arc::new(Translations::Embedded(translations))))
+            .collect();
+
+        Self::try_from_parts(
+            current_code,
+            default_code,
+            enabled,
+            languages,
+            aliases,
+            translations,
+            store_fn,
+        )
+    }
+
+    fn try_from_parts(
+        current_code: impl Into<String>,
+        default_code: impl Into<String>,
+        enabled: Vec<&'static str>,
+        languages: FxHashMap<&'static str, &'static str>,
+        aliases: FxHashMap<&'static str, &'static str>,
+        translations: FxHashMap<&'static str, Arc<Translations>>,
+        store_fn: Option<Arc<StoreFn>>,
+    ) -> Result<Self> {
         let current_code: String = current_code.into();
         let current_code = aliases
             .get(current_code.as_str())
@@ -460,18 +585,17 @@ impl Dictionary {
     }
 
     #[inline(always)]
-    pub fn current_translations(&self) -> Arc<FxHashMap<&'static str, &'static str>> {
+    pub fn current_translations(&self) -> Arc<Translations> {
         self.current_translations.load().clone()
     }
 
     #[inline(always)]
     pub fn translate(&self, text: &str) -> Option<&'static str> {
-        let current_translations = self.current_translations.load().clone();
-        current_translations.get(text).copied()
+        self.current_translations.load().get(text)
     }
 
     #[inline(always)]
-    pub fn default_translations(&self) -> &Arc<FxHashMap<&'static str, &'static str>> {
+    pub fn default_translations(&self) -> &Arc<Translations> {
         &self.default_translations
     }
 
@@ -488,9 +612,9 @@ impl Dictionary {
         let language_code: String = language_code.into();
         let current_code = self.resolve_aliases(language_code.as_str())?;
         let current_title = self.language_title(current_code.as_str())?.to_string();
-        let current_translations = dictionary()
+        let current_translations = self
             .translations
-            .get(language_code.as_str())
+            .get(current_code.as_str())
             .ok_or(Error::UnknownLanguageCode(language_code))?
             .clone();
 
@@ -501,6 +625,18 @@ impl Dictionary {
         Ok(())
     }
 
+    /// Normalize a locale (such as `en-GB` or `en_GB.UTF-8`) to the code
+    /// of an enabled language, falling back on the primary language
+    /// subtag (`en-GB` → `en`). Returns `None` if the language is not enabled.
+    pub fn normalize_language_code(&self, locale: &str) -> Option<&'static str> {
+        let aliases = self
+            .aliases
+            .iter()
+            .map(|(alias, code)| (*alias, *code))
+            .collect::<Vec<_>>();
+        crate::detect::normalize(locale, &self.enabled, &aliases)
+    }
+
     /// Obtain a list of available language codes.
     pub fn language_codes(&self) -> Vec<String> {
         self.languages.keys().map(|s| s.to_string()).collect()
@@ -524,6 +660,41 @@ impl Dictionary {
     }
 }
 
+/// Translations of a language: a map (JSON data) or a table
+/// within an embedded dictionary (see [`embed`](crate::embed)).
+pub enum Translations {
+    Map(FxHashMap<&'static str, &'static str>),
+    Embedded(EmbeddedTranslations),
+}
+
+impl Translations {
+    #[inline(always)]
+    pub fn get(&self, text: &str) -> Option<&'static str> {
+        match self {
+            Translations::Map(map) => map.get(text).copied(),
+            Translations::Embedded(table) => table.get(text),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Translations::Map(map) => map.len(),
+            Translations::Embedded(table) => table.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (&'static str, &'static str)> + '_> {
+        match self {
+            Translations::Map(map) => Box::new(map.iter().map(|(k, v)| (*k, *v))),
+            Translations::Embedded(table) => Box::new(table.iter()),
+        }
+    }
+}
+
 pub struct Languages(FxHashMap<&'static str, &'static str>);
 
 impl Default for Languages {
@@ -607,7 +778,7 @@ pub struct Data<'data> {
     enabled: Vec<&'data str>,
     aliases: FxHashMap<&'data str, &'data str>,
     languages: FxHashMap<&'data str, &'data str>,
-    translations: FxHashMap<&'data str, Arc<FxHashMap<&'data str, &'data str>>>,
+    translations: FxHashMap<&'data str, FxHashMap<&'data str, &'data str>>,
 }
 
 impl<'data> Default for Data<'data> {
@@ -625,7 +796,7 @@ impl<'data> Default for Data<'data> {
 
         let mut translations = FxHashMap::default();
         languages.iter().for_each(|(code, _)| {
-            translations.insert(*code, Arc::new(FxHashMap::default()));
+            translations.insert(*code, FxHashMap::default());
         });
 
         Data {
@@ -8,6 +8,8 @@
 //!
 //! i18n is a performance-oriented library for internationalization and translation embedding into Rust applications.
 //!
+pub mod detect;
+pub mod embed;
 pub mod error;
 pub mod i18n;
 pub mod json;
@@ -77,15 +77,28 @@ pub use self::console::*;
 
 pub mod levels;
 
+#[cfg(all(not(target_arch = "bpf"), any(test, feature = "test")))]
+pub mod test;
+
+#[cfg(not(target_arch = "bpf"))]
+pub mod throttle;
+#[cfg(not(target_arch = "bpf"))]
+pub use throttle::{clear_rate_limit, set_rate_limit};
+
 pub mod prelude {
     pub use super::console::*;
     pub use super::log::{
         log_debug, log_error, log_info, log_trace, log_warn, set_log_level, Level, LevelFilter,
     };
+    #[cfg(not(target_arch = "bpf"))]
+    pub use super::throttle::{
+        log_debug_throttled, log_error_throttled, log_info_throttled, log_trace_throttled,
+        log_warn_throttled,
+    };
 }
 
 #[cfg(test)]
-mod test {
+mod tests {
     use crate::*;
     use std::sync::Arc;
 
@@ -255,10 +255,30 @@ pub mod wasm_log {
 pub mod impls {
     use super::*;
 
+    /// Logs the message with the given `level`
+    #[inline(always)]
+    pub fn log_impl(level: Level, target: Option<&str>, args: &fmt::Arguments<'_>) {
+        match level {
+            Level::Error => error_impl(target, args),
+            Level::Warn => warn_impl(target, args),
+            Level::Info => info_impl(target, args),
+            Level::Debug => debug_impl(target, args),
+            Level::Trace => trace_impl(target, args),
+        }
+    }
+
     #[inline(always)]
     #[allow(unused_variables)]
     pub fn error_impl(target: Option<&str>, args: &fmt::Arguments<'_>) {
+        #[cfg(not(target_arch = "bpf"))]
+        if !crate::throttle::admit(Level::Error) {
+            return;
+        }
         if log_level_enabled(Level::Error) {
+            #[cfg(all(not(target_arch = "bpf"), any(test, feature = "test")))]
+            if crate::test::capture_record(target, Level::Error, args) {
+                return;
+            }
             #[cfg(all(not(target_arch = "bpf"), feature = "sink"))]
             {
                 if to_sink(target, Level::Error, args) {
@@ -280,7 +300,15 @@ pub mod impls {
     #[inline(always)]
     #[allow(unused_variables)]
     pub fn warn_impl(target: Option<&str>, args: &fmt::Arguments<'_>) {
+        #[cfg(not(target_arch = "bpf"))]
+        if !crate::throttle::admit(Level::Warn) {
+            return;
+        }
         if log_level_enabled(Level::Warn) {
+            #[cfg(all(not(target_arch = "bpf"), any(test, feature = "test")))]
+            if crate::test::capture_record(target, Level::Warn, args) {
+                return;
+            }
             #[cfg(all(not(target_arch = "bpf"), feature = "sink"))]
             {
                 if to_sink(target, Level::Warn, args) {
@@ -302,7 +330,15 @@ pub mod impls {
     #[inline(always)]
     #[allow(unused_variables)]
     pub fn info_impl(target: Option<&str>, args: &fmt::Arguments<'_>) {
+        #[cfg(not(target_arch = "bpf"))]
+        if !crate::throttle::admit(Level::Info) {
+            return;
+        }
         if log_level_enabled(Level::Info) {
+            #[cfg(all(not(target_arch = "bpf"), any(test, feature = "test")))]
+            if crate::test::capture_record(target, Level::Info, args) {
+                return;
+            }
             #[cfg(all(not(target_arch = "bpf"), feature = "sink"))]
             {
                 if to_sink(target, Level::Info, args) {
@@ -324,7 +360,15 @@ pub mod impls {
     #[inline(always)]
     #[allow(unused_variables)]
     pub fn debug_impl(target: Option<&str>, args: &fmt::Arguments<'_>) {
+        #[cfg(not(target_arch = "bpf"))]
+        if !crate::throttle::admit(Level::Debug) {
+            return;
+        }
         if log_level_enabled(Level::Debug) {
+            #[cfg(all(not(target_arch = "bpf"), any(test, feature = "test")))]
+            if crate::test::capture_record(target, Level::Debug, args) {
+                return;
+            }
             #[cfg(all(not(target_arch = "bpf"), feature = "sink"))]
             {
                 if to_sink(target, Level::Debug, args) {
@@ -346,7 +390,15 @@ pub mod impls {
     #[inline(always)]
     #[allow(unused_variables)]
     pub fn trace_impl(target: Option<&str>, args: &fmt::Arguments<'_>) {
+        #[cfg(not(target_arch = "bpf"))]
+        if !crate::throttle::admit(Level::Trace) {
+            return;
+        }
         if log_level_enabled(Level::Trace) {
+            #[cfg(all(not(target_arch = "bpf"), any(test, feature = "test")))]
+            if crate::test::capture_record(target, Level::Trace, args) {
+                return;
+            }
             #[cfg(all(not(target_arch = "bpf"), feature = "sink"))]
             {
                 if to_sink(target, Level::Trace, args) {
@@ -0,0 +1,216 @@
+//!
+//! Log capture for unit tests. [`capture()`] returns a guard collecting
+//! log records produced by the current thread for the lifetime of the guard:
+//!
+//! ```ignore
+//! let guard = workflow_log::test::capture();
+//! log_warn!("connection lost");
+//! assert!(guard.contains(Level::Warn, "lost"));
+//! ```
+//!
+//! Captured records are not passed to the installed [`Sink`](crate::Sink) or
+//! the console, while log output of other threads (including concurrently
+//! running tests) is unaffected. Only records passing the log level filter
+//! are captured. Nested captures stack: records are collected by the most
+//! recently created capture until it is dropped.
+//!
+//! This module is available in the crate's own tests and, for dependent
+//! crates, when the `test` feature is enabled (typically as a dev-dependency).
+//!
+//! Capture is thread-local; logs produced by tasks running on other
+//! threads (e.g. multi-threaded async executors) are not captured.
+//!
+
+use crate::Level;
+use std::cell::RefCell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Captured log record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub level: Level,
+    pub target: Option<String>,
+    pub message: String,
+}
+
+type Records = Arc<Mutex<Vec<Record>>>;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static CAPTURES: RefCell<Vec<(u64, Records)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Log capture guard created by [`capture()`]. Capture stops when the guard
+/// is dropped (including during unwinding caused by a panic).
+pub struct CaptureGuard {
+    id: u64,
+    records: Records,
+    // the capture is bound to the thread that created it
+    _thread: PhantomData<*const ()>,
+}
+
+impl CaptureGuard {
+    /// Records captured so far
+    pub fn records(&self) -> Vec<Record> {
+        self.records.lock().unwrap().clone()
+    }
+
+    /// Checks if a record with the given `level` containing `text` has been captured.
+    pub fn contains(&self, level: Level, text: &str) -> bool {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|record| record.level == level && record.message.contains(text))
+    }
+
+    /// Discards captured records
+    pub fn clear(&self) {
+        self.records.lock().unwrap().clear();
+    }
+}
+
+impl Drop for CaptureGuard {
+    fn drop(&mut self) {
+        // guards can be dropped out of order, hence removal by id
+        CAPTURES
+            .try_with(|captures| captures.borrow_mut().retain(|(id, _)| *id != self.id))
+            .ok();
+    }
+}
+
+/// Starts capturing log records produced by the current thread.
+pub fn capture() -> CaptureGuard {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let records = Records::default();
+    CAPTURES.with(|captures| captures.borrow_mut().push((id, records.clone())));
+    CaptureGuard {
+        id,
+        records,
+        _thread: PhantomData,
+    }
+}
+
+/// Passes the record to the active capture of the current thread,
+/// returning `true` if the record has been captured.
+#[inline(always)]
+pub(crate) fn capture_record(
+    target: Option<&str>,
+    level: Level,
+    args: &fmt::Arguments<'_>,
+) -> bool {
+    let records = CAPTURES
+        .try_with(|captures| captures.borrow().last().map(|(_, records)| records.clone()))
+        .ok()
+        .flatten();
+    match records {
+        Some(records) => {
+            // format before locking as formatting may log
+            let record = Record {
+                level,
+                target: target.map(String::from),
+                message: args.to_string(),
+            };
+            records.lock().unwrap().push(record);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn test_capture_records() {
+        let guard = capture();
+        log_error!("error {}", 1);
+        log_trace!("trace {}", 2);
+        assert!(guard.contains(Level::Error, "error 1"));
+        // below the default log level filter
+        assert!(!guard.contains(Level::Trace, "trace 2"));
+        assert_eq!(guard.records().len(), 1);
+        assert!(!guard.contains(Level::Info, "error 1"));
+        assert_eq!(
+            guard.records()[0],
+            Record {
+                level: Level::Error,
+                target: None,
+                message: "error 1".to_string()
+            }
+        );
+        guard.clear();
+        assert!(guard.records().is_empty());
+    }
+
+    #[test]
+    fn test_capture_nested() {
+        let outer = capture();
+        log_info!("outer 1");
+        {
+            let inner = capture();
+            log_info!("inner");
+            assert!(inner.contains(Level::Info, "inner"));
+        }
+        log_info!("outer 2");
+
+        let messages = outer
+            .records()
+            .into_iter()
+            .map(|record| record.message)
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec!["outer 1", "outer 2"]);
+    }
+
+    #[test]
+    fn test_capture_panic() {
+        let outer = capture();
+        let result = std::panic::catch_unwind(|| {
+            let inner = capture();
+            log_warn!("before panic");
+            assert!(inner.contains(Level::Warn, "before panic"));
+            panic!("capture test panic");
+        });
+        assert!(result.is_err());
+
+        // the inner capture has been removed during unwinding
+        log_warn!("after panic");
+        assert_eq!(outer.records().len(), 1);
+        assert!(outer.contains(Level::Warn, "after panic"));
+    }
+
+    #[test]
+    fn test_capture_isolation() {
+        let threads = (0..8)
+            .map(|n| {
+                std::thread::spawn(move || {
+                    let guard = capture();
+                    for i in 0..100 {
+                        log_info!("thread {n} message {i}");
+                    }
+                    let records = guard.records();
+                    assert_eq!(records.len(), 100);
+                    assert!(records
+                        .iter()
+                        .all(|record| record.message.starts_with(&format!("thread {n} "))));
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // threads without a capture are unaffected
+        let guard = capture();
+        std::thread::spawn(|| log_info!("uncaptured"))
+            .join()
+            .unwrap();
+        assert!(guard.records().is_empty());
+    }
+}
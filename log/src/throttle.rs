@@ -0,0 +1,329 @@
+//!
+//! Rate limiting of noisy log statements.
+//!
+//! Throttled macros ([`log_warn_throttled!`](crate::log_warn_throttled) etc.)
+//! emit at most one record per time window per call site. The first record
+//! logged after the window has elapsed is followed by a summary record
+//! `(suppressed N similar messages at file:line)`:
+//!
+//! ```ignore
+//! loop {
+//!     log_warn_throttled!(Duration::from_secs(5), "peer {} is not responding", peer);
+//! }
+//! ```
+//!
+//! In addition, [`set_rate_limit()`] installs a global limit on the number
+//! of records per second of a given level. Records exceeding the limit are
+//! dropped and reported by a summary record once the next second starts.
+//!
+//! Both the per call site and the global counters are atomics and are cheap
+//! to check; summary records pass through the sink like any other record.
+//!
+
+use crate::{impls, Level};
+use std::fmt;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+const NEVER: u64 = u64::MAX;
+const UNLIMITED: u32 = u32::MAX;
+
+cfg_if::cfg_if! {
+    if #[cfg(target_arch = "wasm32")] {
+        use wasm_bindgen::prelude::*;
+
+        #[wasm_bindgen]
+        extern "C" {
+            #[wasm_bindgen(js_namespace = Date, js_name = now)]
+            fn date_now() -> f64;
+        }
+
+        #[inline(always)]
+        fn now_millis() -> u64 {
+            date_now() as u64
+        }
+    } else {
+        use std::time::Instant;
+
+        lazy_static::lazy_static! {
+            static ref START: Instant = Instant::now();
+        }
+
+        #[inline(always)]
+        fn now_millis() -> u64 {
+            START.elapsed().as_millis() as u64
+        }
+    }
+}
+
+/// Per call site throttle declared as a `static` by the throttled macros.
+pub struct Throttle {
+    // start of the current window in msec, `NEVER` if nothing has been emitted
+    window_start: AtomicU64,
+    suppressed: AtomicU64,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Throttle {
+    pub const fn new() -> Self {
+        Self {
+            window_start: AtomicU64::new(NEVER),
+            suppressed: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns `Some(suppressed)` if the record should be emitted, where
+    /// `suppressed` is the number of records suppressed since the last
+    /// emitted one, or `None` if the record should be suppressed.
+    pub fn admit(&self, window: Duration) -> Option<u64> {
+        let now = now_millis();
+        let start = self.window_start.load(Ordering::Acquire);
+        if (start == NEVER || now.saturating_sub(start) >= window.as_millis() as u64)
+            && self
+                .window_start
+                .compare_exchange(start, now, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+        {
+            Some(self.suppressed.swap(0, Ordering::AcqRel))
+        } else {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+
+    /// Number of records suppressed in the current window
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed.load(Ordering::Relaxed)
+    }
+}
+
+/// Emits the record admitted by [`Throttle::admit()`], followed by
+/// the summary of the `suppressed` records (if any).
+pub fn emit(level: Level, suppressed: u64, file: &str, line: u32, args: &fmt::Arguments<'_>) {
+    impls::log_impl(level, None, args);
+    if suppressed > 0 {
+        impls::log_impl(
+            level,
+            None,
+            &format_args!("(suppressed {suppressed} similar messages at {file}:{line})"),
+        );
+    }
+}
+
+struct RateLimit {
+    per_second: AtomicU32,
+    second: AtomicU64,
+    count: AtomicU32,
+    dropped: AtomicU64,
+}
+
+impl RateLimit {
+    const fn new() -> Self {
+        Self {
+            per_second: AtomicU32::new(UNLIMITED),
+            second: AtomicU64::new(0),
+            count: AtomicU32::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+}
+
+static RATE_LIMITS: [RateLimit; 5] = [
+    RateLimit::new(),
+    RateLimit::new(),
+    RateLimit::new(),
+    RateLimit::new(),
+    RateLimit::new(),
+];
+
+#[inline(always)]
+fn rate_limit(level: Level) -> &'static RateLimit {
+    &RATE_LIMITS[level as usize - 1]
+}
+
+/// Limits the number of records of `level` emitted per second
+/// (across all call sites). Records exceeding the limit are dropped.
+pub fn set_rate_limit(level: Level, per_second: u32) {
+    rate_limit(level)
+        .per_second
+        .store(per_second.min(UNLIMITED - 1), Ordering::Relaxed);
+}
+
+/// Removes the rate limit of `level` installed by [`set_rate_limit()`].
+pub fn clear_rate_limit(level: Level) {
+    let limit = rate_limit(level);
+    limit.per_second.store(UNLIMITED, Ordering::Relaxed);
+    limit.dropped.store(0, Ordering::Relaxed);
+}
+
+/// Checks the global rate limit of `level`, returning
+/// `false` if the record should be dropped.
+#[inline(always)]
+pub(crate) fn admit(level: Level) -> bool {
+    let limit = rate_limit(level);
+    let per_second = limit.per_second.load(Ordering::Relaxed);
+    if per_second == UNLIMITED {
+        return true;
+    }
+
+    let second = now_millis() / 1000;
+    let current = limit.second.load(Ordering::Acquire);
+    if current != second
+        && limit
+            .second
+            .compare_exchange(current, second, Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok()
+    {
+        limit.count.store(0, Ordering::Release);
+        let dropped = limit.dropped.swap(0, Ordering::AcqRel);
+        if dropped > 0 {
+            // counts towards the limit of the new second
+            impls::log_impl(
+                level,
+                None,
+                &format_args!("(rate limit: dropped {dropped} {level} messages)"),
+            );
+        }
+    }
+
+    if limit.count.fetch_add(1, Ordering::AcqRel) < per_second {
+        true
+    } else {
+        limit.dropped.fetch_add(1, Ordering::Relaxed);
+        false
+    }
+}
+
+/// Format and log message with the given [`Level`], at most
+/// once per `window` ([`Duration`]) per call site.
+#[macro_export]
+macro_rules! log_throttled {
+    ($level:expr, $window:expr, $($t:tt)*) => {{
+        static THROTTLE: workflow_log::throttle::Throttle = workflow_log::throttle::Throttle::new();
+        if let Some(suppressed) = THROTTLE.admit($window) {
+            workflow_log::throttle::emit($level, suppressed, file!(), line!(), &format_args!($($t)*));
+        }
+    }};
+}
+
+/// Format and log message with [`Level::Error`] at most once per window per call site
+#[macro_export]
+macro_rules! log_error_throttled {
+    ($window:expr, $($t:tt)*) => (
+        workflow_log::log_throttled!(workflow_log::Level::Error, $window, $($t)*)
+    )
+}
+
+/// Format and log message with [`Level::Warn`] at most once per window per call site
+#[macro_export]
+macro_rules! log_warn_throttled {
+    ($window:expr, $($t:tt)*) => (
+        workflow_log::log_throttled!(workflow_log::Level::Warn, $window, $($t)*)
+    )
+}
+
+/// Format and log message with [`Level::Info`] at most once per window per call site
+#[macro_export]
+macro_rules! log_info_throttled {
+    ($window:expr, $($t:tt)*) => (
+        workflow_log::log_throttled!(workflow_log::Level::Info, $window, $($t)*)
+    )
+}
+
+/// Format and log message with [`Level::Debug`] at most once per window per call site
+#[macro_export]
+macro_rules! log_debug_throttled {
+    ($window:expr, $($t:tt)*) => (
+        workflow_log::log_throttled!(workflow_log::Level::Debug, $window, $($t)*)
+    )
+}
+
+/// Format and log message with [`Level::Trace`] at most once per window per call site
+#[macro_export]
+macro_rules! log_trace_throttled {
+    ($window:expr, $($t:tt)*) => (
+        workflow_log::log_throttled!(workflow_log::Level::Trace, $window, $($t)*)
+    )
+}
+
+pub use log_debug_throttled;
+pub use log_error_throttled;
+pub use log_info_throttled;
+pub use log_throttled;
+pub use log_trace_throttled;
+pub use log_warn_throttled;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::capture;
+    use crate::*;
+
+    #[test]
+    fn test_throttled_call_site() {
+        let guard = capture();
+        for i in 0..10_000 {
+            log_warn_throttled!(Duration::from_secs(60), "hammered {i}");
+        }
+        let records = guard.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message, "hammered 0");
+
+        // call sites are throttled independently
+        guard.clear();
+        for _ in 0..100 {
+            log_info_throttled!(Duration::from_secs(60), "first site");
+            log_info_throttled!(Duration::from_secs(60), "second site");
+        }
+        assert_eq!(guard.records().len(), 2);
+        assert!(guard.contains(Level::Info, "first site"));
+        assert!(guard.contains(Level::Info, "second site"));
+    }
+
+    #[test]
+    fn test_throttled_summary() {
+        let guard = capture();
+        let window = Duration::from_millis(50);
+        let emit = |n: usize| log_error_throttled!(window, "burst {n}");
+        for n in 0..1_000 {
+            emit(n);
+        }
+        std::thread::sleep(window * 2);
+        emit(1_000);
+
+        let records = guard.records();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].message, "burst 0");
+        assert_eq!(records[1].message, "burst 1000");
+        assert!(records[2]
+            .message
+            .starts_with("(suppressed 999 similar messages at "));
+        assert!(records.iter().all(|record| record.level == Level::Error));
+    }
+
+    #[test]
+    fn test_global_rate_limit() {
+        // debug level is not used by other tests of this crate
+        // (trace records remain below the filter)
+        set_log_level(LevelFilter::Debug);
+        let guard = capture();
+        set_rate_limit(Level::Debug, 10);
+        for n in 0..10_000 {
+            log_debug!("flood {n}");
+        }
+        let emitted = guard.records().len();
+        clear_rate_limit(Level::Debug);
+        // the loop may straddle a second boundary
+        assert!((10..=21).contains(&emitted), "{emitted} records emitted");
+
+        guard.clear();
+        log_debug!("unlimited");
+        assert_eq!(guard.records().len(), 1);
+        set_log_level(LevelFilter::Info);
+    }
+}
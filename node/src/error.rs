@@ -24,6 +24,14 @@ pub enum Error {
     Callback(#[from] workflow_wasm::callback::CallbackError),
     #[error("{0}")]
     JsValue(Printable),
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+    #[error("Connection closed")]
+    ConnectionClosed,
+    #[error("missing runtime capability: {0}")]
+    MissingCapability(crate::runtime::Capability),
+    #[error("{0}")]
+    Custom(String),
 }
 
 unsafe impl Send for Error {}
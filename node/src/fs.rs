@@ -3,6 +3,9 @@ use js_sys::Object;
 use lazy_static::lazy_static;
 use wasm_bindgen::prelude::*;
 
+pub mod watch;
+pub use watch::{watch, watch_capabilities, FsEvent, WatchCapabilities, WatchOptions};
+
 lazy_static! {
     static ref FS: Fs = require("fs").unchecked_into();
     static ref FSP: FsPromises = require("fs/promises").unchecked_into();
@@ -32,6 +35,13 @@ extern "C" {
     #[wasm_bindgen(js_name = readdirSync, method)]
     pub fn fs_readdir_sync(this: &Fs, path: &str, callback: js_sys::Function);
 
+    #[wasm_bindgen(catch, js_name = readdirSync, method)]
+    fn fs_readdir_sync_with_options(
+        this: &Fs,
+        path: &str,
+        options: Object,
+    ) -> std::result::Result<js_sys::Array, JsValue>;
+
     #[wasm_bindgen(catch, js_name = existsSync, method)]
     fn fs_exists_sync(this: &Fs, path: &str) -> std::result::Result<bool, JsValue>;
 
@@ -61,6 +71,76 @@ extern "C" {
 
     #[wasm_bindgen(catch, js_name = statSync, method)]
     fn fs_stat_sync(this: &Fs, path: &str) -> std::result::Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch, js_name = lstatSync, method)]
+    fn fs_lstat_sync(this: &Fs, path: &str) -> std::result::Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch, js_name = openSync, method)]
+    fn fs_open_sync(this: &Fs, path: &str, flags: &str) -> std::result::Result<u32, JsValue>;
+
+    #[wasm_bindgen(catch, js_name = readSync, method)]
+    fn fs_read_sync(
+        this: &Fs,
+        fd: u32,
+        buffer: &js_sys::Uint8Array,
+        offset: u32,
+        length: u32,
+        position: JsValue,
+    ) -> std::result::Result<u32, JsValue>;
+
+    #[wasm_bindgen(catch, js_name = closeSync, method)]
+    fn fs_close_sync(this: &Fs, fd: u32) -> std::result::Result<(), JsValue>;
+
+    #[wasm_bindgen(catch, js_name = mkdtempSync, method)]
+    fn fs_mkdtemp_sync(this: &Fs, prefix: &str) -> std::result::Result<String, JsValue>;
+
+    #[wasm_bindgen(catch, js_name = rmSync, method)]
+    fn fs_rm_sync(this: &Fs, path: &str, options: Object) -> std::result::Result<(), JsValue>;
+
+    #[wasm_bindgen(catch, js_name = watch, method)]
+    fn fs_watch(
+        this: &Fs,
+        path: &str,
+        options: Object,
+        listener: &js_sys::Function,
+    ) -> std::result::Result<FsWatcher, JsValue>;
+
+    #[wasm_bindgen(catch, js_name = watchFile, method)]
+    fn fs_watch_file(
+        this: &Fs,
+        path: &str,
+        options: Object,
+        listener: &js_sys::Function,
+    ) -> std::result::Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(js_name = unwatchFile, method)]
+    fn fs_unwatch_file(this: &Fs, path: &str, listener: &js_sys::Function);
+
+    #[wasm_bindgen(extends = Object, js_namespace = fs)]
+    #[derive(Clone, Debug)]
+    pub type Stats;
+
+    #[wasm_bindgen(method, js_name = isFile)]
+    pub fn is_file(this: &Stats) -> bool;
+
+    #[wasm_bindgen(method, js_name = isDirectory)]
+    pub fn is_directory(this: &Stats) -> bool;
+
+    #[wasm_bindgen(method, js_name = isSymbolicLink)]
+    pub fn is_symbolic_link(this: &Stats) -> bool;
+
+    #[wasm_bindgen(method, getter)]
+    pub fn size(this: &Stats) -> f64;
+
+    #[wasm_bindgen(extends = Object, js_namespace = fs)]
+    #[derive(Clone, Debug)]
+    pub type FsWatcher;
+
+    #[wasm_bindgen(method)]
+    pub fn close(this: &FsWatcher);
+
+    #[wasm_bindgen(method)]
+    pub fn on(this: &FsWatcher, event: &str, listener: &js_sys::Function);
 }
 
 unsafe impl Send for Fs {}
@@ -86,6 +166,16 @@ pub fn readdir_sync(path: &str, callback: js_sys::Function) {
     FS.fs_readdir_sync(path, callback)
 }
 
+/// Synchronous `readdir` returning the array of entry names
+/// (or `Dirent` objects if `withFileTypes` option is set).
+#[inline(always)]
+pub fn readdir_sync_with_options(
+    path: &str,
+    options: Object,
+) -> std::result::Result<js_sys::Array, JsValue> {
+    FS.fs_readdir_sync_with_options(path, options)
+}
+
 #[inline(always)]
 pub fn exists_sync(path: &str) -> std::result::Result<bool, JsValue> {
     FS.fs_exists_sync(path)
@@ -124,3 +214,45 @@ pub fn rename_sync(from: &str, to: &str) -> std::result::Result<(), JsValue> {
 pub fn stat_sync(path: &str) -> std::result::Result<JsValue, JsValue> {
     FS.fs_stat_sync(path)
 }
+
+/// Synchronous `lstat` (does not follow symbolic links).
+#[inline(always)]
+pub fn lstat_sync(path: &str) -> std::result::Result<JsValue, JsValue> {
+    FS.fs_lstat_sync(path)
+}
+
+/// Opens the file, returning the file descriptor.
+#[inline(always)]
+pub fn open_sync(path: &str, flags: &str) -> std::result::Result<u32, JsValue> {
+    FS.fs_open_sync(path, flags)
+}
+
+/// Reads up to `length` bytes from the file descriptor into the `buffer`
+/// at `offset`, from the current file position if `position` is `None`.
+/// Returns the number of bytes read (`0` at the end of the file).
+#[inline(always)]
+pub fn read_sync(
+    fd: u32,
+    buffer: &js_sys::Uint8Array,
+    offset: u32,
+    length: u32,
+    position: Option<u64>,
+) -> std::result::Result<u32, JsValue> {
+    let position = position.map_or(JsValue::NULL, |position| JsValue::from(position as f64));
+    FS.fs_read_sync(fd, buffer, offset, length, position)
+}
+
+#[inline(always)]
+pub fn close_sync(fd: u32) -> std::result::Result<(), JsValue> {
+    FS.fs_close_sync(fd)
+}
+
+#[inline(always)]
+pub fn mkdtemp_sync(prefix: &str) -> std::result::Result<String, JsValue> {
+    FS.fs_mkdtemp_sync(prefix)
+}
+
+#[inline(always)]
+pub fn rm_sync(path: &str, options: Object) -> std::result::Result<(), JsValue> {
+    FS.fs_rm_sync(path, options)
+}
@@ -0,0 +1,452 @@
+//!
+//! File system watcher backed by the Node.js `fs.watch()` API (or the
+//! `fs.watchFile()` stat polling when [`WatchOptions::with_polling()`]
+//! is used). [`watch()`] returns a channel receiving [`FsEvent`]
+//! notifications; the watcher is closed once the receiver is dropped.
+//!
+//! The raw notifications are normalized as follows:
+//! - `rename` notifications are resolved to [`FsEvent::Created`] or
+//!   [`FsEvent::Removed`] by checking if the path exists
+//! - notifications for the same path received within the debounce window
+//!   are coalesced (e.g. a file created and written reports only
+//!   [`FsEvent::Created`], a file created and removed reports nothing)
+//! - a removal immediately followed by a creation within the debounce
+//!   window (the two notifications produced by a rename) is reported
+//!   as [`FsEvent::Renamed`]
+//!
+//! Recursive watching is not supported by `fs.watch()` on all platforms
+//! (Linux requires Node.js 20+); [`watch_capabilities()`] reports the
+//! support and [`watch()`] fails with [`Error::MissingCapability`] if
+//! a recursive watch is requested where it is not available.
+//!
+//! ```ignore
+//! let events = workflow_node::fs::watch("./src", WatchOptions::default().with_recursive(true))?;
+//! while let Ok(event) = events.recv().await {
+//!     log_info!("{event:?}");
+//! }
+//! ```
+//!
+
+use super::{exists_sync, stat_sync, FsWatcher, FS};
+use crate::error::Error;
+use crate::result::Result;
+use crate::runtime::{capabilities, Capability, Version};
+use futures::{select, FutureExt};
+use js_sys::{Function, Object, Reflect};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use workflow_core::channel::{unbounded, Channel, Receiver};
+use workflow_core::runtime::{platform, Platform};
+use workflow_core::task::sleep;
+use workflow_log::*;
+use workflow_wasm::callback::*;
+
+/// Interval at which an idle watcher checks if the receiver has been dropped
+const DISPOSAL_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// File system change reported by [`watch()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsEvent {
+    Created(PathBuf),
+    Modified(PathBuf),
+    Removed(PathBuf),
+    Renamed { from: PathBuf, to: PathBuf },
+}
+
+impl FsEvent {
+    /// Path affected by the change (the destination path for [`FsEvent::Renamed`])
+    pub fn path(&self) -> &Path {
+        match self {
+            FsEvent::Created(path) | FsEvent::Modified(path) | FsEvent::Removed(path) => path,
+            FsEvent::Renamed { to, .. } => to,
+        }
+    }
+}
+
+/// Options of the [`watch()`] function
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    recursive: bool,
+    debounce: Duration,
+    poll: Option<Duration>,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            recursive: false,
+            debounce: Duration::from_millis(50),
+            poll: None,
+        }
+    }
+}
+
+impl WatchOptions {
+    /// Watch the subdirectories of the watched directory
+    /// (see [`WatchCapabilities::recursive`])
+    pub fn with_recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Window within which the notifications are coalesced (default 50 msec).
+    /// Events are delivered once the window elapses after the first notification.
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Use `fs.watchFile()` stat polling at the given `interval` instead of
+    /// `fs.watch()`. Polling watches a single path (that may not exist yet)
+    /// and works on network file systems, but it can not be recursive and
+    /// reports changes to a directory itself rather than to its entries.
+    pub fn with_polling(mut self, interval: Duration) -> Self {
+        self.poll = Some(interval);
+        self
+    }
+
+    pub fn recursive(&self) -> bool {
+        self.recursive
+    }
+
+    pub fn debounce(&self) -> Duration {
+        self.debounce
+    }
+
+    pub fn poll(&self) -> Option<Duration> {
+        self.poll
+    }
+}
+
+/// Support of the `fs.watch()` features by the current platform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchCapabilities {
+    /// Recursive watching (macOS, Windows, Linux with Node.js 20+)
+    pub recursive: bool,
+    /// Notifications include the name of the changed entry (macOS, Windows, Linux).
+    /// Where it is not available, changes are reported as [`FsEvent::Modified`]
+    /// for the watched path.
+    pub filenames: bool,
+}
+
+/// `fs.watch()` features supported by the current platform
+pub fn watch_capabilities() -> WatchCapabilities {
+    let platform = platform();
+    let recursive = match platform {
+        Platform::MacOS | Platform::Windows => true,
+        Platform::Linux => capabilities()
+            .node_version
+            .is_some_and(|version| version >= Version::new(20, 0, 0)),
+        _ => false,
+    };
+    let filenames = matches!(
+        platform,
+        Platform::MacOS | Platform::Windows | Platform::Linux
+    );
+    WatchCapabilities {
+        recursive,
+        filenames,
+    }
+}
+
+/// Watch `path` for changes (see the [module](self) documentation).
+/// Fails if the path can not be watched or if a recursive watch is not
+/// supported by the platform. The receiver is closed if the watcher
+/// fails (e.g. when the watched directory is removed).
+pub fn watch(path: &str, options: WatchOptions) -> Result<Receiver<FsEvent>> {
+    if options.recursive && (options.poll.is_some() || !watch_capabilities().recursive) {
+        return Err(Error::MissingCapability(Capability::RecursiveWatch));
+    }
+
+    let signals = Channel::<Signal>::unbounded();
+    let callbacks = CallbackMap::new();
+
+    let source = match options.poll {
+        Some(interval) => {
+            let root = PathBuf::from(path);
+            let sender = signals.sender.clone();
+            let listener = callback!(move |current: JsValue, previous: JsValue| {
+                // the stats of a missing file are zeroed
+                let kind = match (mtime(&previous) == 0.0, mtime(&current) == 0.0) {
+                    (true, true) => return,
+                    (true, false) => Kind::Created,
+                    (false, true) => Kind::Removed,
+                    (false, false) => Kind::Modified,
+                };
+                sender.try_send(Signal::Change(root.clone(), kind)).ok();
+            });
+            let watch_options = Object::new();
+            Reflect::set(
+                &watch_options,
+                &"interval".into(),
+                &(interval.as_millis() as f64).into(),
+            )?;
+            FS.fs_watch_file(path, watch_options, listener.as_ref())?;
+            let function: &Function = listener.as_ref();
+            let source = Source::Poll(path.to_string(), function.clone());
+            callbacks.retain(listener)?;
+            source
+        }
+        None => {
+            let is_dir = is_directory(path)?;
+            let root = PathBuf::from(path);
+            let sender = signals.sender.clone();
+            let listener = callback!(move |event: String, filename: JsValue| {
+                let path = match filename.as_string() {
+                    Some(filename) if is_dir => root.join(filename),
+                    _ => root.clone(),
+                };
+                let kind = match event.as_str() {
+                    "rename" => {
+                        if exists_sync(&path.to_string_lossy()).unwrap_or_default() {
+                            Kind::Created
+                        } else {
+                            Kind::Removed
+                        }
+                    }
+                    _ => Kind::Modified,
+                };
+                sender.try_send(Signal::Change(path, kind)).ok();
+            });
+            let watch_options = Object::new();
+            Reflect::set(
+                &watch_options,
+                &"recursive".into(),
+                &options.recursive.into(),
+            )?;
+            let watcher = FS.fs_watch(path, watch_options, listener.as_ref())?;
+            callbacks.retain(listener)?;
+
+            let sender = signals.sender.clone();
+            let on_error = callback!(move |err: JsValue| {
+                sender.try_send(Signal::Error(err)).ok();
+            });
+            watcher.on("error", on_error.as_ref());
+            callbacks.retain(on_error)?;
+            Source::Watch(watcher)
+        }
+    };
+
+    let (sender, receiver) = unbounded();
+    let debounce = options.debounce;
+    spawn_local(async move {
+        'watch: loop {
+            let signal = select! {
+                signal = signals.receiver.recv().fuse() => signal.ok(),
+                _ = sleep(DISPOSAL_CHECK_INTERVAL).fuse() => None,
+            };
+            if sender.is_closed() {
+                break;
+            }
+
+            let mut changes = Changes::default();
+            match signal {
+                Some(Signal::Change(path, kind)) => changes.record(path, kind),
+                Some(Signal::Error(err)) => {
+                    log_warn!("fs::watch(): {}", Error::from(err));
+                    break;
+                }
+                None => continue,
+            }
+            if !debounce.is_zero() {
+                sleep(debounce).await;
+            }
+            while let Ok(signal) = signals.receiver.try_recv() {
+                match signal {
+                    Signal::Change(path, kind) => changes.record(path, kind),
+                    Signal::Error(err) => {
+                        log_warn!("fs::watch(): {}", Error::from(err));
+                        break 'watch;
+                    }
+                }
+            }
+
+            for event in changes.into_events() {
+                if sender.send(event).await.is_err() {
+                    break 'watch;
+                }
+            }
+        }
+
+        match source {
+            Source::Watch(watcher) => watcher.close(),
+            Source::Poll(path, listener) => FS.fs_unwatch_file(&path, &listener),
+        }
+        callbacks.clear();
+    });
+
+    Ok(receiver)
+}
+
+/// Notification received from the watcher
+enum Signal {
+    Change(PathBuf, Kind),
+    Error(JsValue),
+}
+
+/// Active watcher, disposed once the receiver is dropped
+enum Source {
+    Watch(FsWatcher),
+    Poll(String, Function),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Created,
+    Modified,
+    Removed,
+}
+
+/// Changes recorded within the debounce window
+/// (in the order of their first notification)
+#[derive(Default)]
+struct Changes {
+    entries: Vec<(PathBuf, Kind)>,
+}
+
+impl Changes {
+    fn record(&mut self, path: PathBuf, kind: Kind) {
+        let Some(index) = self.entries.iter().position(|(entry, _)| *entry == path) else {
+            self.entries.push((path, kind));
+            return;
+        };
+
+        let merged = match (self.entries[index].1, kind) {
+            // transient entry
+            (Kind::Created, Kind::Removed) => None,
+            (Kind::Created, _) => Some(Kind::Created),
+            // replaced entry
+            (Kind::Removed, Kind::Created | Kind::Modified) => Some(Kind::Modified),
+            (_, kind) => Some(kind),
+        };
+        match merged {
+            Some(kind) => self.entries[index].1 = kind,
+            None => {
+                self.entries.remove(index);
+            }
+        }
+    }
+
+    fn into_events(self) -> Vec<FsEvent> {
+        let mut events = Vec::with_capacity(self.entries.len());
+        let mut entries = self.entries.into_iter().peekable();
+        while let Some((path, kind)) = entries.next() {
+            let event = match kind {
+                Kind::Removed => match entries.next_if(|(_, kind)| *kind == Kind::Created) {
+                    Some((to, _)) => FsEvent::Renamed { from: path, to },
+                    None => FsEvent::Removed(path),
+                },
+                Kind::Created => FsEvent::Created(path),
+                Kind::Modified => FsEvent::Modified(path),
+            };
+            events.push(event);
+        }
+        events
+    }
+}
+
+fn is_directory(path: &str) -> Result<bool> {
+    let stats = stat_sync(path)?;
+    let is_directory = Reflect::get(&stats, &"isDirectory".into())?.dyn_into::<Function>()?;
+    Ok(is_directory.call0(&stats)?.is_truthy())
+}
+
+fn mtime(stats: &JsValue) -> f64 {
+    Reflect::get(stats, &"mtimeMs".into())
+        .ok()
+        .and_then(|mtime| mtime.as_f64())
+        .unwrap_or_default()
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use crate::fs::{mkdtemp_sync, rename_sync, rm_sync, unlink_sync, write_file_sync};
+    use wasm_bindgen_test::*;
+
+    fn write(path: &Path, text: &str) {
+        write_file_sync(&path.to_string_lossy(), text.into(), Object::new()).unwrap();
+    }
+
+    async fn next(events: &Receiver<FsEvent>) -> FsEvent {
+        select! {
+            event = events.recv().fuse() => event.unwrap(),
+            _ = sleep(Duration::from_secs(5)).fuse() => panic!("fs::watch(): no event received"),
+        }
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_fs_watch() {
+        let os = crate::require("os");
+        let tmpdir = Reflect::get(&os, &"tmpdir".into())
+            .unwrap()
+            .unchecked_into::<Function>()
+            .call0(&os)
+            .unwrap()
+            .as_string()
+            .unwrap();
+        let dir = mkdtemp_sync(&format!("{tmpdir}/workflow-node-watch-")).unwrap();
+        let root = PathBuf::from(&dir);
+        let a = root.join("a.txt");
+        let b = root.join("b.txt");
+
+        let events = watch(
+            &dir,
+            WatchOptions::default().with_debounce(Duration::from_millis(100)),
+        )
+        .unwrap();
+
+        // creating and writing the file is reported as a single event
+        write(&a, "hello");
+        write(&a, "hello world");
+        assert_eq!(next(&events).await, FsEvent::Created(a.clone()));
+
+        sleep(Duration::from_millis(200)).await;
+        write(&a, "updated");
+        assert_eq!(next(&events).await, FsEvent::Modified(a.clone()));
+
+        sleep(Duration::from_millis(200)).await;
+        rename_sync(&a.to_string_lossy(), &b.to_string_lossy()).unwrap();
+        assert_eq!(
+            next(&events).await,
+            FsEvent::Renamed {
+                from: a.clone(),
+                to: b.clone()
+            }
+        );
+
+        sleep(Duration::from_millis(200)).await;
+        unlink_sync(&b.to_string_lossy()).unwrap();
+        assert_eq!(next(&events).await, FsEvent::Removed(b.clone()));
+        assert!(events.is_empty());
+
+        drop(events);
+        let options = Object::new();
+        Reflect::set(&options, &"recursive".into(), &true.into()).unwrap();
+        rm_sync(&dir, options).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_fs_watch_recursive_capability() {
+        let result = watch(".", WatchOptions::default().with_recursive(true));
+        if watch_capabilities().recursive {
+            assert!(result.is_ok());
+        } else {
+            assert!(matches!(
+                result,
+                Err(Error::MissingCapability(Capability::RecursiveWatch))
+            ));
+        }
+        assert!(matches!(
+            watch(
+                ".",
+                WatchOptions::default()
+                    .with_recursive(true)
+                    .with_polling(Duration::from_millis(100))
+            ),
+            Err(Error::MissingCapability(Capability::RecursiveWatch))
+        ));
+    }
+}
@@ -0,0 +1,620 @@
+//!
+//! Minimal HTTP server backed by the Node.js `http` module, allowing
+//! Node.js and NWJS applications to expose a local HTTP endpoint.
+//!
+//! Requests are received in full (method, path, headers and body) and
+//! passed to an async Rust handler returning an [`HttpResponse`]. The
+//! response body can be supplied as a buffer or as an async stream of
+//! chunks written with backpressure (awaiting the `drain` event).
+//!
+//! ```text
+//! let server = HttpServer::listen("127.0.0.1:8080", |request: HttpRequest| async move {
+//!     Ok(HttpResponse::ok().with_text(format!("{} {}", request.method(), request.path())))
+//! }).await?;
+//! // ...
+//! server.close().await?;
+//! ```
+//!
+
+use crate::error::Error;
+use crate::require;
+use crate::result::Result;
+use futures::future::LocalBoxFuture;
+use futures::stream::{LocalBoxStream, StreamExt};
+use futures::Future;
+use js_sys::{Array, Function, Object, Uint8Array};
+use lazy_static::lazy_static;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use workflow_core::channel::{oneshot, Channel};
+use workflow_log::*;
+use workflow_wasm::callback::*;
+
+lazy_static! {
+    static ref HTTP: Http = require("http").unchecked_into();
+}
+
+#[wasm_bindgen]
+extern "C" {
+
+    #[wasm_bindgen(extends = Object)]
+    #[derive(Clone)]
+    pub type Http;
+
+    #[wasm_bindgen(js_name = createServer, method)]
+    fn http_create_server(this: &Http, listener: &Function) -> NodeHttpServer;
+
+    #[wasm_bindgen(extends = Object, js_namespace = http)]
+    #[derive(Clone, Debug)]
+    pub type NodeHttpServer;
+
+    #[wasm_bindgen(method)]
+    fn listen(this: &NodeHttpServer, port: u16, host: &str, callback: &Function);
+
+    #[wasm_bindgen(method)]
+    fn close(this: &NodeHttpServer, callback: &Function);
+
+    #[wasm_bindgen(method, js_name = closeAllConnections)]
+    fn close_all_connections(this: &NodeHttpServer);
+
+    #[wasm_bindgen(method)]
+    fn address(this: &NodeHttpServer) -> JsValue;
+
+    #[wasm_bindgen(method)]
+    fn on(this: &NodeHttpServer, event: &str, listener: &Function);
+
+    #[wasm_bindgen(method, js_name = removeListener)]
+    fn remove_listener(this: &NodeHttpServer, event: &str, listener: &Function);
+
+    #[wasm_bindgen(extends = Object, js_namespace = http)]
+    #[derive(Clone, Debug)]
+    pub type IncomingMessage;
+
+    #[wasm_bindgen(method, getter)]
+    fn method(this: &IncomingMessage) -> String;
+
+    #[wasm_bindgen(method, getter)]
+    fn url(this: &IncomingMessage) -> String;
+
+    #[wasm_bindgen(method, getter, js_name = rawHeaders)]
+    fn raw_headers(this: &IncomingMessage) -> Array;
+
+    #[wasm_bindgen(method)]
+    fn on(this: &IncomingMessage, event: &str, listener: &Function);
+
+    #[wasm_bindgen(extends = Object, js_namespace = http)]
+    #[derive(Clone, Debug)]
+    pub type ServerResponse;
+
+    #[wasm_bindgen(method, setter, js_name = statusCode)]
+    fn set_status_code(this: &ServerResponse, status: u16);
+
+    #[wasm_bindgen(method, getter)]
+    fn destroyed(this: &ServerResponse) -> bool;
+
+    #[wasm_bindgen(method, js_name = setHeader)]
+    fn set_header(this: &ServerResponse, name: &str, value: &str);
+
+    #[wasm_bindgen(method)]
+    fn write(this: &ServerResponse, chunk: &Uint8Array) -> bool;
+
+    #[wasm_bindgen(method)]
+    fn end(this: &ServerResponse);
+
+    #[wasm_bindgen(method, js_name = end)]
+    fn end_with_data(this: &ServerResponse, chunk: &Uint8Array);
+
+    #[wasm_bindgen(method)]
+    fn destroy(this: &ServerResponse);
+
+    #[wasm_bindgen(method)]
+    fn on(this: &ServerResponse, event: &str, listener: &Function);
+
+    #[wasm_bindgen(method, js_name = removeListener)]
+    fn remove_listener(this: &ServerResponse, event: &str, listener: &Function);
+}
+
+unsafe impl Send for Http {}
+unsafe impl Sync for Http {}
+
+/// HTTP request received by the [`HttpServer`]
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Request method (`GET`, `POST`, ...)
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Request target including the query string
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Request path without the query string
+    pub fn path(&self) -> &str {
+        self.url.split_once('?').map_or(&self.url, |(path, _)| path)
+    }
+
+    /// Query string (without the leading `?`)
+    pub fn query(&self) -> Option<&str> {
+        self.url.split_once('?').map(|(_, query)| query)
+    }
+
+    /// Request headers in the order received (names as sent by the client)
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Value of the first header matching `name` (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+
+    /// Request body decoded as UTF-8 (lossy)
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Body of an [`HttpResponse`]
+pub enum Body {
+    Bytes(Vec<u8>),
+    /// Chunks written as they are produced; an error terminates the connection
+    Stream(LocalBoxStream<'static, Result<Vec<u8>>>),
+}
+
+impl Default for Body {
+    fn default() -> Self {
+        Body::Bytes(Vec::new())
+    }
+}
+
+impl std::fmt::Debug for Body {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Body::Bytes(bytes) => write!(f, "Bytes({} bytes)", bytes.len()),
+            Body::Stream(_) => write!(f, "Stream"),
+        }
+    }
+}
+
+/// HTTP response builder returned by the [`HttpServer`] request handler
+#[derive(Debug)]
+pub struct HttpResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Body,
+}
+
+impl Default for HttpResponse {
+    fn default() -> Self {
+        Self::ok()
+    }
+}
+
+impl HttpResponse {
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Body::default(),
+        }
+    }
+
+    /// `200 OK` response with an empty body
+    pub fn ok() -> Self {
+        Self::new(200)
+    }
+
+    /// `404 Not Found` response with an empty body
+    pub fn not_found() -> Self {
+        Self::new(404)
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Body::Bytes(body.into());
+        self
+    }
+
+    /// Sets a UTF-8 body and `Content-Type: text/plain` (unless set)
+    pub fn with_text(self, text: impl Into<String>) -> Self {
+        self.with_default_content_type("text/plain; charset=utf-8")
+            .with_body(text.into())
+    }
+
+    /// Sets a body streamed from `stream`, sent using chunked transfer encoding
+    pub fn with_stream<S>(mut self, stream: S) -> Self
+    where
+        S: futures::Stream<Item = Result<Vec<u8>>> + 'static,
+    {
+        self.body = Body::Stream(stream.boxed_local());
+        self
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    pub fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn with_default_content_type(self, content_type: &str) -> Self {
+        if self
+            .headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        {
+            self
+        } else {
+            self.with_header("Content-Type", content_type)
+        }
+    }
+}
+
+/// Request handler invoked by the [`HttpServer`] for each request.
+/// Errors are reported to the client as `500 Internal Server Error`.
+pub type HttpHandlerFn =
+    Rc<dyn Fn(HttpRequest) -> LocalBoxFuture<'static, Result<HttpResponse>> + 'static>;
+
+struct Inner {
+    server: NodeHttpServer,
+    handler: HttpHandlerFn,
+    callbacks: CallbackMap,
+    port: Mutex<Option<u16>>,
+}
+
+unsafe impl Send for Inner {}
+unsafe impl Sync for Inner {}
+
+///
+/// HTTP server running on top of the Node.js `http` module.
+///
+/// The server keeps the JS listeners alive until [`HttpServer::close()`]
+/// is called.
+///
+#[derive(Clone)]
+pub struct HttpServer {
+    inner: Arc<Inner>,
+}
+
+impl HttpServer {
+    /// Creates a server listening on `addr` (`host:port`, port `0` binds a
+    /// random port, see [`HttpServer::port()`]) dispatching requests to `handler`.
+    pub async fn listen<F, Fut>(addr: &str, handler: F) -> Result<HttpServer>
+    where
+        F: Fn(HttpRequest) -> Fut + 'static,
+        Fut: Future<Output = Result<HttpResponse>> + 'static,
+    {
+        let (host, port) = addr
+            .rsplit_once(':')
+            .and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?)))
+            .ok_or_else(|| Error::InvalidAddress(addr.to_string()))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+
+        let handler: HttpHandlerFn = Rc::new(move |request| Box::pin(handler(request)));
+        let callbacks = CallbackMap::new();
+
+        let request_handler = handler.clone();
+        let on_request = callback!(move |req: IncomingMessage, res: ServerResponse| {
+            receive(req, res, request_handler.clone());
+        });
+        let server = HTTP.http_create_server(on_request.as_ref());
+        callbacks.retain(on_request)?;
+
+        let server = HttpServer {
+            inner: Arc::new(Inner {
+                server,
+                handler,
+                callbacks,
+                port: Mutex::new(None),
+            }),
+        };
+        server.bind(host, port).await?;
+        Ok(server)
+    }
+
+    async fn bind(&self, host: &str, port: u16) -> Result<()> {
+        let server = &self.inner.server;
+        let (sender, receiver) = oneshot::<std::result::Result<(), JsValue>>();
+
+        let error_sender = sender.clone();
+        let on_error = callback!(move |err: JsValue| {
+            if error_sender.try_send(Err(err.clone())).is_err() {
+                log_error!("HttpServer: {:?}", err);
+            }
+        });
+        server.on("error", on_error.as_ref());
+        self.inner.callbacks.retain(on_error.clone())?;
+
+        let on_listening = callback!(move || {
+            sender.try_send(Ok(())).ok();
+        });
+        server.listen(port, host, on_listening.as_ref());
+
+        let result = receiver.recv().await?;
+        // the listening callback is invoked once and this point is
+        // reached outside of its invocation, so it can be released
+        drop(on_listening);
+        if let Err(err) = result {
+            server.remove_listener("error", on_error.as_ref());
+            self.inner.callbacks.clear();
+            return Err(err.into());
+        }
+
+        let port = js_sys::Reflect::get(&server.address(), &"port".into())?
+            .as_f64()
+            .map(|port| port as u16);
+        *self.inner.port.lock().unwrap() = port;
+        Ok(())
+    }
+
+    /// Port the server is listening on
+    pub fn port(&self) -> Option<u16> {
+        *self.inner.port.lock().unwrap()
+    }
+
+    /// Request handler of this server
+    pub fn handler(&self) -> &HttpHandlerFn {
+        &self.inner.handler
+    }
+
+    /// Stops accepting new connections and resolves once all existing
+    /// connections have been closed, releasing the server callbacks.
+    pub async fn close(&self) -> Result<()> {
+        let (sender, receiver) = oneshot::<JsValue>();
+        let on_close = callback!(move |err: JsValue| {
+            sender.try_send(err).ok();
+        });
+        self.inner.server.close(on_close.as_ref());
+        let err = receiver.recv().await?;
+        drop(on_close);
+        self.inner.callbacks.clear();
+        *self.inner.port.lock().unwrap() = None;
+        if err.is_undefined() || err.is_null() {
+            Ok(())
+        } else {
+            Err(err.into())
+        }
+    }
+
+    /// Closes all connections, including the ones with requests in
+    /// progress (Node.js 18.2+), and then [`closes`](HttpServer::close()) the server.
+    pub async fn close_all(&self) -> Result<()> {
+        self.inner.server.close_all_connections();
+        self.close().await
+    }
+}
+
+/// Collects the request body and dispatches the request to `handler`.
+fn receive(req: IncomingMessage, res: ServerResponse, handler: HttpHandlerFn) {
+    let body = Rc::new(RefCell::new(Vec::new()));
+    let callbacks = CallbackMap::new();
+
+    let body_ = body.clone();
+    let on_data = callback!(move |chunk: Uint8Array| {
+        body_.borrow_mut().extend_from_slice(&chunk.to_vec());
+    });
+    req.on("data", on_data.as_ref());
+    callbacks.retain(on_data).ok();
+
+    let request = req.clone();
+    let on_end = callback!(move || {
+        let raw_headers = request.raw_headers().to_vec();
+        let headers = raw_headers
+            .chunks(2)
+            .filter_map(|pair| Some((pair.first()?.as_string()?, pair.get(1)?.as_string()?)))
+            .collect();
+        let request = HttpRequest {
+            method: request.method(),
+            url: request.url(),
+            headers,
+            body: body.take(),
+        };
+        let handler = handler.clone();
+        let res = res.clone();
+        spawn_local(async move {
+            let response = handler(request).await.unwrap_or_else(|err| {
+                log_error!("HttpServer: request handler error: {err}");
+                HttpResponse::new(500).with_text(err.to_string())
+            });
+            if let Err(err) = respond(&res, response).await {
+                log_error!("HttpServer: unable to send response: {err}");
+            }
+        });
+    });
+    req.on("end", on_end.as_ref());
+    callbacks.retain(on_end).ok();
+
+    // `close` is emitted once the request has been consumed or aborted;
+    // the callbacks are released outside of the callback invocation
+    let callbacks_ = callbacks.clone();
+    let on_close = callback!(move || {
+        let callbacks = callbacks_.clone();
+        spawn_local(async move {
+            callbacks.clear();
+        });
+    });
+    req.on("close", on_close.as_ref());
+    callbacks.retain(on_close).ok();
+}
+
+enum WriteEvent {
+    Drain,
+    Close,
+}
+
+async fn respond(res: &ServerResponse, response: HttpResponse) -> Result<()> {
+    let HttpResponse {
+        status,
+        headers,
+        body,
+    } = response;
+    res.set_status_code(status);
+    for (name, value) in headers.iter() {
+        res.set_header(name, value);
+    }
+
+    match body {
+        Body::Bytes(bytes) => {
+            res.end_with_data(&Uint8Array::from(bytes.as_slice()));
+            Ok(())
+        }
+        Body::Stream(stream) => {
+            let events = Channel::<WriteEvent>::unbounded();
+            let drain_sender = events.sender.clone();
+            let on_drain = callback!(move || {
+                drain_sender.try_send(WriteEvent::Drain).ok();
+            });
+            let close_sender = events.sender.clone();
+            let on_close = callback!(move || {
+                close_sender.try_send(WriteEvent::Close).ok();
+            });
+            res.on("drain", on_drain.as_ref());
+            res.on("close", on_close.as_ref());
+
+            let result = write_stream(res, stream, &events).await;
+
+            res.remove_listener("drain", on_drain.as_ref());
+            res.remove_listener("close", on_close.as_ref());
+            match result {
+                Ok(()) => res.end(),
+                Err(_) => res.destroy(),
+            }
+            result
+        }
+    }
+}
+
+async fn write_stream(
+    res: &ServerResponse,
+    mut stream: LocalBoxStream<'static, Result<Vec<u8>>>,
+    events: &Channel<WriteEvent>,
+) -> Result<()> {
+    while let Some(chunk) = stream.next().await {
+        if res.destroyed() {
+            return Err(Error::ConnectionClosed);
+        }
+        if !res.write(&Uint8Array::from(chunk?.as_slice())) {
+            match events.recv().await? {
+                WriteEvent::Drain => {}
+                WriteEvent::Close => return Err(Error::ConnectionClosed),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use futures::stream;
+    use wasm_bindgen_test::*;
+    use workflow_http::{Method, Request};
+
+    async fn handler(request: HttpRequest) -> Result<HttpResponse> {
+        match request.path() {
+            "/echo" => Ok(HttpResponse::ok()
+                .with_header("X-Method", request.method())
+                .with_header("X-Query", request.query().unwrap_or_default())
+                .with_header(
+                    "X-Client-Header",
+                    request.header("x-test").unwrap_or_default(),
+                )
+                .with_body(request.into_body())),
+            "/stream" => {
+                let chunks = (0..64).map(|n| Ok(vec![b'a' + (n % 26) as u8; 16 * 1024]));
+                Ok(HttpResponse::ok().with_stream(stream::iter(chunks)))
+            }
+            "/fail" => Err(Error::Custom("handler failure".to_string())),
+            _ => Ok(HttpResponse::not_found()),
+        }
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_http_server() {
+        let server = HttpServer::listen("127.0.0.1:0", handler).await.unwrap();
+        let base = format!("http://127.0.0.1:{}", server.port().unwrap());
+
+        let response = Request::post(format!("{base}/echo?a=1"))
+            .with_header("X-Test", "header value")
+            .with_text("hello")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.header("x-method"), Some(Method::Post.as_str()));
+        assert_eq!(response.header("x-query"), Some("a=1"));
+        assert_eq!(response.header("x-client-header"), Some("header value"));
+        assert_eq!(response.text().unwrap(), "hello");
+
+        let response = Request::new(format!("{base}/stream")).send().await.unwrap();
+        let bytes = response.into_bytes();
+        assert_eq!(bytes.len(), 64 * 16 * 1024);
+        assert!(bytes[16 * 1024..32 * 1024].iter().all(|b| *b == b'b'));
+
+        let response = Request::new(format!("{base}/fail")).send().await.unwrap();
+        assert_eq!(response.status(), 500);
+        assert_eq!(response.text().unwrap(), "handler failure");
+
+        let response = Request::new(format!("{base}/missing"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+
+        server.close_all().await.unwrap();
+        assert!(server.port().is_none());
+        assert!(Request::new(format!("{base}/echo")).send().await.is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_http_server_address_in_use() {
+        let server = HttpServer::listen("127.0.0.1:0", handler).await.unwrap();
+        let addr = format!("127.0.0.1:{}", server.port().unwrap());
+        assert!(matches!(
+            HttpServer::listen(&addr, handler).await,
+            Err(Error::JsValue(_))
+        ));
+        assert!(matches!(
+            HttpServer::listen("127.0.0.1", handler).await,
+            Err(Error::InvalidAddress(_))
+        ));
+        server.close().await.unwrap();
+    }
+}
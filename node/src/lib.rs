@@ -12,9 +12,11 @@
 pub mod child_process;
 pub mod error;
 pub mod fs;
+pub mod http;
 pub mod process;
 pub mod require;
 pub mod result;
+pub mod runtime;
 
 pub mod prelude {
     pub use crate::process::*;
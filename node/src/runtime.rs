@@ -0,0 +1,145 @@
+//!
+//! Runtime capability probe. [`capabilities()`] reports the features of
+//! the JavaScript environment (Node.js, NW.js, Web Worker, DOM) detected
+//! by probing the global object; the guards ([`require_node()`],
+//! [`require_nw()`], [`require_dom()`]) fail with [`Error::MissingCapability`]
+//! naming the capability that is not available.
+//!
+//! The detection is implemented by [`workflow_core::runtime`] (shared with
+//! functions such as [`workflow_core::runtime::is_node()`]).
+//!
+//! ```ignore
+//! let node = workflow_node::runtime::require_node()?;
+//! if node.version() >= Version::new(18, 0, 0) {
+//!     let fs = node.require("fs");
+//!     // ...
+//! }
+//! ```
+//!
+
+use crate::error::Error;
+use crate::result::Result;
+use std::fmt;
+use wasm_bindgen::prelude::*;
+pub use workflow_core::runtime::{capabilities, Capabilities, Version};
+
+/// Capability of the JavaScript environment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// `process` object
+    Process,
+    /// `require()` function
+    Require,
+    /// Node.js runtime
+    Node,
+    /// NW.js runtime
+    Nw,
+    /// DOM (`document`)
+    Dom,
+    /// Recursive `fs.watch()` (see [`crate::fs::watch_capabilities()`])
+    RecursiveWatch,
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let capability = match self {
+            Capability::Process => "process",
+            Capability::Require => "require",
+            Capability::Node => "Node.js",
+            Capability::Nw => "NW.js",
+            Capability::Dom => "DOM",
+            Capability::RecursiveWatch => "recursive fs.watch",
+        };
+        f.write_str(capability)
+    }
+}
+
+/// Node.js environment returned by [`require_node()`]
+#[derive(Debug, Clone)]
+pub struct NodeContext {
+    version: Version,
+    nw_version: Option<Version>,
+}
+
+impl NodeContext {
+    /// Node.js version
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// NW.js version (if running under NW.js)
+    pub fn nw_version(&self) -> Option<Version> {
+        self.nw_version
+    }
+
+    /// Load the Node.js module
+    pub fn require(&self, module: &str) -> JsValue {
+        crate::require::require(module)
+    }
+}
+
+/// Obtain the Node.js environment, failing if `process`, `require()`
+/// or the Node.js version are not available.
+pub fn require_node() -> Result<NodeContext> {
+    let capabilities = capabilities();
+    if !capabilities.has_process {
+        return Err(Error::MissingCapability(Capability::Process));
+    }
+    let version = capabilities
+        .node_version
+        .ok_or(Error::MissingCapability(Capability::Node))?;
+    if !capabilities.has_require {
+        return Err(Error::MissingCapability(Capability::Require));
+    }
+    Ok(NodeContext {
+        version,
+        nw_version: capabilities.nw_version,
+    })
+}
+
+/// Obtain the NW.js version, failing if not running under NW.js
+pub fn require_nw() -> Result<Version> {
+    let capabilities = capabilities();
+    match capabilities.nw_version {
+        Some(version) if capabilities.has_nw => Ok(version),
+        _ => Err(Error::MissingCapability(Capability::Nw)),
+    }
+}
+
+/// Fail if the DOM is not available (Node.js, Web Workers)
+pub fn require_dom() -> Result<()> {
+    if capabilities().has_dom {
+        Ok(())
+    } else {
+        Err(Error::MissingCapability(Capability::Dom))
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn test_node_capabilities() {
+        let capabilities = capabilities();
+        assert!(capabilities.has_process);
+        assert!(capabilities.has_require);
+        assert!(!capabilities.has_nw);
+        assert!(!capabilities.has_dom);
+        assert!(!capabilities.is_worker);
+        assert_eq!(capabilities.nw_version, None);
+
+        let node = require_node().unwrap();
+        assert!(node.version() >= Version::new(12, 0, 0));
+        assert_eq!(Some(node.version()), capabilities.node_version);
+        assert!(node.require("path").is_object());
+
+        assert!(matches!(
+            require_nw(),
+            Err(Error::MissingCapability(Capability::Nw))
+        ));
+        let err = require_dom().unwrap_err();
+        assert_eq!(err.to_string(), "missing runtime capability: DOM");
+    }
+}
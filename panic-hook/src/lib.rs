@@ -12,6 +12,26 @@
 //! a full-screen DIV element dumping the stack info in it.  This is useful when debugging on devices
 //! without access to console output.
 //!
+//! The full-page output can be customized using `set_popup_title()` and `set_popup_style()`
+//! (invoked before [`set_once()`]); it allows the panic output to be copied to the clipboard
+//! and dismissed.
+//!
+//! A third mode, [`Type::Custom`], delivers a structured [`PanicReport`] to a [`PanicSink`],
+//! allowing applications to store the report and submit it to a backend on the next launch.
+//! Reports (as well as the console and full-page output) include the breadcrumbs recorded
+//! via [`breadcrumb()`].
+//!
+//! The report of the last panic is available via [`last_panic()`] and [`has_panicked()`];
+//! additional sinks can be registered using [`add_panic_observer()`] (e.g. to notify the
+//! JavaScript application that the WASM instance is no longer usable).
+//!
+//! ```ignore
+//! set_once(Type::Custom(Arc::new(|report: &PanicReport| {
+//!     local_storage().set_item("panic-report", &report.to_string()).ok();
+//! })));
+//! breadcrumb("wallet opened");
+//! ```
+//!
 //! ## Error.stackTraceLimit
 //!
 //! Many browsers only capture the top 10 frames of a stack trace. In rust programs this is less likely to be enough. To see more frames, you can set the non-standard value `Error.stackTraceLimit`. For more information see the [MDN Web Docs](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Microsoft_Extensions/Error.stackTraceLimit) or [v8 docs](https://v8.dev/docs/stack-trace-api).
@@ -20,12 +40,23 @@
 #[macro_use]
 extern crate cfg_if;
 
+mod report;
+
+use report::notify;
+pub use report::{
+    breadcrumb, breadcrumbs, clear_breadcrumbs, has_panicked, last_panic, set_breadcrumb_capacity,
+    PanicReport, PanicSink, DEFAULT_BREADCRUMB_CAPACITY,
+};
 use std::panic;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Once};
 
 cfg_if! {
     if #[cfg(target_arch = "wasm32")] {
         extern crate wasm_bindgen;
         use wasm_bindgen::prelude::*;
+        use report::format_breadcrumbs;
         mod logger;
 
         #[wasm_bindgen]
@@ -42,7 +73,15 @@ cfg_if! {
             fn stack(error: &Error) -> String;
         }
 
-        fn process(info: &panic::PanicInfo) -> String{
+        fn js_stack() -> String {
+            Error::new().stack()
+        }
+
+        fn stack() -> String {
+            js_stack()
+        }
+
+        fn process(info: &panic::PanicInfo, stack: &str) -> String{
             let mut msg = info.to_string();
 
             // Add the error stack to our message.
@@ -56,9 +95,13 @@ cfg_if! {
             // the message's contents, by including the stack in the message
             // contents we make sure it is available to the user.
             msg.push_str("\n\nStack:\n\n");
-            let e = Error::new();
-            let stack = e.stack();
-            msg.push_str(&stack);
+            msg.push_str(stack);
+
+            let breadcrumbs = breadcrumbs();
+            if !breadcrumbs.is_empty() {
+                msg.push('\n');
+                msg.push_str(&format_breadcrumbs(&breadcrumbs));
+            }
 
             // Safari's devtools, on the other hand, _do_ mess with logged
             // messages' contents, so we attempt to break their heuristics for
@@ -71,12 +114,16 @@ cfg_if! {
 
 
         fn console_hook(info: &panic::PanicInfo){
+            let stack = js_stack();
+            notify(&PanicReport::new(info, stack.clone()));
             // Finally, log the panic with `console.error`!
-            console_error(process(info));
+            console_error(process(info, &stack));
         }
         fn popup_hook(info: &panic::PanicInfo){
+            let stack = js_stack();
+            notify(&PanicReport::new(info, stack.clone()));
             // Finally, log the panic with `logger::error`!
-            logger::error(process(info));
+            logger::error(process(info, &stack));
         }
 
         fn init(logger_type:Type){
@@ -89,40 +136,197 @@ cfg_if! {
                     logger::init_logger();
                     panic::set_hook(Box::new(popup_hook));
                 }
-                Type::Native=>{
+                Type::Native { .. }=>{
                     panic!("Native logger not supported under wasm");
                 }
+                Type::Custom(sink)=>{
+                    panic::set_hook(Box::new(move |info| {
+                        let report = PanicReport::new(info, js_stack());
+                        // invoked first, the console output may fail
+                        sink.report(&report);
+                        notify(&report);
+                        console_error(format!("{report}\n\n"));
+                    }));
+                }
             }
 
         }
-        pub use logger::show_logs;
+        pub use logger::{
+            set_popup_style, set_popup_title, show_logs, DEFAULT_POPUP_STYLE, DEFAULT_POPUP_TITLE,
+        };
     } else {
-        use std::io::{self, Write};
+        mod native;
+        pub use native::{latest_crash_report, resolve_crash_dir, set_terminal_restore, write_crash_report};
 
-        fn hook(info: &panic::PanicInfo) {
-            let _ = writeln!(io::stderr(), "{info}");
+        fn init(logger_type:Type){
+            match logger_type {
+                Type::Custom(sink) => {
+                    panic::set_hook(Box::new(move |info| {
+                        let report = native::report(info);
+                        sink.report(&report);
+                        notify(&report);
+                        native::print(&report);
+                    }));
+                }
+                Type::Native { crash_dir } => {
+                    panic::set_hook(Box::new(move |info| native::hook(info, crash_dir.as_deref())));
+                }
+                Type::Console | Type::Popup => {
+                    panic::set_hook(Box::new(|info| native::hook(info, None)));
+                }
+            }
         }
 
-        fn init(_logger_type:Type){
-            panic::set_hook(Box::new(hook));
+        fn stack() -> String {
+            std::backtrace::Backtrace::force_capture().to_string()
         }
 
         pub fn show_logs(){
             panic!("Native (non-WASM) platform build doesn't support panic logs");
         }
+
+        /// Has no effect on native platforms.
+        pub fn set_popup_style(_css: &str) {}
+
+        /// Has no effect on native platforms.
+        pub fn set_popup_title(_title: &str) {}
     }
 }
 
 pub enum Type {
     Console,
     Popup,
-    Native,
+    /// Prints the panic report including the backtrace to `stderr`
+    /// (restoring the terminal first, see `set_terminal_restore()`).
+    /// If `crash_dir` is specified, the report is also stored as a
+    /// timestamped crash file in this folder (relative paths are
+    /// located in the `workflow_core::dirs::data_dir()` folder),
+    /// retrievable using `latest_crash_report()`.
+    Native {
+        crash_dir: Option<PathBuf>,
+    },
+    /// Delivers a [`PanicReport`] to the supplied [`PanicSink`].
+    Custom(Arc<dyn PanicSink>),
 }
+
+static HOOK_SET: AtomicBool = AtomicBool::new(false);
+
 /// Set the `console.error` panic hook the first time this is called. Subsequent
 /// invocations do nothing.
 #[inline]
 pub fn set_once(logger_type: Type) {
-    use std::sync::Once;
     static SET_HOOK: Once = Once::new();
-    SET_HOOK.call_once(|| init(logger_type));
+    SET_HOOK.call_once(|| {
+        HOOK_SET.store(true, Ordering::SeqCst);
+        init(logger_type)
+    });
+}
+
+/// Registers a [`PanicSink`] receiving the report of every panic, in addition
+/// to the sink of [`Type::Custom`]. Observers are supported by all hook modes;
+/// if the hook has not been installed via [`set_once()`] yet, the current
+/// panic hook is wrapped to deliver the reports until it is installed.
+pub fn add_panic_observer(observer: Arc<dyn PanicSink>) {
+    report::add_observer(observer);
+    static WRAP_HOOK: Once = Once::new();
+    if !HOOK_SET.load(Ordering::SeqCst) {
+        WRAP_HOOK.call_once(|| {
+            let hook = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                notify(&PanicReport::new(info, stack()));
+                hook(info);
+            }));
+        });
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_custom_sink_report() {
+        let reports = Arc::new(Mutex::new(Vec::<PanicReport>::new()));
+        let reports_ = reports.clone();
+        set_once(Type::Custom(Arc::new(move |report: &PanicReport| {
+            reports_.lock().unwrap().push(report.clone());
+        })));
+
+        let observed = Arc::new(Mutex::new(Vec::<String>::new()));
+        let observed_ = observed.clone();
+        add_panic_observer(Arc::new(move |report: &PanicReport| {
+            observed_.lock().unwrap().push(report.message.clone());
+        }));
+
+        set_breadcrumb_capacity(2);
+        breadcrumb("started");
+        breadcrumb("opened wallet");
+        breadcrumb(format!("sent {} KAS", 10));
+
+        let line = line!() + 2;
+        let result = std::panic::catch_unwind(|| {
+            panic!("failure {}", 42);
+        });
+        assert!(result.is_err());
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        let report = &reports[0];
+        assert_eq!(report.message, "failure 42");
+        assert_eq!(
+            report.location.as_deref(),
+            Some(format!("{}:{line}:13", file!()).as_str())
+        );
+        assert_eq!(report.breadcrumbs, vec!["opened wallet", "sent 10 KAS"]);
+        assert!(report.timestamp > 0);
+        assert!(has_panicked());
+        assert_eq!(last_panic().as_ref(), Some(report));
+        assert_eq!(*observed.lock().unwrap(), vec!["failure 42"]);
+
+        let text = report.to_string();
+        assert!(text.contains("failure 42"));
+        assert!(text.contains("Breadcrumbs:\n\nopened wallet\nsent 10 KAS\n"));
+    }
+
+    const CRASH_CHILD: &str = "WORKFLOW_PANIC_HOOK_CRASH_DIR";
+
+    /// Panics when executed by `test_native_crash_file` in a subprocess.
+    #[test]
+    fn crash_child() {
+        if let Ok(crash_dir) = std::env::var(CRASH_CHILD) {
+            set_once(Type::Native {
+                crash_dir: Some(crash_dir.into()),
+            });
+            breadcrumb("about to crash");
+            panic!("crash {}", "test");
+        }
+    }
+
+    #[test]
+    fn test_native_crash_file() {
+        let crash_dir =
+            std::env::temp_dir().join(format!("workflow-panic-hook-crash-{}", std::process::id()));
+        std::fs::remove_dir_all(&crash_dir).ok();
+        assert!(latest_crash_report(&crash_dir).is_none());
+
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "tests::crash_child", "--nocapture"])
+            .env(CRASH_CHILD, &crash_dir)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("crash report:"));
+
+        let (path, report) = latest_crash_report(&crash_dir).unwrap();
+        assert!(path.starts_with(&crash_dir));
+        assert!(report.contains("crash test"));
+        assert!(report.contains(&format!("at {}:", file!())));
+        assert!(report.contains("Stack:"));
+        assert!(report.contains("crash_child"));
+        assert!(report.contains("Breadcrumbs:\n\nabout to crash\n"));
+        std::fs::remove_dir_all(&crash_dir).ok();
+    }
 }
@@ -1,67 +1,352 @@
+use js_sys::{Function, Reflect};
+use std::cell::RefCell;
 use std::result::Result;
-use std::sync::Mutex;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 use web_sys::{Document, Element};
 
+pub const DEFAULT_POPUP_TITLE: &str = "Application Panic";
+
+pub const DEFAULT_POPUP_STYLE: &str = r#"
+.wasm-logs {
+    position: fixed; top: 0; left: 0; right: 0; bottom: 0; z-index: 2147483647;
+    display: flex; flex-direction: column; margin: 0;
+    background: rgba(16, 0, 0, 0.95); color: #fcc; font-family: monospace; font-size: 13px;
+}
+.wasm-logs[hidden] { display: none; }
+.wasm-logs-header {
+    display: flex; align-items: center; gap: 8px; padding: 8px 12px;
+    background: #600; color: #fff; font-family: sans-serif;
+}
+.wasm-logs-title { flex: 1; font-weight: bold; }
+.wasm-logs-count { opacity: 0.8; }
+.wasm-logs-header button { padding: 4px 12px; cursor: pointer; }
+.wasm-logs-content {
+    flex: 1; overflow: auto; margin: 0; padding: 12px;
+    white-space: pre-wrap; word-break: break-all; user-select: text;
+}
+"#;
+
+const STYLE_ID: &str = "wasm-logs-style";
+
 pub fn document() -> Document {
     let window = web_sys::window().expect("no global `window` exists");
     window.document().expect("unable to get `document` node")
 }
 
-#[derive(Clone)]
-struct Logger {
+struct Overlay {
     element: Element,
+    title: Element,
+    count: Element,
+    content: Element,
+}
+
+/// Event handlers shared by all overlays; created once and never
+/// dropped (a closure can not be dropped while it is being invoked).
+struct Handlers {
+    copy: Closure<dyn FnMut()>,
+    dismiss: Closure<dyn FnMut()>,
+    ready: Closure<dyn FnMut()>,
 }
 
-unsafe impl Send for Logger {}
+struct State {
+    title: String,
+    style: String,
+    messages: Vec<String>,
+    initialized: bool,
+    visible: bool,
+    waiting_for_body: bool,
+    overlay: Option<Overlay>,
+    handlers: Option<Handlers>,
+}
 
-static LOGGER: Mutex<Option<Logger>> = Mutex::new(None);
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State {
+        title: DEFAULT_POPUP_TITLE.to_string(),
+        style: DEFAULT_POPUP_STYLE.to_string(),
+        messages: Vec::new(),
+        initialized: false,
+        visible: false,
+        waiting_for_body: false,
+        overlay: None,
+        handlers: None,
+    });
+}
 
-impl Logger {
-    fn new() -> Result<Self, JsValue> {
-        let element = document().create_element("pre")?;
-        element.set_attribute("class", "wasm-logs")?;
-        element.set_attribute("style", "display:none")?;
+/// Runs `f` with the logger state; returns `None` if the state is
+/// in use (i.e. the panic occurred within the logger itself).
+fn with_state<R>(f: impl FnOnce(&mut State) -> R) -> Option<R> {
+    STATE.with(|state| state.try_borrow_mut().ok().map(|mut state| f(&mut state)))
+}
 
-        document()
-            .body()
-            .expect("Unable to find body element")
-            .append_child(&element)?;
-        Ok(Self { element })
+impl State {
+    fn handlers(&mut self) -> &Handlers {
+        self.handlers.get_or_insert_with(|| Handlers {
+            copy: Closure::new(copy),
+            dismiss: Closure::new(dismiss),
+            ready: Closure::new(|| {
+                with_state(|state| {
+                    state.waiting_for_body = false;
+                    state.update().ok();
+                });
+            }),
+        })
     }
 
-    fn get() -> Option<Logger> {
-        LOGGER.lock().unwrap().clone()
+    /// Creates the overlay if needed and renders the accumulated messages.
+    /// If `document.body` is not available yet, the overlay is created
+    /// once the `DOMContentLoaded` event fires.
+    fn update(&mut self) -> Result<(), JsValue> {
+        if !self.initialized {
+            return Ok(());
+        }
+
+        if self.overlay.is_none() {
+            let doc = document();
+            match doc.body() {
+                Some(body) => {
+                    let overlay = self.create_overlay(&doc)?;
+                    body.append_child(&overlay.element)?;
+                    self.overlay = Some(overlay);
+                }
+                None => {
+                    if !self.waiting_for_body {
+                        self.waiting_for_body = true;
+                        let ready = self.handlers().ready.as_ref().unchecked_ref::<Function>();
+                        doc.add_event_listener_with_callback("DOMContentLoaded", ready)?;
+                    }
+                    return Ok(());
+                }
+            }
+        }
+
+        if let Some(overlay) = &self.overlay {
+            overlay.title.set_text_content(Some(&self.title));
+            let count = (self.messages.len() > 1).then(|| format!("({})", self.messages.len()));
+            overlay.count.set_text_content(count.as_deref());
+            overlay
+                .content
+                .set_text_content(Some(&self.messages.concat()));
+            if self.visible {
+                overlay.element.remove_attribute("hidden")?;
+            } else {
+                overlay.element.set_attribute("hidden", "")?;
+            }
+        }
+        Ok(())
     }
 
-    fn log_error(&self, msg: String) {
-        let html = self.element.inner_html() + &msg;
-        self.element.set_inner_html(&html);
+    fn create_overlay(&mut self, doc: &Document) -> Result<Overlay, JsValue> {
+        self.inject_style(doc)?;
+
+        let element = doc.create_element("div")?;
+        element.set_attribute("class", "wasm-logs")?;
+
+        let header = doc.create_element("div")?;
+        header.set_attribute("class", "wasm-logs-header")?;
+        let title = doc.create_element("span")?;
+        title.set_attribute("class", "wasm-logs-title")?;
+        let count = doc.create_element("span")?;
+        count.set_attribute("class", "wasm-logs-count")?;
+
+        let handlers = self.handlers();
+        let copy = doc.create_element("button")?;
+        copy.set_attribute("class", "wasm-logs-copy")?;
+        copy.set_text_content(Some("Copy to clipboard"));
+        copy.add_event_listener_with_callback("click", handlers.copy.as_ref().unchecked_ref())?;
+        let dismiss = doc.create_element("button")?;
+        dismiss.set_attribute("class", "wasm-logs-dismiss")?;
+        dismiss.set_text_content(Some("Dismiss"));
+        dismiss
+            .add_event_listener_with_callback("click", handlers.dismiss.as_ref().unchecked_ref())?;
+
+        header.append_child(&title)?;
+        header.append_child(&count)?;
+        header.append_child(&copy)?;
+        header.append_child(&dismiss)?;
+
+        let content = doc.create_element("pre")?;
+        content.set_attribute("class", "wasm-logs-content")?;
+
+        element.append_child(&header)?;
+        element.append_child(&content)?;
+
+        Ok(Overlay {
+            element,
+            title,
+            count,
+            content,
+        })
     }
-    fn show_element(&self) -> Result<(), JsValue> {
-        self.element.remove_attribute("style")?;
+
+    fn inject_style(&self, doc: &Document) -> Result<(), JsValue> {
+        let style = match doc.get_element_by_id(STYLE_ID) {
+            Some(style) => style,
+            None => {
+                let style = doc.create_element("style")?;
+                style.set_attribute("id", STYLE_ID)?;
+                let root = match doc.query_selector("head")? {
+                    Some(head) => head,
+                    None => doc.document_element().ok_or("no document element")?,
+                };
+                root.append_child(&style)?;
+                style
+            }
+        };
+        style.set_text_content(Some(&self.style));
         Ok(())
     }
 }
 
+/// Copies the panic output using `navigator.clipboard`; the output is
+/// also selected so that it can be copied manually (or via the legacy
+/// `document.execCommand("copy")`) if the clipboard API is unavailable.
+fn copy() {
+    with_state(|state| {
+        let Some(overlay) = &state.overlay else {
+            return;
+        };
+        let text = state.messages.concat();
+        let window = web_sys::window().expect("no global `window` exists");
+
+        if let Ok(Some(selection)) = window.get_selection() {
+            selection.select_all_children(&overlay.content).ok();
+        }
+
+        let clipboard = Reflect::get(&window.navigator(), &"clipboard".into())
+            .ok()
+            .filter(|clipboard| !clipboard.is_undefined() && !clipboard.is_null());
+        let write_text = clipboard.as_ref().and_then(|clipboard| {
+            Reflect::get(clipboard, &"writeText".into())
+                .ok()?
+                .dyn_into::<Function>()
+                .ok()
+        });
+        let copied = match (clipboard, write_text) {
+            (Some(clipboard), Some(write_text)) => {
+                write_text.call1(&clipboard, &text.into()).is_ok()
+            }
+            _ => false,
+        };
+
+        if !copied {
+            let doc = document();
+            if let Ok(exec_command) = Reflect::get(&doc, &"execCommand".into()) {
+                if let Ok(exec_command) = exec_command.dyn_into::<Function>() {
+                    exec_command.call1(&doc, &"copy".into()).ok();
+                }
+            }
+        }
+    });
+}
+
+/// Removes the overlay and discards the accumulated panic output.
+fn dismiss() {
+    with_state(|state| {
+        if let Some(overlay) = state.overlay.take() {
+            overlay.element.remove();
+        }
+        state.messages.clear();
+        state.visible = false;
+    });
+}
+
 pub fn error(msg: String) {
-    if let Some(logger) = Logger::get() {
-        logger.log_error(msg);
-    }
+    with_state(|state| {
+        state.messages.push(msg);
+        state.update().ok();
+    });
 }
 
 pub fn init_logger() {
-    match Logger::new() {
-        Ok(l) => *LOGGER.lock().unwrap() = Some(l),
-        Err(_e) => {
-            panic!("unable to create Logger");
-        }
+    let result = with_state(|state| {
+        state.initialized = true;
+        state.update()
+    });
+    if !matches!(result, Some(Ok(()))) {
+        panic!("unable to create Logger");
     }
 }
 
-//unsafe impl Sync for Logger{}
 pub fn show_logs() {
-    if let Some(logger) = Logger::get() {
-        let _r = logger.show_element();
+    with_state(|state| {
+        state.visible = true;
+        state.update().ok();
+    });
+}
+
+/// Sets the CSS of the panic overlay, replacing [`DEFAULT_POPUP_STYLE`].
+/// The overlay consists of the `.wasm-logs` element containing the
+/// `.wasm-logs-header` (with `.wasm-logs-title`, `.wasm-logs-count`,
+/// `.wasm-logs-copy` and `.wasm-logs-dismiss` elements) and the
+/// `.wasm-logs-content` panic output.
+pub fn set_popup_style(css: &str) {
+    with_state(|state| {
+        state.style = css.to_string();
+        if state.overlay.is_some() {
+            state.inject_style(&document()).ok();
+        }
+    });
+}
+
+/// Sets the title of the panic overlay.
+pub fn set_popup_title(title: &str) {
+    with_state(|state| {
+        state.title = title.to_string();
+        state.update().ok();
+    });
+}
+
+#[cfg(test)]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+    use web_sys::HtmlElement;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn query(selector: &str) -> Option<Element> {
+        document().query_selector(selector).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_popup_overlay() {
+        set_popup_title("Crash");
+        set_popup_style(".wasm-logs { background: black; }");
+        init_logger();
+        error("first panic\n".to_string());
+        error("second panic\n".to_string());
+
+        let overlay = query(".wasm-logs").unwrap();
+        assert!(overlay.has_attribute("hidden"));
+        show_logs();
+        assert!(!overlay.has_attribute("hidden"));
+
+        let text = |selector: &str| query(selector).unwrap().text_content().unwrap();
+        assert_eq!(text(".wasm-logs-title"), "Crash");
+        assert_eq!(text(".wasm-logs-count"), "(2)");
+        assert_eq!(text(".wasm-logs-content"), "first panic\nsecond panic\n");
+        assert_eq!(text(".wasm-logs-copy"), "Copy to clipboard");
+        assert_eq!(
+            text(&format!("#{STYLE_ID}")),
+            ".wasm-logs { background: black; }"
+        );
+
+        query(".wasm-logs-copy")
+            .unwrap()
+            .dyn_into::<HtmlElement>()
+            .unwrap()
+            .click();
+        query(".wasm-logs-dismiss")
+            .unwrap()
+            .dyn_into::<HtmlElement>()
+            .unwrap()
+            .click();
+        assert!(query(".wasm-logs").is_none());
+
+        // a subsequent panic creates a new overlay
+        error("third panic\n".to_string());
+        assert_eq!(text(".wasm-logs-content"), "third panic\n");
+        assert_eq!(text(".wasm-logs-count"), "");
     }
 }
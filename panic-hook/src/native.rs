@@ -0,0 +1,110 @@
+//!
+//! Native panic handling: terminal restoration, backtrace capture
+//! and crash files.
+//!
+
+use crate::report::PanicReport;
+use std::backtrace::Backtrace;
+use std::fs;
+use std::io::{self, Write};
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+type RestoreFn = Box<dyn Fn() + Send + Sync>;
+
+static TERMINAL_RESTORE: Mutex<Option<RestoreFn>> = Mutex::new(None);
+
+/// Registers a function restoring the terminal state (e.g. disabling
+/// raw mode) invoked by the panic hook before the report is printed.
+/// `workflow-terminal` registers it when entering raw mode.
+pub fn set_terminal_restore<F>(restore: F)
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    *TERMINAL_RESTORE
+        .lock()
+        .unwrap_or_else(|err| err.into_inner()) = Some(Box::new(restore));
+}
+
+fn restore_terminal() {
+    if let Ok(restore) = TERMINAL_RESTORE.try_lock() {
+        if let Some(restore) = restore.as_ref() {
+            restore();
+        }
+    }
+}
+
+/// Resolves `crash_dir`; relative paths are located
+/// in the [`workflow_core::dirs::data_dir()`] folder.
+pub fn resolve_crash_dir(crash_dir: &Path) -> PathBuf {
+    if crash_dir.is_absolute() {
+        crash_dir.to_path_buf()
+    } else {
+        workflow_core::dirs::data_dir()
+            .unwrap_or_default()
+            .join(crash_dir)
+    }
+}
+
+fn crash_timestamp(path: &Path) -> Option<u64> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix("crash-")?
+        .strip_suffix(".log")?
+        .parse()
+        .ok()
+}
+
+/// Writes `report` to `crash-<timestamp>.log` in `crash_dir`.
+pub fn write_crash_report(crash_dir: &Path, report: &PanicReport) -> io::Result<PathBuf> {
+    let crash_dir = resolve_crash_dir(crash_dir);
+    fs::create_dir_all(&crash_dir)?;
+    let path = crash_dir.join(format!("crash-{}.log", report.timestamp));
+    fs::write(&path, report.to_string())?;
+    Ok(path)
+}
+
+/// Returns the path and the contents of the most recent crash file
+/// in `crash_dir`, allowing the application to offer submission of
+/// the crash report on the next start.
+pub fn latest_crash_report(crash_dir: impl AsRef<Path>) -> Option<(PathBuf, String)> {
+    let crash_dir = resolve_crash_dir(crash_dir.as_ref());
+    let path = fs::read_dir(crash_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter_map(|path| crash_timestamp(&path).map(|timestamp| (timestamp, path)))
+        .max_by_key(|(timestamp, _)| *timestamp)?
+        .1;
+    let contents = fs::read_to_string(&path).ok()?;
+    Some((path, contents))
+}
+
+/// Restores the terminal and builds the report including
+/// the backtrace (regardless of `RUST_BACKTRACE`).
+pub(crate) fn report(info: &panic::PanicHookInfo) -> PanicReport {
+    restore_terminal();
+    PanicReport::new(info, Backtrace::force_capture().to_string())
+}
+
+pub(crate) fn print(report: &PanicReport) {
+    let thread = std::thread::current();
+    let thread = thread.name().unwrap_or("<unnamed>");
+    let _ = write!(io::stderr(), "\nthread '{thread}' {report}");
+}
+
+pub(crate) fn hook(info: &panic::PanicHookInfo, crash_dir: Option<&Path>) {
+    let report = report(info);
+    crate::report::notify(&report);
+    print(&report);
+    if let Some(crash_dir) = crash_dir {
+        match write_crash_report(crash_dir, &report) {
+            Ok(path) => {
+                let _ = writeln!(io::stderr(), "crash report: {}", path.display());
+            }
+            Err(err) => {
+                let _ = writeln!(io::stderr(), "unable to write crash report: {err}");
+            }
+        }
+    }
+}
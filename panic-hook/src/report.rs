@@ -0,0 +1,203 @@
+//!
+//! Structured panic reports delivered to a custom [`PanicSink`] and
+//! a breadcrumb buffer recording the most recent application events.
+//!
+//! The report of the last panic is retained (see [`last_panic()`]) and
+//! delivered to the observers registered via `add_panic_observer()`
+//! regardless of the panic hook mode.
+//!
+//! Breadcrumbs can be fed from the `workflow-log` subsystem by installing
+//! a log sink (via `workflow_log::pipe()`) that forwards log lines to
+//! [`breadcrumb()`].
+//!
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Default number of retained breadcrumbs.
+pub const DEFAULT_BREADCRUMB_CAPACITY: usize = 32;
+
+static BREADCRUMB_CAPACITY: AtomicUsize = AtomicUsize::new(DEFAULT_BREADCRUMB_CAPACITY);
+static BREADCRUMBS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static LAST_PANIC: Mutex<Option<PanicReport>> = Mutex::new(None);
+static OBSERVERS: Mutex<Vec<Arc<dyn PanicSink>>> = Mutex::new(Vec::new());
+
+/// Records a breadcrumb included in panic reports. Only the
+/// last [`set_breadcrumb_capacity()`] entries are retained.
+pub fn breadcrumb(text: impl Into<String>) {
+    let capacity = BREADCRUMB_CAPACITY.load(Ordering::Relaxed);
+    let mut breadcrumbs = BREADCRUMBS.lock().unwrap_or_else(|err| err.into_inner());
+    while breadcrumbs.len() >= capacity.max(1) {
+        breadcrumbs.pop_front();
+    }
+    breadcrumbs.push_back(text.into());
+}
+
+/// Sets the number of retained breadcrumbs
+/// (defaults to [`DEFAULT_BREADCRUMB_CAPACITY`]).
+pub fn set_breadcrumb_capacity(capacity: usize) {
+    BREADCRUMB_CAPACITY.store(capacity.max(1), Ordering::Relaxed);
+    let mut breadcrumbs = BREADCRUMBS.lock().unwrap_or_else(|err| err.into_inner());
+    while breadcrumbs.len() > capacity.max(1) {
+        breadcrumbs.pop_front();
+    }
+}
+
+/// Returns the retained breadcrumbs, oldest first.
+pub fn breadcrumbs() -> Vec<String> {
+    // `try_lock()` avoids a deadlock if the panic occurred while the
+    // buffer was locked by the panicking thread
+    match BREADCRUMBS.try_lock() {
+        Ok(breadcrumbs) => breadcrumbs.iter().cloned().collect(),
+        Err(std::sync::TryLockError::Poisoned(err)) => err.into_inner().iter().cloned().collect(),
+        Err(std::sync::TryLockError::WouldBlock) => Vec::new(),
+    }
+}
+
+pub fn clear_breadcrumbs() {
+    BREADCRUMBS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .clear();
+}
+
+/// Returns the report of the most recent panic.
+pub fn last_panic() -> Option<PanicReport> {
+    LAST_PANIC
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone()
+}
+
+/// Returns `true` if a panic has occurred. Under WASM, the
+/// instance should be considered unusable following a panic.
+pub fn has_panicked() -> bool {
+    last_panic().is_some()
+}
+
+pub(crate) fn add_observer(observer: Arc<dyn PanicSink>) {
+    OBSERVERS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .push(observer);
+}
+
+/// Retains the `report` as the last panic and delivers it to the observers.
+pub(crate) fn notify(report: &PanicReport) {
+    if let Ok(mut last) = LAST_PANIC.try_lock() {
+        last.replace(report.clone());
+    }
+    // the observers are invoked without holding the lock
+    let observers = match OBSERVERS.try_lock() {
+        Ok(observers) => observers.clone(),
+        Err(_) => return,
+    };
+    for observer in observers {
+        observer.report(report);
+    }
+}
+
+/// Panic information supplied to a [`PanicSink`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicReport {
+    /// Panic message (payload).
+    pub message: String,
+    /// Source location as `file:line:column`.
+    pub location: Option<String>,
+    /// JavaScript stack (WASM) or Rust backtrace (native).
+    pub stack: String,
+    /// Milliseconds since the UNIX epoch.
+    pub timestamp: u64,
+    /// Breadcrumbs recorded before the panic, oldest first.
+    pub breadcrumbs: Vec<String>,
+}
+
+impl PanicReport {
+    /// Creates a report from the panic `info` and the supplied `stack`.
+    pub fn new(info: &panic::PanicHookInfo, stack: String) -> Self {
+        let payload = info.payload();
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "Box<dyn Any>".to_string()
+        };
+
+        Self {
+            message,
+            location: info.location().map(|location| {
+                format!(
+                    "{}:{}:{}",
+                    location.file(),
+                    location.line(),
+                    location.column()
+                )
+            }),
+            stack,
+            timestamp: timestamp(),
+            breadcrumbs: breadcrumbs(),
+        }
+    }
+}
+
+impl fmt::Display for PanicReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "panicked")?;
+        if let Some(location) = &self.location {
+            write!(f, " at {location}")?;
+        }
+        writeln!(f, ":\n{}", self.message)?;
+        if !self.stack.is_empty() {
+            write!(f, "\nStack:\n\n{}\n", self.stack)?;
+        }
+        if !self.breadcrumbs.is_empty() {
+            write!(f, "{}", format_breadcrumbs(&self.breadcrumbs))?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats breadcrumbs as a section appended to panic output.
+pub(crate) fn format_breadcrumbs(breadcrumbs: &[String]) -> String {
+    let mut text = "\nBreadcrumbs:\n\n".to_string();
+    for breadcrumb in breadcrumbs {
+        text.push_str(breadcrumb);
+        text.push('\n');
+    }
+    text
+}
+
+/// Receiver of panic reports installed using
+/// `set_once(Type::Custom(sink))`. The sink is invoked synchronously
+/// from the panic hook (before the program aborts or unwinds), so it
+/// should persist the report locally (e.g. in `localStorage`) rather
+/// than perform network requests.
+pub trait PanicSink: Send + Sync {
+    fn report(&self, report: &PanicReport);
+}
+
+impl<F> PanicSink for F
+where
+    F: Fn(&PanicReport) + Send + Sync,
+{
+    fn report(&self, report: &PanicReport) {
+        self(report)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn timestamp() -> u64 {
+    js_sys::Date::now() as u64
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
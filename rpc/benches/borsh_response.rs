@@ -0,0 +1,91 @@
+//!
+//! Serialization of a 100-byte `Borsh` RPC response: header, payload and
+//! frame encoded into separate buffers (`BorshServerMessage::try_to_vec()`)
+//! versus in-place serialization into a pooled buffer (as performed by the
+//! server). The number of allocations made per response is reported before
+//! running the benchmarks.
+//!
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use workflow_rpc::messages::borsh::{
+    BorshServerMessage, BorshServerMessageHeader, ServerMessageKind,
+};
+use workflow_rpc::result::ServerResult;
+use workflow_rpc::server::pool::PooledBuffer;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+enum Ops {
+    Get,
+}
+
+fn header() -> BorshServerMessageHeader<Ops, u64> {
+    BorshServerMessageHeader::new(Some(0xc0ffee), ServerMessageKind::Success, Some(Ops::Get))
+}
+
+fn separate_buffers(response: &ServerResult<Vec<u8>>) -> Vec<u8> {
+    let payload = borsh::to_vec(response).unwrap();
+    BorshServerMessage::new(header(), &payload)
+        .try_to_vec()
+        .unwrap()
+}
+
+fn pooled_buffer(response: &ServerResult<Vec<u8>>) -> Vec<u8> {
+    let mut frame = PooledBuffer::take();
+    header().serialize(&mut *frame).unwrap();
+    BorshSerialize::serialize(response, &mut *frame).unwrap();
+    frame.into_frame()
+}
+
+fn allocations(f: impl Fn() -> Vec<u8>) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let frame = f();
+    let after = ALLOCATIONS.load(Ordering::Relaxed);
+    drop(frame);
+    after - before
+}
+
+fn borsh_response(c: &mut Criterion) {
+    let response: ServerResult<Vec<u8>> = Ok(vec![0xab; 100]);
+    assert_eq!(separate_buffers(&response), pooled_buffer(&response));
+
+    let separate = allocations(|| separate_buffers(&response));
+    let pooled = allocations(|| pooled_buffer(&response));
+    println!("allocations per response: {separate} (separate buffers), {pooled} (pooled buffer)");
+    assert!(pooled < separate);
+
+    c.bench_function("borsh response (separate buffers)", |b| {
+        b.iter(|| separate_buffers(black_box(&response)))
+    });
+    c.bench_function("borsh response (pooled buffer)", |b| {
+        b.iter(|| pooled_buffer(black_box(&response)))
+    });
+}
+
+criterion_group!(benches, borsh_response);
+criterion_main!(benches);
@@ -0,0 +1,349 @@
+//!
+//! Client-side deduplication of idempotent RPC calls.
+//!
+//! Ops registered using [`RpcClient::register_idempotent()`](super::RpcClient::register_idempotent)
+//! share a single request while an identical call (same op and serialized request)
+//! is in flight: subsequent callers await the response of the first call instead
+//! of posting a new message. Optionally, the response is cached for a short
+//! period of time ([`Idempotent::with_ttl()`]) and returned to repeat calls.
+//!
+//! Only ops that do not mutate the server state should be registered;
+//! calls to ops that are not registered are always issued.
+//!
+
+use super::error::Error;
+use super::result::Result;
+use crate::imports::*;
+use futures::future::{BoxFuture, Shared};
+
+/// Deduplication policy of an idempotent op
+#[derive(Debug, Clone, Default)]
+pub struct Idempotent {
+    ttl: Option<Duration>,
+    cache_errors: bool,
+}
+
+impl Idempotent {
+    /// Deduplicate in-flight calls without caching the responses
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the last response to repeat calls issued within `ttl`
+    /// of the response arrival
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Cache failed calls as well (errors are not cached by default)
+    pub fn with_cache_errors(mut self, cache_errors: bool) -> Self {
+        self.cache_errors = cache_errors;
+        self
+    }
+
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    pub fn cache_errors(&self) -> bool {
+        self.cache_errors
+    }
+}
+
+/// Identity of a call: the encoding, the op and the serialized request
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Key<Ops> {
+    encoding: Encoding,
+    op: Ops,
+    payload: Vec<u8>,
+}
+
+impl<Ops> Key<Ops> {
+    pub fn new(encoding: Encoding, op: Ops, payload: Vec<u8>) -> Self {
+        Self {
+            encoding,
+            op,
+            payload,
+        }
+    }
+}
+
+type SharedResult<V> = std::result::Result<V, Arc<Error>>;
+type InFlight<V> = Shared<BoxFuture<'static, SharedResult<V>>>;
+
+/// In-flight calls and cached responses of idempotent ops.
+/// `V` is the undecoded response shared between the callers.
+pub(crate) struct Dedup<Ops, V> {
+    policies: Mutex<AHashMap<Ops, Idempotent>>,
+    in_flight: Mutex<AHashMap<Key<Ops>, InFlight<V>>>,
+    cache: Mutex<AHashMap<Key<Ops>, (Instant, SharedResult<V>)>>,
+}
+
+impl<Ops, V> Default for Dedup<Ops, V>
+where
+    Ops: OpsT,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self {
+            policies: Mutex::new(AHashMap::new()),
+            in_flight: Mutex::new(AHashMap::new()),
+            cache: Mutex::new(AHashMap::new()),
+        }
+    }
+}
+
+impl<Ops, V> Dedup<Ops, V>
+where
+    Ops: OpsT,
+    V: Clone + Send + Sync + 'static,
+{
+    pub fn register(&self, op: Ops, policy: Idempotent) {
+        self.policies.lock().unwrap().insert(op, policy);
+    }
+
+    pub fn unregister(&self, op: &Ops) {
+        self.policies.lock().unwrap().remove(op);
+        self.cache.lock().unwrap().retain(|key, _| &key.op != op);
+    }
+
+    /// Policy of the op, `None` if the op is not registered as idempotent
+    pub fn policy(&self, op: &Ops) -> Option<Idempotent> {
+        self.policies.lock().unwrap().get(op).cloned()
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    fn cached(&self, key: &Key<Ops>, ttl: Duration) -> Option<SharedResult<V>> {
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (timestamp, _)| timestamp.elapsed() < ttl);
+        cache.get(key).map(|(_, result)| result.clone())
+    }
+
+    ///
+    /// Issue the call (using `issue`) unless an identical call is in flight
+    /// or a response is cached, decoding the shared response using `decode`.
+    ///
+    /// The in-flight call is driven by whichever caller polls it, so
+    /// dropping the first caller does not cancel the call for the others.
+    /// Errors received by the callers of a shared call are reported
+    /// as [`Error::Shared`].
+    ///
+    pub async fn call<R, F, D>(
+        &self,
+        key: Key<Ops>,
+        policy: &Idempotent,
+        issue: F,
+        decode: D,
+    ) -> Result<R>
+    where
+        F: FnOnce() -> BoxFuture<'static, Result<V>>,
+        D: Fn(&V) -> Result<R>,
+    {
+        if let Some(result) = policy.ttl.and_then(|ttl| self.cached(&key, ttl)) {
+            return Self::decode(&result, decode);
+        }
+
+        let future = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| {
+                issue()
+                    .map(|result| result.map_err(Arc::new))
+                    .boxed()
+                    .shared()
+            })
+            .clone();
+
+        let result = future.clone().await;
+
+        {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            if in_flight
+                .get(&key)
+                .is_some_and(|pending| pending.ptr_eq(&future))
+            {
+                in_flight.remove(&key);
+            }
+        }
+
+        let decoded = Self::decode(&result, decode);
+        if policy.ttl.is_some() && (decoded.is_ok() || policy.cache_errors) {
+            self.cache
+                .lock()
+                .unwrap()
+                .insert(key, (Instant::now(), result));
+        }
+        decoded
+    }
+
+    fn decode<R, D>(result: &SharedResult<V>, decode: D) -> Result<R>
+    where
+        D: Fn(&V) -> Result<R>,
+    {
+        match result {
+            Ok(value) => decode(value),
+            Err(err) => Err(Error::Shared(err.clone())),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::join_all;
+    use std::sync::atomic::AtomicUsize;
+
+    #[derive(
+        Clone, Debug, Eq, PartialEq, Hash, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+    )]
+    enum TestOps {
+        Get,
+        Put,
+    }
+
+    /// Transport counting the posted messages and responding
+    /// with the message sequence number after a delay
+    #[derive(Clone, Default)]
+    struct CountingTransport {
+        posted: Arc<AtomicUsize>,
+        fail: Arc<AtomicBool>,
+    }
+
+    impl CountingTransport {
+        fn issue(&self) -> impl FnOnce() -> BoxFuture<'static, Result<u8>> {
+            let this = self.clone();
+            move || {
+                let n = this.posted.fetch_add(1, Ordering::SeqCst) + 1;
+                let fail = this.fail.load(Ordering::SeqCst);
+                async move {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    if fail {
+                        Err(Error::Timeout)
+                    } else {
+                        Ok(n as u8)
+                    }
+                }
+                .boxed()
+            }
+        }
+
+        fn posted(&self) -> usize {
+            self.posted.load(Ordering::SeqCst)
+        }
+    }
+
+    fn decode(value: &u8) -> Result<u8> {
+        Ok(*value)
+    }
+
+    fn key(op: TestOps, payload: &[u8]) -> Key<TestOps> {
+        Key::new(Encoding::Borsh, op, payload.to_vec())
+    }
+
+    #[tokio::test]
+    async fn test_dedup_concurrent_calls() {
+        let dedup = Dedup::<TestOps, u8>::default();
+        let transport = CountingTransport::default();
+        dedup.register(TestOps::Get, Idempotent::new());
+        assert!(dedup.policy(&TestOps::Put).is_none());
+        let policy = dedup.policy(&TestOps::Get).unwrap();
+
+        let results = join_all(
+            (0..3).map(|_| dedup.call(key(TestOps::Get, b"a"), &policy, transport.issue(), decode)),
+        )
+        .await;
+        assert_eq!(transport.posted(), 1);
+        assert!(results.into_iter().all(|result| result.unwrap() == 1));
+
+        // different requests or encodings are not shared
+        let (a, b, c) = futures::join!(
+            dedup.call(key(TestOps::Get, b"a"), &policy, transport.issue(), decode),
+            dedup.call(key(TestOps::Get, b"b"), &policy, transport.issue(), decode),
+            dedup.call(
+                Key::new(Encoding::SerdeJson, TestOps::Get, b"a".to_vec()),
+                &policy,
+                transport.issue(),
+                decode
+            ),
+        );
+        assert_eq!(transport.posted(), 4);
+        let mut responses = vec![a.unwrap(), b.unwrap(), c.unwrap()];
+        responses.sort();
+        assert_eq!(responses, vec![2, 3, 4]);
+
+        // without a TTL, completed calls are not cached
+        let result = dedup
+            .call(key(TestOps::Get, b"a"), &policy, transport.issue(), decode)
+            .await;
+        assert_eq!((result.unwrap(), transport.posted()), (5, 5));
+        assert!(dedup.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dedup_ttl_cache() {
+        let dedup = Dedup::<TestOps, u8>::default();
+        let transport = CountingTransport::default();
+        let policy = Idempotent::new().with_ttl(Duration::from_millis(200));
+
+        for _ in 0..3 {
+            let result = dedup
+                .call(key(TestOps::Get, b"a"), &policy, transport.issue(), decode)
+                .await;
+            assert_eq!(result.unwrap(), 1);
+        }
+        assert_eq!(transport.posted(), 1);
+
+        dedup.clear_cache();
+        let result = dedup
+            .call(key(TestOps::Get, b"a"), &policy, transport.issue(), decode)
+            .await;
+        assert_eq!(result.unwrap(), 2);
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        let result = dedup
+            .call(key(TestOps::Get, b"a"), &policy, transport.issue(), decode)
+            .await;
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_errors_not_cached() {
+        let dedup = Dedup::<TestOps, u8>::default();
+        let transport = CountingTransport::default();
+        let policy = Idempotent::new().with_ttl(Duration::from_secs(60));
+
+        transport.fail.store(true, Ordering::SeqCst);
+        let results = join_all(
+            (0..3).map(|_| dedup.call(key(TestOps::Get, b"a"), &policy, transport.issue(), decode)),
+        )
+        .await;
+        assert_eq!(transport.posted(), 1);
+        for result in results {
+            assert!(matches!(result, Err(Error::Shared(err)) if matches!(*err, Error::Timeout)));
+        }
+
+        transport.fail.store(false, Ordering::SeqCst);
+        let result = dedup
+            .call(key(TestOps::Get, b"a"), &policy, transport.issue(), decode)
+            .await;
+        assert_eq!((result.unwrap(), transport.posted()), (2, 2));
+
+        // errors are cached if requested
+        let policy = policy.with_cache_errors(true);
+        transport.fail.store(true, Ordering::SeqCst);
+        for _ in 0..2 {
+            let result = dedup
+                .call(key(TestOps::Put, b"a"), &policy, transport.issue(), decode)
+                .await;
+            assert!(result.is_err());
+        }
+        assert_eq!(transport.posted(), 3);
+    }
+}
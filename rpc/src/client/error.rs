@@ -4,6 +4,8 @@
 
 use crate::error::ServerError;
 use crate::messages::serde_json::JsonServerError;
+use crate::negotiation::VersionRange;
+use crate::trace::TraceId;
 use serde::*;
 use std::fmt::Display;
 use thiserror::Error;
@@ -37,9 +39,34 @@ pub enum Error {
     #[error("RPC client is missing notification handler")]
     MissingNotificationHandler,
 
+    /// A multiplexed channel with this namespace already exists
+    #[error("RPC namespace `{0}` is already in use")]
+    DuplicateNamespace(String),
+
+    /// Message received for a namespace without a multiplexed channel
+    #[error("RPC namespace `{0}` not found")]
+    UnknownNamespace(String),
+
+    /// The client and the server have no protocol version in common
+    #[error("RPC protocol version mismatch (client {client}, server {server})")]
+    ProtocolVersionMismatch {
+        client: VersionRange,
+        server: VersionRange,
+    },
+
     /// Underlying WebSocket error
     #[error("WebSocket -> {0}")]
     WebSocketError(#[from] WebSocketError),
+    /// Operation applicable only to clients operating over a WebSocket
+    /// (the client has been created with a custom transport)
+    #[error("RPC client transport does not support `{0}`")]
+    NotWebSocket(&'static str),
+    /// Failure posting to the `MessagePort` (see `MessagePortTransport`)
+    #[error("MessagePort -> {0}")]
+    Port(String),
+    /// Op name not matching any variant of the `Ops` enum
+    #[error("unknown RPC op `{0}`")]
+    UnknownOp(String),
     /// RPC call timeout
     #[error("RPC request timeout")]
     Timeout,
@@ -102,10 +129,97 @@ pub enum Error {
 
     #[error("{0}")]
     JsonServerError(JsonServerError),
+
+    /// Error of a deduplicated call, shared by all callers
+    /// awaiting the call (see [`Idempotent`](super::Idempotent))
+    #[error(transparent)]
+    Shared(std::sync::Arc<Error>),
+
+    /// Error of an RPC call carrying the trace id of the call
+    /// (include the trace id when reporting server-side issues)
+    #[error("{source} (trace {trace})")]
+    Traced { trace: TraceId, source: Box<Error> },
     // #[error("{0}")]
     // RegexError(#[from] regex::Error),
 }
 
+impl Error {
+    /// Attach the `trace` id of the RPC call to the error
+    pub fn with_trace(self, trace: TraceId) -> Self {
+        match self {
+            Error::Traced { .. } => self,
+            err => Error::Traced {
+                trace,
+                source: Box::new(err),
+            },
+        }
+    }
+
+    /// Trace id of the RPC call that produced the error
+    pub fn trace_id(&self) -> Option<TraceId> {
+        match self {
+            Error::Traced { trace, .. } => Some(*trace),
+            Error::Shared(err) => err.trace_id(),
+            _ => None,
+        }
+    }
+
+    /// Name of the error variant (the trace id and the sharing
+    /// of deduplicated calls are not reflected in the name)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Error::InvalidEvent { .. } => "InvalidEvent",
+            Error::InvalidUrl { .. } => "InvalidUrl",
+            Error::RpcError { .. } => "RpcError",
+            Error::ResponseHandler { .. } => "ResponseHandler",
+            Error::Disconnect { .. } => "Disconnect",
+            Error::NotificationMethod { .. } => "NotificationMethod",
+            Error::WebSocketMessageType { .. } => "WebSocketMessageType",
+            Error::MissingNotificationHandler { .. } => "MissingNotificationHandler",
+            Error::DuplicateNamespace { .. } => "DuplicateNamespace",
+            Error::UnknownNamespace { .. } => "UnknownNamespace",
+            Error::ProtocolVersionMismatch { .. } => "ProtocolVersionMismatch",
+            Error::WebSocketError { .. } => "WebSocketError",
+            Error::NotWebSocket { .. } => "NotWebSocket",
+            Error::Port { .. } => "Port",
+            Error::UnknownOp { .. } => "UnknownOp",
+            Error::Timeout { .. } => "Timeout",
+            Error::ReceiverCtl { .. } => "ReceiverCtl",
+            Error::NoDataInSuccessResponse { .. } => "NoDataInSuccessResponse",
+            Error::NoDataInNotificationMessage { .. } => "NoDataInNotificationMessage",
+            Error::NoDataInErrorResponse { .. } => "NoDataInErrorResponse",
+            Error::ErrorDeserializingServerMessageData { .. } => {
+                "ErrorDeserializingServerMessageData"
+            }
+            Error::ErrorDeserializingResponseData { .. } => "ErrorDeserializingResponseData",
+            Error::StatusCode { .. } => "StatusCode",
+            Error::RpcCall { .. } => "RpcCall",
+            Error::BorshSerialize { .. } => "BorshSerialize",
+            Error::BorshDeserialize { .. } => "BorshDeserialize",
+            Error::SerdeSerialize { .. } => "SerdeSerialize",
+            Error::SerdeDeserialize { .. } => "SerdeDeserialize",
+            Error::BorshResponseDeserialize { .. } => "BorshResponseDeserialize",
+            Error::ChannelRecvError { .. } => "ChannelRecvError",
+            Error::ChannelSendError { .. } => "ChannelSendError",
+            Error::Utf8Error { .. } => "Utf8Error",
+            Error::SerdeJSON { .. } => "SerdeJSON",
+            Error::Task { .. } => "Task",
+            Error::ServerError { .. } => "ServerError",
+            Error::JsonServerError { .. } => "JsonServerError",
+            Error::Shared(err) => err.name(),
+            Error::Traced { source, .. } => source.name(),
+        }
+    }
+
+    /// The error without the trace id attached by [`Error::with_trace()`]
+    pub fn untraced(&self) -> &Error {
+        match self {
+            Error::Traced { source, .. } => source.untraced(),
+            err => err,
+        }
+    }
+}
+
 impl From<ServerError> for Error {
     fn from(err: ServerError) -> Self {
         Error::ServerError(err)
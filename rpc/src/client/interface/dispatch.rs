@@ -0,0 +1,350 @@
+//!
+//! Dispatch modes of client-side notification handlers
+//! (see [`Notification::with_dispatch()`](super::Notification::with_dispatch)).
+//!
+
+use super::NotificationFn;
+use crate::imports::*;
+use tokio::sync::Semaphore;
+use workflow_core::channel::{bounded, Sender, TrySendError};
+use workflow_core::task::spawn;
+
+/// Default queue capacity of [`Dispatch::ordered()`]
+pub const DEFAULT_DISPATCH_CAPACITY: usize = 1024;
+
+/// Handling of notifications received while the
+/// [`Dispatch::Ordered`] queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Suspend the client message reader until the queue has room
+    /// (this delays processing of all messages received by the client)
+    Block,
+    /// Drop the notification, incrementing the dropped notification
+    /// counter (see [`Interface::dropped_notifications()`](super::Interface::dropped_notifications))
+    Drop,
+}
+
+/// Dispatch mode of a notification handler
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dispatch {
+    /// Execute the handler within the client message reader; the
+    /// reader does not process any further messages until it returns.
+    #[default]
+    Inline,
+    /// Execute the handler sequentially in a dedicated task, preserving
+    /// the order of notifications. Up to `capacity` notifications wait
+    /// for execution; further notifications are handled per `overflow`.
+    Ordered { capacity: usize, overflow: Overflow },
+    /// Execute each handler in its own task, at most `limit` at a time;
+    /// the client message reader waits while the limit is reached.
+    Concurrent { limit: usize },
+    /// Execute the handler sequentially in a dedicated task, retaining
+    /// only the newest pending notification (suitable for notifications
+    /// carrying the full state). Replaced notifications are counted as dropped.
+    Latest,
+}
+
+impl Dispatch {
+    /// [`Dispatch::Ordered`] with [`DEFAULT_DISPATCH_CAPACITY`] blocking on overflow
+    pub fn ordered() -> Self {
+        Dispatch::Ordered {
+            capacity: DEFAULT_DISPATCH_CAPACITY,
+            overflow: Overflow::Block,
+        }
+    }
+}
+
+type Item<Msg> = (Option<TraceId>, Msg);
+
+pub(crate) struct LatestState<Msg> {
+    pending: Option<Item<Msg>>,
+    running: bool,
+}
+
+/// Per-notification dispatch state; dispatcher tasks are
+/// started on the first notification and exit once the
+/// [`Dispatcher`] is dropped.
+pub(crate) enum Dispatcher<Msg> {
+    Inline,
+    Ordered {
+        capacity: usize,
+        overflow: Overflow,
+        sender: Mutex<Option<Sender<Item<Msg>>>>,
+    },
+    Concurrent {
+        semaphore: Arc<Semaphore>,
+    },
+    Latest {
+        state: Arc<Mutex<LatestState<Msg>>>,
+    },
+}
+
+impl<Msg> Dispatcher<Msg>
+where
+    Msg: Send + Sync + 'static,
+{
+    pub fn new(dispatch: Dispatch) -> Self {
+        match dispatch {
+            Dispatch::Inline => Dispatcher::Inline,
+            Dispatch::Ordered { capacity, overflow } => {
+                assert!(
+                    capacity > 0,
+                    "RPC notification queue capacity must be greater than 0"
+                );
+                Dispatcher::Ordered {
+                    capacity,
+                    overflow,
+                    sender: Mutex::new(None),
+                }
+            }
+            Dispatch::Concurrent { limit } => {
+                assert!(
+                    limit > 0,
+                    "RPC notification concurrency limit must be greater than 0"
+                );
+                Dispatcher::Concurrent {
+                    semaphore: Arc::new(Semaphore::new(limit)),
+                }
+            }
+            Dispatch::Latest => Dispatcher::Latest {
+                state: Arc::new(Mutex::new(LatestState {
+                    pending: None,
+                    running: false,
+                })),
+            },
+        }
+    }
+
+    pub async fn dispatch(
+        &self,
+        method: &NotificationFn<Msg>,
+        dropped: &AtomicU64,
+        trace: Option<TraceId>,
+        msg: Msg,
+    ) -> ServerResult<()> {
+        match self {
+            Dispatcher::Inline => method(trace, msg).await,
+            Dispatcher::Ordered {
+                capacity,
+                overflow,
+                sender,
+            } => {
+                let sender = sender
+                    .lock()
+                    .unwrap()
+                    .get_or_insert_with(|| {
+                        let (sender, receiver) = bounded::<Item<Msg>>(*capacity);
+                        let method = method.clone();
+                        spawn(async move {
+                            while let Ok((trace, msg)) = receiver.recv().await {
+                                call(&method, trace, msg).await;
+                            }
+                        });
+                        sender
+                    })
+                    .clone();
+
+                match overflow {
+                    Overflow::Block => sender
+                        .send((trace, msg))
+                        .await
+                        .map_err(|_| ServerError::Close),
+                    Overflow::Drop => match sender.try_send((trace, msg)) {
+                        Ok(()) => Ok(()),
+                        Err(TrySendError::Full(_)) => {
+                            dropped.fetch_add(1, Ordering::SeqCst);
+                            Ok(())
+                        }
+                        Err(TrySendError::Closed(_)) => Err(ServerError::Close),
+                    },
+                }
+            }
+            Dispatcher::Concurrent { semaphore } => {
+                let permit = semaphore
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .map_err(|_| ServerError::Close)?;
+                let method = method.clone();
+                spawn(async move {
+                    call(&method, trace, msg).await;
+                    drop(permit);
+                });
+                Ok(())
+            }
+            Dispatcher::Latest { state } => {
+                let start = {
+                    let mut state = state.lock().unwrap();
+                    if state.pending.replace((trace, msg)).is_some() {
+                        dropped.fetch_add(1, Ordering::SeqCst);
+                    }
+                    !std::mem::replace(&mut state.running, true)
+                };
+
+                if start {
+                    let state = state.clone();
+                    let method = method.clone();
+                    spawn(async move {
+                        loop {
+                            let (trace, msg) = {
+                                let mut state = state.lock().unwrap();
+                                match state.pending.take() {
+                                    Some(item) => item,
+                                    None => {
+                                        state.running = false;
+                                        break;
+                                    }
+                                }
+                            };
+                            call(&method, trace, msg).await;
+                        }
+                    });
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+async fn call<Msg>(method: &NotificationFn<Msg>, trace: Option<TraceId>, msg: Msg) {
+    method(trace, msg)
+        .await
+        .unwrap_or_else(|err| log_trace!("error handling server notification {}", err));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{Interface, Notification};
+    use std::sync::atomic::AtomicUsize;
+    use workflow_core::channel::{unbounded, Receiver};
+
+    #[derive(
+        Clone, Debug, Eq, PartialEq, Hash, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+    )]
+    enum TestOps {
+        State,
+    }
+
+    /// Interface with a [`TestOps::State`] notification handler
+    /// forwarding the received sequence numbers to the returned receiver
+    fn interface(dispatch: Dispatch) -> (Interface<TestOps>, Receiver<u32>) {
+        let (sender, receiver) = unbounded();
+        let mut interface = Interface::new();
+        interface.notification(
+            TestOps::State,
+            Notification::new(move |seq: u32| {
+                let sender = sender.clone();
+                Box::pin(async move {
+                    if seq % 10 == 0 {
+                        tokio::task::yield_now().await;
+                    }
+                    sender.send(seq).await.unwrap();
+                    Ok(())
+                })
+            })
+            .with_dispatch(dispatch),
+        );
+        (interface, receiver)
+    }
+
+    async fn notify(interface: &Interface<TestOps>, seq: u32) {
+        let payload = borsh::to_vec(&seq).unwrap();
+        interface
+            .call_notification_with_borsh(&TestOps::State, None, &payload)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_ordered() {
+        let (interface, receiver) = interface(Dispatch::Ordered {
+            capacity: 16,
+            overflow: Overflow::Block,
+        });
+        for seq in 0..1000 {
+            notify(&interface, seq).await;
+        }
+
+        let mut received = Vec::new();
+        while received.len() < 1000 {
+            received.push(receiver.recv().await.unwrap());
+        }
+        assert_eq!(received, (0..1000).collect::<Vec<_>>());
+        assert_eq!(interface.dropped_notifications(&TestOps::State), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_ordered_overflow() {
+        let (interface, receiver) = interface(Dispatch::Ordered {
+            capacity: 8,
+            overflow: Overflow::Drop,
+        });
+        // the dispatcher task does not run until the reader yields
+        for seq in 0..100 {
+            notify(&interface, seq).await;
+        }
+        assert_eq!(interface.dropped_notifications(&TestOps::State), 92);
+
+        let mut received = Vec::new();
+        while received.len() < 8 {
+            received.push(receiver.recv().await.unwrap());
+        }
+        assert_eq!(received, (0..8).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_latest() {
+        let (interface, receiver) = interface(Dispatch::Latest);
+        for seq in 0..1000 {
+            notify(&interface, seq).await;
+        }
+        assert_eq!(receiver.recv().await.unwrap(), 999);
+        assert_eq!(interface.dropped_notifications(&TestOps::State), 999);
+
+        notify(&interface, 1000).await;
+        assert_eq!(receiver.recv().await.unwrap(), 1000);
+        assert_eq!(interface.dropped_notifications(&TestOps::State), 999);
+        assert!(receiver.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_dispatch_concurrent() {
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (sender, receiver) = unbounded();
+        let mut interface = Interface::new();
+        interface.notification(
+            TestOps::State,
+            Notification::new({
+                let active = active.clone();
+                let peak = peak.clone();
+                move |seq: u32| {
+                    let active = active.clone();
+                    let peak = peak.clone();
+                    let sender = sender.clone();
+                    Box::pin(async move {
+                        let n = active.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(n, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(2)).await;
+                        active.fetch_sub(1, Ordering::SeqCst);
+                        sender.send(seq).await.unwrap();
+                        Ok(())
+                    })
+                }
+            })
+            .with_dispatch(Dispatch::Concurrent { limit: 4 }),
+        );
+
+        for seq in 0..100 {
+            notify(&interface, seq).await;
+        }
+        let mut received = Vec::new();
+        while received.len() < 100 {
+            received.push(receiver.recv().await.unwrap());
+        }
+        received.sort();
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+        assert!(peak.load(Ordering::SeqCst) <= 4);
+    }
+}
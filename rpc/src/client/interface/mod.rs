@@ -1,5 +1,7 @@
+pub mod dispatch;
 pub mod notification;
 use crate::imports::*;
+pub use dispatch::{Dispatch, Overflow, DEFAULT_DISPATCH_CAPACITY};
 pub use notification::*;
 
 /// Collection of server-side notification handlers
@@ -40,9 +42,23 @@ where
         }
     }
 
-    pub async fn call_notification_with_borsh(&self, op: &Ops, payload: &[u8]) -> ServerResult<()> {
+    /// Number of notifications of `op` dropped due to the
+    /// [`Dispatch`] mode of the handler (see [`Notification::with_dispatch()`])
+    pub fn dropped_notifications(&self, op: &Ops) -> u64 {
+        self.notifications
+            .get(op)
+            .map(|notification| notification.dropped())
+            .unwrap_or_default()
+    }
+
+    pub async fn call_notification_with_borsh(
+        &self,
+        op: &Ops,
+        trace: Option<TraceId>,
+        payload: &[u8],
+    ) -> ServerResult<()> {
         if let Some(notification) = self.notifications.get(op) {
-            notification.call_with_borsh(payload).await
+            notification.call_with_borsh(trace, payload).await
         } else {
             Err(ServerError::NotFound)
         }
@@ -51,10 +67,11 @@ where
     pub async fn call_notification_with_serde_json(
         &self,
         op: &Ops,
+        trace: Option<TraceId>,
         payload: Value,
     ) -> ServerResult<()> {
         if let Some(notification) = self.notifications.get(op) {
-            notification.call_with_serde_json(payload).await
+            notification.call_with_serde_json(trace, payload).await
         } else {
             Err(ServerError::NotFound)
         }
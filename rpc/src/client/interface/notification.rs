@@ -1,13 +1,16 @@
+use super::dispatch::{Dispatch, Dispatcher};
 use crate::imports::*;
 
 #[async_trait]
 pub trait NotificationTrait: Send + Sync + 'static {
-    async fn call_with_borsh(&self, data: &[u8]) -> ServerResult<()>;
-    async fn call_with_serde_json(&self, value: Value) -> ServerResult<()>;
+    async fn call_with_borsh(&self, trace: Option<TraceId>, data: &[u8]) -> ServerResult<()>;
+    async fn call_with_serde_json(&self, trace: Option<TraceId>, value: Value) -> ServerResult<()>;
+    /// Number of notifications dropped by the [`Dispatch`] mode
+    fn dropped(&self) -> u64;
 }
 
 pub type NotificationFn<Msg> =
-    Arc<Box<dyn Send + Sync + Fn(Msg) -> NotificationFnReturn<()> + 'static>>;
+    Arc<Box<dyn Send + Sync + Fn(Option<TraceId>, Msg) -> NotificationFnReturn<()> + 'static>>;
 
 pub type NotificationFnReturn<T> =
     Pin<Box<(dyn Send + 'static + Future<Output = ServerResult<T>>)>>;
@@ -17,6 +20,8 @@ where
     Msg: BorshDeserialize + DeserializeOwned + Send + Sync + 'static,
 {
     method: NotificationFn<Msg>,
+    dispatcher: Dispatcher<Msg>,
+    dropped: AtomicU64,
 }
 
 impl<Msg> Notification<Msg>
@@ -26,11 +31,33 @@ where
     pub fn new<FN>(method_fn: FN) -> Notification<Msg>
     where
         FN: Send + Sync + Fn(Msg) -> NotificationFnReturn<()> + 'static,
+    {
+        Notification {
+            method: Arc::new(Box::new(move |_trace, msg| method_fn(msg))),
+            dispatcher: Dispatcher::Inline,
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Create a notification handler receiving the trace id of the RPC
+    /// call during which the server has posted the notification
+    /// (see [`trace`](crate::trace)).
+    pub fn new_with_trace<FN>(method_fn: FN) -> Notification<Msg>
+    where
+        FN: Send + Sync + Fn(Option<TraceId>, Msg) -> NotificationFnReturn<()> + 'static,
     {
         Notification {
             method: Arc::new(Box::new(method_fn)),
+            dispatcher: Dispatcher::Inline,
+            dropped: AtomicU64::new(0),
         }
     }
+
+    /// Set the [`Dispatch`] mode of the handler (defaults to [`Dispatch::Inline`])
+    pub fn with_dispatch(mut self, dispatch: Dispatch) -> Self {
+        self.dispatcher = Dispatcher::new(dispatch);
+        self
+    }
 }
 
 #[async_trait]
@@ -38,15 +65,23 @@ impl<Msg> NotificationTrait for Notification<Msg>
 where
     Msg: BorshDeserialize + DeserializeOwned + Send + Sync + 'static,
 {
-    async fn call_with_borsh(&self, data: &[u8]) -> ServerResult<()> {
+    async fn call_with_borsh(&self, trace: Option<TraceId>, data: &[u8]) -> ServerResult<()> {
         let msg = Msg::try_from_slice(data)
             .map_err(|err| ServerError::NotificationDeserialize(err.to_string()))?;
-        (self.method)(msg).await
+        self.dispatcher
+            .dispatch(&self.method, &self.dropped, trace, msg)
+            .await
     }
 
-    async fn call_with_serde_json(&self, value: Value) -> ServerResult<()> {
+    async fn call_with_serde_json(&self, trace: Option<TraceId>, value: Value) -> ServerResult<()> {
         let msg: Msg = serde_json::from_value(value)
             .map_err(|err| ServerError::NotificationDeserialize(err.to_string()))?;
-        (self.method)(msg).await
+        self.dispatcher
+            .dispatch(&self.method, &self.dropped, trace, msg)
+            .await
+    }
+
+    fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::SeqCst)
     }
 }
@@ -2,21 +2,40 @@
 //! RPC client (operates uniformly in native and WASM-browser environments).
 //!
 
+mod dedup;
 pub mod error;
 mod interface;
+mod multiplexer;
+mod negotiator;
+#[cfg(target_arch = "wasm32")]
+mod port;
 pub mod prelude;
 mod protocol;
 pub mod result;
+pub mod transport;
+#[cfg(all(target_arch = "wasm32", feature = "wasm32-sdk"))]
+pub mod wasm;
 pub use crate::client::error::Error;
 pub use crate::client::result::Result;
 
+use crate::heartbeat::Beat;
 use crate::imports::*;
+pub use crate::negotiation::{Negotiated, Negotiation, VersionRange};
+pub use dedup::Idempotent;
+use dedup::{Dedup, Key};
+use futures::StreamExt;
 use futures_util::select_biased;
-pub use interface::{Interface, Notification};
-use protocol::ProtocolHandler;
+pub use interface::{Dispatch, Interface, Notification, Overflow, DEFAULT_DISPATCH_CAPACITY};
+use multiplexer::Channel;
+pub use multiplexer::RpcMultiplexer;
+use negotiator::Negotiator;
+#[cfg(target_arch = "wasm32")]
+pub use port::MessagePortTransport;
 pub use protocol::{BorshProtocol, JsonProtocol};
+use protocol::{ProtocolHandler, Transport};
 use std::fmt::Debug;
 use std::str::FromStr;
+pub use transport::RpcTransport;
 use workflow_core::{channel::Multiplexer, task::yield_now};
 pub use workflow_websocket::client::{
     ConnectOptions, ConnectResult, ConnectStrategy, Resolver, ResolverResult, WebSocketConfig,
@@ -96,6 +115,7 @@ pub trait NotificationHandler: Send + Sync + 'static {
 pub struct Options<'url> {
     pub ctl_multiplexer: Option<Multiplexer<Ctl>>,
     pub url: Option<&'url str>,
+    pub negotiation: Option<Negotiation>,
 }
 
 impl<'url> Options<'url> {
@@ -112,10 +132,28 @@ impl<'url> Options<'url> {
         self.ctl_multiplexer = Some(ctl_multiplexer);
         self
     }
+
+    /// Negotiate the protocol version and capabilities with the server
+    /// when connecting (see [`crate::negotiation`]). The negotiation is
+    /// performed as the handshake of the underlying WebSocket, replacing
+    /// the `handshake` supplied in the [`WebSocketConfig`].
+    pub fn with_negotiation(mut self, negotiation: Negotiation) -> Self {
+        self.negotiation = Some(negotiation);
+        self
+    }
+}
+
+/// Undecoded response shared between the callers of a deduplicated call
+#[derive(Clone)]
+enum RawResponse {
+    Borsh(Vec<u8>),
+    Json(Value),
 }
 
 struct Inner<Ops> {
-    ws: Arc<WebSocket>,
+    transport: Arc<dyn RpcTransport>,
+    /// WebSocket of the transport (if the client operates over a WebSocket)
+    ws: Option<Arc<WebSocket>>,
     is_running: AtomicBool,
     is_connected: AtomicBool,
     receiver_is_running: AtomicBool,
@@ -126,6 +164,11 @@ struct Inner<Ops> {
     timeout_duration: AtomicU64,
     ctl_multiplexer: Option<Multiplexer<Ctl>>,
     protocol: Arc<dyn ProtocolHandler<Ops>>,
+    channel: Option<Channel>,
+    negotiator: Option<Arc<Negotiator>>,
+    dedup: Dedup<Ops, RawResponse>,
+    /// Respond to the heartbeat pings of the server
+    heartbeat_responder: AtomicBool,
 }
 
 impl<Ops> Inner<Ops>
@@ -133,14 +176,18 @@ where
     Ops: OpsT,
 {
     fn new<T>(
-        ws: Arc<WebSocket>,
+        transport: Arc<dyn RpcTransport>,
+        ws: Option<Arc<WebSocket>>,
         protocol: Arc<dyn ProtocolHandler<Ops>>,
         options: Options,
+        channel: Option<Channel>,
+        negotiator: Option<Arc<Negotiator>>,
     ) -> Result<Self>
     where
         T: ProtocolHandler<Ops> + Send + Sync + 'static,
     {
         let inner = Inner {
+            transport,
             ws,
             is_running: AtomicBool::new(false),
             is_connected: AtomicBool::new(false),
@@ -152,6 +199,10 @@ where
             timeout_timer_interval: AtomicU64::new(5_000),
             ctl_multiplexer: options.ctl_multiplexer,
             protocol,
+            channel,
+            negotiator,
+            dedup: Dedup::default(),
+            heartbeat_responder: AtomicBool::new(true),
         };
 
         Ok(inner)
@@ -174,7 +225,7 @@ where
     }
 
     pub async fn shutdown(self: &Arc<Self>) -> Result<()> {
-        self.ws.disconnect().await?;
+        self.transport.disconnect().await?;
         yield_now().await;
         if self.is_running.load(Ordering::Relaxed) {
             self.stop_timeout().await?;
@@ -187,6 +238,7 @@ where
     fn timeout_task(self: Arc<Self>) {
         self.timeout_is_running.store(true, Ordering::SeqCst);
         workflow_core::task::spawn(async move {
+            let mut clock_jump = Box::pin(workflow_core::clock::clock_jump()).fuse();
             'outer: loop {
                 let timeout_timer_interval =
                     Duration::from_millis(self.timeout_timer_interval.load(Ordering::SeqCst));
@@ -195,6 +247,14 @@ where
                         let timeout = Duration::from_millis(self.timeout_duration.load(Ordering::Relaxed));
                         self.protocol.handle_timeout(timeout).await;
                     },
+                    jump = clock_jump.next() => {
+                        if let Some(jump) = jump {
+                            // re-validate pending requests right away; the monotonic
+                            // age of the requests does not include the suspended time
+                            let timeout = Duration::from_millis(self.timeout_duration.load(Ordering::Relaxed));
+                            self.protocol.handle_timeout(timeout.saturating_sub(jump.suspended)).await;
+                        }
+                    },
                     _ = self.timeout_shutdown.request.receiver.recv().fuse() => {
                         break 'outer;
                     },
@@ -209,7 +269,7 @@ where
 
     fn receiver_task(self: Arc<Self>) {
         self.receiver_is_running.store(true, Ordering::SeqCst);
-        let receiver_rx = self.ws.receiver_rx().clone();
+        let receiver_rx = self.transport.receiver_rx().clone();
         workflow_core::task::spawn(async move {
             'outer: loop {
                 select_biased! {
@@ -218,25 +278,18 @@ where
                             Ok(msg) => {
                                 match msg {
                                     WebSocketMessage::Binary(_) | WebSocketMessage::Text(_) => {
+                                        if let Some(beat) = Beat::from_message(&msg) {
+                                            self.handle_heartbeat(beat).await;
+                                            continue;
+                                        }
                                         self.protocol.handle_message(msg).await
                                         .unwrap_or_else(|err|log_trace!("wRPC error: `{err}`"));
                                     }
                                     WebSocketMessage::Open => {
-                                        self.is_connected.store(true, Ordering::SeqCst);
-                                        if let Some(ctl_channel) = &self.ctl_multiplexer {
-                                            ctl_channel.try_broadcast(Ctl::Connect).expect("ctl_channel.try_broadcast(Ctl::Connect)");
-                                        }
+                                        self.handle_open();
                                     }
                                     WebSocketMessage::Close => {
-                                        self.is_connected.store(false, Ordering::SeqCst);
-
-                                        self.protocol.handle_disconnect().await.unwrap_or_else(|err|{
-                                            log_error!("wRPC error during protocol disconnect: {err}");
-                                        });
-
-                                        if let Some(ctl_channel) = &self.ctl_multiplexer {
-                                            ctl_channel.try_broadcast(Ctl::Disconnect).expect("ctl_channel.try_broadcast(Ctl::Disconnect)");
-                                        }
+                                        self.handle_close().await;
                                     }
                                 }
                             },
@@ -260,6 +313,46 @@ where
         });
     }
 
+    async fn handle_heartbeat(&self, beat: Beat) {
+        if let Beat::Ping(seq) = beat {
+            if self.heartbeat_responder.load(Ordering::Relaxed) {
+                let pong = Beat::Pong(seq).encode(self.protocol.encoding());
+                self.transport
+                    .post(pong.into())
+                    .await
+                    .unwrap_or_else(|err| log_trace!("wRPC heartbeat error: `{err}`"));
+            }
+        }
+    }
+
+    fn handle_open(&self) {
+        self.is_connected.store(true, Ordering::SeqCst);
+        if let Some(ctl_channel) = &self.ctl_multiplexer {
+            ctl_channel
+                .try_broadcast(Ctl::Connect)
+                .expect("ctl_channel.try_broadcast(Ctl::Connect)");
+        }
+    }
+
+    async fn handle_close(&self) {
+        self.is_connected.store(false, Ordering::SeqCst);
+        // responses of the previous connection may be stale
+        self.dedup.clear_cache();
+
+        self.protocol
+            .handle_disconnect()
+            .await
+            .unwrap_or_else(|err| {
+                log_error!("wRPC error during protocol disconnect: {err}");
+            });
+
+        if let Some(ctl_channel) = &self.ctl_multiplexer {
+            ctl_channel
+                .try_broadcast(Ctl::Disconnect)
+                .expect("ctl_channel.try_broadcast(Ctl::Disconnect)");
+        }
+    }
+
     async fn stop_receiver(&self) -> Result<()> {
         if !self.receiver_is_running.load(Ordering::SeqCst) {
             return Ok(());
@@ -370,7 +463,7 @@ where
     ///
     pub fn new<T>(
         interface: Option<Arc<Interface<Ops>>>,
-        options: Options,
+        mut options: Options,
         config: Option<WebSocketConfig>,
     ) -> Result<RpcClient<Ops, Id>>
     where
@@ -378,9 +471,107 @@ where
     {
         let url = options.url.map(sanitize_url).transpose()?;
 
-        let ws = Arc::new(WebSocket::new(url.as_deref(), config)?);
-        let protocol: Arc<dyn ProtocolHandler<Ops>> = Arc::new(T::new(ws.clone(), interface));
-        let inner = Arc::new(Inner::new::<T>(ws, protocol.clone(), options)?);
+        let ws = Arc::new(WebSocket::new(url.as_deref(), config.clone())?);
+        let protocol: Arc<dyn ProtocolHandler<Ops>> =
+            Arc::new(T::new(Transport::new(ws.clone()), interface));
+
+        let negotiator = options.negotiation.take().map(|negotiation| {
+            let negotiator = Arc::new(Negotiator::new(protocol.encoding(), negotiation));
+            ws.configure(negotiator.configure(config));
+            negotiator
+        });
+
+        let inner = Arc::new(Inner::new::<T>(
+            ws.clone(),
+            Some(ws),
+            protocol.clone(),
+            options,
+            None,
+            negotiator,
+        )?);
+
+        let client = RpcClient::<Ops, Id> {
+            inner,
+            protocol: protocol.into(),
+            ops: PhantomData,
+            id: PhantomData,
+        };
+
+        Ok(client)
+    }
+
+    /// Create a client operating over the WebSocket
+    /// of the [`RpcMultiplexer`] owning the `channel`.
+    fn new_channel<T>(
+        channel: Channel,
+        interface: Option<Arc<Interface<Ops>>>,
+    ) -> Result<RpcClient<Ops, Id>>
+    where
+        T: ProtocolHandler<Ops> + Send + Sync + 'static,
+    {
+        let ws = channel.ws();
+        let transport = Transport::with_namespace(ws.clone(), channel.namespace());
+        let protocol: Arc<dyn ProtocolHandler<Ops>> = Arc::new(T::new(transport, interface));
+        let inner = Arc::new(Inner::new::<T>(
+            ws.clone(),
+            Some(ws),
+            protocol.clone(),
+            Options::default(),
+            Some(channel),
+            None,
+        )?);
+
+        let client = RpcClient::<Ops, Id> {
+            inner,
+            protocol: protocol.into(),
+            ops: PhantomData,
+            id: PhantomData,
+        };
+
+        Ok(client)
+    }
+
+    ///
+    /// Create new wRPC client operating over the supplied [`RpcTransport`]
+    /// (such as the `MessagePortTransport` in WASM) instead of a WebSocket.
+    /// Protocol negotiation ([`Options::with_negotiation()`]) and the
+    /// WebSocket-specific functions ([`RpcClient::set_url()`],
+    /// [`RpcClient::trigger_abort()`]) are not supported by such clients.
+    ///
+    pub fn new_with_transport(
+        encoding: Encoding,
+        interface: Option<Arc<Interface<Ops>>>,
+        options: Options,
+        transport: Arc<dyn RpcTransport>,
+    ) -> Result<RpcClient<Ops, Id>> {
+        match encoding {
+            Encoding::Borsh => {
+                Self::new_transport::<BorshProtocol<Ops, Id>>(interface, options, transport)
+            }
+            Encoding::SerdeJson => {
+                Self::new_transport::<JsonProtocol<Ops, Id>>(interface, options, transport)
+            }
+        }
+    }
+
+    fn new_transport<T>(
+        interface: Option<Arc<Interface<Ops>>>,
+        options: Options,
+        transport: Arc<dyn RpcTransport>,
+    ) -> Result<RpcClient<Ops, Id>>
+    where
+        T: ProtocolHandler<Ops> + Send + Sync + 'static,
+    {
+        let protocol: Arc<dyn ProtocolHandler<Ops>> =
+            Arc::new(T::new(Transport::new(transport.clone()), interface));
+        let inner = Arc::new(Inner::new::<T>(
+            transport,
+            None,
+            protocol.clone(),
+            options,
+            None,
+            None,
+        )?);
 
         let client = RpcClient::<Ops, Id> {
             inner,
@@ -392,32 +583,66 @@ where
         Ok(client)
     }
 
-    /// Connect to the target wRPC endpoint (websocket address)
+    /// Connect to the target wRPC endpoint (websocket address).
+    /// For clients created by the [`RpcMultiplexer`], this connects
+    /// the shared WebSocket (if it is not already connected).
+    /// When negotiating the protocol using a blocking connection,
+    /// this returns [`Error::ProtocolVersionMismatch`] if the server
+    /// supports none of the client protocol versions.
     pub async fn connect(&self, options: ConnectOptions) -> ConnectResult<Error> {
+        if let Some(channel) = &self.inner.channel {
+            return channel.connect(options).await;
+        }
+
         if !self.inner.is_running() {
             self.inner.start()?;
         }
-        Ok(self.inner.ws.connect(options).await?)
+
+        match (&self.inner.negotiator, &self.inner.ws) {
+            (Some(negotiator), Some(ws)) => negotiator.connect(ws, options).await,
+            _ => self.inner.transport.connect(options).await,
+        }
     }
 
-    /// Stop wRPC client services
+    /// Stop wRPC client services. For clients created by the
+    /// [`RpcMultiplexer`], this closes the channel; the shared
+    /// WebSocket is disconnected once the last channel is closed.
     pub async fn shutdown(&self) -> Result<()> {
-        self.inner.shutdown().await?;
+        if let Some(channel) = &self.inner.channel {
+            channel.close().await?;
+        } else {
+            self.inner.shutdown().await?;
+        }
         Ok(())
     }
 
+    /// Namespace of a client created by the [`RpcMultiplexer`]
+    pub fn namespace(&self) -> Option<&str> {
+        self.inner
+            .channel
+            .as_ref()
+            .map(|channel| channel.namespace())
+    }
+
     pub fn ctl_multiplexer(&self) -> &Option<Multiplexer<Ctl>> {
         &self.inner.ctl_multiplexer
     }
 
-    /// Test if the underlying WebSocket is currently open
+    /// Test if the underlying transport is currently open
+    /// (and the multiplexed channel, if any, is not closed)
     pub fn is_connected(&self) -> bool {
-        self.inner.ws.is_connected()
+        self.inner.transport.is_connected()
+            && self
+                .inner
+                .channel
+                .as_ref()
+                .is_none_or(|channel| channel.is_open())
     }
 
     /// Obtain the current URL of the underlying WebSocket
+    /// (`None` if the client operates over a custom transport)
     pub fn url(&self) -> Option<String> {
-        self.inner.ws.url()
+        self.inner.ws.as_ref().and_then(|ws| ws.url())
     }
 
     /// Change the URL of the underlying WebSocket
@@ -425,15 +650,36 @@ where
     /// Alternatively, the new URL can be supplied
     /// in the `connect()` method using [`ConnectOptions`].
     pub fn set_url(&self, url: &str) -> Result<()> {
-        self.inner.ws.set_url(url);
+        self.ws("set_url")?.set_url(url);
         Ok(())
     }
 
     /// Change the configuration of the underlying WebSocket.
     /// This method can be used to alter the configuration
-    /// for the next connection.
+    /// for the next connection (ignored by clients operating
+    /// over a custom transport).
     pub fn configure(&self, config: WebSocketConfig) {
-        self.inner.ws.configure(config);
+        let Some(ws) = &self.inner.ws else {
+            return;
+        };
+        match &self.inner.negotiator {
+            Some(negotiator) => ws.configure(negotiator.configure(Some(config))),
+            None => ws.configure(config),
+        }
+    }
+
+    /// Protocol version and capabilities negotiated with the server during
+    /// the last connection (if the client has been created with
+    /// [`Options::with_negotiation()`]).
+    pub fn negotiated(&self) -> Option<Negotiated> {
+        match &self.inner.channel {
+            Some(channel) => channel.negotiated(),
+            None => self
+                .inner
+                .negotiator
+                .as_ref()
+                .and_then(|negotiator| negotiator.negotiated()),
+        }
     }
 
     ///
@@ -480,17 +726,118 @@ where
             return Err(WebSocketError::NotConnected.into());
         }
 
+        if let Some(policy) = self.inner.dedup.policy(&op) {
+            return self.call_deduplicated(op, req, &policy).await;
+        }
+
         match &self.protocol {
             Protocol::Borsh(protocol) => Ok(protocol.request(op, req).await?),
             Protocol::Json(protocol) => Ok(protocol.request(op, req).await?),
         }
     }
 
+    async fn call_deduplicated<Req, Resp>(
+        &self,
+        op: Ops,
+        req: Req,
+        policy: &Idempotent,
+    ) -> Result<Resp>
+    where
+        Req: MsgT,
+        Resp: MsgT,
+    {
+        match &self.protocol {
+            Protocol::Borsh(protocol) => {
+                let payload = borsh::to_vec(&req).map_err(|_| Error::BorshSerialize)?;
+                let key = Key::new(Encoding::Borsh, op.clone(), payload.clone());
+                let protocol = protocol.clone();
+                let issue = move || {
+                    async move {
+                        let trace = TraceId::new();
+                        protocol
+                            .request_raw(op, payload, trace)
+                            .await
+                            .map(RawResponse::Borsh)
+                            .map_err(|err| err.with_trace(trace))
+                    }
+                    .boxed()
+                };
+                self.inner
+                    .dedup
+                    .call(key, policy, issue, |response| match response {
+                        RawResponse::Borsh(data) => BorshProtocol::<Ops, Id>::decode_response(data),
+                        RawResponse::Json(_) => Err(Error::ErrorDeserializingResponseData),
+                    })
+                    .await
+            }
+            Protocol::Json(protocol) => {
+                let payload = serde_json::to_value(req)?;
+                let key = Key::new(
+                    Encoding::SerdeJson,
+                    op.clone(),
+                    serde_json::to_vec(&payload)?,
+                );
+                let protocol = protocol.clone();
+                let issue = move || {
+                    async move {
+                        let trace = TraceId::new();
+                        protocol
+                            .request_raw(op, payload, trace)
+                            .await
+                            .map(RawResponse::Json)
+                            .map_err(|err| err.with_trace(trace))
+                    }
+                    .boxed()
+                };
+                self.inner
+                    .dedup
+                    .call(key, policy, issue, |response| match response {
+                        RawResponse::Json(data) => JsonProtocol::<Ops, Id>::decode_response(data),
+                        RawResponse::Borsh(_) => Err(Error::ErrorDeserializingResponseData),
+                    })
+                    .await
+            }
+        }
+    }
+
+    ///
+    /// Register `op` as idempotent: identical concurrent calls (same op
+    /// and serialized request) share a single request and, if the `policy`
+    /// has a TTL, repeat calls receive the cached response (see [`Idempotent`]).
+    /// Ops mutating the server state must not be registered.
+    ///
+    pub fn register_idempotent(&self, op: Ops, policy: Idempotent) {
+        self.inner.dedup.register(op, policy);
+    }
+
+    /// Remove the `op` registration, discarding its cached responses
+    pub fn unregister_idempotent(&self, op: &Ops) {
+        self.inner.dedup.unregister(op);
+    }
+
+    /// Discard cached responses of idempotent ops
+    /// (the cache is also cleared on disconnection)
+    pub fn clear_response_cache(&self) {
+        self.inner.dedup.clear_cache();
+    }
+
     /// Triggers a disconnection on the underlying WebSocket.
     /// This is intended for debug purposes only.
     /// Can be used to test application reconnection logic.
     pub fn trigger_abort(&self) -> Result<()> {
-        Ok(self.inner.ws.trigger_abort()?)
+        Ok(self.ws("trigger_abort")?.trigger_abort()?)
+    }
+
+    /// Stop responding to the heartbeat pings of the server
+    #[cfg(test)]
+    pub(crate) fn disable_heartbeat_responder(&self) {
+        self.inner
+            .heartbeat_responder
+            .store(false, Ordering::Relaxed);
+    }
+
+    fn ws(&self, op: &'static str) -> Result<&Arc<WebSocket>> {
+        self.inner.ws.as_ref().ok_or(Error::NotWebSocket(op))
     }
 }
 
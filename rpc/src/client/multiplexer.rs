@@ -0,0 +1,450 @@
+//!
+//! [`RpcMultiplexer`] - multiple logical RPC clients (channels)
+//! operating over a single shared WebSocket connection.
+//!
+
+use super::{BorshProtocol, ConnectOptions, ConnectResult, JsonProtocol, WebSocketConfig};
+use super::{Ctl, Error, Inner, Interface, Negotiated, Negotiator, Options, Result, RpcClient};
+use crate::heartbeat::Beat;
+use crate::imports::*;
+use crate::messages::envelope;
+use futures_util::select_biased;
+use workflow_core::{channel::Multiplexer, task::yield_now};
+
+/// Message dispatch interface of a multiplexed channel,
+/// retained by the multiplexer without generics.
+#[async_trait]
+trait ChannelHandler: Send + Sync + 'static {
+    async fn handle_message(&self, message: WebSocketMessage) -> Result<()>;
+    async fn handle_timeout(&self);
+    fn handle_open(&self);
+    async fn handle_close(&self);
+    /// Marks the channel as closed after its removal from the multiplexer
+    fn detach(&self);
+}
+
+#[async_trait]
+impl<Ops> ChannelHandler for Inner<Ops>
+where
+    Ops: OpsT,
+{
+    async fn handle_message(&self, message: WebSocketMessage) -> Result<()> {
+        self.protocol.handle_message(message).await
+    }
+
+    async fn handle_timeout(&self) {
+        let timeout = Duration::from_millis(self.timeout_duration.load(Ordering::Relaxed));
+        self.protocol.handle_timeout(timeout).await;
+    }
+
+    fn handle_open(&self) {
+        Inner::handle_open(self);
+    }
+
+    async fn handle_close(&self) {
+        Inner::handle_close(self).await;
+    }
+
+    fn detach(&self) {
+        if let Some(channel) = &self.channel {
+            channel.is_open.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Link between a client created by [`RpcMultiplexer::channel()`]
+/// and the multiplexer owning the WebSocket.
+pub(super) struct Channel {
+    namespace: String,
+    multiplexer: Arc<MultiplexerInner>,
+    is_open: AtomicBool,
+}
+
+impl Channel {
+    pub(super) fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub(super) fn ws(&self) -> Arc<WebSocket> {
+        self.multiplexer.ws.clone()
+    }
+
+    pub(super) fn is_open(&self) -> bool {
+        self.is_open.load(Ordering::SeqCst)
+    }
+
+    pub(super) async fn connect(&self, options: ConnectOptions) -> ConnectResult<Error> {
+        self.multiplexer.connect(options).await
+    }
+
+    pub(super) fn negotiated(&self) -> Option<Negotiated> {
+        self.multiplexer.negotiated()
+    }
+
+    pub(super) async fn close(&self) -> Result<()> {
+        if self.is_open.swap(false, Ordering::SeqCst) {
+            self.multiplexer.release(&self.namespace).await?;
+        }
+        Ok(())
+    }
+}
+
+struct MultiplexerInner {
+    ws: Arc<WebSocket>,
+    encoding: Encoding,
+    negotiator: Option<Arc<Negotiator>>,
+    channels: Mutex<AHashMap<String, Arc<dyn ChannelHandler>>>,
+    ctl_multiplexer: Option<Multiplexer<Ctl>>,
+    is_running: AtomicBool,
+    is_connecting: AtomicBool,
+    receiver_is_running: AtomicBool,
+    timeout_is_running: AtomicBool,
+    receiver_shutdown: DuplexChannel,
+    timeout_shutdown: DuplexChannel,
+    timeout_timer_interval: AtomicU64,
+}
+
+impl MultiplexerInner {
+    fn channels(&self) -> Vec<Arc<dyn ChannelHandler>> {
+        self.channels.lock().unwrap().values().cloned().collect()
+    }
+
+    fn start(self: &Arc<Self>) {
+        if !self.is_running.swap(true, Ordering::SeqCst) {
+            self.clone().timeout_task();
+            self.clone().receiver_task();
+        }
+    }
+
+    async fn connect(self: &Arc<Self>, options: ConnectOptions) -> ConnectResult<Error> {
+        self.start();
+        // the connection is initiated only once; the WebSocket
+        // reconnects on its own until it is disconnected
+        if self.is_connecting.swap(true, Ordering::SeqCst) {
+            return Ok(None);
+        }
+        let result = match &self.negotiator {
+            Some(negotiator) => negotiator.connect(&self.ws, options).await,
+            None => self.ws.connect(options).await.map_err(Error::from),
+        };
+        if result.is_err() {
+            self.is_connecting.store(false, Ordering::SeqCst);
+        }
+        result
+    }
+
+    fn negotiated(&self) -> Option<Negotiated> {
+        self.negotiator
+            .as_ref()
+            .and_then(|negotiator| negotiator.negotiated())
+    }
+
+    async fn shutdown(self: &Arc<Self>) -> Result<()> {
+        self.ws.disconnect().await?;
+        self.is_connecting.store(false, Ordering::SeqCst);
+        yield_now().await;
+        if self.is_running.load(Ordering::SeqCst) {
+            self.stop_timeout().await;
+            self.stop_receiver().await;
+            self.is_running.store(false, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    /// Removes the channel, failing its pending requests, and
+    /// disconnects the WebSocket if this was the last channel.
+    async fn release(self: &Arc<Self>, namespace: &str) -> Result<()> {
+        let (channel, is_last) = {
+            let mut channels = self.channels.lock().unwrap();
+            (channels.remove(namespace), channels.is_empty())
+        };
+
+        if let Some(channel) = channel {
+            channel.handle_close().await;
+            if is_last {
+                self.shutdown().await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn dispatch(&self, message: WebSocketMessage) -> Result<()> {
+        // heartbeat messages are not wrapped in the namespace envelope
+        if let Some(beat) = Beat::from_message(&message) {
+            if let Beat::Ping(seq) = beat {
+                let pong = Beat::Pong(seq).encode(self.encoding);
+                self.ws.post(pong.into()).await?;
+            }
+            return Ok(());
+        }
+
+        let (namespace, message) = match message {
+            WebSocketMessage::Binary(data) => {
+                let (namespace, payload) = envelope::unwrap_binary(&data)?;
+                (namespace, WebSocketMessage::Binary(payload.to_vec()))
+            }
+            WebSocketMessage::Text(text) => {
+                let (namespace, message) = envelope::unwrap_text(&text)?;
+                (namespace, WebSocketMessage::Text(message))
+            }
+            _ => return Err(Error::WebSocketMessageType),
+        };
+
+        let channel = self.channels.lock().unwrap().get(&namespace).cloned();
+        match channel {
+            Some(channel) => channel.handle_message(message).await,
+            None => Err(Error::UnknownNamespace(namespace)),
+        }
+    }
+
+    fn timeout_task(self: Arc<Self>) {
+        self.timeout_is_running.store(true, Ordering::SeqCst);
+        workflow_core::task::spawn(async move {
+            'outer: loop {
+                let timeout_timer_interval =
+                    Duration::from_millis(self.timeout_timer_interval.load(Ordering::SeqCst));
+                select_biased! {
+                    _ = workflow_core::task::sleep(timeout_timer_interval).fuse() => {
+                        for channel in self.channels() {
+                            channel.handle_timeout().await;
+                        }
+                    },
+                    _ = self.timeout_shutdown.request.receiver.recv().fuse() => {
+                        break 'outer;
+                    },
+                }
+            }
+
+            self.timeout_is_running.store(false, Ordering::SeqCst);
+            self.timeout_shutdown.response.sender.send(()).await.unwrap_or_else(|err|
+                log_error!("wRPC multiplexer - unable to signal shutdown completion for timeout task: `{err}`"));
+        });
+    }
+
+    fn receiver_task(self: Arc<Self>) {
+        self.receiver_is_running.store(true, Ordering::SeqCst);
+        let receiver_rx = self.ws.receiver_rx().clone();
+        workflow_core::task::spawn(async move {
+            'outer: loop {
+                select_biased! {
+                    msg = receiver_rx.recv().fuse() => {
+                        match msg {
+                            Ok(WebSocketMessage::Open) => {
+                                for channel in self.channels() {
+                                    channel.handle_open();
+                                }
+                                if let Some(ctl_channel) = &self.ctl_multiplexer {
+                                    ctl_channel.try_broadcast(Ctl::Connect).expect("ctl_channel.try_broadcast(Ctl::Connect)");
+                                }
+                            }
+                            Ok(WebSocketMessage::Close) => {
+                                for channel in self.channels() {
+                                    channel.handle_close().await;
+                                }
+                                if let Some(ctl_channel) = &self.ctl_multiplexer {
+                                    ctl_channel.try_broadcast(Ctl::Disconnect).expect("ctl_channel.try_broadcast(Ctl::Disconnect)");
+                                }
+                            }
+                            Ok(msg) => {
+                                self.dispatch(msg).await
+                                    .unwrap_or_else(|err| log_trace!("wRPC error: `{err}`"));
+                            }
+                            Err(err) => {
+                                log_error!("wRPC multiplexer receiver channel error: {err}");
+                                break 'outer;
+                            }
+                        }
+                    },
+                    _ = self.receiver_shutdown.request.receiver.recv().fuse() => {
+                        break 'outer;
+                    },
+                }
+            }
+
+            self.receiver_is_running.store(false, Ordering::SeqCst);
+            self.receiver_shutdown.response.sender.send(()).await.unwrap_or_else(|err|
+                log_error!("wRPC multiplexer - unable to signal shutdown completion for receiver task: `{err}`")
+            );
+        });
+    }
+
+    async fn stop_receiver(&self) {
+        if self.receiver_is_running.load(Ordering::SeqCst) {
+            self.receiver_shutdown
+                .signal(())
+                .await
+                .unwrap_or_else(|err| {
+                    log_error!("wRPC multiplexer unable to signal receiver shutdown: `{err}`")
+                });
+        }
+    }
+
+    async fn stop_timeout(&self) {
+        if self.timeout_is_running.load(Ordering::SeqCst) {
+            self.timeout_shutdown
+                .signal(())
+                .await
+                .unwrap_or_else(|err| {
+                    log_error!("wRPC multiplexer unable to signal timeout shutdown: `{err}`")
+                });
+        }
+    }
+}
+
+///
+/// [`RpcMultiplexer`] owns a single WebSocket connection shared by
+/// multiple logical [`RpcClient`] instances (channels), each identified
+/// by a namespace. Messages of each channel are wrapped in an envelope
+/// carrying the namespace and are dispatched on the server by the
+/// [`Router`](crate::server::Router) to the `Interface` registered
+/// for that namespace.
+///
+/// ```ignore
+/// let multiplexer = RpcMultiplexer::new(Encoding::Borsh, RpcClientOptions::new().with_url(url), None)?;
+/// let wallet = multiplexer.channel::<WalletOps, Id64>("wallet", wallet_interface.into())?;
+/// let node = multiplexer.channel::<NodeOps, Id64>("node", None)?;
+/// multiplexer.connect(ConnectOptions::blocking_fallback()).await?;
+///
+/// let balance: Balance = wallet.call(WalletOps::Balance, BalanceRequest { }).await?;
+/// ```
+///
+/// Calling [`RpcClient::shutdown()`] on a channel closes only that channel;
+/// the WebSocket is disconnected once the last channel is closed.
+///
+#[derive(Clone)]
+pub struct RpcMultiplexer {
+    inner: Arc<MultiplexerInner>,
+}
+
+impl RpcMultiplexer {
+    /// Create a new multiplexer using the supplied [`Encoding`] for all
+    /// channels. The `ctl_multiplexer` supplied via [`Options`] receives
+    /// connection events of the shared WebSocket.
+    pub fn new(
+        encoding: Encoding,
+        options: Options,
+        config: Option<WebSocketConfig>,
+    ) -> Result<RpcMultiplexer> {
+        let url = options.url.map(super::sanitize_url).transpose()?;
+        let negotiator = options
+            .negotiation
+            .map(|negotiation| Arc::new(Negotiator::new(encoding, negotiation)));
+        let config = match &negotiator {
+            Some(negotiator) => Some(negotiator.configure(config)),
+            None => config,
+        };
+        let ws = Arc::new(WebSocket::new(url.as_deref(), config)?);
+
+        let inner = MultiplexerInner {
+            ws,
+            encoding,
+            negotiator,
+            channels: Mutex::new(AHashMap::new()),
+            ctl_multiplexer: options.ctl_multiplexer,
+            is_running: AtomicBool::new(false),
+            is_connecting: AtomicBool::new(false),
+            receiver_is_running: AtomicBool::new(false),
+            timeout_is_running: AtomicBool::new(false),
+            receiver_shutdown: DuplexChannel::oneshot(),
+            timeout_shutdown: DuplexChannel::oneshot(),
+            timeout_timer_interval: AtomicU64::new(5_000),
+        };
+
+        Ok(RpcMultiplexer {
+            inner: Arc::new(inner),
+        })
+    }
+
+    /// Create a logical [`RpcClient`] for the `namespace`. Server notifications
+    /// posted to this namespace are dispatched to the supplied `interface`.
+    pub fn channel<Ops, Id>(
+        &self,
+        namespace: &str,
+        interface: Option<Arc<Interface<Ops>>>,
+    ) -> Result<RpcClient<Ops, Id>>
+    where
+        Ops: OpsT,
+        Id: IdT,
+    {
+        let mut channels = self.inner.channels.lock().unwrap();
+        if channels.contains_key(namespace) {
+            return Err(Error::DuplicateNamespace(namespace.to_string()));
+        }
+
+        let channel = Channel {
+            namespace: namespace.to_string(),
+            multiplexer: self.inner.clone(),
+            is_open: AtomicBool::new(true),
+        };
+
+        let client = match self.inner.encoding {
+            Encoding::Borsh => {
+                RpcClient::<Ops, Id>::new_channel::<BorshProtocol<Ops, Id>>(channel, interface)?
+            }
+            Encoding::SerdeJson => {
+                RpcClient::<Ops, Id>::new_channel::<JsonProtocol<Ops, Id>>(channel, interface)?
+            }
+        };
+
+        if self.inner.ws.is_connected() {
+            client.inner.handle_open();
+        }
+        channels.insert(namespace.to_string(), client.inner.clone());
+
+        Ok(client)
+    }
+
+    /// Connect the shared WebSocket. Subsequent calls (including
+    /// [`RpcClient::connect()`] on channels) have no effect until
+    /// the multiplexer is shut down.
+    pub async fn connect(&self, options: ConnectOptions) -> ConnectResult<Error> {
+        self.inner.connect(options).await
+    }
+
+    /// Close all channels and disconnect the shared WebSocket.
+    pub async fn shutdown(&self) -> Result<()> {
+        let channels = std::mem::take(&mut *self.inner.channels.lock().unwrap());
+        for channel in channels.into_values() {
+            channel.detach();
+            channel.handle_close().await;
+        }
+        self.inner.shutdown().await
+    }
+
+    /// Namespaces of the currently open channels
+    pub fn namespaces(&self) -> Vec<String> {
+        self.inner
+            .channels
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.inner.encoding
+    }
+
+    /// Protocol version and capabilities negotiated with the server
+    /// during the last connection (see [`Options::with_negotiation()`])
+    pub fn negotiated(&self) -> Option<Negotiated> {
+        self.inner.negotiated()
+    }
+
+    /// Test if the shared WebSocket is currently open
+    pub fn is_connected(&self) -> bool {
+        self.inner.ws.is_connected()
+    }
+
+    /// Obtain the current URL of the shared WebSocket
+    pub fn url(&self) -> Option<String> {
+        self.inner.ws.url()
+    }
+
+    /// Change the URL of the shared WebSocket
+    /// (applicable only to the next connection).
+    pub fn set_url(&self, url: &str) {
+        self.inner.ws.set_url(url);
+    }
+}
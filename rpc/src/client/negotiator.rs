@@ -0,0 +1,127 @@
+//!
+//! Client-side protocol negotiation (see [`crate::negotiation`]),
+//! performed as the [`Handshake`] of the underlying WebSocket.
+//!
+
+use super::{ConnectOptions, ConnectResult, Error, Result, WebSocketConfig, WebSocketError};
+use crate::heartbeat::HEARTBEAT_CAPABILITY;
+use crate::imports::*;
+use crate::negotiation::*;
+use workflow_core::channel::{Receiver, Sender};
+use workflow_websocket::client::{Handshake, Result as WebSocketResult};
+
+pub(super) struct Negotiator {
+    encoding: Encoding,
+    negotiation: Negotiation,
+    negotiated: Mutex<Option<Negotiated>>,
+    /// Receives the outcome of the negotiation during a blocking connect
+    listener: Mutex<Option<Sender<Result<Negotiated>>>>,
+}
+
+impl Negotiator {
+    /// The [`HEARTBEAT_CAPABILITY`] is advertised by all clients
+    /// (heartbeat pings are answered by the client receiver task).
+    pub(super) fn new(encoding: Encoding, negotiation: Negotiation) -> Self {
+        Self {
+            encoding,
+            negotiation: negotiation.with_capability(HEARTBEAT_CAPABILITY),
+            negotiated: Mutex::new(None),
+            listener: Mutex::new(None),
+        }
+    }
+
+    pub(super) fn negotiated(&self) -> Option<Negotiated> {
+        self.negotiated.lock().unwrap().clone()
+    }
+
+    /// Install the negotiator as the handshake of the WebSocket `config`
+    pub(super) fn configure(self: &Arc<Self>, config: Option<WebSocketConfig>) -> WebSocketConfig {
+        let mut config = config.unwrap_or_default();
+        config.handshake = Some(self.clone());
+        config
+    }
+
+    /// Connect the WebSocket. If the connection is blocking, this
+    /// waits for the negotiation and disconnects the WebSocket if
+    /// the negotiation fails.
+    pub(super) async fn connect(
+        &self,
+        ws: &WebSocket,
+        options: ConnectOptions,
+    ) -> ConnectResult<Error> {
+        if !options.block_async_connect {
+            return Ok(ws.connect(options).await?);
+        }
+
+        let (sender, receiver) = oneshot();
+        self.listener.lock().unwrap().replace(sender);
+        if let Err(err) = ws.connect(options).await {
+            self.listener.lock().unwrap().take();
+            return Err(err.into());
+        }
+        match receiver.recv().await? {
+            Ok(_) => Ok(None),
+            Err(err) => {
+                ws.disconnect().await?;
+                Err(err)
+            }
+        }
+    }
+
+    async fn negotiate(
+        &self,
+        sender: &Sender<WebSocketMessage>,
+        receiver: &Receiver<WebSocketMessage>,
+    ) -> Result<Negotiated> {
+        let hello = Hello {
+            versions: self.negotiation.versions(),
+            capabilities: self.negotiation.capabilities().to_vec(),
+        };
+        sender.send(encode(self.encoding, &hello)?.into()).await?;
+
+        let welcome: Welcome = match receiver.recv().await? {
+            WebSocketMessage::Binary(data) => decode(self.encoding, &data)?,
+            WebSocketMessage::Text(text) => decode(self.encoding, text.as_bytes())?,
+            _ => return Err(Error::WebSocketMessageType),
+        };
+
+        match welcome {
+            Welcome::Accept(negotiated) => Ok(negotiated),
+            Welcome::Reject { server } => Err(Error::ProtocolVersionMismatch {
+                client: hello.versions,
+                server,
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl Handshake for Negotiator {
+    async fn handshake(
+        &self,
+        sender: &Sender<WebSocketMessage>,
+        receiver: &Receiver<WebSocketMessage>,
+    ) -> WebSocketResult<()> {
+        let result = self.negotiate(sender, receiver).await;
+        *self.negotiated.lock().unwrap() = result.as_ref().ok().cloned();
+        let accepted = result.is_ok();
+
+        let listener = self.listener.lock().unwrap().take();
+        match listener {
+            Some(listener) => {
+                listener.try_send(result).ok();
+            }
+            None => {
+                if let Err(err) = result {
+                    log_warn!("wRPC protocol negotiation failure: {err}");
+                }
+            }
+        }
+
+        if accepted {
+            Ok(())
+        } else {
+            Err(WebSocketError::NegotiationFailure)
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//!
+//! [`MessagePortTransport`] - [`RpcTransport`] connecting the [`RpcClient`](super::RpcClient)
+//! to a [`PortServer`](crate::server::PortServer) located in another browser context
+//! (e.g. a Web Worker) over a `MessagePort` (WASM only).
+//!
+
+use super::{ConnectOptions, ConnectResult, Error, Result, RpcTransport, WebSocketError};
+use crate::imports::*;
+use crate::port::Port;
+use web_sys::MessagePort;
+use workflow_core::channel::{Channel, Receiver};
+
+/// [`RpcTransport`] operating over a `MessagePort`, typically one end of a
+/// `MessageChannel` whose other end has been transferred to a Web Worker
+/// serving the RPC [`Interface`](crate::server::Interface) via the
+/// [`PortServer`](crate::server::PortServer).
+///
+/// ```ignore
+/// let channel = web_sys::MessageChannel::new()?;
+/// worker.post_message_with_transfer(&channel.port2(), &js_sys::Array::of1(&channel.port2()))?;
+/// let transport = Arc::new(MessagePortTransport::new(channel.port1()));
+/// let rpc = RpcClient::<Ops>::new_with_transport(Encoding::Borsh, None, Options::default(), transport)?;
+/// rpc.connect(ConnectOptions::default()).await?;
+/// ```
+///
+/// The connection is opened by [`RpcClient::connect()`](super::RpcClient::connect);
+/// a blocking connect waits for the server to acknowledge the connection
+/// (within the `connect_timeout` of the [`ConnectOptions`]). Reconnection
+/// strategies and URLs supplied in the [`ConnectOptions`] are not applicable.
+pub struct MessagePortTransport {
+    port: Port,
+    channel: Channel<WebSocketMessage>,
+    is_connected: Arc<AtomicBool>,
+    ack: Channel<()>,
+}
+
+impl MessagePortTransport {
+    pub fn new(port: MessagePort) -> Self {
+        let inbound = Channel::unbounded();
+        let channel = Channel::unbounded();
+        let ack = Channel::unbounded();
+        let transport = Self {
+            port: Port::new(port, inbound.sender.clone()),
+            channel,
+            is_connected: Arc::new(AtomicBool::new(false)),
+            ack,
+        };
+        transport.relay(inbound.receiver);
+        transport
+    }
+
+    /// Relay the inbound messages to the client, tracking the connection state.
+    fn relay(&self, inbound: Receiver<WebSocketMessage>) {
+        let sender = self.channel.sender.clone();
+        let ack = self.ack.sender.clone();
+        let is_connected = self.is_connected.clone();
+        workflow_core::task::spawn(async move {
+            while let Ok(message) = inbound.recv().await {
+                match message {
+                    WebSocketMessage::Open => {
+                        ack.try_send(()).ok();
+                    }
+                    WebSocketMessage::Close => {
+                        // closed by the server
+                        if !is_connected.swap(false, Ordering::SeqCst) {
+                            continue;
+                        }
+                    }
+                    _ => {}
+                }
+                if sender.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl RpcTransport for MessagePortTransport {
+    async fn connect(&self, options: ConnectOptions) -> ConnectResult<Error> {
+        if self.is_connected() {
+            return Ok(None);
+        }
+
+        while self.ack.try_recv().is_ok() {}
+        self.port
+            .post(WebSocketMessage::Open)
+            .map_err(Error::Port)?;
+
+        if options.block_async_connect {
+            let timeout = options.connect_timeout();
+            futures::select! {
+                ack = self.ack.recv().fuse() => {
+                    ack.map_err(|_| Error::Disconnect)?;
+                }
+                _ = workflow_core::task::sleep(timeout).fuse() => {
+                    return Err(WebSocketError::ConnectionTimeout.into());
+                }
+            }
+        }
+
+        // `Open` is relayed to the client by the server acknowledgement
+        self.is_connected.store(true, Ordering::SeqCst);
+        Ok(None)
+    }
+
+    async fn disconnect(&self) -> Result<()> {
+        if self.is_connected.swap(false, Ordering::SeqCst) {
+            self.port
+                .post(WebSocketMessage::Close)
+                .map_err(Error::Port)?;
+            self.channel.sender.send(WebSocketMessage::Close).await?;
+        }
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.is_connected.load(Ordering::SeqCst)
+    }
+
+    async fn post(&self, message: WebSocketMessage) -> Result<()> {
+        if !self.is_connected() {
+            return Err(WebSocketError::NotConnected.into());
+        }
+        self.port.post(message).map_err(Error::Port)
+    }
+
+    fn receiver_rx(&self) -> &Receiver<WebSocketMessage> {
+        &self.channel.receiver
+    }
+}
@@ -3,6 +3,7 @@
 //!
 pub use crate::client::{
     notification, result::Result as ClientResult, BorshProtocol, ConnectOptions, ConnectStrategy,
-    Interface, JsonProtocol, Options as RpcClientOptions, RpcClient,
+    Dispatch, Idempotent, Interface, JsonProtocol, Options as RpcClientOptions, RpcClient,
+    RpcMultiplexer, RpcTransport,
 };
 pub use crate::encoding::Encoding;
@@ -1,4 +1,4 @@
-use super::{Pending, PendingMap, ProtocolHandler};
+use super::{Pending, PendingMap, ProtocolHandler, Transport};
 pub use crate::client::error::Error;
 pub use crate::client::result::Result;
 use crate::client::Interface;
@@ -15,7 +15,7 @@ where
     Ops: OpsT,
     Id: IdT,
 {
-    ws: Arc<WebSocket>,
+    transport: Transport,
     pending: PendingMap<Id, BorshResponseFn>,
     interface: Option<Arc<Interface<Ops>>>,
     ops: PhantomData<Ops>,
@@ -27,9 +27,9 @@ where
     Ops: OpsT,
     Id: IdT,
 {
-    fn new(ws: Arc<WebSocket>, interface: Option<Arc<Interface<Ops>>>) -> Self {
+    fn new(transport: Transport, interface: Option<Arc<Interface<Ops>>>) -> Self {
         BorshProtocol {
-            ws,
+            transport,
             pending: Arc::new(Mutex::new(AHashMap::new())),
             interface,
             ops: PhantomData,
@@ -38,7 +38,7 @@ where
     }
 }
 
-type MessageInfo<'l, Ops, Id> = (Option<Id>, Option<Ops>, Result<&'l [u8]>);
+type MessageInfo<'l, Ops, Id> = (Option<Id>, Option<Ops>, Option<TraceId>, Result<&'l [u8]>);
 
 impl<Ops, Id> BorshProtocol<Ops, Id>
 where
@@ -51,17 +51,24 @@ where
                 let header = msg.header;
                 match header.kind {
                     ServerMessageKind::Success => {
-                        Ok((header.id, header.op, Ok(msg.payload)))
+                        Ok((header.id, header.op, header.trace, Ok(msg.payload)))
                         // Ok((Some(header.id), header.op.clone(), Ok(msg.data)))
                     }
                     ServerMessageKind::Error => {
                         if let Ok(err) = ServerError::try_from_slice(msg.payload) {
-                            Ok((header.id, None, Err(Error::RpcCall(err))))
+                            Ok((header.id, None, header.trace, Err(Error::RpcCall(err))))
                         } else {
-                            Ok((header.id, None, Err(Error::ErrorDeserializingResponseData)))
+                            Ok((
+                                header.id,
+                                None,
+                                header.trace,
+                                Err(Error::ErrorDeserializingResponseData),
+                            ))
                         }
                     }
-                    ServerMessageKind::Notification => Ok((None, header.op, Ok(msg.payload))),
+                    ServerMessageKind::Notification => {
+                        Ok((None, header.op, header.trace, Ok(msg.payload)))
+                    }
                 }
             }
             Err(err) => Err(ServerError::RespDeserialize(err.to_string())),
@@ -74,7 +81,20 @@ where
         Resp: MsgT,
     {
         let payload = borsh::to_vec(&req).map_err(|_| Error::BorshSerialize)?;
+        let trace = TraceId::new();
+        self.request_raw(op, payload, trace)
+            .await
+            .and_then(|data| Self::decode_response(&data))
+            .map_err(|err| err.with_trace(trace))
+    }
 
+    /// Post the serialized request, returning the undecoded response data
+    pub(crate) async fn request_raw(
+        &self,
+        op: Ops,
+        payload: Vec<u8>,
+        trace: TraceId,
+    ) -> Result<Vec<u8>> {
         let id = Id::generate();
         let (sender, receiver) = oneshot();
 
@@ -90,12 +110,21 @@ where
         }
 
         // TODO - post error into sender if ws.send() fails
-        self.ws
-            .post(to_ws_msg(BorshReqHeader::new(Some(id), op), &payload))
+        self.transport
+            .post(to_ws_msg(
+                BorshReqHeader::new(Some(id), op).with_trace(Some(trace)),
+                &payload,
+            ))
             .await?;
 
-        let data = receiver.recv().await??;
-        let resp = ServerResult::<Resp>::try_from_slice(data.as_ref())
+        receiver.recv().await?
+    }
+
+    pub(crate) fn decode_response<Resp>(data: &[u8]) -> Result<Resp>
+    where
+        Resp: MsgT,
+    {
+        let resp = ServerResult::<Resp>::try_from_slice(data)
             .map_err(|e| Error::BorshDeserialize(e.to_string()))?;
 
         Ok(resp?)
@@ -106,7 +135,7 @@ where
         Msg: BorshSerialize + Send + Sync + 'static,
     {
         let payload = borsh::to_vec(&payload).map_err(|_| Error::BorshSerialize)?;
-        self.ws
+        self.transport
             .post(to_ws_msg(
                 BorshReqHeader::<Ops, Id>::new(None, op),
                 &payload,
@@ -115,10 +144,15 @@ where
         Ok(())
     }
 
-    async fn handle_notification(&self, op: &Ops, payload: &[u8]) -> Result<()> {
+    async fn handle_notification(
+        &self,
+        op: &Ops,
+        trace: Option<TraceId>,
+        payload: &[u8],
+    ) -> Result<()> {
         if let Some(interface) = &self.interface {
             interface
-                .call_notification_with_borsh(op, payload)
+                .call_notification_with_borsh(op, trace, payload)
                 .await
                 .unwrap_or_else(|err| log_trace!("error handling server notification {}", err));
         } else {
@@ -135,11 +169,15 @@ where
     Id: IdT,
     Ops: OpsT,
 {
-    fn new(ws: Arc<WebSocket>, interface: Option<Arc<Interface<Ops>>>) -> Self
+    fn new(transport: Transport, interface: Option<Arc<Interface<Ops>>>) -> Self
     where
         Self: Sized,
     {
-        BorshProtocol::new(ws, interface)
+        BorshProtocol::new(transport, interface)
+    }
+
+    fn encoding(&self) -> Encoding {
+        Encoding::Borsh
     }
 
     async fn handle_timeout(&self, timeout: Duration) {
@@ -167,7 +205,7 @@ where
 
     async fn handle_message(&self, message: WebSocketMessage) -> Result<()> {
         if let WebSocketMessage::Binary(server_message) = message {
-            let (id, op, result) = self.decode(server_message.as_slice())?;
+            let (id, op, trace, result) = self.decode(server_message.as_slice())?;
             if let Some(id) = id {
                 if let Some(pending) = self.pending.lock().unwrap().remove(&id) {
                     (pending.callback)(result, Some(&pending.timestamp.elapsed()))
@@ -176,7 +214,7 @@ where
                 }
             } else if let Some(op) = op {
                 match result {
-                    Ok(data) => self.handle_notification(&op, data).await,
+                    Ok(data) => self.handle_notification(&op, trace, data).await,
                     _ => Ok(()),
                 }
             } else {
@@ -7,16 +7,56 @@ use crate::imports::*;
 
 pub use self::borsh::BorshProtocol;
 pub use self::serde_json::JsonProtocol;
-use crate::client::Interface;
+use crate::client::{Interface, RpcTransport};
+use crate::messages::envelope;
+
+/// Outbound path of a protocol handler. Messages of clients
+/// created by the [`RpcMultiplexer`](crate::client::RpcMultiplexer)
+/// are wrapped in the namespace envelope.
+#[derive(Clone)]
+pub struct Transport {
+    transport: Arc<dyn RpcTransport>,
+    namespace: Option<Arc<str>>,
+}
+
+impl Transport {
+    pub fn new(transport: Arc<dyn RpcTransport>) -> Self {
+        Self {
+            transport,
+            namespace: None,
+        }
+    }
+
+    pub fn with_namespace(transport: Arc<dyn RpcTransport>, namespace: &str) -> Self {
+        Self {
+            transport,
+            namespace: Some(namespace.into()),
+        }
+    }
+
+    pub async fn post(&self, message: WebSocketMessage) -> Result<()> {
+        let message = match (&self.namespace, message) {
+            (Some(namespace), WebSocketMessage::Binary(data)) => {
+                WebSocketMessage::Binary(envelope::wrap_binary(namespace, &data))
+            }
+            (Some(namespace), WebSocketMessage::Text(text)) => {
+                WebSocketMessage::Text(envelope::wrap_text(namespace, &text)?)
+            }
+            (_, message) => message,
+        };
+        self.transport.post(message).await
+    }
+}
 
 #[async_trait]
 pub trait ProtocolHandler<Ops>: DowncastSync
 where
     Ops: OpsT,
 {
-    fn new(ws: Arc<WebSocket>, interface: Option<Arc<Interface<Ops>>>) -> Self
+    fn new(transport: Transport, interface: Option<Arc<Interface<Ops>>>) -> Self
     where
         Self: Sized;
+    fn encoding(&self) -> Encoding;
     async fn handle_timeout(&self, timeout: Duration);
     async fn handle_message(&self, message: WebSocketMessage) -> Result<()>;
     async fn handle_disconnect(&self) -> Result<()>;
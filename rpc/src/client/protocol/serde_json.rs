@@ -1,6 +1,6 @@
 use core::marker::PhantomData;
 
-use super::{Pending, PendingMap, ProtocolHandler};
+use super::{Pending, PendingMap, ProtocolHandler, Transport};
 pub use crate::client::error::Error;
 pub use crate::client::result::Result;
 use crate::client::Interface;
@@ -16,7 +16,7 @@ where
     Ops: OpsT,
     Id: IdT,
 {
-    ws: Arc<WebSocket>,
+    transport: Transport,
     pending: PendingMap<Id, JsonResponseFn>,
     interface: Option<Arc<Interface<Ops>>>,
     // ops: PhantomData<Ops>,
@@ -28,9 +28,9 @@ where
     Id: IdT,
     Ops: OpsT,
 {
-    fn new(ws: Arc<WebSocket>, interface: Option<Arc<Interface<Ops>>>) -> Self {
+    fn new(transport: Transport, interface: Option<Arc<Interface<Ops>>>) -> Self {
         JsonProtocol::<Ops, Id> {
-            ws,
+            transport,
             pending: Arc::new(Mutex::new(AHashMap::new())),
             interface,
             // ops: PhantomData,
@@ -39,7 +39,7 @@ where
     }
 }
 
-type MessageInfo<Ops, Id> = (Option<Id>, Option<Ops>, Result<Value>);
+type MessageInfo<Ops, Id> = (Option<Id>, Option<Ops>, Option<TraceId>, Result<Value>);
 
 impl<Ops, Id> JsonProtocol<Ops, Id>
 where
@@ -50,17 +50,17 @@ where
         let msg: JSONServerMessage<Ops, Id> = serde_json::from_str(server_message)?;
 
         if let Some(error) = msg.error {
-            Ok((msg.id, None, Err(error.into())))
+            Ok((msg.id, None, msg.trace, Err(error.into())))
         } else if msg.id.is_some() {
             if let Some(result) = msg.params {
-                Ok((msg.id, None, Ok(result)))
+                Ok((msg.id, None, msg.trace, Ok(result)))
             } else {
-                Ok((msg.id, None, Err(Error::NoDataInSuccessResponse)))
+                Ok((msg.id, None, msg.trace, Err(Error::NoDataInSuccessResponse)))
             }
         } else if let Some(params) = msg.params {
-            Ok((None, msg.method, Ok(params)))
+            Ok((None, msg.method, msg.trace, Ok(params)))
         } else {
-            Ok((None, None, Err(Error::NoDataInNotificationMessage)))
+            Ok((None, None, None, Err(Error::NoDataInNotificationMessage)))
         }
     }
 
@@ -69,6 +69,24 @@ where
         Req: MsgT,
         Resp: MsgT,
     {
+        let payload = serde_json::to_value(req)?;
+        let trace = TraceId::new();
+        self.request_raw(op, payload, trace)
+            .await
+            .and_then(|data| {
+                <Resp as Deserialize>::deserialize(data)
+                    .map_err(|e| Error::SerdeDeserialize(e.to_string()))
+            })
+            .map_err(|err| err.with_trace(trace))
+    }
+
+    /// Post the serialized request, returning the undecoded response data
+    pub(crate) async fn request_raw(
+        &self,
+        op: Ops,
+        payload: Value,
+        trace: TraceId,
+    ) -> Result<Value> {
         let id = Id::generate();
         let (sender, receiver) = oneshot();
 
@@ -83,17 +101,19 @@ where
             );
         }
 
-        let payload = serde_json::to_value(req)?;
-        let client_message = JsonClientMessage::new(Some(id), op, payload);
+        let client_message = JsonClientMessage::new(Some(id), op, payload).with_trace(Some(trace));
         let json = serde_json::to_string(&client_message)?;
 
-        self.ws.post(WebSocketMessage::Text(json)).await?;
+        self.transport.post(WebSocketMessage::Text(json)).await?;
 
-        let data = receiver.recv().await??;
+        receiver.recv().await?
+    }
 
-        let resp = <Resp as Deserialize>::deserialize(data)
-            .map_err(|e| Error::SerdeDeserialize(e.to_string()))?;
-        Ok(resp)
+    pub(crate) fn decode_response<Resp>(data: &Value) -> Result<Resp>
+    where
+        Resp: MsgT,
+    {
+        <Resp as Deserialize>::deserialize(data).map_err(|e| Error::SerdeDeserialize(e.to_string()))
     }
 
     pub async fn notify<Msg>(&self, op: Ops, data: Msg) -> Result<()>
@@ -103,14 +123,19 @@ where
         let payload = serde_json::to_value(data)?;
         let client_message = JsonClientMessage::<Ops, Id>::new(None, op, payload);
         let json = serde_json::to_string(&client_message)?;
-        self.ws.post(WebSocketMessage::Text(json)).await?;
+        self.transport.post(WebSocketMessage::Text(json)).await?;
         Ok(())
     }
 
-    async fn handle_notification(&self, op: Ops, payload: Value) -> Result<()> {
+    async fn handle_notification(
+        &self,
+        op: Ops,
+        trace: Option<TraceId>,
+        payload: Value,
+    ) -> Result<()> {
         if let Some(interface) = &self.interface {
             interface
-                .call_notification_with_serde_json(&op, payload)
+                .call_notification_with_serde_json(&op, trace, payload)
                 .await
                 .unwrap_or_else(|err| log_trace!("error handling server notification {}", err));
         } else {
@@ -127,11 +152,15 @@ where
     Ops: OpsT,
     Id: IdT,
 {
-    fn new(ws: Arc<WebSocket>, interface: Option<Arc<Interface<Ops>>>) -> Self
+    fn new(transport: Transport, interface: Option<Arc<Interface<Ops>>>) -> Self
     where
         Self: Sized,
     {
-        JsonProtocol::new(ws, interface)
+        JsonProtocol::new(transport, interface)
+    }
+
+    fn encoding(&self) -> Encoding {
+        Encoding::SerdeJson
     }
 
     async fn handle_timeout(&self, timeout: Duration) {
@@ -149,7 +178,7 @@ where
 
     async fn handle_message(&self, message: WebSocketMessage) -> Result<()> {
         if let WebSocketMessage::Text(server_message) = message {
-            let (id, method, result) = self.decode(server_message.as_str())?;
+            let (id, method, trace, result) = self.decode(server_message.as_str())?;
             if let Some(id) = id {
                 if let Some(pending) = self.pending.lock().unwrap().remove(&id) {
                     (pending.callback)(result, Some(&pending.timestamp.elapsed()))
@@ -158,7 +187,7 @@ where
                 }
             } else if let Some(method) = method {
                 match result {
-                    Ok(data) => self.handle_notification(method, data).await,
+                    Ok(data) => self.handle_notification(method, trace, data).await,
                     _ => Ok(()),
                 }
             } else {
@@ -0,0 +1,51 @@
+//!
+//! [`RpcTransport`] - the connection used by the [`RpcClient`](super::RpcClient)
+//! to exchange messages with the server. Implemented by the [`WebSocket`]
+//! and (in WASM) by the [`MessagePortTransport`](super::MessagePortTransport).
+//!
+
+use super::{ConnectOptions, ConnectResult, Error, Result};
+use crate::imports::*;
+use workflow_core::channel::Receiver;
+
+/// Message transport of the RPC client. Inbound messages, as well as
+/// the connection lifecycle ([`WebSocketMessage::Open`] and
+/// [`WebSocketMessage::Close`]), are delivered via [`RpcTransport::receiver_rx()`].
+#[async_trait]
+pub trait RpcTransport: Send + Sync + 'static {
+    /// Open the connection
+    async fn connect(&self, options: ConnectOptions) -> ConnectResult<Error>;
+    /// Close the connection
+    async fn disconnect(&self) -> Result<()>;
+    /// Test if the connection is currently open
+    fn is_connected(&self) -> bool;
+    /// Post a [`WebSocketMessage::Binary`] or [`WebSocketMessage::Text`] message
+    async fn post(&self, message: WebSocketMessage) -> Result<()>;
+    /// Channel receiving inbound messages and connection events
+    fn receiver_rx(&self) -> &Receiver<WebSocketMessage>;
+}
+
+#[async_trait]
+impl RpcTransport for WebSocket {
+    async fn connect(&self, options: ConnectOptions) -> ConnectResult<Error> {
+        Ok(WebSocket::connect(self, options).await?)
+    }
+
+    async fn disconnect(&self) -> Result<()> {
+        WebSocket::disconnect(self).await?;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        WebSocket::is_connected(self)
+    }
+
+    async fn post(&self, message: WebSocketMessage) -> Result<()> {
+        WebSocket::post(self, message).await?;
+        Ok(())
+    }
+
+    fn receiver_rx(&self) -> &Receiver<WebSocketMessage> {
+        WebSocket::receiver_rx(self)
+    }
+}
@@ -0,0 +1,507 @@
+//!
+//! JavaScript/TypeScript facade of the [`RpcClient`] (`wasm32-sdk` feature).
+//!
+//! `#[wasm_bindgen]` classes can not be generic, so the JS class is declared
+//! for the application `Ops` enum using the [`rpc_client_bindings!`](crate::rpc_client_bindings)
+//! macro; the class delegates to [`JsRpcClient`]. Ops are specified as
+//! strings and resolved using [`Describe::from_str()`] (the `Ops` enum must
+//! derive [`Describe`](workflow_core::enums::Describe)).
+//!
+//! ```ignore
+//! #[derive(Describe, Clone, Debug, Eq, PartialEq, Hash, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+//! pub enum Ops { Ping, Status }
+//!
+//! workflow_rpc::rpc_client_bindings!(RpcClient, Ops);
+//! ```
+//!
+//! ```js
+//! const rpc = new RpcClient({ url : "ws://127.0.0.1:9292", encoding : "json" });
+//! rpc.addEventListener("connect", () => console.log("connected"));
+//! rpc.subscribe("Status", (status) => console.log(status));
+//! await rpc.connect();
+//! const pong = await rpc.call("Ping", { ts : Date.now() });
+//! ```
+//!
+//! With the JSON encoding, payloads are plain JS values converted using
+//! the serde bridge ([`workflow_wasm::serde`]); with the Borsh encoding,
+//! payloads are `Uint8Array` buffers containing Borsh-serialized data.
+//! Errors are thrown as JS `Error` objects with the `name` set to the
+//! name of the [`Error`] variant (see [`Error::name()`]) and the `trace`
+//! property set to the trace id of the failed call (if available).
+//!
+
+use crate::client::{
+    ConnectOptions, Ctl, Error, IConnectOptions, Interface, Notification, Options, RpcClient,
+};
+use crate::imports::*;
+use js_sys::Uint8Array;
+pub use js_sys::{Function, Promise};
+use serde::{de::Error as _, ser::Error as _};
+use std::io;
+use wasm_bindgen::prelude::*;
+pub use wasm_bindgen_futures::future_to_promise;
+use workflow_core::channel::{Multiplexer, MultiplexerChannel};
+use workflow_core::enums::Describe;
+use workflow_core::task::dispatch;
+use workflow_wasm::callback::{AsCallback, CallbackId, CallbackMap};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_RPC_CLIENT_ARGS: &'static str = r#"
+/**
+ * Arguments of the wRPC client constructor.
+ *
+ * @category wRPC
+ */
+export interface IRpcClientArgs {
+    /** WebSocket URL of the wRPC server (`ws://`, `wss://`, `wrpc://` or `wrpcs://`) */
+    url : string,
+    /** Protocol encoding: `"json"` (default) or `"borsh"` */
+    encoding? : "json" | "borsh",
+    /** RPC call timeout in milliseconds (default 60 seconds) */
+    timeout? : number,
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "IRpcClientArgs")]
+    pub type IRpcClientArgs;
+}
+
+#[derive(Deserialize)]
+struct RpcClientArgs {
+    url: String,
+    #[serde(default)]
+    encoding: Option<Encoding>,
+    #[serde(default)]
+    timeout: Option<u64>,
+}
+
+/// Converts the `err` into a JS `Error` named after the error variant
+pub fn js_error(err: Error) -> JsValue {
+    let error = js_sys::Error::new(&err.to_string());
+    error.set_name(err.name());
+    if let Some(trace) = err.trace_id() {
+        js_sys::Reflect::set(&error, &"trace".into(), &trace.to_string().into()).ok();
+    }
+    error.into()
+}
+
+/// Request, response and notification payload relayed to and from JS
+/// without decoding (JSON value or Borsh-serialized data).
+enum JsPayload {
+    Json(Value),
+    Borsh(Vec<u8>),
+}
+
+impl JsPayload {
+    fn try_from_js(encoding: Encoding, value: JsValue) -> std::result::Result<Self, Error> {
+        match encoding {
+            Encoding::SerdeJson => workflow_wasm::serde::from_value(value)
+                .map(JsPayload::Json)
+                .map_err(|err| Error::SerdeSerialize(err.to_string())),
+            Encoding::Borsh => value
+                .dyn_into::<Uint8Array>()
+                .map(|data| JsPayload::Borsh(data.to_vec()))
+                .map_err(|_| {
+                    Error::SerdeSerialize("Borsh payload must be a Uint8Array".to_string())
+                }),
+        }
+    }
+
+    fn try_into_js(self) -> std::result::Result<JsValue, Error> {
+        match self {
+            // JSON objects are converted to plain JS objects (instead of `Map`)
+            JsPayload::Json(value) => value
+                .serialize(&workflow_wasm::serde::Serializer::json_compatible())
+                .map_err(|err| Error::SerdeDeserialize(err.to_string())),
+            JsPayload::Borsh(data) => Ok(Uint8Array::from(data.as_slice()).into()),
+        }
+    }
+}
+
+impl BorshSerialize for JsPayload {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            JsPayload::Borsh(data) => writer.write_all(data),
+            JsPayload::Json(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "JSON payload in a Borsh message",
+            )),
+        }
+    }
+}
+
+impl BorshDeserialize for JsPayload {
+    fn deserialize_reader<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Ok(JsPayload::Borsh(data))
+    }
+}
+
+impl Serialize for JsPayload {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            JsPayload::Json(value) => value.serialize(serializer),
+            JsPayload::Borsh(_) => Err(S::Error::custom("Borsh payload in a JSON message")),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for JsPayload {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        Value::deserialize(deserializer)
+            .map(JsPayload::Json)
+            .map_err(D::Error::custom)
+    }
+}
+
+/// JS function retained in a [`CallbackMap`]
+struct JsFunction {
+    id: CallbackId,
+    function: Function,
+}
+
+unsafe impl Send for JsFunction {}
+unsafe impl Sync for JsFunction {}
+
+impl AsCallback for JsFunction {
+    fn get_id(&self) -> CallbackId {
+        self.id
+    }
+
+    fn get_fn(&self) -> &Function {
+        &self.function
+    }
+}
+
+/// JS callbacks registered by key (notification op or connection event)
+struct Callbacks<K> {
+    callbacks: CallbackMap,
+    ids: Mutex<AHashMap<K, CallbackId>>,
+}
+
+impl<K> Callbacks<K>
+where
+    K: Eq + Hash,
+{
+    fn new() -> Self {
+        Callbacks {
+            callbacks: CallbackMap::new(),
+            ids: Mutex::new(AHashMap::new()),
+        }
+    }
+
+    fn insert(&self, key: K, function: Function) -> std::result::Result<(), JsValue> {
+        let id = CallbackId::new();
+        self.callbacks.retain(JsFunction { id, function })?;
+        if let Some(previous) = self.ids.lock().unwrap().insert(key, id) {
+            self.callbacks.remove(&previous)?;
+        }
+        Ok(())
+    }
+
+    fn remove(&self, key: &K) -> std::result::Result<(), JsValue> {
+        if let Some(id) = self.ids.lock().unwrap().remove(key) {
+            self.callbacks.remove(&id)?;
+        }
+        Ok(())
+    }
+
+    fn get(&self, key: &K) -> Option<Function> {
+        let id = *self.ids.lock().unwrap().get(key)?;
+        self.callbacks
+            .inner()
+            .get(&id)
+            .map(|callback| callback.get_fn().clone())
+    }
+}
+
+/// Connection event listeners. The dispatch task relaying the events
+/// holds a weak reference; dropping the listeners unregisters and closes
+/// the event channel, terminating the task.
+struct Listeners {
+    callbacks: Callbacks<Ctl>,
+    channel: MultiplexerChannel<Ctl>,
+}
+
+impl Drop for Listeners {
+    fn drop(&mut self) {
+        self.channel.close();
+        self.channel.receiver.close();
+    }
+}
+
+/// Implementation of the JS class declared by [`rpc_client_bindings!`](crate::rpc_client_bindings)
+#[derive(Clone)]
+pub struct JsRpcClient<Ops>
+where
+    Ops: OpsT,
+{
+    client: RpcClient<Ops>,
+    encoding: Encoding,
+    subscriptions: Arc<Callbacks<Ops>>,
+    listeners: Arc<Listeners>,
+}
+
+impl<Ops> JsRpcClient<Ops>
+where
+    Ops: OpsT + Describe,
+{
+    /// Creates the client from the [`IRpcClientArgs`] object
+    pub fn try_new(args: IRpcClientArgs) -> std::result::Result<Self, JsValue> {
+        let RpcClientArgs {
+            url,
+            encoding,
+            timeout,
+        } = workflow_wasm::serde::from_value(args.into())?;
+        let encoding = encoding.unwrap_or(Encoding::SerdeJson);
+
+        // notifications of all ops are relayed to the subscribed callbacks
+        let subscriptions = Arc::new(Callbacks::<Ops>::new());
+        let mut interface = Interface::<Ops>::new();
+        for op in <Ops as Describe>::into_iter() {
+            let subscriptions = subscriptions.clone();
+            let notification_op = op.clone();
+            interface.notification(
+                op,
+                Notification::new(move |payload: JsPayload| {
+                    if let Some(callback) = subscriptions.get(&notification_op) {
+                        match payload.try_into_js() {
+                            Ok(payload) => {
+                                if let Err(err) = callback.call1(&JsValue::UNDEFINED, &payload) {
+                                    log_error!("wRPC notification callback error: {err:?}");
+                                }
+                            }
+                            Err(err) => log_error!("wRPC notification error: {err}"),
+                        }
+                    }
+                    Box::pin(async move { Ok(()) })
+                }),
+            );
+        }
+
+        let ctl_multiplexer = Multiplexer::new();
+        let listeners = Arc::new(Listeners {
+            callbacks: Callbacks::new(),
+            channel: ctl_multiplexer.channel(),
+        });
+        let receiver = listeners.channel.receiver.clone();
+        let ctl_listeners = Arc::downgrade(&listeners);
+        dispatch(async move {
+            while let Ok(ctl) = receiver.recv().await {
+                let Some(listeners) = ctl_listeners.upgrade() else {
+                    break;
+                };
+                if let Some(callback) = listeners.callbacks.get(&ctl) {
+                    if let Err(err) = callback.call0(&JsValue::UNDEFINED) {
+                        log_error!("wRPC `{ctl}` event listener error: {err:?}");
+                    }
+                }
+            }
+        });
+
+        let options = Options::new()
+            .with_url(&url)
+            .with_ctl_multiplexer(ctl_multiplexer);
+        let client = RpcClient::new_with_encoding(encoding, interface.into(), options, None)
+            .map_err(js_error)?;
+        if let Some(timeout) = timeout {
+            client
+                .inner
+                .timeout_duration
+                .store(timeout, Ordering::SeqCst);
+        }
+
+        Ok(JsRpcClient {
+            client,
+            encoding,
+            subscriptions,
+            listeners,
+        })
+    }
+
+    pub fn client(&self) -> &RpcClient<Ops> {
+        &self.client
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    fn op(op: &str) -> std::result::Result<Ops, JsValue> {
+        <Ops as Describe>::from_str(op).ok_or_else(|| js_error(Error::UnknownOp(op.to_string())))
+    }
+
+    pub fn connect(&self, args: Option<IConnectOptions>) -> Promise {
+        let client = self.client.clone();
+        future_to_promise(async move {
+            let options = match args {
+                Some(args) => ConnectOptions::try_from(args)
+                    .map_err(|err| js_error(Error::WebSocketError(err)))?,
+                None => ConnectOptions::default(),
+            };
+            client.connect(options).await.map_err(js_error)?;
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+
+    pub fn disconnect(&self) -> Promise {
+        let client = self.client.clone();
+        future_to_promise(async move {
+            client.shutdown().await.map_err(js_error)?;
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.client.is_connected()
+    }
+
+    /// Issues the RPC call, resolving to the response
+    pub fn call(&self, op: String, payload: JsValue) -> Promise {
+        let this = self.clone();
+        future_to_promise(async move {
+            let op = Self::op(&op)?;
+            let payload = JsPayload::try_from_js(this.encoding, payload).map_err(js_error)?;
+            let response: JsPayload = this.client.call(op, payload).await.map_err(js_error)?;
+            response.try_into_js().map_err(js_error)
+        })
+    }
+
+    /// Posts the notification to the server
+    pub fn notify(&self, op: String, payload: JsValue) -> Promise {
+        let this = self.clone();
+        future_to_promise(async move {
+            let op = Self::op(&op)?;
+            let payload = JsPayload::try_from_js(this.encoding, payload).map_err(js_error)?;
+            this.client.notify(op, payload).await.map_err(js_error)?;
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+
+    /// Registers the `callback` receiving server notifications of
+    /// `op` (replacing the callback previously registered for `op`)
+    pub fn subscribe(&self, op: &str, callback: Function) -> std::result::Result<(), JsValue> {
+        self.subscriptions.insert(Self::op(op)?, callback)
+    }
+
+    pub fn unsubscribe(&self, op: &str) -> std::result::Result<(), JsValue> {
+        self.subscriptions.remove(&Self::op(op)?)
+    }
+
+    /// Registers the `callback` invoked on the `"connect"` or `"disconnect"` event
+    pub fn add_event_listener(
+        &self,
+        event: &str,
+        callback: Function,
+    ) -> std::result::Result<(), JsValue> {
+        let ctl = event.parse::<Ctl>().map_err(js_error)?;
+        self.listeners.callbacks.insert(ctl, callback)
+    }
+
+    pub fn remove_event_listener(&self, event: &str) -> std::result::Result<(), JsValue> {
+        let ctl = event.parse::<Ctl>().map_err(js_error)?;
+        self.listeners.callbacks.remove(&ctl)
+    }
+}
+
+///
+/// Declares the `#[wasm_bindgen]` JS class `$name` wrapping the
+/// [`RpcClient`](crate::client::RpcClient) for the `$ops` enum
+/// (see [`client::wasm`](crate::client::wasm)). The crate declaring
+/// the class must depend on `wasm-bindgen`.
+///
+#[macro_export]
+macro_rules! rpc_client_bindings {
+    ($name:ident, $ops:ty) => {
+        /// wRPC client
+        /// @category wRPC
+        #[::wasm_bindgen::prelude::wasm_bindgen]
+        pub struct $name {
+            inner: $crate::client::wasm::JsRpcClient<$ops>,
+        }
+
+        #[::wasm_bindgen::prelude::wasm_bindgen]
+        impl $name {
+            #[wasm_bindgen(constructor)]
+            pub fn new(
+                args: $crate::client::wasm::IRpcClientArgs,
+            ) -> std::result::Result<$name, ::wasm_bindgen::JsValue> {
+                Ok($name {
+                    inner: $crate::client::wasm::JsRpcClient::try_new(args)?,
+                })
+            }
+
+            #[wasm_bindgen(getter, js_name = "isConnected")]
+            pub fn is_connected(&self) -> bool {
+                self.inner.is_connected()
+            }
+
+            pub fn connect(
+                &self,
+                args: Option<$crate::client::IConnectOptions>,
+            ) -> $crate::client::wasm::Promise {
+                self.inner.connect(args)
+            }
+
+            pub fn disconnect(&self) -> $crate::client::wasm::Promise {
+                self.inner.disconnect()
+            }
+
+            pub fn call(
+                &self,
+                op: String,
+                payload: ::wasm_bindgen::JsValue,
+            ) -> $crate::client::wasm::Promise {
+                self.inner.call(op, payload)
+            }
+
+            pub fn notify(
+                &self,
+                op: String,
+                payload: ::wasm_bindgen::JsValue,
+            ) -> $crate::client::wasm::Promise {
+                self.inner.notify(op, payload)
+            }
+
+            pub fn subscribe(
+                &self,
+                op: &str,
+                callback: $crate::client::wasm::Function,
+            ) -> std::result::Result<(), ::wasm_bindgen::JsValue> {
+                self.inner.subscribe(op, callback)
+            }
+
+            pub fn unsubscribe(
+                &self,
+                op: &str,
+            ) -> std::result::Result<(), ::wasm_bindgen::JsValue> {
+                self.inner.unsubscribe(op)
+            }
+
+            #[wasm_bindgen(js_name = "addEventListener")]
+            pub fn add_event_listener(
+                &self,
+                event: &str,
+                callback: $crate::client::wasm::Function,
+            ) -> std::result::Result<(), ::wasm_bindgen::JsValue> {
+                self.inner.add_event_listener(event, callback)
+            }
+
+            #[wasm_bindgen(js_name = "removeEventListener")]
+            pub fn remove_event_listener(
+                &self,
+                event: &str,
+            ) -> std::result::Result<(), ::wasm_bindgen::JsValue> {
+                self.inner.remove_event_listener(event)
+            }
+        }
+    };
+}
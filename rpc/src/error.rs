@@ -22,6 +22,14 @@ pub enum Error {
 
     #[error("invalid encoding {0}")]
     Encoding(String),
+
+    /// Malformed namespace envelope of a multiplexed connection
+    #[error("invalid envelope: {0}")]
+    Envelope(String),
+
+    /// Malformed protocol negotiation message
+    #[error("invalid negotiation message: {0}")]
+    Negotiation(String),
 }
 
 ///
@@ -70,6 +78,13 @@ pub enum ServerError {
     ReceiveChannelRx,
     #[error("Receiver channel send")]
     ReceiveChannelTx,
+    /// The request exceeds the concurrency limit of the method or the connection
+    #[error("server is busy")]
+    Busy,
+    /// The message could not be decoded. Sent in response to malformed
+    /// requests whose id could be recovered (see [`crate::types`]).
+    #[error("parse error: {0}")]
+    ParseError(String),
 }
 
 impl From<std::io::Error> for ServerError {
@@ -0,0 +1,160 @@
+//!
+//! Liveness heartbeat of idle connections. If the server
+//! [`RpcHandler`](crate::server::RpcHandler) supplies a [`Heartbeat`]
+//! and the client has negotiated the [`HEARTBEAT_CAPABILITY`] (advertised
+//! by all clients configured using
+//! [`Options::with_negotiation()`](crate::client::Options::with_negotiation)),
+//! the server sends a ping to the connection once no messages have been
+//! received from it for the heartbeat interval. The client responds with a
+//! pong automatically. A connection that misses the configured number of
+//! consecutive pongs is closed using the [`HEARTBEAT_TIMEOUT`] close code
+//! and removed from the connection registry of the server (see
+//! [`RpcServer::connections()`](crate::server::RpcServer::connections)).
+//!
+//! Heartbeat messages are exchanged outside of the RPC message framing
+//! (and of the namespace envelope of multiplexed connections):
+//! - `Borsh`: a binary frame consisting of the `0xfe 0xff 'h' 'b'` marker
+//!   (which can not start a Borsh message header or a namespace envelope),
+//!   followed by the kind (`0` ping, `1` pong) and the sequence number (`u64` LE)
+//! - `JSON`: `{"ping":<seq>}` and `{"pong":<seq>}`
+//!
+
+use crate::imports::*;
+use crate::negotiation::Frame;
+
+/// Capability negotiated by clients responding to heartbeat pings
+pub const HEARTBEAT_CAPABILITY: &str = "heartbeat";
+
+/// WebSocket close code sent by the server when the connection
+/// has missed the configured number of consecutive pongs.
+pub const HEARTBEAT_TIMEOUT: u16 = 4011;
+
+/// Default idle period after which the server sends a ping
+pub const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Default number of consecutive missed pongs after
+/// which the connection is closed
+pub const DEFAULT_HEARTBEAT_MAX_MISSED: u32 = 3;
+
+/// Heartbeat configuration of the server
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Heartbeat {
+    /// Idle period after which a ping is sent (also the time
+    /// allowed for the client to respond with a pong)
+    pub interval: Duration,
+    /// Number of consecutive missed pongs after which
+    /// the connection is closed
+    pub max_missed: u32,
+}
+
+impl Heartbeat {
+    pub fn new(interval: Duration, max_missed: u32) -> Self {
+        assert!(
+            max_missed > 0,
+            "heartbeat max_missed must be greater than 0"
+        );
+        Self {
+            interval,
+            max_missed,
+        }
+    }
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self::new(DEFAULT_HEARTBEAT_INTERVAL, DEFAULT_HEARTBEAT_MAX_MISSED)
+    }
+}
+
+/// Marker prefixing binary heartbeat messages
+const MARKER: [u8; 4] = [0xfe, 0xff, b'h', b'b'];
+const BINARY_LEN: usize = MARKER.len() + 1 + 8;
+
+/// Heartbeat message carrying the sequence number of the ping
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Beat {
+    Ping(u64),
+    Pong(u64),
+}
+
+impl Beat {
+    pub fn encode(&self, encoding: Encoding) -> Frame {
+        match encoding {
+            Encoding::Borsh => {
+                let (kind, seq) = match self {
+                    Beat::Ping(seq) => (0, seq),
+                    Beat::Pong(seq) => (1, seq),
+                };
+                let mut data = Vec::with_capacity(BINARY_LEN);
+                data.extend_from_slice(&MARKER);
+                data.push(kind);
+                data.extend_from_slice(&seq.to_le_bytes());
+                Frame::Binary(data)
+            }
+            Encoding::SerdeJson => {
+                let (kind, seq) = match self {
+                    Beat::Ping(seq) => ("ping", seq),
+                    Beat::Pong(seq) => ("pong", seq),
+                };
+                Frame::Text(format!("{{\"{kind}\":{seq}}}"))
+            }
+        }
+    }
+
+    pub fn from_binary(data: &[u8]) -> Option<Self> {
+        if data.len() != BINARY_LEN || !data.starts_with(&MARKER) {
+            return None;
+        }
+        let seq = u64::from_le_bytes(data[MARKER.len() + 1..].try_into().ok()?);
+        match data[MARKER.len()] {
+            0 => Some(Beat::Ping(seq)),
+            1 => Some(Beat::Pong(seq)),
+            _ => None,
+        }
+    }
+
+    pub fn from_text(text: &str) -> Option<Self> {
+        // skip the parsing of RPC messages
+        if text.len() > 32 || !text.starts_with("{\"p") {
+            return None;
+        }
+        serde_json::from_str(text).ok()
+    }
+
+    pub fn from_message(message: &WebSocketMessage) -> Option<Self> {
+        match message {
+            WebSocketMessage::Binary(data) => Self::from_binary(data),
+            WebSocketMessage::Text(text) => Self::from_text(text),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_beat_encoding() {
+        for beat in [Beat::Ping(0), Beat::Pong(u64::MAX)] {
+            match beat.encode(Encoding::Borsh) {
+                Frame::Binary(data) => assert_eq!(Beat::from_binary(&data), Some(beat)),
+                Frame::Text(_) => panic!("expected binary frame"),
+            }
+            match beat.encode(Encoding::SerdeJson) {
+                Frame::Text(text) => assert_eq!(Beat::from_text(&text), Some(beat)),
+                Frame::Binary(_) => panic!("expected text frame"),
+            }
+        }
+        assert_eq!(Beat::from_text(r#"{"ping":7}"#), Some(Beat::Ping(7)));
+
+        // RPC messages are not mistaken for heartbeats
+        assert_eq!(Beat::from_text(r#"{"params":1}"#), None);
+        assert_eq!(Beat::from_text(r#"{"ping":1,"id":2}"#), None);
+        assert_eq!(Beat::from_binary(&[1; BINARY_LEN]), None);
+        let mut data = MARKER.to_vec();
+        data.extend_from_slice(&[2; 9]);
+        assert_eq!(Beat::from_binary(&data), None);
+    }
+}
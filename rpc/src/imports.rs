@@ -2,6 +2,7 @@ pub use crate::encoding::*;
 pub use crate::error::ServerError;
 pub use crate::id::*;
 pub use crate::result::ServerResult;
+pub use crate::trace::TraceId;
 pub use crate::types::*;
 pub use ahash::AHashMap;
 pub use async_trait::async_trait;
@@ -16,6 +16,11 @@
 //! - Server to Client notification messages
 //! - Server-side handshake scaffolding for custom connection negotiation
 //! - Easy to retain connection data structure for posting async client notifications
+//! - Multiplexing of multiple RPC interfaces (namespaces) over a single connection
+//! - Protocol version and capability negotiation
+//! - Heartbeat-based liveness of idle connections
+//! - Request tracing with trace ids propagated to notifications
+//! - In-browser RPC between Web Workers over `MessagePort` (WASM)
 //!
 //! This framework provides [`server`] and [`client`] modules. The server infrastructure is built on top of
 //! [Tokio](https://crates.io/crates/tokio) and [Tungtenite](https://crates.io/crates/tungstenite) and
@@ -24,7 +29,9 @@
 //! The client is built on top of [Workflow WebSocket](https://crates.io/crates/workflow-websocket) and
 //! operates uniformly in native applications and in the browser WASM environment.  For native applications
 //! Workflow Websocket uses Tokio and Tungstenite and in the browser environment it uses the browser
-//! `WebSocket` object.
+//! `WebSocket` object. In the browser, the client can also connect over a `MessagePort`
+//! (see [`MessagePortTransport`](client::MessagePortTransport)) to an [`Interface`](server::Interface)
+//! served from within a Web Worker by the [`PortServer`](server::PortServer).
 //!
 //!
 //! ### Client-side
@@ -66,12 +73,20 @@ extern crate self as workflow_rpc;
 
 pub mod client;
 pub mod error;
+pub mod heartbeat;
 pub mod id;
 mod imports;
 pub mod messages;
+pub mod negotiation;
+#[cfg(target_arch = "wasm32")]
+mod port;
 pub mod result;
+pub mod trace;
 pub mod types;
 
 pub mod encoding;
-#[cfg(not(any(target_arch = "wasm32", target_arch = "bpf")))]
+#[cfg(not(target_arch = "bpf"))]
 pub mod server;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test;
@@ -4,7 +4,8 @@
 
 pub mod serde_json {
     //! RPC message serialization for JSON encoding
-    use serde::{Deserialize, Serialize};
+    use crate::trace::TraceId;
+    use serde::{de::DeserializeOwned, Deserialize, Serialize};
     use serde_json::{self, Value};
 
     #[derive(Debug, Serialize, Deserialize)]
@@ -13,6 +14,8 @@ pub mod serde_json {
         pub id: Option<Id>,
         pub method: Ops,
         pub params: Value,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub trace: Option<TraceId>,
     }
 
     impl<Ops, Id> JsonClientMessage<Ops, Id> {
@@ -22,8 +25,14 @@ pub mod serde_json {
                 id,
                 method,
                 params: payload,
+                trace: None,
             }
         }
+
+        pub fn with_trace(mut self, trace: Option<TraceId>) -> Self {
+            self.trace = trace;
+            self
+        }
     }
 
     #[derive(Debug, Serialize, Deserialize)]
@@ -39,6 +48,8 @@ pub mod serde_json {
         // pub result: Option<Value>,
         #[serde(skip_serializing_if = "Option::is_none")]
         pub error: Option<JsonServerError>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub trace: Option<TraceId>,
     }
 
     impl<Ops, Id> JSONServerMessage<Ops, Id> {
@@ -56,8 +67,14 @@ pub mod serde_json {
                 // result,
                 error,
                 id,
+                trace: None,
             }
         }
+
+        pub fn with_trace(mut self, trace: Option<TraceId>) -> Self {
+            self.trace = trace;
+            self
+        }
     }
 
     #[derive(Debug, Serialize, Deserialize)]
@@ -77,21 +94,42 @@ pub mod serde_json {
         }
     }
 
+    impl JsonServerError {
+        /// The [`ServerError`](crate::error::ServerError) carried in the `data` field
+        pub fn server_error(&self) -> Option<crate::error::ServerError> {
+            self.data
+                .clone()
+                .and_then(|data| serde_json::from_value(data).ok())
+        }
+    }
+
     impl From<crate::error::ServerError> for JsonServerError {
         fn from(err: crate::error::ServerError) -> Self {
             JsonServerError {
                 code: 0, //err.code,
                 message: err.to_string(),
-                data: None, //err.data,
+                data: serde_json::to_value(&err).ok(),
             }
         }
     }
+
+    /// Recovers the request id of a message that could not be
+    /// decoded as [`JsonClientMessage`] (if the message is a
+    /// JSON object carrying a valid `id` field).
+    pub fn recover_id<Id>(text: &str) -> Option<Id>
+    where
+        Id: DeserializeOwned,
+    {
+        let value: Value = serde_json::from_str(text).ok()?;
+        serde_json::from_value(value.get("id")?.clone()).ok()
+    }
 }
 
 pub mod borsh {
     //! RPC message serialization for Borsh encoding
 
     use crate::error::Error;
+    use crate::trace::TraceId;
     use borsh::{BorshDeserialize, BorshSerialize};
     use workflow_websocket::client::message::Message as WebSocketMessage;
     // use borsh::de::*;
@@ -122,6 +160,7 @@ pub mod borsh {
     {
         pub id: Option<Id>, //u64,
         pub op: Ops,
+        pub trace: Option<TraceId>,
     }
 
     impl<Ops, Id> BorshReqHeader<Ops, Id>
@@ -130,7 +169,16 @@ pub mod borsh {
         Ops: BorshSerialize + BorshDeserialize,
     {
         pub fn new(id: Option<Id>, op: Ops) -> Self {
-            BorshReqHeader { id, op }
+            BorshReqHeader {
+                id,
+                op,
+                trace: None,
+            }
+        }
+
+        pub fn with_trace(mut self, trace: Option<TraceId>) -> Self {
+            self.trace = trace;
+            self
         }
     }
 
@@ -139,6 +187,7 @@ pub mod borsh {
         pub id: Option<Id>, //u64,
         pub kind: ServerMessageKind,
         pub op: Option<Ops>,
+        pub trace: Option<TraceId>,
     }
 
     impl<Ops, Id> BorshServerMessageHeader<Ops, Id>
@@ -146,7 +195,17 @@ pub mod borsh {
     //     Id: Default,
     {
         pub fn new(id: Option<Id>, kind: ServerMessageKind, op: Option<Ops>) -> Self {
-            Self { id, kind, op }
+            Self {
+                id,
+                kind,
+                op,
+                trace: None,
+            }
+        }
+
+        pub fn with_trace(mut self, trace: Option<TraceId>) -> Self {
+            self.trace = trace;
+            self
         }
     }
 
@@ -164,6 +223,16 @@ pub mod borsh {
         }
     }
 
+    /// Recovers the request id of a message whose [`BorshReqHeader`]
+    /// could not be decoded. The id precedes the op in the header, so
+    /// it remains readable when only the op or the payload is invalid.
+    pub fn recover_id<Id>(src: &[u8]) -> Option<Id>
+    where
+        Id: BorshDeserialize,
+    {
+        Option::<Id>::deserialize(&mut &src[..]).ok().flatten()
+    }
+
     #[derive(Debug)]
     pub enum RespError<T>
     where
@@ -268,3 +337,53 @@ pub mod borsh {
         }
     }
 }
+
+pub mod envelope {
+    //! Namespace envelope carried by messages of multiplexed connections
+    //! (see [`RpcMultiplexer`](crate::client::RpcMultiplexer)). `Borsh`
+    //! messages are prefixed with the Borsh-serialized namespace, while
+    //! `JSON` messages are wrapped as `{"namespace":"...","message":{...}}`.
+
+    use crate::error::Error;
+    use borsh::BorshDeserialize;
+    use serde::Deserialize;
+    use serde_json::Value;
+
+    #[derive(Debug, Deserialize)]
+    struct JsonEnvelope {
+        namespace: String,
+        message: Value,
+    }
+
+    /// Prefixes the Borsh message `payload` with the `namespace`.
+    pub fn wrap_binary(namespace: &str, payload: &[u8]) -> Vec<u8> {
+        // Borsh `String` layout: u32 length followed by UTF-8 bytes
+        let mut data = Vec::with_capacity(4 + namespace.len() + payload.len());
+        data.extend_from_slice(&(namespace.len() as u32).to_le_bytes());
+        data.extend_from_slice(namespace.as_bytes());
+        data.extend_from_slice(payload);
+        data
+    }
+
+    /// Splits the Borsh message into the namespace and the wrapped message.
+    pub fn unwrap_binary(data: &[u8]) -> Result<(String, &[u8]), Error> {
+        let mut payload = data;
+        let namespace =
+            <String as BorshDeserialize>::deserialize(&mut payload).map_err(|err| Error::Envelope(err.to_string()))?;
+        Ok((namespace, payload))
+    }
+
+    /// Wraps the JSON message `json` in the envelope carrying the `namespace`.
+    pub fn wrap_text(namespace: &str, json: &str) -> Result<String, Error> {
+        let namespace =
+            serde_json::to_string(namespace).map_err(|err| Error::Envelope(err.to_string()))?;
+        Ok(format!("{{\"namespace\":{namespace},\"message\":{json}}}"))
+    }
+
+    /// Splits the JSON message into the namespace and the wrapped message.
+    pub fn unwrap_text(text: &str) -> Result<(String, String), Error> {
+        let envelope: JsonEnvelope =
+            serde_json::from_str(text).map_err(|err| Error::Envelope(err.to_string()))?;
+        Ok((envelope.namespace, envelope.message.to_string()))
+    }
+}
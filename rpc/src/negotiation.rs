@@ -0,0 +1,218 @@
+//!
+//! Protocol version and capability negotiation. If the server
+//! [`RpcHandler`](crate::server::RpcHandler) supplies a [`Negotiation`],
+//! the client (configured using
+//! [`Options::with_negotiation()`](crate::client::Options::with_negotiation))
+//! opens each connection by sending the range of protocol versions and
+//! the capabilities it supports. The server responds with the highest
+//! version supported by both sides and the intersection of capabilities
+//! ([`Negotiated`]), which is then available on the client via
+//! [`RpcClient::negotiated()`](crate::client::RpcClient::negotiated) and
+//! on the server via [`Messenger::negotiated()`](crate::server::Messenger::negotiated).
+//!
+//! If there is no overlapping version, the server responds with the range
+//! of versions it supports and closes the connection with the
+//! [`PROTOCOL_VERSION_MISMATCH`] close code.
+//!
+
+use crate::error::Error;
+use crate::imports::*;
+use std::fmt;
+
+/// Protocol version
+pub type Version = u32;
+
+/// WebSocket close code sent by the server when the client
+/// and the server have no protocol version in common.
+pub const PROTOCOL_VERSION_MISMATCH: u16 = 4010;
+
+/// Inclusive range of supported protocol versions
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Eq,
+    PartialEq,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct VersionRange {
+    pub min: Version,
+    pub max: Version,
+}
+
+impl VersionRange {
+    pub fn new(min: Version, max: Version) -> Self {
+        assert!(min <= max, "invalid version range {min}..{max}");
+        Self { min, max }
+    }
+
+    /// Range containing a single version
+    pub fn single(version: Version) -> Self {
+        Self::new(version, version)
+    }
+
+    pub fn contains(&self, version: Version) -> bool {
+        (self.min..=self.max).contains(&version)
+    }
+
+    /// Highest version contained in both ranges
+    pub fn highest_common(&self, other: &VersionRange) -> Option<Version> {
+        let max = self.max.min(other.max);
+        (max >= self.min.max(other.min)).then_some(max)
+    }
+}
+
+impl fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.min == self.max {
+            write!(f, "v{}", self.min)
+        } else {
+            write!(f, "v{}-v{}", self.min, self.max)
+        }
+    }
+}
+
+/// Protocol versions and capabilities supported by one side of the connection
+#[derive(Debug, Clone)]
+pub struct Negotiation {
+    versions: VersionRange,
+    capabilities: Vec<String>,
+}
+
+impl Negotiation {
+    pub fn new(versions: VersionRange) -> Self {
+        Self {
+            versions,
+            capabilities: Vec::new(),
+        }
+    }
+
+    pub fn with_capability(mut self, capability: &str) -> Self {
+        if !self.capabilities.iter().any(|c| c == capability) {
+            self.capabilities.push(capability.to_string());
+        }
+        self
+    }
+
+    pub fn with_capabilities<I, S>(self, capabilities: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        capabilities.into_iter().fold(self, |this, capability| {
+            this.with_capability(capability.as_ref())
+        })
+    }
+
+    pub fn versions(&self) -> VersionRange {
+        self.versions
+    }
+
+    pub fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
+    /// Negotiate against the versions and capabilities of the remote side.
+    /// Returns `None` if there is no version supported by both sides.
+    pub fn negotiate(
+        &self,
+        versions: &VersionRange,
+        capabilities: &[String],
+    ) -> Option<Negotiated> {
+        let version = self.versions.highest_common(versions)?;
+        let capabilities = self
+            .capabilities
+            .iter()
+            .filter(|capability| capabilities.contains(capability))
+            .cloned()
+            .collect();
+        Some(Negotiated {
+            version,
+            capabilities,
+        })
+    }
+}
+
+/// Result of a successful negotiation
+#[derive(Debug, Clone, Eq, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub struct Negotiated {
+    version: Version,
+    capabilities: Vec<String>,
+}
+
+impl Negotiated {
+    /// Protocol version chosen for the connection
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Capabilities supported by both sides
+    pub fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
+    /// Check if the capability is supported by both sides
+    pub fn has(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+}
+
+/// Negotiation request sent by the client
+#[derive(Debug, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub(crate) struct Hello {
+    pub versions: VersionRange,
+    pub capabilities: Vec<String>,
+}
+
+/// Negotiation response sent by the server
+#[derive(Debug, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Welcome {
+    Accept(Negotiated),
+    Reject { server: VersionRange },
+}
+
+/// Negotiation message serialized according to the connection [`Encoding`]
+pub(crate) enum Frame {
+    Binary(Vec<u8>),
+    Text(String),
+}
+
+impl From<Frame> for WebSocketMessage {
+    fn from(frame: Frame) -> Self {
+        match frame {
+            Frame::Binary(data) => WebSocketMessage::Binary(data),
+            Frame::Text(text) => WebSocketMessage::Text(text),
+        }
+    }
+}
+
+pub(crate) fn encode<T>(encoding: Encoding, msg: &T) -> Result<Frame, Error>
+where
+    T: BorshSerialize + Serialize,
+{
+    match encoding {
+        Encoding::Borsh => Ok(Frame::Binary(borsh::to_vec(msg)?)),
+        Encoding::SerdeJson => serde_json::to_string(msg)
+            .map(Frame::Text)
+            .map_err(|err| Error::Negotiation(err.to_string())),
+    }
+}
+
+pub(crate) fn decode<T>(encoding: Encoding, data: &[u8]) -> Result<T, Error>
+where
+    T: BorshDeserialize + DeserializeOwned,
+{
+    match encoding {
+        Encoding::Borsh => {
+            T::try_from_slice(data).map_err(|err| Error::Negotiation(err.to_string()))
+        }
+        Encoding::SerdeJson => {
+            serde_json::from_slice(data).map_err(|err| Error::Negotiation(err.to_string()))
+        }
+    }
+}
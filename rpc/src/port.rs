@@ -0,0 +1,200 @@
+//!
+//! `MessagePort` channel shared by the [`MessagePortTransport`](crate::client::MessagePortTransport)
+//! and the [`PortServer`](crate::server::PortServer). Binary messages are posted as
+//! transferred `ArrayBuffer` objects (avoiding a copy of Borsh payloads), text messages
+//! as strings and the connection lifecycle as `{ wrpc : "open" | "close" }` objects.
+//!
+
+use crate::imports::*;
+use js_sys::{Array, ArrayBuffer, Object, Reflect, Uint8Array};
+use wasm_bindgen::prelude::*;
+use web_sys::{MessageEvent, MessagePort};
+use workflow_core::channel::Sender;
+use workflow_wasm::callback::{callback, CallbackMap};
+
+const CTL: &str = "wrpc";
+const CTL_OPEN: &str = "open";
+const CTL_CLOSE: &str = "close";
+
+/// Encode the message for posting to a `MessagePort`. Returns
+/// the value to post and the list of transferable objects.
+fn encode(message: WebSocketMessage) -> std::result::Result<(JsValue, Array), JsValue> {
+    match message {
+        WebSocketMessage::Binary(data) => {
+            let buffer = Uint8Array::from(data.as_slice()).buffer();
+            Ok((buffer.clone().into(), Array::of1(&buffer)))
+        }
+        WebSocketMessage::Text(text) => Ok((text.into(), Array::new())),
+        WebSocketMessage::Open => Ok((ctl(CTL_OPEN)?, Array::new())),
+        WebSocketMessage::Close => Ok((ctl(CTL_CLOSE)?, Array::new())),
+    }
+}
+
+fn ctl(kind: &str) -> std::result::Result<JsValue, JsValue> {
+    let object = Object::new();
+    Reflect::set(&object, &CTL.into(), &kind.into())?;
+    Ok(object.into())
+}
+
+/// Decode the message received from a `MessagePort`
+fn decode(data: JsValue) -> Option<WebSocketMessage> {
+    if let Some(buffer) = data.dyn_ref::<ArrayBuffer>() {
+        Some(WebSocketMessage::Binary(Uint8Array::new(buffer).to_vec()))
+    } else if let Some(text) = data.as_string() {
+        Some(WebSocketMessage::Text(text))
+    } else if data.is_object() {
+        let kind = Reflect::get(&data, &CTL.into()).ok()?.as_string()?;
+        match kind.as_str() {
+            CTL_OPEN => Some(WebSocketMessage::Open),
+            CTL_CLOSE => Some(WebSocketMessage::Close),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+/// `MessagePort` relaying inbound messages to the `sender` channel.
+/// The port is closed when dropped.
+pub(crate) struct Port {
+    port: MessagePort,
+    callbacks: CallbackMap,
+}
+
+// MessagePort is bound to the thread of the browser context owning it
+unsafe impl Send for Port {}
+unsafe impl Sync for Port {}
+
+impl Port {
+    pub fn new(port: MessagePort, sender: Sender<WebSocketMessage>) -> Self {
+        let onmessage = callback!(move |event: MessageEvent| {
+            match decode(event.data()) {
+                Some(message) => sender.try_send(message).unwrap_or_else(|err| {
+                    log_trace!("wRPC port unable to try_send() `message` to channel: `{err}`")
+                }),
+                None => log_trace!("wRPC port received unsupported message"),
+            }
+        });
+        port.set_onmessage(Some(onmessage.as_ref()));
+        // onmessage starts the port implicitly; this is for completeness
+        port.start();
+
+        let callbacks = CallbackMap::new();
+        callbacks
+            .retain(onmessage)
+            .expect("retain port onmessage callback");
+
+        Self { port, callbacks }
+    }
+
+    pub fn post(&self, message: WebSocketMessage) -> std::result::Result<(), String> {
+        let (data, transfer) = encode(message).map_err(|err| format!("{err:?}"))?;
+        self.port
+            .post_message_with_transferable(&data, &transfer)
+            .map_err(|err| format!("{err:?}"))
+    }
+}
+
+impl Drop for Port {
+    fn drop(&mut self) {
+        self.port.set_onmessage(None);
+        self.port.close();
+        self.callbacks.clear();
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use crate::client::{ConnectOptions, MessagePortTransport, Options, RpcClient};
+    use crate::encoding::Encoding;
+    use crate::server::{Interface, PortServer};
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use js_sys::Array;
+    use serde::{Deserialize, Serialize};
+    use std::sync::Arc;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::*;
+    use web_sys::{Blob, BlobPropertyBag, MessageChannel, Url, Worker};
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    /// Worker relaying messages between the two transferred ports
+    /// (in applications, the worker hosts the [`PortServer`]).
+    const RELAY: &str = r#"
+        const relay = (from, to) => {
+            from.onmessage = (event) => {
+                const data = event.data;
+                to.postMessage(data, data instanceof ArrayBuffer ? [data] : []);
+            };
+        };
+        onmessage = (event) => {
+            const [a, b] = event.ports;
+            relay(a, b);
+            relay(b, a);
+        };
+    "#;
+
+    #[derive(
+        Clone, Debug, Eq, PartialEq, Hash, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+    )]
+    enum TestOps {
+        Add,
+    }
+
+    fn relay_worker() -> Result<Worker, JsValue> {
+        let options = BlobPropertyBag::new();
+        options.set_type("application/javascript");
+        let blob = Blob::new_with_str_sequence_and_options(&Array::of1(&RELAY.into()), &options)?;
+        let url = Url::create_object_url_with_blob(&blob)?;
+        let worker = Worker::new(&url)?;
+        Url::revoke_object_url(&url)?;
+        Ok(worker)
+    }
+
+    async fn round_trip(encoding: Encoding) {
+        let mut interface = Interface::<(), (), TestOps>::new(());
+        interface.method(
+            TestOps::Add,
+            crate::server::method!(|_connection_ctx, _server_ctx, req: (u64, u64)| async move {
+                Ok(req.0 + req.1)
+            }),
+        );
+
+        let client_channel = MessageChannel::new().unwrap();
+        let server_channel = MessageChannel::new().unwrap();
+        let worker = relay_worker().unwrap();
+        let ports = Array::of2(&client_channel.port2(), &server_channel.port1());
+        worker.post_message_with_transfer(&ports, &ports).unwrap();
+
+        PortServer::<(), (), TestOps>::new(encoding, Arc::new(interface))
+            .serve(server_channel.port2(), |_messenger| ());
+
+        let transport = Arc::new(MessagePortTransport::new(client_channel.port1()));
+        let rpc =
+            RpcClient::<TestOps>::new_with_transport(encoding, None, Options::default(), transport)
+                .unwrap();
+        rpc.connect(ConnectOptions::default()).await.unwrap();
+        assert!(rpc.is_connected());
+        assert_eq!(rpc.url(), None);
+
+        let sum: u64 = rpc.call(TestOps::Add, (2u64, 3u64)).await.unwrap();
+        assert_eq!(sum, 5);
+        let payload = (u32::MAX as u64, 1u64);
+        let sum: u64 = rpc.call(TestOps::Add, payload).await.unwrap();
+        assert_eq!(sum, u32::MAX as u64 + 1);
+
+        rpc.shutdown().await.unwrap();
+        assert!(!rpc.is_connected());
+        worker.terminate();
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_port_round_trip_borsh() {
+        round_trip(Encoding::Borsh).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_port_round_trip_json() {
+        round_trip(Encoding::SerdeJson).await;
+    }
+}
@@ -0,0 +1,194 @@
+//!
+//! WebSocket handler serving [`Encoding::Borsh`] and [`Encoding::SerdeJson`]
+//! clients on the same listener (see [`RpcServer::new_with_auto_encoding`](super::RpcServer::new_with_auto_encoding)).
+//!
+
+use super::negotiation::{self, NEGOTIATION_TIMEOUT};
+use super::{
+    dispatch, BorshProtocol, Connections, Interface, JsonProtocol, ProtocolHandler, RpcConnection,
+    RpcHandler,
+};
+use crate::imports::*;
+use futures::StreamExt;
+use std::net::SocketAddr;
+use workflow_websocket::server::{
+    Error as WebSocketError, Message, Result as WebSocketResult, WebSocketHandler,
+    WebSocketReceiver, WebSocketSender, WebSocketSink,
+};
+
+/// Detect the encoding of the client from a message: JSON messages are
+/// text frames containing an object or an array, while Borsh messages
+/// are binary frames. Returns `None` for other frames.
+pub fn detect_encoding(msg: &Message) -> Option<Encoding> {
+    match msg {
+        Message::Text(text) if text.trim_start().starts_with(['{', '[']) => {
+            Some(Encoding::SerdeJson)
+        }
+        Message::Binary(data) if !data.is_empty() => Some(Encoding::Borsh),
+        _ => None,
+    }
+}
+
+/// Receive the first data frame of the connection, skipping control frames.
+async fn first_frame(receiver: &mut WebSocketReceiver) -> WebSocketResult<Message> {
+    while let Some(msg) = receiver.next().await {
+        match msg? {
+            msg @ (Message::Text(_) | Message::Binary(_)) => return Ok(msg),
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    Err(WebSocketError::AbnormalClose)
+}
+
+/// WebSocket processor dispatching each connection to the [`BorshProtocol`]
+/// or the [`JsonProtocol`] handler, based on the encoding detected from the
+/// first frame received from the client.
+pub(super) struct AutoWebSocketHandler<ServerContext, ConnectionContext, Ops, Id>
+where
+    Ops: OpsT,
+    Id: IdT,
+    ServerContext: Clone + Send + Sync + 'static,
+    ConnectionContext: Clone + Send + Sync + 'static,
+{
+    rpc_handler: Arc<dyn RpcHandler<Context = ConnectionContext>>,
+    borsh: Arc<BorshProtocol<ServerContext, ConnectionContext, Ops, Id>>,
+    json: Arc<JsonProtocol<ServerContext, ConnectionContext, Ops, Id>>,
+    connections: Connections,
+    enable_async_handling: bool,
+}
+
+impl<ServerContext, ConnectionContext, Ops, Id>
+    AutoWebSocketHandler<ServerContext, ConnectionContext, Ops, Id>
+where
+    Ops: OpsT,
+    Id: IdT,
+    ServerContext: Clone + Send + Sync + 'static,
+    ConnectionContext: Clone + Send + Sync + 'static,
+{
+    pub fn new(
+        rpc_handler: Arc<dyn RpcHandler<Context = ConnectionContext>>,
+        interface: Arc<Interface<ServerContext, ConnectionContext, Ops>>,
+        connections: Connections,
+        enable_async_handling: bool,
+    ) -> Self {
+        Self {
+            rpc_handler,
+            borsh: Arc::new(BorshProtocol::new(interface.clone())),
+            json: Arc::new(JsonProtocol::new(interface)),
+            connections,
+            enable_async_handling,
+        }
+    }
+
+    fn limiter(&self, encoding: Encoding) -> Option<Arc<super::Limiter>> {
+        match encoding {
+            Encoding::Borsh => self.borsh.connection_limiter(),
+            Encoding::SerdeJson => self.json.connection_limiter(),
+        }
+    }
+}
+
+#[async_trait]
+impl<ServerContext, ConnectionContext, Ops, Id> WebSocketHandler
+    for AutoWebSocketHandler<ServerContext, ConnectionContext, Ops, Id>
+where
+    Ops: OpsT,
+    Id: IdT,
+    ServerContext: Clone + Send + Sync + 'static,
+    ConnectionContext: Clone + Send + Sync + 'static,
+{
+    type Context = RpcConnection<ConnectionContext>;
+
+    fn accept(&self, peer: &SocketAddr) -> bool {
+        self.rpc_handler.accept(peer)
+    }
+
+    async fn connect(self: &Arc<Self>, peer: &SocketAddr) -> WebSocketResult<()> {
+        self.rpc_handler.clone().connect(peer).await
+    }
+
+    async fn disconnect(self: &Arc<Self>, ctx: Self::Context, result: WebSocketResult<()>) {
+        let result = self.connections.disconnect(&ctx.connection, result);
+        self.rpc_handler
+            .clone()
+            .disconnect(ctx.connection_ctx, result)
+            .await
+    }
+
+    async fn handshake(
+        self: &Arc<Self>,
+        peer: &SocketAddr,
+        sender: &mut WebSocketSender,
+        receiver: &mut WebSocketReceiver,
+        sink: &WebSocketSink,
+    ) -> WebSocketResult<Self::Context> {
+        // the negotiation request is expected right after the connection
+        // is opened, while a client that does not negotiate may remain
+        // idle until its first call
+        let negotiating = self.rpc_handler.negotiation().is_some();
+        let first = if negotiating {
+            tokio::time::timeout(NEGOTIATION_TIMEOUT, first_frame(receiver))
+                .await
+                .map_err(|_| WebSocketError::ConnectionTimeout)??
+        } else {
+            first_frame(receiver).await?
+        };
+        let encoding = detect_encoding(&first).ok_or(WebSocketError::MalformedHandshake)?;
+        let (hello, first) = if negotiating {
+            (Some(first), None)
+        } else {
+            (None, Some(first))
+        };
+
+        let messenger =
+            negotiation::messenger(&self.rpc_handler, encoding, hello, sender, receiver, sink)
+                .await?;
+
+        let connection_ctx = self
+            .rpc_handler
+            .clone()
+            .handshake(peer, sender, receiver, messenger.clone())
+            .await?;
+
+        let connection = self
+            .connections
+            .connect(&self.rpc_handler, peer, &messenger, sink);
+
+        let ctx = RpcConnection {
+            connection_ctx,
+            encoding,
+            connection,
+            limiter: self.limiter(encoding),
+            malformed: Arc::default(),
+        };
+
+        // the first RPC message (used to detect the encoding)
+        // is dispatched once the connection is established
+        if let Some(msg) = first {
+            if let Err(err) = self.message(&ctx, msg, sink).await {
+                log_trace!("RPC connection {peer} closed by the first message: {err}");
+                self.disconnect(ctx, Err(err)).await;
+                return Err(WebSocketError::ServerClose);
+            }
+        }
+
+        Ok(ctx)
+    }
+
+    async fn message(
+        self: &Arc<Self>,
+        ctx: &Self::Context,
+        msg: Message,
+        sink: &WebSocketSink,
+    ) -> WebSocketResult<()> {
+        match ctx.encoding {
+            Encoding::Borsh => {
+                dispatch(&self.borsh, ctx, msg, sink, self.enable_async_handling).await
+            }
+            Encoding::SerdeJson => {
+                dispatch(&self.json, ctx, msg, sink, self.enable_async_handling).await
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//!
+//! Registry of the connections served by the [`RpcServer`](super::RpcServer),
+//! tracking the liveness of each connection (see [`crate::heartbeat`]).
+//!
+
+use super::{heartbeat, Messenger, RpcHandler};
+use crate::heartbeat::HEARTBEAT_CAPABILITY;
+use crate::imports::*;
+use std::net::SocketAddr;
+use std::sync::atomic::AtomicU32;
+use workflow_core::time::unixtime_as_millis_u64;
+use workflow_websocket::server::{
+    Error as WebSocketError, Result as WebSocketResult, WebSocketSink,
+};
+
+/// Liveness state of a connection
+#[derive(Debug)]
+pub(crate) struct Connection {
+    id: u64,
+    peer: SocketAddr,
+    encoding: Encoding,
+    heartbeat: bool,
+    last_seen: Mutex<Instant>,
+    /// Consecutive pings sent without receiving any message
+    missed: AtomicU32,
+    reaped: AtomicBool,
+}
+
+impl Connection {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Records the receipt of a message from the connection
+    pub fn seen(&self) {
+        *self.last_seen.lock().unwrap() = Instant::now();
+        self.missed.store(0, Ordering::Relaxed);
+    }
+
+    /// Time elapsed since a message has been received from the connection
+    pub fn idle(&self) -> Duration {
+        self.last_seen.lock().unwrap().elapsed()
+    }
+
+    pub fn missed(&self) -> u32 {
+        self.missed.load(Ordering::Relaxed)
+    }
+
+    pub fn ping(&self) {
+        self.missed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn reap(&self) {
+        self.reaped.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the connection has been closed
+    /// due to the missing heartbeat pongs
+    pub fn is_reaped(&self) -> bool {
+        self.reaped.load(Ordering::Relaxed)
+    }
+}
+
+/// Snapshot of a connection returned by
+/// [`RpcServer::connections()`](super::RpcServer::connections)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    /// Identifier of the connection, unique within the server
+    pub id: u64,
+    pub peer: SocketAddr,
+    /// Encoding of the messages exchanged with the client
+    pub encoding: Encoding,
+    /// `true` if the heartbeat is active for the connection
+    pub heartbeat: bool,
+    /// UNIX time (in milliseconds) of the last message received
+    pub last_seen: u64,
+    /// Time elapsed since the last message has been received
+    pub idle: Duration,
+    /// Consecutive heartbeat pings not answered by the client
+    pub missed_pongs: u32,
+}
+
+impl From<&Connection> for ConnectionInfo {
+    fn from(connection: &Connection) -> Self {
+        let idle = connection.idle();
+        ConnectionInfo {
+            id: connection.id,
+            peer: connection.peer,
+            encoding: connection.encoding,
+            heartbeat: connection.heartbeat,
+            last_seen: unixtime_as_millis_u64().saturating_sub(idle.as_millis() as u64),
+            idle,
+            missed_pongs: connection.missed(),
+        }
+    }
+}
+
+/// Connections that have completed the handshake
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Connections {
+    next_id: Arc<AtomicU64>,
+    connections: Arc<Mutex<AHashMap<u64, Arc<Connection>>>>,
+}
+
+impl Connections {
+    /// Registers the connection that has completed the handshake, starting
+    /// its heartbeat if supplied by the `rpc_handler` and negotiated by the client.
+    pub fn connect<ConnectionContext>(
+        &self,
+        rpc_handler: &Arc<dyn RpcHandler<Context = ConnectionContext>>,
+        peer: &SocketAddr,
+        messenger: &Messenger,
+        sink: &WebSocketSink,
+    ) -> Arc<Connection>
+    where
+        ConnectionContext: Send + Sync + 'static,
+    {
+        let heartbeat = rpc_handler.heartbeat().filter(|_| {
+            messenger
+                .negotiated()
+                .is_some_and(|negotiated| negotiated.has(HEARTBEAT_CAPABILITY))
+        });
+        let connection = self.register(peer, messenger.encoding(), heartbeat.is_some());
+        if let Some(heartbeat) = heartbeat {
+            heartbeat::start(
+                heartbeat,
+                messenger.encoding(),
+                connection.clone(),
+                self.clone(),
+                sink.clone(),
+            );
+        }
+        connection
+    }
+
+    /// Unregisters the closed connection. The disconnection `result` of a
+    /// connection closed due to the missing heartbeat pongs is replaced
+    /// with [`WebSocketError::ConnectionTimeout`].
+    pub fn disconnect(
+        &self,
+        connection: &Connection,
+        result: WebSocketResult<()>,
+    ) -> WebSocketResult<()> {
+        self.unregister(connection);
+        match result {
+            Ok(()) if connection.is_reaped() => Err(WebSocketError::ConnectionTimeout),
+            result => result,
+        }
+    }
+
+    fn register(&self, peer: &SocketAddr, encoding: Encoding, heartbeat: bool) -> Arc<Connection> {
+        let connection = Arc::new(Connection {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            peer: *peer,
+            encoding,
+            heartbeat,
+            last_seen: Mutex::new(Instant::now()),
+            missed: AtomicU32::new(0),
+            reaped: AtomicBool::new(false),
+        });
+        self.connections
+            .lock()
+            .unwrap()
+            .insert(connection.id, connection.clone());
+        connection
+    }
+
+    pub fn unregister(&self, connection: &Connection) {
+        self.connections.lock().unwrap().remove(&connection.id);
+    }
+
+    pub fn snapshot(&self) -> Vec<ConnectionInfo> {
+        let mut connections = self
+            .connections
+            .lock()
+            .unwrap()
+            .values()
+            .map(|connection| ConnectionInfo::from(connection.as_ref()))
+            .collect::<Vec<_>>();
+        connections.sort_by_key(|info| info.id);
+        connections
+    }
+
+    /// Number of connections using each encoding
+    pub fn count_by_encoding(&self) -> Vec<(Encoding, usize)> {
+        let connections = self.connections.lock().unwrap();
+        Encoding::iter()
+            .map(|encoding| {
+                let count = connections
+                    .values()
+                    .filter(|connection| connection.encoding == *encoding)
+                    .count();
+                (*encoding, count)
+            })
+            .collect()
+    }
+}
@@ -1,13 +1,16 @@
 //! [`enum@Error`] declarations for the [`server`](super) module
 
 use thiserror::Error;
+#[cfg(not(target_arch = "wasm32"))]
 pub use workflow_websocket::server::Error as WebSocketError;
 
 #[derive(Debug, Error)]
 pub enum Error {
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("WebSocket error: {0}")]
     WebSocketError(#[from] workflow_websocket::server::error::Error),
 
+    #[cfg(not(target_arch = "wasm32"))]
     #[error(transparent)]
     ChannelSendError(#[from] tokio::sync::mpsc::error::SendError<tungstenite::Message>),
 
@@ -22,4 +25,8 @@ pub enum Error {
 
     #[error("SerdeJSON error: {0}")]
     SerdeJSON(#[from] serde_json::Error),
+
+    /// Failure posting to the `MessagePort` (see `PortServer`)
+    #[error("MessagePort error: {0}")]
+    Port(String),
 }
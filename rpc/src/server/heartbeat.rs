@@ -0,0 +1,72 @@
+//!
+//! Server-side connection heartbeat (see [`crate::heartbeat`]).
+//!
+
+use super::connections::{Connection, Connections};
+use crate::heartbeat::*;
+use crate::imports::*;
+use std::borrow::Cow;
+use tungstenite::protocol::frame::coding::CloseCode;
+use tungstenite::protocol::CloseFrame;
+use workflow_core::task::{sleep, spawn};
+use workflow_websocket::server::{Message, WebSocketSink};
+
+/// Returns `true` if the message received from the client is
+/// a heartbeat message (which is not relayed to the protocol handler).
+pub(crate) fn is_heartbeat(msg: &Message) -> bool {
+    match msg {
+        Message::Binary(data) => Beat::from_binary(data).is_some(),
+        Message::Text(text) => Beat::from_text(text).is_some(),
+        _ => false,
+    }
+}
+
+/// Spawns the task sending pings to the idle `connection`. Once the
+/// connection misses `max_missed` consecutive pongs, it is removed from
+/// the `connections` and closed with the [`HEARTBEAT_TIMEOUT`] close code.
+/// The task terminates when the connection is closed.
+pub(crate) fn start(
+    heartbeat: Heartbeat,
+    encoding: Encoding,
+    connection: Arc<Connection>,
+    connections: Connections,
+    sink: WebSocketSink,
+) {
+    spawn(async move {
+        let mut seq = 0;
+        loop {
+            if sink.is_closed() {
+                break;
+            }
+
+            let idle = connection.idle();
+            if idle < heartbeat.interval {
+                sleep(heartbeat.interval - idle).await;
+                continue;
+            }
+
+            if connection.missed() >= heartbeat.max_missed {
+                log_trace!(
+                    "wRPC: closing connection {} after {} missed heartbeats",
+                    connection.id(),
+                    heartbeat.max_missed
+                );
+                connection.reap();
+                connections.unregister(&connection);
+                sink.send(Message::Close(Some(CloseFrame {
+                    code: CloseCode::Library(HEARTBEAT_TIMEOUT),
+                    reason: Cow::Borrowed("heartbeat timeout"),
+                })))
+                .ok();
+                break;
+            }
+
+            seq += 1;
+            if sink.send(Beat::Ping(seq).encode(encoding).into()).is_err() {
+                break;
+            }
+            connection.ping();
+            sleep(heartbeat.interval).await;
+        }
+    });
+}
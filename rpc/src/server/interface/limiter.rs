@@ -0,0 +1,143 @@
+//! Module containing the [`Limiter`] used to bound the number
+//! of concurrently executing RPC method calls.
+use crate::imports::*;
+use std::sync::atomic::AtomicUsize;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Default number of requests that can wait for execution
+/// when using [`OverloadPolicy::default()`]
+pub const DEFAULT_QUEUE_CAPACITY: usize = 1024;
+
+/// Handling of requests exceeding the concurrency limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverloadPolicy {
+    /// Wait for execution in a FIFO queue of the given capacity;
+    /// requests exceeding the capacity are rejected with [`ServerError::Busy`].
+    Queue(usize),
+    /// Reject requests exceeding the limit with [`ServerError::Busy`].
+    Reject,
+}
+
+impl Default for OverloadPolicy {
+    fn default() -> Self {
+        OverloadPolicy::Queue(DEFAULT_QUEUE_CAPACITY)
+    }
+}
+
+/// Snapshot of the [`Limiter`] state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimiterMetrics {
+    pub max_concurrency: usize,
+    /// Requests currently executing
+    pub in_flight: usize,
+    /// Requests waiting for execution
+    pub queued: usize,
+}
+
+/// Concurrency limiter applied to an RPC method (see [`Method::with_max_concurrency()`](super::Method::with_max_concurrency))
+/// or to all method calls of a connection (see [`Interface::set_connection_concurrency()`](super::Interface::set_connection_concurrency)).
+#[derive(Debug)]
+pub struct Limiter {
+    max_concurrency: usize,
+    policy: OverloadPolicy,
+    semaphore: Semaphore,
+    in_flight: AtomicUsize,
+    queued: AtomicUsize,
+}
+
+impl Limiter {
+    pub fn new(max_concurrency: usize, policy: OverloadPolicy) -> Self {
+        assert!(
+            max_concurrency > 0,
+            "RPC concurrency limit must be greater than 0"
+        );
+        Self {
+            max_concurrency,
+            policy,
+            semaphore: Semaphore::new(max_concurrency),
+            in_flight: AtomicUsize::new(0),
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn policy(&self) -> OverloadPolicy {
+        self.policy
+    }
+
+    pub fn metrics(&self) -> LimiterMetrics {
+        LimiterMetrics {
+            max_concurrency: self.max_concurrency,
+            in_flight: self.in_flight.load(Ordering::SeqCst),
+            queued: self.queued.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Obtain an execution permit, waiting in the queue (if allowed
+    /// by the [`OverloadPolicy`]) while the limit is reached.
+    pub(crate) async fn acquire(&self) -> ServerResult<Permit<'_>> {
+        // permits are handed over to the queued requests first,
+        // so this can not bypass the queue
+        if let Ok(permit) = self.semaphore.try_acquire() {
+            return Ok(self.permit(permit));
+        }
+
+        let capacity = match self.policy {
+            OverloadPolicy::Queue(capacity) => capacity,
+            OverloadPolicy::Reject => 0,
+        };
+        self.queued
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |queued| {
+                (queued < capacity).then_some(queued + 1)
+            })
+            .map_err(|_| ServerError::Busy)?;
+
+        // decrements the queue length if the request is dropped while waiting
+        let _queued = Counter(&self.queued);
+        let permit = self
+            .semaphore
+            .acquire()
+            .await
+            .map_err(|_| ServerError::Close)?;
+        Ok(self.permit(permit))
+    }
+
+    fn permit<'limiter>(&'limiter self, permit: SemaphorePermit<'limiter>) -> Permit<'limiter> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        Permit {
+            _permit: permit,
+            _in_flight: Counter(&self.in_flight),
+        }
+    }
+}
+
+/// Decrements the counter when dropped
+struct Counter<'limiter>(&'limiter AtomicUsize);
+
+impl Drop for Counter<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Execution permit, released when dropped
+pub(crate) struct Permit<'limiter> {
+    _permit: SemaphorePermit<'limiter>,
+    _in_flight: Counter<'limiter>,
+}
+
+/// Obtain permits of the method and the connection limiters (in that order,
+/// so that requests queued by a method limit do not occupy the connection slots).
+pub(crate) async fn acquire<'limiter>(
+    method: Option<&'limiter Limiter>,
+    connection: Option<&'limiter Limiter>,
+) -> ServerResult<(Option<Permit<'limiter>>, Option<Permit<'limiter>>)> {
+    let method = match method {
+        Some(limiter) => Some(limiter.acquire().await?),
+        None => None,
+    };
+    let connection = match connection {
+        Some(limiter) => Some(limiter.acquire().await?),
+        None => None,
+    };
+    Ok((method, connection))
+}
@@ -1,4 +1,5 @@
 //! Module containing RPC [`Method`] closure wrappers
+use super::limiter::{self, Limiter, LimiterMetrics, OverloadPolicy};
 use crate::imports::*;
 
 /// Base trait representing an RPC method, used to retain
@@ -13,13 +14,17 @@ pub(crate) trait MethodTrait<ServerContext, ConnectionContext>:
         server_ctx: ServerContext,
         connection_ctx: ConnectionContext,
         data: &[u8],
-    ) -> ServerResult<Vec<u8>>;
+        limiter: Option<&Limiter>,
+        response: &mut Vec<u8>,
+    ) -> ServerResult<()>;
     async fn call_with_serde_json(
         &self,
         server_ctx: ServerContext,
         connection_ctx: ConnectionContext,
         value: Value,
+        limiter: Option<&Limiter>,
     ) -> ServerResult<Value>;
+    fn metrics(&self) -> Option<LimiterMetrics>;
 }
 
 /// RPC method function type
@@ -43,6 +48,8 @@ where
     Resp: MsgT,
 {
     method: MethodFn<ServerContext, ConnectionContext, Req, Resp>,
+    limiter: Option<Limiter>,
+    policy: OverloadPolicy,
 }
 
 impl<ServerContext, ConnectionContext, Req, Resp>
@@ -61,8 +68,32 @@ where
     {
         Method {
             method: Arc::new(Box::new(method_fn)),
+            limiter: None,
+            policy: OverloadPolicy::default(),
         }
     }
+
+    /// Limit the number of concurrently executing calls of this method
+    /// (across all connections). Calls exceeding the limit are handled
+    /// according to the [`OverloadPolicy`] (queued by default).
+    ///
+    /// Please note that calls are executed concurrently only if the
+    /// [`RpcServer`](crate::server::RpcServer) is created with
+    /// `enable_async_handling` set to `true`.
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.limiter = Some(Limiter::new(max_concurrency, self.policy));
+        self
+    }
+
+    /// Set the handling of calls exceeding the limit set via
+    /// [`Method::with_max_concurrency()`].
+    pub fn with_overload_policy(mut self, policy: OverloadPolicy) -> Self {
+        self.policy = policy;
+        if let Some(limiter) = self.limiter.as_ref() {
+            self.limiter = Some(Limiter::new(limiter.metrics().max_concurrency, policy));
+        }
+        self
+    }
 }
 
 #[async_trait]
@@ -79,11 +110,14 @@ where
         server_ctx: ServerContext,
         connection_ctx: ConnectionContext,
         data: &[u8],
-    ) -> ServerResult<Vec<u8>> {
-        let req = Req::try_from_slice(data)?;
+        limiter: Option<&Limiter>,
+        response: &mut Vec<u8>,
+    ) -> ServerResult<()> {
+        let req = Req::try_from_slice(data).map_err(|_| ServerError::ReqDeserialize)?;
+        let _permits = limiter::acquire(self.limiter.as_ref(), limiter).await?;
         let resp = (self.method)(server_ctx, connection_ctx, req).await;
-        let vec = borsh::to_vec(&resp)?;
-        Ok(vec)
+        BorshSerialize::serialize(&resp, response)?;
+        Ok(())
     }
 
     async fn call_with_serde_json(
@@ -91,9 +125,15 @@ where
         server_ctx: ServerContext,
         connection_ctx: ConnectionContext,
         value: Value,
+        limiter: Option<&Limiter>,
     ) -> ServerResult<Value> {
         let req: Req = serde_json::from_value(value).map_err(|_| ServerError::ReqDeserialize)?;
+        let _permits = limiter::acquire(self.limiter.as_ref(), limiter).await?;
         let resp = (self.method)(server_ctx, connection_ctx, req).await?;
         Ok(serde_json::to_value(resp).map_err(|_| ServerError::RespSerialize)?)
     }
+
+    fn metrics(&self) -> Option<LimiterMetrics> {
+        self.limiter.as_ref().map(Limiter::metrics)
+    }
 }
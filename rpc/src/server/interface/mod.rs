@@ -3,10 +3,13 @@
 //! mappings of RPC method and notification handlers.
 //!
 
+pub mod limiter;
 pub mod method;
 pub mod notification;
 
 use crate::imports::*;
+use crate::server::malformed::DEFAULT_MALFORMED_MESSAGE_LIMIT;
+pub use limiter::*;
 pub use method::*;
 pub use notification::*;
 
@@ -22,6 +25,8 @@ where
     server_ctx: ServerContext,
     methods: AHashMap<Ops, Box<dyn MethodTrait<ServerContext, ConnectionContext>>>,
     notifications: AHashMap<Ops, Box<dyn NotificationTrait<ServerContext, ConnectionContext>>>,
+    connection_concurrency: Option<(usize, OverloadPolicy)>,
+    malformed_message_limit: usize,
 }
 
 impl<ServerContext, ConnectionContext, Ops> Interface<ServerContext, ConnectionContext, Ops>
@@ -39,9 +44,49 @@ where
             server_ctx,
             methods: AHashMap::new(),
             notifications: AHashMap::new(),
+            connection_concurrency: None,
+            malformed_message_limit: DEFAULT_MALFORMED_MESSAGE_LIMIT,
         }
     }
 
+    /// Limit the number of concurrently executing method calls of each
+    /// connection. Calls exceeding the limit are handled according to the
+    /// `policy`. Per-method limits (see [`Method::with_max_concurrency()`])
+    /// are applied before the connection limit, so calls waiting for a
+    /// method limit do not occupy the connection slots.
+    pub fn set_connection_concurrency(&mut self, max_in_flight: usize, policy: OverloadPolicy) {
+        self.connection_concurrency = Some((max_in_flight, policy));
+    }
+
+    /// Create the connection limiter configured via [`Interface::set_connection_concurrency()`]
+    pub(crate) fn connection_limiter(&self) -> Option<Arc<Limiter>> {
+        self.connection_concurrency
+            .map(|(max_in_flight, policy)| Arc::new(Limiter::new(max_in_flight, policy)))
+    }
+
+    /// Disconnect clients after `limit` consecutive messages that could
+    /// not be decoded (defaults to [`DEFAULT_MALFORMED_MESSAGE_LIMIT`]).
+    /// Malformed requests with a recoverable id are answered with
+    /// [`ServerError::ParseError`] until the limit is reached.
+    pub fn set_malformed_message_limit(&mut self, limit: usize) {
+        self.malformed_message_limit = limit;
+    }
+
+    /// Limit configured via [`Interface::set_malformed_message_limit()`]
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub(crate) fn malformed_message_limit(&self) -> usize {
+        self.malformed_message_limit
+    }
+
+    /// Current number of executing and queued calls of the
+    /// methods declared with a concurrency limit.
+    pub fn metrics(&self) -> Vec<(Ops, LimiterMetrics)> {
+        self.methods
+            .iter()
+            .filter_map(|(op, method)| method.metrics().map(|metrics| (op.clone(), metrics)))
+            .collect()
+    }
+
     ///
     /// Declare an RPC method handler. You can use a [`method!()`](macro@crate::server::method)
     /// macro to declare the method as follows:
@@ -107,15 +152,24 @@ where
         }
     }
 
+    /// Executes the method, appending the serialized result to `response`.
     pub(crate) async fn call_method_with_borsh(
         &self,
         op: &Ops,
         connection_ctx: ConnectionContext,
         payload: &[u8],
-    ) -> ServerResult<Vec<u8>> {
+        limiter: Option<&Limiter>,
+        response: &mut Vec<u8>,
+    ) -> ServerResult<()> {
         if let Some(method) = self.methods.get(op) {
             method
-                .call_with_borsh(self.server_ctx.clone(), connection_ctx, payload)
+                .call_with_borsh(
+                    self.server_ctx.clone(),
+                    connection_ctx,
+                    payload,
+                    limiter,
+                    response,
+                )
                 .await
         } else {
             Err(ServerError::NotFound)
@@ -127,10 +181,11 @@ where
         op: &Ops,
         connection_ctx: ConnectionContext,
         payload: Value,
+        limiter: Option<&Limiter>,
     ) -> ServerResult<Value> {
         if let Some(method) = self.methods.get(op) {
             method
-                .call_with_serde_json(self.server_ctx.clone(), connection_ctx, payload)
+                .call_with_serde_json(self.server_ctx.clone(), connection_ctx, payload, limiter)
                 .await
         } else {
             Err(ServerError::NotFound)
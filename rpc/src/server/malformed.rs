@@ -0,0 +1,93 @@
+//!
+//! Tracking of consecutive malformed messages received by a connection
+//! (see [`Interface::set_malformed_message_limit()`](super::Interface::set_malformed_message_limit)).
+//!
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::imports::*;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::atomic::AtomicUsize;
+#[cfg(not(target_arch = "wasm32"))]
+use workflow_websocket::server::{
+    Error as WebSocketError, Message, Result as WebSocketResult, WebSocketSink,
+};
+
+/// Default number of consecutive malformed messages
+/// after which the connection is closed.
+pub const DEFAULT_MALFORMED_MESSAGE_LIMIT: usize = 16;
+
+/// Per-connection counter of consecutive malformed messages.
+/// Protocol handlers signal malformed messages by returning
+/// [`WebSocketError::MalformedMessage`] (after responding with
+/// an error frame if possible).
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+pub(crate) struct MalformedMessages {
+    consecutive: AtomicUsize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl MalformedMessages {
+    /// Accounts for the `result` of the message handler. Malformed
+    /// messages are tolerated until `limit` consecutive messages are
+    /// malformed, at which point the connection is closed via the `sink`
+    /// (messages handled asynchronously can not terminate the connection
+    /// by returning an error) and the error is returned.
+    pub fn track(
+        &self,
+        result: WebSocketResult<()>,
+        limit: usize,
+        sink: &WebSocketSink,
+    ) -> WebSocketResult<()> {
+        match result {
+            Ok(()) => {
+                self.consecutive.store(0, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(WebSocketError::MalformedMessage) => {
+                let consecutive = self.consecutive.fetch_add(1, Ordering::Relaxed) + 1;
+                if consecutive >= limit {
+                    log_trace!("wRPC: closing connection after {consecutive} malformed messages");
+                    sink.send(Message::Close(None)).ok();
+                    Err(WebSocketError::MalformedMessage)
+                } else {
+                    Ok(())
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_malformed_message_limit() {
+        let (sink, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let malformed = MalformedMessages::default();
+        let track = |result| malformed.track(result, 3, &sink);
+
+        assert!(track(Err(WebSocketError::MalformedMessage)).is_ok());
+        assert!(track(Err(WebSocketError::MalformedMessage)).is_ok());
+        // a well-formed message resets the count
+        assert!(track(Ok(())).is_ok());
+        assert!(track(Err(WebSocketError::MalformedMessage)).is_ok());
+        assert!(track(Err(WebSocketError::MalformedMessage)).is_ok());
+        assert!(receiver.try_recv().is_err());
+
+        assert!(matches!(
+            track(Err(WebSocketError::MalformedMessage)),
+            Err(WebSocketError::MalformedMessage)
+        ));
+        assert!(matches!(receiver.try_recv(), Ok(Message::Close(None))));
+
+        // other errors are passed through
+        assert!(matches!(
+            track(Err(WebSocketError::ServerClose)),
+            Err(WebSocketError::ServerClose)
+        ));
+    }
+}
@@ -1,33 +1,82 @@
 //!
-//! RPC server module (native only). This module encapsulates
-//! server-side types used to create an RPC server: [`RpcServer`],
-//! [`RpcHandler`], [`Messenger`], [`Interface`] and the
-//! protocol handlers: [`BorshProtocol`] and [`JsonProtocol`].
+//! RPC server module. This module encapsulates server-side types
+//! used to create an RPC server: [`RpcServer`], [`RpcHandler`],
+//! [`Messenger`], [`Interface`], [`Router`] and the protocol
+//! handlers: [`BorshProtocol`] and [`JsonProtocol`] (native only).
+//!
+//! In WASM, the [`Interface`] can be served to a client located
+//! in another browser context (e.g. from within a Web Worker)
+//! over a `MessagePort` using the [`PortServer`].
 //!
 
+#[cfg(not(target_arch = "wasm32"))]
+mod auto;
+#[cfg(not(target_arch = "wasm32"))]
+mod connections;
 pub mod error;
+#[cfg(not(target_arch = "wasm32"))]
+mod heartbeat;
 mod interface;
+mod malformed;
+#[cfg(not(target_arch = "wasm32"))]
+mod negotiation;
+pub mod pool;
+#[cfg(target_arch = "wasm32")]
+mod port;
 pub mod prelude;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod protocol;
 pub mod result;
+#[cfg(not(target_arch = "wasm32"))]
+mod router;
 
 pub use super::error::*;
 pub use crate::encoding::Encoding;
+pub use crate::heartbeat::Heartbeat;
+#[cfg(not(target_arch = "wasm32"))]
 use crate::imports::*;
-pub use interface::{Interface, Method, Notification};
+pub use crate::negotiation::{Negotiated, Negotiation, VersionRange};
+#[cfg(not(target_arch = "wasm32"))]
+pub use auto::detect_encoding;
+#[cfg(not(target_arch = "wasm32"))]
+use auto::AutoWebSocketHandler;
+#[cfg(not(target_arch = "wasm32"))]
+pub use connections::ConnectionInfo;
+#[cfg(not(target_arch = "wasm32"))]
+use connections::{Connection, Connections};
+pub use interface::{
+    Interface, Limiter, LimiterMetrics, Method, Notification, OverloadPolicy,
+    DEFAULT_QUEUE_CAPACITY,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use malformed::MalformedMessages;
+pub use malformed::DEFAULT_MALFORMED_MESSAGE_LIMIT;
+pub use pool::PoolMetrics;
+#[cfg(target_arch = "wasm32")]
+pub use port::{PortMessenger, PortServer};
+#[cfg(not(target_arch = "wasm32"))]
 pub use protocol::{BorshProtocol, JsonProtocol, ProtocolHandler};
+#[cfg(not(target_arch = "wasm32"))]
+pub use router::Router;
+#[cfg(not(target_arch = "wasm32"))]
+use router::RouterWebSocketHandler;
+#[cfg(not(target_arch = "wasm32"))]
 pub use std::net::SocketAddr;
+#[cfg(not(target_arch = "wasm32"))]
 pub use tokio::sync::mpsc::UnboundedSender as TokioUnboundedSender;
 pub use workflow_core::task::spawn;
+#[cfg(not(target_arch = "wasm32"))]
 pub use workflow_websocket::server::{
     Error as WebSocketError, Message, Result as WebSocketResult, TcpListener, WebSocketConfig,
     WebSocketCounters, WebSocketHandler, WebSocketReceiver, WebSocketSender, WebSocketServer,
     WebSocketServerTrait, WebSocketSink,
 };
+#[cfg(not(target_arch = "wasm32"))]
 pub mod handshake {
     //! WebSocket handshake helpers
     pub use workflow_websocket::server::handshake::*;
 }
+#[cfg(not(target_arch = "wasm32"))]
 use crate::server::result::Result;
 
 ///
@@ -102,6 +151,7 @@ pub use workflow_rpc_macros::server_method as method;
 ///
 pub use workflow_rpc_macros::server_notification as notification;
 
+#[cfg(not(target_arch = "wasm32"))]
 /// A basic example RpcContext, can be used to keep track of
 /// connected peers.
 #[derive(Debug, Clone)]
@@ -109,6 +159,7 @@ pub struct RpcContext {
     pub peer: SocketAddr,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 /// [`RpcHandler`] - a server-side event handler for RPC connections.
 #[async_trait]
 pub trait RpcHandler: Send + Sync + 'static {
@@ -129,6 +180,23 @@ pub trait RpcHandler: Send + Sync + 'static {
         Ok(())
     }
 
+    /// Protocol versions and capabilities supported by the server. If supplied,
+    /// each connection is opened by the protocol negotiation (performed before
+    /// [`RpcHandler::handshake()`]) and connections of clients that do not
+    /// negotiate or have no protocol version in common with the server are
+    /// rejected. The negotiated result is available via [`Messenger::negotiated()`].
+    fn negotiation(&self) -> Option<Negotiation> {
+        None
+    }
+
+    /// Heartbeat of idle connections (see [`crate::heartbeat`]). The heartbeat
+    /// requires the [`RpcHandler::negotiation()`]: it is only active for clients
+    /// that negotiate the [`HEARTBEAT_CAPABILITY`](crate::heartbeat::HEARTBEAT_CAPABILITY),
+    /// which is added to the capabilities of the server automatically.
+    fn heartbeat(&self) -> Option<Heartbeat> {
+        None
+    }
+
     /// [`RpcHandler::handshake()`] is called right after [`RpcHandler::connect()`]
     /// and is provided with a [`WebSocketSender`] and [`WebSocketReceiver`] channels
     /// which can be used to communicate with the underlying WebSocket connection
@@ -154,6 +222,7 @@ pub trait RpcHandler: Send + Sync + 'static {
     async fn disconnect(self: Arc<Self>, _ctx: Self::Context, _result: WebSocketResult<()>) {}
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 ///
 /// The [`Messenger`] struct is supplied to the [`RpcHandler::handshake()`] call at
 /// the connection negotiation time. This structure comes in as [`Arc<Messenger>`]
@@ -162,17 +231,53 @@ pub trait RpcHandler: Send + Sync + 'static {
 /// and [`Messenger::close`] that can be used to terminate the RPC connection with
 /// the client.
 ///
+/// When the connection is multiplexed (see [`Router`]), notifications must be
+/// posted using a messenger obtained via [`Messenger::with_namespace`].
+///
 #[derive(Debug)]
 pub struct Messenger {
     encoding: Encoding,
     sink: WebSocketSink,
+    namespace: Option<String>,
+    negotiated: Option<Arc<Negotiated>>,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl Messenger {
     pub fn new(encoding: Encoding, sink: &WebSocketSink) -> Self {
         Self {
             encoding,
             sink: sink.clone(),
+            namespace: None,
+            negotiated: None,
+        }
+    }
+
+    fn with_negotiated(mut self, negotiated: Option<Negotiated>) -> Self {
+        self.negotiated = negotiated.map(Arc::new);
+        self
+    }
+
+    /// Create a messenger posting notifications to the `namespace`
+    /// of a multiplexed connection (see [`Router`]).
+    pub fn with_namespace(&self, namespace: &str) -> Self {
+        Self {
+            encoding: self.encoding,
+            sink: self.sink.clone(),
+            namespace: Some(namespace.to_string()),
+            negotiated: self.negotiated.clone(),
+        }
+    }
+
+    /// Namespace of the messenger created via [`Messenger::with_namespace`]
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    fn envelope(&self, msg: Message) -> Result<Message> {
+        match &self.namespace {
+            Some(namespace) => router::wrap_message(namespace, msg),
+            None => Ok(msg),
         }
     }
 
@@ -184,24 +289,21 @@ impl Messenger {
         Ok(())
     }
 
-    /// Post notification message to the WebSocket connection
+    /// Post notification message to the WebSocket connection. Notifications
+    /// posted from within an RPC method handler carry the trace id of the
+    /// call (see [`trace`](crate::trace)).
     pub async fn notify<Ops, Msg>(&self, op: Ops, msg: Msg) -> Result<()>
     where
         Ops: OpsT,
         Msg: BorshSerialize + BorshDeserialize + Serialize + Send + Sync + 'static,
     {
-        match self.encoding {
-            Encoding::Borsh => {
-                self.sink
-                    .send(protocol::borsh::create_serialized_notification_message(
-                        op, msg,
-                    )?)?;
-            }
+        let msg = match self.encoding {
+            Encoding::Borsh => protocol::borsh::create_serialized_notification_message(op, msg)?,
             Encoding::SerdeJson => {
-                self.sink
-                    .send(protocol::serde_json::create_serialized_notification_message(op, msg)?)?;
+                protocol::serde_json::create_serialized_notification_message(op, msg)?
             }
-        }
+        };
+        self.sink.send(self.envelope(msg)?)?;
 
         Ok(())
     }
@@ -217,14 +319,13 @@ impl Messenger {
         Ops: OpsT,
         Msg: MsgT,
     {
-        match self.encoding {
-            Encoding::Borsh => Ok(protocol::borsh::create_serialized_notification_message(
-                op, msg,
-            )?),
+        let msg = match self.encoding {
+            Encoding::Borsh => protocol::borsh::create_serialized_notification_message(op, msg)?,
             Encoding::SerdeJson => {
-                Ok(protocol::serde_json::create_serialized_notification_message(op, msg)?)
+                protocol::serde_json::create_serialized_notification_message(op, msg)?
             }
-        }
+        };
+        self.envelope(msg)
     }
 
     /// Send a raw [`tungstenite::Message`] via the websocket tokio channel.
@@ -242,8 +343,25 @@ impl Messenger {
     pub fn encoding(&self) -> Encoding {
         self.encoding
     }
+
+    /// Protocol version and capabilities negotiated with the client
+    /// (if the [`RpcHandler`] supplies a [`Negotiation`]).
+    pub fn negotiated(&self) -> Option<&Negotiated> {
+        self.negotiated.as_deref()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// Connection context of the [`RpcWebSocketHandler`]
+struct RpcConnection<ConnectionContext> {
+    connection_ctx: ConnectionContext,
+    encoding: Encoding,
+    connection: Arc<Connection>,
+    limiter: Option<Arc<Limiter>>,
+    malformed: Arc<MalformedMessages>,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 /// WebSocket processor in charge of managing
 /// WRPC Request/Response interactions.
 #[derive(Clone)]
@@ -256,11 +374,13 @@ where
 {
     rpc_handler: Arc<dyn RpcHandler<Context = ConnectionContext>>,
     protocol: Arc<Protocol>,
+    connections: Connections,
     enable_async_handling: bool,
     _server_ctx: PhantomData<ServerContext>,
     _ops: PhantomData<Ops>,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl<ServerContext, ConnectionContext, Protocol, Ops>
     RpcWebSocketHandler<ServerContext, ConnectionContext, Protocol, Ops>
 where
@@ -272,12 +392,14 @@ where
     pub fn new(
         rpc_handler: Arc<dyn RpcHandler<Context = ConnectionContext>>,
         interface: Arc<Interface<ServerContext, ConnectionContext, Ops>>,
+        connections: Connections,
         enable_async_handling: bool,
     ) -> Self {
         let protocol = Arc::new(Protocol::new(interface));
         Self {
             rpc_handler,
             protocol,
+            connections,
             enable_async_handling,
             _server_ctx: PhantomData,
             _ops: PhantomData,
@@ -285,6 +407,7 @@ where
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 #[async_trait]
 impl<ServerContext, ConnectionContext, Protocol, Ops> WebSocketHandler
     for RpcWebSocketHandler<ServerContext, ConnectionContext, Protocol, Ops>
@@ -294,7 +417,7 @@ where
     ConnectionContext: Clone + Send + Sync + 'static,
     Protocol: ProtocolHandler<ServerContext, ConnectionContext, Ops> + Send + Sync + 'static,
 {
-    type Context = ConnectionContext;
+    type Context = RpcConnection<ConnectionContext>;
 
     fn accept(&self, peer: &SocketAddr) -> bool {
         self.rpc_handler.accept(peer)
@@ -305,7 +428,11 @@ where
     }
 
     async fn disconnect(self: &Arc<Self>, ctx: Self::Context, result: WebSocketResult<()>) {
-        self.rpc_handler.clone().disconnect(ctx, result).await
+        let result = self.connections.disconnect(&ctx.connection, result);
+        self.rpc_handler
+            .clone()
+            .disconnect(ctx.connection_ctx, result)
+            .await
     }
 
     async fn handshake(
@@ -315,38 +442,89 @@ where
         receiver: &mut WebSocketReceiver,
         sink: &WebSocketSink,
     ) -> WebSocketResult<Self::Context> {
-        let messenger = Arc::new(Messenger::new(self.protocol.encoding(), sink));
-
-        self.rpc_handler
+        let messenger = negotiation::messenger(
+            &self.rpc_handler,
+            self.protocol.encoding(),
+            None,
+            sender,
+            receiver,
+            sink,
+        )
+        .await?;
+
+        let connection_ctx = self
+            .rpc_handler
             .clone()
-            .handshake(peer, sender, receiver, messenger)
-            .await
+            .handshake(peer, sender, receiver, messenger.clone())
+            .await?;
+
+        let connection = self
+            .connections
+            .connect(&self.rpc_handler, peer, &messenger, sink);
+
+        Ok(RpcConnection {
+            connection_ctx,
+            encoding: self.protocol.encoding(),
+            connection,
+            limiter: self.protocol.connection_limiter(),
+            malformed: Arc::default(),
+        })
     }
 
     async fn message(
         self: &Arc<Self>,
-        connection_ctx: &Self::Context,
+        ctx: &Self::Context,
         msg: Message,
         sink: &WebSocketSink,
     ) -> WebSocketResult<()> {
-        let connection_ctx = (*connection_ctx).clone();
-        if self.enable_async_handling {
-            let sink = sink.clone();
-            let this = self.clone();
-            spawn(async move {
-                this.protocol
-                    .handle_message(connection_ctx, msg, &sink)
-                    .await
-            });
-            Ok(())
-        } else {
-            self.protocol
-                .handle_message(connection_ctx, msg, sink)
-                .await
-        }
+        dispatch(&self.protocol, ctx, msg, sink, self.enable_async_handling).await
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+/// Dispatch an incoming message of the connection to the `protocol`
+/// handler (within a new async task if `enable_async_handling` is set).
+async fn dispatch<ServerContext, ConnectionContext, Protocol, Ops>(
+    protocol: &Arc<Protocol>,
+    ctx: &RpcConnection<ConnectionContext>,
+    msg: Message,
+    sink: &WebSocketSink,
+    enable_async_handling: bool,
+) -> WebSocketResult<()>
+where
+    Ops: OpsT,
+    ServerContext: Clone + Send + Sync + 'static,
+    ConnectionContext: Clone + Send + Sync + 'static,
+    Protocol: ProtocolHandler<ServerContext, ConnectionContext, Ops> + Send + Sync + 'static,
+{
+    ctx.connection.seen();
+    if heartbeat::is_heartbeat(&msg) {
+        return Ok(());
+    }
+
+    let connection_ctx = ctx.connection_ctx.clone();
+    let limit = protocol.malformed_message_limit();
+    if enable_async_handling {
+        let sink = sink.clone();
+        let limiter = ctx.limiter.clone();
+        let malformed = ctx.malformed.clone();
+        let protocol = protocol.clone();
+        spawn(async move {
+            let result = protocol
+                .handle_message(connection_ctx, msg, &sink, limiter.as_deref())
+                .await;
+            malformed.track(result, limit, &sink)
+        });
+        Ok(())
+    } else {
+        let result = protocol
+            .handle_message(connection_ctx, msg, sink, ctx.limiter.as_deref())
+            .await;
+        ctx.malformed.track(result, limit, sink)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 /// [`RpcServer`] - a server-side object that listens
 /// for incoming websocket connections and delegates interaction
 /// with them to the supplied interfaces: [`RpcHandler`] (for RPC server
@@ -354,8 +532,10 @@ where
 #[derive(Clone)]
 pub struct RpcServer {
     ws_server: Arc<dyn WebSocketServerTrait>,
+    connections: Connections,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl RpcServer {
     /// Create a new [`RpcServer`] supplying an [`Arc`] of the previously-created
     /// [`RpcHandler`] trait and the [`Interface`] struct.
@@ -382,15 +562,24 @@ impl RpcServer {
         Protocol: ProtocolHandler<ServerContext, ConnectionContext, Ops> + Send + Sync + 'static,
         Ops: OpsT,
     {
+        let connections = Connections::default();
         let ws_handler = Arc::new(RpcWebSocketHandler::<
             ServerContext,
             ConnectionContext,
             Protocol,
             Ops,
-        >::new(rpc_handler, interface, enable_async_handling));
+        >::new(
+            rpc_handler,
+            interface,
+            connections.clone(),
+            enable_async_handling,
+        ));
 
         let ws_server = WebSocketServer::new(ws_handler, counters);
-        RpcServer { ws_server }
+        RpcServer {
+            ws_server,
+            connections,
+        }
     }
     /// Create a new [`RpcServer`] supplying an [`Arc`] of the previously-created
     /// [`RpcHandler`] trait and the [`Interface`] struct.
@@ -452,6 +641,97 @@ impl RpcServer {
         }
     }
 
+    /// Create a new [`RpcServer`] accepting both [`Encoding::Borsh`] and
+    /// [`Encoding::SerdeJson`] clients on the same listener. The encoding of
+    /// each connection is detected from the first frame received from the
+    /// client (see [`detect_encoding()`]): the negotiation request if the
+    /// [`RpcHandler`] supplies a [`Negotiation`], otherwise the first RPC
+    /// message. The connection is handled by the corresponding protocol
+    /// handler ([`BorshProtocol`] or [`JsonProtocol`]), both dispatching
+    /// to the same `interface`.
+    ///
+    /// As the [`RpcHandler::handshake()`] is performed once the encoding is
+    /// detected, the [`Messenger`] of a connection (and hence server-side
+    /// notifications) is not available until the client has sent its first
+    /// frame, and the [`RpcHandler::handshake()`] must not expect the client
+    /// to respond to a frame sent by the server first.
+    ///
+    /// The generics and `enable_async_handling` have the same meaning as in
+    /// [`RpcServer::new_with_encoding`].
+    pub fn new_with_auto_encoding<ServerContext, ConnectionContext, Ops, Id>(
+        rpc_handler: Arc<dyn RpcHandler<Context = ConnectionContext>>,
+        interface: Arc<Interface<ServerContext, ConnectionContext, Ops>>,
+        counters: Option<Arc<WebSocketCounters>>,
+        enable_async_handling: bool,
+    ) -> RpcServer
+    where
+        ServerContext: Clone + Send + Sync + 'static,
+        ConnectionContext: Clone + Send + Sync + 'static,
+        Ops: OpsT,
+        Id: IdT,
+    {
+        let connections = Connections::default();
+        let ws_handler = Arc::new(AutoWebSocketHandler::<
+            ServerContext,
+            ConnectionContext,
+            Ops,
+            Id,
+        >::new(
+            rpc_handler,
+            interface,
+            connections.clone(),
+            enable_async_handling,
+        ));
+
+        let ws_server = WebSocketServer::new(ws_handler, counters);
+        RpcServer {
+            ws_server,
+            connections,
+        }
+    }
+
+    /// Create a new [`RpcServer`] serving multiplexed connections (see
+    /// [`RpcMultiplexer`](crate::client::RpcMultiplexer)). Messages are
+    /// dispatched by the supplied [`Router`] to the [`Interface`] registered
+    /// for the namespace carried by each message. `enable_async_handling`
+    /// has the same meaning as in [`RpcServer::new_with_encoding`].
+    pub fn new_with_router<ConnectionContext>(
+        rpc_handler: Arc<dyn RpcHandler<Context = ConnectionContext>>,
+        router: Router<ConnectionContext>,
+        counters: Option<Arc<WebSocketCounters>>,
+        enable_async_handling: bool,
+    ) -> RpcServer
+    where
+        ConnectionContext: Clone + Send + Sync + 'static,
+    {
+        let connections = Connections::default();
+        let ws_handler = Arc::new(RouterWebSocketHandler::new(
+            rpc_handler,
+            router,
+            connections.clone(),
+            enable_async_handling,
+        ));
+
+        let ws_server = WebSocketServer::new(ws_handler, counters);
+        RpcServer {
+            ws_server,
+            connections,
+        }
+    }
+
+    /// Connections currently served (that have completed the handshake),
+    /// including the time the last message has been received from each
+    /// connection (see [`crate::heartbeat`]).
+    pub fn connections(&self) -> Vec<ConnectionInfo> {
+        self.connections.snapshot()
+    }
+
+    /// Number of connections currently served using each encoding
+    /// (see [`RpcServer::new_with_auto_encoding`])
+    pub fn connections_by_encoding(&self) -> Vec<(Encoding, usize)> {
+        self.connections.count_by_encoding()
+    }
+
     /// Bind network interface address to the `TcpListener`
     pub async fn bind(&self, addr: &str) -> WebSocketResult<TcpListener> {
         let addr = addr.replace("wrpc://", "");
@@ -0,0 +1,120 @@
+//!
+//! Server-side protocol negotiation (see [`crate::negotiation`]).
+//!
+
+use super::{Messenger, RpcHandler};
+use crate::heartbeat::HEARTBEAT_CAPABILITY;
+use crate::imports::*;
+use crate::negotiation::*;
+use futures::{SinkExt, StreamExt};
+use std::borrow::Cow;
+use tungstenite::protocol::frame::coding::CloseCode;
+use tungstenite::protocol::CloseFrame;
+use workflow_websocket::server::{
+    Error as WebSocketError, Message, Result as WebSocketResult, WebSocketReceiver,
+    WebSocketSender, WebSocketSink,
+};
+
+/// Time allowed for the client to send the negotiation request
+pub(crate) const NEGOTIATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl From<Frame> for Message {
+    fn from(frame: Frame) -> Self {
+        match frame {
+            Frame::Binary(data) => Message::Binary(data),
+            Frame::Text(text) => Message::Text(text),
+        }
+    }
+}
+
+async fn send(
+    sender: &mut WebSocketSender,
+    encoding: Encoding,
+    welcome: &Welcome,
+) -> WebSocketResult<()> {
+    let frame = encode(encoding, welcome).map_err(|err| WebSocketError::Other(err.to_string()))?;
+    sender.send(frame.into()).await?;
+    Ok(())
+}
+
+/// Receive the client negotiation request (unless already received as
+/// the `hello` frame) and respond with the negotiated version and
+/// capabilities. If there is no version supported by both sides, the
+/// connection is closed using the [`PROTOCOL_VERSION_MISMATCH`] close code.
+async fn negotiate(
+    negotiation: &Negotiation,
+    encoding: Encoding,
+    hello: Option<Message>,
+    sender: &mut WebSocketSender,
+    receiver: &mut WebSocketReceiver,
+) -> WebSocketResult<Negotiated> {
+    let msg = match hello {
+        Some(msg) => msg,
+        None => match tokio::time::timeout(NEGOTIATION_TIMEOUT, receiver.next()).await {
+            Ok(Some(Ok(msg))) => msg,
+            Ok(_) => return Err(WebSocketError::MalformedHandshake),
+            Err(_) => return Err(WebSocketError::ConnectionTimeout),
+        },
+    };
+    if !msg.is_binary() && !msg.is_text() {
+        return Err(WebSocketError::MalformedHandshake);
+    }
+    let hello: Hello =
+        decode(encoding, &msg.into_data()).map_err(|_| WebSocketError::MalformedHandshake)?;
+
+    match negotiation.negotiate(&hello.versions, &hello.capabilities) {
+        Some(negotiated) => {
+            send(sender, encoding, &Welcome::Accept(negotiated.clone())).await?;
+            Ok(negotiated)
+        }
+        None => {
+            let server = negotiation.versions();
+            send(sender, encoding, &Welcome::Reject { server }).await?;
+            let reason = format!(
+                "protocol version mismatch (client {}, server {server})",
+                hello.versions
+            );
+            sender
+                .send(Message::Close(Some(CloseFrame {
+                    code: CloseCode::Library(PROTOCOL_VERSION_MISMATCH),
+                    reason: Cow::Owned(reason.clone()),
+                })))
+                .await?;
+            Err(WebSocketError::NegotiationFailureWithReason(reason))
+        }
+    }
+}
+
+/// Create the [`Messenger`] of a new connection, negotiating the
+/// protocol first if the [`RpcHandler`] supplies a [`Negotiation`]
+/// (extended with the [`HEARTBEAT_CAPABILITY`] if the [`RpcHandler`]
+/// supplies a [`Heartbeat`](crate::heartbeat::Heartbeat)). The `hello`
+/// frame is the negotiation request if it has already been received.
+pub(crate) async fn messenger<ConnectionContext>(
+    rpc_handler: &Arc<dyn RpcHandler<Context = ConnectionContext>>,
+    encoding: Encoding,
+    hello: Option<Message>,
+    sender: &mut WebSocketSender,
+    receiver: &mut WebSocketReceiver,
+    sink: &WebSocketSink,
+) -> WebSocketResult<Arc<Messenger>>
+where
+    ConnectionContext: Send + Sync + 'static,
+{
+    let negotiation = rpc_handler.negotiation().map(|negotiation| {
+        if rpc_handler.heartbeat().is_some() {
+            negotiation.with_capability(HEARTBEAT_CAPABILITY)
+        } else {
+            negotiation
+        }
+    });
+    let negotiated = match negotiation {
+        Some(negotiation) => {
+            Some(negotiate(&negotiation, encoding, hello, sender, receiver).await?)
+        }
+        None => None,
+    };
+    Ok(Arc::new(
+        Messenger::new(encoding, sink).with_negotiated(negotiated),
+    ))
+}
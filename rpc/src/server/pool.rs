@@ -0,0 +1,147 @@
+//!
+//! Module containing the pool of reusable buffers used by the server
+//! to serialize `Borsh` response messages.
+//!
+//! The message header and the response payload are serialized in place
+//! into a single pooled buffer. As the WebSocket takes ownership of the
+//! outgoing message, a complete frame of up to [`POOLED_BUFFER_CAPACITY`]
+//! bytes is copied into an exactly-sized message (the only allocation made
+//! while responding) and the buffer is returned to the pool. Frames
+//! exceeding the capacity are handed over without copying and their
+//! buffers are released instead of being returned to the pool.
+//!
+
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Capacity of the pooled buffers
+pub const POOLED_BUFFER_CAPACITY: usize = 16 * 1024;
+/// Maximum number of idle buffers retained by each thread
+pub const POOL_SIZE: usize = 32;
+
+thread_local! {
+    static POOL: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+}
+
+static REUSED: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static OVERSIZED: AtomicUsize = AtomicUsize::new(0);
+
+/// Snapshot of the buffer pool statistics (accumulated across all threads)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PoolMetrics {
+    /// Buffers taken from the pool
+    pub reused: usize,
+    /// Buffers allocated because the pool was empty
+    pub allocated: usize,
+    /// Frames exceeding [`POOLED_BUFFER_CAPACITY`]
+    pub oversized: usize,
+}
+
+pub fn metrics() -> PoolMetrics {
+    PoolMetrics {
+        reused: REUSED.load(Ordering::Relaxed),
+        allocated: ALLOCATED.load(Ordering::Relaxed),
+        oversized: OVERSIZED.load(Ordering::Relaxed),
+    }
+}
+
+/// Buffer taken from the pool, returned to the pool of the
+/// current thread when dropped.
+pub struct PooledBuffer {
+    buffer: Vec<u8>,
+}
+
+impl PooledBuffer {
+    pub fn take() -> Self {
+        let buffer = match POOL.with(|pool| pool.borrow_mut().pop()) {
+            Some(buffer) => {
+                REUSED.fetch_add(1, Ordering::Relaxed);
+                buffer
+            }
+            None => {
+                ALLOCATED.fetch_add(1, Ordering::Relaxed);
+                Vec::with_capacity(POOLED_BUFFER_CAPACITY)
+            }
+        };
+        PooledBuffer { buffer }
+    }
+
+    /// Returns the contents of the buffer as a message frame.
+    pub fn into_frame(mut self) -> Vec<u8> {
+        if self.buffer.capacity() > POOLED_BUFFER_CAPACITY {
+            OVERSIZED.fetch_add(1, Ordering::Relaxed);
+            std::mem::take(&mut self.buffer)
+        } else {
+            self.buffer.as_slice().to_vec()
+        }
+    }
+}
+
+impl Deref for PooledBuffer {
+    type Target = Vec<u8>;
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buffer
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        let capacity = self.buffer.capacity();
+        if capacity == 0 || capacity > POOLED_BUFFER_CAPACITY {
+            return;
+        }
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        // the pool may be inaccessible while the thread is shutting down
+        POOL.try_with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if pool.len() < POOL_SIZE {
+                pool.push(buffer);
+            }
+        })
+        .ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pooled_buffer_reuse() {
+        // runs on a dedicated thread so that the pool is not shared with other tests
+        std::thread::spawn(|| {
+            let mut buffer = PooledBuffer::take();
+            buffer.extend_from_slice(&[1, 2, 3]);
+            let ptr = buffer.as_ptr();
+            assert_eq!(buffer.into_frame(), vec![1, 2, 3]);
+
+            let buffer = PooledBuffer::take();
+            assert!(buffer.is_empty());
+            assert_eq!(buffer.as_ptr(), ptr);
+            assert_eq!(POOL.with(|pool| pool.borrow().len()), 0);
+            drop(buffer);
+            assert_eq!(POOL.with(|pool| pool.borrow().len()), 1);
+
+            // oversized buffers are handed over and not returned to the pool
+            let mut buffer = PooledBuffer::take();
+            buffer.resize(POOLED_BUFFER_CAPACITY + 1, 0xff);
+            let ptr = buffer.as_ptr();
+            let frame = buffer.into_frame();
+            assert_eq!(frame.len(), POOLED_BUFFER_CAPACITY + 1);
+            assert_eq!(frame.as_ptr(), ptr);
+            assert_eq!(POOL.with(|pool| pool.borrow().len()), 0);
+            assert!(metrics().oversized > 0);
+        })
+        .join()
+        .unwrap();
+    }
+}
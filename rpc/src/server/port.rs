@@ -0,0 +1,362 @@
+//!
+//! [`PortServer`] - serves the RPC [`Interface`] over a `MessagePort`
+//! (WASM only), typically from within a Web Worker, to clients using
+//! the [`MessagePortTransport`](crate::client::MessagePortTransport).
+//!
+
+use super::error::Error;
+use super::pool::PooledBuffer;
+use super::result::Result;
+use super::Interface;
+use crate::imports::*;
+use crate::messages::{borsh, serde_json as json};
+use crate::port::Port;
+use crate::trace;
+use web_sys::MessagePort;
+use workflow_core::channel::{Channel, Sender};
+
+/// Notification and connection control interface of a `MessagePort`
+/// connection, supplied to the `connect` closure of [`PortServer::serve()`]
+/// (the equivalent of the `Messenger` of the native server).
+pub struct PortMessenger {
+    encoding: Encoding,
+    port: Arc<Port>,
+    sender: Sender<WebSocketMessage>,
+}
+
+impl PortMessenger {
+    /// Close the connection (the client can subsequently reconnect)
+    pub fn close(&self) -> Result<()> {
+        self.port
+            .post(WebSocketMessage::Close)
+            .map_err(Error::Port)?;
+        self.sender.try_send(WebSocketMessage::Close).ok();
+        Ok(())
+    }
+
+    /// Post notification message to the client
+    pub async fn notify<Ops, Msg>(&self, op: Ops, msg: Msg) -> Result<()>
+    where
+        Ops: OpsT,
+        Msg: BorshSerialize + Serialize + Send + Sync + 'static,
+    {
+        let msg = match self.encoding {
+            Encoding::Borsh => {
+                let payload = ::borsh::to_vec(&msg)?;
+                let data = borsh::BorshServerMessage::new(
+                    borsh::BorshServerMessageHeader::<Ops, ()>::new(
+                        None,
+                        borsh::ServerMessageKind::Notification,
+                        Some(op),
+                    )
+                    .with_trace(trace::current()),
+                    &payload,
+                )
+                .try_to_vec()?;
+                WebSocketMessage::Binary(data)
+            }
+            Encoding::SerdeJson => {
+                let payload = serde_json::to_value(msg)?;
+                WebSocketMessage::Text(serde_json::to_string(
+                    &json::JSONServerMessage::<Ops, ()>::new(None, Some(op), Some(payload), None)
+                        .with_trace(trace::current()),
+                )?)
+            }
+        };
+        self.port.post(msg).map_err(Error::Port)
+    }
+
+    /// Get encoding of the current messenger.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+}
+
+/// Server dispatching RPC calls received over `MessagePort` objects
+/// to the [`Interface`]. The connection is opened and closed by the
+/// client ([`RpcClient::connect()`](crate::client::RpcClient::connect)
+/// and [`RpcClient::shutdown()`](crate::client::RpcClient::shutdown)).
+///
+/// ```ignore
+/// // in the worker
+/// let server = PortServer::<ServerContext, ConnectionContext, Ops>::new(Encoding::Borsh, interface);
+/// // `port` is the MessagePort transferred to the worker
+/// server.serve(port, |messenger| ConnectionContext::new(messenger));
+/// ```
+pub struct PortServer<ServerContext, ConnectionContext, Ops, Id = Id64>
+where
+    ServerContext: Clone + Send + Sync + 'static,
+    ConnectionContext: Clone + Send + Sync + 'static,
+    Ops: OpsT,
+    Id: IdT,
+{
+    encoding: Encoding,
+    interface: Arc<Interface<ServerContext, ConnectionContext, Ops>>,
+    id: PhantomData<Id>,
+}
+
+impl<ServerContext, ConnectionContext, Ops, Id> Clone
+    for PortServer<ServerContext, ConnectionContext, Ops, Id>
+where
+    ServerContext: Clone + Send + Sync + 'static,
+    ConnectionContext: Clone + Send + Sync + 'static,
+    Ops: OpsT,
+    Id: IdT,
+{
+    fn clone(&self) -> Self {
+        Self {
+            encoding: self.encoding,
+            interface: self.interface.clone(),
+            id: PhantomData,
+        }
+    }
+}
+
+impl<ServerContext, ConnectionContext, Ops, Id>
+    PortServer<ServerContext, ConnectionContext, Ops, Id>
+where
+    ServerContext: Clone + Send + Sync + 'static,
+    ConnectionContext: Clone + Send + Sync + 'static,
+    Ops: OpsT,
+    Id: IdT,
+{
+    pub fn new(
+        encoding: Encoding,
+        interface: Arc<Interface<ServerContext, ConnectionContext, Ops>>,
+    ) -> Self {
+        Self {
+            encoding,
+            interface,
+            id: PhantomData,
+        }
+    }
+
+    /// Serve the RPC interface over the `port`. The `connect` closure
+    /// is called each time the client opens the connection and creates
+    /// the `ConnectionContext` supplied to the RPC method and notification
+    /// handlers. Calls are dispatched concurrently. The port is served
+    /// for the lifetime of the browser context (the connection can be
+    /// closed via [`PortMessenger::close()`] and reopened by the client).
+    pub fn serve<F>(&self, port: MessagePort, connect: F)
+    where
+        F: Fn(Arc<PortMessenger>) -> ConnectionContext + Send + Sync + 'static,
+    {
+        let channel = Channel::unbounded();
+        let port = Arc::new(Port::new(port, channel.sender.clone()));
+        let this = self.clone();
+        workflow_core::task::spawn(async move {
+            this.run(port, channel, connect).await;
+        });
+    }
+
+    async fn run<F>(&self, port: Arc<Port>, channel: Channel<WebSocketMessage>, connect: F)
+    where
+        F: Fn(Arc<PortMessenger>) -> ConnectionContext,
+    {
+        let mut connection_ctx = None;
+        let mut limiter = None;
+        while let Ok(message) = channel.receiver.recv().await {
+            match message {
+                WebSocketMessage::Open => {
+                    let messenger = Arc::new(PortMessenger {
+                        encoding: self.encoding,
+                        port: port.clone(),
+                        sender: channel.sender.clone(),
+                    });
+                    connection_ctx = Some(connect(messenger));
+                    limiter = self.interface.connection_limiter();
+                    port.post(WebSocketMessage::Open).unwrap_or_else(|err| {
+                        log_trace!("wRPC port server unable to acknowledge connection: {err}")
+                    });
+                }
+                WebSocketMessage::Close => {
+                    connection_ctx = None;
+                    limiter = None;
+                }
+                message => {
+                    let Some(connection_ctx) = connection_ctx.clone() else {
+                        log_trace!("wRPC port server: message received before connection");
+                        continue;
+                    };
+                    let this = self.clone();
+                    let port = port.clone();
+                    let limiter = limiter.clone();
+                    workflow_core::task::spawn(async move {
+                        if let Err(err) = this
+                            .dispatch(connection_ctx, message, &port, limiter.as_deref())
+                            .await
+                        {
+                            log_trace!("wRPC port server error: {err}");
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    async fn dispatch(
+        &self,
+        connection_ctx: ConnectionContext,
+        message: WebSocketMessage,
+        port: &Port,
+        limiter: Option<&super::Limiter>,
+    ) -> Result<()> {
+        let response = match (self.encoding, message) {
+            (Encoding::Borsh, WebSocketMessage::Binary(data)) => {
+                self.dispatch_borsh(connection_ctx, &data, limiter).await?
+            }
+            (Encoding::SerdeJson, WebSocketMessage::Text(text)) => {
+                self.dispatch_json(connection_ctx, &text, limiter).await?
+            }
+            _ => {
+                log_trace!("wRPC port server: invalid message type for the protocol");
+                None
+            }
+        };
+        if let Some(response) = response {
+            port.post(response).map_err(Error::Port)?;
+        }
+        Ok(())
+    }
+
+    async fn dispatch_borsh(
+        &self,
+        connection_ctx: ConnectionContext,
+        data: &[u8],
+        limiter: Option<&super::Limiter>,
+    ) -> Result<Option<WebSocketMessage>> {
+        let req: borsh::BorshClientMessage<Ops, Id> = match data.try_into() {
+            Ok(req) => req,
+            Err(err) => {
+                let id = borsh::recover_id::<Id>(data);
+                let err = ServerError::ParseError(err.to_string());
+                return Ok(id
+                    .map(|id| borsh_error::<Ops, Id>(Some(id), None, &err))
+                    .transpose()?);
+            }
+        };
+
+        let trace = req.header.trace;
+        if req.header.id.is_none() {
+            self.interface
+                .call_notification_with_borsh(&req.header.op, connection_ctx, req.payload)
+                .await
+                .unwrap_or_else(|err| {
+                    log_trace!("error handling client-side notification {}", err)
+                });
+            return Ok(None);
+        }
+
+        let mut frame = PooledBuffer::take();
+        borsh::BorshServerMessageHeader::<Ops, Id>::new(
+            req.header.id.clone(),
+            borsh::ServerMessageKind::Success,
+            Some(req.header.op.clone()),
+        )
+        .with_trace(trace)
+        .serialize(&mut *frame)?;
+
+        let result = trace::scope(
+            trace,
+            self.interface.call_method_with_borsh(
+                &req.header.op,
+                connection_ctx,
+                req.payload,
+                limiter,
+                &mut frame,
+            ),
+        )
+        .await;
+
+        let msg = match result {
+            Ok(()) => WebSocketMessage::Binary(frame.into_frame()),
+            Err(err) => {
+                drop(frame);
+                borsh_error::<Ops, Id>(req.header.id, trace, &err)?
+            }
+        };
+        Ok(Some(msg))
+    }
+
+    async fn dispatch_json(
+        &self,
+        connection_ctx: ConnectionContext,
+        text: &str,
+        limiter: Option<&super::Limiter>,
+    ) -> Result<Option<WebSocketMessage>> {
+        let req: json::JsonClientMessage<Ops, Id> = match serde_json::from_str(text) {
+            Ok(req) => req,
+            Err(err) => {
+                let id = json::recover_id::<Id>(text);
+                let err = ServerError::ParseError(err.to_string());
+                return Ok(id
+                    .map(|id| json_error::<Ops, Id>(Some(id), None, None, err))
+                    .transpose()?);
+            }
+        };
+
+        let trace = req.trace;
+        if req.id.is_none() {
+            self.interface
+                .call_notification_with_serde_json(&req.method, connection_ctx, req.params)
+                .await
+                .unwrap_or_else(|err| {
+                    log_trace!("error handling client-side notification {}", err)
+                });
+            return Ok(None);
+        }
+
+        let result = trace::scope(
+            trace,
+            self.interface.call_method_with_serde_json(
+                &req.method,
+                connection_ctx,
+                req.params,
+                limiter,
+            ),
+        )
+        .await;
+
+        let msg = match result {
+            Ok(payload) => WebSocketMessage::Text(serde_json::to_string(
+                &json::JSONServerMessage::new(req.id, Some(req.method), Some(payload), None)
+                    .with_trace(trace),
+            )?),
+            Err(err) => json_error::<Ops, Id>(req.id, Some(req.method), trace, err)?,
+        };
+        Ok(Some(msg))
+    }
+}
+
+fn borsh_error<Ops, Id>(
+    id: Option<Id>,
+    trace: Option<TraceId>,
+    err: &ServerError,
+) -> Result<WebSocketMessage>
+where
+    Ops: OpsT,
+    Id: IdT,
+{
+    let mut frame = PooledBuffer::take();
+    borsh::BorshServerMessageHeader::<Ops, Id>::new(id, borsh::ServerMessageKind::Error, None)
+        .with_trace(trace)
+        .serialize(&mut *frame)?;
+    BorshSerialize::serialize(err, &mut *frame)?;
+    Ok(WebSocketMessage::Binary(frame.into_frame()))
+}
+
+fn json_error<Ops, Id>(
+    id: Option<Id>,
+    method: Option<Ops>,
+    trace: Option<TraceId>,
+    err: ServerError,
+) -> Result<WebSocketMessage>
+where
+    Ops: OpsT,
+    Id: IdT,
+{
+    Ok(WebSocketMessage::Text(serde_json::to_string(
+        &json::JSONServerMessage::new(id, method, None, Some(json::JsonServerError::from(err)))
+            .with_trace(trace),
+    )?))
+}
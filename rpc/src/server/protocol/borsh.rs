@@ -7,9 +7,11 @@
 use super::Encoding;
 use crate::imports::*;
 use crate::messages::borsh::*;
+use crate::server::pool::PooledBuffer;
 pub use crate::server::result::Result;
-use crate::server::Interface;
 use crate::server::ProtocolHandler;
+use crate::server::{Interface, Limiter};
+use crate::trace;
 use workflow_websocket::server::{
     Error as WebSocketError, Message, Result as WebSocketResult, WebSocketSink,
 };
@@ -52,59 +54,84 @@ where
         Encoding::Borsh
     }
 
+    fn connection_limiter(&self) -> Option<Arc<Limiter>> {
+        self.interface.connection_limiter()
+    }
+
+    fn malformed_message_limit(&self) -> usize {
+        self.interface.malformed_message_limit()
+    }
+
     async fn handle_message(
         &self,
         connection_ctx: ConnectionContext,
         msg: Message,
         sink: &WebSocketSink,
+        limiter: Option<&Limiter>,
     ) -> WebSocketResult<()> {
         let data = &msg.into_data();
-        let req: BorshClientMessage<Ops, Id> = data
-            .try_into()
-            .map_err(|_| WebSocketError::MalformedMessage)?;
+        let req: BorshClientMessage<Ops, Id> = match data.try_into() {
+            Ok(req) => req,
+            Err(err) => {
+                if let Some(id) = recover_id::<Id>(data) {
+                    send_error::<Ops, Id>(
+                        sink,
+                        Some(id),
+                        None,
+                        &ServerError::ParseError(err.to_string()),
+                    );
+                }
+                return Err(WebSocketError::MalformedMessage);
+            }
+        };
 
+        let trace = req.header.trace;
         if req.header.id.is_some() {
-            let result = self
-                .interface
-                .call_method_with_borsh(&req.header.op, connection_ctx, req.payload)
-                .await;
+            // the header is written ahead of the response payload
+            // serialized by the method into the same buffer
+            let mut frame = PooledBuffer::take();
+            let header = BorshServerMessageHeader::<Ops, Id>::new(
+                req.header.id.clone(),
+                ServerMessageKind::Success,
+                Some(req.header.op.clone()),
+            )
+            .with_trace(trace);
+            if let Err(err) = header.serialize(&mut *frame) {
+                log_trace!("RPC response header serialization error: {err}");
+                return Ok(());
+            }
+
+            let result = trace::scope(
+                trace,
+                self.interface.call_method_with_borsh(
+                    &req.header.op,
+                    connection_ctx,
+                    req.payload,
+                    limiter,
+                    &mut frame,
+                ),
+            )
+            .await;
 
             match result {
-                Ok(data) => {
-                    if let Ok(msg) = BorshServerMessage::<Ops, Id>::new(
-                        BorshServerMessageHeader::new(
-                            req.header.id,
-                            ServerMessageKind::Success,
-                            Some(req.header.op),
-                        ),
-                        &data,
-                    )
-                    .try_to_vec()
-                    {
-                        if let Err(e) = sink.send(msg.into()) {
-                            log_trace!("Sink error: {:?}", e);
-                        }
+                Ok(()) => {
+                    if let Err(e) = sink.send(Message::Binary(frame.into_frame())) {
+                        log_trace!("Sink error: {:?}", e);
                     }
                 }
                 Err(err) => {
-                    // log_trace!("RPC server error: {:?} req: {:#?}", err, req);
+                    drop(frame);
+                    log_trace!(
+                        "RPC server error: {err} op: {:?} trace: {}",
+                        req.header.op,
+                        trace.map(|trace| trace.to_string()).unwrap_or_default()
+                    );
                     if err == ServerError::Close {
                         return Err(WebSocketError::ServerClose);
-                    } else if let Ok(err_vec) = borsh::to_vec(&err) {
-                        if let Ok(msg) = BorshServerMessage::new(
-                            BorshServerMessageHeader::<Ops, Id>::new(
-                                req.header.id,
-                                ServerMessageKind::Error,
-                                None,
-                            ),
-                            &err_vec,
-                        )
-                        .try_to_vec()
-                        {
-                            if let Err(e) = sink.send(msg.into()) {
-                                log_trace!("Sink error: {:?}", e);
-                            }
-                        }
+                    }
+                    send_error::<Ops, Id>(sink, req.header.id, trace, &err);
+                    if err == ServerError::ReqDeserialize {
+                        return Err(WebSocketError::MalformedMessage);
                     }
                 }
             }
@@ -128,6 +155,31 @@ where
     }
 }
 
+/// Responds with the [`ServerMessageKind::Error`] message carrying the `err`.
+fn send_error<Ops, Id>(
+    sink: &WebSocketSink,
+    id: Option<Id>,
+    trace: Option<TraceId>,
+    err: &ServerError,
+) where
+    Ops: OpsT,
+    Id: IdT,
+{
+    let mut frame = PooledBuffer::take();
+    let header = BorshServerMessageHeader::<Ops, Id>::new(id, ServerMessageKind::Error, None)
+        .with_trace(trace);
+    let serialized = header
+        .serialize(&mut *frame)
+        .and_then(|_| BorshSerialize::serialize(err, &mut *frame));
+    if serialized.is_ok() {
+        if let Err(e) = sink.send(Message::Binary(frame.into_frame())) {
+            log_trace!("Sink error: {:?}", e);
+        }
+    }
+}
+
+/// Serializes the notification message, attaching the trace id of the
+/// RPC call handled by the current task (see [`trace::current()`]).
 pub fn create_serialized_notification_message<Ops, Msg>(op: Ops, msg: Msg) -> Result<Message>
 where
     Ops: OpsT,
@@ -135,7 +187,8 @@ where
 {
     let payload = borsh::to_vec(&msg)?;
     let data = BorshServerMessage::new(
-        BorshServerMessageHeader::<Ops, ()>::new(None, ServerMessageKind::Notification, Some(op)),
+        BorshServerMessageHeader::<Ops, ()>::new(None, ServerMessageKind::Notification, Some(op))
+            .with_trace(trace::current()),
         &payload,
     )
     .try_to_vec()?;
@@ -9,7 +9,7 @@ pub mod serde_json;
 
 use crate::imports::*;
 pub use crate::server::result::Result;
-use crate::server::Interface;
+use crate::server::{Interface, Limiter};
 use workflow_websocket::server::{Message, Result as WebSocketResult, WebSocketSink};
 
 pub use self::borsh::BorshProtocol;
@@ -30,11 +30,25 @@ where
 
     fn encoding(&self) -> Encoding;
 
+    /// Create the per-connection [`Limiter`] configured via
+    /// [`Interface::set_connection_concurrency()`]
+    fn connection_limiter(&self) -> Option<Arc<Limiter>>;
+
+    /// Number of consecutive malformed messages after which the connection
+    /// is closed (see [`Interface::set_malformed_message_limit()`])
+    fn malformed_message_limit(&self) -> usize;
+
+    /// Handle an incoming message. Method calls are subject to the
+    /// connection `limiter` (if any) in addition to the method limits.
+    /// Messages that can not be decoded are answered with an error frame
+    /// (if the request id is recoverable) and reported by returning
+    /// [`WebSocketError::MalformedMessage`](workflow_websocket::server::Error::MalformedMessage).
     async fn handle_message(
         &self,
         connection_ctx: ConnectionContext,
         message: Message,
         sink: &WebSocketSink,
+        limiter: Option<&Limiter>,
     ) -> WebSocketResult<()>;
 
     fn serialize_notification_message<Msg>(
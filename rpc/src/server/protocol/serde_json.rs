@@ -7,8 +7,9 @@ use super::Encoding;
 use crate::imports::*;
 use crate::messages::serde_json::*;
 pub use crate::server::result::Result;
-use crate::server::Interface;
 use crate::server::ProtocolHandler;
+use crate::server::{Interface, Limiter};
+use crate::trace;
 use workflow_websocket::server::{
     Error as WebSocketError, Message, Result as WebSocketResult, WebSocketSink,
 };
@@ -51,50 +52,77 @@ where
         Encoding::SerdeJson
     }
 
+    fn connection_limiter(&self) -> Option<Arc<Limiter>> {
+        self.interface.connection_limiter()
+    }
+
+    fn malformed_message_limit(&self) -> usize {
+        self.interface.malformed_message_limit()
+    }
+
     async fn handle_message(
         &self,
         connection_ctx: ConnectionContext,
         msg: Message,
         sink: &WebSocketSink,
+        limiter: Option<&Limiter>,
     ) -> WebSocketResult<()> {
-        let text = &msg.into_text()?;
-        let req: JsonClientMessage<Ops, Id> =
-            serde_json::from_str(text).map_err(|_| WebSocketError::MalformedMessage)?;
+        let text = &msg
+            .into_text()
+            .map_err(|_| WebSocketError::MalformedMessage)?;
+        let req: JsonClientMessage<Ops, Id> = match serde_json::from_str(text) {
+            Ok(req) => req,
+            Err(err) => {
+                if let Some(id) = recover_id::<Id>(text) {
+                    send_error::<Ops, Id>(
+                        sink,
+                        Some(id),
+                        None,
+                        None,
+                        ServerError::ParseError(err.to_string()),
+                    );
+                }
+                return Err(WebSocketError::MalformedMessage);
+            }
+        };
 
+        let trace = req.trace;
         if req.id.is_some() {
-            let result = self
-                .interface
-                .call_method_with_serde_json(&req.method, connection_ctx, req.params)
-                .await;
+            let result = trace::scope(
+                trace,
+                self.interface.call_method_with_serde_json(
+                    &req.method,
+                    connection_ctx,
+                    req.params,
+                    limiter,
+                ),
+            )
+            .await;
 
             match result {
                 Ok(payload) => {
-                    if let Ok(msg) = serde_json::to_string(&JSONServerMessage::new(
-                        req.id,
-                        Some(req.method),
-                        Some(payload),
-                        None,
-                    )) {
+                    if let Ok(msg) = serde_json::to_string(
+                        &JSONServerMessage::new(req.id, Some(req.method), Some(payload), None)
+                            .with_trace(trace),
+                    ) {
                         if let Err(e) = sink.send(msg.into()) {
                             log_trace!("Sink error: {:?}", e);
                         }
                     }
                 }
                 Err(err) => {
+                    log_trace!(
+                        "RPC server error: {err} method: {:?} trace: {}",
+                        req.method,
+                        trace.map(|trace| trace.to_string()).unwrap_or_default()
+                    );
                     if err == ServerError::Close {
                         return Err(WebSocketError::ServerClose);
-                    } else {
-                        let server_err = JsonServerError::from(err);
-                        if let Ok(msg) = serde_json::to_string(&JSONServerMessage::new(
-                            req.id,
-                            Some(req.method),
-                            None,
-                            Some(server_err),
-                        )) {
-                            if let Err(e) = sink.send(msg.into()) {
-                                log_trace!("Sink error: {:?}", e);
-                            }
-                        }
+                    }
+                    let malformed = err == ServerError::ReqDeserialize;
+                    send_error::<Ops, Id>(sink, req.id, Some(req.method), trace, err);
+                    if malformed {
+                        return Err(WebSocketError::MalformedMessage);
                     }
                 }
             }
@@ -117,17 +145,38 @@ where
     }
 }
 
+/// Responds with the message carrying the [`JsonServerError`] created from `err`.
+fn send_error<Ops, Id>(
+    sink: &WebSocketSink,
+    id: Option<Id>,
+    method: Option<Ops>,
+    trace: Option<TraceId>,
+    err: ServerError,
+) where
+    Ops: OpsT,
+    Id: IdT,
+{
+    if let Ok(msg) = serde_json::to_string(
+        &JSONServerMessage::new(id, method, None, Some(JsonServerError::from(err)))
+            .with_trace(trace),
+    ) {
+        if let Err(e) = sink.send(msg.into()) {
+            log_trace!("Sink error: {:?}", e);
+        }
+    }
+}
+
+/// Serializes the notification message, attaching the trace id of the
+/// RPC call handled by the current task (see [`trace::current()`]).
 pub fn create_serialized_notification_message<Ops, Msg>(op: Ops, msg: Msg) -> Result<Message>
 where
     Ops: OpsT,
     Msg: Serialize + Send + Sync + 'static,
 {
     let payload = serde_json::to_value(msg)?;
-    let json = serde_json::to_string(&JSONServerMessage::<Ops, ()>::new(
-        None,
-        Some(op),
-        Some(payload),
-        None,
-    ))?;
+    let json = serde_json::to_string(
+        &JSONServerMessage::<Ops, ()>::new(None, Some(op), Some(payload), None)
+            .with_trace(trace::current()),
+    )?;
     Ok(Message::Text(json))
 }
@@ -0,0 +1,369 @@
+//!
+//! Module containing the [`Router`] that dispatches messages of
+//! multiplexed connections (see [`RpcMultiplexer`](crate::client::RpcMultiplexer))
+//! to per-namespace [`Interface`]s served by a single listener.
+//!
+
+use super::connections::{Connection, Connections};
+use super::malformed::{MalformedMessages, DEFAULT_MALFORMED_MESSAGE_LIMIT};
+use super::{
+    heartbeat, BorshProtocol, Interface, JsonProtocol, Limiter, ProtocolHandler, RpcHandler,
+    SocketAddr,
+};
+use crate::imports::*;
+use crate::messages::envelope;
+use crate::server::result::Result;
+use workflow_core::task::spawn;
+use workflow_websocket::server::{
+    Error as WebSocketError, Message, Result as WebSocketResult, WebSocketHandler,
+    WebSocketReceiver, WebSocketSender, WebSocketSink,
+};
+
+/// Wraps the message in the envelope carrying the `namespace`.
+pub(crate) fn wrap_message(namespace: &str, msg: Message) -> Result<Message> {
+    match msg {
+        Message::Binary(data) => Ok(Message::Binary(envelope::wrap_binary(namespace, &data))),
+        Message::Text(text) => Ok(Message::Text(envelope::wrap_text(namespace, &text)?)),
+        msg => Ok(msg),
+    }
+}
+
+fn unwrap_message(msg: Message) -> WebSocketResult<(String, Message)> {
+    match msg {
+        Message::Binary(data) => {
+            let (namespace, payload) =
+                envelope::unwrap_binary(&data).map_err(|_| WebSocketError::MalformedMessage)?;
+            Ok((namespace, Message::Binary(payload.to_vec())))
+        }
+        Message::Text(text) => {
+            let (namespace, message) =
+                envelope::unwrap_text(&text).map_err(|_| WebSocketError::MalformedMessage)?;
+            Ok((namespace, Message::Text(message)))
+        }
+        _ => Err(WebSocketError::MalformedMessage),
+    }
+}
+
+/// Protocol handler of a namespace, retained by the [`Router`]
+/// without the `ServerContext` and `Ops` generics.
+#[async_trait]
+trait Route<ConnectionContext>: Send + Sync + 'static {
+    fn connection_limiter(&self) -> Option<Arc<Limiter>>;
+
+    async fn handle_message(
+        &self,
+        connection_ctx: ConnectionContext,
+        msg: Message,
+        sink: &WebSocketSink,
+        limiter: Option<&Limiter>,
+    ) -> WebSocketResult<()>;
+}
+
+struct ProtocolRoute<ServerContext, ConnectionContext, Protocol, Ops> {
+    protocol: Protocol,
+    _server_ctx: PhantomData<ServerContext>,
+    _connection_ctx: PhantomData<ConnectionContext>,
+    _ops: PhantomData<Ops>,
+}
+
+#[async_trait]
+impl<ServerContext, ConnectionContext, Protocol, Ops> Route<ConnectionContext>
+    for ProtocolRoute<ServerContext, ConnectionContext, Protocol, Ops>
+where
+    Ops: OpsT,
+    ServerContext: Clone + Send + Sync + 'static,
+    ConnectionContext: Clone + Send + Sync + 'static,
+    Protocol: ProtocolHandler<ServerContext, ConnectionContext, Ops> + Send + Sync + 'static,
+{
+    fn connection_limiter(&self) -> Option<Arc<Limiter>> {
+        self.protocol.connection_limiter()
+    }
+
+    async fn handle_message(
+        &self,
+        connection_ctx: ConnectionContext,
+        msg: Message,
+        sink: &WebSocketSink,
+        limiter: Option<&Limiter>,
+    ) -> WebSocketResult<()> {
+        self.protocol
+            .handle_message(connection_ctx, msg, sink, limiter)
+            .await
+    }
+}
+
+///
+/// [`Router`] carries a mapping of namespaces to [`Interface`]s served
+/// over multiplexed connections using [`RpcServer::new_with_router`](super::RpcServer::new_with_router).
+/// All interfaces share the `ConnectionContext` produced by the
+/// [`RpcHandler`], while each interface retains its own `ServerContext`.
+///
+/// ```ignore
+/// let mut router = Router::new(Encoding::Borsh);
+/// router.register::<_, WalletOps, Id64>("wallet", wallet_interface);
+/// router.register::<_, NodeOps, Id64>("node", node_interface);
+/// let server = RpcServer::new_with_router(handler, router, None, false);
+/// ```
+///
+pub struct Router<ConnectionContext>
+where
+    ConnectionContext: Clone + Send + Sync + 'static,
+{
+    encoding: Encoding,
+    routes: AHashMap<String, Arc<dyn Route<ConnectionContext>>>,
+    malformed_message_limit: usize,
+}
+
+impl<ConnectionContext> Router<ConnectionContext>
+where
+    ConnectionContext: Clone + Send + Sync + 'static,
+{
+    /// Create a router using the supplied [`Encoding`] for all namespaces.
+    pub fn new(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            routes: AHashMap::new(),
+            malformed_message_limit: DEFAULT_MALFORMED_MESSAGE_LIMIT,
+        }
+    }
+
+    /// Disconnect clients after `limit` consecutive malformed messages
+    /// received on any of the namespaces (see [`Interface::set_malformed_message_limit()`],
+    /// the limits of the registered interfaces are not used by the router).
+    pub fn set_malformed_message_limit(&mut self, limit: usize) {
+        self.malformed_message_limit = limit;
+    }
+
+    /// Register the `interface` serving the `namespace`.
+    pub fn register<ServerContext, Ops, Id>(
+        &mut self,
+        namespace: &str,
+        interface: Arc<Interface<ServerContext, ConnectionContext, Ops>>,
+    ) where
+        ServerContext: Clone + Send + Sync + 'static,
+        Ops: OpsT,
+        Id: IdT,
+    {
+        let route: Arc<dyn Route<ConnectionContext>> = match self.encoding {
+            Encoding::Borsh => Arc::new(ProtocolRoute {
+                protocol: BorshProtocol::<ServerContext, ConnectionContext, Ops, Id>::new(
+                    interface,
+                ),
+                _server_ctx: PhantomData,
+                _connection_ctx: PhantomData,
+                _ops: PhantomData,
+            }),
+            Encoding::SerdeJson => Arc::new(ProtocolRoute {
+                protocol: JsonProtocol::<ServerContext, ConnectionContext, Ops, Id>::new(interface),
+                _server_ctx: PhantomData,
+                _connection_ctx: PhantomData,
+                _ops: PhantomData,
+            }),
+        };
+
+        if self.routes.insert(namespace.to_string(), route).is_some() {
+            panic!("RPC namespace `{namespace}` is registered multiple times")
+        }
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Registered namespaces
+    pub fn namespaces(&self) -> impl Iterator<Item = &str> {
+        self.routes.keys().map(String::as_str)
+    }
+}
+
+/// Connection context of the [`RouterWebSocketHandler`] retaining
+/// per-namespace sinks that wrap outgoing messages in the envelope
+/// and per-namespace connection limiters (see [`Interface::set_connection_concurrency()`]).
+pub(crate) struct RouterContext<ConnectionContext> {
+    connection_ctx: ConnectionContext,
+    connection: Arc<Connection>,
+    sinks: AHashMap<String, WebSocketSink>,
+    limiters: AHashMap<String, Arc<Limiter>>,
+    malformed: Arc<MalformedMessages>,
+}
+
+/// WebSocket processor dispatching messages of multiplexed
+/// connections to the [`Router`] namespaces.
+pub(crate) struct RouterWebSocketHandler<ConnectionContext>
+where
+    ConnectionContext: Clone + Send + Sync + 'static,
+{
+    rpc_handler: Arc<dyn RpcHandler<Context = ConnectionContext>>,
+    router: Router<ConnectionContext>,
+    connections: Connections,
+    enable_async_handling: bool,
+}
+
+impl<ConnectionContext> RouterWebSocketHandler<ConnectionContext>
+where
+    ConnectionContext: Clone + Send + Sync + 'static,
+{
+    pub fn new(
+        rpc_handler: Arc<dyn RpcHandler<Context = ConnectionContext>>,
+        router: Router<ConnectionContext>,
+        connections: Connections,
+        enable_async_handling: bool,
+    ) -> Self {
+        Self {
+            rpc_handler,
+            router,
+            connections,
+            enable_async_handling,
+        }
+    }
+
+    /// Creates a sink relaying messages wrapped in the `namespace`
+    /// envelope to the connection `sink`. The relay task terminates
+    /// once all clones of the returned sink are dropped.
+    fn namespace_sink(namespace: &str, sink: &WebSocketSink) -> WebSocketSink {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<Message>();
+        let namespace = namespace.to_string();
+        let sink = sink.clone();
+        spawn(async move {
+            while let Some(msg) = receiver.recv().await {
+                match wrap_message(&namespace, msg) {
+                    Ok(msg) => {
+                        if sink.send(msg).is_err() {
+                            break;
+                        }
+                    }
+                    Err(err) => log_trace!("wRPC envelope error: {err}"),
+                }
+            }
+        });
+        sender
+    }
+}
+
+#[async_trait]
+impl<ConnectionContext> WebSocketHandler for RouterWebSocketHandler<ConnectionContext>
+where
+    ConnectionContext: Clone + Send + Sync + 'static,
+{
+    type Context = RouterContext<ConnectionContext>;
+
+    fn accept(&self, peer: &SocketAddr) -> bool {
+        self.rpc_handler.accept(peer)
+    }
+
+    async fn connect(self: &Arc<Self>, peer: &SocketAddr) -> WebSocketResult<()> {
+        self.rpc_handler.clone().connect(peer).await
+    }
+
+    async fn disconnect(self: &Arc<Self>, ctx: Self::Context, result: WebSocketResult<()>) {
+        let result = self.connections.disconnect(&ctx.connection, result);
+        self.rpc_handler
+            .clone()
+            .disconnect(ctx.connection_ctx, result)
+            .await
+    }
+
+    async fn handshake(
+        self: &Arc<Self>,
+        peer: &SocketAddr,
+        sender: &mut WebSocketSender,
+        receiver: &mut WebSocketReceiver,
+        sink: &WebSocketSink,
+    ) -> WebSocketResult<Self::Context> {
+        let messenger = super::negotiation::messenger(
+            &self.rpc_handler,
+            self.router.encoding,
+            None,
+            sender,
+            receiver,
+            sink,
+        )
+        .await?;
+
+        let connection_ctx = self
+            .rpc_handler
+            .clone()
+            .handshake(peer, sender, receiver, messenger.clone())
+            .await?;
+
+        let connection = self
+            .connections
+            .connect(&self.rpc_handler, peer, &messenger, sink);
+
+        let sinks = self
+            .router
+            .namespaces()
+            .map(|namespace| (namespace.to_string(), Self::namespace_sink(namespace, sink)))
+            .collect();
+
+        let limiters = self
+            .router
+            .routes
+            .iter()
+            .filter_map(|(namespace, route)| {
+                route
+                    .connection_limiter()
+                    .map(|limiter| (namespace.clone(), limiter))
+            })
+            .collect();
+
+        Ok(RouterContext {
+            connection_ctx,
+            connection,
+            sinks,
+            limiters,
+            malformed: Arc::default(),
+        })
+    }
+
+    async fn message(
+        self: &Arc<Self>,
+        ctx: &Self::Context,
+        msg: Message,
+        sink: &WebSocketSink,
+    ) -> WebSocketResult<()> {
+        ctx.connection.seen();
+        if heartbeat::is_heartbeat(&msg) {
+            return Ok(());
+        }
+
+        let limit = self.router.malformed_message_limit;
+        let (namespace, msg) = match unwrap_message(msg) {
+            Ok(unwrapped) => unwrapped,
+            Err(err) => return ctx.malformed.track(Err(err), limit, sink),
+        };
+        let (Some(route), Some(namespace_sink)) = (
+            self.router.routes.get(&namespace),
+            ctx.sinks.get(&namespace),
+        ) else {
+            log_trace!("wRPC: no interface registered for namespace `{namespace}`");
+            return Ok(());
+        };
+
+        let connection_ctx = ctx.connection_ctx.clone();
+        let limiter = ctx.limiters.get(&namespace);
+        if self.enable_async_handling {
+            let route = route.clone();
+            let sink = namespace_sink.clone();
+            let limiter = limiter.cloned();
+            let malformed = ctx.malformed.clone();
+            spawn(async move {
+                let result = route
+                    .handle_message(connection_ctx, msg, &sink, limiter.as_deref())
+                    .await;
+                malformed.track(result, limit, &sink)
+            });
+            Ok(())
+        } else {
+            let result = route
+                .handle_message(
+                    connection_ctx,
+                    msg,
+                    namespace_sink,
+                    limiter.map(Arc::as_ref),
+                )
+                .await;
+            ctx.malformed.track(result, limit, namespace_sink)
+        }
+    }
+}
@@ -0,0 +1,1221 @@
+use crate::client::{
+    ConnectOptions, Error as ClientError, Idempotent, Interface as ClientInterface, Notification,
+    Options as RpcClientOptions, RpcClient, RpcMultiplexer,
+};
+use crate::encoding::Encoding;
+use crate::error::ServerError;
+use crate::heartbeat::HEARTBEAT_CAPABILITY;
+use crate::id::Id64;
+use crate::negotiation::{Negotiation, VersionRange};
+use crate::server::{
+    Heartbeat, Interface, Messenger, Method, OverloadPolicy, Router, RpcHandler, RpcServer,
+    SocketAddr, WebSocketReceiver, WebSocketResult, WebSocketSender,
+};
+use crate::trace::TraceId;
+use crate::types::OpsT;
+use async_trait::async_trait;
+use borsh::{BorshDeserialize, BorshSerialize};
+use futures::future::join_all;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use workflow_core::channel::{unbounded, Sender};
+
+#[derive(
+    Clone, Debug, Eq, PartialEq, Hash, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+enum WalletOps {
+    Balance,
+    Notify,
+}
+
+#[derive(
+    Clone, Debug, Eq, PartialEq, Hash, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+enum NodeOps {
+    Height,
+    Notify,
+}
+
+#[derive(Clone)]
+struct ConnectionContext {
+    messenger: Arc<Messenger>,
+}
+
+struct TestRpcHandler;
+
+#[async_trait]
+impl RpcHandler for TestRpcHandler {
+    type Context = ConnectionContext;
+
+    async fn handshake(
+        self: Arc<Self>,
+        _peer: &SocketAddr,
+        _sender: &mut WebSocketSender,
+        _receiver: &mut WebSocketReceiver,
+        messenger: Arc<Messenger>,
+    ) -> WebSocketResult<ConnectionContext> {
+        Ok(ConnectionContext { messenger })
+    }
+}
+
+#[derive(
+    Clone, Debug, Eq, PartialEq, Hash, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+enum ProtocolOps {
+    Negotiated,
+}
+
+/// Server supporting protocol versions 1 and 2
+struct NegotiatingRpcHandler;
+
+#[async_trait]
+impl RpcHandler for NegotiatingRpcHandler {
+    type Context = ConnectionContext;
+
+    fn negotiation(&self) -> Option<Negotiation> {
+        Some(Negotiation::new(VersionRange::new(1, 2)).with_capabilities(["batch", "compress"]))
+    }
+
+    async fn handshake(
+        self: Arc<Self>,
+        _peer: &SocketAddr,
+        _sender: &mut WebSocketSender,
+        _receiver: &mut WebSocketReceiver,
+        messenger: Arc<Messenger>,
+    ) -> WebSocketResult<ConnectionContext> {
+        Ok(ConnectionContext { messenger })
+    }
+}
+
+async fn server(encoding: Encoding, addr: &str) -> RpcServer {
+    let mut wallet = Interface::<(), ConnectionContext, WalletOps>::new(());
+    wallet.method(
+        WalletOps::Balance,
+        crate::server::method!(|_connection_ctx, _server_ctx, req: u64| async move { Ok(req * 2) }),
+    );
+
+    // the node `Height` call also posts a notification
+    // to the node namespace of the calling connection
+    let mut node = Interface::<(), ConnectionContext, NodeOps>::new(());
+    node.method(
+        NodeOps::Height,
+        crate::server::method!(
+            |_server_ctx, connection_ctx: ConnectionContext, req: u64| async move {
+                connection_ctx
+                    .messenger
+                    .with_namespace("node")
+                    .notify(NodeOps::Notify, req)
+                    .await
+                    .unwrap();
+                Ok(req + 1000)
+            }
+        ),
+    );
+
+    let mut router = Router::new(encoding);
+    router.register::<_, WalletOps, Id64>("wallet", Arc::new(wallet));
+    router.register::<_, NodeOps, Id64>("node", Arc::new(node));
+
+    let server = RpcServer::new_with_router(Arc::new(TestRpcHandler), router, None, true);
+    let listener = server.bind(addr).await.unwrap();
+    let server_ = server.clone();
+    workflow_core::task::spawn(async move {
+        server_.listen(listener, None).await.ok();
+    });
+    server
+}
+
+fn notification_interface<Ops>(op: Ops, sender: Sender<u64>) -> ClientInterface<Ops>
+where
+    Ops: OpsT,
+{
+    let mut interface = ClientInterface::<Ops>::new();
+    interface.notification(
+        op,
+        Notification::new(move |msg: u64| {
+            let sender = sender.clone();
+            Box::pin(async move {
+                sender.try_send(msg).unwrap();
+                Ok(())
+            })
+        }),
+    );
+    interface
+}
+
+#[tokio::test]
+async fn test_multiplexer() {
+    for (encoding, port) in [(Encoding::Borsh, 19121), (Encoding::SerdeJson, 19122)] {
+        let addr = format!("127.0.0.1:{port}");
+        let server = server(encoding, &addr).await;
+
+        let multiplexer = RpcMultiplexer::new(
+            encoding,
+            RpcClientOptions::new().with_url(&format!("ws://{addr}")),
+            None,
+        )
+        .unwrap();
+
+        let (wallet_sender, wallet_receiver) = unbounded();
+        let (node_sender, node_receiver) = unbounded();
+        let wallet: RpcClient<WalletOps> = multiplexer
+            .channel(
+                "wallet",
+                notification_interface(WalletOps::Notify, wallet_sender).into(),
+            )
+            .unwrap();
+        let node: RpcClient<NodeOps> = multiplexer
+            .channel(
+                "node",
+                notification_interface(NodeOps::Notify, node_sender).into(),
+            )
+            .unwrap();
+        assert!(matches!(
+            multiplexer.channel::<NodeOps, Id64>("node", None),
+            Err(crate::client::Error::DuplicateNamespace(_))
+        ));
+
+        multiplexer
+            .connect(ConnectOptions::blocking_fallback())
+            .await
+            .unwrap();
+        // connecting a channel is a no-op once the socket is connected
+        wallet
+            .connect(ConnectOptions::blocking_fallback())
+            .await
+            .unwrap();
+        assert!(wallet.is_connected() && node.is_connected());
+
+        // concurrent calls on both namespaces over one connection
+        let wallet_calls = (0..32u64).map(|v| {
+            let wallet = wallet.clone();
+            async move { wallet.call::<u64, u64>(WalletOps::Balance, v).await }
+        });
+        let node_calls = (0..32u64).map(|v| {
+            let node = node.clone();
+            async move { node.call::<u64, u64>(NodeOps::Height, v).await }
+        });
+        let (wallet_results, node_results) =
+            futures::join!(join_all(wallet_calls), join_all(node_calls));
+        for (v, result) in wallet_results.into_iter().enumerate() {
+            assert_eq!(result.unwrap(), v as u64 * 2);
+        }
+        for (v, result) in node_results.into_iter().enumerate() {
+            assert_eq!(result.unwrap(), v as u64 + 1000);
+        }
+
+        // node notifications are routed to the node interface only
+        let mut notifications = Vec::new();
+        for _ in 0..32 {
+            notifications.push(node_receiver.recv().await.unwrap());
+        }
+        notifications.sort();
+        assert_eq!(notifications, (0..32).collect::<Vec<u64>>());
+        assert!(wallet_receiver.is_empty());
+
+        // closing one channel retains the shared socket
+        wallet.shutdown().await.unwrap();
+        assert!(!wallet.is_connected());
+        assert!(wallet
+            .call::<u64, u64>(WalletOps::Balance, 1)
+            .await
+            .is_err());
+        assert_eq!(multiplexer.namespaces(), vec!["node".to_string()]);
+        assert!(multiplexer.is_connected());
+        assert_eq!(
+            node.call::<u64, u64>(NodeOps::Height, 1).await.unwrap(),
+            1001
+        );
+        assert_eq!(node_receiver.recv().await.unwrap(), 1);
+
+        // closing the last channel disconnects the socket
+        node.shutdown().await.unwrap();
+        assert!(!node.is_connected());
+        assert!(multiplexer.namespaces().is_empty());
+
+        server.stop_and_join().await.unwrap();
+    }
+}
+
+async fn negotiating_server(encoding: Encoding, addr: &str) -> RpcServer {
+    // responds with the negotiated version and the availability of the requested capability
+    let mut interface = Interface::<(), ConnectionContext, ProtocolOps>::new(());
+    interface.method(
+        ProtocolOps::Negotiated,
+        crate::server::method!(|_server_ctx,
+                                connection_ctx: ConnectionContext,
+                                capability: String| async move {
+            let negotiated = connection_ctx.messenger.negotiated().cloned().unwrap();
+            Ok((negotiated.version(), negotiated.has(&capability)))
+        }),
+    );
+
+    let server = RpcServer::new_with_encoding::<_, _, _, Id64>(
+        encoding,
+        Arc::new(NegotiatingRpcHandler),
+        Arc::new(interface),
+        None,
+        true,
+    );
+    let listener = server.bind(addr).await.unwrap();
+    let server_ = server.clone();
+    workflow_core::task::spawn(async move {
+        server_.listen(listener, None).await.ok();
+    });
+    server
+}
+
+fn negotiating_client(
+    encoding: Encoding,
+    addr: &str,
+    versions: VersionRange,
+) -> RpcClient<ProtocolOps> {
+    let negotiation = Negotiation::new(versions).with_capabilities(["batch", "stream"]);
+    RpcClient::new_with_encoding(
+        encoding,
+        None,
+        RpcClientOptions::new()
+            .with_url(&format!("ws://{addr}"))
+            .with_negotiation(negotiation),
+        None,
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_negotiation() {
+    let server = Negotiation::new(VersionRange::new(1, 2)).with_capability("batch");
+    assert_eq!(
+        server
+            .negotiate(&VersionRange::new(2, 3), &[])
+            .unwrap()
+            .version(),
+        2
+    );
+    assert_eq!(VersionRange::new(3, 4).to_string(), "v3-v4");
+
+    for (encoding, port) in [(Encoding::Borsh, 19123), (Encoding::SerdeJson, 19124)] {
+        let addr = format!("127.0.0.1:{port}");
+        let server = negotiating_server(encoding, &addr).await;
+
+        // v1-only client against the v1+v2 server
+        let client = negotiating_client(encoding, &addr, VersionRange::single(1));
+        client
+            .connect(ConnectOptions::blocking_fallback())
+            .await
+            .unwrap();
+        let negotiated = client.negotiated().unwrap();
+        assert_eq!(negotiated.version(), 1);
+        assert_eq!(negotiated.capabilities(), ["batch".to_string()]);
+        assert_eq!(
+            client
+                .call::<String, (u32, bool)>(ProtocolOps::Negotiated, "batch".to_string())
+                .await
+                .unwrap(),
+            (1, true)
+        );
+        assert_eq!(
+            client
+                .call::<String, (u32, bool)>(ProtocolOps::Negotiated, "stream".to_string())
+                .await
+                .unwrap(),
+            (1, false)
+        );
+        client.shutdown().await.unwrap();
+
+        // disjoint versions
+        let client = negotiating_client(encoding, &addr, VersionRange::new(3, 4));
+        let err = client
+            .connect(ConnectOptions::blocking_fallback())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ClientError::ProtocolVersionMismatch { client, server }
+                if client == VersionRange::new(3, 4) && server == VersionRange::new(1, 2)
+        ));
+        assert!(client.negotiated().is_none());
+        client.shutdown().await.unwrap();
+
+        server.stop_and_join().await.unwrap();
+    }
+}
+
+#[derive(
+    Clone, Debug, Eq, PartialEq, Hash, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+enum LimitedOps {
+    Double,
+}
+
+type LimitedInterface = Interface<(), ConnectionContext, LimitedOps>;
+
+/// Server with a method limited to 2 concurrent calls, recording
+/// the highest number of calls observed executing at the same time.
+async fn limited_server(
+    addr: &str,
+    policy: OverloadPolicy,
+) -> (RpcServer, Arc<LimitedInterface>, Arc<AtomicUsize>) {
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let peak_ = peak.clone();
+
+    let mut interface = LimitedInterface::new(());
+    interface.method(
+        LimitedOps::Double,
+        Method::new(move |_server_ctx, _connection_ctx, value: u64| {
+            let running = running.clone();
+            let peak = peak_.clone();
+            Box::pin(async move {
+                let current = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(value * 2)
+            })
+        })
+        .with_max_concurrency(2)
+        .with_overload_policy(policy),
+    );
+    let interface = Arc::new(interface);
+
+    let server = RpcServer::new_with_encoding::<_, _, _, Id64>(
+        Encoding::Borsh,
+        Arc::new(TestRpcHandler),
+        interface.clone(),
+        None,
+        true,
+    );
+    let listener = server.bind(addr).await.unwrap();
+    let server_ = server.clone();
+    workflow_core::task::spawn(async move {
+        server_.listen(listener, None).await.ok();
+    });
+    (server, interface, peak)
+}
+
+async fn limited_client(addr: &str) -> RpcClient<LimitedOps> {
+    let client = RpcClient::new_with_encoding(
+        Encoding::Borsh,
+        None,
+        RpcClientOptions::new().with_url(&format!("ws://{addr}")),
+        None,
+    )
+    .unwrap();
+    client
+        .connect(ConnectOptions::blocking_fallback())
+        .await
+        .unwrap();
+    client
+}
+
+#[tokio::test]
+async fn test_method_concurrency_limit() {
+    let addr = "127.0.0.1:19125";
+    let (server, interface, peak) = limited_server(addr, OverloadPolicy::Queue(32)).await;
+    let client = Arc::new(limited_client(addr).await);
+
+    let calls = join_all((0..20u64).map(|value| {
+        let client = client.clone();
+        async move { client.call::<u64, u64>(LimitedOps::Double, value).await }
+    }));
+    // requests may reach the server spread over several TCP segments,
+    // so the metrics are sampled once the limit has been saturated
+    let metrics = async {
+        for _ in 0..100 {
+            let metrics = interface.metrics();
+            if metrics
+                .iter()
+                .any(|(_, metrics)| metrics.in_flight == 2 && metrics.queued > 0)
+            {
+                return metrics;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        interface.metrics()
+    };
+    let (results, mut metrics) = futures::join!(calls, metrics);
+
+    let (op, metrics) = metrics.pop().unwrap();
+    assert_eq!(op, LimitedOps::Double);
+    assert_eq!(metrics.max_concurrency, 2);
+    assert_eq!(metrics.in_flight, 2);
+    assert!(metrics.queued > 0);
+
+    for (value, result) in results.into_iter().enumerate() {
+        assert_eq!(result.unwrap(), value as u64 * 2);
+    }
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+    let (_, metrics) = interface.metrics().pop().unwrap();
+    assert_eq!((metrics.in_flight, metrics.queued), (0, 0));
+
+    client.shutdown().await.unwrap();
+    server.stop_and_join().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_method_concurrency_reject() {
+    let addr = "127.0.0.1:19126";
+    let (server, _interface, peak) = limited_server(addr, OverloadPolicy::Reject).await;
+    let client = Arc::new(limited_client(addr).await);
+
+    let results = join_all((0..20u64).map(|value| {
+        let client = client.clone();
+        async move {
+            (
+                value,
+                client.call::<u64, u64>(LimitedOps::Double, value).await,
+            )
+        }
+    }))
+    .await;
+
+    let mut accepted = 0;
+    for (value, result) in results {
+        match result {
+            Ok(doubled) => {
+                assert_eq!(doubled, value * 2);
+                accepted += 1;
+            }
+            Err(err) => assert!(
+                matches!(err.untraced(), ClientError::RpcCall(ServerError::Busy)),
+                "unexpected error: {err}"
+            ),
+        }
+    }
+    assert!((2..20).contains(&accepted));
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+
+    client.shutdown().await.unwrap();
+    server.stop_and_join().await.unwrap();
+}
+
+#[derive(
+    Clone, Debug, Eq, PartialEq, Hash, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+enum CountingOps {
+    Get,
+    Put,
+}
+
+/// Server counting the calls received (the wire messages)
+async fn counting_server(encoding: Encoding, addr: &str) -> (RpcServer, Arc<AtomicUsize>) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut interface = Interface::<(), ConnectionContext, CountingOps>::new(());
+    for op in [CountingOps::Get, CountingOps::Put] {
+        let calls = calls.clone();
+        interface.method(
+            op,
+            Method::new(move |_server_ctx, _connection_ctx, value: u64| {
+                let calls = calls.clone();
+                Box::pin(async move {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok(value + 1)
+                })
+            }),
+        );
+    }
+
+    let server = RpcServer::new_with_encoding::<_, _, _, Id64>(
+        encoding,
+        Arc::new(TestRpcHandler),
+        Arc::new(interface),
+        None,
+        true,
+    );
+    let listener = server.bind(addr).await.unwrap();
+    let server_ = server.clone();
+    workflow_core::task::spawn(async move {
+        server_.listen(listener, None).await.ok();
+    });
+    (server, calls)
+}
+
+#[tokio::test]
+async fn test_idempotent_call_deduplication() {
+    for (encoding, port) in [(Encoding::Borsh, 19127), (Encoding::SerdeJson, 19128)] {
+        let addr = format!("127.0.0.1:{port}");
+        let (server, calls) = counting_server(encoding, &addr).await;
+        let client = RpcClient::<CountingOps>::new_with_encoding(
+            encoding,
+            None,
+            RpcClientOptions::new().with_url(&format!("ws://{addr}")),
+            None,
+        )
+        .unwrap();
+        client
+            .connect(ConnectOptions::blocking_fallback())
+            .await
+            .unwrap();
+        client.register_idempotent(
+            CountingOps::Get,
+            Idempotent::new().with_ttl(Duration::from_millis(500)),
+        );
+
+        // three concurrent identical calls produce one wire message
+        let results = join_all((0..3).map(|_| client.call::<u64, u64>(CountingOps::Get, 1))).await;
+        assert!(results.into_iter().all(|result| result.unwrap() == 2));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // repeat call within the TTL is served from the cache
+        assert_eq!(
+            client.call::<u64, u64>(CountingOps::Get, 1).await.unwrap(),
+            2
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // a different request is issued
+        assert_eq!(
+            client.call::<u64, u64>(CountingOps::Get, 2).await.unwrap(),
+            3
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // ops not registered as idempotent (mutating) are always issued
+        let results = join_all((0..3).map(|_| client.call::<u64, u64>(CountingOps::Put, 1))).await;
+        assert!(results.into_iter().all(|result| result.unwrap() == 2));
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+
+        client.unregister_idempotent(&CountingOps::Get);
+        assert_eq!(
+            client.call::<u64, u64>(CountingOps::Get, 1).await.unwrap(),
+            2
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+
+        client.shutdown().await.unwrap();
+        server.stop_and_join().await.unwrap();
+    }
+}
+
+#[derive(
+    Clone, Debug, Eq, PartialEq, Hash, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+enum TracingOps {
+    Call,
+    Fail,
+    Notify,
+}
+
+/// Server recording the trace id seen by the method handlers;
+/// the `Call` method posts a notification from within the handler
+async fn tracing_server(
+    encoding: Encoding,
+    addr: &str,
+) -> (RpcServer, Arc<Mutex<Vec<Option<TraceId>>>>) {
+    let traces = Arc::new(Mutex::new(Vec::new()));
+    let mut interface = Interface::<(), ConnectionContext, TracingOps>::new(());
+    let traces_ = traces.clone();
+    interface.method(
+        TracingOps::Call,
+        Method::new(
+            move |_server_ctx, connection_ctx: ConnectionContext, value: u64| {
+                let traces = traces_.clone();
+                Box::pin(async move {
+                    traces.lock().unwrap().push(crate::trace::current());
+                    connection_ctx
+                        .messenger
+                        .notify(TracingOps::Notify, value)
+                        .await
+                        .unwrap();
+                    Ok(value)
+                })
+            },
+        ),
+    );
+    let traces_ = traces.clone();
+    interface.method(
+        TracingOps::Fail,
+        Method::new(move |_server_ctx, _connection_ctx, _value: u64| {
+            let traces = traces_.clone();
+            Box::pin(async move {
+                traces.lock().unwrap().push(crate::trace::current());
+                Err::<u64, _>(ServerError::Text("failure".to_string()))
+            })
+        }),
+    );
+
+    let server = RpcServer::new_with_encoding::<_, _, _, Id64>(
+        encoding,
+        Arc::new(TestRpcHandler),
+        Arc::new(interface),
+        None,
+        true,
+    );
+    let listener = server.bind(addr).await.unwrap();
+    let server_ = server.clone();
+    workflow_core::task::spawn(async move {
+        server_.listen(listener, None).await.ok();
+    });
+    (server, traces)
+}
+
+#[tokio::test]
+async fn test_trace_propagation() {
+    for (encoding, port) in [(Encoding::Borsh, 19129), (Encoding::SerdeJson, 19130)] {
+        let addr = format!("127.0.0.1:{port}");
+        let (server, traces) = tracing_server(encoding, &addr).await;
+
+        let (sender, receiver) = unbounded();
+        let mut interface = ClientInterface::<TracingOps>::new();
+        interface.notification(
+            TracingOps::Notify,
+            Notification::new_with_trace(move |trace, msg: u64| {
+                let sender = sender.clone();
+                Box::pin(async move {
+                    sender.try_send((trace, msg)).unwrap();
+                    Ok(())
+                })
+            }),
+        );
+        let client = RpcClient::<TracingOps>::new_with_encoding(
+            encoding,
+            interface.into(),
+            RpcClientOptions::new().with_url(&format!("ws://{addr}")),
+            None,
+        )
+        .unwrap();
+        client
+            .connect(ConnectOptions::blocking_fallback())
+            .await
+            .unwrap();
+
+        // the notification posted by the handler carries the trace id of the call
+        for value in [1u64, 2] {
+            assert_eq!(
+                client
+                    .call::<u64, u64>(TracingOps::Call, value)
+                    .await
+                    .unwrap(),
+                value
+            );
+            let (trace, msg) = receiver.recv().await.unwrap();
+            assert_eq!(msg, value);
+            let handled = traces.lock().unwrap().last().cloned().unwrap();
+            assert!(handled.is_some());
+            assert_eq!(trace, handled);
+        }
+        // each call has its own trace id
+        let handled = traces.lock().unwrap().clone();
+        assert_ne!(handled[0], handled[1]);
+
+        // errors expose the trace id of the call
+        let err = client
+            .call::<u64, u64>(TracingOps::Fail, 0)
+            .await
+            .unwrap_err();
+        let handled = traces.lock().unwrap().last().cloned().unwrap();
+        assert_eq!(err.trace_id(), handled);
+        assert!(err.to_string().contains(&handled.unwrap().to_string()));
+        assert!(!matches!(err.untraced(), ClientError::Traced { .. }));
+
+        // there is no trace scope outside of the method handlers
+        assert_eq!(crate::trace::current(), None);
+
+        client.shutdown().await.unwrap();
+        server.stop_and_join().await.unwrap();
+    }
+}
+
+#[derive(
+    Clone, Debug, Eq, PartialEq, Hash, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+enum FuzzOps {
+    Echo,
+    Sum,
+}
+
+fn fuzz_interface() -> Arc<Interface<(), (), FuzzOps>> {
+    let mut interface = Interface::<(), (), FuzzOps>::new(());
+    interface.method(
+        FuzzOps::Echo,
+        crate::server::method!(|_connection_ctx, _server_ctx, req: String| async move { Ok(req) }),
+    );
+    interface.method(
+        FuzzOps::Sum,
+        crate::server::method!(|_connection_ctx, _server_ctx, req: Vec<u64>| async move {
+            Ok(req.into_iter().fold(0u64, u64::wrapping_add))
+        }),
+    );
+    Arc::new(interface)
+}
+
+fn fuzz_id(id: u64) -> Id64 {
+    Id64::try_from_slice(&id.to_le_bytes()).unwrap()
+}
+
+/// Random mutation of a valid message: bit flips, truncation,
+/// byte insertion or splicing of random bytes
+fn mutate(rng: &mut StdRng, mut data: Vec<u8>) -> Vec<u8> {
+    for _ in 0..rng.gen_range(1..4) {
+        match rng.gen_range(0..4) {
+            0 if !data.is_empty() => {
+                let i = rng.gen_range(0..data.len());
+                data[i] ^= 1 << rng.gen_range(0..8);
+            }
+            1 if !data.is_empty() => data.truncate(rng.gen_range(0..data.len())),
+            2 => {
+                let i = rng.gen_range(0..=data.len());
+                data.insert(i, rng.gen());
+            }
+            _ => {
+                let i = rng.gen_range(0..=data.len());
+                let bytes: Vec<u8> = (0..rng.gen_range(1..16)).map(|_| rng.gen()).collect();
+                data.splice(i..i, bytes);
+            }
+        }
+    }
+    data
+}
+
+#[tokio::test]
+async fn test_borsh_malformed_fuzz() {
+    use crate::messages::borsh::*;
+    use crate::server::{BorshProtocol, ProtocolHandler, WebSocketError};
+
+    let protocol = BorshProtocol::<(), (), FuzzOps, Id64>::new(fuzz_interface());
+    let (sink, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut rng = StdRng::seed_from_u64(0x5eed);
+
+    let valid = [
+        to_ws_msg(
+            BorshReqHeader::new(Some(fuzz_id(1)), FuzzOps::Echo),
+            &borsh::to_vec("hello").unwrap(),
+        ),
+        to_ws_msg(
+            BorshReqHeader::new(Some(fuzz_id(2)), FuzzOps::Sum),
+            &borsh::to_vec(&vec![1u64, 2, 3]).unwrap(),
+        ),
+    ]
+    .map(Vec::<u8>::from);
+
+    for i in 0..5000 {
+        let data = match i % 3 {
+            0 => (0..rng.gen_range(0..48)).map(|_| rng.gen()).collect(),
+            _ => mutate(&mut rng, valid[i % 2].clone()),
+        };
+        let result = protocol.handle_message((), data.into(), &sink, None).await;
+        assert!(matches!(
+            result,
+            Ok(()) | Err(WebSocketError::MalformedMessage)
+        ));
+        // every response must be a well-formed frame
+        while let Ok(msg) = receiver.try_recv() {
+            let data = msg.into_data();
+            let msg = BorshServerMessage::<FuzzOps, Id64>::try_from(data.as_slice()).unwrap();
+            if let ServerMessageKind::Error = msg.header.kind {
+                ServerError::try_from_slice(msg.payload).unwrap();
+            }
+        }
+    }
+
+    // unknown op with a recoverable id is answered with a parse error
+    let mut data = borsh::to_vec(&Some(fuzz_id(7))).unwrap();
+    data.push(0xff);
+    let result = protocol.handle_message((), data.into(), &sink, None).await;
+    assert!(matches!(result, Err(WebSocketError::MalformedMessage)));
+    let data = receiver.try_recv().unwrap().into_data();
+    let msg = BorshServerMessage::<FuzzOps, Id64>::try_from(data.as_slice()).unwrap();
+    assert_eq!(msg.header.id, Some(fuzz_id(7)));
+    assert!(matches!(
+        ServerError::try_from_slice(msg.payload).unwrap(),
+        ServerError::ParseError(_)
+    ));
+
+    // length prefixes exceeding the message are rejected without allocation
+    for prefix in [u32::MAX, u32::MAX / 8, 1 << 20] {
+        let msg = to_ws_msg(
+            BorshReqHeader::new(Some(fuzz_id(8)), FuzzOps::Sum),
+            &prefix.to_le_bytes(),
+        );
+        let result = protocol
+            .handle_message((), Vec::<u8>::from(msg).into(), &sink, None)
+            .await;
+        assert!(matches!(result, Err(WebSocketError::MalformedMessage)));
+        let data = receiver.try_recv().unwrap().into_data();
+        let msg = BorshServerMessage::<FuzzOps, Id64>::try_from(data.as_slice()).unwrap();
+        assert_eq!(msg.header.id, Some(fuzz_id(8)));
+        assert_eq!(
+            ServerError::try_from_slice(msg.payload).unwrap(),
+            ServerError::ReqDeserialize
+        );
+    }
+
+    // undecodable messages without an id are dropped
+    let result = protocol
+        .handle_message((), vec![0x02].into(), &sink, None)
+        .await;
+    assert!(matches!(result, Err(WebSocketError::MalformedMessage)));
+    assert!(receiver.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_json_malformed_fuzz() {
+    use crate::messages::serde_json::*;
+    use crate::server::{JsonProtocol, Message, ProtocolHandler, WebSocketError};
+
+    let protocol = JsonProtocol::<(), (), FuzzOps, Id64>::new(fuzz_interface());
+    let (sink, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut rng = StdRng::seed_from_u64(0x5eed);
+
+    let valid = [
+        r#"{"id":1,"method":"Echo","params":"hello"}"#,
+        r#"{"id":2,"method":"Sum","params":[1,2,3]}"#,
+    ];
+
+    for i in 0..5000 {
+        let data = mutate(&mut rng, valid[i % 2].as_bytes().to_vec());
+        let msg = match String::from_utf8(data) {
+            Ok(text) => Message::Text(text),
+            Err(err) => Message::Binary(err.into_bytes()),
+        };
+        let result = protocol.handle_message((), msg, &sink, None).await;
+        assert!(matches!(
+            result,
+            Ok(()) | Err(WebSocketError::MalformedMessage)
+        ));
+        while let Ok(msg) = receiver.try_recv() {
+            let msg: JSONServerMessage<FuzzOps, Id64> =
+                serde_json::from_str(&msg.into_text().unwrap()).unwrap();
+            if let Some(error) = msg.error {
+                assert!(error.server_error().is_some());
+            }
+        }
+    }
+
+    // unknown method with a recoverable id is answered with a parse error
+    let msg = Message::Text(r#"{"id":7,"method":"Bogus","params":null}"#.to_string());
+    let result = protocol.handle_message((), msg, &sink, None).await;
+    assert!(matches!(result, Err(WebSocketError::MalformedMessage)));
+    let msg: JSONServerMessage<FuzzOps, Id64> =
+        serde_json::from_str(&receiver.try_recv().unwrap().into_text().unwrap()).unwrap();
+    assert_eq!(msg.id, Some(fuzz_id(7)));
+    assert!(matches!(
+        msg.error.unwrap().server_error(),
+        Some(ServerError::ParseError(_))
+    ));
+
+    // undecodable messages without an id are dropped
+    let msg = Message::Text(r#"{"method":"#.to_string());
+    let result = protocol.handle_message((), msg, &sink, None).await;
+    assert!(matches!(result, Err(WebSocketError::MalformedMessage)));
+    assert!(receiver.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_borsh_pooled_responses() {
+    use crate::messages::borsh::*;
+    use crate::server::pool::POOLED_BUFFER_CAPACITY;
+    use crate::server::{BorshProtocol, ProtocolHandler};
+
+    let protocol = BorshProtocol::<(), (), FuzzOps, Id64>::new(fuzz_interface());
+    let (sink, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+    // responses below and above the pooled buffer capacity
+    // are framed identically to separately encoded messages
+    for (id, len) in [0, 100, POOLED_BUFFER_CAPACITY, POOLED_BUFFER_CAPACITY * 4]
+        .into_iter()
+        .enumerate()
+    {
+        let text = "x".repeat(len);
+        let msg = to_ws_msg(
+            BorshReqHeader::new(Some(fuzz_id(id as u64)), FuzzOps::Echo),
+            &borsh::to_vec(&text).unwrap(),
+        );
+        let msg = Vec::<u8>::from(msg).into();
+        protocol.handle_message((), msg, &sink, None).await.unwrap();
+
+        let payload = borsh::to_vec(&Ok::<_, ServerError>(text)).unwrap();
+        let expected = BorshServerMessage::new(
+            BorshServerMessageHeader::<FuzzOps, Id64>::new(
+                Some(fuzz_id(id as u64)),
+                ServerMessageKind::Success,
+                Some(FuzzOps::Echo),
+            ),
+            &payload,
+        )
+        .try_to_vec()
+        .unwrap();
+        assert_eq!(receiver.try_recv().unwrap().into_data(), expected);
+    }
+
+    // error responses reuse the buffer holding the success header
+    let msg = to_ws_msg(
+        BorshReqHeader::new(Some(fuzz_id(9)), FuzzOps::Sum),
+        &borsh::to_vec("not a vector").unwrap(),
+    );
+    let msg = Vec::<u8>::from(msg).into();
+    assert!(protocol.handle_message((), msg, &sink, None).await.is_err());
+    let data = receiver.try_recv().unwrap().into_data();
+    let msg = BorshServerMessage::<FuzzOps, Id64>::try_from(data.as_slice()).unwrap();
+    assert!(matches!(msg.header.kind, ServerMessageKind::Error));
+    assert_eq!(
+        ServerError::try_from_slice(msg.payload).unwrap(),
+        ServerError::ReqDeserialize
+    );
+}
+
+/// Server sending heartbeat pings after 100ms of inactivity and
+/// closing connections after 2 missed pongs, reporting disconnections
+struct HeartbeatRpcHandler {
+    disconnects: Sender<WebSocketResult<()>>,
+}
+
+#[async_trait]
+impl RpcHandler for HeartbeatRpcHandler {
+    type Context = ConnectionContext;
+
+    fn negotiation(&self) -> Option<Negotiation> {
+        Some(Negotiation::new(VersionRange::single(1)))
+    }
+
+    fn heartbeat(&self) -> Option<Heartbeat> {
+        Some(Heartbeat::new(Duration::from_millis(100), 2))
+    }
+
+    async fn handshake(
+        self: Arc<Self>,
+        _peer: &SocketAddr,
+        _sender: &mut WebSocketSender,
+        _receiver: &mut WebSocketReceiver,
+        messenger: Arc<Messenger>,
+    ) -> WebSocketResult<ConnectionContext> {
+        Ok(ConnectionContext { messenger })
+    }
+
+    async fn disconnect(self: Arc<Self>, _ctx: ConnectionContext, result: WebSocketResult<()>) {
+        self.disconnects.try_send(result).ok();
+    }
+}
+
+#[tokio::test]
+async fn test_heartbeat_reaping() {
+    for (encoding, port) in [(Encoding::Borsh, 19131), (Encoding::SerdeJson, 19132)] {
+        let addr = format!("127.0.0.1:{port}");
+        let (disconnects, disconnected) = unbounded();
+        let mut interface = Interface::<(), ConnectionContext, ProtocolOps>::new(());
+        interface.method(
+            ProtocolOps::Negotiated,
+            crate::server::method!(|_server_ctx,
+                                    connection_ctx: ConnectionContext,
+                                    capability: String| async move {
+                let negotiated = connection_ctx.messenger.negotiated().cloned().unwrap();
+                Ok((negotiated.version(), negotiated.has(&capability)))
+            }),
+        );
+        let server = RpcServer::new_with_encoding::<_, _, _, Id64>(
+            encoding,
+            Arc::new(HeartbeatRpcHandler { disconnects }),
+            Arc::new(interface),
+            None,
+            true,
+        );
+        let listener = server.bind(&addr).await.unwrap();
+        let server_ = server.clone();
+        workflow_core::task::spawn(async move {
+            server_.listen(listener, None).await.ok();
+        });
+
+        // the client responding to pings remains connected while idle
+        let client = negotiating_client(encoding, &addr, VersionRange::single(1));
+        client
+            .connect(ConnectOptions::blocking_fallback())
+            .await
+            .unwrap();
+        assert!(client.negotiated().unwrap().has(HEARTBEAT_CAPABILITY));
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        assert!(client.is_connected());
+        let connections = server.connections();
+        assert_eq!(connections.len(), 1);
+        assert!(connections[0].heartbeat);
+        assert!(connections[0].idle < Duration::from_millis(300));
+        assert_eq!(
+            client
+                .call::<String, (u32, bool)>(
+                    ProtocolOps::Negotiated,
+                    HEARTBEAT_CAPABILITY.to_string()
+                )
+                .await
+                .unwrap(),
+            (1, true)
+        );
+        client.shutdown().await.unwrap();
+        let result = disconnected.recv().await.unwrap();
+        assert!(!matches!(
+            result,
+            Err(crate::server::WebSocketError::ConnectionTimeout)
+        ));
+
+        // the client not responding to pings is reaped after
+        // 3 intervals of inactivity (the third ping is not sent)
+        let client = negotiating_client(encoding, &addr, VersionRange::single(1));
+        client.disable_heartbeat_responder();
+        let start = std::time::Instant::now();
+        client
+            .connect(ConnectOptions::blocking_fallback())
+            .await
+            .unwrap();
+        let id = server.connections()[0].id;
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        let connection = server.connections()[0].clone();
+        assert_eq!(connection.id, id);
+        assert_eq!(connection.missed_pongs, 1);
+        assert!(connection.idle >= Duration::from_millis(100));
+
+        let result = disconnected.recv().await.unwrap();
+        let elapsed = start.elapsed();
+        assert!(matches!(
+            result,
+            Err(crate::server::WebSocketError::ConnectionTimeout)
+        ));
+        assert!(
+            elapsed >= Duration::from_millis(300) && elapsed < Duration::from_millis(1000),
+            "connection reaped after {elapsed:?}"
+        );
+        assert!(server.connections().iter().all(|info| info.id != id));
+
+        // the client reconnects as a new connection
+        while server.connections().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_ne!(server.connections()[0].id, id);
+        client.shutdown().await.unwrap();
+
+        server.stop_and_join().await.unwrap();
+    }
+}
+
+#[derive(
+    Clone, Debug, Eq, PartialEq, Hash, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+enum AutoOps {
+    Describe,
+    Notify,
+}
+
+/// Server accepting both encodings on one port; the `Describe` call posts
+/// a notification and responds with the doubled value, the encoding and
+/// the negotiated protocol version of the connection (`0` if not negotiated)
+async fn auto_encoding_server(
+    rpc_handler: Arc<dyn RpcHandler<Context = ConnectionContext>>,
+    addr: &str,
+) -> RpcServer {
+    let mut interface = Interface::<(), ConnectionContext, AutoOps>::new(());
+    interface.method(
+        AutoOps::Describe,
+        crate::server::method!(
+            |_server_ctx, connection_ctx: ConnectionContext, req: u64| async move {
+                let messenger = &connection_ctx.messenger;
+                messenger.notify(AutoOps::Notify, req).await.unwrap();
+                let version = messenger
+                    .negotiated()
+                    .map(|negotiated| negotiated.version())
+                    .unwrap_or_default();
+                Ok((req * 2, messenger.encoding().to_string(), version))
+            }
+        ),
+    );
+
+    let server = RpcServer::new_with_auto_encoding::<_, _, _, Id64>(
+        rpc_handler,
+        Arc::new(interface),
+        None,
+        true,
+    );
+    let listener = server.bind(addr).await.unwrap();
+    let server_ = server.clone();
+    workflow_core::task::spawn(async move {
+        server_.listen(listener, None).await.ok();
+    });
+    server
+}
+
+#[tokio::test]
+async fn test_auto_encoding() {
+    use crate::server::{detect_encoding, Message};
+    assert_eq!(
+        detect_encoding(&Message::Text(r#" {"method":"x"}"#.to_string())),
+        Some(Encoding::SerdeJson)
+    );
+    assert_eq!(
+        detect_encoding(&Message::Binary(vec![1, 0])),
+        Some(Encoding::Borsh)
+    );
+    assert_eq!(detect_encoding(&Message::Text("hello".to_string())), None);
+    assert_eq!(detect_encoding(&Message::Binary(vec![])), None);
+
+    let handlers: [(Arc<dyn RpcHandler<Context = ConnectionContext>>, u32, u16); 2] = [
+        (Arc::new(TestRpcHandler), 0, 19133),
+        (Arc::new(NegotiatingRpcHandler), 2, 19134),
+    ];
+    for (rpc_handler, version, port) in handlers {
+        let addr = format!("127.0.0.1:{port}");
+        let server = auto_encoding_server(rpc_handler, &addr).await;
+
+        let url = format!("ws://{addr}");
+        let mut clients = Vec::new();
+        for encoding in [Encoding::Borsh, Encoding::SerdeJson] {
+            let mut options = RpcClientOptions::new().with_url(&url);
+            if version > 0 {
+                options = options.with_negotiation(Negotiation::new(VersionRange::new(1, 3)));
+            }
+            let (sender, receiver) = unbounded();
+            let interface = notification_interface(AutoOps::Notify, sender);
+            let client = RpcClient::<AutoOps>::new_with_encoding(
+                encoding,
+                Some(Arc::new(interface)),
+                options,
+                None,
+            )
+            .unwrap();
+            client
+                .connect(ConnectOptions::blocking_fallback())
+                .await
+                .unwrap();
+            clients.push((encoding, client, receiver));
+        }
+
+        // both clients call the same method over the same port
+        for (encoding, client, receiver) in clients.iter() {
+            for value in 0..4u64 {
+                assert_eq!(
+                    client
+                        .call::<u64, (u64, String, u32)>(AutoOps::Describe, value)
+                        .await
+                        .unwrap(),
+                    (value * 2, encoding.to_string(), version)
+                );
+                assert_eq!(receiver.recv().await.unwrap(), value);
+            }
+        }
+
+        let mut encodings = server
+            .connections()
+            .iter()
+            .map(|info| info.encoding)
+            .collect::<Vec<_>>();
+        encodings.sort_by_key(|encoding| *encoding as u8);
+        assert_eq!(encodings, [Encoding::Borsh, Encoding::SerdeJson]);
+        assert_eq!(
+            server.connections_by_encoding(),
+            [(Encoding::Borsh, 1), (Encoding::SerdeJson, 1)]
+        );
+
+        for (_, client, _) in clients {
+            client.shutdown().await.unwrap();
+        }
+        while !server.connections().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            server.connections_by_encoding(),
+            [(Encoding::Borsh, 0), (Encoding::SerdeJson, 0)]
+        );
+        server.stop_and_join().await.unwrap();
+    }
+}
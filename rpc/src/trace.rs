@@ -0,0 +1,63 @@
+//!
+//! Request tracing. The client generates a [`TraceId`] for each RPC call
+//! and passes it to the server in the message header (`Borsh`) or in the
+//! `trace` field (`JSON`). The server echoes the trace id in the response
+//! and runs the method handler within the trace scope: [`current()`]
+//! returns the trace id of the call being handled and notifications
+//! posted via [`Messenger`](crate::server::Messenger) from within the
+//! handler carry the same trace id. Client-side errors of a call expose
+//! the trace id via [`Error::trace_id()`](crate::client::error::Error::trace_id).
+//!
+
+/// Trace id of an RPC call
+pub type TraceId = workflow_core::id::Id;
+
+#[cfg(not(any(target_arch = "wasm32", target_arch = "bpf")))]
+mod scope {
+    use super::TraceId;
+    use std::future::Future;
+
+    tokio::task_local! {
+        static TRACE: Option<TraceId>;
+    }
+
+    /// Trace id of the RPC call handled by the current task
+    /// (available within server-side method handlers).
+    pub fn current() -> Option<TraceId> {
+        TRACE.try_with(|trace| *trace).ok().flatten()
+    }
+
+    /// Run `future` within the scope of the `trace` id.
+    pub async fn scope<F>(trace: Option<TraceId>, future: F) -> F::Output
+    where
+        F: Future,
+    {
+        TRACE.scope(trace, future).await
+    }
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_arch = "bpf")))]
+pub use scope::{current, scope};
+
+#[cfg(target_arch = "wasm32")]
+mod scope {
+    use super::TraceId;
+    use std::future::Future;
+
+    /// Trace id of the RPC call handled by the current task
+    /// (trace scopes are not tracked in WASM, this always returns `None`).
+    pub fn current() -> Option<TraceId> {
+        None
+    }
+
+    /// Run `future` (trace scopes are not tracked in WASM).
+    pub async fn scope<F>(_trace: Option<TraceId>, future: F) -> F::Output
+    where
+        F: Future,
+    {
+        future.await
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub use scope::{current, scope};
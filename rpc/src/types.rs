@@ -1,6 +1,28 @@
 //!
 //! Trait constraints for RPC methods (Ops) and message (Req,Resp,Msg).
 //!
+//! # Malformed messages
+//!
+//! Messages the server is unable to decode are answered with an error
+//! frame carrying [`ServerError::ParseError`] (or [`ServerError::ReqDeserialize`]
+//! if only the request payload is invalid), provided that the request id
+//! can be recovered from the message:
+//!
+//! - `Borsh`: a [`BorshServerMessageHeader`](crate::messages::borsh::BorshServerMessageHeader)
+//!   with the request id, [`ServerMessageKind::Error`](crate::messages::borsh::ServerMessageKind::Error)
+//!   and no op, followed by the Borsh-serialized [`ServerError`]. The id is
+//!   recovered if the message starts with a valid `Option<Id>`.
+//! - `JSON`: `{"id":...,"error":{"code":0,"message":"parse error: ...","data":{"ParseError":"..."}}}`,
+//!   where `data` carries the serialized [`ServerError`] (see
+//!   [`JsonServerError::server_error()`](crate::messages::serde_json::JsonServerError::server_error)).
+//!   The id is recovered if the message is a JSON object with a valid `id` field.
+//!
+//! Messages without a recoverable id are dropped. Decoding is bounded by
+//! the size of the received message: Borsh collection length prefixes
+//! exceeding the remaining data fail without preallocating the announced
+//! length. The server closes connections sending consecutive malformed
+//! messages (see [`Interface::set_malformed_message_limit()`](crate::server::Interface::set_malformed_message_limit)).
+//!
 
 use crate::imports::*;
 
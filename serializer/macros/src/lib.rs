@@ -0,0 +1,49 @@
+use proc_macro::TokenStream;
+mod serial;
+
+///
+/// Derive [`Serializer`](https://docs.rs/workflow-serializer) for structs and enums.
+///
+/// Struct fields are stored in the declaration order using Borsh.
+///
+/// Enum variants are stored as a `u32` tag followed by the Borsh-serialized
+/// variant fields (as a length-prefixed payload). The tag of each variant must
+/// be supplied explicitly using `#[serial(tag = N)]`, so that variants can be
+/// reordered or inserted without affecting the previously stored data.
+/// Duplicate tags produce a compile error.
+///
+/// A single variant can be marked `#[serial(other)]` to receive the variants
+/// with an unknown tag. This variant must contain two fields: the `u32` tag
+/// and the `Vec<u8>` payload, which are stored as-is (allowing data written
+/// by a newer enum definition to be round-tripped). Without such variant,
+/// unknown tags produce an [`std::io::ErrorKind::InvalidData`] error.
+///
+/// ```ignore
+/// #[derive(Serializer, Deserializer)]
+/// enum Message {
+///     #[serial(tag = 1)]
+///     Ping(u64),
+///     #[serial(tag = 2)]
+///     Notify { id: u64, text: String },
+///     #[serial(other)]
+///     Unknown(u32, Vec<u8>),
+/// }
+/// ```
+///
+#[proc_macro_derive(Serializer, attributes(serial))]
+pub fn derive_serializer(item: TokenStream) -> TokenStream {
+    serial::derive_serializer(item.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+///
+/// Derive [`Deserializer`](https://docs.rs/workflow-serializer) for structs and enums
+/// (see [`macro@Serializer`] for the data layout and the supported attributes).
+///
+#[proc_macro_derive(Deserializer, attributes(serial))]
+pub fn derive_deserializer(item: TokenStream) -> TokenStream {
+    serial::derive_deserializer(item.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
@@ -0,0 +1,337 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Lit, Meta, NestedMeta, Result, Variant};
+
+/// Variant of the enum along with its `#[serial]` attributes
+struct SerialVariant<'a> {
+    variant: &'a Variant,
+    /// `None` for the `#[serial(other)]` variant
+    tag: Option<u32>,
+}
+
+/// Parsed enum declaration
+struct SerialEnum<'a> {
+    variants: Vec<SerialVariant<'a>>,
+    other: Option<&'a Variant>,
+}
+
+/// `#[serial(...)]` arguments of a variant
+#[derive(Default)]
+struct Args {
+    tag: Option<(u32, Span)>,
+    other: Option<Span>,
+}
+
+fn parse_args(attrs: &[Attribute]) -> Result<Args> {
+    let mut args = Args::default();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("serial")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => {
+                return Err(Error::new_spanned(
+                    meta,
+                    "expected #[serial(tag = N)] or #[serial(other)]",
+                ))
+            }
+        };
+        for nested in list.nested.iter() {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("tag") => {
+                    let tag = match &nv.lit {
+                        Lit::Int(lit) => lit.base10_parse::<u32>()?,
+                        lit => {
+                            return Err(Error::new_spanned(
+                                lit,
+                                "#[serial] tag must be a u32 literal",
+                            ))
+                        }
+                    };
+                    if args.tag.is_some() {
+                        return Err(Error::new_spanned(nv, "duplicate #[serial] tag attribute"));
+                    }
+                    args.tag = Some((tag, nv.lit.span()));
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("other") => {
+                    args.other = Some(path.get_ident().unwrap().span());
+                }
+                nested => {
+                    return Err(Error::new_spanned(
+                        nested,
+                        "unknown #[serial] attribute, expected `tag = N` or `other`",
+                    ))
+                }
+            }
+        }
+    }
+    Ok(args)
+}
+
+fn parse_enum<'a>(
+    ident: &Ident,
+    variants: impl Iterator<Item = &'a Variant>,
+) -> Result<SerialEnum<'a>> {
+    let mut serial = SerialEnum {
+        variants: Vec::new(),
+        other: None,
+    };
+    let mut tags: Vec<(u32, &Ident)> = Vec::new();
+
+    for variant in variants {
+        let args = parse_args(&variant.attrs)?;
+        match (args.tag, args.other) {
+            (Some((tag, span)), None) => {
+                if let Some((_, existing)) = tags.iter().find(|(existing, _)| *existing == tag) {
+                    return Err(Error::new(
+                        span,
+                        format!("duplicate #[serial] tag {tag} (already used by `{existing}`)"),
+                    ));
+                }
+                tags.push((tag, &variant.ident));
+                serial.variants.push(SerialVariant {
+                    variant,
+                    tag: Some(tag),
+                });
+            }
+            (None, Some(span)) => {
+                if serial.other.is_some() {
+                    return Err(Error::new(
+                        span,
+                        "only one variant can be marked as #[serial(other)]",
+                    ));
+                }
+                if variant.fields.len() != 2 || matches!(variant.fields, Fields::Unit) {
+                    return Err(Error::new_spanned(
+                        variant,
+                        "#[serial(other)] variant must contain the `u32` tag and the `Vec<u8>` payload fields",
+                    ));
+                }
+                serial.other = Some(variant);
+                serial.variants.push(SerialVariant { variant, tag: None });
+            }
+            (Some((_, span)), Some(_)) => {
+                return Err(Error::new(
+                    span,
+                    "#[serial(other)] variant can not have a tag",
+                ))
+            }
+            (None, None) => {
+                return Err(Error::new_spanned(
+                    &variant.ident,
+                    format!(
+                        "variant `{ident}::{}` requires a #[serial(tag = N)] attribute",
+                        variant.ident
+                    ),
+                ))
+            }
+        }
+    }
+
+    Ok(serial)
+}
+
+/// Pattern binding the fields of the variant (or struct) to `__field{N}`
+fn bindings(path: TokenStream, fields: &Fields) -> (TokenStream, Vec<Ident>) {
+    let names = (0..fields.len())
+        .map(|idx| format_ident!("__field{}", idx))
+        .collect::<Vec<_>>();
+    let pattern = match fields {
+        Fields::Named(named) => {
+            let idents = named
+                .named
+                .iter()
+                .map(|field| field.ident.as_ref().unwrap());
+            quote! { #path { #(#idents: #names),* } }
+        }
+        Fields::Unnamed(_) => quote! { #path ( #(#names),* ) },
+        Fields::Unit => quote! { #path },
+    };
+    (pattern, names)
+}
+
+pub fn derive_serializer(input: TokenStream) -> Result<TokenStream> {
+    let ast = syn::parse2::<DeriveInput>(input)?;
+    let ident = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let body = match &ast.data {
+        Data::Struct(data) => {
+            let (pattern, names) = bindings(quote! { #ident }, &data.fields);
+            quote! {
+                let #pattern = self;
+                #(::workflow_serializer::borsh::BorshSerialize::serialize(#names, writer)?;)*
+            }
+        }
+        Data::Enum(data) => {
+            let serial = parse_enum(ident, data.variants.iter())?;
+            let arms = serial.variants.iter().map(|SerialVariant { variant, tag }| {
+                let name = &variant.ident;
+                let (pattern, names) = bindings(quote! { #ident::#name }, &variant.fields);
+                match tag {
+                    Some(tag) => quote! {
+                        #pattern => {
+                            let mut payload = Vec::<u8>::new();
+                            #(::workflow_serializer::borsh::BorshSerialize::serialize(#names, &mut payload)?;)*
+                            ::workflow_serializer::borsh::BorshSerialize::serialize(&#tag, writer)?;
+                            ::workflow_serializer::borsh::BorshSerialize::serialize(&payload, writer)?;
+                        }
+                    },
+                    None => {
+                        let tag = &names[0];
+                        let payload = &names[1];
+                        quote! {
+                            #pattern => {
+                                ::workflow_serializer::borsh::BorshSerialize::serialize(#tag, writer)?;
+                                ::workflow_serializer::borsh::BorshSerialize::serialize(#payload, writer)?;
+                            }
+                        }
+                    }
+                }
+            });
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                ident,
+                "#[derive(Serializer)] supports only structs and enums",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::workflow_serializer::serializer::Serializer for #ident #ty_generics #where_clause {
+            fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+                #body
+                Ok(())
+            }
+        }
+    })
+}
+
+pub fn derive_deserializer(input: TokenStream) -> Result<TokenStream> {
+    let ast = syn::parse2::<DeriveInput>(input)?;
+    let ident = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let body = match &ast.data {
+        Data::Struct(data) => {
+            let (pattern, names) = bindings(quote! { #ident }, &data.fields);
+            quote! {
+                #(let #names = ::workflow_serializer::borsh::BorshDeserialize::deserialize_reader(reader)?;)*
+                Ok(#pattern)
+            }
+        }
+        Data::Enum(data) => {
+            let serial = parse_enum(ident, data.variants.iter())?;
+            let arms = serial
+                .variants
+                .iter()
+                .filter_map(|SerialVariant { variant, tag }| {
+                    let tag = (*tag)?;
+                    let name = &variant.ident;
+                    let (pattern, names) = bindings(quote! { #ident::#name }, &variant.fields);
+                    Some(quote! {
+                        #tag => {
+                            #(let #names = ::workflow_serializer::borsh::BorshDeserialize::deserialize_reader(&mut source)?;)*
+                            #pattern
+                        }
+                    })
+                });
+            let unknown = match serial.other {
+                Some(variant) => {
+                    let name = &variant.ident;
+                    let (pattern, _) = bindings(quote! { #ident::#name }, &variant.fields);
+                    // bind the tag and the payload to the variant fields
+                    quote! {
+                        _ => {
+                            let __field0 = tag;
+                            let __field1 = payload;
+                            return Ok(#pattern);
+                        }
+                    }
+                }
+                None => {
+                    let message = format!("unknown `{ident}` variant tag {{tag}}");
+                    quote! {
+                        _ => {
+                            return Err(std::io::Error::new(
+                                std::io::ErrorKind::InvalidData,
+                                format!(#message),
+                            ));
+                        }
+                    }
+                }
+            };
+            let trailing = format!("trailing bytes in the `{ident}` variant payload");
+            quote! {
+                let tag = <u32 as ::workflow_serializer::borsh::BorshDeserialize>::deserialize_reader(reader)?;
+                let payload = <Vec<u8> as ::workflow_serializer::borsh::BorshDeserialize>::deserialize_reader(reader)?;
+                #[allow(unused_mut)]
+                let mut source = payload.as_slice();
+                let value = match tag {
+                    #(#arms)*
+                    #unknown
+                };
+                if !source.is_empty() {
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, #trailing));
+                }
+                Ok(value)
+            }
+        }
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                ident,
+                "#[derive(Deserializer)] supports only structs and enums",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::workflow_serializer::serializer::Deserializer for #ident #ty_generics #where_clause {
+            fn deserialize<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+                #body
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(input: &str) -> String {
+        derive_serializer(input.parse().unwrap())
+            .expect_err("expected a compile error")
+            .to_string()
+    }
+
+    #[test]
+    fn test_serial_attribute_errors() {
+        assert_eq!(
+            error(
+                "enum E { #[serial(tag = 1)] A, #[serial(tag = 2)] B(u8), #[serial(tag = 1)] C }"
+            ),
+            "duplicate #[serial] tag 1 (already used by `A`)"
+        );
+        assert_eq!(
+            error("enum E { #[serial(tag = 1)] A, B }"),
+            "variant `E::B` requires a #[serial(tag = N)] attribute"
+        );
+        assert_eq!(
+            error("enum E { #[serial(other)] A(u32, Vec<u8>), #[serial(other)] B(u32, Vec<u8>) }"),
+            "only one variant can be marked as #[serial(other)]"
+        );
+        assert!(error("enum E { #[serial(other)] A(u32) }").contains("`Vec<u8>` payload"));
+        assert!(error("enum E { #[serial(tag = -1)] A }").contains("invalid digit"));
+
+        derive_serializer(
+            "enum E { #[serial(tag = 2)] A, #[serial(tag = 1)] B { x: u8 }, #[serial(other)] C(u32, Vec<u8>) }"
+                .parse()
+                .unwrap(),
+        )
+        .unwrap();
+    }
+}
@@ -4,10 +4,15 @@ pub mod result;
 pub mod serializer;
 pub mod tests;
 
+// allows the derive macros to refer to `::workflow_serializer` within this crate
+extern crate self as workflow_serializer;
+
 pub mod prelude {
     pub use crate::serializer::{Deserializer, Serializable, Serializer};
     pub use crate::{deserialize, load, payload, reader, serialize, store, version, writer};
     pub use borsh::{BorshDeserialize, BorshSerialize};
+    pub use workflow_serializer_macros::{Deserializer, Serializer};
 }
 
 pub use borsh;
+pub use workflow_serializer_macros::{Deserializer, Serializer};
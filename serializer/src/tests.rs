@@ -93,4 +93,115 @@ mod tests {
 
         Ok(())
     }
+
+    mod v1 {
+        use crate::prelude::{Deserializer, Serializer};
+
+        #[derive(Serializer, Deserializer, Debug, PartialEq)]
+        pub enum Message {
+            #[serial(tag = 1)]
+            Ping(u64),
+            #[serial(tag = 2)]
+            Notify { id: u32, text: String },
+            #[serial(tag = 3)]
+            Close,
+        }
+    }
+
+    mod v2 {
+        use crate::prelude::{Deserializer, Serializer};
+
+        // variants reordered and a variant inserted in the middle
+        #[derive(Serializer, Deserializer, Debug, PartialEq)]
+        pub enum Message {
+            #[serial(tag = 3)]
+            Close,
+            #[serial(tag = 4)]
+            Status(bool),
+            #[serial(tag = 2)]
+            Notify { id: u32, text: String },
+            #[serial(tag = 1)]
+            Ping(u64),
+            #[serial(other)]
+            Unknown { tag: u32, payload: Vec<u8> },
+        }
+    }
+
+    // data written using the `v1::Message` definition
+    const PING_V1: &[u8] = &[1, 0, 0, 0, 8, 0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0];
+    const NOTIFY_V1: &[u8] = &[2, 0, 0, 0, 10, 0, 0, 0, 9, 0, 0, 0, 2, 0, 0, 0, b'h', b'i'];
+    const CLOSE_V1: &[u8] = &[3, 0, 0, 0, 0, 0, 0, 0];
+    // variant with tag 5 written by a newer definition
+    const FUTURE: &[u8] = &[5, 0, 0, 0, 3, 0, 0, 0, 1, 2, 3];
+
+    #[test]
+    fn test_serializer_enum_fixtures() -> Result<(), Box<dyn std::error::Error>> {
+        let notify = v1::Message::Notify {
+            id: 9,
+            text: "hi".to_string(),
+        };
+        assert_eq!(v1::Message::Ping(7).try_to_vec()?, PING_V1);
+        assert_eq!(notify.try_to_vec()?, NOTIFY_V1);
+        assert_eq!(v1::Message::Close.try_to_vec()?, CLOSE_V1);
+        assert_eq!(v1::Message::try_from_slice(NOTIFY_V1)?, notify);
+
+        assert_eq!(v2::Message::try_from_slice(PING_V1)?, v2::Message::Ping(7));
+        assert_eq!(
+            v2::Message::try_from_slice(NOTIFY_V1)?,
+            v2::Message::Notify {
+                id: 9,
+                text: "hi".to_string()
+            }
+        );
+        assert_eq!(v2::Message::try_from_slice(CLOSE_V1)?, v2::Message::Close);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_serializer_enum_unknown_tags() -> Result<(), Box<dyn std::error::Error>> {
+        // unknown variants are preserved by the `other` variant
+        let unknown = v2::Message::try_from_slice(FUTURE)?;
+        assert_eq!(
+            unknown,
+            v2::Message::Unknown {
+                tag: 5,
+                payload: vec![1, 2, 3]
+            }
+        );
+        assert_eq!(unknown.try_to_vec()?, FUTURE);
+
+        // and rejected without it
+        let status = v2::Message::Status(true).try_to_vec()?;
+        let err = v1::Message::try_from_slice(&status).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "unknown `Message` variant tag 4");
+
+        // variant payload must be consumed entirely
+        let mut trailing = CLOSE_V1.to_vec();
+        trailing[4] = 1;
+        trailing.push(0);
+        assert!(v1::Message::try_from_slice(&trailing).is_err());
+
+        Ok(())
+    }
+
+    #[derive(Serializer, Deserializer, Debug, PartialEq)]
+    struct Record {
+        id: u32,
+        message: String,
+    }
+
+    #[test]
+    fn test_serializer_struct_derive() -> Result<(), Box<dyn std::error::Error>> {
+        let record = Record {
+            id: 1,
+            message: "abc".to_string(),
+        };
+        let data = record.try_to_vec()?;
+        assert_eq!(data, [1, 0, 0, 0, 3, 0, 0, 0, b'a', b'b', b'c']);
+        assert_eq!(Record::try_from_slice(&data)?, record);
+
+        Ok(())
+    }
 }
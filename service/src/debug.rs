@@ -1,6 +1,15 @@
-//! global atomic debug flag (for developer testing)
+//!
+//! Developer diagnostics: global atomic debug flag and
+//! the runtime event [`Journal`] (see [`Runtime::events_snapshot()`]).
+//!
 
 use crate::imports::*;
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+use workflow_core::channel::{unbounded, Receiver, Sender};
+use workflow_core::time::unixtime_as_millis_u64;
+use workflow_utils::format::{Align, Table};
 
 static DEBUG: AtomicBool = AtomicBool::new(false);
 
@@ -11,3 +20,266 @@ pub fn enable(debug: bool) {
 pub fn debug() -> bool {
     DEBUG.load(Ordering::SeqCst)
 }
+
+/// Default number of events retained by the runtime [`Journal`]
+pub const DEFAULT_JOURNAL_CAPACITY: usize = 1024;
+
+/// Service lifecycle state recorded in the [`Journal`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceState {
+    Starting,
+    Running,
+    Failed,
+    Terminating,
+    Terminated,
+}
+
+impl fmt::Display for ServiceState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self {
+            ServiceState::Starting => "starting",
+            ServiceState::Running => "running",
+            ServiceState::Failed => "failed",
+            ServiceState::Terminating => "terminating",
+            ServiceState::Terminated => "terminated",
+        };
+        write!(f, "{state}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventKind {
+    /// Service bound to the runtime
+    Registered,
+    /// Service lifecycle state change
+    State(ServiceState),
+    /// Service restart (recorded by services that restart themselves)
+    Restart { attempt: u32 },
+    /// Service health change (recorded by services that monitor their health)
+    Health {
+        healthy: bool,
+        reason: Option<String>,
+    },
+    /// Service configuration reload (see [`Runtime::reload()`])
+    Reload(ReloadOutcome),
+    /// Process signal received by the runtime
+    Signal { name: String, count: u64 },
+    /// Free-form message
+    Message(String),
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventKind::Registered => write!(f, "registered"),
+            EventKind::State(state) => write!(f, "{state}"),
+            EventKind::Restart { attempt } => write!(f, "restart #{attempt}"),
+            EventKind::Health { healthy, reason } => {
+                let health = if *healthy { "healthy" } else { "unhealthy" };
+                match reason {
+                    Some(reason) => write!(f, "{health}: {reason}"),
+                    None => write!(f, "{health}"),
+                }
+            }
+            EventKind::Reload(outcome) => write!(f, "{outcome}"),
+            EventKind::Signal { name, count } => write!(f, "signal {name} (#{count})"),
+            EventKind::Message(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+/// Event recorded in the [`Journal`]
+#[derive(Debug, Clone)]
+pub struct Event {
+    /// Sequence number, unique for the lifetime of the journal
+    pub seq: u64,
+    /// Unix time in milliseconds
+    pub timestamp: u64,
+    /// Time elapsed since the creation of the journal
+    pub elapsed: Duration,
+    /// Name of the service or the runtime component producing the event
+    pub source: String,
+    pub kind: EventKind,
+}
+
+struct JournalInner {
+    events: VecDeque<Event>,
+    seq: u64,
+    subscribers: Vec<Sender<Event>>,
+}
+
+/// Bounded in-memory journal of runtime events. Once the capacity
+/// is reached, the oldest events are discarded.
+pub struct Journal {
+    capacity: usize,
+    start: Instant,
+    inner: Mutex<JournalInner>,
+}
+
+impl Default for Journal {
+    fn default() -> Self {
+        Self::new(DEFAULT_JOURNAL_CAPACITY)
+    }
+}
+
+impl Journal {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "journal capacity must be greater than 0");
+        Self {
+            capacity,
+            start: Instant::now(),
+            inner: Mutex::new(JournalInner {
+                events: VecDeque::with_capacity(capacity),
+                seq: 0,
+                subscribers: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn record(&self, source: &str, kind: EventKind) {
+        let mut inner = self.inner.lock().unwrap();
+        let seq = inner.seq;
+        inner.seq += 1;
+        if inner.events.len() == self.capacity {
+            inner.events.pop_front();
+        }
+        let event = Event {
+            seq,
+            timestamp: unixtime_as_millis_u64(),
+            elapsed: self.start.elapsed(),
+            source: source.to_string(),
+            kind,
+        };
+        inner
+            .subscribers
+            .retain(|subscriber| subscriber.try_send(event.clone()).is_ok());
+        inner.events.push_back(event);
+    }
+
+    /// Receive the events recorded from now on. The subscription
+    /// is released once the returned receiver is dropped.
+    pub fn subscribe(&self) -> Receiver<Event> {
+        let (sender, receiver) = unbounded();
+        self.inner.lock().unwrap().subscribers.push(sender);
+        receiver
+    }
+
+    /// Retained events, oldest first
+    pub fn snapshot(&self) -> Vec<Event> {
+        self.inner.lock().unwrap().events.iter().cloned().collect()
+    }
+
+    /// Number of events discarded due to the capacity limit
+    pub fn evicted(&self) -> u64 {
+        let inner = self.inner.lock().unwrap();
+        inner.seq - inner.events.len() as u64
+    }
+
+    /// Render retained events as an aligned text table
+    pub fn dump(&self) -> String {
+        let events = self.snapshot();
+        let mut table = Table::new()
+            .with_header(["#", "time", "source", "event"])
+            .with_align(0, Align::Right)
+            .with_align(1, Align::Right);
+        for event in events.iter() {
+            table.add_row([
+                event.seq.to_string(),
+                format!("{:.3}s", event.elapsed.as_secs_f64()),
+                event.source.clone(),
+                event.kind.to_string(),
+            ]);
+        }
+
+        let mut text = table.render();
+        let evicted = self.evicted();
+        if evicted > 0 {
+            text.push_str(&format!("({evicted} earlier events discarded)\n"));
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_eviction() {
+        let journal = Journal::new(3);
+        for attempt in 0..5 {
+            journal.record("svc", EventKind::Restart { attempt });
+        }
+
+        let events = journal.snapshot();
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events.iter().map(|event| event.seq).collect::<Vec<_>>(),
+            vec![2, 3, 4]
+        );
+        assert_eq!(events[0].kind, EventKind::Restart { attempt: 2 });
+        assert_eq!(journal.evicted(), 2);
+
+        let dump = journal.dump();
+        let lines = dump.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(
+            lines[0].split_whitespace().collect::<Vec<_>>(),
+            vec!["#", "time", "source", "event"]
+        );
+        assert!(lines[2].ends_with("svc     restart #2"));
+        assert_eq!(lines[5], "(2 earlier events discarded)");
+    }
+
+    #[test]
+    fn test_journal_subscribe() {
+        let journal = Journal::new(8);
+        journal.record("svc", EventKind::Registered);
+        let receiver = journal.subscribe();
+        journal.record("svc", EventKind::State(ServiceState::Running));
+
+        let event = receiver.try_recv().unwrap();
+        assert_eq!(event.seq, 1);
+        assert_eq!(event.kind, EventKind::State(ServiceState::Running));
+        assert!(receiver.try_recv().is_err());
+
+        // dropped subscriptions are released on the next record
+        drop(receiver);
+        journal.record("svc", EventKind::Registered);
+        assert!(journal.inner.lock().unwrap().subscribers.is_empty());
+    }
+
+    #[test]
+    fn test_journal_concurrent_records() {
+        const THREADS: usize = 8;
+        const EVENTS: usize = 200;
+
+        let journal = Arc::new(Journal::new(THREADS * EVENTS / 2));
+        let threads = (0..THREADS)
+            .map(|idx| {
+                let journal = journal.clone();
+                std::thread::spawn(move || {
+                    let source = format!("svc-{idx}");
+                    for _ in 0..EVENTS {
+                        journal.record(&source, EventKind::State(ServiceState::Running));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        threads
+            .into_iter()
+            .for_each(|thread| thread.join().unwrap());
+
+        let events = journal.snapshot();
+        assert_eq!(events.len(), journal.capacity());
+        assert_eq!(journal.evicted() as usize, THREADS * EVENTS / 2);
+        // sequence numbers are assigned under the lock, so the retained
+        // events are exactly the most recent ones in recording order
+        let expected = (THREADS * EVENTS / 2) as u64..(THREADS * EVENTS) as u64;
+        assert!(events.iter().map(|event| event.seq).eq(expected));
+    }
+}
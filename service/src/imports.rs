@@ -3,12 +3,14 @@ pub use futures_util::future::join_all;
 pub use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 pub use std::sync::{Arc, Mutex};
 
-pub use workflow_core::channel::{oneshot, Channel};
+pub use workflow_core::channel::{oneshot, Channel, Receiver};
 pub use workflow_core::task::spawn;
 pub use workflow_log::prelude::*;
 
 pub use crate::debug::*;
+pub use crate::reload::*;
 pub use crate::result::Result;
 pub use crate::runtime::Runtime;
 pub use crate::service::*;
 pub use crate::signals::Shutdown;
+pub use crate::status::ServiceStatus;
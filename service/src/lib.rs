@@ -5,10 +5,14 @@ cfg_if::cfg_if! {
         pub mod error;
         mod imports;
         pub mod prelude;
+        pub mod reload;
         pub mod result;
         pub mod runtime;
         pub mod service;
         pub mod signals;
+        pub mod status;
+        #[cfg(feature = "terminal")]
+        pub mod terminal;
 
     } else {
         pub mod prelude { }
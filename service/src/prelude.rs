@@ -1,5 +1,10 @@
+pub use crate::debug::{Event, EventKind, Journal, ServiceState};
 pub use crate::error::Error as ServiceError;
+pub use crate::reload::{ReloadConfig, ReloadOutcome, ReloadReport};
 pub use crate::result::Result as ServiceResult;
 pub use crate::runtime::*;
 pub use crate::service::*;
 pub use crate::signals::*;
+pub use crate::status::ServiceStatus;
+#[cfg(feature = "terminal")]
+pub use crate::terminal::DebugHandler;
@@ -0,0 +1,62 @@
+//!
+//! Configuration reload delivered to running services via
+//! [`Runtime::reload()`] (see [`Service::on_reload()`]).
+//!
+
+use crate::imports::*;
+use std::any::Any;
+use std::fmt;
+
+/// Configuration supplied to [`Runtime::reload()`]. Services
+/// downcast it to the configuration type of the application.
+pub type ReloadConfig = Arc<dyn Any + Send + Sync>;
+
+/// Result of the configuration reload of a single service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReloadOutcome {
+    Reloaded,
+    /// The service does not support configuration reload
+    Skipped,
+    Failed(String),
+}
+
+impl fmt::Display for ReloadOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReloadOutcome::Reloaded => write!(f, "reloaded"),
+            ReloadOutcome::Skipped => write!(f, "reload skipped"),
+            ReloadOutcome::Failed(err) => write!(f, "reload failed: {err}"),
+        }
+    }
+}
+
+/// Per-service results of [`Runtime::reload()`], in the order
+/// the services were bound to the runtime.
+#[derive(Debug, Clone, Default)]
+pub struct ReloadReport {
+    pub results: Vec<(&'static str, ReloadOutcome)>,
+}
+
+impl ReloadReport {
+    pub fn get(&self, name: &str) -> Option<&ReloadOutcome> {
+        self.results
+            .iter()
+            .find(|(service, _)| *service == name)
+            .map(|(_, outcome)| outcome)
+    }
+
+    /// Services that failed to reload, along with their errors
+    pub fn failures(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        self.results
+            .iter()
+            .filter_map(|(name, outcome)| match outcome {
+                ReloadOutcome::Failed(err) => Some((*name, err.as_str())),
+                _ => None,
+            })
+    }
+
+    /// Returns `true` if no service failed to reload
+    pub fn is_ok(&self) -> bool {
+        self.failures().next().is_none()
+    }
+}
@@ -4,6 +4,7 @@ struct Inner {
     services: Mutex<Vec<Arc<dyn Service>>>,
     is_running: Arc<AtomicBool>,
     termination: Channel<()>,
+    journal: Journal,
 }
 
 impl Shutdown for Inner {
@@ -19,21 +20,71 @@ pub struct Runtime {
 
 impl Default for Runtime {
     fn default() -> Self {
+        Self::with_journal_capacity(DEFAULT_JOURNAL_CAPACITY)
+    }
+}
+
+impl Runtime {
+    /// Create a runtime retaining up to `capacity` events in its [`Journal`]
+    pub fn with_journal_capacity(capacity: usize) -> Self {
         Self {
             inner: Arc::new(Inner {
                 services: Mutex::new(Vec::new()),
                 is_running: Arc::new(AtomicBool::new(false)),
                 termination: Channel::oneshot(),
+                journal: Journal::new(capacity),
             }),
         }
     }
-}
 
-impl Runtime {
     pub fn bind(&self, service: Arc<dyn Service>) {
+        self.record_event(service.name(), EventKind::Registered);
         self.inner.services.lock().unwrap().push(service);
     }
 
+    /// Record an event in the runtime journal. Services can use this
+    /// to record events such as restarts or health changes.
+    pub fn record_event(&self, source: &str, kind: EventKind) {
+        self.inner.journal.record(source, kind);
+    }
+
+    /// Events recorded by the runtime, oldest first
+    pub fn events_snapshot(&self) -> Vec<Event> {
+        self.inner.journal.snapshot()
+    }
+
+    /// Receive the events recorded by the runtime from now on
+    /// (e.g. to refresh a status display on state changes)
+    pub fn subscribe(&self) -> Receiver<Event> {
+        self.inner.journal.subscribe()
+    }
+
+    /// Lifecycle state and health of the bound services (in the
+    /// order they were bound), derived from the retained journal events
+    pub fn status(&self) -> Vec<ServiceStatus> {
+        let mut status = self
+            .services()
+            .iter()
+            .map(|service| ServiceStatus::new(service.name()))
+            .collect::<Vec<_>>();
+        for event in self.events_snapshot() {
+            status
+                .iter_mut()
+                .filter(|status| status.name == event.source)
+                .for_each(|status| status.apply(&event));
+        }
+        status
+    }
+
+    /// Render the runtime event journal as an aligned text table
+    pub fn dump(&self) -> String {
+        self.inner.journal.dump()
+    }
+
+    fn record_state(&self, service: &Arc<dyn Service>, state: ServiceState) {
+        self.record_event(service.name(), EventKind::State(state));
+    }
+
     fn services(&self) -> Vec<Arc<dyn Service>> {
         self.inner.services.lock().unwrap().clone()
     }
@@ -46,9 +97,14 @@ impl Runtime {
             if debug() {
                 println!("✨ {}", service.name());
             }
+            self.record_state(&service, ServiceState::Starting);
             match service.clone().spawn(runtime).await {
-                Ok(_) => active.push(service),
+                Ok(_) => {
+                    self.record_state(&service, ServiceState::Running);
+                    active.push(service)
+                }
                 Err(err) => {
+                    self.record_state(&service, ServiceState::Failed);
                     log_error!("Service spawn error: {err}");
                     self.stop_services(Some(active.clone()));
                     self.join_services(Some(active)).await;
@@ -68,6 +124,7 @@ impl Runtime {
                 if debug() {
                     println!("⛬ {}", service.name());
                 }
+                self.record_state(&service, ServiceState::Terminating);
                 service.terminate();
             });
     }
@@ -82,11 +139,22 @@ impl Runtime {
             for service in services {
                 let name = service.name();
                 println!("⚡ {name}");
-                service.join().await.expect("service join failure");
+                service.clone().join().await.expect("service join failure");
+                self.record_state(&service, ServiceState::Terminated);
                 println!("💀 {name}");
             }
         } else {
-            let futures = services.map(|service| service.join()).collect::<Vec<_>>();
+            let futures = services
+                .map(|service| async move {
+                    let result = service.clone().join().await;
+                    let state = if result.is_ok() {
+                        ServiceState::Terminated
+                    } else {
+                        ServiceState::Failed
+                    };
+                    self.record_state(&service, state);
+                })
+                .collect::<Vec<_>>();
             join_all(futures).await;
         }
     }
@@ -94,15 +162,20 @@ impl Runtime {
     /// Start the runtime runtime.
     async fn start(&self) -> Result<()> {
         self.inner.is_running.store(true, Ordering::SeqCst);
-        self.start_services().await
+        self.record_event("runtime", EventKind::State(ServiceState::Starting));
+        self.start_services().await?;
+        self.record_event("runtime", EventKind::State(ServiceState::Running));
+        Ok(())
     }
 
     /// Shutdown runtime runtime.
     async fn shutdown(&self) {
         if self.inner.is_running.load(Ordering::SeqCst) {
             self.inner.is_running.store(false, Ordering::SeqCst);
+            self.record_event("runtime", EventKind::State(ServiceState::Terminating));
             self.stop_services(None);
             self.join_services(None).await;
+            self.record_event("runtime", EventKind::State(ServiceState::Terminated));
         }
     }
 
@@ -120,7 +193,190 @@ impl Runtime {
         Ok(())
     }
 
+    /// Deliver the `config` to the running services (in the order they were
+    /// bound) via [`Service::on_reload()`]. Failures are recorded in the
+    /// returned report and do not affect the remaining services.
+    pub async fn reload(&self, config: ReloadConfig) -> ReloadReport {
+        let mut report = ReloadReport::default();
+        if !self.inner.is_running.load(Ordering::SeqCst) {
+            return report;
+        }
+
+        for service in self.services() {
+            let outcome = match service.clone().on_reload(config.clone()).await {
+                None => ReloadOutcome::Skipped,
+                Some(Ok(())) => ReloadOutcome::Reloaded,
+                Some(Err(err)) => {
+                    log_error!("Service reload error: {err}");
+                    ReloadOutcome::Failed(err.to_string())
+                }
+            };
+            if outcome != ReloadOutcome::Skipped {
+                self.record_event(service.name(), EventKind::Reload(outcome.clone()));
+            }
+            report.results.push((service.name(), outcome));
+        }
+
+        report
+    }
+
     pub fn terminate(&self) {
         self.inner.termination.try_send(()).unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    struct Config {
+        level: u32,
+    }
+
+    struct MockService {
+        name: &'static str,
+        reloadable: bool,
+        fail: bool,
+        level: AtomicU64,
+    }
+
+    impl MockService {
+        fn new(name: &'static str, reloadable: bool, fail: bool) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                reloadable,
+                fail,
+                level: AtomicU64::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Service for MockService {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn spawn(self: Arc<Self>, _runtime: Runtime) -> Result<()> {
+            Ok(())
+        }
+
+        fn terminate(self: Arc<Self>) {}
+
+        async fn join(self: Arc<Self>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn on_reload(self: Arc<Self>, config: ReloadConfig) -> Option<Result<()>> {
+            if !self.reloadable {
+                return None;
+            }
+            if self.fail {
+                return Some(Err(Error::custom("invalid configuration")));
+            }
+            let config = config.downcast_ref::<Config>()?;
+            self.level.store(config.level as u64, Ordering::SeqCst);
+            Some(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runtime_reload() {
+        let runtime = Runtime::default();
+        let first = MockService::new("first", true, false);
+        let failing = MockService::new("failing", true, true);
+        let static_ = MockService::new("static", false, false);
+        let last = MockService::new("last", true, false);
+        for service in [&first, &failing, &static_, &last] {
+            runtime.bind(service.clone());
+        }
+
+        // services are not running
+        let report = runtime.reload(Arc::new(Config { level: 1 })).await;
+        assert!(report.results.is_empty());
+
+        runtime.start().await.unwrap();
+        let report = runtime.reload(Arc::new(Config { level: 2 })).await;
+        assert_eq!(
+            report
+                .results
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>(),
+            vec!["first", "failing", "static", "last"]
+        );
+        assert_eq!(report.get("first"), Some(&ReloadOutcome::Reloaded));
+        assert_eq!(report.get("static"), Some(&ReloadOutcome::Skipped));
+        assert_eq!(report.get("last"), Some(&ReloadOutcome::Reloaded));
+        assert!(!report.is_ok());
+        assert_eq!(
+            report.failures().collect::<Vec<_>>(),
+            vec![("failing", "Error: invalid configuration")]
+        );
+        assert_eq!(first.level.load(Ordering::SeqCst), 2);
+        assert_eq!(last.level.load(Ordering::SeqCst), 2);
+
+        let reloads = runtime
+            .events_snapshot()
+            .into_iter()
+            .filter(|event| matches!(event.kind, EventKind::Reload(_)))
+            .map(|event| (event.source, event.kind.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reloads,
+            vec![
+                ("first".to_string(), "reloaded".to_string()),
+                (
+                    "failing".to_string(),
+                    "reload failed: Error: invalid configuration".to_string()
+                ),
+                ("last".to_string(), "reloaded".to_string()),
+            ]
+        );
+
+        runtime.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_runtime_status() {
+        let runtime = Runtime::default();
+        let first = MockService::new("first", false, false);
+        let second = MockService::new("second", false, false);
+        runtime.bind(first);
+        runtime.bind(second);
+        let events = runtime.subscribe();
+
+        let status = runtime.status();
+        assert_eq!(status.len(), 2);
+        assert_eq!(status[0].name, "first");
+        assert_eq!(status[0].state, None);
+
+        runtime.start().await.unwrap();
+        runtime.record_event(
+            "second",
+            EventKind::Health {
+                healthy: false,
+                reason: Some("disconnected".to_string()),
+            },
+        );
+        let status = runtime.status();
+        assert_eq!(status[0].state, Some(ServiceState::Running));
+        assert!(status[0].is_ok());
+        assert_eq!(status[1].healthy, Some(false));
+        assert_eq!(status[1].reason.as_deref(), Some("disconnected"));
+        assert!(!status[1].is_ok());
+
+        runtime.shutdown().await;
+        assert!(runtime
+            .status()
+            .iter()
+            .all(|status| status.state == Some(ServiceState::Terminated)));
+
+        let sources = std::iter::from_fn(|| events.try_recv().ok())
+            .map(|event| event.source)
+            .collect::<Vec<_>>();
+        assert_eq!(sources.first().map(String::as_str), Some("runtime"));
+        assert!(sources.iter().any(|source| source == "second"));
+    }
+}
@@ -14,4 +14,11 @@ pub trait Service: Sync + Send {
 
     /// Block until the service is terminated
     async fn join(self: Arc<Self>) -> Result<()>;
+
+    /// Apply the configuration supplied to [`Runtime::reload()`].
+    /// Returns `None` if the service does not support configuration
+    /// reload (the default).
+    async fn on_reload(self: Arc<Self>, _config: ReloadConfig) -> Option<Result<()>> {
+        None
+    }
 }
@@ -18,6 +18,13 @@ impl Signals {
 
         ctrlc::set_handler(move || {
             let v = signals.iterations.fetch_add(1, Ordering::SeqCst);
+            signals.runtime.record_event(
+                "signals",
+                EventKind::Signal {
+                    name: "SIGTERM".to_string(),
+                    count: v + 1,
+                },
+            );
 
             match v {
                 0 => {
@@ -32,4 +39,50 @@ impl Signals {
         })
         .expect("Error setting signal handler");
     }
+
+    /// Reload the configuration on `SIGHUP`: the configuration produced
+    /// by `load` is delivered to the running services via [`Runtime::reload()`].
+    /// Must be called within the async runtime context.
+    #[cfg(unix)]
+    pub fn bind_reload(
+        runtime: &Runtime,
+        load: impl Fn() -> Result<ReloadConfig> + Send + Sync + 'static,
+    ) -> Result<()> {
+        use signal_hook::{consts::SIGHUP, iterator::Signals as SignalIterator};
+
+        let mut signals = SignalIterator::new([SIGHUP])?;
+        let requests = Channel::<()>::unbounded();
+        let sender = requests.sender.clone();
+        std::thread::spawn(move || {
+            for _ in signals.forever() {
+                if sender.try_send(()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let runtime = runtime.clone();
+        spawn(async move {
+            let mut count = 0;
+            while requests.recv().await.is_ok() {
+                count += 1;
+                runtime.record_event(
+                    "signals",
+                    EventKind::Signal {
+                        name: "SIGHUP".to_string(),
+                        count,
+                    },
+                );
+                println!("^SIGHUP - reloading configuration...");
+                match load() {
+                    Ok(config) => {
+                        runtime.reload(config).await;
+                    }
+                    Err(err) => log_error!("Configuration load error: {err}"),
+                }
+            }
+        });
+
+        Ok(())
+    }
 }
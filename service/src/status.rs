@@ -0,0 +1,48 @@
+//!
+//! Per-service status derived from the runtime [`Journal`]
+//! (see [`Runtime::status()`]).
+//!
+
+use crate::imports::*;
+
+/// Lifecycle state and health of a service bound to the [`Runtime`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceStatus {
+    pub name: &'static str,
+    /// Last lifecycle state recorded (`None` before the service is started)
+    pub state: Option<ServiceState>,
+    /// Last health change recorded by the service (`None` if
+    /// the service does not monitor its health)
+    pub healthy: Option<bool>,
+    /// Reason supplied with the last health change
+    pub reason: Option<String>,
+}
+
+impl ServiceStatus {
+    pub(crate) fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            state: None,
+            healthy: None,
+            reason: None,
+        }
+    }
+
+    /// Apply the journal `event` of this service
+    pub(crate) fn apply(&mut self, event: &Event) {
+        match &event.kind {
+            EventKind::State(state) => self.state = Some(*state),
+            EventKind::Health { healthy, reason } => {
+                self.healthy = Some(*healthy);
+                self.reason.clone_from(reason);
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns `true` if the service is running and
+    /// has not reported itself as unhealthy
+    pub fn is_ok(&self) -> bool {
+        self.state == Some(ServiceState::Running) && self.healthy != Some(false)
+    }
+}
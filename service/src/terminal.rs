@@ -0,0 +1,49 @@
+//!
+//! `debug` command for [`workflow_terminal::HandlerCli`] printing
+//! the runtime event journal (see [`Runtime::dump()`]).
+//!
+//! ```ignore
+//! let cli = HandlerCli::new();
+//! cli.register(&ctx, DebugHandler::new(&runtime));
+//! ```
+//!
+
+use crate::imports::*;
+use workflow_terminal::result::Result as TerminalResult;
+use workflow_terminal::{Context, Handler};
+
+pub struct DebugHandler {
+    runtime: Runtime,
+}
+
+impl DebugHandler {
+    pub fn new(runtime: &Runtime) -> Self {
+        Self {
+            runtime: runtime.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Handler for DebugHandler {
+    fn verb(&self, _ctx: &Arc<dyn Context>) -> Option<&'static str> {
+        Some("debug")
+    }
+
+    fn help(&self, _ctx: &Arc<dyn Context>) -> &'static str {
+        "Display the service runtime event log"
+    }
+
+    async fn handle(
+        self: Arc<Self>,
+        ctx: &Arc<dyn Context>,
+        _argv: Vec<String>,
+        _cmd: &str,
+    ) -> TerminalResult<()> {
+        let term = ctx.term();
+        for line in self.runtime.dump().lines() {
+            term.writeln(line);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,426 @@
+//!
+//! Backup archives of a [`JsonStore`]. [`JsonStore::export_archive()`]
+//! produces a single binary blob carrying all top-level keys of the
+//! document and [`JsonStore::import_archive()`] restores them:
+//!
+//! ```text
+//! "wfsa" | version: u8 | flags: u8 | count: u32 | (key_len: u32 | key | value_len: u32 | value)*
+//! ```
+//!
+//! Integers are little-endian, keys are UTF-8 and values are JSON text.
+//! The flags are reserved (no compression is currently applied) and must
+//! be zero. Archives can be password-protected using
+//! [`JsonStore::export_archive_encrypted()`], which wraps the archive in
+//! the `workflow-encryption` envelope (`XChaCha20Poly1305` with the key
+//! derived from the password via `Argon2id`).
+//!
+//! ```ignore
+//! let backup = settings.export_archive().await?;
+//! // ...
+//! let report = settings.import_archive(&backup, ImportMode::Merge).await?;
+//! for conflict in report.conflicts {
+//!     log_info!("`{}` has been overwritten", conflict.key);
+//! }
+//! ```
+//!
+
+use crate::json::JsonStore;
+use crate::permissions::{write_atomic_with_options, WriteOptions};
+use crate::result::Result;
+use serde_json::{Map, Value};
+use std::path::Path;
+use thiserror::Error;
+use workflow_encryption::chacha20poly1305::{decrypt_slice, encrypt_slice};
+use workflow_encryption::secret::Secret;
+
+/// Magic bytes identifying a store archive.
+pub const ARCHIVE_MAGIC: [u8; 4] = *b"wfsa";
+/// Current archive format version.
+pub const ARCHIVE_VERSION: u8 = 1;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveError {
+    #[error("not a store archive")]
+    Magic,
+
+    #[error("unsupported archive version {0}")]
+    Version(u8),
+
+    #[error("unsupported archive flags {0:#04x}")]
+    Flags(u8),
+
+    #[error("archive is truncated")]
+    Truncated,
+
+    #[error("archive has trailing data")]
+    TrailingData,
+
+    #[error("archive entry {index} has an invalid key")]
+    Key { index: u32 },
+
+    #[error("archive entry `{key}` has an invalid value: {reason}")]
+    Value { key: String, reason: String },
+
+    #[error("archive contains the key `{0}` multiple times")]
+    DuplicateKey(String),
+}
+
+/// Handling of the archive entries by [`JsonStore::import_archive()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Replace the entire document with the archive
+    /// (keys missing in the archive are removed)
+    Replace,
+    /// Restore all entries, overwriting existing keys
+    Merge,
+    /// Restore entries of keys missing in the document,
+    /// retaining the values of existing keys
+    SkipExisting,
+}
+
+/// Entry of the archive whose key exists in the document with a different value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportConflict {
+    pub key: String,
+    /// `true` if the archived value has been written
+    pub overwritten: bool,
+}
+
+/// Outcome of [`JsonStore::import_archive()`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Number of entries written to the document
+    pub imported: usize,
+    /// Number of keys removed from the document ([`ImportMode::Replace`])
+    pub removed: usize,
+    pub conflicts: Vec<ImportConflict>,
+}
+
+/// Serializes the top-level entries of the `doc` (an object) into an archive.
+pub fn encode(doc: &Map<String, Value>) -> Result<Vec<u8>> {
+    let mut data = ARCHIVE_MAGIC.to_vec();
+    data.push(ARCHIVE_VERSION);
+    data.push(0);
+    data.extend_from_slice(&(doc.len() as u32).to_le_bytes());
+    for (key, value) in doc {
+        let value = serde_json::to_vec(value)?;
+        data.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        data.extend_from_slice(key.as_bytes());
+        data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        data.extend_from_slice(&value);
+    }
+    Ok(data)
+}
+
+/// Validates the archive and deserializes its entries.
+pub fn decode(data: &[u8]) -> std::result::Result<Vec<(String, Value)>, ArchiveError> {
+    let mut reader = Reader(data);
+    if reader.take(ARCHIVE_MAGIC.len())? != ARCHIVE_MAGIC {
+        return Err(ArchiveError::Magic);
+    }
+    let version = reader.u8()?;
+    if version != ARCHIVE_VERSION {
+        return Err(ArchiveError::Version(version));
+    }
+    let flags = reader.u8()?;
+    if flags != 0 {
+        return Err(ArchiveError::Flags(flags));
+    }
+
+    let count = reader.u32()?;
+    // each entry occupies at least 8 bytes, so that a corrupted
+    // count can not trigger an excessive allocation
+    if count as usize > reader.0.len() / 8 {
+        return Err(ArchiveError::Truncated);
+    }
+    let mut entries = Vec::with_capacity(count as usize);
+    let mut keys = std::collections::HashSet::new();
+    for index in 0..count {
+        let len = reader.u32()? as usize;
+        let key = std::str::from_utf8(reader.take(len)?)
+            .map_err(|_| ArchiveError::Key { index })?
+            .to_string();
+        let len = reader.u32()? as usize;
+        let value =
+            serde_json::from_slice(reader.take(len)?).map_err(|err| ArchiveError::Value {
+                key: key.clone(),
+                reason: err.to_string(),
+            })?;
+        if !keys.insert(key.clone()) {
+            return Err(ArchiveError::DuplicateKey(key));
+        }
+        entries.push((key, value));
+    }
+    if !reader.0.is_empty() {
+        return Err(ArchiveError::TrailingData);
+    }
+    Ok(entries)
+}
+
+struct Reader<'data>(&'data [u8]);
+
+impl<'data> Reader<'data> {
+    fn take(&mut self, len: usize) -> std::result::Result<&'data [u8], ArchiveError> {
+        if self.0.len() < len {
+            return Err(ArchiveError::Truncated);
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> std::result::Result<u8, ArchiveError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> std::result::Result<u32, ArchiveError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+/// Applies the archive `entries` to the `doc` according to the `mode`.
+fn apply(doc: &mut Value, entries: Vec<(String, Value)>, mode: ImportMode) -> ImportReport {
+    let mut report = ImportReport::default();
+    if !doc.is_object() {
+        *doc = Value::Object(Map::new());
+    }
+    let map = doc.as_object_mut().unwrap();
+
+    if mode == ImportMode::Replace {
+        let before = map.len();
+        map.retain(|key, _| entries.iter().any(|(archived, _)| archived == key));
+        report.removed = before - map.len();
+    }
+
+    for (key, value) in entries {
+        match map.get(&key) {
+            Some(existing) if *existing == value => {}
+            Some(_) => {
+                let overwritten = mode != ImportMode::SkipExisting;
+                report.conflicts.push(ImportConflict {
+                    key: key.clone(),
+                    overwritten,
+                });
+                if overwritten {
+                    map.insert(key, value);
+                    report.imported += 1;
+                }
+            }
+            None => {
+                map.insert(key, value);
+                report.imported += 1;
+            }
+        }
+    }
+    report
+}
+
+impl JsonStore {
+    /// Exports all top-level entries of the document into an archive
+    /// (see [`crate::archive`]). A document that is not an object is
+    /// exported as an empty archive.
+    pub async fn export_archive(&self) -> Result<Vec<u8>> {
+        match self.load().await? {
+            Value::Object(doc) => encode(&doc),
+            _ => encode(&Map::new()),
+        }
+    }
+
+    /// Restores the entries of the archive produced by [`JsonStore::export_archive()`].
+    /// The archive is validated before the document is modified and the document
+    /// is written once, while retaining the exclusive store lock (see
+    /// [`JsonStore::transact()`]). Entries whose keys exist in the document
+    /// with a different value are reported as [`ImportConflict`]s.
+    pub async fn import_archive(&self, archive: &[u8], mode: ImportMode) -> Result<ImportReport> {
+        let entries = decode(archive)?;
+        self.transact(move |doc| Ok(apply(doc, entries, mode)))
+            .await
+    }
+
+    /// Exports the archive encrypted with the `password`.
+    pub async fn export_archive_encrypted(&self, password: &Secret) -> Result<Vec<u8>> {
+        let archive = self.export_archive().await?;
+        Ok(encrypt_slice(&archive, password)?)
+    }
+
+    /// Writes the archive encrypted with the `password` to the file at `path`.
+    /// The file is written atomically and restricted to the owner
+    /// ([`SECRET_FILE_MODE`](crate::permissions::SECRET_FILE_MODE)).
+    pub async fn save_archive_encrypted(&self, path: &Path, password: &Secret) -> Result<()> {
+        let encrypted = self.export_archive_encrypted(password).await?;
+        write_atomic_with_options(path, WriteOptions::secret(), &encrypted).await
+    }
+
+    /// Decrypts the archive produced by [`JsonStore::export_archive_encrypted()`]
+    /// and restores its entries (see [`JsonStore::import_archive()`]).
+    pub async fn import_archive_encrypted(
+        &self,
+        archive: &[u8],
+        password: &Secret,
+        mode: ImportMode,
+    ) -> Result<ImportReport> {
+        let archive = decrypt_slice(archive, password)?;
+        self.import_archive(archive.as_slice(), mode).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_archive_validation() {
+        let doc = json!({ "a": 1, "b": { "c": [true, null] } });
+        let archive = encode(doc.as_object().unwrap()).unwrap();
+        assert_eq!(
+            decode(&archive).unwrap(),
+            vec![
+                ("a".to_string(), json!(1)),
+                ("b".to_string(), json!({ "c": [true, null] }))
+            ]
+        );
+
+        assert_eq!(decode(b"json"), Err(ArchiveError::Magic));
+        let mut data = archive.clone();
+        data[4] = 2;
+        assert_eq!(decode(&data), Err(ArchiveError::Version(2)));
+        let mut data = archive.clone();
+        data[5] = 1;
+        assert_eq!(decode(&data), Err(ArchiveError::Flags(1)));
+        for len in 0..archive.len() {
+            assert!(decode(&archive[..len]).is_err());
+        }
+        let mut data = archive.clone();
+        data.push(0);
+        assert_eq!(decode(&data), Err(ArchiveError::TrailingData));
+        // corrupted entry count
+        let mut data = archive.clone();
+        data[6..10].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(decode(&data), Err(ArchiveError::Truncated));
+    }
+
+    #[test]
+    fn test_archive_import_modes() {
+        let entries = || vec![("a".to_string(), json!(1)), ("b".to_string(), json!(2))];
+        let existing = || json!({ "a": 1, "b": 0, "c": 3 });
+
+        let mut doc = existing();
+        let report = apply(&mut doc, entries(), ImportMode::Replace);
+        assert_eq!(doc, json!({ "a": 1, "b": 2 }));
+        assert_eq!((report.imported, report.removed), (1, 1));
+
+        let mut doc = existing();
+        let report = apply(&mut doc, entries(), ImportMode::Merge);
+        assert_eq!(doc, json!({ "a": 1, "b": 2, "c": 3 }));
+        assert_eq!(
+            report.conflicts,
+            vec![ImportConflict {
+                key: "b".to_string(),
+                overwritten: true
+            }]
+        );
+
+        let mut doc = existing();
+        let report = apply(&mut doc, entries(), ImportMode::SkipExisting);
+        assert_eq!(doc, existing());
+        assert_eq!(report.imported, 0);
+        assert_eq!(
+            report.conflicts,
+            vec![ImportConflict {
+                key: "b".to_string(),
+                overwritten: false
+            }]
+        );
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod store_tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::store::Store;
+    use serde_json::json;
+
+    fn json_store(name: &str) -> JsonStore {
+        let filename =
+            std::env::temp_dir().join(format!("workflow-store-{name}-{}.json", std::process::id()));
+        std::fs::remove_file(&filename).ok();
+        let mut store = Store::new();
+        store.with_generic(filename.to_str().unwrap());
+        JsonStore::new(store)
+    }
+
+    fn cleanup(store: &JsonStore) {
+        let filename = store.store().filename();
+        std::fs::remove_file(&filename).ok();
+        std::fs::remove_file(format!("{filename}.lock")).ok();
+    }
+
+    #[async_std::test]
+    async fn test_archive_round_trip() {
+        let source = json_store("archive-source");
+        let doc = json!({
+            "settings": { "theme": "dark", "panels": [200, 300] },
+            "accounts": ["alice", "bob"],
+            "version": 3
+        });
+        source.merge(doc.clone()).await.unwrap();
+        let archive = source.export_archive().await.unwrap();
+
+        let target = json_store("archive-target");
+        target.merge(json!({ "stale": true })).await.unwrap();
+        let report = target
+            .import_archive(&archive, ImportMode::Replace)
+            .await
+            .unwrap();
+        assert_eq!((report.imported, report.removed), (3, 1));
+        assert_eq!(target.load().await.unwrap(), doc);
+
+        // invalid archives leave the document unchanged
+        assert!(matches!(
+            target
+                .import_archive(&archive[..archive.len() - 1], ImportMode::Replace)
+                .await,
+            Err(Error::Archive(ArchiveError::Truncated))
+        ));
+        assert_eq!(target.load().await.unwrap(), doc);
+
+        // password-protected archive
+        let password = Secret::from("backup password");
+        let encrypted = source.export_archive_encrypted(&password).await.unwrap();
+        let restored = json_store("archive-restored");
+        restored
+            .import_archive_encrypted(&encrypted, &password, ImportMode::Merge)
+            .await
+            .unwrap();
+        assert_eq!(restored.load().await.unwrap(), doc);
+        assert!(matches!(
+            restored
+                .import_archive_encrypted(&encrypted, &Secret::from("wrong"), ImportMode::Merge)
+                .await,
+            Err(Error::Encryption(_))
+        ));
+
+        // encrypted archive files are restricted to the owner
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let path = std::env::temp_dir()
+                .join(format!("workflow-store-archive-{}.bin", std::process::id()));
+            source
+                .save_archive_encrypted(&path, &password)
+                .await
+                .unwrap();
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+            std::fs::remove_file(&path).ok();
+        }
+
+        cleanup(&source);
+        cleanup(&target);
+        cleanup(&restored);
+    }
+}
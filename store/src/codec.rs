@@ -0,0 +1,196 @@
+//!
+//! Compact text encoding of binary data stored in string-only storage
+//! backends (browser `localStorage` and `chrome.storage`).
+//!
+//! Binary data is packed 15 bits per UTF-16 code unit, with each 15-bit
+//! group mapped to a character in the `U+3000..=U+AFFF` range (avoiding
+//! the surrogate range, so the text is valid UTF-16). The encoded text
+//! starts with a single header character marking the encoding:
+//!
+//! ```text
+//! '\u{1}' | groups      - packed data
+//! '\u{2}' | groups      - packed data, the last decoded byte is padding
+//! ```
+//!
+//! Compared to the hex encoding previously used for binary data (which
+//! is still decoded transparently), this reduces the storage consumed by
+//! binary values from 2 to approximately 0.53 UTF-16 code units per byte.
+//!
+
+use thiserror::Error;
+use workflow_core::hex::FromHex;
+
+/// Header of packed data
+pub const PACKED: char = '\u{1}';
+/// Header of packed data where the last decoded byte is padding
+pub const PACKED_PADDED: char = '\u{2}';
+/// Code point representing the 15-bit group `0`
+const BASE: u32 = 0x3000;
+const BITS: u32 = 15;
+const MASK: u32 = (1 << BITS) - 1;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CodecError {
+    /// The text contains a character outside of the encoding alphabet
+    #[error("invalid character {0:?} at position {1}")]
+    Character(char, usize),
+    /// The text is neither packed nor legacy hex-encoded data
+    #[error("unknown encoding")]
+    Encoding,
+}
+
+/// Encoding of binary data in a stored text value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// 15 bits per character (see [module](self) documentation)
+    Packed,
+    /// Hex encoding used by earlier versions (no header)
+    Hex,
+}
+
+impl Encoding {
+    /// Detects the encoding of the stored `text`
+    pub fn detect(text: &str) -> Option<Encoding> {
+        match text.chars().next() {
+            Some(PACKED) | Some(PACKED_PADDED) => Some(Encoding::Packed),
+            _ if text.len() % 2 == 0 && text.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                Some(Encoding::Hex)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Encodes `data` using the [`Encoding::Packed`] encoding
+pub fn encode(data: &[u8]) -> String {
+    let groups = (data.len() * 8).div_ceil(BITS as usize);
+    let padded = groups * BITS as usize - data.len() * 8 >= 8;
+
+    let mut text = String::with_capacity(1 + groups * 3);
+    text.push(if padded { PACKED_PADDED } else { PACKED });
+
+    let mut acc = 0u32;
+    let mut bits = 0u32;
+    for byte in data {
+        acc = (acc << 8) | *byte as u32;
+        bits += 8;
+        if bits >= BITS {
+            bits -= BITS;
+            text.push(group((acc >> bits) & MASK));
+            acc &= (1 << bits) - 1;
+        }
+    }
+    if bits > 0 {
+        text.push(group((acc << (BITS - bits)) & MASK));
+    }
+    text
+}
+
+fn group(value: u32) -> char {
+    // BASE + MASK is below the surrogate range
+    char::from_u32(BASE + value).unwrap()
+}
+
+/// Decodes `text` produced by [`encode()`] or hex-encoded by earlier versions
+pub fn decode(text: &str) -> Result<Vec<u8>, CodecError> {
+    match Encoding::detect(text) {
+        Some(Encoding::Packed) => decode_packed(text),
+        Some(Encoding::Hex) => Vec::<u8>::from_hex(text).map_err(|_| CodecError::Encoding),
+        None => Err(CodecError::Encoding),
+    }
+}
+
+fn decode_packed(text: &str) -> Result<Vec<u8>, CodecError> {
+    let mut chars = text.chars();
+    let padded = chars.next() == Some(PACKED_PADDED);
+
+    let mut data = Vec::with_capacity(text.len() / 3 * 15 / 8);
+    let mut acc = 0u32;
+    let mut bits = 0u32;
+    for (index, c) in chars.enumerate() {
+        let value = (c as u32)
+            .checked_sub(BASE)
+            .filter(|value| *value <= MASK)
+            .ok_or(CodecError::Character(c, index + 1))?;
+        acc = (acc << BITS) | value;
+        bits += BITS;
+        while bits >= 8 {
+            bits -= 8;
+            data.push((acc >> bits) as u8);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    if padded {
+        data.pop();
+    }
+    Ok(data)
+}
+
+/// Estimated number of bytes consumed by a stored `text`
+/// (web storage quotas are measured in UTF-16 code units)
+pub fn storage_size(text: &str) -> usize {
+    text.encode_utf16().count() * 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use workflow_core::hex::ToHex;
+
+    /// xorshift generator producing reproducible test data
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn blob(&mut self) -> Vec<u8> {
+            let len = (self.next() % 512) as usize;
+            (0..len).map(|_| self.next() as u8).collect()
+        }
+    }
+
+    #[test]
+    fn test_codec_round_trip() {
+        for len in 0..64 {
+            let data = (0..len).map(|n| (n * 37) as u8).collect::<Vec<_>>();
+            assert_eq!(decode(&encode(&data)).unwrap(), data);
+        }
+
+        let mut random = Random(0x9e3779b97f4a7c15);
+        for _ in 0..1000 {
+            let data = random.blob();
+            let text = encode(&data);
+            assert_eq!(Encoding::detect(&text), Some(Encoding::Packed));
+            assert!(text
+                .encode_utf16()
+                .all(|unit| !(0xd800..0xe000).contains(&unit)));
+            assert_eq!(decode(&text).unwrap(), data);
+        }
+
+        assert_eq!(encode(&[]), PACKED.to_string());
+        assert_eq!(encode(&[0xff; 15]).chars().count(), 1 + 8);
+        assert_eq!(storage_size(&encode(&[0; 1500])), 2 * (1 + 800));
+    }
+
+    #[test]
+    fn test_codec_legacy_hex() {
+        let data = (0..=255).collect::<Vec<u8>>();
+        let legacy = data.to_hex();
+        assert_eq!(Encoding::detect(&legacy), Some(Encoding::Hex));
+        assert_eq!(decode(&legacy).unwrap(), data);
+
+        // rewriting a legacy entry reduces its size
+        let migrated = encode(&decode(&legacy).unwrap());
+        assert_eq!(decode(&migrated).unwrap(), data);
+        assert!(storage_size(&migrated) * 3 < storage_size(&legacy));
+
+        assert_eq!(decode("abc"), Err(CodecError::Encoding));
+        assert_eq!(decode("xyz0"), Err(CodecError::Encoding));
+        assert_eq!(decode("\u{1}\u{3000}a"), Err(CodecError::Character('a', 2)));
+    }
+}
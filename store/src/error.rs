@@ -52,6 +52,37 @@ pub enum Error {
 
     #[error("This operation is not supported")]
     NotSupported,
+
+    #[error("Store lock is held by another owner: {0}")]
+    WouldBlock(String),
+
+    #[error("Timeout waiting for store lock: {0}")]
+    Timeout(String),
+
+    #[error("Store lock is no longer held: {0}")]
+    LockLost(String),
+
+    #[error(transparent)]
+    JsonPath(#[from] crate::json::JsonPathError),
+
+    #[error("Store archive error: {0}")]
+    Archive(#[from] crate::archive::ArchiveError),
+
+    #[error("Storage quota exceeded writing {size} bytes to `{key}`")]
+    QuotaExceeded { key: String, size: usize },
+
+    #[error("Binary data decode error: {0}")]
+    Codec(#[from] crate::codec::CodecError),
+
+    #[error("`{path}` is accessible by other users (mode {mode:#o}, allowed {max_mode:#o})")]
+    InsecurePermissions {
+        path: String,
+        mode: u32,
+        max_mode: u32,
+    },
+
+    #[error(transparent)]
+    Encryption(#[from] workflow_encryption::error::Error),
 }
 
 impl From<Error> for JsValue {
@@ -11,6 +11,7 @@
 //! for localstorage. If you want to manually specify the localstorage key.
 //!
 
+use crate::codec;
 use crate::error::Error;
 use crate::result::Result;
 use cfg_if::cfg_if;
@@ -43,6 +44,65 @@ pub fn local_storage() -> web_sys::Storage {
         .expect("localStorage is not available")
 }
 
+/// Typical per-origin localStorage quota in bytes; browsers do not
+/// expose the actual quota, which commonly allows 5 MiB of UTF-16 text.
+pub const LOCAL_STORAGE_QUOTA_ESTIMATE: u64 = 5 * 1024 * 1024;
+
+/// Estimated localStorage usage (see [`local_storage_usage()`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageUsage {
+    /// Size of all stored keys and values, measured in UTF-16 code units
+    /// (2 bytes each) as counted against the quota by the browsers
+    pub used_bytes_estimate: u64,
+    /// See [`LOCAL_STORAGE_QUOTA_ESTIMATE`]
+    pub quota_estimate: u64,
+}
+
+impl StorageUsage {
+    /// Estimated number of bytes that can still be stored
+    pub fn available_estimate(&self) -> u64 {
+        self.quota_estimate.saturating_sub(self.used_bytes_estimate)
+    }
+}
+
+/// Computes the [`StorageUsage`] from the lengths of all localStorage keys and values
+pub fn local_storage_usage() -> Result<StorageUsage> {
+    let local_storage = local_storage();
+    let mut used_bytes_estimate = 0;
+    for i in 0..local_storage.length()? {
+        if let Some(key) = local_storage.key(i)? {
+            let value = local_storage.get_item(&key)?.unwrap_or_default();
+            used_bytes_estimate += (codec::storage_size(&key) + codec::storage_size(&value)) as u64;
+        }
+    }
+    Ok(StorageUsage {
+        used_bytes_estimate,
+        quota_estimate: LOCAL_STORAGE_QUOTA_ESTIMATE,
+    })
+}
+
+/// Stores `value` in localStorage, failing with [`Error::QuotaExceeded`]
+/// if the browser refuses the write due to the storage quota.
+pub fn set_local_storage_item(key: &str, value: &str) -> Result<()> {
+    local_storage().set_item(key, value).map_err(|err| {
+        if is_quota_exceeded(&err) {
+            Error::QuotaExceeded {
+                key: key.to_string(),
+                size: codec::storage_size(key) + codec::storage_size(value),
+            }
+        } else {
+            err.into()
+        }
+    })
+}
+
+fn is_quota_exceeded(err: &JsValue) -> bool {
+    Reflect::get(err, &"name".into())
+        .ok()
+        .and_then(|name| name.as_string())
+        .is_some_and(|name| name == "QuotaExceededError" || name == "NS_ERROR_DOM_QUOTA_REACHED")
+}
+
 #[derive(Default)]
 pub struct Options {
     pub local_storage_key: Option<String>,
@@ -64,7 +124,6 @@ impl Options {
 
 cfg_if! {
     if #[cfg(target_arch = "wasm32")] {
-        use workflow_core::hex::*;
         use workflow_wasm::jserror::*;
         use workflow_node as node;
         use js_sys::Object;
@@ -159,7 +218,7 @@ cfg_if! {
                 };
 
                 if let Some(text) = data{
-                    let data = Vec::<u8>::from_hex(&text)?;
+                    let data = codec::decode(&text)?;
                     Ok(data)
                 } else {
                     Err(Error::NotFound(filename.as_ref().to_string_lossy().to_string()))
@@ -179,7 +238,7 @@ cfg_if! {
             } else {
                 let key_name = options.local_storage_key(filename.as_ref());
                 if let Some(text) = local_storage().get_item(&key_name)? {
-                    let data = Vec::<u8>::from_hex(&text)?;
+                    let data = codec::decode(&text)?;
                     Ok(data)
                 } else {
                     Err(Error::NotFound(filename.as_ref().to_string_lossy().to_string()))
@@ -199,7 +258,7 @@ cfg_if! {
                 if runtime::is_chrome_extension(){
                     ChromeStorage::set_item(&key_name, text).await?;
                 }else{
-                    local_storage().set_item(&key_name, text)?;
+                    set_local_storage_item(&key_name, text)?;
                 }
             }
 
@@ -217,7 +276,7 @@ cfg_if! {
                 return Err(Error::Custom("localStorage api is unavailable, you can use write_string_with_options() for chrome.storage.local api.".to_string()));
             }else{
                 let key_name = options.local_storage_key(filename.as_ref());
-                local_storage().set_item(&key_name, text)?;
+                set_local_storage_item(&key_name, text)?;
             }
             Ok(())
         }
@@ -232,9 +291,9 @@ cfg_if! {
             } else {
                 let key_name = options.local_storage_key(filename.as_ref());
                 if runtime::is_chrome_extension(){
-                    ChromeStorage::set_item(&key_name, data.to_hex().as_str()).await?;
+                    ChromeStorage::set_item(&key_name, &codec::encode(data)).await?;
                 }else{
-                    local_storage().set_item(&key_name, data.to_hex().as_str())?;
+                    set_local_storage_item(&key_name, &codec::encode(data))?;
                 }
             }
             Ok(())
@@ -251,7 +310,7 @@ cfg_if! {
                 return Err(Error::Custom("localStorage api is unavailable, you can use write_binary_with_options() for chrome.storage.local api.".to_string()));
             }else{
                 let key_name = options.local_storage_key(filename.as_ref());
-                local_storage().set_item(&key_name, data.to_hex().as_str())?;
+                set_local_storage_item(&key_name, &codec::encode(data))?;
             }
 
             Ok(())
@@ -649,14 +708,14 @@ pub fn read_to_string_sync(filename: &Path) -> Result<String> {
 
 /// Read binary file contents to a `Vec<u8>`. If using within the web browser
 /// environment, a local storage key with the name of the file
-/// will be used and the data is assumed to be hex-encoded.
+/// will be used and the data is decoded using [`codec::decode()`].
 pub async fn read(filename: &Path) -> Result<Vec<u8>> {
     read_binary_with_options(filename, Options::default()).await
 }
 
 /// Read binary file contents to a `Vec<u8>`. If using within the web browser
 /// environment, a local storage key with the name of the file
-/// will be used and the data is assumed to be hex-encoded.
+/// will be used and the data is decoded using [`codec::decode()`].
 pub fn read_sync(filename: &Path) -> Result<Vec<u8>> {
     read_binary_with_options_sync(filename, Options::default())
 }
@@ -677,14 +736,14 @@ pub fn write_string_sync(filename: &Path, text: &str) -> Result<()> {
 
 /// Write a `Vec<u8>` to a binary file. If using within the web browser
 /// environment, a local storage key with the name of the file
-/// will be used and the data will be hex-encoded.
+/// will be used and the data is encoded using [`codec::encode()`].
 pub async fn write(filename: &Path, data: &[u8]) -> Result<()> {
     write_binary_with_options(filename, Options::default(), data).await
 }
 
 /// Write a `Vec<u8>` to a binary file. If using within the web browser
 /// environment, a local storage key with the name of the file
-/// will be used and the data will be hex-encoded.
+/// will be used and the data is encoded using [`codec::encode()`].
 pub async fn write_sync(filename: &Path, data: &[u8]) -> Result<()> {
     write_binary_with_options_sync(filename, Options::default(), data)
 }
@@ -787,7 +846,7 @@ pub fn resolve_path(path: &str) -> Result<PathBuf> {
                 if #[cfg(target_arch = "wasm32")] {
                     Ok(PathBuf::from(path))
                 } else {
-                    Ok(home::home_dir().ok_or_else(||Error::HomeDir(path.to_string()))?.join(_stripped))
+                    Ok(dirs::home_dir().ok_or_else(||Error::HomeDir(path.to_string()))?.join(_stripped))
                 }
             }
         }
@@ -886,3 +945,23 @@ where
     let path = path.replace(from, to);
     PathBuf::from(path)
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[async_std::test]
+    async fn test_resolve_path_respects_sandbox() {
+        let sandbox = workflow_core::testing::sandbox();
+        let filename = resolve_path("~/.workflow/settings.json").unwrap();
+        assert_eq!(filename, sandbox.home_dir().join(".workflow/settings.json"));
+
+        create_dir_all(sandbox.home_dir().join(".workflow"))
+            .await
+            .unwrap();
+        write_string(&filename, "{}").await.unwrap();
+        assert!(filename.is_file());
+        assert_eq!(read_to_string(&filename).await.unwrap(), "{}");
+    }
+}
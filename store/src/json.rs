@@ -0,0 +1,556 @@
+//!
+//! [`JsonStore`] - JSON document persisted in a [`Store`] with
+//! path-based reads and writes and deep (RFC 7386) merging.
+//!
+//! Paths consist of object keys separated by `.` and array indices
+//! enclosed in `[]` (e.g. `ui.panels[0].width`). The characters `.`, `[`,
+//! `]` and `\` can be used in keys when escaped with `\` (e.g. `hosts.example\.com`).
+//! An empty path refers to the entire document.
+//!
+//! ```ignore
+//! let mut store = Store::new();
+//! store.with_generic("~/.app/settings.json");
+//! let settings = JsonStore::new(store);
+//! settings.set_path("ui.theme", json!("dark")).await?;
+//! let theme = settings.get_path("ui.theme").await?;
+//! ```
+//!
+
+use crate::result::Result;
+use crate::store::Store;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum JsonPathError {
+    #[error("invalid path `{path}`: {reason}")]
+    Syntax { path: String, reason: String },
+
+    #[error("`{path}` is {found}, expected {expected}")]
+    TypeConflict {
+        path: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+
+    #[error("index {index} is out of bounds at `{path}` (length {len})")]
+    IndexOutOfBounds {
+        path: String,
+        index: usize,
+        len: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+impl Segment {
+    fn expected(&self) -> &'static str {
+        match self {
+            Segment::Key(_) => "an object",
+            Segment::Index(_) => "an array",
+        }
+    }
+}
+
+fn parse_path(path: &str) -> std::result::Result<Vec<Segment>, JsonPathError> {
+    let syntax = |reason: &str| JsonPathError::Syntax {
+        path: path.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let mut segments = Vec::new();
+    if path.is_empty() {
+        return Ok(segments);
+    }
+
+    let mut key = String::new();
+    // `true` following `]`, where only `.`, `[` or the end of the path are allowed
+    let mut indexed = false;
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                if indexed {
+                    indexed = false;
+                } else if key.is_empty() {
+                    return Err(syntax("empty key"));
+                } else {
+                    segments.push(Segment::Key(std::mem::take(&mut key)));
+                }
+            }
+            '[' => {
+                if !key.is_empty() {
+                    segments.push(Segment::Key(std::mem::take(&mut key)));
+                } else if !indexed && !segments.is_empty() {
+                    return Err(syntax("empty key"));
+                }
+                let mut index = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => index.push(c),
+                        None => return Err(syntax("unterminated index")),
+                    }
+                }
+                let index = index
+                    .parse::<usize>()
+                    .map_err(|_| syntax(&format!("invalid index `{index}`")))?;
+                segments.push(Segment::Index(index));
+                indexed = true;
+            }
+            ']' => return Err(syntax("unexpected `]`")),
+            c => {
+                if indexed {
+                    return Err(syntax("expected `.` or `[` after `]`"));
+                }
+                if c == '\\' {
+                    key.push(chars.next().ok_or_else(|| syntax("trailing escape"))?);
+                } else {
+                    key.push(c);
+                }
+            }
+        }
+    }
+
+    if !indexed {
+        if key.is_empty() {
+            return Err(syntax("empty key"));
+        }
+        segments.push(Segment::Key(key));
+    }
+
+    Ok(segments)
+}
+
+/// Path of the `segments` as displayed in errors
+fn display_path(segments: &[Segment]) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Key(key) => {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                for c in key.chars() {
+                    if matches!(c, '.' | '[' | ']' | '\\') {
+                        path.push('\\');
+                    }
+                    path.push(c);
+                }
+            }
+            Segment::Index(index) => path.push_str(&format!("[{index}]")),
+        }
+    }
+    path
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+fn get<'doc>(doc: &'doc Value, segments: &[Segment]) -> Option<&'doc Value> {
+    segments
+        .iter()
+        .try_fold(doc, |value, segment| match segment {
+            Segment::Key(key) => value.get(key.as_str()),
+            Segment::Index(index) => value.get(*index),
+        })
+}
+
+/// Sets the `value` at the path `segments`, creating missing (or `null`)
+/// intermediate objects and arrays. Array elements can be appended by
+/// referring to the index equal to the array length.
+fn set(
+    doc: &mut Value,
+    segments: &[Segment],
+    value: Value,
+) -> std::result::Result<(), JsonPathError> {
+    let mut target = doc;
+    for (depth, segment) in segments.iter().enumerate() {
+        if target.is_null() {
+            *target = match segment {
+                Segment::Key(_) => Value::Object(Map::new()),
+                Segment::Index(_) => Value::Array(Vec::new()),
+            };
+        }
+        target = match (segment, target) {
+            (Segment::Key(key), Value::Object(map)) => {
+                map.entry(key.clone()).or_insert(Value::Null)
+            }
+            (Segment::Index(index), Value::Array(array)) => {
+                if *index == array.len() {
+                    array.push(Value::Null);
+                }
+                let len = array.len();
+                array
+                    .get_mut(*index)
+                    .ok_or_else(|| JsonPathError::IndexOutOfBounds {
+                        path: display_path(&segments[..depth]),
+                        index: *index,
+                        len,
+                    })?
+            }
+            (segment, target) => {
+                return Err(JsonPathError::TypeConflict {
+                    path: display_path(&segments[..depth]),
+                    expected: segment.expected(),
+                    found: kind(target),
+                })
+            }
+        };
+    }
+    *target = value;
+    Ok(())
+}
+
+/// Deep merge of the `patch` into the `target` following RFC 7386
+/// (JSON Merge Patch): objects are merged recursively, `null` members
+/// of the patch remove the corresponding members of the target and
+/// all other values (including arrays) replace the target.
+pub fn merge_patch(target: &mut Value, patch: Value) {
+    match patch {
+        Value::Object(patch) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            if let Value::Object(map) = target {
+                for (key, value) in patch {
+                    if value.is_null() {
+                        map.remove(&key);
+                    } else {
+                        merge_patch(map.entry(key).or_insert(Value::Null), value);
+                    }
+                }
+            }
+        }
+        patch => *target = patch,
+    }
+}
+
+///
+/// JSON document persisted in a [`Store`]. Updates are performed by
+/// [`JsonStore::transact()`] that retains the exclusive store lock
+/// (see [`Store::lock_exclusive()`]) while reading, modifying and
+/// writing the document, preventing concurrent writers (including
+/// other processes) from losing each other's updates.
+///
+/// A missing or empty store is treated as an empty object.
+///
+pub struct JsonStore {
+    store: Store,
+}
+
+impl JsonStore {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Reads the entire document
+    pub async fn load(&self) -> Result<Value> {
+        if !self.store.exists().await? {
+            return Ok(Value::Object(Map::new()));
+        }
+        let text = self.store.read_to_string().await?;
+        if text.trim().is_empty() {
+            Ok(Value::Object(Map::new()))
+        } else {
+            Ok(serde_json::from_str(&text)?)
+        }
+    }
+
+    /// Reads the value at the `path`, returning `None` if the
+    /// path does not exist (or traverses a value of another type).
+    pub async fn get_path(&self, path: &str) -> Result<Option<Value>> {
+        let segments = parse_path(path)?;
+        let doc = self.load().await?;
+        Ok(get(&doc, &segments).cloned())
+    }
+
+    /// Sets the value at the `path`, failing with [`JsonPathError::TypeConflict`]
+    /// if the path traverses a value other than an object or an array.
+    pub async fn set_path(&self, path: &str, value: Value) -> Result<()> {
+        let segments = parse_path(path)?;
+        self.transact(move |doc| Ok(set(doc, &segments, value)?))
+            .await
+    }
+
+    /// Merges the `patch` into the document (see [`merge_patch()`]).
+    pub async fn merge(&self, patch: Value) -> Result<()> {
+        self.transact(move |doc| {
+            merge_patch(doc, patch);
+            Ok(())
+        })
+        .await
+    }
+
+    /// Reads the document, applies the closure and writes the document
+    /// while retaining the exclusive store lock. The document is not
+    /// written if the closure fails.
+    pub async fn transact<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut Value) -> Result<R>,
+    {
+        let lock = self.store.lock_exclusive().await?;
+        let mut doc = self.load().await?;
+        let result = f(&mut doc)?;
+        self.store
+            .write_string_atomic(&serde_json::to_string_pretty(&doc)?)
+            .await?;
+        lock.release()?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_path_parse() {
+        use Segment::*;
+        let key = |key: &str| Key(key.to_string());
+
+        assert_eq!(parse_path("").unwrap(), vec![]);
+        assert_eq!(
+            parse_path("ui.theme").unwrap(),
+            vec![key("ui"), key("theme")]
+        );
+        assert_eq!(
+            parse_path("ui.panels[1][0].width").unwrap(),
+            vec![key("ui"), key("panels"), Index(1), Index(0), key("width")]
+        );
+        assert_eq!(parse_path("[2].id").unwrap(), vec![Index(2), key("id")]);
+        assert_eq!(
+            parse_path(r"hosts.example\.com.port").unwrap(),
+            vec![key("hosts"), key("example.com"), key("port")]
+        );
+        assert_eq!(parse_path(r"a\[0\]\\").unwrap(), vec![key(r"a[0]\")]);
+        assert_eq!(
+            display_path(&parse_path(r"a\[0\].b\.c[3]").unwrap()),
+            r"a\[0\].b\.c[3]"
+        );
+
+        for path in [
+            "ui.",
+            ".ui",
+            "ui..theme",
+            "ui[x]",
+            "ui[1",
+            "ui]",
+            "ui[0]x",
+            "ui.[0]",
+            r"ui\",
+        ] {
+            assert!(
+                matches!(parse_path(path), Err(JsonPathError::Syntax { .. })),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_path_set() {
+        let mut doc = json!({ "ui": { "theme": "light" }, "items": [1, 2] });
+        let mut set_path =
+            |path: &str, value: Value| set(&mut doc, &parse_path(path).unwrap(), value);
+
+        set_path("ui.theme", json!("dark")).unwrap();
+        set_path("ui.panels[0].width", json!(200)).unwrap();
+        set_path("items[2]", json!(3)).unwrap();
+        set_path(r"hosts.example\.com", json!(true)).unwrap();
+
+        assert_eq!(
+            set_path("ui.theme.name", json!("x")),
+            Err(JsonPathError::TypeConflict {
+                path: "ui.theme".to_string(),
+                expected: "an object",
+                found: "a string",
+            })
+        );
+        assert_eq!(
+            set_path("ui[0]", json!("x")),
+            Err(JsonPathError::TypeConflict {
+                path: "ui".to_string(),
+                expected: "an array",
+                found: "an object",
+            })
+        );
+        assert_eq!(
+            set_path("items[5]", json!(6)),
+            Err(JsonPathError::IndexOutOfBounds {
+                path: "items".to_string(),
+                index: 5,
+                len: 3,
+            })
+        );
+
+        assert_eq!(
+            doc,
+            json!({
+                "ui": { "theme": "dark", "panels": [{ "width": 200 }] },
+                "items": [1, 2, 3],
+                "hosts": { "example.com": true },
+            })
+        );
+        assert_eq!(
+            get(&doc, &parse_path("ui.panels[0].width").unwrap()),
+            Some(&json!(200))
+        );
+        assert_eq!(get(&doc, &parse_path("ui.theme.name").unwrap()), None);
+    }
+
+    #[test]
+    fn test_merge_patch() {
+        // RFC 7386 appendix A examples
+        let cases = [
+            (json!({"a":"b"}), json!({"a":"c"}), json!({"a":"c"})),
+            (json!({"a":"b"}), json!({"b":"c"}), json!({"a":"b","b":"c"})),
+            (json!({"a":"b"}), json!({"a":null}), json!({})),
+            (
+                json!({"a":"b","b":"c"}),
+                json!({"a":null}),
+                json!({"b":"c"}),
+            ),
+            (json!({"a":["b"]}), json!({"a":"c"}), json!({"a":"c"})),
+            (json!({"a":"c"}), json!({"a":["b"]}), json!({"a":["b"]})),
+            (
+                json!({"a":{"b":"c"}}),
+                json!({"a":{"b":"d","c":null}}),
+                json!({"a":{"b":"d"}}),
+            ),
+            (json!({"a":[{"b":"c"}]}), json!({"a":[1]}), json!({"a":[1]})),
+            (json!(["a", "b"]), json!(["c", "d"]), json!(["c", "d"])),
+            (json!({"a":"b"}), json!(["c"]), json!(["c"])),
+            (json!({"a":"foo"}), json!(null), json!(null)),
+            (json!({"a":"foo"}), json!("bar"), json!("bar")),
+            (json!({"e":null}), json!({"a":1}), json!({"e":null,"a":1})),
+            (json!([1, 2]), json!({"a":"b","c":null}), json!({"a":"b"})),
+            (
+                json!({}),
+                json!({"a":{"bb":{"ccc":null}}}),
+                json!({"a":{"bb":{}}}),
+            ),
+        ];
+        for (mut target, patch, expected) in cases {
+            merge_patch(&mut target, patch);
+            assert_eq!(target, expected);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod store_tests {
+    use super::*;
+    use crate::error::Error;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn json_store(name: &str) -> JsonStore {
+        let filename =
+            std::env::temp_dir().join(format!("workflow-store-{name}-{}.json", std::process::id()));
+        std::fs::remove_file(&filename).ok();
+        let mut store = Store::new();
+        store.with_generic(filename.to_str().unwrap());
+        JsonStore::new(store)
+    }
+
+    fn cleanup(store: &JsonStore) {
+        let filename = store.store().filename();
+        std::fs::remove_file(&filename).ok();
+        std::fs::remove_file(format!("{filename}.lock")).ok();
+    }
+
+    #[async_std::test]
+    async fn test_json_store_round_trip() {
+        let store = json_store("json-round-trip");
+        assert_eq!(store.load().await.unwrap(), json!({}));
+        assert_eq!(store.get_path("ui.theme").await.unwrap(), None);
+
+        store.set_path("ui.theme", json!("dark")).await.unwrap();
+        store
+            .set_path("ui.panels[0]", json!({ "width": 200 }))
+            .await
+            .unwrap();
+        store
+            .merge(json!({ "ui": { "panels": null, "font": "mono" }, "version": 2 }))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.get_path("ui.theme").await.unwrap(),
+            Some(json!("dark"))
+        );
+        assert_eq!(
+            store.get_path("").await.unwrap(),
+            Some(json!({ "ui": { "theme": "dark", "font": "mono" }, "version": 2 }))
+        );
+
+        // failed updates leave the document unchanged
+        assert!(matches!(
+            store.set_path("version.major", json!(1)).await,
+            Err(Error::JsonPath(JsonPathError::TypeConflict { .. }))
+        ));
+        let text = store.store().read_to_string().await.unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&text).unwrap()["version"],
+            json!(2)
+        );
+
+        cleanup(&store);
+    }
+
+    #[async_std::test]
+    async fn test_json_store_concurrent_transact() {
+        const TASKS: usize = 4;
+        const UPDATES: usize = 10;
+
+        let store = Arc::new(json_store("json-transact"));
+        let tasks = (0..TASKS)
+            .map(|n| {
+                let store = store.clone();
+                async_std::task::spawn(async move {
+                    for _ in 0..UPDATES {
+                        store
+                            .transact(|doc| {
+                                let counter = doc["counter"].as_u64().unwrap_or(0);
+                                doc["counter"] = json!(counter + 1);
+                                let count = doc["tasks"][n.to_string()].as_u64().unwrap_or(0);
+                                doc["tasks"][n.to_string()] = json!(count + 1);
+                                Ok(())
+                            })
+                            .await
+                            .unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for task in tasks {
+            task.await;
+        }
+
+        let doc = store.load().await.unwrap();
+        assert_eq!(doc["counter"], json!(TASKS * UPDATES));
+        for n in 0..TASKS {
+            assert_eq!(doc["tasks"][n.to_string()], json!(UPDATES));
+        }
+
+        cleanup(&store);
+    }
+}
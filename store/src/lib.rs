@@ -18,9 +18,14 @@ use cfg_if::cfg_if;
 cfg_if! {
     if #[cfg(not(target_arch = "bpf"))] {
         pub mod prelude;
+        pub mod archive;
+        pub mod codec;
         pub mod error;
         pub mod result;
         pub mod fs;
+        pub mod json;
+        pub mod lock;
+        pub mod permissions;
         pub mod store;
     }
 }
@@ -0,0 +1,391 @@
+//!
+//! Lease-based locking used in environments lacking file locks
+//! (Node.js lock files and browser localStorage). A lease record
+//! lists lock holders along with their expiry timestamps; holders
+//! must renew their lease before it expires, allowing other
+//! contenders to recover locks abandoned by crashed holders.
+//!
+//! Lease storage backends do not offer compare-and-swap semantics,
+//! hence acquisition is best-effort; a holder losing its lease to
+//! a racing contender detects the loss on renewal or verification.
+//!
+
+use super::LockMode;
+use crate::result::Result;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use workflow_core::id::Id;
+use workflow_core::time::unixtime_as_millis_u64;
+
+/// Storage of the lease record.
+pub(crate) trait LeaseStorage: Send + Sync {
+    fn read(&self) -> Result<Option<String>>;
+    fn write(&self, record: &str) -> Result<()>;
+    /// Creates the record unless it already exists, returning
+    /// `false` if it does.
+    fn create(&self, record: &str) -> Result<bool>;
+    fn remove(&self) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Holder {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pid: Option<u32>,
+    expires: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Record {
+    mode: LockMode,
+    holders: Vec<Holder>,
+}
+
+impl Record {
+    /// Removes expired holders, returning `true` if any holders remain.
+    fn retain_live(&mut self, now: u64) -> bool {
+        self.holders.retain(|holder| holder.expires > now);
+        !self.holders.is_empty()
+    }
+}
+
+pub(crate) struct Lease {
+    storage: Box<dyn LeaseStorage>,
+    id: String,
+    pid: Option<u32>,
+    mode: LockMode,
+    duration: Duration,
+}
+
+impl Lease {
+    pub fn new(
+        storage: Box<dyn LeaseStorage>,
+        mode: LockMode,
+        duration: Duration,
+        pid: Option<u32>,
+    ) -> Self {
+        Self {
+            storage,
+            id: Id::new().to_string(),
+            pid,
+            mode,
+            duration,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    fn holder(&self, now: u64) -> Holder {
+        Holder {
+            id: self.id.clone(),
+            pid: self.pid,
+            expires: now + self.duration.as_millis() as u64,
+        }
+    }
+
+    /// Loads the lease record; a malformed record is treated as absent.
+    fn load(&self) -> Result<Option<Record>> {
+        Ok(self
+            .storage
+            .read()?
+            .and_then(|record| serde_json::from_str(&record).ok()))
+    }
+
+    fn store(&self, record: &Record) -> Result<()> {
+        self.storage.write(&serde_json::to_string(record)?)
+    }
+
+    /// Attempts to acquire the lease, returning `false`
+    /// if it is held by other (live) holders.
+    pub fn try_acquire(&self) -> Result<bool> {
+        let now = unixtime_as_millis_u64();
+        let record = Record {
+            mode: self.mode,
+            holders: vec![self.holder(now)],
+        };
+
+        // the second attempt follows removal of a stale record
+        for _ in 0..2 {
+            if self.storage.create(&serde_json::to_string(&record)?)? {
+                return self.is_held();
+            }
+
+            if let Some(mut existing) = self.load()? {
+                if existing.retain_live(now) {
+                    if self.mode == LockMode::Shared && existing.mode == LockMode::Shared {
+                        existing.holders.push(self.holder(now));
+                        self.store(&existing)?;
+                        return self.is_held();
+                    }
+                    return Ok(false);
+                }
+            }
+            self.storage.remove()?;
+        }
+
+        Ok(false)
+    }
+
+    /// Extends the lease, returning `false` if the lease has been lost.
+    pub fn renew(&self) -> Result<bool> {
+        let now = unixtime_as_millis_u64();
+        let Some(mut record) = self.load()? else {
+            return Ok(false);
+        };
+        let Some(holder) = record
+            .holders
+            .iter_mut()
+            .find(|holder| holder.id == self.id && holder.expires > now)
+        else {
+            return Ok(false);
+        };
+        holder.expires = now + self.duration.as_millis() as u64;
+        self.store(&record)?;
+        Ok(true)
+    }
+
+    pub fn is_held(&self) -> Result<bool> {
+        let now = unixtime_as_millis_u64();
+        Ok(self.load()?.is_some_and(|record| {
+            record
+                .holders
+                .iter()
+                .any(|holder| holder.id == self.id && holder.expires > now)
+        }))
+    }
+
+    pub fn release(&self) -> Result<()> {
+        let now = unixtime_as_millis_u64();
+        if let Some(mut record) = self.load()? {
+            record.holders.retain(|holder| holder.id != self.id);
+            if record.retain_live(now) {
+                self.store(&record)?;
+            } else {
+                self.storage.remove()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) use backends::*;
+
+#[cfg(target_arch = "wasm32")]
+mod backends {
+    use super::*;
+    use crate::error::Error;
+    use crate::fs::local_storage;
+    use js_sys::{Object, Reflect};
+    use wasm_bindgen::prelude::*;
+    use workflow_node as node;
+
+    /// Process id of the Node.js process.
+    pub fn node_pid() -> Option<u32> {
+        Reflect::get(&js_sys::global(), &"process".into())
+            .and_then(|process| Reflect::get(&process, &"pid".into()))
+            .ok()
+            .and_then(|pid| pid.as_f64())
+            .map(|pid| pid as u32)
+    }
+
+    /// Lease record stored in a Node.js lock file.
+    pub struct LockFile {
+        path: String,
+    }
+
+    impl LockFile {
+        pub fn new(path: String) -> Self {
+            Self { path }
+        }
+
+        fn options(flag: &str) -> Result<Object> {
+            let options = Object::new();
+            Reflect::set(&options, &"encoding".into(), &"utf-8".into())?;
+            Reflect::set(&options, &"flag".into(), &flag.into())?;
+            Ok(options)
+        }
+    }
+
+    impl LeaseStorage for LockFile {
+        fn read(&self) -> Result<Option<String>> {
+            match node::fs::read_file_sync(&self.path, Self::options("r")?) {
+                Ok(text) => Ok(text.as_string()),
+                Err(err) => {
+                    let err = Error::from(err);
+                    if err.code() == Some("ENOENT") {
+                        Ok(None)
+                    } else {
+                        Err(err)
+                    }
+                }
+            }
+        }
+
+        fn write(&self, record: &str) -> Result<()> {
+            node::fs::write_file_sync(&self.path, JsValue::from(record), Self::options("w")?)?;
+            Ok(())
+        }
+
+        fn create(&self, record: &str) -> Result<bool> {
+            // `wx` fails if the file already exists
+            match node::fs::write_file_sync(&self.path, JsValue::from(record), Self::options("wx")?)
+            {
+                Ok(()) => Ok(true),
+                Err(err) => {
+                    let err = Error::from(err);
+                    if err.code() == Some("EEXIST") {
+                        Ok(false)
+                    } else {
+                        Err(err)
+                    }
+                }
+            }
+        }
+
+        fn remove(&self) -> Result<()> {
+            match node::fs::unlink_sync(&self.path) {
+                Ok(()) => Ok(()),
+                Err(err) => {
+                    let err = Error::from(err);
+                    if err.code() == Some("ENOENT") {
+                        Ok(())
+                    } else {
+                        Err(err)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Lease record stored under a browser localStorage key.
+    pub struct LocalStorageLease {
+        key: String,
+    }
+
+    impl LocalStorageLease {
+        pub fn new(key: String) -> Self {
+            Self { key }
+        }
+    }
+
+    impl LeaseStorage for LocalStorageLease {
+        fn read(&self) -> Result<Option<String>> {
+            Ok(local_storage().get_item(&self.key)?)
+        }
+
+        fn write(&self, record: &str) -> Result<()> {
+            Ok(local_storage().set_item(&self.key, record)?)
+        }
+
+        fn create(&self, record: &str) -> Result<bool> {
+            let storage = local_storage();
+            if storage.get_item(&self.key)?.is_some() {
+                Ok(false)
+            } else {
+                storage.set_item(&self.key, record)?;
+                Ok(true)
+            }
+        }
+
+        fn remove(&self) -> Result<()> {
+            Ok(local_storage().remove_item(&self.key)?)
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// In-memory lease storage shared between test leases.
+    #[derive(Clone, Default)]
+    pub struct MemoryStorage(Arc<Mutex<Option<String>>>);
+
+    impl LeaseStorage for MemoryStorage {
+        fn read(&self) -> Result<Option<String>> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn write(&self, record: &str) -> Result<()> {
+            self.0.lock().unwrap().replace(record.to_string());
+            Ok(())
+        }
+
+        fn create(&self, record: &str) -> Result<bool> {
+            let mut storage = self.0.lock().unwrap();
+            if storage.is_some() {
+                Ok(false)
+            } else {
+                storage.replace(record.to_string());
+                Ok(true)
+            }
+        }
+
+        fn remove(&self) -> Result<()> {
+            self.0.lock().unwrap().take();
+            Ok(())
+        }
+    }
+
+    fn lease(storage: &MemoryStorage, mode: LockMode, millis: u64) -> Lease {
+        Lease::new(
+            Box::new(storage.clone()),
+            mode,
+            Duration::from_millis(millis),
+            Some(1),
+        )
+    }
+
+    #[test]
+    fn test_lease_modes() {
+        let storage = MemoryStorage::default();
+        let shared_a = lease(&storage, LockMode::Shared, 10_000);
+        let shared_b = lease(&storage, LockMode::Shared, 10_000);
+        let exclusive = lease(&storage, LockMode::Exclusive, 10_000);
+
+        assert!(shared_a.try_acquire().unwrap());
+        assert!(shared_b.try_acquire().unwrap());
+        assert!(!exclusive.try_acquire().unwrap());
+
+        shared_a.release().unwrap();
+        assert!(!shared_a.is_held().unwrap());
+        assert!(shared_b.is_held().unwrap());
+        assert!(!exclusive.try_acquire().unwrap());
+
+        shared_b.release().unwrap();
+        assert!(storage.read().unwrap().is_none());
+        assert!(exclusive.try_acquire().unwrap());
+        assert!(!shared_a.try_acquire().unwrap());
+        assert!(exclusive.renew().unwrap());
+
+        // a malformed record is replaced
+        exclusive.release().unwrap();
+        storage.write("{").unwrap();
+        assert!(shared_a.try_acquire().unwrap());
+    }
+
+    #[async_std::test]
+    async fn test_lease_expiry_recovery() {
+        let storage = MemoryStorage::default();
+        let crashed = lease(&storage, LockMode::Exclusive, 50);
+        let contender = lease(&storage, LockMode::Exclusive, 10_000);
+
+        assert!(crashed.try_acquire().unwrap());
+        assert!(!contender.try_acquire().unwrap());
+
+        // the holder stops renewing its lease
+        async_std::task::sleep(Duration::from_millis(100)).await;
+        assert!(!crashed.is_held().unwrap());
+        assert!(contender.try_acquire().unwrap());
+        assert!(!crashed.renew().unwrap());
+
+        // releasing a lost lease retains the new holder
+        crashed.release().unwrap();
+        assert!(contender.is_held().unwrap());
+    }
+}
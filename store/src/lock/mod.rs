@@ -0,0 +1,349 @@
+//!
+//! Advisory locking of [`Store`](crate::store::Store) data, preventing
+//! concurrent writers (such as multiple instances of an application)
+//! from clobbering each other.
+//!
+//! - native: `flock`-style file locks on the `<filename>.lock` file
+//! - Node.js: `<filename>.lock` lock file carrying the holder PID and a
+//!   lease renewed by a heartbeat
+//! - browser: localStorage lease under the `<key>.lock` key
+//!
+//! Locks are advisory: they exclude other lock holders but do not
+//! prevent unlocked access to the underlying data.
+//!
+
+mod lease;
+
+use crate::error::Error;
+use crate::result::Result;
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use workflow_core::time::Instant;
+
+/// Default time [`Store::lock_exclusive()`](crate::store::Store::lock_exclusive)
+/// and [`Store::lock_shared()`](crate::store::Store::lock_shared) wait for the lock.
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Default lease duration of lease-based locks (Node.js and browser).
+pub const DEFAULT_LOCK_LEASE: Duration = Duration::from_secs(10);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(25);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockMode {
+    /// Multiple holders can retain the lock simultaneously
+    /// (excluding the exclusive lock holder).
+    Shared,
+    /// A single holder can retain the lock.
+    Exclusive,
+}
+
+cfg_if! {
+    if #[cfg(target_arch = "wasm32")] {
+        use lease::{Lease, LocalStorageLease, LockFile};
+        use workflow_core::runtime;
+        use workflow_core::task::{sleep, spawn};
+        use workflow_log::log_error;
+
+        struct Backend {
+            lease: Lease,
+        }
+
+        impl Backend {
+            fn try_acquire(filename: &str, mode: LockMode, duration: Duration) -> Result<Option<Self>> {
+                let lock_name = format!("{filename}.lock");
+                let lease = if runtime::is_node() || runtime::is_nw() {
+                    Lease::new(Box::new(LockFile::new(lock_name)), mode, duration, lease::node_pid())
+                } else {
+                    Lease::new(Box::new(LocalStorageLease::new(lock_name)), mode, duration, None)
+                };
+                Ok(lease.try_acquire()?.then_some(Backend { lease }))
+            }
+
+            fn is_held(&self) -> Result<bool> {
+                self.lease.is_held()
+            }
+
+            fn release(&self) -> Result<()> {
+                self.lease.release()
+            }
+        }
+
+        /// Renews the lease until the lock is released or lost.
+        fn heartbeat(inner: &Arc<LockInner>) {
+            let interval = inner.backend.lease.duration() / 3;
+            let inner = Arc::downgrade(inner);
+            spawn(async move {
+                loop {
+                    sleep(interval).await;
+                    let Some(inner) = inner.upgrade() else {
+                        break;
+                    };
+                    if inner.released.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    match inner.backend.lease.renew() {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            log_error!("Store lock `{}` has been lost", inner.filename);
+                            break;
+                        }
+                        Err(err) => {
+                            log_error!("Unable to renew store lock `{}`: {err}", inner.filename);
+                            break;
+                        }
+                    }
+                }
+            });
+        }
+    } else {
+        use async_std::task::sleep;
+        use fs2::FileExt;
+        use std::fs::{File, OpenOptions};
+        use std::path::PathBuf;
+
+        struct Backend {
+            file: File,
+            path: PathBuf,
+        }
+
+        impl Backend {
+            fn try_acquire(filename: &str, mode: LockMode, _lease: Duration) -> Result<Option<Self>> {
+                let path = PathBuf::from(format!("{filename}.lock"));
+                let file = OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .open(&path)?;
+                let result = match mode {
+                    LockMode::Shared => FileExt::try_lock_shared(&file),
+                    LockMode::Exclusive => FileExt::try_lock_exclusive(&file),
+                };
+                match result {
+                    Ok(()) => Ok(Some(Backend { file, path })),
+                    Err(err) if err.raw_os_error() == fs2::lock_contended_error().raw_os_error() => {
+                        Ok(None)
+                    }
+                    Err(err) => Err(err.into()),
+                }
+            }
+
+            /// The lock is lost if the lock file has been removed
+            /// or replaced (allowing others to lock the new file).
+            fn is_held(&self) -> Result<bool> {
+                let Ok(metadata) = std::fs::metadata(&self.path) else {
+                    return Ok(false);
+                };
+                cfg_if! {
+                    if #[cfg(unix)] {
+                        use std::os::unix::fs::MetadataExt;
+                        let file = self.file.metadata()?;
+                        Ok(metadata.dev() == file.dev() && metadata.ino() == file.ino())
+                    } else {
+                        Ok(metadata.is_file())
+                    }
+                }
+            }
+
+            fn release(&self) -> Result<()> {
+                Ok(FileExt::unlock(&self.file)?)
+            }
+        }
+
+        fn heartbeat(_inner: &Arc<LockInner>) {}
+    }
+}
+
+pub(crate) struct LockInner {
+    filename: String,
+    mode: LockMode,
+    backend: Backend,
+    released: AtomicBool,
+}
+
+impl LockInner {
+    pub fn is_held(&self) -> Result<bool> {
+        if self.released.load(Ordering::SeqCst) {
+            Ok(false)
+        } else {
+            self.backend.is_held()
+        }
+    }
+
+    fn release(&self) -> Result<()> {
+        if !self.released.swap(true, Ordering::SeqCst) {
+            self.backend.release()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LockInner {
+    fn drop(&mut self) {
+        self.release().ok();
+    }
+}
+
+///
+/// Advisory lock acquired using [`Store::lock_exclusive()`](crate::store::Store::lock_exclusive)
+/// or [`Store::lock_shared()`](crate::store::Store::lock_shared).
+/// The lock is released when [`StoreLock`] is dropped.
+///
+pub struct StoreLock {
+    inner: Arc<LockInner>,
+}
+
+impl StoreLock {
+    /// Acquires the lock for the store `filename`, waiting up to
+    /// `timeout` if `wait` is set or failing immediately otherwise.
+    pub(crate) async fn acquire(
+        filename: &str,
+        mode: LockMode,
+        timeout: Duration,
+        lease: Duration,
+        wait: bool,
+    ) -> Result<StoreLock> {
+        let started = Instant::now();
+        loop {
+            if let Some(backend) = Backend::try_acquire(filename, mode, lease)? {
+                let inner = Arc::new(LockInner {
+                    filename: filename.to_string(),
+                    mode,
+                    backend,
+                    released: AtomicBool::new(false),
+                });
+                heartbeat(&inner);
+                return Ok(StoreLock { inner });
+            }
+
+            if !wait {
+                return Err(Error::WouldBlock(filename.to_string()));
+            }
+            let elapsed = started.elapsed();
+            if elapsed >= timeout {
+                return Err(Error::Timeout(filename.to_string()));
+            }
+            sleep(LOCK_RETRY_INTERVAL.min(timeout - elapsed)).await;
+        }
+    }
+
+    pub(crate) fn inner(&self) -> &Arc<LockInner> {
+        &self.inner
+    }
+
+    pub fn mode(&self) -> LockMode {
+        self.inner.mode
+    }
+
+    /// Checks if the lock is still held. Lease-based locks can be
+    /// lost if the lease expires (e.g. if the heartbeat is suspended).
+    pub fn is_held(&self) -> Result<bool> {
+        self.inner.is_held()
+    }
+
+    /// Releases the lock.
+    pub fn release(self) -> Result<()> {
+        self.inner.release()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::Store;
+    use std::sync::atomic::AtomicUsize;
+
+    fn store(name: &str) -> Arc<Store> {
+        let filename =
+            std::env::temp_dir().join(format!("workflow-store-{name}-{}.json", std::process::id()));
+        let mut store = Store::new();
+        store
+            .with_generic(filename.to_str().unwrap())
+            .with_lock_timeout(Duration::from_secs(2));
+        Arc::new(store)
+    }
+
+    #[async_std::test]
+    async fn test_exclusive_lock_contention() {
+        let store = store("contention");
+        let active = Arc::new(AtomicUsize::new(0));
+
+        let tasks = (0..2)
+            .map(|n| {
+                let store = store.clone();
+                let active = active.clone();
+                async_std::task::spawn(async move {
+                    for i in 0..5 {
+                        let lock = store.lock_exclusive().await.unwrap();
+                        assert_eq!(active.fetch_add(1, Ordering::SeqCst), 0);
+                        store.write_string(&format!("{n}:{i}")).await.unwrap();
+                        async_std::task::sleep(Duration::from_millis(10)).await;
+                        assert_eq!(store.read_to_string().await.unwrap(), format!("{n}:{i}"));
+                        assert_eq!(active.fetch_sub(1, Ordering::SeqCst), 1);
+                        lock.release().unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for task in tasks {
+            task.await;
+        }
+
+        // contention is reported as `WouldBlock` or `Timeout`
+        let exclusive = store.lock_exclusive().await.unwrap();
+        assert!(matches!(
+            store.try_lock_shared().await,
+            Err(Error::WouldBlock(_))
+        ));
+        let mut impatient = Store::new();
+        impatient
+            .with_generic(&store.filename())
+            .with_lock_timeout(Duration::from_millis(50));
+        assert!(matches!(
+            impatient.lock_exclusive().await,
+            Err(Error::Timeout(_))
+        ));
+        drop(exclusive);
+
+        // shared locks exclude only the exclusive lock
+        let shared_a = store.lock_shared().await.unwrap();
+        let shared_b = store.try_lock_shared().await.unwrap();
+        assert_eq!(shared_b.mode(), LockMode::Shared);
+        assert!(matches!(
+            store.try_lock_exclusive().await,
+            Err(Error::WouldBlock(_))
+        ));
+        drop((shared_a, shared_b));
+        store.try_lock_exclusive().await.unwrap();
+
+        std::fs::remove_file(store.filename()).ok();
+        std::fs::remove_file(format!("{}.lock", store.filename())).ok();
+    }
+
+    #[async_std::test]
+    async fn test_lost_lock_rejects_writes() {
+        let store = store("lost");
+        let lock = store.lock_exclusive().await.unwrap();
+        store.write_string("locked").await.unwrap();
+
+        // replacing the lock file allows others to lock the store
+        std::fs::remove_file(format!("{}.lock", store.filename())).unwrap();
+        assert!(!lock.is_held().unwrap());
+        assert!(matches!(
+            store.write_string("lost").await,
+            Err(Error::LockLost(_))
+        ));
+        assert_eq!(store.read_to_string().await.unwrap(), "locked");
+
+        // unlocked writes are not verified
+        drop(lock);
+        store.write_string("unlocked").await.unwrap();
+
+        std::fs::remove_file(store.filename()).ok();
+        std::fs::remove_file(format!("{}.lock", store.filename())).ok();
+    }
+}
@@ -0,0 +1,188 @@
+//!
+//! File permissions of secret data written by the native fs backend.
+//!
+//! Files are created with the default permissions (`0644` with the usual
+//! umask), readable by other users. [`write_atomic_with_options()`] writes
+//! the data to a temporary file, restricts its permissions to the
+//! [`WriteOptions::mode`] before any data is written and renames it over
+//! the destination, so that the data is never observable with wider
+//! permissions. [`verify_permissions()`] checks the permissions of an
+//! existing file.
+//!
+//! - Unix: the mode is applied as-is
+//! - Windows: the mode is not applicable; if the mode denies group and
+//!   world access, the ACL of the file is restricted to the current user
+//!   on a best-effort basis (using `icacls`)
+//! - wasm (browser and Node.js): permissions are not applied or verified
+//!
+
+use crate::error::Error;
+use crate::result::Result;
+use cfg_if::cfg_if;
+use std::path::Path;
+
+/// Mode of secret files (read/write access by the owner only)
+pub const SECRET_FILE_MODE: u32 = 0o600;
+
+/// Options of [`write_atomic_with_options()`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Unix permissions of the written file (default permissions if `None`)
+    pub mode: Option<u32>,
+}
+
+impl WriteOptions {
+    pub fn with_mode(mode: u32) -> Self {
+        WriteOptions { mode: Some(mode) }
+    }
+
+    /// Options restricting the file to the owner ([`SECRET_FILE_MODE`])
+    pub fn secret() -> Self {
+        Self::with_mode(SECRET_FILE_MODE)
+    }
+}
+
+cfg_if! {
+    if #[cfg(target_arch = "wasm32")] {
+
+        /// Writes the data (see the [module](self) documentation).
+        pub async fn write_atomic_with_options<P: AsRef<Path>>(filename: P, _options: WriteOptions, data: &[u8]) -> Result<()> {
+            crate::fs::write_binary_with_options(filename, crate::fs::Options::default(), data).await
+        }
+
+        /// Permissions are not verified on wasm targets.
+        pub fn verify_permissions<P: AsRef<Path>>(_path: P, _max_mode: u32) -> Result<()> {
+            Ok(())
+        }
+
+    } else {
+
+        use std::fs::{File, OpenOptions};
+        use std::io::Write;
+
+        /// Writes the data to the `<filename>.tmp` file created with the
+        /// [`WriteOptions::mode`] and renames it over the `filename`
+        /// (see the [module](self) documentation).
+        pub async fn write_atomic_with_options<P: AsRef<Path>>(filename: P, options: WriteOptions, data: &[u8]) -> Result<()> {
+            write_atomic_with_options_sync(filename, options, data)
+        }
+
+        /// Writes the data to the `<filename>.tmp` file created with the
+        /// [`WriteOptions::mode`] and renames it over the `filename`
+        /// (see the [module](self) documentation).
+        pub fn write_atomic_with_options_sync<P: AsRef<Path>>(filename: P, options: WriteOptions, data: &[u8]) -> Result<()> {
+            let filename = filename.as_ref();
+            let mut temp = filename.as_os_str().to_owned();
+            temp.push(".tmp");
+
+            let mut file = OpenOptions::new().write(true).create(true).truncate(true).open(&temp)?;
+            if let Some(mode) = options.mode {
+                // an existing temporary file retains its permissions
+                restrict(&file, Path::new(&temp), mode)?;
+            }
+            file.write_all(data)?;
+            file.sync_all()?;
+            drop(file);
+            std::fs::rename(&temp, filename)?;
+            Ok(())
+        }
+
+        /// Returns [`Error::InsecurePermissions`] if the permissions of the
+        /// existing file at `path` exceed `max_mode` (e.g. the file is group
+        /// or world readable while `max_mode` is [`SECRET_FILE_MODE`]).
+        /// Permissions are only verified on Unix.
+        pub fn verify_permissions<P: AsRef<Path>>(path: P, max_mode: u32) -> Result<()> {
+            let path = path.as_ref();
+            let metadata = std::fs::metadata(path)?;
+            cfg_if! {
+                if #[cfg(unix)] {
+                    use std::os::unix::fs::PermissionsExt;
+                    let mode = metadata.permissions().mode() & 0o777;
+                    if mode & !max_mode != 0 {
+                        return Err(Error::InsecurePermissions {
+                            path: path.to_string_lossy().to_string(),
+                            mode,
+                            max_mode,
+                        });
+                    }
+                } else {
+                    let _ = (metadata, max_mode);
+                }
+            }
+            Ok(())
+        }
+
+        #[cfg(unix)]
+        fn restrict(file: &File, _path: &Path, mode: u32) -> Result<()> {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(mode))?;
+            Ok(())
+        }
+
+        #[cfg(windows)]
+        fn restrict(_file: &File, path: &Path, mode: u32) -> Result<()> {
+            if mode & 0o077 == 0 {
+                if let Ok(user) = std::env::var("USERNAME") {
+                    // best-effort: failures leave the inherited ACL in place
+                    let _ = std::process::Command::new("icacls")
+                        .arg(path)
+                        .args(["/inheritance:r", "/grant:r"])
+                        .arg(format!("{user}:F"))
+                        .output();
+                }
+            }
+            Ok(())
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        fn restrict(_file: &File, _path: &Path, _mode: u32) -> Result<()> {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn mode(path: &Path) -> u32 {
+        std::fs::metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    #[test]
+    fn test_write_atomic_with_mode() {
+        let sandbox = workflow_core::testing::sandbox();
+        let filename = sandbox.home_dir().join("wallet.json");
+
+        write_atomic_with_options_sync(&filename, WriteOptions::with_mode(0o600), b"{}").unwrap();
+        assert_eq!(mode(&filename), 0o600);
+        assert_eq!(std::fs::read(&filename).unwrap(), b"{}");
+        verify_permissions(&filename, SECRET_FILE_MODE).unwrap();
+
+        // a stale temporary file does not widen the permissions
+        let temp = sandbox.home_dir().join("wallet.json.tmp");
+        std::fs::write(&temp, "").unwrap();
+        std::fs::set_permissions(&temp, std::fs::Permissions::from_mode(0o666)).unwrap();
+        write_atomic_with_options_sync(&filename, WriteOptions::secret(), b"[]").unwrap();
+        assert_eq!(mode(&filename), 0o600);
+        assert!(!temp.exists());
+    }
+
+    #[test]
+    fn test_verify_permissions() {
+        let sandbox = workflow_core::testing::sandbox();
+        let filename = sandbox.home_dir().join("wallet.json");
+        std::fs::write(&filename, "{}").unwrap();
+        std::fs::set_permissions(&filename, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        match verify_permissions(&filename, SECRET_FILE_MODE) {
+            Err(Error::InsecurePermissions { mode, max_mode, .. }) => {
+                assert_eq!(mode, 0o644);
+                assert_eq!(max_mode, SECRET_FILE_MODE);
+            }
+            result => panic!("expected InsecurePermissions, got {result:?}"),
+        }
+        verify_permissions(&filename, 0o644).unwrap();
+    }
+}
@@ -1,2 +1,6 @@
+pub use crate::archive::{ImportMode, ImportReport};
 pub use crate::fs;
+pub use crate::json::JsonStore;
+pub use crate::lock::{LockMode, StoreLock};
+pub use crate::permissions::{verify_permissions, WriteOptions, SECRET_FILE_MODE};
 pub use crate::store;
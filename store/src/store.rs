@@ -1,14 +1,20 @@
+use crate::error::Error;
+use crate::lock::{LockInner, LockMode, StoreLock, DEFAULT_LOCK_LEASE, DEFAULT_LOCK_TIMEOUT};
 use crate::result::Result;
 use cfg_if::cfg_if;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, Weak};
+use std::time::Duration;
 
 cfg_if! {
     if #[cfg(not(target_arch = "wasm32"))] {
         use async_std::path::PathBuf;
         use async_std::fs;
+        use crate::permissions::{write_atomic_with_options, WriteOptions};
     } else {
         // use base64::{Engine as _, engine::general_purpose};
+        use crate::fs::{local_storage_usage, StorageUsage};
     }
 }
 
@@ -32,6 +38,14 @@ pub struct Store {
     pub generic: Option<String>,
     // browser locastorage (fallsback to a hash of generic in hex)
     pub browser: Option<String>,
+    // time to wait for the lock in `lock_exclusive()` and `lock_shared()`
+    pub lock_timeout: Duration,
+    // lease duration of Node.js and browser locks
+    pub lock_lease: Duration,
+    // unix permissions of files written by `write_string_atomic()`
+    pub mode: Option<u32>,
+    // lock verified by writes
+    lock: Mutex<Option<Weak<LockInner>>>,
 }
 
 impl Default for Store {
@@ -49,6 +63,10 @@ impl Store {
             windows: None,
             generic: None,
             browser: None,
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+            lock_lease: DEFAULT_LOCK_LEASE,
+            mode: None,
+            lock: Mutex::new(None),
         }
     }
 
@@ -82,6 +100,23 @@ impl Store {
         self
     }
 
+    pub fn with_lock_timeout(&mut self, timeout: Duration) -> &mut Store {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    pub fn with_lock_lease(&mut self, lease: Duration) -> &mut Store {
+        self.lock_lease = lease;
+        self
+    }
+
+    /// Sets the permissions of the store file applied by
+    /// [`Store::write_string_atomic()`] (see [`crate::permissions`]).
+    pub fn with_mode(&mut self, mode: u32) -> &mut Store {
+        self.mode = Some(mode);
+        self
+    }
+
     pub fn filename(&self) -> String {
         cfg_if! {
             if #[cfg(target_os = "macos")] {
@@ -105,6 +140,67 @@ impl Store {
         }
     }
 
+    fn lock_filename(&self) -> String {
+        cfg_if! {
+            if #[cfg(target_arch = "wasm32")] {
+                self.filename()
+            } else {
+                parse(self.filename()).to_string_lossy().to_string()
+            }
+        }
+    }
+
+    async fn lock(&self, mode: LockMode, wait: bool) -> Result<StoreLock> {
+        let lock = StoreLock::acquire(
+            &self.lock_filename(),
+            mode,
+            self.lock_timeout,
+            self.lock_lease,
+            wait,
+        )
+        .await?;
+        self.lock
+            .lock()
+            .unwrap()
+            .replace(std::sync::Arc::downgrade(lock.inner()));
+        Ok(lock)
+    }
+
+    /// Acquires an exclusive advisory lock (see [`crate::lock`]), waiting
+    /// up to [`Store::lock_timeout`] before failing with [`Error::Timeout`].
+    /// While the lock is retained, writes verify that the lock is still
+    /// held and fail with [`Error::LockLost`] otherwise.
+    pub async fn lock_exclusive(&self) -> Result<StoreLock> {
+        self.lock(LockMode::Exclusive, true).await
+    }
+
+    /// Acquires a shared advisory lock, waiting up to [`Store::lock_timeout`]
+    /// before failing with [`Error::Timeout`].
+    pub async fn lock_shared(&self) -> Result<StoreLock> {
+        self.lock(LockMode::Shared, true).await
+    }
+
+    /// Acquires an exclusive advisory lock or fails
+    /// with [`Error::WouldBlock`] if it is held by others.
+    pub async fn try_lock_exclusive(&self) -> Result<StoreLock> {
+        self.lock(LockMode::Exclusive, false).await
+    }
+
+    /// Acquires a shared advisory lock or fails with
+    /// [`Error::WouldBlock`] if it is held exclusively by others.
+    pub async fn try_lock_shared(&self) -> Result<StoreLock> {
+        self.lock(LockMode::Shared, false).await
+    }
+
+    /// Verifies that the lock retained by the store (if any) is still held.
+    fn verify_lock(&self) -> Result<()> {
+        let lock = self.lock.lock().unwrap().as_ref().and_then(Weak::upgrade);
+        match lock {
+            Some(lock) if !lock.is_held()? => Err(Error::LockLost(self.filename())),
+            _ => Ok(()),
+        }
+    }
+
     cfg_if! {
         if #[cfg(target_arch = "wasm32")] {
             pub async fn exists(&self) -> Result<bool> {
@@ -120,12 +216,23 @@ impl Store {
             }
 
             pub async fn write_string(&self, data: &str) -> Result<()> {
+                self.verify_lock()?;
                 let filename = self.filename();
                 // let v = general_purpose::STANDARD.encode(data);
-                local_storage().set_item(&filename, data)?;
+                crate::fs::set_local_storage_item(&filename, data)?;
                 Ok(())
             }
 
+            /// Estimated usage of the browser localStorage shared by all stores
+            pub fn usage(&self) -> Result<StorageUsage> {
+                local_storage_usage()
+            }
+
+            /// localStorage updates are atomic, hence this is equivalent to [`Store::write_string()`].
+            pub async fn write_string_atomic(&self, data: &str) -> Result<()> {
+                self.write_string(data).await
+            }
+
         } else {
             pub async fn exists(&self) -> Result<bool> {
                 let filename = parse(self.filename());
@@ -138,23 +245,31 @@ impl Store {
             }
 
             pub async fn write_string(&self, data: &str) -> Result<()> {
+                self.verify_lock()?;
                 let filename = parse(self.filename());
                 Ok(fs::write(&filename, data).await?)
             }
+
+            /// Writes the data to the `<filename>.tmp` file and renames it over
+            /// the store file, so that readers never observe a partial write.
+            /// The file is created with the [`Store::mode`] permissions (if set).
+            /// Concurrent writers should be excluded using [`Store::lock_exclusive()`].
+            pub async fn write_string_atomic(&self, data: &str) -> Result<()> {
+                self.verify_lock()?;
+                let filename = parse(self.filename());
+                let options = WriteOptions { mode: self.mode };
+                write_atomic_with_options(&filename, options, data.as_bytes()).await
+            }
         }
     }
 }
 
 cfg_if! {
     if #[cfg(not(target_arch = "wasm32"))] {
+        /// Resolves the `~/` prefix using [`fs::resolve_path()`](crate::fs::resolve_path),
+        /// i.e. against [`workflow_core::dirs::home_dir()`].
         pub fn parse(path : String) -> PathBuf {
-
-            if let Some(stripped) = path.strip_prefix('~') {
-                let home_dir: PathBuf = home::home_dir().unwrap().into();
-                home_dir.join(stripped)
-            } else {
-                PathBuf::from(path)
-            }
+            crate::fs::resolve_path(&path).unwrap().into()
         }
     } else {
         pub fn local_storage() -> web_sys::Storage {
@@ -181,3 +296,25 @@ where
     let v = hasher.finish();
     format!("{v:x}")
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[async_std::test]
+    async fn test_store_respects_sandbox() {
+        let sandbox = workflow_core::testing::sandbox();
+        let mut store = Store::new();
+        store.with_generic("~/.workflow/settings.json");
+        assert_eq!(
+            parse(store.filename()),
+            PathBuf::from(sandbox.home_dir().join(".workflow/settings.json"))
+        );
+
+        std::fs::create_dir_all(sandbox.home_dir().join(".workflow")).unwrap();
+        store.write_string("{}").await.unwrap();
+        assert!(sandbox.home_dir().join(".workflow/settings.json").is_file());
+        assert_eq!(store.read_to_string().await.unwrap(), "{}");
+    }
+}
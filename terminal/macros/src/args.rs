@@ -0,0 +1,314 @@
+use convert_case::{Case, Casing};
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Fields, GenericArgument, Lit, Meta, NestedMeta,
+    PathArguments, Result, Type,
+};
+
+enum Kind {
+    Switch,
+    Value,
+    OptionalValue,
+    Positional,
+    OptionalPositional,
+    Rest,
+}
+
+struct Arg {
+    ident: syn::Ident,
+    /// Field type or the inner type of `Option<T>` and `Vec<T>`
+    ty: Type,
+    kind: Kind,
+    name: String,
+    short: Option<char>,
+    long: Option<String>,
+    help: Option<String>,
+}
+
+impl Arg {
+    fn is_flag(&self) -> bool {
+        matches!(self.kind, Kind::Switch | Kind::Value | Kind::OptionalValue)
+    }
+
+    fn flag_names(&self) -> String {
+        let short = self.short.map(|short| format!("-{short}"));
+        let long = self.long.as_ref().map(|long| format!("--{long}"));
+        short.into_iter().chain(long).collect::<Vec<_>>().join("|")
+    }
+
+    fn usage(&self) -> String {
+        let value = format!("<{}>", self.name);
+        match self.kind {
+            Kind::Switch => format!("[{}]", self.flag_names()),
+            Kind::Value => format!("{} {value}", self.flag_names()),
+            Kind::OptionalValue => format!("[{} {value}]", self.flag_names()),
+            Kind::Positional => value,
+            Kind::OptionalPositional => format!("[{value}]"),
+            Kind::Rest => format!("[{value}...]"),
+        }
+    }
+
+    fn help_label(&self) -> String {
+        if self.is_flag() {
+            self.flag_names().replace('|', ", ")
+        } else {
+            format!("<{}>", self.name)
+        }
+    }
+}
+
+/// Returns the inner type if `ty` is `wrapper<T>`.
+fn inner_type(ty: &Type, wrapper: &str) -> Option<Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(GenericArgument::Type(ty)) => Some(ty.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_bool(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.is_ident("bool"))
+}
+
+fn parse_field(field: &syn::Field) -> Result<Arg> {
+    let ident = field.ident.clone().unwrap();
+    let name = ident.to_string().to_case(Case::Kebab);
+
+    let mut is_flag = false;
+    let mut short = None;
+    let mut long = None;
+    let mut help = None;
+    for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("arg")) {
+        let Meta::List(list) = attr.parse_meta()? else {
+            return Err(Error::new_spanned(
+                attr,
+                "usage: #[arg(short, long, help = \"...\")]",
+            ));
+        };
+        for nested in list.nested.iter() {
+            match nested {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("short") => {
+                    is_flag = true;
+                    short = name.chars().next();
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("long") => {
+                    is_flag = true;
+                    long = Some(name.clone());
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("short") => {
+                    let Lit::Char(c) = &nv.lit else {
+                        return Err(Error::new_spanned(&nv.lit, "expected a char literal"));
+                    };
+                    is_flag = true;
+                    short = Some(c.value());
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("long") => {
+                    let Lit::Str(s) = &nv.lit else {
+                        return Err(Error::new_spanned(&nv.lit, "expected a string literal"));
+                    };
+                    is_flag = true;
+                    long = Some(s.value());
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("help") => {
+                    let Lit::Str(s) = &nv.lit else {
+                        return Err(Error::new_spanned(&nv.lit, "expected a string literal"));
+                    };
+                    help = Some(s.value());
+                }
+                _ => {
+                    return Err(Error::new_spanned(
+                        nested,
+                        "unsupported argument attribute, expected `short`, `long` or `help`",
+                    ));
+                }
+            }
+        }
+    }
+
+    let (kind, ty) = if let Some(ty) = inner_type(&field.ty, "Vec") {
+        if is_flag {
+            return Err(Error::new_spanned(
+                &field.ty,
+                "`Vec` fields capture the remaining arguments and can not be options",
+            ));
+        }
+        (Kind::Rest, ty)
+    } else if let Some(ty) = inner_type(&field.ty, "Option") {
+        let kind = if is_flag {
+            Kind::OptionalValue
+        } else {
+            Kind::OptionalPositional
+        };
+        (kind, ty)
+    } else if is_flag && is_bool(&field.ty) {
+        (Kind::Switch, field.ty.clone())
+    } else if is_flag {
+        (Kind::Value, field.ty.clone())
+    } else {
+        (Kind::Positional, field.ty.clone())
+    };
+
+    Ok(Arg {
+        ident,
+        ty,
+        kind,
+        name,
+        short,
+        long,
+        help,
+    })
+}
+
+fn parse_args(ast: &DeriveInput) -> Result<Vec<Arg>> {
+    let fields = match &ast.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &ast.ident,
+                    "CliArgs can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &ast.ident,
+                "CliArgs can only be derived for structs",
+            ))
+        }
+    };
+
+    let args = fields.iter().map(parse_field).collect::<Result<Vec<_>>>()?;
+
+    // positional arguments must be unambiguous: required arguments
+    // precede optional arguments, followed by the rest capture
+    let mut optional = false;
+    let mut rest = false;
+    for (arg, field) in args.iter().zip(fields.iter()) {
+        match arg.kind {
+            Kind::Positional | Kind::OptionalPositional | Kind::Rest if rest => {
+                return Err(Error::new_spanned(
+                    field,
+                    "positional arguments can not follow the `Vec` field capturing the remaining arguments",
+                ));
+            }
+            Kind::Positional if optional => {
+                return Err(Error::new_spanned(
+                    field,
+                    "required positional arguments can not follow optional arguments",
+                ));
+            }
+            Kind::OptionalPositional => optional = true,
+            Kind::Rest => rest = true,
+            _ => {}
+        }
+    }
+
+    Ok(args)
+}
+
+fn render_usage(args: &[Arg]) -> String {
+    // options are listed before positional arguments
+    let usage = args
+        .iter()
+        .filter(|arg| arg.is_flag())
+        .chain(args.iter().filter(|arg| !arg.is_flag()))
+        .map(Arg::usage)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let help = args
+        .iter()
+        .filter_map(|arg| arg.help.as_ref().map(|help| (arg.help_label(), help)))
+        .collect::<Vec<_>>();
+    let width = help.iter().map(|(label, _)| label.len()).max().unwrap_or(0) + 4;
+    help.into_iter().fold(usage, |usage, (label, help)| {
+        format!("{usage}\n  {label:width$}{help}")
+    })
+}
+
+pub fn cli_args(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    match render(&ast) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn render(ast: &DeriveInput) -> Result<TokenStream2> {
+    let args = parse_args(ast)?;
+    let usage = render_usage(&args);
+
+    let flags = args.iter().filter(|arg| arg.is_flag()).map(|arg| {
+        let name = &arg.name;
+        let short = match arg.short {
+            Some(short) => quote! { Some(#short) },
+            None => quote! { None },
+        };
+        let long = match &arg.long {
+            Some(long) => quote! { Some(#long) },
+            None => quote! { None },
+        };
+        let takes_value = !matches!(arg.kind, Kind::Switch);
+        quote! {
+            workflow_terminal::args::Flag {
+                name: #name,
+                short: #short,
+                long: #long,
+                takes_value: #takes_value,
+            }
+        }
+    });
+
+    let mut flag = 0usize;
+    let fields = args.iter().map(|arg| {
+        let Arg {
+            ident, ty, name, ..
+        } = arg;
+        let value = match arg.kind {
+            Kind::Switch => quote! { parser.switch(#flag) },
+            Kind::Value => quote! { parser.required_value::<#ty>(#flag)? },
+            Kind::OptionalValue => quote! { parser.value::<#ty>(#flag)? },
+            Kind::Positional => quote! { parser.positional::<#ty>(#name)? },
+            Kind::OptionalPositional => quote! { parser.optional_positional::<#ty>(#name)? },
+            Kind::Rest => quote! { parser.rest::<#ty>(#name)? },
+        };
+        if arg.is_flag() {
+            flag += 1;
+        }
+        quote! { let #ident = #value; }
+    });
+    let fields = fields.collect::<Vec<_>>();
+    let idents = args.iter().map(|arg| &arg.ident);
+
+    let ident = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics workflow_terminal::args::CliArgs for #ident #ty_generics #where_clause {
+            fn usage() -> String {
+                #usage.to_string()
+            }
+
+            fn parse(argv: &[String]) -> std::result::Result<Self, workflow_terminal::args::ArgsError> {
+                let flags: &[workflow_terminal::args::Flag] = &[#(#flags),*];
+                #[allow(unused_mut)]
+                let mut parser = workflow_terminal::args::Parser::try_new(argv, flags, Self::usage())?;
+                #(#fields)*
+                parser.finish()?;
+                Ok(Self { #(#idents),* })
+            }
+        }
+    })
+}
@@ -8,8 +8,8 @@ use syn::{
     parse_macro_input,
     punctuated::Punctuated,
     token::Colon2,
-    DeriveInput, Error, Expr, ExprLit, ExprPath, Lit, LitStr, Meta, NestedMeta, Path, PathSegment,
-    Result, Token,
+    DeriveInput, Error, Expr, ExprArray, ExprLit, ExprPath, Lit, LitStr, Meta, MetaNameValue,
+    NestedMeta, Path, PathSegment, Result, Token, Type,
 };
 
 #[derive(Debug)]
@@ -17,6 +17,32 @@ struct DeclareHandler {
     type_expr: Expr,
     verb: LitStr,
     help: LitStr,
+    details: HelpDetails,
+    args: Option<Type>,
+}
+
+/// Help metadata supplied via `#[help(...)]` in addition to the summary
+#[derive(Debug, Default)]
+struct HelpDetails {
+    description: Option<LitStr>,
+    usage: Option<LitStr>,
+    aliases: Option<ExprArray>,
+    subcommands: Option<ExprArray>,
+}
+
+/// `name = value` entry of `#[help(...)]`
+struct HelpField {
+    name: Ident,
+    value: Expr,
+}
+
+impl Parse for HelpField {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let value = input.parse()?;
+        Ok(HelpField { name, value })
+    }
 }
 
 impl Parse for DeclareHandler {
@@ -98,6 +124,8 @@ impl Parse for DeclareHandler {
             type_expr,
             verb,
             help: help_expr.clone(),
+            details: HelpDetails::default(),
+            args: None,
         };
         Ok(handlers)
     }
@@ -120,13 +148,25 @@ pub fn declare_handler_derive(input: TokenStream) -> TokenStream {
         LitStr::new(s.as_str(), Span::call_site())
     });
 
-    let help =
-        get_attribute(&mut ast, "help").unwrap_or_else(|| LitStr::new("", Span::call_site()));
+    let (help, details) = match get_help_attribute(&mut ast) {
+        Ok((help, details)) => (
+            help.unwrap_or_else(|| LitStr::new("", Span::call_site())),
+            details,
+        ),
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let args = match get_type_attribute(&mut ast, "args") {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error().into(),
+    };
 
     let handler = DeclareHandler {
         type_expr,
         verb,
         help,
+        details,
+        args,
     };
 
     render(handler)
@@ -137,9 +177,70 @@ fn render(handler: DeclareHandler) -> TokenStream {
         type_expr,
         verb,
         help,
-        ..
+        details,
+        args,
     } = handler;
 
+    let description = details.description.map(|description| {
+        quote! {
+            fn description(&self, _ctx: &Arc<dyn workflow_terminal::cli::Context>) -> &'static str {
+                #description
+            }
+        }
+    });
+
+    // without an explicit usage, the usage of `#[args(T)]` is used
+    let usage = match (details.usage, &args) {
+        (Some(usage), _) => Some(quote! { #usage.to_string() }),
+        (None, Some(args)) => Some(quote! {
+            format!("{} {}", #verb, <#args as workflow_terminal::args::CliArgs>::usage())
+                .trim_end()
+                .to_string()
+        }),
+        (None, None) => None,
+    };
+    let usage = usage.map(|usage| {
+        quote! {
+            fn usage(&self, _ctx: &Arc<dyn workflow_terminal::cli::Context>) -> String {
+                #usage
+            }
+        }
+    });
+
+    let aliases = details.aliases.map(|aliases| {
+        quote! {
+            fn aliases(&self, _ctx: &Arc<dyn workflow_terminal::cli::Context>) -> &'static [&'static str] {
+                &#aliases
+            }
+        }
+    });
+
+    let subcommands = details.subcommands.map(|subcommands| {
+        quote! {
+            fn subcommands(&self, _ctx: &Arc<dyn workflow_terminal::cli::Context>) -> &'static [(&'static str, &'static str)] {
+                &#subcommands
+            }
+        }
+    });
+
+    // with `#[args(T)]`, arguments are parsed into `T` and parsing
+    // errors are reported to the terminal without invoking the handler
+    let handle = if let Some(args) = args {
+        quote! {
+            match <#args as workflow_terminal::args::CliArgs>::parse(&argv) {
+                Ok(args) => self.main(ctx, args).await.map_err(|e|e.to_string().into()),
+                Err(err) => {
+                    ctx.term().writeln(workflow_terminal::CrLf::crlf(err.to_string().as_str()));
+                    Ok(())
+                }
+            }
+        }
+    } else {
+        quote! {
+            self.main(ctx,argv,cmd).await.map_err(|e|e.to_string().into())
+        }
+    };
+
     quote! {
 
         #[async_trait::async_trait]
@@ -153,8 +254,14 @@ fn render(handler: DeclareHandler) -> TokenStream {
                 #help
             }
 
+            #description
+            #usage
+            #aliases
+            #subcommands
+
+            #[allow(unused_variables)]
             async fn handle(self : Arc<Self>, ctx: &Arc<dyn workflow_terminal::cli::Context>, argv : Vec<String>, cmd: &str) -> workflow_terminal::cli::Result<()> {
-                self.main(ctx,argv,cmd).await.map_err(|e|e.to_string().into())
+                #handle
             }
         }
 
@@ -211,3 +318,69 @@ fn get_attribute(ast: &mut DeriveInput, name: &str) -> Option<LitStr> {
         None
     }
 }
+
+/// Parses `#[help = "summary"]`, `#[help("summary")]` or
+/// `#[help(summary = "...", description = "...", usage = "...",
+/// aliases = ["..."], subcommands = [("...", "...")])]`.
+fn get_help_attribute(ast: &mut DeriveInput) -> Result<(Option<LitStr>, HelpDetails)> {
+    let mut details = HelpDetails::default();
+    let Some(index) = ast.attrs.iter().position(|attr| attr.path.is_ident("help")) else {
+        return Ok((None, details));
+    };
+    let attr = ast.attrs.remove(index);
+
+    if let Ok(Meta::NameValue(MetaNameValue {
+        lit: Lit::Str(summary),
+        ..
+    })) = attr.parse_meta()
+    {
+        return Ok((Some(summary), details));
+    }
+    if let Ok(summary) = attr.parse_args::<LitStr>() {
+        return Ok((Some(summary), details));
+    }
+
+    let mut summary = None;
+    let fields = attr.parse_args_with(Punctuated::<HelpField, Token![,]>::parse_terminated)?;
+    for HelpField { name, value } in fields {
+        match name.to_string().as_str() {
+            "summary" => summary = Some(lit_str(value)?),
+            "description" => details.description = Some(lit_str(value)?),
+            "usage" => details.usage = Some(lit_str(value)?),
+            "aliases" => details.aliases = Some(expr_array(value)?),
+            "subcommands" => details.subcommands = Some(expr_array(value)?),
+            _ => {
+                return Err(Error::new_spanned(
+                    name,
+                    "unknown help attribute (expected `summary`, `description`, `usage`, `aliases` or `subcommands`)",
+                ))
+            }
+        }
+    }
+
+    Ok((summary, details))
+}
+
+fn lit_str(expr: Expr) -> Result<LitStr> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(lit), ..
+        }) => Ok(lit),
+        _ => Err(Error::new_spanned(expr, "expected a string literal")),
+    }
+}
+
+fn expr_array(expr: Expr) -> Result<ExprArray> {
+    match expr {
+        Expr::Array(array) => Ok(array),
+        _ => Err(Error::new_spanned(expr, "expected an array")),
+    }
+}
+
+fn get_type_attribute(ast: &mut DeriveInput, name: &str) -> Result<Option<Type>> {
+    let Some(index) = ast.attrs.iter().position(|attr| attr.path.is_ident(name)) else {
+        return Ok(None);
+    };
+    let attr = ast.attrs.remove(index);
+    attr.parse_args::<Type>().map(Some)
+}
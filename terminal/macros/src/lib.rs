@@ -1,4 +1,5 @@
 use proc_macro::TokenStream;
+mod args;
 mod handlers;
 mod register;
 
@@ -7,7 +8,7 @@ pub fn declare_handler(input: TokenStream) -> TokenStream {
     handlers::declare_handler(input)
 }
 
-#[proc_macro_derive(Handler, attributes(help))]
+#[proc_macro_derive(Handler, attributes(help, args))]
 pub fn declare_handler_derive(input: TokenStream) -> TokenStream {
     handlers::declare_handler_derive(input)
 }
@@ -16,3 +17,8 @@ pub fn declare_handler_derive(input: TokenStream) -> TokenStream {
 pub fn register_handlers(input: TokenStream) -> TokenStream {
     register::register_handlers(input)
 }
+
+#[proc_macro_derive(CliArgs, attributes(arg))]
+pub fn cli_args_derive(input: TokenStream) -> TokenStream {
+    args::cli_args(input)
+}
@@ -0,0 +1,401 @@
+//!
+//! Command argument parsing. The [`CliArgs`](macro@CliArgs) derive
+//! generates a parser for a struct describing command arguments:
+//!
+//! - fields marked with `#[arg(short, long)]` are options; `bool` fields
+//!   are switches, other fields take a value (`-c 5`, `--count 5` or
+//!   `--count=5`) and are optional if declared as `Option<T>`
+//! - other fields are positional arguments, optional if declared as
+//!   `Option<T>`
+//! - a trailing `Vec<T>` field captures the remaining positional arguments
+//!
+//! Values are parsed using [`std::str::FromStr`]. Option names can be
+//! customized using `#[arg(short = 'x', long = "name")]` and described
+//! using `#[arg(help = "...")]`. Arguments following `--` are treated
+//! as positional.
+//!
+//! ```ignore
+//! #[derive(CliArgs)]
+//! struct AskArgs {
+//!     #[arg(short, long, help = "do not echo the input")]
+//!     secret: bool,
+//!     prompt: Vec<String>,
+//! }
+//! ```
+//!
+//! [`Handler`](crate::cli::Handler) implementations receive typed arguments
+//! by declaring `#[args(AskArgs)]` alongside `#[derive(Handler)]`.
+//!
+
+use std::fmt;
+use std::str::FromStr;
+use workflow_log::style;
+pub use workflow_terminal_macros::CliArgs;
+
+/// Command arguments parsed from the command line
+/// (implemented by the [`CliArgs`](macro@CliArgs) derive).
+pub trait CliArgs: Sized {
+    /// Argument usage, followed by argument descriptions if available.
+    fn usage() -> String;
+    /// Parses command arguments (excluding the command verb).
+    fn parse(argv: &[String]) -> std::result::Result<Self, ArgsError>;
+}
+
+/// Argument parsing error. The error renders the message, the command
+/// arguments with the offending token highlighted and the usage.
+#[derive(Debug, Clone)]
+pub struct ArgsError {
+    message: String,
+    argv: Vec<String>,
+    position: Option<usize>,
+    usage: String,
+}
+
+impl ArgsError {
+    pub fn new(message: String, argv: &[String], position: Option<usize>, usage: String) -> Self {
+        Self {
+            message,
+            argv: argv.to_vec(),
+            position,
+            usage,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Index of the offending token in the command arguments
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    pub fn usage(&self) -> &str {
+        &self.usage
+    }
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.message)?;
+        if let Some(position) = self.position.filter(|position| *position < self.argv.len()) {
+            let mut line = String::new();
+            let mut marker = String::new();
+            for (index, token) in self.argv.iter().enumerate() {
+                if index > 0 {
+                    line.push(' ');
+                    marker.push(' ');
+                }
+                let width = token.chars().count();
+                if index == position {
+                    line.push_str(&style(token.as_str()).red().bold().to_string());
+                    marker.push_str(&"^".repeat(width.max(1)));
+                } else {
+                    line.push_str(token);
+                    marker.push_str(&" ".repeat(width));
+                }
+            }
+            writeln!(f, "  {line}")?;
+            writeln!(f, "  {}", marker.trim_end())?;
+        }
+        write!(f, "usage: {}", self.usage)
+    }
+}
+
+impl std::error::Error for ArgsError {}
+
+/// Option declaration used by the parser generated by the
+/// [`CliArgs`](macro@CliArgs) derive.
+pub struct Flag {
+    pub name: &'static str,
+    pub short: Option<char>,
+    pub long: Option<&'static str>,
+    pub takes_value: bool,
+}
+
+impl Flag {
+    fn display(&self) -> String {
+        match (self.long, self.short) {
+            (Some(long), _) => format!("--{long}"),
+            (None, Some(short)) => format!("-{short}"),
+            (None, None) => self.name.to_string(),
+        }
+    }
+}
+
+/// Argument parser driven by the code generated by the
+/// [`CliArgs`](macro@CliArgs) derive. Options are collected upfront,
+/// positional arguments are consumed in the order of declaration.
+pub struct Parser<'a> {
+    argv: &'a [String],
+    usage: String,
+    flags: &'a [Flag],
+    /// Option values along with the index of the token carrying the value
+    values: Vec<Option<(usize, String)>>,
+    switches: Vec<bool>,
+    positionals: Vec<(usize, &'a str)>,
+    next: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn try_new(
+        argv: &'a [String],
+        flags: &'a [Flag],
+        usage: String,
+    ) -> std::result::Result<Self, ArgsError> {
+        let mut parser = Parser {
+            argv,
+            usage,
+            flags,
+            values: vec![None; flags.len()],
+            switches: vec![false; flags.len()],
+            positionals: Vec::new(),
+            next: 0,
+        };
+
+        let mut tokens = argv.iter().map(String::as_str).enumerate();
+        while let Some((index, token)) = tokens.next() {
+            if token == "--" {
+                parser.positionals.extend(tokens.by_ref());
+                break;
+            }
+
+            let (flag, inline) = if let Some(long) = token.strip_prefix("--") {
+                let (name, inline) = match long.split_once('=') {
+                    Some((name, value)) => (name, Some(value)),
+                    None => (long, None),
+                };
+                let flag = flags.iter().position(|flag| flag.long == Some(name));
+                (flag, inline)
+            } else if token.len() > 1
+                && token.starts_with('-')
+                && !token[1..].starts_with(|c: char| c.is_ascii_digit() || c == '.')
+            {
+                let mut chars = token[1..].chars();
+                let short = chars.next();
+                let flag = flags.iter().position(|flag| flag.short == short);
+                let rest = chars.as_str();
+                (flag, (!rest.is_empty()).then_some(rest))
+            } else {
+                parser.positionals.push((index, token));
+                continue;
+            };
+
+            let Some(flag) = flag else {
+                return Err(parser.error(format!("unknown option `{token}`"), Some(index)));
+            };
+
+            let descriptor = &flags[flag];
+            if descriptor.takes_value {
+                if parser.values[flag].is_some() {
+                    return Err(parser.error(
+                        format!(
+                            "option `{}` is specified more than once",
+                            descriptor.display()
+                        ),
+                        Some(index),
+                    ));
+                }
+                let value = match inline {
+                    Some(value) => (index, value.to_string()),
+                    None => match tokens.next() {
+                        Some((index, value)) => (index, value.to_string()),
+                        None => {
+                            return Err(parser.error(
+                                format!("option `{}` requires a value", descriptor.display()),
+                                Some(index),
+                            ));
+                        }
+                    },
+                };
+                parser.values[flag] = Some(value);
+            } else if inline.is_some() {
+                return Err(parser.error(
+                    format!("option `{}` does not take a value", descriptor.display()),
+                    Some(index),
+                ));
+            } else {
+                parser.switches[flag] = true;
+            }
+        }
+
+        Ok(parser)
+    }
+
+    fn error(&self, message: String, position: Option<usize>) -> ArgsError {
+        ArgsError::new(message, self.argv, position, self.usage.clone())
+    }
+
+    fn convert<T: FromStr>(
+        &self,
+        name: &str,
+        index: usize,
+        value: &str,
+    ) -> std::result::Result<T, ArgsError> {
+        value
+            .parse::<T>()
+            .map_err(|_| self.error(format!("invalid value `{value}` for `{name}`"), Some(index)))
+    }
+
+    /// Value of the switch declared at `flag`
+    pub fn switch(&self, flag: usize) -> bool {
+        self.switches[flag]
+    }
+
+    /// Value of the option declared at `flag`
+    pub fn value<T: FromStr>(&self, flag: usize) -> std::result::Result<Option<T>, ArgsError> {
+        self.values[flag]
+            .as_ref()
+            .map(|(index, value)| self.convert(&self.flags[flag].display(), *index, value))
+            .transpose()
+    }
+
+    /// Value of the mandatory option declared at `flag`
+    pub fn required_value<T: FromStr>(&self, flag: usize) -> std::result::Result<T, ArgsError> {
+        self.value(flag)?.ok_or_else(|| {
+            self.error(
+                format!("missing option `{}`", self.flags[flag].display()),
+                None,
+            )
+        })
+    }
+
+    /// Next positional argument
+    pub fn positional<T: FromStr>(&mut self, name: &str) -> std::result::Result<T, ArgsError> {
+        self.optional_positional(name)?
+            .ok_or_else(|| self.error(format!("missing argument <{name}>"), None))
+    }
+
+    /// Next positional argument, if available
+    pub fn optional_positional<T: FromStr>(
+        &mut self,
+        name: &str,
+    ) -> std::result::Result<Option<T>, ArgsError> {
+        let Some(&(index, value)) = self.positionals.get(self.next) else {
+            return Ok(None);
+        };
+        self.next += 1;
+        self.convert(name, index, value).map(Some)
+    }
+
+    /// Remaining positional arguments
+    pub fn rest<T: FromStr>(&mut self, name: &str) -> std::result::Result<Vec<T>, ArgsError> {
+        let rest = &self.positionals[self.next..];
+        self.next = self.positionals.len();
+        rest.iter()
+            .map(|(index, value)| self.convert(name, *index, value))
+            .collect()
+    }
+
+    /// Fails if there are unconsumed positional arguments.
+    pub fn finish(self) -> std::result::Result<(), ArgsError> {
+        match self.positionals.get(self.next) {
+            Some((index, value)) => {
+                Err(self.error(format!("unexpected argument `{value}`"), Some(*index)))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(CliArgs, Debug, PartialEq)]
+    struct Transfer {
+        #[arg(short, long, help = "do not ask for confirmation")]
+        yes: bool,
+        #[arg(short = 'n', long, help = "number of attempts")]
+        retries: Option<u32>,
+        #[arg(long = "fee-rate")]
+        fee_rate: f64,
+        address: String,
+        amount: Option<u64>,
+        memo: Vec<String>,
+    }
+
+    fn argv(args: &str) -> Vec<String> {
+        args.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_args_parsing() {
+        let args = Transfer::parse(&argv("--fee-rate 1.5 addr 100 hello world -y -n3")).unwrap();
+        assert_eq!(
+            args,
+            Transfer {
+                yes: true,
+                retries: Some(3),
+                fee_rate: 1.5,
+                address: "addr".to_string(),
+                amount: Some(100),
+                memo: vec!["hello".to_string(), "world".to_string()],
+            }
+        );
+
+        let args = Transfer::parse(&argv("addr --fee-rate=2")).unwrap();
+        assert!(!args.yes);
+        assert_eq!(args.retries, None);
+        assert_eq!(args.amount, None);
+        assert!(args.memo.is_empty());
+
+        // tokens following `--` and negative numbers are positional
+        let args = Transfer::parse(&argv("--fee-rate -1 -- --yes")).unwrap();
+        assert_eq!(args.fee_rate, -1.0);
+        assert_eq!(args.address, "--yes");
+        assert!(!args.yes);
+    }
+
+    #[test]
+    fn test_args_errors() {
+        let err = Transfer::parse(&argv("--fee-rate 1 addr abc")).unwrap_err();
+        assert_eq!(err.message(), "invalid value `abc` for `amount`");
+        assert_eq!(err.position(), Some(3));
+        let rendered = err.to_string();
+        let lines = rendered.lines().collect::<Vec<_>>();
+        assert_eq!(lines[2], "                    ^^^");
+        assert!(lines[3].starts_with("usage: [-y|--yes] [-n|--retries <retries>]"));
+
+        let err = Transfer::parse(&argv("addr --fee-rate 1 --verbose")).unwrap_err();
+        assert_eq!(err.message(), "unknown option `--verbose`");
+        assert_eq!(err.position(), Some(3));
+
+        let err = Transfer::parse(&argv("addr")).unwrap_err();
+        assert_eq!(err.message(), "missing option `--fee-rate`");
+        assert_eq!(err.position(), None);
+
+        let err = Transfer::parse(&argv("--fee-rate 1")).unwrap_err();
+        assert_eq!(err.message(), "missing argument <address>");
+
+        let err = Transfer::parse(&argv("addr --fee-rate")).unwrap_err();
+        assert_eq!(err.message(), "option `--fee-rate` requires a value");
+        assert_eq!(err.position(), Some(1));
+
+        let err = Transfer::parse(&argv("addr --fee-rate 1 --yes=no")).unwrap_err();
+        assert_eq!(err.message(), "option `--yes` does not take a value");
+    }
+
+    #[derive(CliArgs, Debug)]
+    struct Exact {
+        first: String,
+        second: Option<String>,
+    }
+
+    #[test]
+    fn test_args_usage() {
+        let err = Exact::parse(&argv("a b c")).unwrap_err();
+        assert_eq!(err.message(), "unexpected argument `c`");
+        assert_eq!(err.position(), Some(2));
+        assert_eq!(Exact::usage(), "<first> [<second>]");
+
+        let usage = Transfer::usage();
+        let lines = usage.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            "[-y|--yes] [-n|--retries <retries>] --fee-rate <fee-rate> <address> [<amount>] [<memo>...]"
+        );
+        assert_eq!(lines[1], "  -y, --yes        do not ask for confirmation");
+        assert_eq!(lines[2], "  -n, --retries    number of attempts");
+    }
+}
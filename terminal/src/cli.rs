@@ -3,7 +3,9 @@
 //!
 
 use crate::error::Error;
+use crate::help::{render_list, suggest, CommandHelp};
 use crate::parse;
+use crate::prompt::Prompt;
 pub use crate::result::Result;
 use crate::terminal::Terminal;
 use async_trait::async_trait;
@@ -26,6 +28,23 @@ pub trait Cli: Sync + Send {
         cmd: String,
     ) -> Result<Option<Vec<String>>>;
     fn prompt(&self) -> Option<String>;
+    /// Right-aligned prompt rendered at the end of the input line
+    fn rprompt(&self) -> Option<String> {
+        None
+    }
+    /// Evaluated before each prompt render and on
+    /// [`Terminal::refresh_prompt()`]; override to produce dynamic
+    /// prompts. Defaults to [`Cli::prompt()`] and [`Cli::rprompt()`].
+    async fn prompt_async(self: Arc<Self>, _term: Arc<Terminal>) -> Prompt {
+        Prompt {
+            left: self.prompt(),
+            right: self.rprompt(),
+        }
+    }
+    /// Invoked on mouse wheel events when mouse support is enabled via
+    /// [`Options::with_mouse()`](crate::Options::with_mouse);
+    /// `delta` is in wheel steps and is negative when scrolling up
+    fn on_scroll(self: Arc<Self>, _term: &Arc<Terminal>, _delta: i32) {}
 }
 
 pub trait Context: Sync + Send + AnySync {
@@ -54,6 +73,23 @@ pub trait Handler: Sync + Send + AnySync {
     fn dyn_help(&self, _ctx: &Arc<dyn Context>) -> String {
         "".to_owned()
     }
+    /// Long description displayed by `help <command>`
+    fn description(&self, _ctx: &Arc<dyn Context>) -> &'static str {
+        ""
+    }
+    /// Usage displayed by `help <command>`; the first line is the
+    /// command signature, followed by optional argument descriptions
+    fn usage(&self, _ctx: &Arc<dyn Context>) -> String {
+        String::new()
+    }
+    /// Alternative names the command can be invoked with
+    fn aliases(&self, _ctx: &Arc<dyn Context>) -> &'static [&'static str] {
+        &[]
+    }
+    /// `(name, help)` pairs displayed by `help <command>`
+    fn subcommands(&self, _ctx: &Arc<dyn Context>) -> &'static [(&'static str, &'static str)] {
+        &[]
+    }
     async fn complete(&self, _ctx: &Arc<dyn Context>, _cmd: &str) -> Result<Option<Vec<String>>> {
         Ok(None)
     }
@@ -85,6 +121,8 @@ pub fn get_handler_help(handler: Arc<dyn Handler>, ctx: &Arc<dyn Context>) -> St
 #[derive(Default)]
 struct Inner {
     handlers: HashMap<String, Arc<dyn Handler>>,
+    /// alias -> verb
+    aliases: HashMap<String, String>,
 }
 
 #[derive(Default)]
@@ -108,7 +146,22 @@ impl HandlerCli {
     }
 
     pub fn get(&self, name: &str) -> Option<Arc<dyn Handler>> {
-        self.inner().handlers.get(name).cloned()
+        let inner = self.inner();
+        inner.handlers.get(name).cloned().or_else(|| {
+            inner
+                .aliases
+                .get(name)
+                .and_then(|verb| inner.handlers.get(verb).cloned())
+        })
+    }
+
+    fn insert(&self, ctx: &Arc<dyn Context>, name: &str, handler: Arc<dyn Handler>) {
+        let name = name.to_lowercase();
+        let mut inner = self.inner();
+        for alias in handler.aliases(ctx) {
+            inner.aliases.insert(alias.to_lowercase(), name.clone());
+        }
+        inner.handlers.insert(name, handler);
     }
 
     pub fn register<T, H>(&self, ctx: &Arc<T>, handler: H)
@@ -119,9 +172,7 @@ impl HandlerCli {
         let ctx: Arc<dyn Context> = ctx.clone();
         match handler.verb(&ctx) {
             Some(name) if handler.condition(&ctx) => {
-                self.inner()
-                    .handlers
-                    .insert(name.to_lowercase(), Arc::new(handler));
+                self.insert(&ctx, name, Arc::new(handler));
             }
             _ => {}
         }
@@ -135,20 +186,87 @@ impl HandlerCli {
         let ctx: Arc<dyn Context> = ctx.clone();
         match handler.verb(&ctx) {
             Some(name) if handler.condition(&ctx) => {
-                self.inner()
-                    .handlers
-                    .insert(name.to_lowercase(), handler.clone());
+                self.insert(&ctx, name, handler.clone());
             }
             _ => {}
         }
     }
 
     pub fn unregister(&self, name: &str) -> Option<Arc<dyn Handler>> {
-        self.inner().handlers.remove(name)
+        let mut inner = self.inner();
+        inner.aliases.retain(|_, verb| verb != name);
+        inner.handlers.remove(name)
     }
 
     pub fn clear(&self) -> Result<()> {
-        self.inner().handlers.clear();
+        let mut inner = self.inner();
+        inner.handlers.clear();
+        inner.aliases.clear();
+        Ok(())
+    }
+
+    fn command_not_found(&self, action: String) -> Error {
+        let inner = self.inner();
+        let mut verbs = inner
+            .handlers
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        if !inner.handlers.contains_key("help") {
+            verbs.push("help");
+        }
+        match suggest(&action, verbs) {
+            Some(suggestion) => Error::CommandNotFoundSuggestion {
+                command: action,
+                suggestion: suggestion.to_string(),
+            },
+            None => Error::CommandNotFound(action),
+        }
+    }
+
+    /// Built-in `help` command, used by [`HandlerCli::execute()`] unless
+    /// a `help` handler is registered. Without arguments, displays the
+    /// sorted list of command summaries (paginated if it does not fit in
+    /// the terminal); `help <command>` displays the description, usage,
+    /// aliases and subcommands of the command.
+    pub async fn help<T>(&self, ctx: &Arc<T>, argv: &[String]) -> Result<()>
+    where
+        T: Context + Sized,
+    {
+        let ctx: Arc<dyn Context> = ctx.clone();
+        self.help_impl(&ctx, argv).await
+    }
+
+    async fn help_impl(&self, ctx: &Arc<dyn Context>, argv: &[String]) -> Result<()> {
+        let term = ctx.term();
+
+        let lines = if let Some(name) = argv.first() {
+            let name = name.to_lowercase();
+            let handler = self
+                .get(&name)
+                .ok_or_else(|| self.command_not_found(name.clone()))?;
+            let verb = handler.verb(ctx).unwrap_or(name.as_str()).to_lowercase();
+            CommandHelp::new(&verb, &handler, ctx).render(term.width())
+        } else {
+            let mut list = self
+                .inner()
+                .handlers
+                .iter()
+                .map(|(verb, handler)| (verb.clone(), get_handler_help(handler.clone(), ctx)))
+                .collect::<Vec<_>>();
+            if !list.iter().any(|(verb, _)| verb == "help") {
+                list.push((
+                    "help".to_string(),
+                    "display commands or `help <command>` for details".to_string(),
+                ));
+            }
+            render_list(&list, None, term.width())
+        };
+
+        term.writeln("");
+        term.page(&lines).await?;
+        term.writeln("");
+
         Ok(())
     }
 
@@ -192,8 +310,10 @@ impl HandlerCli {
                 .handle(&ctx, argv[1..].to_vec(), cmd)
                 .await?;
             Ok(())
+        } else if action == "help" {
+            self.help_impl(&ctx, &argv[1..]).await
         } else {
-            Err(Error::CommandNotFound(action))
+            Err(self.command_not_found(action))
         }
     }
 
@@ -209,8 +329,10 @@ impl HandlerCli {
         let handler = self.get(action.as_str());
         if let Some(handler) = handler {
             Ok(handler.clone().complete(&ctx, cmd).await?)
+        } else if action == "help" {
+            Ok(None)
         } else {
-            Err(Error::CommandNotFound(action))
+            Err(self.command_not_found(action))
         }
     }
 }
@@ -32,10 +32,26 @@ pub enum Error {
     DowncastError(String),
     #[error("command not found: {0}")]
     CommandNotFound(String),
+    #[error("command not found: {command} (did you mean `{suggestion}`?)")]
+    CommandNotFoundSuggestion { command: String, suggestion: String },
     #[error("aborting...")]
     UserAbort,
     #[error(transparent)]
     CallbackError(#[from] workflow_wasm::callback::CallbackError),
+    #[error(transparent)]
+    Args(#[from] crate::args::ArgsError),
+    #[error(transparent)]
+    Task(#[from] workflow_task::TaskError),
+    #[error("no such job: {0}")]
+    JobNotFound(u64),
+    #[error(transparent)]
+    Store(#[from] workflow_store::error::Error),
+    #[error("transcript line {line}: {reason}")]
+    Transcript { line: usize, reason: String },
+    #[error("invalid replay speed: {0}")]
+    ReplaySpeed(f64),
+    #[error("redirection: {0}")]
+    Redirection(String),
 }
 
 impl From<String> for Error {
@@ -0,0 +1,234 @@
+//!
+//! Help output generated from [`Handler`] metadata
+//! (see [`HandlerCli::help()`](crate::cli::HandlerCli::help)).
+//!
+
+use crate::cli::{get_handler_help, Context, Handler};
+use pad::PadStr;
+use std::sync::Arc;
+
+/// Minimum width of the wrapped help text column
+const MIN_HELP_WIDTH: usize = 20;
+
+/// Renders `(command, help)` pairs as a sorted two-column list,
+/// wrapping the help text to fit within `width` columns.
+pub fn render_list<S: ToString, H: ToString>(
+    list: &[(S, H)],
+    separator: Option<&str>,
+    width: usize,
+) -> Vec<String> {
+    let mut list = list
+        .iter()
+        .map(|(cmd, help)| (cmd.to_string(), help.to_string()))
+        .collect::<Vec<_>>();
+    list.sort_by_key(|(cmd, _)| cmd.to_string());
+    let separator = separator.unwrap_or(" ");
+    let cmd_width = list.iter().map(|(c, _)| c.len()).fold(0, |a, b| a.max(b)) + 2;
+    let help_width = width
+        .saturating_sub(cmd_width + 2 + 4 + separator.len())
+        .max(MIN_HELP_WIDTH);
+    let cmd_space = "".pad_to_width(cmd_width);
+
+    let mut lines = Vec::new();
+    for (cmd, help) in list {
+        for (index, line) in textwrap::wrap(help.as_str(), help_width)
+            .into_iter()
+            .enumerate()
+        {
+            if index == 0 {
+                let line = format!(
+                    "{:>4}{}{}{}",
+                    "",
+                    cmd.pad_to_width(cmd_width),
+                    separator,
+                    line
+                );
+                lines.push(line.trim_end().to_string());
+            } else {
+                lines.push(format!("{:>4}{cmd_space}{}{}", "", separator, line));
+            }
+        }
+    }
+    lines
+}
+
+/// Detailed help of a single command rendered by `help <command>`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandHelp {
+    pub verb: String,
+    pub summary: String,
+    pub description: String,
+    pub usage: String,
+    pub aliases: Vec<String>,
+    pub subcommands: Vec<(String, String)>,
+}
+
+impl CommandHelp {
+    pub fn new(verb: &str, handler: &Arc<dyn Handler>, ctx: &Arc<dyn Context>) -> Self {
+        CommandHelp {
+            verb: verb.to_string(),
+            summary: get_handler_help(handler.clone(), ctx),
+            description: handler.description(ctx).to_string(),
+            usage: handler.usage(ctx),
+            aliases: handler
+                .aliases(ctx)
+                .iter()
+                .map(|alias| alias.to_string())
+                .collect(),
+            subcommands: handler
+                .subcommands(ctx)
+                .iter()
+                .map(|(name, help)| (name.to_string(), help.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Renders the summary, usage, aliases, description and
+    /// subcommands, wrapping the text to fit within `width` columns.
+    pub fn render(&self, width: usize) -> Vec<String> {
+        let text_width = width.saturating_sub(4).max(MIN_HELP_WIDTH);
+        let indent = |text: &str| {
+            textwrap::wrap(text, text_width)
+                .into_iter()
+                .map(|line| format!("{:>4}{line}", ""))
+                .collect::<Vec<_>>()
+        };
+
+        let mut lines = Vec::new();
+        if self.summary.is_empty() {
+            lines.push(format!("{:>4}{}", "", self.verb));
+        } else {
+            lines.extend(indent(&format!("{} - {}", self.verb, self.summary)));
+        }
+
+        let usage = if self.usage.is_empty() {
+            self.verb.as_str()
+        } else {
+            self.usage.as_str()
+        };
+        lines.push(String::new());
+        let mut usage = usage.lines();
+        if let Some(signature) = usage.next() {
+            lines.push(format!("{:>4}Usage: {signature}", ""));
+        }
+        // option lines of `CliArgs::usage()` are already aligned
+        lines.extend(usage.map(|line| format!("{:>4}{line}", "")));
+
+        if !self.aliases.is_empty() {
+            lines.push(format!("{:>4}Aliases: {}", "", self.aliases.join(", ")));
+        }
+
+        if !self.description.is_empty() {
+            for paragraph in self.description.split("\n\n") {
+                lines.push(String::new());
+                lines.extend(indent(paragraph));
+            }
+        }
+
+        if !self.subcommands.is_empty() {
+            lines.push(String::new());
+            lines.push(format!("{:>4}Subcommands:", ""));
+            lines.extend(
+                render_list(&self.subcommands, None, width)
+                    .into_iter()
+                    .map(|line| format!("  {line}")),
+            );
+        }
+
+        lines
+    }
+}
+
+/// Levenshtein distance between `a` and `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Returns the candidate closest to `name`, provided that it is
+/// within an edit distance of a third of the length of `name`
+/// (at least one edit).
+pub fn suggest<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_list() {
+        let list = [
+            ("sleep", "sleep for the given duration"),
+            ("ask", "ask user for text input"),
+        ];
+        assert_eq!(
+            render_list(&list, None, 80),
+            [
+                "    ask     ask user for text input",
+                "    sleep   sleep for the given duration",
+            ]
+        );
+
+        let lines = render_list(&list, Some("- "), 38);
+        assert_eq!(lines[0], "    ask    - ask user for text input");
+        assert_eq!(lines[1], "    sleep  - sleep for the given");
+        assert_eq!(lines[2], "           - duration");
+    }
+
+    #[test]
+    fn test_command_help() {
+        let help = CommandHelp {
+            verb: "sleep".to_string(),
+            summary: "sleep for the given duration".to_string(),
+            description: "Suspends the terminal.\n\nPress Ctrl+C to abort.".to_string(),
+            usage: "sleep [<duration>]\n  <duration>  e.g. 250ms".to_string(),
+            aliases: vec!["zz".to_string()],
+            subcommands: vec![],
+        };
+        assert_eq!(
+            help.render(80),
+            [
+                "    sleep - sleep for the given duration",
+                "",
+                "    Usage: sleep [<duration>]",
+                "      <duration>  e.g. 250ms",
+                "    Aliases: zz",
+                "",
+                "    Suspends the terminal.",
+                "",
+                "    Press Ctrl+C to abort.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_suggest() {
+        let commands = ["help", "hello", "history", "exit"];
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(suggest("helo", commands), Some("hello"));
+        assert_eq!(suggest("hep", commands), Some("help"));
+        assert_eq!(suggest("exot", commands), Some("exit"));
+        assert_eq!(suggest("histroy", commands), Some("history"));
+        assert_eq!(suggest("xyz", commands), None);
+    }
+}
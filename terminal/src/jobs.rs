@@ -0,0 +1,471 @@
+//!
+//! Shell-like job control: background commands spawned by [`Cli`](crate::Cli)
+//! handlers using [`Terminal::spawn_job()`] keep running after the command returns.
+//!
+//! Output of background jobs ([`JobContext::writeln()`]) is prefixed with `[job N]`
+//! and relayed via [`Terminal::pipe_crlf`], which redraws the prompt and the user
+//! input below the output. [`JobsHandler`] (`jobs`) and [`StopHandler`] (`stop <id>`)
+//! can be registered with the [`HandlerCli`](crate::HandlerCli) to manage the jobs.
+//!
+//! ```ignore
+//! term.spawn_job("watch", |ctx| async move {
+//!     loop {
+//!         select! {
+//!             _ = ctx.stop().recv().fuse() => break,
+//!             _ = sleep(Duration::from_secs(1)).fuse() => ctx.writeln("tick"),
+//!         }
+//!     }
+//!     Ok(())
+//! })?;
+//! ```
+//!
+
+use crate::cli::{Context, Handler};
+use crate::error::Error;
+use crate::result::Result;
+use crate::terminal::Terminal;
+use async_trait::async_trait;
+use futures::Future;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use workflow_core::channel::{Receiver, Sender};
+use workflow_core::time::Instant;
+use workflow_task::{FnReturn, Task};
+
+pub type JobId = u64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobState {
+    Running,
+    /// Termination has been signalled, the job has not exited yet
+    Stopping,
+    Done,
+    Stopped,
+    Failed(String),
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobState::Running => write!(f, "running"),
+            JobState::Stopping => write!(f, "stopping"),
+            JobState::Done => write!(f, "done"),
+            JobState::Stopped => write!(f, "stopped"),
+            JobState::Failed(err) => write!(f, "failed: {err}"),
+        }
+    }
+}
+
+impl JobState {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobState::Done | JobState::Stopped | JobState::Failed(_)
+        )
+    }
+}
+
+/// Context supplied to the job function
+pub struct JobContext {
+    id: JobId,
+    stop: Receiver<()>,
+    output: Sender<String>,
+}
+
+impl JobContext {
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    /// Termination channel of the job task, receiving
+    /// a message when `stop <id>` is issued.
+    pub fn stop(&self) -> &Receiver<()> {
+        &self.stop
+    }
+
+    /// `true` if the job termination has been signalled
+    /// (the signal is not consumed)
+    pub fn is_stopping(&self) -> bool {
+        !self.stop.is_empty()
+    }
+
+    /// Write a line prefixed with `[job N]` above the prompt
+    pub fn writeln<S: fmt::Display>(&self, text: S) {
+        self.output
+            .try_send(format!("[job {}] {text}", self.id))
+            .ok();
+    }
+}
+
+struct JobData {
+    state: JobState,
+    started: Instant,
+    finished: Option<Instant>,
+}
+
+struct JobInner {
+    id: JobId,
+    command: String,
+    data: Mutex<JobData>,
+    task: Task<Arc<JobInner>, ()>,
+}
+
+impl JobInner {
+    fn data(&self) -> MutexGuard<'_, JobData> {
+        self.data.lock().unwrap()
+    }
+
+    /// Records the job completion, returning the notification line
+    fn complete(&self, result: Result<()>) -> String {
+        let mut data = self.data();
+        data.finished = Some(Instant::now());
+        data.state = match result {
+            Ok(()) if data.state == JobState::Stopping => JobState::Stopped,
+            Ok(()) => JobState::Done,
+            Err(err) => JobState::Failed(err.to_string()),
+        };
+        format!("[job {}] {} ({})", self.id, data.state, self.command)
+    }
+}
+
+/// Background job spawned using [`Terminal::spawn_job()`]
+#[derive(Clone)]
+pub struct TerminalJob {
+    inner: Arc<JobInner>,
+}
+
+impl TerminalJob {
+    pub fn id(&self) -> JobId {
+        self.inner.id
+    }
+
+    pub fn command(&self) -> &str {
+        &self.inner.command
+    }
+
+    pub fn state(&self) -> JobState {
+        self.inner.data().state.clone()
+    }
+
+    /// Time elapsed since the job start (until the job completion)
+    pub fn runtime(&self) -> Duration {
+        let data = self.inner.data();
+        data.finished
+            .unwrap_or_else(Instant::now)
+            .duration_since(data.started)
+    }
+
+    /// Signal termination via the termination channel of the job task
+    pub fn stop(&self) -> Result<()> {
+        let mut data = self.inner.data();
+        if data.state == JobState::Running {
+            data.state = JobState::Stopping;
+            self.inner.task.stop()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct JobsInner {
+    last_id: JobId,
+    jobs: BTreeMap<JobId, TerminalJob>,
+}
+
+/// Registry of background jobs of the [`Terminal`]
+pub struct Jobs {
+    inner: Mutex<JobsInner>,
+    output: Sender<String>,
+}
+
+impl Jobs {
+    pub(crate) fn new(output: Sender<String>) -> Self {
+        Self {
+            inner: Mutex::new(JobsInner::default()),
+            output,
+        }
+    }
+
+    fn inner(&self) -> MutexGuard<'_, JobsInner> {
+        self.inner.lock().unwrap()
+    }
+
+    /// Spawn the job future created by `job_fn`. A notification line
+    /// is printed once the job completes.
+    pub fn spawn<F, Fut>(&self, command: &str, job_fn: F) -> Result<TerminalJob>
+    where
+        F: FnOnce(JobContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + Sync + 'static,
+    {
+        let job_fn = Mutex::new(Some(job_fn));
+        let output = self.output.clone();
+        let task = Task::new(move |job: Arc<JobInner>, stop| -> FnReturn<()> {
+            let ctx = JobContext {
+                id: job.id,
+                stop,
+                output: output.clone(),
+            };
+            let future = job_fn.lock().unwrap().take().map(|job_fn| job_fn(ctx));
+            let output = output.clone();
+            Box::pin(async move {
+                if let Some(future) = future {
+                    let notification = job.complete(future.await);
+                    output.try_send(notification).ok();
+                }
+            })
+        });
+
+        let job = {
+            let mut inner = self.inner();
+            inner.last_id += 1;
+            let job = TerminalJob {
+                inner: Arc::new(JobInner {
+                    id: inner.last_id,
+                    command: command.to_string(),
+                    data: Mutex::new(JobData {
+                        state: JobState::Running,
+                        started: Instant::now(),
+                        finished: None,
+                    }),
+                    task,
+                }),
+            };
+            inner.jobs.insert(job.id(), job.clone());
+            job
+        };
+
+        job.inner.task.run(job.inner.clone())?;
+        Ok(job)
+    }
+
+    pub fn get(&self, id: JobId) -> Option<TerminalJob> {
+        self.inner().jobs.get(&id).cloned()
+    }
+
+    /// Jobs ordered by id, including finished jobs not yet removed by [`Jobs::prune()`]
+    pub fn list(&self) -> Vec<TerminalJob> {
+        self.inner().jobs.values().cloned().collect()
+    }
+
+    pub fn stop(&self, id: JobId) -> Result<()> {
+        self.get(id).ok_or(Error::JobNotFound(id))?.stop()
+    }
+
+    /// Remove finished jobs
+    pub fn prune(&self) {
+        self.inner()
+            .jobs
+            .retain(|_, job| !job.state().is_finished());
+    }
+
+    /// Job listing lines (id, state, runtime and command)
+    pub fn listing(&self) -> Vec<String> {
+        self.list()
+            .iter()
+            .map(|job| {
+                format!(
+                    "[{}] {:<10} {:>8}  {}",
+                    job.id(),
+                    job.state().to_string(),
+                    format_runtime(job.runtime()),
+                    job.command()
+                )
+            })
+            .collect()
+    }
+}
+
+fn format_runtime(runtime: Duration) -> String {
+    let secs = runtime.as_secs();
+    if secs < 60 {
+        format!("{:.1}s", runtime.as_secs_f64())
+    } else if secs < 3600 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+/// `jobs` command listing background jobs. Finished jobs
+/// are listed once and removed afterwards.
+pub struct JobsHandler;
+
+#[async_trait]
+impl Handler for JobsHandler {
+    fn verb(&self, _ctx: &Arc<dyn Context>) -> Option<&'static str> {
+        Some("jobs")
+    }
+
+    fn help(&self, _ctx: &Arc<dyn Context>) -> &'static str {
+        "List background jobs"
+    }
+
+    async fn handle(
+        self: Arc<Self>,
+        ctx: &Arc<dyn Context>,
+        _argv: Vec<String>,
+        _cmd: &str,
+    ) -> Result<()> {
+        let term = ctx.term();
+        let jobs = term.jobs();
+        let listing = jobs.listing();
+        if listing.is_empty() {
+            term.writeln("no jobs");
+        }
+        listing.into_iter().for_each(|line| term.writeln(line));
+        jobs.prune();
+        Ok(())
+    }
+}
+
+/// `stop <id>` command signalling termination of a background job
+pub struct StopHandler;
+
+#[async_trait]
+impl Handler for StopHandler {
+    fn verb(&self, _ctx: &Arc<dyn Context>) -> Option<&'static str> {
+        Some("stop")
+    }
+
+    fn help(&self, _ctx: &Arc<dyn Context>) -> &'static str {
+        "Stop a background job: stop <id>"
+    }
+
+    async fn handle(
+        self: Arc<Self>,
+        ctx: &Arc<dyn Context>,
+        argv: Vec<String>,
+        _cmd: &str,
+    ) -> Result<()> {
+        let id = argv
+            .first()
+            .map(|id| id.trim_start_matches('%'))
+            .and_then(|id| id.parse::<JobId>().ok())
+            .ok_or_else(|| Error::Custom("usage: stop <id>".to_string()))?;
+        ctx.term().jobs().stop(id)
+    }
+}
+
+impl Terminal {
+    /// Background jobs of the terminal
+    pub fn jobs(&self) -> &Arc<Jobs> {
+        &self.jobs
+    }
+
+    /// Spawn a background job (see [`Jobs::spawn()`])
+    pub fn spawn_job<F, Fut>(&self, command: &str, job_fn: F) -> Result<TerminalJob>
+    where
+        F: FnOnce(JobContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + Sync + 'static,
+    {
+        self.jobs.spawn(command, job_fn)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+
+    struct TestCli;
+
+    #[async_trait]
+    impl Cli for TestCli {
+        async fn digest(self: Arc<Self>, _term: Arc<Terminal>, _cmd: String) -> Result<()> {
+            Ok(())
+        }
+        async fn complete(
+            self: Arc<Self>,
+            _term: Arc<Terminal>,
+            _cmd: String,
+        ) -> Result<Option<Vec<String>>> {
+            Ok(None)
+        }
+        fn prompt(&self) -> Option<String> {
+            None
+        }
+    }
+
+    /// Receive `count` lines relayed to the (not running) terminal
+    async fn output(term: &Terminal, count: usize) -> Vec<String> {
+        let mut lines = Vec::new();
+        for _ in 0..count {
+            let line = tokio::time::timeout(Duration::from_secs(5), term.pipe_crlf.recv())
+                .await
+                .expect("timeout waiting for job output")
+                .unwrap();
+            lines.push(line);
+        }
+        lines
+    }
+
+    #[tokio::test]
+    async fn test_terminal_jobs() {
+        let term = Terminal::try_new(Arc::new(TestCli), "$ ").unwrap();
+
+        let build = term
+            .spawn_job("build", |ctx| async move {
+                ctx.writeln("compiling");
+                Ok(())
+            })
+            .unwrap();
+        let watch = term
+            .spawn_job("watch --all", |ctx| async move {
+                ctx.writeln("watching");
+                ctx.stop().recv().await.ok();
+                ctx.writeln("shutting down");
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!((build.id(), watch.id()), (1, 2));
+
+        let mut lines = output(&term, 3).await;
+        lines.sort();
+        assert_eq!(
+            lines,
+            vec![
+                "[job 1] compiling",
+                "[job 1] done (build)",
+                "[job 2] watching"
+            ]
+        );
+
+        assert_eq!(build.state(), JobState::Done);
+        assert_eq!(watch.state(), JobState::Running);
+        let listing = term.jobs().listing();
+        assert_eq!(listing.len(), 2);
+        assert!(listing[0].starts_with("[1] done "));
+        assert!(listing[0].ends_with("  build"));
+        assert!(listing[1].starts_with("[2] running "));
+        assert!(listing[1].ends_with("  watch --all"));
+
+        term.jobs().stop(2).unwrap();
+        assert_eq!(
+            output(&term, 2).await,
+            vec!["[job 2] shutting down", "[job 2] stopped (watch --all)"]
+        );
+        assert_eq!(watch.state(), JobState::Stopped);
+        assert!(matches!(term.jobs().stop(3), Err(Error::JobNotFound(3))));
+
+        term.jobs().prune();
+        assert!(term.jobs().list().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_terminal_job_failure() {
+        let term = Terminal::try_new(Arc::new(TestCli), "$ ").unwrap();
+        let job = term
+            .spawn_job("fetch", |_ctx| async move {
+                Err(Error::Custom("timeout".to_string()))
+            })
+            .unwrap();
+        assert_eq!(
+            output(&term, 1).await,
+            vec!["[job 1] failed: timeout (fetch)"]
+        );
+        assert_eq!(job.state(), JobState::Failed("timeout".to_string()));
+    }
+}
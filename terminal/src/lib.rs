@@ -95,21 +95,32 @@
 
 extern crate self as workflow_terminal;
 
+pub mod args;
 pub mod clear;
 pub mod cli;
 pub mod crlf;
 pub mod cursor;
 pub mod error;
+pub mod help;
+pub mod jobs;
 pub mod keys;
 pub mod macros;
+pub mod mouse;
 pub mod prelude;
+pub mod prompt;
+pub mod redirect;
 pub mod result;
 pub mod terminal;
+pub mod transcript;
 pub mod unicode;
 
+pub use args::CliArgs;
 pub use cli::{Cli, Context, Handler, HandlerCli};
 pub use crlf::CrLf;
+pub use jobs::{JobContext, JobState, Jobs, JobsHandler, StopHandler, TerminalJob};
 pub use macros::*;
+pub use mouse::{MouseButton, MouseEvent, MouseEventKind, MouseModifiers};
+pub use prompt::Prompt;
 pub use result::Result;
 pub use terminal::parse;
 pub use terminal::Event;
@@ -119,6 +130,7 @@ pub use terminal::TargetElement;
 pub use terminal::Terminal;
 pub use terminal::{Theme, ThemeOption};
 pub use textwrap;
+pub use transcript::{InputCapture, Transcript, TranscriptSink};
 pub use unicode::UnicodeString;
 
 cfg_if::cfg_if! {
@@ -0,0 +1,448 @@
+//!
+//! Mouse reporting support: SGR (`DECSET 1006`) mouse escape sequence
+//! parser and mapping of mouse clicks to the edit cursor position.
+//!
+//! Mouse reporting is opt-in and is enabled via
+//! [`Options::with_mouse()`](crate::Options::with_mouse).
+//!
+
+/// Escape sequence enabling mouse button (`DECSET 1000`)
+/// reporting in the SGR extended format (`DECSET 1006`)
+pub const ENABLE_MOUSE: &str = "\x1b[?1000h\x1b[?1006h";
+/// Escape sequence disabling mouse reporting
+pub const DISABLE_MOUSE: &str = "\x1b[?1006l\x1b[?1000l";
+/// Cursor position request (the terminal replies with `ESC [ row ; col R`)
+pub const REQUEST_CURSOR_POSITION: &str = "\x1b[6n";
+
+/// Maximum length of an escape sequence accepted by [`SgrParser`]
+const MAX_SEQUENCE_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseEventKind {
+    Press(MouseButton),
+    Release(MouseButton),
+    Drag(MouseButton),
+    Move,
+    ScrollUp,
+    ScrollDown,
+    ScrollLeft,
+    ScrollRight,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MouseModifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool,
+}
+
+/// Mouse event; `column` and `row` are zero-based screen coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MouseEvent {
+    pub kind: MouseEventKind,
+    pub column: u16,
+    pub row: u16,
+    pub modifiers: MouseModifiers,
+}
+
+impl MouseEvent {
+    /// Scroll delta in wheel steps (negative when scrolling up),
+    /// `None` if this is not a vertical scroll event
+    pub fn scroll_delta(&self) -> Option<i32> {
+        match self.kind {
+            MouseEventKind::ScrollUp => Some(-1),
+            MouseEventKind::ScrollDown => Some(1),
+            _ => None,
+        }
+    }
+
+    /// Decodes SGR mouse report parameters (`ESC [ < Cb ; Cx ; Cy M|m`)
+    fn from_sgr(cb: u16, cx: u16, cy: u16, release: bool) -> Option<Self> {
+        let button = match cb & 0b11 {
+            0 => Some(MouseButton::Left),
+            1 => Some(MouseButton::Middle),
+            2 => Some(MouseButton::Right),
+            _ => None,
+        };
+        let kind = if cb & 64 != 0 {
+            match cb & 0b11 {
+                0 => MouseEventKind::ScrollUp,
+                1 => MouseEventKind::ScrollDown,
+                2 => MouseEventKind::ScrollLeft,
+                _ => MouseEventKind::ScrollRight,
+            }
+        } else if cb & 32 != 0 {
+            button
+                .map(MouseEventKind::Drag)
+                .unwrap_or(MouseEventKind::Move)
+        } else if release {
+            MouseEventKind::Release(button?)
+        } else {
+            MouseEventKind::Press(button?)
+        };
+
+        Some(MouseEvent {
+            kind,
+            column: cx.saturating_sub(1),
+            row: cy.saturating_sub(1),
+            modifiers: MouseModifiers {
+                shift: cb & 4 != 0,
+                alt: cb & 8 != 0,
+                ctrl: cb & 16 != 0,
+            },
+        })
+    }
+}
+
+/// Input decoded by [`SgrParser`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sequence {
+    Mouse(MouseEvent),
+    /// Cursor position report (reply to [`REQUEST_CURSOR_POSITION`]),
+    /// zero-based screen coordinates
+    CursorPosition {
+        column: u16,
+        row: u16,
+    },
+    /// Bytes that are not part of a recognized sequence
+    Unhandled(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum State {
+    #[default]
+    Ground,
+    Escape,
+    Csi,
+    Params {
+        sgr: bool,
+    },
+}
+
+/// Incremental parser for SGR mouse reports and cursor position
+/// reports. Bytes are fed one at a time; partial sequences are
+/// buffered until complete, so input may be split arbitrarily.
+#[derive(Debug, Default)]
+pub struct SgrParser {
+    state: State,
+    params: Vec<u16>,
+    param: Option<u16>,
+    raw: Vec<u8>,
+}
+
+impl SgrParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds a single byte, returning a sequence once complete
+    pub fn feed(&mut self, byte: u8) -> Option<Sequence> {
+        if byte == 0x1b && self.state != State::Ground {
+            // ESC interrupts the pending sequence and starts a new one
+            let pending = self.unhandled();
+            self.raw.push(byte);
+            self.state = State::Escape;
+            return Some(pending);
+        }
+        self.raw.push(byte);
+        match (self.state, byte) {
+            (State::Ground, 0x1b) => {
+                self.state = State::Escape;
+                None
+            }
+            (State::Ground, _) => Some(self.unhandled()),
+            (State::Escape, b'[') => {
+                self.state = State::Csi;
+                None
+            }
+            (State::Csi, b'<') => {
+                self.state = State::Params { sgr: true };
+                None
+            }
+            (State::Csi, b'0'..=b'9') | (State::Params { .. }, b'0'..=b'9') => {
+                if self.state == State::Csi {
+                    self.state = State::Params { sgr: false };
+                }
+                let digit = (byte - b'0') as u16;
+                let param = self.param.unwrap_or(0);
+                self.param = Some(param.saturating_mul(10).saturating_add(digit));
+                self.check_len()
+            }
+            (State::Params { .. }, b';') => {
+                self.params.push(self.param.take().unwrap_or(0));
+                self.check_len()
+            }
+            (State::Params { sgr: true }, b'M' | b'm') => {
+                self.params.extend(self.param.take());
+                match self.params[..] {
+                    [cb, cx, cy] => match MouseEvent::from_sgr(cb, cx, cy, byte == b'm') {
+                        Some(event) => {
+                            self.reset();
+                            Some(Sequence::Mouse(event))
+                        }
+                        None => Some(self.unhandled()),
+                    },
+                    _ => Some(self.unhandled()),
+                }
+            }
+            (State::Params { sgr: false }, b'R') => {
+                self.params.extend(self.param.take());
+                match self.params[..] {
+                    [row, column] => {
+                        self.reset();
+                        Some(Sequence::CursorPosition {
+                            column: column.saturating_sub(1),
+                            row: row.saturating_sub(1),
+                        })
+                    }
+                    _ => Some(self.unhandled()),
+                }
+            }
+            _ => Some(self.unhandled()),
+        }
+    }
+
+    /// Feeds a slice of bytes, returning all completed sequences
+    pub fn feed_all(&mut self, bytes: &[u8]) -> Vec<Sequence> {
+        bytes.iter().filter_map(|byte| self.feed(*byte)).collect()
+    }
+
+    fn check_len(&mut self) -> Option<Sequence> {
+        (self.raw.len() > MAX_SEQUENCE_LEN).then(|| self.unhandled())
+    }
+
+    fn unhandled(&mut self) -> Sequence {
+        let raw = std::mem::take(&mut self.raw);
+        self.reset();
+        Sequence::Unhandled(raw)
+    }
+
+    fn reset(&mut self) {
+        self.state = State::Ground;
+        self.params.clear();
+        self.param = None;
+        self.raw.clear();
+    }
+}
+
+/// Number of screen columns occupied by `text`,
+/// excluding ANSI escape sequences (such as prompt colors)
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            if chars.next_if_eq(&'[').is_some() {
+                // CSI: parameters followed by a final byte in `@`..=`~`
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            } else {
+                chars.next();
+            }
+        } else if !c.is_control() {
+            width += 1;
+        }
+    }
+    width
+}
+
+/// Maps a click at `click` (zero-based `(column, row)`) to an edit
+/// cursor position within the current input line.
+///
+/// `screen_cursor` is the current screen position of the edit cursor,
+/// located at `cursor` characters into the input of `len` characters
+/// following a prompt of `prompt_width` columns, in a terminal `cols`
+/// columns wide (the line is wrapped across rows).
+///
+/// Returns `None` if the click lands outside of the input line
+/// (or on the prompt); clicks past the end of the input map to
+/// the end of the input.
+pub fn click_to_cursor(
+    click: (u16, u16),
+    screen_cursor: (u16, u16),
+    prompt_width: usize,
+    cursor: usize,
+    len: usize,
+    cols: usize,
+) -> Option<usize> {
+    if cols == 0 {
+        return None;
+    }
+    let (click_col, click_row) = (click.0 as usize, click.1 as usize);
+    let cursor_row = screen_cursor.1 as usize;
+    let start_row = cursor_row.checked_sub((prompt_width + cursor) / cols)?;
+    let end_row = start_row + (prompt_width + len) / cols;
+    if click_row < start_row || click_row > end_row {
+        return None;
+    }
+    let offset = (click_row - start_row) * cols + click_col;
+    let position = offset.checked_sub(prompt_width)?;
+    Some(position.min(len))
+}
+
+/// Screen state captured by the terminal backend for a mouse event
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Screen {
+    /// Zero-based `(column, row)` of the screen cursor
+    pub cursor: (u16, u16),
+    pub cols: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Vec<Sequence> {
+        SgrParser::new().feed_all(input.as_bytes())
+    }
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> Sequence {
+        Sequence::Mouse(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: MouseModifiers::default(),
+        })
+    }
+
+    #[test]
+    fn test_sgr_press_release() {
+        assert_eq!(
+            parse("\x1b[<0;10;5M\x1b[<0;10;5m"),
+            vec![
+                mouse(MouseEventKind::Press(MouseButton::Left), 9, 4),
+                mouse(MouseEventKind::Release(MouseButton::Left), 9, 4),
+            ]
+        );
+        assert_eq!(
+            parse("\x1b[<2;1;1M"),
+            vec![mouse(MouseEventKind::Press(MouseButton::Right), 0, 0)]
+        );
+    }
+
+    #[test]
+    fn test_sgr_scroll_drag_modifiers() {
+        assert_eq!(
+            parse("\x1b[<64;3;4M\x1b[<65;3;4M\x1b[<32;7;2M\x1b[<35;7;2M"),
+            vec![
+                mouse(MouseEventKind::ScrollUp, 2, 3),
+                mouse(MouseEventKind::ScrollDown, 2, 3),
+                mouse(MouseEventKind::Drag(MouseButton::Left), 6, 1),
+                mouse(MouseEventKind::Move, 6, 1),
+            ]
+        );
+        let Sequence::Mouse(event) = &parse("\x1b[<20;1;1M")[0] else {
+            panic!("expected mouse event");
+        };
+        assert_eq!(event.kind, MouseEventKind::Press(MouseButton::Left));
+        assert_eq!(
+            event.modifiers,
+            MouseModifiers {
+                shift: true,
+                alt: false,
+                ctrl: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_sgr_split_input() {
+        let mut parser = SgrParser::new();
+        assert!(parser.feed_all(b"\x1b[<0;1").is_empty());
+        assert!(parser.feed_all(b"2;").is_empty());
+        assert_eq!(
+            parser.feed_all(b"3M"),
+            vec![mouse(MouseEventKind::Press(MouseButton::Left), 11, 2)]
+        );
+    }
+
+    #[test]
+    fn test_cursor_position_and_passthrough() {
+        assert_eq!(
+            parse("a\x1b[12;40R"),
+            vec![
+                Sequence::Unhandled(b"a".to_vec()),
+                Sequence::CursorPosition {
+                    column: 39,
+                    row: 11
+                },
+            ]
+        );
+        // unrelated sequences are returned verbatim
+        assert_eq!(
+            parse("\x1bOA"),
+            vec![
+                Sequence::Unhandled(b"\x1bO".to_vec()),
+                Sequence::Unhandled(b"A".to_vec())
+            ]
+        );
+        assert_eq!(
+            parse("\x1b[1;2;3M"),
+            vec![Sequence::Unhandled(b"\x1b[1;2;3M".to_vec())]
+        );
+        assert_eq!(
+            parse("\x1b[<0;1M"),
+            vec![Sequence::Unhandled(b"\x1b[<0;1M".to_vec())]
+        );
+        // a lone ESC (Escape key) does not swallow the following report
+        assert_eq!(
+            parse("\x1b\x1b[<0;1;1M"),
+            vec![
+                Sequence::Unhandled(b"\x1b".to_vec()),
+                mouse(MouseEventKind::Press(MouseButton::Left), 0, 0),
+            ]
+        );
+        // the parser recovers after malformed input
+        let mut parser = SgrParser::new();
+        let overlong = format!("\x1b[<{}", "1".repeat(MAX_SEQUENCE_LEN));
+        assert!(parser
+            .feed_all(overlong.as_bytes())
+            .iter()
+            .all(|sequence| matches!(sequence, Sequence::Unhandled(_))));
+        assert_eq!(
+            parser.feed_all(b"\x1b[<1;1;1M"),
+            vec![mouse(MouseEventKind::Press(MouseButton::Middle), 0, 0)]
+        );
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("$ "), 2);
+        assert_eq!(display_width("\x1b[1;32mkaspa\x1b[0m $ "), 8);
+        assert_eq!(display_width("λ "), 2);
+    }
+
+    #[test]
+    fn test_click_to_cursor() {
+        // "$ hello" on row 5, cursor at the end of the input
+        let at = |col, row| click_to_cursor((col, row), (7, 5), 2, 5, 5, 80);
+        assert_eq!(at(2, 5), Some(0));
+        assert_eq!(at(4, 5), Some(2));
+        assert_eq!(at(40, 5), Some(5));
+        assert_eq!(at(1, 5), None);
+        assert_eq!(at(4, 4), None);
+        assert_eq!(at(4, 6), None);
+
+        // 20 characters following "$ " wrapped at 10 columns:
+        // row 3: "$ 01234567", row 4: "8901234567", row 5: "89"
+        // with the cursor at position 12 on row 4, column 4
+        let at = |col, row| click_to_cursor((col, row), (4, 4), 2, 12, 20, 10);
+        assert_eq!(at(5, 3), Some(3));
+        assert_eq!(at(0, 4), Some(8));
+        assert_eq!(at(1, 5), Some(19));
+        assert_eq!(at(9, 5), Some(20));
+        assert_eq!(at(0, 6), None);
+        assert_eq!(at(0, 2), None);
+    }
+}
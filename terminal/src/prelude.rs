@@ -1,4 +1,5 @@
 pub use crate::{
+    args::CliArgs,
     cli,
     cli::{declare_handler, get_handler_help, register_handlers},
     parse,
@@ -0,0 +1,285 @@
+//!
+//! Prompt segments produced by [`Cli::prompt_async()`](crate::Cli::prompt_async)
+//! and rendering of the input line with an optional right-aligned prompt.
+//!
+
+use crate::clear::ClearLine;
+use crate::mouse::display_width;
+use crate::UnicodeString;
+
+/// Prompt evaluated before each prompt render. Both segments may
+/// contain ANSI escape sequences (colors, styles); these are excluded
+/// from the cursor position calculations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Prompt {
+    /// Prompt rendered before the user input; if `None`, the
+    /// terminal falls back to [`Cli::prompt()`](crate::Cli::prompt)
+    /// or to the prompt supplied to the terminal on creation
+    pub left: Option<String>,
+    /// Prompt rendered right-aligned at the end of the line; truncated
+    /// (and eventually omitted) when the line is not wide enough
+    pub right: Option<String>,
+}
+
+impl Prompt {
+    pub fn new<S: Into<String>>(left: S) -> Self {
+        Prompt {
+            left: Some(left.into()),
+            right: None,
+        }
+    }
+
+    pub fn with_right<S: Into<String>>(mut self, right: S) -> Self {
+        self.right = Some(right.into());
+        self
+    }
+}
+
+/// Truncates `text` to at most `width` visible columns, preserving
+/// ANSI escape sequences. Styles are reset if the text was truncated.
+pub fn truncate_width(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut visible = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            truncated.push(c);
+            if let Some(c) = chars.next_if_eq(&'[') {
+                truncated.push(c);
+                for c in chars.by_ref() {
+                    truncated.push(c);
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            } else if let Some(c) = chars.next() {
+                truncated.push(c);
+            }
+        } else if c.is_control() {
+            truncated.push(c);
+        } else if visible < width {
+            truncated.push(c);
+            visible += 1;
+        }
+    }
+    truncated.push_str("\x1b[0m");
+    truncated
+}
+
+/// Renders the input line in place: clears the line, places `right`
+/// at the end of the line (`cols` columns wide), writes `left` followed
+/// by `buffer` and moves the cursor back to `cursor` within the buffer.
+///
+/// The right prompt is separated from the input by at least one column;
+/// it is truncated first when the line is too narrow and is omitted
+/// once there is no room left for it.
+pub fn render_line(
+    left: &str,
+    right: Option<&str>,
+    buffer: &UnicodeString,
+    cursor: usize,
+    cols: usize,
+) -> String {
+    let mut line = ClearLine.to_string();
+
+    if let Some(right) = right {
+        let used = display_width(left) + buffer.len() + 1;
+        let available = cols.saturating_sub(used);
+        if available > 0 {
+            let right = truncate_width(right, available);
+            let column = cols - display_width(&right) + 1;
+            line.push_str(&format!("\x1b[{column}G{right}\r"));
+        }
+    }
+
+    line.push_str(left);
+    line.push_str(&buffer.to_string());
+    for _ in cursor..buffer.len() {
+        line.push('\x08');
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use crate::keys::Key;
+    use crate::result::Result;
+    use crate::terminal::{Options, Terminal};
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const PROMPT: &str = "\x1b[1;32mnode\x1b[0m $ ";
+
+    /// Minimal single-line screen emulation tracking the
+    /// visible line contents and the cursor column
+    struct Line {
+        cells: Vec<char>,
+        column: usize,
+    }
+
+    impl Line {
+        fn new(cols: usize) -> Self {
+            Line {
+                cells: vec![' '; cols],
+                column: 0,
+            }
+        }
+
+        fn feed(&mut self, output: &str) {
+            let mut chars = output.chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\x1b' => {
+                        chars.next_if_eq(&'[');
+                        let mut param = String::new();
+                        for c in chars.by_ref() {
+                            if ('@'..='~').contains(&c) {
+                                let n = param.parse::<usize>().unwrap_or(1);
+                                match c {
+                                    'K' => self.cells.fill(' '),
+                                    'G' => self.column = n - 1,
+                                    'C' => self.column += n,
+                                    'D' => self.column = self.column.saturating_sub(n),
+                                    _ => {}
+                                }
+                                break;
+                            }
+                            param.push(c);
+                        }
+                    }
+                    '\r' => self.column = 0,
+                    '\n' => self.cells.fill(' '),
+                    '\x08' => self.column = self.column.saturating_sub(1),
+                    c => {
+                        if self.column < self.cells.len() {
+                            self.cells[self.column] = c;
+                        }
+                        self.column += 1;
+                    }
+                }
+            }
+        }
+
+        fn text(&self) -> String {
+            self.cells.iter().collect::<String>().trim_end().to_string()
+        }
+    }
+
+    struct HeightCli {
+        height: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Cli for HeightCli {
+        async fn digest(self: Arc<Self>, _term: Arc<Terminal>, _cmd: String) -> Result<()> {
+            Ok(())
+        }
+        async fn complete(
+            self: Arc<Self>,
+            _term: Arc<Terminal>,
+            _cmd: String,
+        ) -> Result<Option<Vec<String>>> {
+            Ok(None)
+        }
+        fn prompt(&self) -> Option<String> {
+            None
+        }
+        async fn prompt_async(self: Arc<Self>, _term: Arc<Terminal>) -> Prompt {
+            let height = self.height.load(Ordering::SeqCst);
+            Prompt::new(PROMPT).with_right(format!("\x1b[2mheight {height}\x1b[0m"))
+        }
+    }
+
+    async fn headless(cols: usize) -> (Arc<HeightCli>, Arc<Terminal>) {
+        let cli = Arc::new(HeightCli {
+            height: AtomicUsize::new(42),
+        });
+        let term = Arc::new(
+            Terminal::try_new_with_options(cli.clone(), Options::new().with_headless(true))
+                .unwrap(),
+        );
+        term.para_width.store(cols, Ordering::SeqCst);
+        term.init().await.unwrap();
+        (cli, term)
+    }
+
+    #[test]
+    fn test_truncate_width() {
+        assert_eq!(truncate_width("abc", 3), "abc");
+        assert_eq!(truncate_width("abcdef", 3), "abc\x1b[0m");
+        assert_eq!(
+            truncate_width("\x1b[2mabcdef\x1b[0m", 2),
+            "\x1b[2mab\x1b[0m\x1b[0m"
+        );
+        assert_eq!(display_width(&truncate_width("\x1b[2mabcdef\x1b[0m", 2)), 2);
+    }
+
+    #[test]
+    fn test_render_line() {
+        let buffer = UnicodeString::from("ls -la");
+        let mut line = Line::new(30);
+        line.feed(&render_line(
+            PROMPT,
+            Some("\x1b[2m12:00\x1b[0m"),
+            &buffer,
+            2,
+            30,
+        ));
+        assert_eq!(line.text(), format!("node $ ls -la{}12:00", " ".repeat(12)));
+        assert_eq!(line.column, 7 + 2);
+
+        // the right prompt is truncated first and omitted when out of room
+        let mut line = Line::new(16);
+        line.feed(&render_line(PROMPT, Some("12:00"), &buffer, 6, 16));
+        assert_eq!(line.text(), "node $ ls -la 12");
+        assert_eq!(line.column, 7 + 6);
+        let mut line = Line::new(14);
+        line.feed(&render_line(PROMPT, Some("12:00"), &buffer, 6, 14));
+        assert_eq!(line.text(), "node $ ls -la");
+        assert_eq!(line.column, 7 + 6);
+    }
+
+    #[tokio::test]
+    async fn test_prompt_refresh() {
+        let (cli, term) = headless(40).await;
+        term.update_prompt().await;
+        term.prompt();
+        for key in [
+            Key::Char('a'),
+            Key::Char('b'),
+            Key::Char('c'),
+            Key::ArrowLeft,
+        ] {
+            term.ingest(key, String::new()).await.unwrap();
+        }
+
+        let mut line = Line::new(40);
+        line.feed(&term.headless_output().unwrap());
+        assert_eq!(
+            line.text(),
+            format!("node $ abc{}height 42", " ".repeat(21))
+        );
+        assert_eq!(line.column, 7 + 2);
+
+        // background update re-renders in place, keeping the typed input
+        cli.height.store(43, Ordering::SeqCst);
+        term.refresh_prompt().await;
+        let mut line = Line::new(40);
+        line.feed(&term.headless_output().unwrap());
+        assert_eq!(
+            line.text(),
+            format!("node $ abc{}height 43", " ".repeat(21))
+        );
+        assert_eq!(line.column, 7 + 2);
+
+        term.ingest(Key::Char('x'), String::new()).await.unwrap();
+        assert_eq!(term.inner().unwrap().buffer.to_string(), "abxc");
+    }
+}
@@ -0,0 +1,399 @@
+//!
+//! Output redirection of commands executed via [`Terminal::exec()`].
+//!
+//! The following trailing constructs are recognized in the command line
+//! (unless disabled via [`Options::with_redirection()`](crate::Options::with_redirection)):
+//!
+//! - `command > file` - write the output of the command to the file
+//! - `command >> file` - append the output of the command to the file
+//! - `command | grep [-i] [-v] pattern` - display only the lines containing
+//!   the pattern (`-i` ignores case, `-v` selects non-matching lines);
+//!   filters can be chained and followed by `> file` or `>> file`
+//!
+//! File names containing spaces can be quoted (`> "my file.txt"`), quoted
+//! `>` and `|` are treated as regular arguments. While the command is
+//! running, the output produced via [`Terminal::write()`] and
+//! [`Terminal::writeln()`] is captured instead of being displayed.
+//! The output is written as plain text (escape sequences are removed)
+//! using [`workflow_store::fs`], i.e. to a local storage key when running
+//! in the browser. Errors produced by the command are displayed.
+//!
+
+use crate::error::Error;
+use crate::result::Result;
+use crate::terminal::{tokenize, Terminal, Token};
+use regex::Regex;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Built-in `grep` filter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grep {
+    pub pattern: String,
+    pub ignore_case: bool,
+    pub invert: bool,
+}
+
+impl Grep {
+    /// Returns `true` if the line passes the filter
+    pub fn matches(&self, line: &str) -> bool {
+        let found = if self.ignore_case {
+            line.to_lowercase().contains(&self.pattern.to_lowercase())
+        } else {
+            line.contains(&self.pattern)
+        };
+        found != self.invert
+    }
+}
+
+/// Redirection target file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// `> file`
+    Truncate(PathBuf),
+    /// `>> file`
+    Append(PathBuf),
+}
+
+/// Output redirection parsed from the command line by [`split()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirection {
+    pub filters: Vec<Grep>,
+    pub target: Option<Target>,
+}
+
+impl Redirection {
+    /// Applies the filters to the captured output, removing
+    /// escape sequences and carriage returns.
+    pub fn apply(&self, output: &str) -> String {
+        let escapes = Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]").unwrap();
+        let output = escapes.replace_all(output, "").replace('\r', "");
+        if self.filters.is_empty() {
+            return output;
+        }
+        output
+            .lines()
+            .filter(|line| self.filters.iter().all(|filter| filter.matches(line)))
+            .map(|line| format!("{line}\n"))
+            .collect()
+    }
+
+    /// Delivers the captured output to the target file, or to
+    /// the terminal if the output is only filtered.
+    pub async fn deliver(&self, term: &Arc<Terminal>, output: &str) -> Result<()> {
+        let output = self.apply(output);
+        match &self.target {
+            None => {
+                output.lines().for_each(|line| term.writeln(line));
+            }
+            Some(Target::Truncate(path)) => {
+                workflow_store::fs::write_string(path, &output).await?;
+            }
+            Some(Target::Append(path)) => {
+                let text = if workflow_store::fs::exists(path).await? {
+                    workflow_store::fs::read_to_string(path).await? + &output
+                } else {
+                    output
+                };
+                workflow_store::fs::write_string(path, &text).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_operator(token: &Token) -> bool {
+    !token.quoted && (token.text == "|" || token.text.starts_with('>'))
+}
+
+/// Splits the command line into the command and the trailing
+/// [`Redirection`], if any. The command is returned verbatim
+/// (including its quotes).
+pub fn split(cmd: &str) -> Result<(&str, Option<Redirection>)> {
+    let tokens = tokenize(cmd);
+    let Some(position) = tokens.iter().position(is_operator) else {
+        return Ok((cmd, None));
+    };
+    if position == 0 {
+        return Err(Error::Redirection("missing command".to_string()));
+    }
+
+    let command = cmd[..tokens[position].start].trim_end();
+    let mut redirection = Redirection {
+        filters: Vec::new(),
+        target: None,
+    };
+    let mut tokens = tokens[position..].iter();
+    while let Some(token) = tokens.next() {
+        if redirection.target.is_some() {
+            return Err(Error::Redirection(format!(
+                "unexpected `{}` after the output file",
+                token.text
+            )));
+        }
+        if !is_operator(token) {
+            return Err(Error::Redirection(format!("unexpected `{}`", token.text)));
+        }
+
+        if token.text == "|" {
+            redirection.filters.push(grep(&mut tokens)?);
+        } else {
+            let (append, inline) = match token.text.strip_prefix(">>") {
+                Some(rest) => (true, rest),
+                None => (false, &token.text[1..]),
+            };
+            let path = if inline.is_empty() {
+                match tokens.next() {
+                    Some(token) if !is_operator(token) => token.text.as_str(),
+                    _ => {
+                        return Err(Error::Redirection(format!(
+                            "missing file name after `{}`",
+                            if append { ">>" } else { ">" }
+                        )))
+                    }
+                }
+            } else {
+                inline
+            };
+            let path = PathBuf::from(path);
+            redirection.target = Some(if append {
+                Target::Append(path)
+            } else {
+                Target::Truncate(path)
+            });
+        }
+    }
+
+    Ok((command, Some(redirection)))
+}
+
+fn grep<'a>(tokens: &mut impl Iterator<Item = &'a Token>) -> Result<Grep> {
+    match tokens.next() {
+        Some(token) if token.text == "grep" && !is_operator(token) => {}
+        Some(token) => {
+            return Err(Error::Redirection(format!(
+                "unsupported command `{}` (only `grep` is available)",
+                token.text
+            )))
+        }
+        None => return Err(Error::Redirection("missing command after `|`".to_string())),
+    }
+
+    let mut filter = Grep {
+        pattern: String::new(),
+        ignore_case: false,
+        invert: false,
+    };
+    loop {
+        let Some(token) = tokens.next().filter(|token| !is_operator(token)) else {
+            return Err(Error::Redirection("missing grep pattern".to_string()));
+        };
+        match token.text.as_str() {
+            "-i" if !token.quoted => filter.ignore_case = true,
+            "-v" if !token.quoted => filter.invert = true,
+            "-iv" | "-vi" if !token.quoted => {
+                filter.ignore_case = true;
+                filter.invert = true;
+            }
+            pattern => {
+                filter.pattern = pattern.to_string();
+                return Ok(filter);
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use crate::terminal::Options;
+    use async_trait::async_trait;
+
+    struct ListCli;
+
+    #[async_trait]
+    impl Cli for ListCli {
+        async fn digest(self: Arc<Self>, term: Arc<Terminal>, cmd: String) -> Result<()> {
+            let argv = crate::parse(&cmd);
+            match argv[0].as_str() {
+                "list" => {
+                    term.writeln("alpha");
+                    term.writeln(workflow_log::style("beta").red().to_string());
+                    term.write("gamma\n\r");
+                    Ok(())
+                }
+                "fail" => {
+                    term.writeln("partial");
+                    Err(Error::Custom("failed".to_string()))
+                }
+                "echo" => {
+                    term.writeln(argv[1..].join(" "));
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
+        }
+        async fn complete(
+            self: Arc<Self>,
+            _term: Arc<Terminal>,
+            _cmd: String,
+        ) -> Result<Option<Vec<String>>> {
+            Ok(None)
+        }
+        fn prompt(&self) -> Option<String> {
+            None
+        }
+    }
+
+    async fn headless() -> Arc<Terminal> {
+        let options = Options::new().with_headless(true);
+        let term = Arc::new(Terminal::try_new_with_options(Arc::new(ListCli), options).unwrap());
+        term.init().await.unwrap();
+        term
+    }
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "workflow terminal redirect {}",
+            workflow_core::id::Id::new()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_redirect_split() {
+        assert_eq!(split("list -a").unwrap(), ("list -a", None));
+        assert_eq!(split("echo \">\" '|'").unwrap(), ("echo \">\" '|'", None));
+
+        let (cmd, redirection) = split("echo \"a b\" >> \"my file.txt\"").unwrap();
+        assert_eq!(cmd, "echo \"a b\"");
+        assert_eq!(
+            redirection.unwrap(),
+            Redirection {
+                filters: vec![],
+                target: Some(Target::Append(PathBuf::from("my file.txt"))),
+            }
+        );
+
+        let (cmd, redirection) = split("list | grep -i A | grep -v '| x' >out.txt").unwrap();
+        assert_eq!(cmd, "list");
+        assert_eq!(
+            redirection.unwrap(),
+            Redirection {
+                filters: vec![
+                    Grep {
+                        pattern: "A".to_string(),
+                        ignore_case: true,
+                        invert: false,
+                    },
+                    Grep {
+                        pattern: "| x".to_string(),
+                        ignore_case: false,
+                        invert: true,
+                    },
+                ],
+                target: Some(Target::Truncate(PathBuf::from("out.txt"))),
+            }
+        );
+
+        let error = |cmd: &str| split(cmd).unwrap_err().to_string();
+        assert_eq!(error("> out.txt"), "redirection: missing command");
+        assert_eq!(error("list >"), "redirection: missing file name after `>`");
+        assert_eq!(
+            error("list >> | grep a"),
+            "redirection: missing file name after `>>`"
+        );
+        assert_eq!(
+            error("list > a b"),
+            "redirection: unexpected `b` after the output file"
+        );
+        assert_eq!(
+            error("list | sort"),
+            "redirection: unsupported command `sort` (only `grep` is available)"
+        );
+        assert_eq!(error("list | grep -i"), "redirection: missing grep pattern");
+    }
+
+    #[test]
+    fn test_parse_quotes() {
+        assert_eq!(crate::parse("  "), vec![""]);
+        assert_eq!(
+            crate::parse(r#" open  "my file.txt" 'it''s' "say \"hi\"" """#),
+            vec!["open", "my file.txt", "its", "say \"hi\"", ""]
+        );
+        assert_eq!(crate::parse(r"cd C:\Users\x"), vec!["cd", r"C:\Users\x"]);
+    }
+
+    #[tokio::test]
+    async fn test_redirect_output() {
+        let dir = temp_dir();
+        let file = dir.join("out file.txt");
+
+        // the screen output is the same as for a command producing no output
+        let reference = headless().await;
+        reference.exec("noop").await.unwrap();
+
+        let term = headless().await;
+        term.exec(format!("list > \"{}\"", file.display()))
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "alpha\nbeta\ngamma\n"
+        );
+        assert_eq!(term.headless_output(), reference.headless_output());
+
+        term.exec(format!("list | grep mm >> \"{}\"", file.display()))
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "alpha\nbeta\ngamma\ngamma\n"
+        );
+        assert_eq!(
+            term.headless_output(),
+            reference.headless_output().map(|output| output.repeat(2))
+        );
+
+        // the output is restored after the command fails
+        let term = headless().await;
+        let failed = dir.join("failed.txt");
+        term.exec(format!("fail > '{}'", failed.display()))
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&failed).unwrap(), "partial\n");
+        assert!(!term.is_capturing());
+        term.exec("echo done").await.unwrap();
+        let output = term.headless_output().unwrap();
+        assert!(output.contains("failed"));
+        assert!(!output.contains("partial"));
+        assert!(output.contains("done"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_redirect_grep() {
+        let term = headless().await;
+        term.exec("list | grep -v BETA | grep -i A").await.unwrap();
+        let output = term.headless_output().unwrap();
+        assert!(output.contains("alpha"));
+        assert!(output.contains("beta"));
+        assert!(output.contains("gamma"));
+
+        let term = headless().await;
+        term.exec("list | grep -iv BETA").await.unwrap();
+        let output = term.headless_output().unwrap();
+        assert!(output.contains("alpha"));
+        assert!(!output.contains("beta"));
+        assert!(output.contains("gamma"));
+
+        let options = Options::new().with_headless(true).with_redirection(false);
+        let term = Arc::new(Terminal::try_new_with_options(Arc::new(ListCli), options).unwrap());
+        term.exec("echo a > b").await.unwrap();
+        assert!(term.headless_output().unwrap().contains("a > b"));
+    }
+}
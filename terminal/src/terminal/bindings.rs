@@ -42,6 +42,47 @@ extern "C" {
     pub fn new(callback: JsValue) -> WebLinksAddon;
 }
 
+// WebGL and search addons are not bundled with the terminal
+// and are expected to be loaded by the application.
+#[wasm_bindgen]
+extern "C" {
+
+    #[wasm_bindgen(js_namespace=["window","WebglAddon"], js_name="WebglAddon")]
+    pub type WebglAddon;
+
+    #[wasm_bindgen(
+        catch,
+        constructor,
+        js_class = "window.WebglAddon.WebglAddon",
+        js_name = "WebglAddon"
+    )]
+    pub fn new() -> std::result::Result<WebglAddon, JsValue>;
+
+    #[wasm_bindgen(method, js_name = "onContextLoss")]
+    pub fn on_context_loss(this: &WebglAddon, callback: &js_sys::Function);
+
+    #[wasm_bindgen(method, js_name = "dispose")]
+    pub fn dispose(this: &WebglAddon);
+}
+
+#[wasm_bindgen]
+extern "C" {
+
+    #[wasm_bindgen(js_namespace=["window","SearchAddon"], js_name="SearchAddon")]
+    pub type SearchAddon;
+
+    #[wasm_bindgen(
+        catch,
+        constructor,
+        js_class = "window.SearchAddon.SearchAddon",
+        js_name = "SearchAddon"
+    )]
+    pub fn new() -> std::result::Result<SearchAddon, JsValue>;
+
+    #[wasm_bindgen(method, js_name = "findNext")]
+    pub fn find_next(this: &SearchAddon, term: &str) -> bool;
+}
+
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(extends = js_sys::Object)]
@@ -57,6 +98,22 @@ extern "C" {
     #[wasm_bindgen(method, getter, js_name = "key")]
     pub fn get_key(this: &XtermEvent) -> String;
 
+    #[wasm_bindgen(extends = js_sys::Object)]
+    pub type XtermBufferNamespace;
+    #[wasm_bindgen(method, getter)]
+    pub fn active(this: &XtermBufferNamespace) -> XtermBuffer;
+
+    #[wasm_bindgen(extends = js_sys::Object)]
+    pub type XtermBuffer;
+    #[wasm_bindgen(method, getter, js_name = "cursorX")]
+    pub fn cursor_x(this: &XtermBuffer) -> u32;
+    #[wasm_bindgen(method, getter, js_name = "cursorY")]
+    pub fn cursor_y(this: &XtermBuffer) -> u32;
+    #[wasm_bindgen(method, getter, js_name = "baseY")]
+    pub fn base_y(this: &XtermBuffer) -> u32;
+    #[wasm_bindgen(method, getter, js_name = "viewportY")]
+    pub fn viewport_y(this: &XtermBuffer) -> u32;
+
     #[wasm_bindgen(js_namespace=window, js_name="Terminal")]
     pub type XtermImpl;
 
@@ -93,6 +150,12 @@ extern "C" {
     #[wasm_bindgen(method, js_name = "onKey")]
     pub fn on_key(this: &XtermImpl, f: &js_sys::Function);
 
+    #[wasm_bindgen(method, js_name = "onData")]
+    pub fn on_data(this: &XtermImpl, f: &js_sys::Function);
+
+    #[wasm_bindgen(method, getter, js_name = "buffer")]
+    pub fn buffer(this: &XtermImpl) -> XtermBufferNamespace;
+
     #[wasm_bindgen(method, js_name = "write")]
     fn _write(this: &XtermImpl, text: String);
 
@@ -102,6 +165,9 @@ extern "C" {
     #[wasm_bindgen(method, js_name = "loadAddon")]
     pub fn load_addon(this: &XtermImpl, addon: JsValue);
 
+    #[wasm_bindgen(catch, method, js_name = "loadAddon")]
+    pub fn try_load_addon(this: &XtermImpl, addon: JsValue) -> std::result::Result<(), JsValue>;
+
     #[wasm_bindgen(method, getter, js_name = "element")]
     pub fn get_element(this: &XtermImpl) -> Element;
 
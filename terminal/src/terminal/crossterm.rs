@@ -1,4 +1,5 @@
 use crate::keys::Key;
+use crate::mouse::{MouseButton, MouseEvent, MouseEventKind, MouseModifiers, Screen};
 use crate::terminal::Options;
 use crate::terminal::Terminal;
 use crate::Result;
@@ -6,8 +7,9 @@ use crossterm::event::KeyEventKind;
 use crossterm::event::KeyModifiers;
 pub use crossterm::terminal::disable_raw_mode;
 use crossterm::{
-    event::{self, Event, KeyCode},
-    terminal,
+    cursor,
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    execute, terminal,
 };
 use std::io::{stdout, Stdout, Write};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -22,18 +24,20 @@ pub struct Crossterm {
     terminal: Arc<Mutex<Option<Arc<Terminal>>>>,
     terminate: Arc<AtomicBool>,
     stdout: Arc<Mutex<Option<Stdout>>>,
+    mouse: bool,
 }
 
 impl Crossterm {
     pub fn try_new() -> Result<Self> {
         Self::try_new_with_options(&Options::default())
     }
-    pub fn try_new_with_options(_options: &Options) -> Result<Self> {
+    pub fn try_new_with_options(options: &Options) -> Result<Self> {
         let crossterm = Crossterm {
             terminal: Arc::new(Mutex::new(None)),
             terminate: Arc::new(AtomicBool::new(false)),
             stdout: Arc::new(Mutex::new(Some(stdout()))),
             // stdout: Arc::new(Mutex::new(Some(stdout().into_raw_mode().unwrap()))),
+            mouse: options.mouse,
         };
         Ok(crossterm)
     }
@@ -52,11 +56,26 @@ impl Crossterm {
     }
 
     pub async fn run(&self) -> Result<()> {
+        // ensures the panic hook leaves the TTY usable
+        let mouse = self.mouse;
+        workflow_panic_hook::set_terminal_restore(move || {
+            if mouse {
+                execute!(stdout(), DisableMouseCapture).ok();
+            }
+            disable_raw_mode().ok();
+        });
         terminal::enable_raw_mode()?;
+        if self.mouse {
+            execute!(stdout(), EnableMouseCapture)?;
+        }
         self.flush();
-        self.intake(&self.terminate).await?;
+        let result = self.intake(&self.terminate).await;
+        if self.mouse {
+            execute!(stdout(), DisableMouseCapture)?;
+        }
         self.flush();
         terminal::disable_raw_mode()?;
+        result?;
 
         Ok(())
     }
@@ -65,7 +84,20 @@ impl Crossterm {
         loop {
             let event = event::read()?;
             // println!("{:?}",event);
-            if let Event::Key(key) = event {
+            if let Event::Mouse(event) = event {
+                let screen = cursor::position().ok().zip(terminal::size().ok()).map(
+                    |(cursor, (cols, _))| Screen {
+                        cursor,
+                        cols: cols as usize,
+                    },
+                );
+                self.terminal()
+                    .ingest_mouse(mouse_event(event), screen)
+                    .await?;
+                self.flush();
+            } else if let Event::Resize(cols, rows) = event {
+                self.terminal().on_resize(cols, rows);
+            } else if let Event::Key(key) = event {
                 if matches!(key.kind, KeyEventKind::Press | KeyEventKind::Repeat) {
                     let key = match key.code {
                         KeyCode::Char(c) => {
@@ -119,6 +151,34 @@ impl Crossterm {
     }
 }
 
+fn mouse_event(event: event::MouseEvent) -> MouseEvent {
+    let button = |button| match button {
+        event::MouseButton::Left => MouseButton::Left,
+        event::MouseButton::Middle => MouseButton::Middle,
+        event::MouseButton::Right => MouseButton::Right,
+    };
+    let kind = match event.kind {
+        event::MouseEventKind::Down(b) => MouseEventKind::Press(button(b)),
+        event::MouseEventKind::Up(b) => MouseEventKind::Release(button(b)),
+        event::MouseEventKind::Drag(b) => MouseEventKind::Drag(button(b)),
+        event::MouseEventKind::Moved => MouseEventKind::Move,
+        event::MouseEventKind::ScrollUp => MouseEventKind::ScrollUp,
+        event::MouseEventKind::ScrollDown => MouseEventKind::ScrollDown,
+        event::MouseEventKind::ScrollLeft => MouseEventKind::ScrollLeft,
+        event::MouseEventKind::ScrollRight => MouseEventKind::ScrollRight,
+    };
+    MouseEvent {
+        kind,
+        column: event.column,
+        row: event.row,
+        modifiers: MouseModifiers {
+            shift: event.modifiers.contains(KeyModifiers::SHIFT),
+            alt: event.modifiers.contains(KeyModifiers::ALT),
+            ctrl: event.modifiers.contains(KeyModifiers::CONTROL),
+        },
+    }
+}
+
 use std::{panic, process};
 
 /// configure custom panic hook that disables terminal raw mode
@@ -131,6 +191,8 @@ where
 {
     let default_hook = panic::take_hook();
     panic::set_hook(Box::new(move |panic_info| {
+        // no-op unless mouse reporting has been enabled
+        execute!(stdout(), DisableMouseCapture).ok();
         disable_raw_mode().ok();
         default_hook(panic_info);
         let exit_code = f();
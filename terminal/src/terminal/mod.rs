@@ -6,14 +6,20 @@ use crate::clear::*;
 use crate::cli::Cli;
 use crate::cursor::*;
 use crate::error::Error;
+use crate::jobs::Jobs;
 use crate::keys::Key;
+use crate::mouse::{
+    click_to_cursor, display_width, MouseButton, MouseEvent, MouseEventKind, Screen,
+};
+use crate::prompt::{render_line, Prompt};
+use crate::redirect;
 use crate::result::Result;
+use crate::transcript::TranscriptSink;
 use crate::CrLf;
 use crate::UnicodeString;
 use cfg_if::cfg_if;
 use futures::*;
 pub use pad::PadStr;
-use regex::Regex;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, LockResult, Mutex, MutexGuard};
 use workflow_core::channel::{unbounded, Channel, DuplexChannel, Receiver, Sender};
@@ -168,13 +174,17 @@ impl UserInput {
             // this is currently a workaround due to DOM
             // clipboard API using JsPromise.
             if #[cfg(target_arch = "wasm32")] {
-                workflow_core::task::dispatch(async move {
-                    let _result = term.term().intake(&terminate).await;
-                });
+                if !term.is_headless() {
+                    workflow_core::task::dispatch(async move {
+                        let _result = term.term().intake(&terminate).await;
+                    });
+                }
             } else {
-                workflow_core::task::spawn(async move {
-                    let _result = term.term().intake(&terminate).await;
-                });
+                if !term.is_headless() {
+                    workflow_core::task::spawn(async move {
+                        let _result = term.term().intake(&terminate).await;
+                    });
+                }
             }
         }
 
@@ -258,6 +268,13 @@ pub struct Terminal {
     pub pipe_crlf: Channel<String>,
     pub pipe_ctl: DuplexChannel<()>,
     pub para_width: Arc<AtomicUsize>,
+    pub(crate) jobs: Arc<Jobs>,
+    pub(crate) transcript: Option<Arc<TranscriptSink>>,
+    pub(crate) headless: Option<Arc<Mutex<String>>>,
+    /// Output captured by the commands being redirected (innermost last)
+    capture: Arc<Mutex<Vec<String>>>,
+    redirection: bool,
+    current_prompt: Arc<Mutex<Prompt>>,
 }
 
 impl Terminal {
@@ -265,6 +282,9 @@ impl Terminal {
     pub fn try_new(handler: Arc<dyn Cli>, prompt: &str) -> Result<Self> {
         let term = Arc::new(Interface::try_new()?);
 
+        let pipe_crlf = Channel::unbounded();
+        let jobs = Arc::new(Jobs::new(pipe_crlf.sender.clone()));
+
         let terminal = Self {
             inner: Arc::new(Mutex::new(Inner::new())),
             running: Arc::new(AtomicBool::new(false)),
@@ -274,9 +294,15 @@ impl Terminal {
             terminate: Arc::new(AtomicBool::new(false)),
             user_input: UserInput::new(),
             pipe_raw: Channel::unbounded(),
-            pipe_crlf: Channel::unbounded(),
+            pipe_crlf,
             pipe_ctl: DuplexChannel::oneshot(),
             para_width: Arc::new(AtomicUsize::new(DEFAULT_PARA_WIDTH)),
+            jobs,
+            transcript: None,
+            headless: None,
+            capture: Arc::new(Mutex::new(Vec::new())),
+            redirection: true,
+            current_prompt: Arc::new(Mutex::new(Prompt::default())),
         };
 
         Ok(terminal)
@@ -291,6 +317,9 @@ impl Terminal {
     ) -> Result<Self> {
         let term = Arc::new(Interface::try_new_with_options(&options)?);
 
+        let pipe_crlf = Channel::unbounded();
+        let jobs = Arc::new(Jobs::new(pipe_crlf.sender.clone()));
+
         let terminal = Self {
             inner: Arc::new(Mutex::new(Inner::new())),
             running: Arc::new(AtomicBool::new(false)),
@@ -300,9 +329,15 @@ impl Terminal {
             terminate: Arc::new(AtomicBool::new(false)),
             user_input: UserInput::new(),
             pipe_raw: Channel::unbounded(),
-            pipe_crlf: Channel::unbounded(),
+            pipe_crlf,
             pipe_ctl: DuplexChannel::oneshot(),
             para_width: Arc::new(AtomicUsize::new(DEFAULT_PARA_WIDTH)),
+            jobs,
+            transcript: options.transcript.clone(),
+            headless: options.headless.then(Default::default),
+            capture: Arc::new(Mutex::new(Vec::new())),
+            redirection: options.redirection,
+            current_prompt: Arc::new(Mutex::new(Prompt::default())),
         };
 
         Ok(terminal)
@@ -310,7 +345,9 @@ impl Terminal {
 
     /// Init the terminal instance
     pub async fn init(self: &Arc<Self>) -> Result<()> {
-        self.term.init(self).await?;
+        if !self.is_headless() {
+            self.term.init(self).await?;
+        }
 
         self.handler.clone().init(self)?;
 
@@ -334,24 +371,58 @@ impl Terminal {
 
     /// Get the current terminal prompt string
     pub fn get_prompt(&self) -> String {
-        if let Some(prompt) = self.handler.prompt() {
+        if let Some(prompt) = self.current_prompt.lock().unwrap().left.clone() {
+            prompt
+        } else if let Some(prompt) = self.handler.prompt() {
             prompt
         } else {
             self.prompt.lock().unwrap().clone()
         }
     }
 
+    /// Get the current right-aligned prompt string
+    pub fn get_rprompt(&self) -> Option<String> {
+        self.current_prompt
+            .lock()
+            .unwrap()
+            .right
+            .clone()
+            .or_else(|| self.handler.rprompt())
+    }
+
+    /// Evaluates [`Cli::prompt_async()`] and retains the result
+    /// for subsequent prompt renders
+    pub async fn update_prompt(self: &Arc<Self>) {
+        let prompt = self.handler.clone().prompt_async(self.clone()).await;
+        *self.current_prompt.lock().unwrap() = prompt;
+    }
+
     /// Render the current prompt in the terminal
     pub fn prompt(&self) {
         let mut data = self.inner().unwrap();
         data.cursor = 0;
         data.buffer.clear();
-        self.term().write(self.get_prompt());
+        self.render_line(&data);
+    }
+
+    /// Re-renders the prompt and the input line in place,
+    /// leaving the cursor at the current edit position
+    fn render_line(&self, data: &Inner) {
+        let cols = self
+            .cols()
+            .unwrap_or_else(|| self.para_width.load(Ordering::SeqCst));
+        self.write(render_line(
+            &self.get_prompt(),
+            self.get_rprompt().as_deref(),
+            &data.buffer,
+            data.cursor,
+            cols,
+        ));
     }
 
     /// Output CRLF sequence
     pub fn crlf(&self) {
-        self.term().write("\n\r".to_string());
+        self.write("\n\r".to_string());
     }
 
     /// Write a string
@@ -359,7 +430,54 @@ impl Terminal {
     where
         S: ToString,
     {
-        self.term().write(s);
+        let s = s.to_string();
+        if let Some(capture) = self.capture.lock().unwrap().last_mut() {
+            capture.push_str(&s);
+            return;
+        }
+        if let Some(transcript) = &self.transcript {
+            transcript.output(&s);
+        }
+        if let Some(headless) = &self.headless {
+            headless.lock().unwrap().push_str(&s);
+        } else {
+            self.term().write(s);
+        }
+    }
+
+    /// Indicates that the terminal was created with
+    /// [`Options::headless`] and does not produce any output
+    /// (see [`Terminal::headless_output()`])
+    pub fn is_headless(&self) -> bool {
+        self.headless.is_some()
+    }
+
+    /// Indicates that the output of the command being executed
+    /// is redirected (see [`redirect`](crate::redirect))
+    pub fn is_capturing(&self) -> bool {
+        !self.capture.lock().unwrap().is_empty()
+    }
+
+    pub(crate) fn begin_capture(&self) {
+        self.capture.lock().unwrap().push(String::new());
+    }
+
+    pub(crate) fn end_capture(&self) -> String {
+        self.capture.lock().unwrap().pop().unwrap_or_default()
+    }
+
+    /// Output accumulated by a headless terminal
+    pub fn headless_output(&self) -> Option<String> {
+        self.headless
+            .as_ref()
+            .map(|headless| headless.lock().unwrap().clone())
+    }
+
+    /// Called by the terminal backend when the terminal is resized
+    pub(crate) fn on_resize(&self, cols: u16, rows: u16) {
+        if let Some(transcript) = &self.transcript {
+            transcript.resize(cols, rows);
+        }
     }
 
     /// Write a string ending with CRLF sequence
@@ -367,7 +485,9 @@ impl Terminal {
     where
         S: ToString,
     {
-        if self.is_running() {
+        if self.is_capturing() {
+            self.write(format!("{}\n", s.to_string()));
+        } else if self.is_running() {
             if self.user_input.is_enabled() {
                 if let Some(prompt) = self.user_input.get_prompt() {
                     self.write(format!("{}{}\n\r", ClearLine, s.to_string()));
@@ -382,28 +502,20 @@ impl Terminal {
         } else {
             self.write(format!("{}{}\n\r", ClearLine, s.to_string()));
             let data = self.inner().unwrap();
-            let p = format!("{}{}", self.get_prompt(), data.buffer);
-            self.write(p);
-            let l = data.buffer.len() - data.cursor;
-            for _ in 0..l {
-                self.write("\x08".to_string());
-            }
+            self.render_line(&data);
         }
     }
 
-    /// Refreshes the prompt and the user input buffer. This function
-    /// is useful when the prompt is handled externally and contains
-    /// data that should be updated.
-    pub fn refresh_prompt(&self) {
-        if !self.is_running() {
-            self.write(format!("{}", ClearLine));
+    /// Re-evaluates the prompt via [`Cli::prompt_async()`] and re-renders
+    /// it in place together with the user input buffer. This function
+    /// can be called from background tasks (e.g. on a notification) when
+    /// the prompt contains data that should be updated; it has no effect
+    /// while a command is running or while the user is answering a question.
+    pub async fn refresh_prompt(self: &Arc<Self>) {
+        self.update_prompt().await;
+        if !self.is_running() && !self.user_input.is_enabled() {
             let data = self.inner().unwrap();
-            let p = format!("{}{}", self.get_prompt(), data.buffer);
-            self.write(p);
-            let l = data.buffer.len() - data.cursor;
-            for _ in 0..l {
-                self.write("\x08".to_string());
-            }
+            self.render_line(&data);
         }
     }
 
@@ -439,43 +551,50 @@ impl Terminal {
         list: &[(S, H)],
         separator: Option<&str>,
     ) -> Result<()> {
-        let mut list = list
-            .iter()
-            .map(|(cmd, help)| (cmd.to_string(), help.to_string()))
-            .collect::<Vec<_>>();
-        list.sort_by_key(|(cmd, _)| cmd.to_string());
-        let separator = separator.unwrap_or(" ");
-        let term_width: usize = self.cols().unwrap_or(80);
-        let cmd_width = list.iter().map(|(c, _)| c.len()).fold(0, |a, b| a.max(b)) + 2;
-        let help_width = term_width - cmd_width - 2 - 4 - separator.len();
-        let cmd_space = "".pad_to_width(cmd_width);
         self.writeln("");
-        for (cmd, help) in list {
-            let mut first = true;
-            let options =
-                textwrap::Options::new(help_width).line_ending(textwrap::LineEnding::CRLF);
-            textwrap::wrap(help.as_str(), options)
-                .into_iter()
-                .for_each(|line| {
-                    if first {
-                        self.writeln(format!(
-                            "{:>4}{}{}{}",
-                            "",
-                            cmd.pad_to_width(cmd_width),
-                            separator,
-                            line
-                        ));
-                        first = false;
-                    } else {
-                        self.writeln(format!("{:>4}{cmd_space}{}{}", "", separator, line));
-                    }
-                });
-        }
+        crate::help::render_list(list, separator, self.cols().unwrap_or(80))
+            .into_iter()
+            .for_each(|line| self.writeln(line));
         self.writeln("");
 
         Ok(())
     }
 
+    /// Writes `lines`, pausing with a `-- more --` prompt after each
+    /// screenful if the lines do not fit in the terminal; pressing `q`
+    /// at the prompt skips the remaining lines. Headless terminals and
+    /// terminals of unknown size output all lines at once.
+    pub async fn page<S: ToString>(self: &Arc<Terminal>, lines: &[S]) -> Result<()> {
+        let page = match self.rows() {
+            Some(rows)
+                if !self.is_headless()
+                    && !self.is_capturing()
+                    && rows > 1
+                    && lines.len() >= rows =>
+            {
+                rows - 1
+            }
+            _ => {
+                lines.iter().for_each(|line| self.writeln(line.to_string()));
+                return Ok(());
+            }
+        };
+
+        for (index, chunk) in lines.chunks(page).enumerate() {
+            if index > 0 {
+                let key = self.kbhit(Some("-- more -- (q to quit)")).await?;
+                // erase the prompt line
+                self.write(format!("{}{}", Up(1), ClearLine));
+                if key.eq_ignore_ascii_case("q") {
+                    break;
+                }
+            }
+            chunk.iter().for_each(|line| self.writeln(line.to_string()));
+        }
+
+        Ok(())
+    }
+
     /// Get a clone of Arc of the underlying terminal instance
     pub fn term(&self) -> Arc<Interface> {
         Arc::clone(&self.term)
@@ -526,6 +645,9 @@ impl Terminal {
         // self.prompt();
 
         self.pipe_start().await?;
+        if self.is_headless() {
+            return Ok(());
+        }
         self.term().run().await
     }
 
@@ -534,6 +656,12 @@ impl Terminal {
         self.terminate.store(true, Ordering::SeqCst);
         self.pipe_stop().await.unwrap_or_else(|err| panic!("{err}"));
         self.term.exit();
+        if let Some(transcript) = &self.transcript {
+            transcript
+                .flush()
+                .await
+                .unwrap_or_else(|err| log_error!("Error writing transcript: {err}"));
+        }
     }
 
     /// Exits the async terminal processing loop (sync fn)
@@ -548,16 +676,24 @@ impl Terminal {
     /// user input (useful for password entry)
     pub async fn ask(self: &Arc<Terminal>, secret: bool, prompt: &str) -> Result<String> {
         self.reset_line_buffer();
-        self.term().write(prompt.to_string());
-        self.user_input
+        self.write(prompt.to_string());
+        let line = self
+            .user_input
             .capture(secret, false, Some(prompt.to_string()), self)
-            .await
+            .await?;
+        // secret input is never recorded
+        if !secret {
+            if let Some(transcript) = &self.transcript {
+                transcript.line(&line);
+            }
+        }
+        Ok(line)
     }
 
     pub async fn kbhit(self: &Arc<Terminal>, prompt: Option<&str>) -> Result<String> {
         self.reset_line_buffer();
         if let Some(prompt) = prompt {
-            self.term().write(prompt.to_string());
+            self.write(prompt.to_string());
         }
         self.user_input
             .capture(true, true, prompt.map(String::from), self)
@@ -601,7 +737,14 @@ impl Terminal {
         Ok(())
     }
 
-    async fn ingest(self: &Arc<Terminal>, key: Key, _term_key: String) -> Result<()> {
+    pub(crate) async fn ingest(self: &Arc<Terminal>, key: Key, _term_key: String) -> Result<()> {
+        if let Some(transcript) = &self.transcript {
+            // secret input is never recorded
+            if !(self.user_input.is_enabled() && self.user_input.is_secret()) {
+                transcript.key(&key);
+            }
+        }
+
         if self.user_input.is_enabled() {
             self.user_input.ingest(key, self)?;
             return Ok(());
@@ -635,8 +778,8 @@ impl Terminal {
                 data.history_index -= 1;
 
                 data.buffer = data.history[data.history_index].clone();
-                self.write(format!("{}{}{}", ClearLine, self.get_prompt(), data.buffer));
                 data.cursor = data.buffer.len();
+                self.render_line(&data);
             }
             Key::ArrowDown => {
                 let mut data = self.inner()?;
@@ -653,8 +796,8 @@ impl Terminal {
                     data.buffer = data.history[data.history_index].clone();
                 }
 
-                self.write(format!("{}{}{}", ClearLine, self.get_prompt(), data.buffer));
                 data.cursor = data.buffer.len();
+                self.render_line(&data);
             }
             Key::ArrowLeft => {
                 let mut data = self.inner()?;
@@ -704,11 +847,16 @@ impl Terminal {
 
                 self.crlf();
 
+                if let (Some(transcript), Some(cmd)) = (&self.transcript, &cmd) {
+                    transcript.line(&cmd.to_string());
+                }
+
                 if let Some(cmd) = cmd {
                     self.running.store(true, Ordering::SeqCst);
                     self.exec(cmd).await.ok();
                     self.running.store(false, Ordering::SeqCst);
                 } else {
+                    self.update_prompt().await;
                     self.prompt();
                 }
             }
@@ -737,6 +885,52 @@ impl Terminal {
         Ok(())
     }
 
+    /// Handles a mouse event reported by the terminal backend; `screen`
+    /// is required to map clicks to the input line and may be omitted
+    /// if the screen cursor position is unavailable
+    async fn ingest_mouse(
+        self: &Arc<Terminal>,
+        event: MouseEvent,
+        screen: Option<Screen>,
+    ) -> Result<()> {
+        if let Some(delta) = event.scroll_delta() {
+            self.handler.clone().on_scroll(self, delta);
+            return Ok(());
+        }
+
+        if event.kind != MouseEventKind::Press(MouseButton::Left)
+            || self.is_running()
+            || self.user_input.is_enabled()
+        {
+            return Ok(());
+        }
+
+        let Some(screen) = screen else {
+            return Ok(());
+        };
+
+        let prompt_width = display_width(&self.get_prompt());
+        let mut data = self.inner()?;
+        let position = click_to_cursor(
+            (event.column, event.row),
+            screen.cursor,
+            prompt_width,
+            data.cursor,
+            data.buffer.len(),
+            screen.cols,
+        );
+        if let Some(position) = position {
+            let start_row = screen.cursor.1 as usize - (prompt_width + data.cursor) / screen.cols;
+            let offset = prompt_width + position;
+            let row = start_row + offset / screen.cols;
+            let col = offset % screen.cols;
+            self.write(format!("\x1b[{};{}H", row + 1, col + 1));
+            data.cursor = position;
+        }
+
+        Ok(())
+    }
+
     fn trail(
         &self,
         cursor: usize,
@@ -771,18 +965,45 @@ impl Terminal {
         self.running.load(Ordering::SeqCst)
     }
 
+    /// Executes the command via [`Cli::digest()`]. Unless disabled via
+    /// [`Options::with_redirection()`], the output of the command can be
+    /// redirected using trailing `> file`, `>> file` or `| grep pattern`
+    /// (see [`redirect`](crate::redirect)).
     pub async fn exec<S: ToString>(self: &Arc<Terminal>, cmd: S) -> Result<()> {
-        if let Err(err) = self
-            .handler
-            .clone()
-            .digest(self.clone(), cmd.to_string())
-            .await
-        {
-            self.writeln(err);
+        let cmd = cmd.to_string();
+        let split = if self.redirection {
+            redirect::split(&cmd)
+        } else {
+            Ok((cmd.as_str(), None))
+        };
+        let result = match split {
+            Ok((cmd, Some(redirection))) => {
+                self.begin_capture();
+                let result = self
+                    .handler
+                    .clone()
+                    .digest(self.clone(), cmd.to_string())
+                    .await;
+                let output = self.end_capture();
+                // partial output is delivered even if the command fails
+                let delivered = redirection.deliver(self, &output).await;
+                result.and(delivered)
+            }
+            Ok((cmd, None)) => {
+                self.handler
+                    .clone()
+                    .digest(self.clone(), cmd.to_string())
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            self.writeln(err.to_string().crlf());
         }
         if self.terminate.load(Ordering::SeqCst) {
             self.term().exit();
         } else {
+            self.update_prompt().await;
             self.prompt();
         }
         Ok(())
@@ -832,6 +1053,17 @@ impl Terminal {
         self.term.cols()
     }
 
+    pub fn rows(&self) -> Option<usize> {
+        self.term.rows()
+    }
+
+    /// Width used for wrapping text: the terminal width if
+    /// available, otherwise [`Terminal::para_width`]
+    pub fn width(&self) -> usize {
+        self.cols()
+            .unwrap_or_else(|| self.para_width.load(Ordering::SeqCst))
+    }
+
     pub async fn select<T>(self: &Arc<Terminal>, prompt: &str, list: &[T]) -> Result<Option<T>>
     where
         T: std::fmt::Display + Clone, // + IdT + Clone + Send + Sync + 'static,
@@ -870,6 +1102,19 @@ impl Terminal {
         }
     }
 
+    /// Searches the terminal buffer for the next occurrence of `pattern`,
+    /// scrolling to and highlighting the match. Returns `false` if no match
+    /// is found. Requires [`Options::search`] (xterm.js only).
+    pub fn find(&self, _pattern: &str) -> Result<bool> {
+        cfg_if! {
+            if #[cfg(target_arch = "wasm32")] {
+                self.term.find(_pattern)
+            } else {
+                Ok(false)
+            }
+        }
+    }
+
     pub fn register_event_handler(self: &Arc<Self>, _handler: EventHandlerFn) -> Result<()> {
         #[cfg(target_arch = "wasm32")]
         self.term.register_event_handler(_handler)?;
@@ -890,9 +1135,66 @@ impl Terminal {
     }
 }
 
-/// Utility function to strip multiple white spaces and return a `Vec<String>`
+/// Command line token produced by [`tokenize()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Token {
+    /// Token text with the quotes removed
+    pub text: String,
+    /// Indicates that the token (or a part of it) was quoted
+    pub quoted: bool,
+    /// Byte offset of the token in the command line
+    pub start: usize,
+}
+
+/// Splits the command line into whitespace-separated tokens. Text enclosed
+/// in single or double quotes is retained as a part of the token (quotes
+/// are removed); within double quotes, `\"` and `\\` are unescaped.
+/// An unterminated quote extends to the end of the line.
+pub(crate) fn tokenize(s: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current: Option<Token> = None;
+    let mut quote = None;
+    let mut chars = s.char_indices().peekable();
+    while let Some((index, ch)) = chars.next() {
+        match quote {
+            Some(q) if ch == q => quote = None,
+            Some('"') if ch == '\\' && matches!(chars.peek(), Some((_, '"' | '\\'))) => {
+                let (_, escaped) = chars.next().unwrap();
+                current.as_mut().unwrap().text.push(escaped);
+            }
+            Some(_) => current.as_mut().unwrap().text.push(ch),
+            None if ch.is_whitespace() => tokens.extend(current.take()),
+            None => {
+                let token = current.get_or_insert_with(|| Token {
+                    text: String::new(),
+                    quoted: false,
+                    start: index,
+                });
+                if ch == '"' || ch == '\'' {
+                    token.quoted = true;
+                    quote = Some(ch);
+                } else {
+                    token.text.push(ch);
+                }
+            }
+        }
+    }
+    tokens.extend(current);
+    tokens
+}
+
+/// Utility function splitting the command line into whitespace-separated
+/// arguments and returning a `Vec<String>`. Arguments containing spaces
+/// can be enclosed in single or double quotes (`"my file.txt"`). The
+/// resulting list contains a single empty string if the command line is empty.
 pub fn parse(s: &str) -> Vec<String> {
-    let regex = Regex::new(r"\s+").unwrap();
-    let s = regex.replace_all(s.trim(), " ");
-    s.split(' ').map(|s| s.to_string()).collect::<Vec<String>>()
+    let argv = tokenize(s)
+        .into_iter()
+        .map(|token| token.text)
+        .collect::<Vec<String>>();
+    if argv.is_empty() {
+        vec![String::new()]
+    } else {
+        argv
+    }
 }
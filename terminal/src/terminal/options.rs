@@ -2,6 +2,9 @@
 //! Terminal creation options
 //!
 
+use super::LinkMatcherHandlerFn;
+use crate::transcript::TranscriptSink;
+use std::sync::Arc;
 use web_sys::Element;
 
 /// Indicates the target element to which the Terminal instance should be
@@ -32,6 +35,33 @@ pub struct Options {
     pub font_size: Option<f64>,
     /// Default scrollback limit (xterm.js only)
     pub scrollback: Option<u32>,
+    /// Fit the terminal to the parent element (xterm.js only)
+    pub fit_addon: bool,
+    /// Render URLs as clickable links (xterm.js only)
+    pub web_links: bool,
+    /// Link click handler; links are opened in a new window
+    /// if not supplied (xterm.js only)
+    pub link_handler: Option<LinkMatcherHandlerFn>,
+    /// Use the WebGL renderer, falling back to the canvas renderer
+    /// if the WebGL context can not be created (xterm.js only,
+    /// requires the `xterm-addon-webgl` script to be loaded)
+    pub webgl: bool,
+    /// Enable [`Terminal::find()`](super::Terminal::find) (xterm.js only,
+    /// requires the `xterm-addon-search` script to be loaded)
+    pub search: bool,
+    /// Enable mouse reporting: clicks within the input line move the
+    /// edit cursor, scroll wheel events are delivered to
+    /// [`Cli::on_scroll()`](crate::Cli::on_scroll)
+    pub mouse: bool,
+    /// Record the session into the supplied [`TranscriptSink`]
+    pub transcript: Option<Arc<TranscriptSink>>,
+    /// Do not bind to the underlying terminal; the output is accumulated
+    /// in memory and is available via
+    /// [`Terminal::headless_output()`](super::Terminal::headless_output)
+    pub headless: bool,
+    /// Recognize output redirection (`> file`, `>> file` and `| grep pattern`)
+    /// in commands executed via [`Terminal::exec()`](super::Terminal::exec)
+    pub redirection: bool,
 }
 
 impl Default for Options {
@@ -43,6 +73,15 @@ impl Default for Options {
             font_family: None,
             font_size: None,
             scrollback: Some(2048),
+            fit_addon: true,
+            web_links: false,
+            link_handler: None,
+            webgl: false,
+            search: false,
+            mouse: false,
+            transcript: None,
+            headless: false,
+            redirection: true,
         }
     }
 }
@@ -71,6 +110,61 @@ impl Options {
         self
     }
 
+    /// Enable or disable the fit addon
+    pub fn with_fit_addon(mut self, fit_addon: bool) -> Self {
+        self.fit_addon = fit_addon;
+        self
+    }
+
+    /// Enable or disable clickable links
+    pub fn with_web_links(mut self, web_links: bool) -> Self {
+        self.web_links = web_links;
+        self
+    }
+
+    /// Enable clickable links routed to the supplied handler
+    pub fn with_link_handler(mut self, handler: LinkMatcherHandlerFn) -> Self {
+        self.web_links = true;
+        self.link_handler = Some(handler);
+        self
+    }
+
+    /// Enable or disable the WebGL renderer
+    pub fn with_webgl(mut self, webgl: bool) -> Self {
+        self.webgl = webgl;
+        self
+    }
+
+    /// Enable or disable search
+    pub fn with_search(mut self, search: bool) -> Self {
+        self.search = search;
+        self
+    }
+
+    /// Enable or disable mouse support
+    pub fn with_mouse(mut self, mouse: bool) -> Self {
+        self.mouse = mouse;
+        self
+    }
+
+    /// Record the session transcript into the supplied sink
+    pub fn with_transcript(mut self, sink: Arc<TranscriptSink>) -> Self {
+        self.transcript = Some(sink);
+        self
+    }
+
+    /// Enable or disable headless mode
+    pub fn with_headless(mut self, headless: bool) -> Self {
+        self.headless = headless;
+        self
+    }
+
+    /// Enable or disable output redirection
+    pub fn with_redirection(mut self, redirection: bool) -> Self {
+        self.redirection = redirection;
+        self
+    }
+
     /// Get prompt string
     pub fn prompt(&self) -> String {
         self.prompt.as_ref().unwrap_or(&"$ ".to_string()).clone()
@@ -1,11 +1,15 @@
 use crate::keys::Key;
+use crate::mouse::{
+    MouseButton, MouseEvent, MouseEventKind, Screen, Sequence, SgrParser, DISABLE_MOUSE,
+    ENABLE_MOUSE, REQUEST_CURSOR_POSITION,
+};
 use crate::terminal::Options;
 use crate::terminal::Terminal;
 use crate::Result;
 use std::io::{stdin, stdout, Stdout, Write};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use termion::event::Key as K;
+use termion::event::{Event, Key as K};
 use termion::input::TermRead;
 use termion::raw::{IntoRawMode, RawTerminal};
 
@@ -18,17 +22,24 @@ pub struct Termion {
     terminal: Arc<Mutex<Option<Arc<Terminal>>>>,
     terminate: Arc<AtomicBool>,
     stdout: Arc<Mutex<Option<RawTerminal<Stdout>>>>,
+    mouse: bool,
+    parser: Mutex<SgrParser>,
+    /// Click awaiting the reply to the cursor position request
+    pending_click: Mutex<Option<MouseEvent>>,
 }
 
 impl Termion {
     pub fn try_new() -> Result<Self> {
         Self::try_new_with_options(&Options::default())
     }
-    pub fn try_new_with_options(_options: &Options) -> Result<Self> {
+    pub fn try_new_with_options(options: &Options) -> Result<Self> {
         let termion = Termion {
             terminal: Arc::new(Mutex::new(None)),
             terminate: Arc::new(AtomicBool::new(false)),
             stdout: Arc::new(Mutex::new(Some(stdout().into_raw_mode().unwrap()))),
+            mouse: options.mouse,
+            parser: Mutex::new(SgrParser::new()),
+            pending_click: Mutex::new(None),
         };
         Ok(termion)
     }
@@ -47,8 +58,19 @@ impl Termion {
     }
 
     pub async fn run(&self) -> Result<()> {
+        if self.mouse {
+            // ensures the panic hook leaves the TTY usable
+            workflow_panic_hook::set_terminal_restore(|| {
+                print!("{DISABLE_MOUSE}");
+                stdout().flush().ok();
+            });
+            print!("{ENABLE_MOUSE}");
+        }
         self.flush();
-        self.intake(&self.terminate).await?;
+        let result = self.intake(&self.terminate).await;
+        if self.mouse {
+            print!("{DISABLE_MOUSE}");
+        }
         self.flush();
         self.stdout
             .lock()
@@ -58,13 +80,21 @@ impl Termion {
             .suspend_raw_mode()
             .unwrap();
         *self.stdout.lock().unwrap() = None;
-        Ok(())
+        result
     }
 
     pub async fn intake(&self, terminate: &Arc<AtomicBool>) -> Result<()> {
         let stdin = stdin();
-        for c in stdin.keys() {
-            let key = match c.unwrap() {
+        for event in stdin.events_and_raw() {
+            let (event, raw) = event?;
+            let key = match event {
+                Event::Key(key) => key,
+                Event::Mouse(_) | Event::Unsupported(_) => {
+                    self.ingest_raw(&raw).await?;
+                    continue;
+                }
+            };
+            let key = match key {
                 // K::Char('q') => break,
                 K::Char(c) => {
                     if c == '\n' || c == '\r' {
@@ -97,6 +127,39 @@ impl Termion {
         Ok(())
     }
 
+    /// Handles mouse reports and cursor position replies
+    async fn ingest_raw(&self, raw: &[u8]) -> Result<()> {
+        let sequences = self.parser.lock().unwrap().feed_all(raw);
+        for sequence in sequences {
+            match sequence {
+                Sequence::Mouse(event)
+                    if event.kind == MouseEventKind::Press(MouseButton::Left) =>
+                {
+                    // the click is processed once the terminal
+                    // reports the cursor position
+                    *self.pending_click.lock().unwrap() = Some(event);
+                    self.write(REQUEST_CURSOR_POSITION);
+                }
+                Sequence::Mouse(event) => {
+                    self.terminal().ingest_mouse(event, None).await?;
+                }
+                Sequence::CursorPosition { column, row } => {
+                    let click = self.pending_click.lock().unwrap().take();
+                    if let Some(event) = click {
+                        let screen = termion::terminal_size().ok().map(|(cols, _)| Screen {
+                            cursor: (column, row),
+                            cols: cols as usize,
+                        });
+                        self.terminal().ingest_mouse(event, screen).await?;
+                    }
+                }
+                Sequence::Unhandled(_) => {}
+            }
+        }
+        self.flush();
+        Ok(())
+    }
+
     pub fn write<S>(&self, s: S)
     where
         S: Into<String>,
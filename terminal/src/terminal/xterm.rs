@@ -1,6 +1,7 @@
 use super::bindings::*;
 use super::{LinkMatcherHandlerFn, Modifiers};
 use crate::keys::Key;
+use crate::mouse::{MouseEvent, Screen, Sequence, SgrParser, DISABLE_MOUSE, ENABLE_MOUSE};
 use crate::terminal::Event;
 use crate::terminal::EventHandlerFn;
 use crate::terminal::Options;
@@ -98,6 +99,7 @@ enum Ctl {
     SinkEvent(SinkEvent),
     Copy(Option<String>),
     Paste(Option<String>),
+    Mouse(MouseEvent),
     Close,
 }
 
@@ -154,6 +156,35 @@ pub struct XtermOptions {
     pub scrollback: Option<u32>,
 }
 
+pub struct AddonOptions {
+    pub fit: bool,
+    pub web_links: bool,
+    pub link_handler: Option<LinkMatcherHandlerFn>,
+    pub webgl: bool,
+    pub search: bool,
+}
+
+type LinkCallback =
+    Callback<dyn FnMut(web_sys::MouseEvent, String) -> std::result::Result<(), JsValue>>;
+
+/// Creates a link click callback relaying the click to the `handler`.
+fn link_callback(handler: LinkMatcherHandlerFn) -> LinkCallback {
+    #[rustfmt::skip]
+    let callback = callback!(
+        move |e: web_sys::MouseEvent, link: String| -> std::result::Result<(), JsValue> {
+            let modifiers = Modifiers {
+                shift: e.shift_key(),
+                ctrl: e.ctrl_key(),
+                alt: e.alt_key(),
+                meta: e.meta_key(),
+            };
+            handler(modifiers, link.as_str());
+            Ok(())
+        }
+    );
+    callback
+}
+
 ///
 /// # Xterm
 ///
@@ -170,12 +201,16 @@ pub struct Xterm {
     sink: Arc<Sink>,
     resize: Rc<RefCell<Option<ResizeObserverInfo>>>,
     fit: Rc<RefCell<Option<FitAddon>>>,
-    _web_links: Rc<RefCell<Option<WebLinksAddon>>>,
+    web_links: Rc<RefCell<Option<WebLinksAddon>>>,
+    webgl: Rc<RefCell<Option<WebglAddon>>>,
+    search: Rc<RefCell<Option<SearchAddon>>>,
     terminate: Arc<AtomicBool>,
     disable_clipboard_handling: bool,
     callbacks: CallbackMap,
     defaults: XtermOptions,
+    addons: AddonOptions,
     event_handler: Rc<RefCell<Option<EventHandlerFn>>>,
+    mouse: bool,
 }
 
 unsafe impl Send for Xterm {}
@@ -210,6 +245,13 @@ impl Xterm {
             font_family: options.font_family.clone(),
             scrollback: options.scrollback,
         };
+        let addons = AddonOptions {
+            fit: options.fit_addon,
+            web_links: options.web_links,
+            link_handler: options.link_handler.clone(),
+            webgl: options.webgl,
+            search: options.search,
+        };
         let terminal = Xterm {
             element,
             listener: Arc::new(Mutex::new(None)),
@@ -219,12 +261,16 @@ impl Xterm {
             resize: Rc::new(RefCell::new(None)),
             // addons: Arc::new(Mutex::new(Vec::new())),
             fit: Rc::new(RefCell::new(None)),
-            _web_links: Rc::new(RefCell::new(None)),
+            web_links: Rc::new(RefCell::new(None)),
+            webgl: Rc::new(RefCell::new(None)),
+            search: Rc::new(RefCell::new(None)),
             terminate: Arc::new(AtomicBool::new(false)),
             disable_clipboard_handling: options.disable_clipboard_handling,
             callbacks: CallbackMap::default(),
             event_handler: Rc::new(RefCell::new(None)),
+            mouse: options.mouse,
             defaults,
+            addons,
         };
         Ok(terminal)
     }
@@ -336,10 +382,79 @@ impl Xterm {
         Ok(())
     }
 
+    /// Loads addons enabled in [`Options`]. Addons that fail
+    /// to load are skipped with a warning.
     fn init_addons(&self, xterm: &XtermImpl) -> Result<()> {
-        let fit = FitAddon::new();
-        xterm.load_addon(fit.clone());
-        *self.fit.borrow_mut() = Some(fit);
+        if self.addons.fit {
+            let fit = FitAddon::new();
+            match xterm.try_load_addon(fit.clone().into()) {
+                Ok(()) => *self.fit.borrow_mut() = Some(fit),
+                Err(err) => log_warn!("xterm: unable to load fit addon: {:?}", err),
+            }
+        }
+
+        if self.addons.web_links {
+            let web_links = if let Some(handler) = self.addons.link_handler.clone() {
+                let callback = link_callback(handler);
+                let web_links = WebLinksAddon::new(callback.get_fn().clone().into());
+                self.callbacks.retain(callback)?;
+                web_links
+            } else {
+                WebLinksAddon::new(JsValue::UNDEFINED)
+            };
+            match xterm.try_load_addon(web_links.clone().into()) {
+                Ok(()) => *self.web_links.borrow_mut() = Some(web_links),
+                Err(err) => log_warn!("xterm: unable to load web links addon: {:?}", err),
+            }
+        }
+
+        if self.addons.search {
+            match SearchAddon::new().and_then(|search| {
+                xterm.try_load_addon(search.clone().into())?;
+                Ok(search)
+            }) {
+                Ok(search) => *self.search.borrow_mut() = Some(search),
+                Err(err) => log_warn!("xterm: unable to load search addon: {:?}", err),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Loads the WebGL renderer if enabled in [`Options`]. The terminal
+    /// retains the canvas renderer if the WebGL context can not be
+    /// created and reverts to it if the WebGL context is lost.
+    fn init_renderer(self: &Arc<Self>, xterm: &XtermImpl) -> Result<()> {
+        if !self.addons.webgl {
+            return Ok(());
+        }
+
+        let webgl = match WebglAddon::new().and_then(|webgl| {
+            xterm.try_load_addon(webgl.clone().into())?;
+            Ok(webgl)
+        }) {
+            Ok(webgl) => webgl,
+            Err(err) => {
+                log_warn!(
+                    "xterm: WebGL renderer is not available, using canvas: {:?}",
+                    err
+                );
+                return Ok(());
+            }
+        };
+
+        let this = self.clone();
+        let callback = callback!(move |_: JsValue| -> std::result::Result<(), JsValue> {
+            log_warn!("xterm: WebGL context lost, reverting to canvas renderer");
+            if let Some(webgl) = this.webgl.borrow_mut().take() {
+                webgl.dispose();
+            }
+            Ok(())
+        });
+        webgl.on_context_loss(callback.as_ref());
+        self.callbacks.retain(callback)?;
+        *self.webgl.borrow_mut() = Some(webgl);
+
         Ok(())
     }
 
@@ -351,9 +466,13 @@ impl Xterm {
         self.init_addons(&xterm)?;
 
         xterm.open(&self.element);
+        self.init_renderer(&xterm)?;
         xterm.focus();
 
         self.init_kbd_listener(&xterm)?;
+        if self.mouse {
+            self.init_mouse_listener(&xterm)?;
+        }
         self.init_resize_observer()?;
         if runtime::is_macos() && !self.disable_clipboard_handling {
             self.init_clipboard_listener_for_macos(&xterm)?;
@@ -403,25 +522,23 @@ impl Xterm {
         let xterm = self.xterm();
         let xterm = xterm.as_ref().expect("unable to get xterm");
 
-        #[rustfmt::skip]
-        let callback = callback!(
-            move |e: web_sys::MouseEvent, link: String| -> std::result::Result<(), JsValue> {
-                let modifiers = Modifiers {
-                    shift: e.shift_key(),
-                    ctrl: e.ctrl_key(),
-                    alt: e.alt_key(),
-                    meta: e.meta_key(),
-                };
-                handler(modifiers, link.as_str());
-                Ok(())
-            }
-        );
+        let callback = link_callback(handler);
         xterm.register_link_matcher(regexp, callback.as_ref());
         self.callbacks.retain(callback)?;
 
         Ok(())
     }
 
+    /// Searches for the next occurrence of `pattern`, scrolling to
+    /// and selecting the match. Returns `false` if no match is found.
+    pub fn find(&self, pattern: &str) -> Result<bool> {
+        let search = self.search.borrow();
+        let search = search
+            .as_ref()
+            .ok_or("xterm: search addon is not enabled")?;
+        Ok(search.find_next(pattern))
+    }
+
     pub fn paste(&self, text: Option<String>) -> Result<()> {
         self.sink
             .sender
@@ -521,13 +638,40 @@ impl Xterm {
         Ok(())
     }
 
+    /// Enables mouse reporting; xterm.js delivers mouse
+    /// reports as SGR sequences via `onData`
+    fn init_mouse_listener(self: &Arc<Self>, xterm: &XtermImpl) -> Result<()> {
+        let this = self.clone();
+        let mut parser = SgrParser::new();
+        let callback = callback!(move |data: String| -> std::result::Result<(), JsValue> {
+            for sequence in parser.feed_all(data.as_bytes()) {
+                if let Sequence::Mouse(event) = sequence {
+                    this.sink
+                        .sender
+                        .try_send(Ctl::Mouse(event))
+                        .expect("Unable to send mouse Ctl");
+                }
+            }
+            Ok(())
+        });
+
+        xterm.on_data(callback.as_ref());
+        self.callbacks.retain(callback)?;
+        xterm.write(ENABLE_MOUSE);
+
+        Ok(())
+    }
+
     pub fn terminal(&self) -> Arc<Terminal> {
         self.terminal.lock().unwrap().as_ref().unwrap().clone()
     }
 
     pub async fn run(self: &Arc<Self>) -> Result<()> {
-        self.intake(&self.terminate).await?;
-        Ok(())
+        let result = self.intake(&self.terminate).await;
+        if self.mouse {
+            self.write(DISABLE_MOUSE);
+        }
+        result
     }
 
     pub async fn intake(self: &Arc<Self>, terminate: &Arc<AtomicBool>) -> Result<()> {
@@ -548,7 +692,7 @@ impl Xterm {
                         let clipboard = nw_sys::clipboard::get();
                         clipboard.set(&text);
                     } else if let Err(err) = clipboard::write_text(&text).await {
-                        log_error!("{}", JsErrorData::from(err));
+                        log_error!("{}", err);
                     }
 
                     if let Some(handler) = self.event_handler() {
@@ -565,9 +709,14 @@ impl Xterm {
                             self.terminal().inject(text)?;
                         }
                     } else {
-                        let data_js_value = clipboard::read_text().await;
-                        if let Some(text) = data_js_value.as_string() {
-                            self.terminal().inject(text)?;
+                        match clipboard::read_text().await {
+                            Ok(text) if !text.is_empty() => {
+                                self.terminal().inject(text)?;
+                            }
+                            Ok(_) => {}
+                            Err(err) => {
+                                log_error!("{}", err);
+                            }
                         }
                     }
 
@@ -575,6 +724,20 @@ impl Xterm {
                         handler(Event::Copy);
                     }
                 }
+                Ctl::Mouse(event) => {
+                    // mouse reports are relative to the viewport while the
+                    // cursor is relative to the bottom page of the buffer
+                    let screen = self.xterm().as_ref().and_then(|xterm| {
+                        let buffer = xterm.buffer().active();
+                        let row = (buffer.cursor_y() + buffer.base_y())
+                            .checked_sub(buffer.viewport_y())?;
+                        Some(Screen {
+                            cursor: (buffer.cursor_x() as u16, row as u16),
+                            cols: xterm.cols() as usize,
+                        })
+                    });
+                    self.terminal().ingest_mouse(event, screen).await?;
+                }
                 Ctl::Close => {
                     break;
                 }
@@ -669,12 +832,18 @@ impl Xterm {
             return Ok(());
         }
 
-        let fit = self.fit.borrow();
-        let fit = fit.as_ref().unwrap();
-        // TODO review if this is correct
-        //fit.propose_dimensions();
-        // TODO review if this is correct
-        fit.fit();
+        if let Some(fit) = self.fit.borrow().as_ref() {
+            // TODO review if this is correct
+            //fit.propose_dimensions();
+            // TODO review if this is correct
+            fit.fit();
+        }
+
+        if let Some((cols, rows)) = self.cols().zip(self.rows()) {
+            if let Some(terminal) = self.terminal.lock().unwrap().as_ref() {
+                terminal.on_resize(cols as u16, rows as u16);
+            }
+        }
 
         Ok(())
     }
@@ -766,3 +935,43 @@ pub async fn load_scripts() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_link_callback() {
+        let clicks = Arc::new(Mutex::new(Vec::new()));
+        let handler: LinkMatcherHandlerFn = {
+            let clicks = clicks.clone();
+            Arc::new(Box::new(move |modifiers: Modifiers, link: &str| {
+                clicks
+                    .lock()
+                    .unwrap()
+                    .push((modifiers.ctrl, modifiers.shift, link.to_string()));
+            }))
+        };
+        let callback = link_callback(handler);
+
+        // invoke the callback the way WebLinksAddon does on link activation
+        let init = web_sys::MouseEventInit::new();
+        init.set_ctrl_key(true);
+        let click = web_sys::MouseEvent::new_with_mouse_event_init_dict("click", &init).unwrap();
+        let f: &js_sys::Function = callback.as_ref();
+        f.call2(
+            &JsValue::NULL,
+            &click,
+            &JsValue::from("https://example.com"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            clicks.lock().unwrap().as_slice(),
+            &[(true, false, "https://example.com".to_string())]
+        );
+    }
+}
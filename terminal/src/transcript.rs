@@ -0,0 +1,402 @@
+//!
+//! Session transcript recording and replay.
+//!
+//! A [`TranscriptSink`] supplied via [`Options::with_transcript()`](crate::Options::with_transcript)
+//! records timestamped terminal output, resizes and (depending on the
+//! [`InputCapture`] mode) input keys or accepted lines. Input entered
+//! via [`Terminal::ask()`] with `secret` set to `true` is never recorded.
+//!
+//! Transcripts are stored as JSONL, one record per line, where `t` is
+//! the time in milliseconds since the start of the recording:
+//! ```text
+//! {"t":0,"o":"$ "}
+//! {"t":412,"k":"l"}
+//! {"t":1650,"l":"ls -la"}
+//! {"t":1702,"r":[120,40]}
+//! ```
+//!
+//! A recorded [`Transcript`] can be played back via [`Terminal::replay()`].
+//!
+
+use crate::error::Error;
+use crate::keys::Key;
+use crate::result::Result;
+use crate::terminal::Terminal;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use workflow_core::task::sleep;
+use workflow_core::time::unixtime_as_millis_u64;
+
+/// Input recorded by the [`TranscriptSink`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InputCapture {
+    /// Do not record input
+    None,
+    /// Record individual keys
+    Keys,
+    /// Record accepted lines
+    #[default]
+    Lines,
+}
+
+/// Transcript event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TranscriptEvent {
+    /// Terminal output
+    #[serde(rename = "o")]
+    Output(String),
+    /// Input key (characters are recorded as-is, other keys by name)
+    #[serde(rename = "k")]
+    Key(String),
+    /// Accepted input line
+    #[serde(rename = "l")]
+    Line(String),
+    /// Terminal resize (columns, rows)
+    #[serde(rename = "r")]
+    Resize(u16, u16),
+}
+
+/// Timestamped transcript event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    /// Milliseconds since the start of the recording
+    pub t: u64,
+    #[serde(flatten)]
+    pub event: TranscriptEvent,
+}
+
+/// Recorded terminal session
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Transcript {
+    records: Vec<Record>,
+}
+
+impl Transcript {
+    /// Create a transcript from a list of records
+    pub fn new(records: Vec<Record>) -> Self {
+        Transcript { records }
+    }
+
+    /// Recorded events
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Concatenated terminal output
+    pub fn output(&self) -> String {
+        self.records
+            .iter()
+            .filter_map(|record| match &record.event {
+                TranscriptEvent::Output(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Serialize the transcript to JSONL
+    pub fn to_jsonl(&self) -> String {
+        self.records
+            .iter()
+            .map(|record| serde_json::to_string(record).unwrap() + "\n")
+            .collect()
+    }
+
+    /// Parse a JSONL transcript; empty lines are ignored
+    pub fn from_jsonl(text: &str) -> Result<Self> {
+        let records = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|err| Error::Transcript {
+                    line: index + 1,
+                    reason: err.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Transcript { records })
+    }
+
+    /// Load a transcript using [`workflow_store::fs`]
+    pub async fn load<P: AsRef<Path>>(filename: P) -> Result<Self> {
+        let text = workflow_store::fs::read_to_string(filename.as_ref()).await?;
+        Self::from_jsonl(&text)
+    }
+
+    /// Store the transcript using [`workflow_store::fs`]
+    pub async fn store<P: AsRef<Path>>(&self, filename: P) -> Result<()> {
+        workflow_store::fs::write_string(filename.as_ref(), &self.to_jsonl()).await?;
+        Ok(())
+    }
+}
+
+/// Transcript recorder supplied to the terminal via
+/// [`Options::with_transcript()`](crate::Options::with_transcript).
+/// Records are kept in memory and are written to the transcript
+/// file (if configured) by [`TranscriptSink::flush()`], which is
+/// also invoked on [`Terminal::exit()`].
+pub struct TranscriptSink {
+    filename: Option<PathBuf>,
+    input: InputCapture,
+    start: u64,
+    records: Mutex<Vec<Record>>,
+}
+
+impl Default for TranscriptSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TranscriptSink {
+    /// Create an in-memory sink recording accepted input lines
+    pub fn new() -> Self {
+        TranscriptSink {
+            filename: None,
+            input: InputCapture::default(),
+            start: unixtime_as_millis_u64(),
+            records: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Set the file the transcript is written to (a local storage
+    /// key when running in the browser)
+    pub fn with_filename<P: AsRef<Path>>(mut self, filename: P) -> Self {
+        self.filename = Some(filename.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the input capture mode
+    pub fn with_input(mut self, input: InputCapture) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Snapshot of the transcript recorded so far
+    pub fn transcript(&self) -> Transcript {
+        Transcript::new(self.records.lock().unwrap().clone())
+    }
+
+    /// Write the transcript to the configured file
+    pub async fn flush(&self) -> Result<()> {
+        if let Some(filename) = &self.filename {
+            self.transcript().store(filename).await?;
+        }
+        Ok(())
+    }
+
+    fn push(&self, event: TranscriptEvent) {
+        let t = unixtime_as_millis_u64().saturating_sub(self.start);
+        self.records.lock().unwrap().push(Record { t, event });
+    }
+
+    pub(crate) fn output(&self, text: &str) {
+        if !text.is_empty() {
+            self.push(TranscriptEvent::Output(text.to_string()));
+        }
+    }
+
+    pub(crate) fn key(&self, key: &Key) {
+        if self.input == InputCapture::Keys {
+            let key = match key {
+                Key::Char(ch) => ch.to_string(),
+                _ => format!("{key:?}"),
+            };
+            self.push(TranscriptEvent::Key(key));
+        }
+    }
+
+    pub(crate) fn line(&self, line: &str) {
+        if self.input == InputCapture::Lines {
+            self.push(TranscriptEvent::Line(line.to_string()));
+        }
+    }
+
+    pub(crate) fn resize(&self, cols: u16, rows: u16) {
+        self.push(TranscriptEvent::Resize(cols, rows));
+    }
+}
+
+impl Terminal {
+    /// Play back the output of the `transcript` honoring the original
+    /// timing scaled by `speed` (`2.0` plays twice as fast, `f64::INFINITY`
+    /// plays without delays). Input and resize events are not replayed.
+    pub async fn replay(self: &Arc<Self>, transcript: &Transcript, speed: f64) -> Result<()> {
+        if speed.is_nan() || speed <= 0.0 {
+            return Err(Error::ReplaySpeed(speed));
+        }
+
+        let mut last = transcript
+            .records()
+            .first()
+            .map(|record| record.t)
+            .unwrap_or_default();
+        for record in transcript.records() {
+            let elapsed = record.t.saturating_sub(last);
+            last = record.t;
+            let delay = elapsed as f64 / speed;
+            if delay >= 1.0 {
+                sleep(Duration::from_secs_f64(delay / 1000.0)).await;
+            }
+            if let TranscriptEvent::Output(text) = &record.event {
+                self.write(text);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use crate::terminal::Options;
+    use async_trait::async_trait;
+    use std::time::Instant;
+
+    struct EchoCli;
+
+    #[async_trait]
+    impl Cli for EchoCli {
+        async fn digest(self: Arc<Self>, term: Arc<Terminal>, cmd: String) -> Result<()> {
+            term.writeln(format!("echo: {cmd}"));
+            Ok(())
+        }
+        async fn complete(
+            self: Arc<Self>,
+            _term: Arc<Terminal>,
+            _cmd: String,
+        ) -> Result<Option<Vec<String>>> {
+            Ok(None)
+        }
+        fn prompt(&self) -> Option<String> {
+            None
+        }
+    }
+
+    async fn headless(sink: Option<Arc<TranscriptSink>>) -> Arc<Terminal> {
+        let mut options = Options::new().with_headless(true);
+        options.transcript = sink;
+        let term = Arc::new(Terminal::try_new_with_options(Arc::new(EchoCli), options).unwrap());
+        term.init().await.unwrap();
+        term
+    }
+
+    async fn type_text(term: &Arc<Terminal>, text: &str) {
+        for ch in text.chars() {
+            let key = if ch == '\n' {
+                Key::Enter
+            } else {
+                Key::Char(ch)
+            };
+            term.ingest(key, String::new()).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transcript_round_trip() {
+        let filename = std::env::temp_dir().join(format!(
+            "workflow-terminal-transcript-{}.jsonl",
+            workflow_core::id::Id::new()
+        ));
+        let sink = Arc::new(TranscriptSink::new().with_filename(&filename));
+        let term = headless(Some(sink.clone())).await;
+
+        term.prompt();
+        type_text(&term, "hello\n").await;
+        let (user, _) = futures::join!(term.ask(false, "user: "), type_text(&term, "alice\n"));
+        assert_eq!(user.unwrap(), "alice");
+        let (password, _) =
+            futures::join!(term.ask(true, "password: "), type_text(&term, "hunter2\n"));
+        assert_eq!(password.unwrap(), "hunter2");
+        term.on_resize(120, 40);
+        type_text(&term, "bye\n").await;
+        sink.flush().await.unwrap();
+
+        let transcript = Transcript::load(&filename).await.unwrap();
+        std::fs::remove_file(&filename).ok();
+        assert_eq!(transcript, sink.transcript());
+
+        let lines = transcript
+            .records()
+            .iter()
+            .filter_map(|record| match &record.event {
+                TranscriptEvent::Line(line) => Some(line.as_str()),
+                TranscriptEvent::Key(_) => panic!("keys must not be recorded"),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec!["hello", "alice", "bye"]);
+        assert!(transcript
+            .records()
+            .iter()
+            .any(|record| record.event == TranscriptEvent::Resize(120, 40)));
+        assert!(!transcript.to_jsonl().contains("hunter2"));
+
+        let output = term.headless_output().unwrap();
+        assert!(output.contains("echo: hello"));
+        assert!(output.contains("echo: bye"));
+        assert_eq!(transcript.output(), output);
+
+        let replay = headless(None).await;
+        replay.replay(&transcript, 100.0).await.unwrap();
+        assert_eq!(replay.headless_output().unwrap(), output);
+    }
+
+    #[tokio::test]
+    async fn test_transcript_keys() {
+        let sink = Arc::new(TranscriptSink::new().with_input(InputCapture::Keys));
+        let term = headless(Some(sink.clone())).await;
+
+        type_text(&term, "ls\n").await;
+        let (password, _) = futures::join!(term.ask(true, "password: "), type_text(&term, "pw\n"));
+        assert_eq!(password.unwrap(), "pw");
+
+        let keys = sink
+            .transcript()
+            .records()
+            .iter()
+            .filter_map(|record| match &record.event {
+                TranscriptEvent::Key(key) => Some(key.clone()),
+                TranscriptEvent::Line(_) => panic!("lines must not be recorded"),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["l", "s", "Enter"]);
+    }
+
+    #[tokio::test]
+    async fn test_transcript_replay_timing() {
+        let transcript = Transcript::from_jsonl(
+            "{\"t\":1000,\"o\":\"a\"}\n\n{\"t\":1200,\"k\":\"x\"}\n{\"t\":1400,\"o\":\"b\"}\n",
+        )
+        .unwrap();
+        assert_eq!(transcript.records().len(), 3);
+        assert_eq!(
+            transcript.to_jsonl(),
+            "{\"t\":1000,\"o\":\"a\"}\n{\"t\":1200,\"k\":\"x\"}\n{\"t\":1400,\"o\":\"b\"}\n"
+        );
+
+        let term = headless(None).await;
+        let start = Instant::now();
+        term.replay(&transcript, 2.0).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(190));
+        assert_eq!(term.headless_output().unwrap(), "ab");
+
+        let start = Instant::now();
+        term.replay(&transcript, f64::INFINITY).await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(190));
+        assert!(matches!(
+            term.replay(&transcript, 0.0).await,
+            Err(Error::ReplaySpeed(_))
+        ));
+        assert!(matches!(
+            Transcript::from_jsonl("{\"t\":0,\"o\":\"a\"}\n{\"t\":1}"),
+            Err(Error::Transcript { line: 2, .. })
+        ));
+    }
+}
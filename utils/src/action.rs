@@ -41,3 +41,264 @@ pub trait Action<Context>: Describe + Clone + Copy + Eq {
 
     fn run(&self, _ctx: &mut Context) -> std::result::Result<(), Self::Error>;
 }
+
+/// Async function executed by the [`ActionRunner`]. Receives the
+/// trigger request and an [`Abortable`] that is signaled when the
+/// run is cancelled via [`ActionRunner::abort()`].
+pub type ActionFn<T> = Arc<Box<dyn Send + Sync + Fn(T, Abortable) -> ActionFnReturn + 'static>>;
+
+/// [`ActionFn`] return type
+pub type ActionFnReturn = Pin<Box<dyn Send + 'static + Future<Output = ()>>>;
+
+/// Execution mode of the [`ActionRunner`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionMode {
+    /// Rapid triggers collapse into a single run (with the latest
+    /// request) once no new triggers arrive for the given duration.
+    Debounce(Duration),
+    /// Triggers received while a run is in progress are collapsed
+    /// into a single pending request (the newest one) that executes
+    /// as soon as the current run finishes.
+    SerializeLatest,
+}
+
+/// Notifications posted by the [`ActionRunner`] on its events channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionEvent {
+    Completed,
+    Aborted,
+}
+
+enum Ctl<T> {
+    Trigger(T),
+    Abort,
+}
+
+/// Executes an async action in response to triggers according to
+/// the selected [`ActionMode`]. Timing is based on [`workflow_core::task::sleep`]
+/// and as such functions uniformly in native and browser environments.
+///
+/// ```rust,ignore
+/// let runner = ActionRunner::new(ActionMode::Debounce(Duration::from_millis(250)), |query: String, _abortable| {
+///     Box::pin(async move { search(query).await; })
+/// });
+/// runner.start();
+/// runner.trigger("w".to_string())?;
+/// runner.trigger("workflow".to_string())?;
+/// ```
+pub struct ActionRunner<T> {
+    mode: ActionMode,
+    action: ActionFn<T>,
+    ctl: Channel<Ctl<T>>,
+    events: Channel<ActionEvent>,
+    shutdown: DuplexChannel,
+    is_running: Arc<AtomicBool>,
+}
+
+impl<T> ActionRunner<T>
+where
+    T: Send + 'static,
+{
+    pub fn new<FN>(mode: ActionMode, action_fn: FN) -> Self
+    where
+        FN: Send + Sync + Fn(T, Abortable) -> ActionFnReturn + 'static,
+    {
+        Self {
+            mode,
+            action: Arc::new(Box::new(action_fn)),
+            ctl: Channel::unbounded(),
+            events: Channel::unbounded(),
+            shutdown: DuplexChannel::oneshot(),
+            is_running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn mode(&self) -> ActionMode {
+        self.mode
+    }
+
+    /// Channel receiving [`ActionEvent`] notifications for each
+    /// completed or aborted run.
+    pub fn events(&self) -> &Receiver<ActionEvent> {
+        &self.events.receiver
+    }
+
+    pub fn trigger(&self, request: T) -> Result<()> {
+        self.ctl
+            .sender
+            .try_send(Ctl::Trigger(request))
+            .map_err(|_| Error::custom("action runner channel is closed"))
+    }
+
+    /// Cancel the pending debounce timer or pending request as well
+    /// as the in-flight run (if any).
+    pub fn abort(&self) -> Result<()> {
+        self.ctl
+            .sender
+            .try_send(Ctl::Abort)
+            .map_err(|_| Error::custom("action runner channel is closed"))
+    }
+
+    pub fn start(&self) {
+        if self.is_running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let mode = self.mode;
+        let action = self.action.clone();
+        let ctl = self.ctl.receiver.clone();
+        let events = self.events.sender.clone();
+        let shutdown = self.shutdown.clone();
+        let is_running = self.is_running.clone();
+
+        spawn(async move {
+            let mut pending: Option<T> = None;
+            let mut deadline: Option<(Instant, Duration)> = None;
+            let mut abortable = Abortable::new();
+            let mut running: Fuse<ActionFnReturn> = Fuse::terminated();
+
+            loop {
+                let timer_deadline = deadline;
+                let timer = async move {
+                    match timer_deadline {
+                        Some((start, delay)) => sleep(delay.saturating_sub(start.elapsed())).await,
+                        None => future::pending::<()>().await,
+                    }
+                }
+                .fuse();
+                futures::pin_mut!(timer);
+
+                select_biased! {
+                    _ = shutdown.request.receiver.recv().fuse() => {
+                        abortable.abort();
+                        break;
+                    },
+                    msg = ctl.recv().fuse() => {
+                        match msg {
+                            Ok(Ctl::Trigger(request)) => match mode {
+                                ActionMode::Debounce(delay) => {
+                                    pending = Some(request);
+                                    deadline = Some((Instant::now(), delay));
+                                }
+                                ActionMode::SerializeLatest => {
+                                    if running.is_terminated() {
+                                        running = (action)(request, abortable.clone()).fuse();
+                                    } else {
+                                        pending = Some(request);
+                                    }
+                                }
+                            },
+                            Ok(Ctl::Abort) => {
+                                pending = None;
+                                deadline = None;
+                                if !running.is_terminated() {
+                                    abortable.abort();
+                                    abortable = Abortable::new();
+                                    running = Fuse::terminated();
+                                    events.try_send(ActionEvent::Aborted).ok();
+                                }
+                            }
+                            Err(_) => break,
+                        }
+                    },
+                    _ = running => {
+                        events.try_send(ActionEvent::Completed).ok();
+                        if deadline.is_none() {
+                            if let Some(request) = pending.take() {
+                                running = (action)(request, abortable.clone()).fuse();
+                            }
+                        }
+                    },
+                    _ = timer => {
+                        deadline = None;
+                        // a run still in progress picks up the request on completion
+                        if running.is_terminated() {
+                            if let Some(request) = pending.take() {
+                                running = (action)(request, abortable.clone()).fuse();
+                            }
+                        }
+                    },
+                }
+            }
+
+            is_running.store(false, Ordering::SeqCst);
+            shutdown.response.sender.send(()).await.ok();
+        });
+    }
+
+    /// Stop the runner, aborting any in-flight run.
+    pub async fn stop(&self) -> Result<()> {
+        if self.is_running.load(Ordering::SeqCst) {
+            self.shutdown
+                .signal(())
+                .await
+                .map_err(|err| Error::custom(err.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn runner(mode: ActionMode, runs: Arc<Mutex<Vec<usize>>>) -> ActionRunner<usize> {
+        ActionRunner::new(mode, move |request, _abortable| {
+            let runs = runs.clone();
+            Box::pin(async move {
+                sleep(Duration::from_millis(50)).await;
+                runs.lock().unwrap().push(request);
+            })
+        })
+    }
+
+    #[tokio::test]
+    async fn test_action_debounce() -> Result<()> {
+        let runs = Arc::new(Mutex::new(Vec::new()));
+        let runner = runner(
+            ActionMode::Debounce(Duration::from_millis(30)),
+            runs.clone(),
+        );
+        runner.start();
+        for request in 0..10 {
+            runner.trigger(request)?;
+            sleep(Duration::from_millis(2)).await;
+        }
+        assert_eq!(runner.events().recv().await, Ok(ActionEvent::Completed));
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(*runs.lock().unwrap(), vec![9]);
+        runner.stop().await
+    }
+
+    #[tokio::test]
+    async fn test_action_serialize_latest() -> Result<()> {
+        let runs = Arc::new(Mutex::new(Vec::new()));
+        let runner = runner(ActionMode::SerializeLatest, runs.clone());
+        runner.start();
+        for request in 0..10 {
+            runner.trigger(request)?;
+            sleep(Duration::from_millis(2)).await;
+        }
+        assert_eq!(runner.events().recv().await, Ok(ActionEvent::Completed));
+        assert_eq!(runner.events().recv().await, Ok(ActionEvent::Completed));
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(*runs.lock().unwrap(), vec![0, 9]);
+        runner.stop().await
+    }
+
+    #[tokio::test]
+    async fn test_action_abort() -> Result<()> {
+        let runs = Arc::new(Mutex::new(Vec::new()));
+        let runner = runner(ActionMode::SerializeLatest, runs.clone());
+        runner.start();
+        runner.trigger(0)?;
+        runner.trigger(1)?;
+        sleep(Duration::from_millis(10)).await;
+        runner.abort()?;
+        assert_eq!(runner.events().recv().await, Ok(ActionEvent::Aborted));
+        sleep(Duration::from_millis(100)).await;
+        assert!(runs.lock().unwrap().is_empty());
+        runner.stop().await
+    }
+}
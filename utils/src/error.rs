@@ -22,6 +22,12 @@ pub enum Error {
 
     #[error(transparent)]
     Http(#[from] workflow_http::error::Error),
+
+    #[error(transparent)]
+    Version(#[from] crate::version::VersionError),
+
+    #[error(transparent)]
+    Cidr(#[from] crate::ip::CidrError),
 }
 
 impl From<String> for Error {
@@ -1,4 +1,6 @@
 use separator::{separated_float, separated_int, separated_uint_with_output, Separatable};
+use std::fmt;
+use unicode_segmentation::UnicodeSegmentation;
 
 /// Display KB or KiB if `short` is false, otherwise if `short` is true
 /// and the value is greater than 1MB or 1MiB, display units using [`as_data_size()`].
@@ -93,3 +95,553 @@ fn format_with_precision(f: f64) -> String {
         separated_float!(format!("{:.2}", f))
     }
 }
+
+/// Unit system used by [`bytes()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    /// Powers of 1024 (KiB, MiB, GiB, ...)
+    Binary,
+    /// Powers of 1000 (kB, MB, GB, ...)
+    Decimal,
+}
+
+impl Unit {
+    fn base(&self) -> u128 {
+        match self {
+            Unit::Binary => 1024,
+            Unit::Decimal => 1000,
+        }
+    }
+
+    fn suffixes(&self) -> &'static [&'static str] {
+        match self {
+            Unit::Binary => &["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"],
+            Unit::Decimal => &["B", "kB", "MB", "GB", "TB", "PB", "EB"],
+        }
+    }
+}
+
+/// Format a byte count as a human-readable size with one decimal
+/// place (for example `1.2 GiB`). Values below one kilo-unit are
+/// rendered as whole bytes (`512 B`).
+pub fn bytes(value: u64, unit: Unit) -> String {
+    let base = unit.base();
+    let suffixes = unit.suffixes();
+    let value = value as u128;
+
+    if value < base {
+        return format!("{value} {}", suffixes[0]);
+    }
+
+    let mut exp = 1;
+    let mut divisor = base;
+    while exp < suffixes.len() - 1 && value >= divisor * base {
+        exp += 1;
+        divisor *= base;
+    }
+
+    // rounding may carry into the next unit (1023.96 KiB => 1.0 MiB)
+    let mut tenths = (value * 10 + divisor / 2) / divisor;
+    if tenths >= base * 10 && exp < suffixes.len() - 1 {
+        exp += 1;
+        divisor *= base;
+        tenths = (value * 10 + divisor / 2) / divisor;
+    }
+
+    format!("{}.{} {}", tenths / 10, tenths % 10, suffixes[exp])
+}
+
+/// Format a duration using its two most significant components
+/// (for example `3m 42s` or `250ms`). See [`duration_with_precision()`].
+pub fn duration(duration: std::time::Duration) -> String {
+    duration_with_precision(duration, 2)
+}
+
+/// Format a duration using up to `precision` consecutive components
+/// starting from the most significant non-zero one. Zero components
+/// within that range are omitted and the remainder is truncated
+/// (`precision` of 2 renders `1h 0m 5s` as `1h`).
+pub fn duration_with_precision(duration: std::time::Duration, precision: usize) -> String {
+    const UNITS: [(&str, u128); 7] = [
+        ("d", 86_400_000_000_000),
+        ("h", 3_600_000_000_000),
+        ("m", 60_000_000_000),
+        ("s", 1_000_000_000),
+        ("ms", 1_000_000),
+        ("µs", 1_000),
+        ("ns", 1),
+    ];
+
+    let mut nanos = duration.as_nanos();
+    if nanos == 0 {
+        return "0s".to_string();
+    }
+
+    let precision = precision.max(1);
+    let mut parts = Vec::new();
+    let mut slots = None;
+    for (suffix, size) in UNITS {
+        let value = nanos / size;
+        nanos %= size;
+        if slots.is_none() && value > 0 {
+            slots = Some(precision);
+        }
+        if let Some(remaining) = slots.as_mut() {
+            if *remaining == 0 {
+                break;
+            }
+            *remaining -= 1;
+            if value > 0 {
+                parts.push(format!("{value}{suffix}"));
+            }
+        }
+    }
+
+    parts.join(" ")
+}
+
+/// Format an integer with `,` as the thousands separator
+/// regardless of locale (`1234567` => `1,234,567`).
+pub fn thousands(value: u128) -> String {
+    let digits = value.to_string();
+    let mut result = String::with_capacity(digits.len() + digits.len() / 3);
+    for (idx, c) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx).is_multiple_of(3) {
+            result.push(',');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Column alignment used by [`Table`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Right,
+    Center,
+}
+
+/// Plain-text table renderer for terminal output.
+///
+/// ```rust
+/// use workflow_utils::format::{Align, Table};
+///
+/// let mut table = Table::new()
+///     .with_header(["name", "size"])
+///     .with_align(1, Align::Right)
+///     .with_max_width(40);
+/// table.add_row(["core", "1.2 MiB"]);
+/// println!("{table}");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Table {
+    header: Option<Vec<String>>,
+    rows: Vec<Vec<String>>,
+    align: Vec<Align>,
+    max_width: Option<usize>,
+    separator: String,
+}
+
+impl Default for Table {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Table {
+    pub fn new() -> Self {
+        Self {
+            header: None,
+            rows: Vec::new(),
+            align: Vec::new(),
+            max_width: None,
+            separator: "  ".to_string(),
+        }
+    }
+
+    pub fn with_header<I, S>(mut self, header: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: ToString,
+    {
+        self.header = Some(header.into_iter().map(|s| s.to_string()).collect());
+        self
+    }
+
+    pub fn with_align(mut self, column: usize, align: Align) -> Self {
+        if self.align.len() <= column {
+            self.align.resize(column + 1, Align::default());
+        }
+        self.align[column] = align;
+        self
+    }
+
+    /// Limit the total rendered width (typically the terminal width).
+    /// The widest columns are shrunk first and cells that no longer
+    /// fit are truncated with an ellipsis.
+    pub fn with_max_width(mut self, max_width: usize) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    /// Set the string placed between columns (two spaces by default).
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    pub fn add_row<I, S>(&mut self, row: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: ToString,
+    {
+        self.rows
+            .push(row.into_iter().map(|s| s.to_string()).collect());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    fn widths(&self) -> Vec<usize> {
+        let mut widths = Vec::new();
+        for row in self.header.iter().chain(self.rows.iter()) {
+            if widths.len() < row.len() {
+                widths.resize(row.len(), 0);
+            }
+            for (idx, cell) in row.iter().enumerate() {
+                widths[idx] = widths[idx].max(cell.chars().count());
+            }
+        }
+
+        if let Some(max_width) = self.max_width {
+            let separators = self.separator.chars().count() * widths.len().saturating_sub(1);
+            let available = max_width.saturating_sub(separators);
+            while widths.iter().sum::<usize>() > available {
+                let (idx, width) = widths
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(idx, width)| (**width, std::cmp::Reverse(*idx)))
+                    .map(|(idx, width)| (idx, *width))
+                    .unwrap();
+                if width <= 1 {
+                    break;
+                }
+                widths[idx] -= 1;
+            }
+        }
+
+        widths
+    }
+
+    fn render_row(&self, row: &[String], widths: &[usize], target: &mut String) {
+        let mut line = String::new();
+        for (idx, width) in widths.iter().enumerate() {
+            if idx > 0 {
+                line.push_str(&self.separator);
+            }
+            let cell = truncate(row.get(idx).map(String::as_str).unwrap_or(""), *width);
+            let align = self.align.get(idx).copied().unwrap_or_default();
+            match align {
+                Align::Left => line.push_str(&format!("{cell:<width$}")),
+                Align::Right => line.push_str(&format!("{cell:>width$}")),
+                Align::Center => line.push_str(&format!("{cell:^width$}")),
+            }
+        }
+        target.push_str(line.trim_end());
+        target.push('\n');
+    }
+
+    pub fn render(&self) -> String {
+        let widths = self.widths();
+        let mut text = String::new();
+        if let Some(header) = &self.header {
+            self.render_row(header, &widths, &mut text);
+            let total = widths.iter().sum::<usize>()
+                + self.separator.chars().count() * widths.len().saturating_sub(1);
+            text.push_str(&"-".repeat(total));
+            text.push('\n');
+        }
+        for row in self.rows.iter() {
+            self.render_row(row, &widths, &mut text);
+        }
+        text
+    }
+}
+
+impl std::fmt::Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render())
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        text.to_string()
+    } else if width == 0 {
+        String::new()
+    } else {
+        let mut truncated = text.chars().take(width - 1).collect::<String>();
+        truncated.push('…');
+        truncated
+    }
+}
+
+/// Placeholder of the redacted portion of a value.
+pub const REDACTION_MARK: &str = "…";
+/// Number of graphemes retained on each side by [`Redacted`]
+/// and [`redact_hex()`].
+pub const REDACTION_KEEP: usize = 4;
+
+/// Redact the middle of `text`, keeping `keep_start` leading and `keep_end`
+/// trailing graphemes (`redact_middle("abcdefghijklmnopqrstuvwxyz", 4, 4)`
+/// => `abcd…wxyz`). The kept graphemes never outnumber the redacted ones;
+/// shorter strings are fully masked (rendered as [`REDACTION_MARK`]).
+pub fn redact_middle(text: &str, keep_start: usize, keep_end: usize) -> String {
+    let graphemes = text.graphemes(true).collect::<Vec<_>>();
+    if graphemes.is_empty() {
+        return String::new();
+    }
+    let keep = keep_start + keep_end;
+    if keep * 2 > graphemes.len() {
+        return REDACTION_MARK.to_string();
+    }
+    let mut redacted = graphemes[..keep_start].concat();
+    redacted.push_str(REDACTION_MARK);
+    redacted.push_str(&graphemes[graphemes.len() - keep_end..].concat());
+    redacted
+}
+
+/// Redact the local part of an email address, keeping its first grapheme
+/// and the domain (`alice@example.com` => `a…@example.com`). Values that
+/// are not email addresses are fully masked.
+pub fn redact_email(email: &str) -> String {
+    match email.rsplit_once('@') {
+        Some((local, domain)) if !local.is_empty() && !domain.is_empty() => {
+            format!("{}@{domain}", redact_middle(local, 1, 0))
+        }
+        _ => REDACTION_MARK.to_string(),
+    }
+}
+
+/// Redact a hex string (key, hash or address), preserving the `0x`
+/// prefix as well as the first and the last [`REDACTION_KEEP`] digits
+/// (`0x1234567890abcdef1234` => `0x1234…1234`).
+pub fn redact_hex(hex: &str) -> String {
+    let (prefix, digits) = match hex.get(..2) {
+        Some(prefix @ ("0x" | "0X")) => (prefix, &hex[2..]),
+        _ => ("", hex),
+    };
+    format!(
+        "{prefix}{}",
+        redact_middle(digits, REDACTION_KEEP, REDACTION_KEEP)
+    )
+}
+
+/// Values that must not be logged verbatim. Logging facilities (such as
+/// structured log fields) format sensitive values using [`Sensitive::redacted()`],
+/// which by default applies [`redact_middle()`] to the [`Display`](fmt::Display)
+/// output; implementations can override it (e.g. using [`redact_email()`]).
+pub trait Sensitive: fmt::Display {
+    fn redacted(&self) -> String {
+        redact_middle(&self.to_string(), REDACTION_KEEP, REDACTION_KEEP)
+    }
+}
+
+/// Wrapper displaying the redacted form of the value (see [`redact_middle()`]).
+/// The [`Debug`](fmt::Debug) output does not reveal the value either.
+///
+/// ```rust
+/// use workflow_utils::format::Redacted;
+///
+/// let key = Redacted::new("5f4dcc3b5aa765d61d8327deb882cf99");
+/// assert_eq!(key.to_string(), "5f4d…cf99");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Redacted<T: fmt::Display>(T);
+
+impl<T: fmt::Display> Redacted<T> {
+    pub fn new(value: T) -> Self {
+        Redacted(value)
+    }
+
+    /// Access the raw value
+    pub fn inner(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = redact_middle(&self.0.to_string(), REDACTION_KEEP, REDACTION_KEEP);
+        f.pad(&text)
+    }
+}
+
+impl<T: fmt::Display> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Redacted").field(&self.to_string()).finish()
+    }
+}
+
+impl<T: fmt::Display> Sensitive for Redacted<T> {
+    fn redacted(&self) -> String {
+        self.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(bytes(0, Unit::Binary), "0 B");
+        assert_eq!(bytes(1023, Unit::Binary), "1023 B");
+        assert_eq!(bytes(1024, Unit::Binary), "1.0 KiB");
+        assert_eq!(bytes(1000, Unit::Decimal), "1.0 kB");
+        assert_eq!(bytes(1536, Unit::Binary), "1.5 KiB");
+        assert_eq!(bytes(1024 * 1024 - 1, Unit::Binary), "1.0 MiB");
+        assert_eq!(bytes(1_288_490_189, Unit::Binary), "1.2 GiB");
+        assert_eq!(bytes(u64::MAX, Unit::Binary), "16.0 EiB");
+        assert_eq!(bytes(u64::MAX, Unit::Decimal), "18.4 EB");
+    }
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(duration(Duration::ZERO), "0s");
+        assert_eq!(duration(Duration::from_secs(222)), "3m 42s");
+        assert_eq!(duration(Duration::from_millis(250)), "250ms");
+        assert_eq!(duration(Duration::from_secs(3605)), "1h");
+        assert_eq!(duration(Duration::from_secs(60)), "1m");
+        assert_eq!(duration(Duration::from_nanos(1_500)), "1µs 500ns");
+        assert_eq!(
+            duration_with_precision(Duration::from_secs(90_061), 4),
+            "1d 1h 1m 1s"
+        );
+        assert_eq!(
+            duration_with_precision(Duration::from_millis(1_250), 1),
+            "1s"
+        );
+        assert_eq!(duration(Duration::MAX), "213503982334601d 7h");
+    }
+
+    #[test]
+    fn test_thousands() {
+        assert_eq!(thousands(0), "0");
+        assert_eq!(thousands(999), "999");
+        assert_eq!(thousands(1000), "1,000");
+        assert_eq!(thousands(1024), "1,024");
+        assert_eq!(thousands(1_234_567), "1,234,567");
+        assert_eq!(
+            thousands(u128::MAX),
+            "340,282,366,920,938,463,463,374,607,431,768,211,455"
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let mut table = Table::new()
+            .with_header(["name", "size"])
+            .with_align(1, Align::Right);
+        table.add_row(["core", "1.2 MiB"]).add_row(["rpc", "512 B"]);
+        assert_eq!(
+            table.render(),
+            "name     size\n\
+             -------------\n\
+             core  1.2 MiB\n\
+             rpc     512 B\n"
+        );
+
+        let mut table = Table::new().with_max_width(16);
+        table.add_row(["workflow-websocket", "ok"]);
+        assert_eq!(table.render(), "workflow-we…  ok\n");
+    }
+
+    #[test]
+    fn test_redact_middle() {
+        let cases = [
+            ("", 4, 4, ""),
+            ("abcdefghijklmnopqrstuvwxyz", 4, 4, "abcd…wxyz"),
+            ("abcdefghijklmnop", 4, 4, "abcd…mnop"),
+            // redaction would reveal (nearly) everything
+            ("abcdefghijklmno", 4, 4, "…"),
+            ("secret", 4, 4, "…"),
+            ("a", 0, 0, "…"),
+            ("abcdefgh", 2, 0, "ab…"),
+            // multibyte characters and grapheme clusters are not split
+            ("ääääbbbbccccdddd", 4, 4, "ääää…dddd"),
+            ("🇨🇭🇨🇭xxxxxxxx🇩🇪🇩🇪", 2, 2, "🇨🇭🇨🇭…🇩🇪🇩🇪"),
+            ("e\u{301}e\u{301}xxxxxxe\u{301}", 1, 1, "e\u{301}…e\u{301}"),
+        ];
+        for (text, keep_start, keep_end, expected) in cases {
+            assert_eq!(
+                redact_middle(text, keep_start, keep_end),
+                expected,
+                "redact_middle({text:?}, {keep_start}, {keep_end})"
+            );
+        }
+    }
+
+    #[test]
+    fn test_redact_email_and_hex() {
+        let emails = [
+            ("alice@example.com", "a…@example.com"),
+            ("al@example.com", "a…@example.com"),
+            ("a@example.com", "…@example.com"),
+            ("élodie@example.com", "é…@example.com"),
+            ("@example.com", "…"),
+            ("alice@", "…"),
+            ("not an email", "…"),
+        ];
+        for (email, expected) in emails {
+            assert_eq!(redact_email(email), expected, "redact_email({email:?})");
+        }
+
+        let hex = [
+            ("0x1234567890abcdef1234", "0x1234…1234"),
+            ("0X1234567890abcdef1234", "0X1234…1234"),
+            ("1234567890abcdef", "1234…cdef"),
+            ("0x1234567890abcde", "0x…"),
+            ("0x", "0x"),
+        ];
+        for (value, expected) in hex {
+            assert_eq!(redact_hex(value), expected, "redact_hex({value:?})");
+        }
+    }
+
+    #[test]
+    fn test_redacted() {
+        struct Email(&'static str);
+        impl std::fmt::Display for Email {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+        impl Sensitive for Email {
+            fn redacted(&self) -> String {
+                redact_email(self.0)
+            }
+        }
+
+        let key = Redacted::new("5f4dcc3b5aa765d61d8327deb882cf99");
+        assert_eq!(key.to_string(), "5f4d…cf99");
+        assert_eq!(format!("{key:?}"), r#"Redacted("5f4d…cf99")"#);
+        assert_eq!(key.redacted(), "5f4d…cf99");
+        assert_eq!(*key.inner(), "5f4dcc3b5aa765d61d8327deb882cf99");
+
+        let pin = Redacted::new(1234);
+        assert_eq!(pin.to_string(), "…");
+        assert!(!format!("{pin:?}").contains("1234"));
+
+        assert_eq!(Email("alice@example.com").redacted(), "a…@example.com");
+    }
+}
@@ -1,11 +1,21 @@
 #![allow(unused_imports)]
 
 pub use std::fmt::{self, Display, Formatter};
+pub use std::future::Future;
+pub use std::pin::Pin;
 pub use std::str::FromStr;
+pub use std::sync::atomic::{AtomicBool, Ordering};
+pub use std::sync::Arc;
 
 pub use ahash::AHashSet;
+pub use futures::future::{self, Fuse, FusedFuture};
+pub use futures::{select_biased, FutureExt};
 pub use serde::{Deserialize, Serialize};
 
+pub use workflow_core::abortable::Abortable;
+pub use workflow_core::channel::{Channel, DuplexChannel, Receiver};
+pub use workflow_core::task::{sleep, spawn};
+pub use workflow_core::time::{Duration, Instant};
 pub use workflow_http::prelude as http;
 
 pub use crate::error::Error;
@@ -1,4 +1,11 @@
+//!
+//! IP address helpers: public address lookup, CIDR prefixes,
+//! allow/deny filtering and local interface enumeration.
+//!
+
 use crate::imports::*;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use thiserror::Error;
 
 pub async fn public() -> Result<String> {
     Ok(http::get("https://api.ipify.org").await?)
@@ -12,3 +19,439 @@ pub mod blocking {
         Ok(reqwest::blocking::get("https://api.ipify.org")?.text()?)
     }
 }
+
+/// Maximum number of host bits for which [`Cidr::hosts()`] will
+/// produce an iterator (`/16` for IPv4, `/112` for IPv6).
+pub const MAX_HOST_BITS: u8 = 16;
+
+/// Typed CIDR parsing errors.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CidrError {
+    #[error("invalid IP address '{0}'")]
+    InvalidAddress(String),
+
+    #[error("invalid prefix length '{0}'")]
+    InvalidPrefix(String),
+
+    #[error("prefix length {prefix} exceeds {max} bits")]
+    PrefixTooLong { prefix: u8, max: u8 },
+
+    #[error("host bits are set in '{0}'")]
+    HostBitsSet(String),
+
+    #[error("prefix /{0} contains too many hosts to iterate")]
+    TooManyHosts(u8),
+}
+
+/// An IPv4 or IPv6 network prefix such as `10.0.0.0/8` or `fd00::/8`.
+///
+/// Parsing rejects addresses with host bits set (`10.0.0.1/8`);
+/// use [`Cidr::new_truncated()`] to mask them off instead. A bare
+/// address is treated as a single-host prefix (`/32` or `/128`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub fn new(addr: IpAddr, prefix: u8) -> std::result::Result<Self, CidrError> {
+        let cidr = Self::new_truncated(addr, prefix)?;
+        if cidr.addr != addr {
+            return Err(CidrError::HostBitsSet(format!("{addr}/{prefix}")));
+        }
+        Ok(cidr)
+    }
+
+    /// Create a prefix, clearing any host bits present in `addr`.
+    pub fn new_truncated(addr: IpAddr, prefix: u8) -> std::result::Result<Self, CidrError> {
+        let max = max_prefix(&addr);
+        if prefix > max {
+            return Err(CidrError::PrefixTooLong { prefix, max });
+        }
+        let addr = from_bits(&addr, to_bits(&addr) & mask(prefix, max));
+        Ok(Self { addr, prefix })
+    }
+
+    /// Network address of this prefix.
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    pub fn is_ipv4(&self) -> bool {
+        self.addr.is_ipv4()
+    }
+
+    pub fn is_ipv6(&self) -> bool {
+        self.addr.is_ipv6()
+    }
+
+    fn host_bits(&self) -> u8 {
+        max_prefix(&self.addr) - self.prefix
+    }
+
+    /// Check if the address belongs to this prefix. IPv4-mapped IPv6
+    /// addresses (`::ffff:10.0.0.1`) are matched against IPv4 prefixes.
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        let addr = addr.to_canonical();
+        if addr.is_ipv4() != self.addr.is_ipv4() {
+            return false;
+        }
+        let mask = mask(self.prefix, max_prefix(&addr));
+        to_bits(&addr) & mask == to_bits(&self.addr)
+    }
+
+    /// Iterate over usable host addresses. For IPv4 prefixes shorter
+    /// than `/31` the network and broadcast addresses are skipped.
+    /// Fails for prefixes with more than [`MAX_HOST_BITS`] host bits.
+    pub fn hosts(&self) -> std::result::Result<Hosts, CidrError> {
+        let host_bits = self.host_bits();
+        if host_bits > MAX_HOST_BITS {
+            return Err(CidrError::TooManyHosts(self.prefix));
+        }
+        let first = to_bits(&self.addr);
+        let last = first + ((1u128 << host_bits) - 1);
+        let (next, last) = if self.is_ipv4() && host_bits > 1 {
+            (first + 1, last - 1)
+        } else {
+            (first, last)
+        };
+        Ok(Hosts {
+            base: self.addr,
+            next,
+            last,
+            done: false,
+        })
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = CidrError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, CidrError> {
+        let s = s.trim();
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr =
+            IpAddr::from_str(addr).map_err(|_| CidrError::InvalidAddress(addr.to_string()))?;
+        let prefix = match prefix {
+            Some(prefix) => {
+                if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_digit()) {
+                    return Err(CidrError::InvalidPrefix(prefix.to_string()));
+                }
+                prefix
+                    .parse::<u8>()
+                    .map_err(|_| CidrError::InvalidPrefix(prefix.to_string()))?
+            }
+            None => max_prefix(&addr),
+        };
+        Cidr::new(addr, prefix)
+    }
+}
+
+impl Display for Cidr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+impl From<IpAddr> for Cidr {
+    fn from(addr: IpAddr) -> Self {
+        Self {
+            addr,
+            prefix: max_prefix(&addr),
+        }
+    }
+}
+
+impl Serialize for Cidr {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cidr {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Cidr::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Iterator over host addresses returned by [`Cidr::hosts()`].
+#[derive(Debug, Clone)]
+pub struct Hosts {
+    base: IpAddr,
+    next: u128,
+    last: u128,
+    done: bool,
+}
+
+impl Iterator for Hosts {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<IpAddr> {
+        if self.done || self.next > self.last {
+            return None;
+        }
+        let addr = from_bits(&self.base, self.next);
+        if self.next == self.last {
+            self.done = true;
+        } else {
+            self.next += 1;
+        }
+        Some(addr)
+    }
+}
+
+/// Allow/deny list of network prefixes used to screen peers.
+///
+/// Deny rules are evaluated first; if the address is not denied it
+/// is allowed when the allow list is empty or contains the address.
+/// [`IpFilter::accept()`] matches the signature of the `accept()`
+/// handler in `workflow-websocket` and `workflow-rpc` servers:
+///
+/// ```rust,ignore
+/// fn accept(&self, peer: &SocketAddr) -> bool {
+///     self.ip_filter.accept(peer)
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpFilter {
+    #[serde(default)]
+    pub allow: Vec<Cidr>,
+    #[serde(default)]
+    pub deny: Vec<Cidr>,
+}
+
+impl IpFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a filter from textual allow and deny lists.
+    pub fn try_from_lists<A, D>(allow: A, deny: D) -> Result<Self>
+    where
+        A: IntoIterator,
+        A::Item: AsRef<str>,
+        D: IntoIterator,
+        D::Item: AsRef<str>,
+    {
+        let allow = allow
+            .into_iter()
+            .map(|s| Cidr::from_str(s.as_ref()))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let deny = deny
+            .into_iter()
+            .map(|s| Cidr::from_str(s.as_ref()))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(Self { allow, deny })
+    }
+
+    pub fn with_allow(mut self, cidr: Cidr) -> Self {
+        self.allow.push(cidr);
+        self
+    }
+
+    pub fn with_deny(mut self, cidr: Cidr) -> Self {
+        self.deny.push(cidr);
+        self
+    }
+
+    pub fn is_allowed(&self, addr: &IpAddr) -> bool {
+        if self.deny.iter().any(|cidr| cidr.contains(addr)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|cidr| cidr.contains(addr))
+    }
+
+    pub fn accept(&self, peer: &SocketAddr) -> bool {
+        self.is_allowed(&peer.ip())
+    }
+}
+
+/// Network interface and its assigned addresses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceInfo {
+    pub name: String,
+    pub addresses: Vec<IpAddr>,
+    pub is_loopback: bool,
+}
+
+/// Enumerate local network interfaces.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn interfaces() -> Result<Vec<InterfaceInfo>> {
+    let mut list: Vec<InterfaceInfo> = Vec::new();
+    for iface in if_addrs::get_if_addrs()? {
+        let addr = iface.ip();
+        match list.iter_mut().find(|info| info.name == iface.name) {
+            Some(info) => info.addresses.push(addr),
+            None => list.push(InterfaceInfo {
+                is_loopback: iface.is_loopback(),
+                name: iface.name,
+                addresses: vec![addr],
+            }),
+        }
+    }
+    Ok(list)
+}
+
+/// Interface enumeration is not available in the browser;
+/// this function always returns an empty list.
+#[cfg(target_arch = "wasm32")]
+pub fn interfaces() -> Result<Vec<InterfaceInfo>> {
+    Ok(Vec::new())
+}
+
+fn max_prefix(addr: &IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn mask(prefix: u8, max: u8) -> u128 {
+    if prefix == 0 {
+        0
+    } else {
+        let all = if max == 128 {
+            u128::MAX
+        } else {
+            (1u128 << max) - 1
+        };
+        all & !((1u128 << (max - prefix)).wrapping_sub(1))
+    }
+}
+
+fn to_bits(addr: &IpAddr) -> u128 {
+    match addr {
+        IpAddr::V4(addr) => u32::from(*addr) as u128,
+        IpAddr::V6(addr) => u128::from(*addr),
+    }
+}
+
+fn from_bits(kind: &IpAddr, bits: u128) -> IpAddr {
+    match kind {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from(bits as u32)),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(bits)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cidr(s: &str) -> Cidr {
+        s.parse().unwrap()
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_cidr_parse() {
+        assert_eq!(cidr("10.0.0.0/8").to_string(), "10.0.0.0/8");
+        assert_eq!(cidr("fd00::/8").to_string(), "fd00::/8");
+        assert_eq!(cidr("192.168.1.1").prefix(), 32);
+        assert_eq!(cidr("::1").prefix(), 128);
+        assert_eq!(cidr("0.0.0.0/0").prefix(), 0);
+        assert_eq!(cidr("::/0").prefix(), 0);
+        assert_eq!(cidr("2001:db8::1/128").prefix(), 128);
+
+        assert_eq!(
+            Cidr::from_str("10.0.0.1/8"),
+            Err(CidrError::HostBitsSet("10.0.0.1/8".into()))
+        );
+        assert_eq!(
+            Cidr::from_str("10.0.0.0/33"),
+            Err(CidrError::PrefixTooLong {
+                prefix: 33,
+                max: 32
+            })
+        );
+        assert_eq!(
+            Cidr::from_str("::/129"),
+            Err(CidrError::PrefixTooLong {
+                prefix: 129,
+                max: 128
+            })
+        );
+        assert_eq!(
+            Cidr::from_str("10.0.0.0/"),
+            Err(CidrError::InvalidPrefix("".into()))
+        );
+        assert_eq!(
+            Cidr::from_str("10.0.0.0/+8"),
+            Err(CidrError::InvalidPrefix("+8".into()))
+        );
+        assert_eq!(
+            Cidr::from_str("10.0.0/8"),
+            Err(CidrError::InvalidAddress("10.0.0".into()))
+        );
+        assert_eq!(
+            Cidr::new_truncated(ip("10.1.2.3"), 8).unwrap(),
+            cidr("10.0.0.0/8")
+        );
+    }
+
+    #[test]
+    fn test_cidr_contains() {
+        assert!(cidr("10.0.0.0/8").contains(&ip("10.255.0.1")));
+        assert!(!cidr("10.0.0.0/8").contains(&ip("11.0.0.1")));
+        assert!(cidr("10.0.0.0/8").contains(&ip("::ffff:10.0.0.1")));
+        assert!(cidr("fd00::/8").contains(&ip("fd12:3456::1")));
+        assert!(!cidr("fd00::/8").contains(&ip("fe80::1")));
+        assert!(!cidr("fd00::/8").contains(&ip("10.0.0.1")));
+        assert!(cidr("0.0.0.0/0").contains(&ip("8.8.8.8")));
+        assert!(!cidr("0.0.0.0/0").contains(&ip("::1")));
+        assert!(cidr("::/0").contains(&ip("2001:db8::1")));
+        assert!(cidr("::1/128").contains(&ip("::1")));
+        assert!(!cidr("::1/128").contains(&ip("::2")));
+    }
+
+    #[test]
+    fn test_cidr_hosts() {
+        let hosts = cidr("192.168.0.0/30").hosts().unwrap().collect::<Vec<_>>();
+        assert_eq!(hosts, vec![ip("192.168.0.1"), ip("192.168.0.2")]);
+        assert_eq!(cidr("10.0.0.0/31").hosts().unwrap().count(), 2);
+        assert_eq!(cidr("10.0.0.1/32").hosts().unwrap().count(), 1);
+        assert_eq!(cidr("255.255.255.255/32").hosts().unwrap().count(), 1);
+        assert_eq!(cidr("::/120").hosts().unwrap().count(), 256);
+        assert_eq!(
+            cidr("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff/128")
+                .hosts()
+                .unwrap()
+                .count(),
+            1
+        );
+        assert_eq!(cidr("10.0.0.0/16").hosts().unwrap().count(), 65534);
+        assert!(cidr("10.0.0.0/8").hosts().is_err());
+        assert!(cidr("::/0").hosts().is_err());
+    }
+
+    #[test]
+    fn test_ip_filter() {
+        let filter = IpFilter::try_from_lists(["10.0.0.0/8", "fd00::/8"], ["10.1.0.0/16"]).unwrap();
+        assert!(filter.is_allowed(&ip("10.2.3.4")));
+        assert!(!filter.is_allowed(&ip("10.1.3.4")));
+        assert!(!filter.is_allowed(&ip("192.168.1.1")));
+        assert!(filter.is_allowed(&ip("fd00::1")));
+        assert!(filter.accept(&"10.2.3.4:8080".parse().unwrap()));
+
+        let filter = IpFilter::new().with_deny(cidr("127.0.0.0/8"));
+        assert!(filter.is_allowed(&ip("1.1.1.1")));
+        assert!(!filter.is_allowed(&ip("127.0.0.1")));
+    }
+}
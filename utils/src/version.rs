@@ -1,10 +1,86 @@
+//!
+//! Semantic version parsing, comparison and requirement matching
+//! following the [semver 2.0.0](https://semver.org) specification.
+//!
+
 use crate::imports::*;
+use std::cmp::Ordering;
+use thiserror::Error;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Typed version and version requirement parsing errors.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum VersionError {
+    #[error("empty version string")]
+    Empty,
+
+    #[error("missing {0} version component")]
+    MissingComponent(&'static str),
+
+    #[error("unexpected trailing version component in '{0}'")]
+    UnexpectedComponent(String),
+
+    #[error("invalid numeric version component '{0}'")]
+    InvalidNumber(String),
+
+    #[error("leading zero in numeric component '{0}'")]
+    LeadingZero(String),
+
+    #[error("empty pre-release or build metadata identifier")]
+    EmptyIdentifier,
+
+    #[error("invalid character in identifier '{0}'")]
+    InvalidIdentifier(String),
+
+    #[error("invalid version requirement '{0}'")]
+    InvalidRequirement(String),
+}
+
+/// A single dot-separated pre-release identifier.
+///
+/// Numeric identifiers always have lower precedence than
+/// alphanumeric identifiers (the variant order encodes this).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Identifier {
+    Numeric(u64),
+    AlphaNumeric(String),
+}
+
+impl Display for Identifier {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Identifier::Numeric(n) => write!(f, "{n}"),
+            Identifier::AlphaNumeric(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+impl FromStr for Identifier {
+    type Err = VersionError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, VersionError> {
+        validate_identifier(s)?;
+        if s.chars().all(|c| c.is_ascii_digit()) {
+            Ok(Identifier::Numeric(parse_numeric(s)?))
+        } else {
+            Ok(Identifier::AlphaNumeric(s.to_string()))
+        }
+    }
+}
+
+/// Semantic version. Construct using [`Version::new()`] or
+/// [`Version::parse()`]; the struct is non-exhaustive so that
+/// further fields can be added without breaking dependent crates.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub struct Version {
     pub major: u64,
     pub minor: u64,
     pub patch: u64,
+    /// Pre-release identifiers (`1.0.0-alpha.1` => `["alpha", 1]`)
+    pub pre: Vec<Identifier>,
+    /// Build metadata (`1.0.0+build.5` => `["build", "5"]`);
+    /// ignored when determining version precedence.
+    pub build: Vec<String>,
 }
 
 impl AsRef<Version> for Version {
@@ -17,64 +93,533 @@ impl FromStr for Version {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
+        Ok(Version::parse(s)?)
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if !self.pre.is_empty() {
+            write!(f, "-{}", join(&self.pre))?;
+        }
+        if !self.build.is_empty() {
+            write!(f, "+{}", self.build.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    /// Total ordering: semver precedence first, build metadata
+    /// is only used as a tie-breaker to remain consistent with [`Eq`].
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_precedence(other)
+            .then_with(|| self.build.cmp(&other.build))
+    }
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Version {
+            major,
+            minor,
+            patch,
+            pre: Vec::new(),
+            build: Vec::new(),
+        }
+    }
+
+    /// Parse a semver string. A leading `v` (as in `v1.2.3`) is accepted.
+    pub fn parse(s: &str) -> std::result::Result<Self, VersionError> {
+        let s = s.trim();
+        let s = s.strip_prefix(['v', 'V']).unwrap_or(s);
+        if s.is_empty() {
+            return Err(VersionError::Empty);
+        }
+
+        let (s, build) = match s.split_once('+') {
+            Some((s, build)) => (s, parse_build(build)?),
+            None => (s, Vec::new()),
+        };
+
+        let (s, pre) = match s.split_once('-') {
+            Some((s, pre)) => (s, parse_pre(pre)?),
+            None => (s, Vec::new()),
+        };
+
         let mut parts = s.split('.');
-        let major = parts
-            .next()
-            .ok_or_else(|| Error::custom("Invalid version"))?
-            .chars()
-            .filter(|c| c.is_ascii_digit())
-            .collect::<String>()
-            .parse()?;
-        let minor = parts
-            .next()
-            .ok_or_else(|| Error::custom("Invalid version"))?
-            .chars()
-            .filter(|c| c.is_ascii_digit())
-            .collect::<String>()
-            .parse()?;
-        let patch = parts
-            .next()
-            .ok_or_else(|| Error::custom("Invalid version"))?
-            .chars()
-            .filter(|c| c.is_ascii_digit())
-            .collect::<String>()
-            .parse()?;
+        let mut component = |name| {
+            parts
+                .next()
+                .ok_or(VersionError::MissingComponent(name))
+                .and_then(parse_numeric)
+        };
+        let major = component("major")?;
+        let minor = component("minor")?;
+        let patch = component("patch")?;
+        if parts.next().is_some() {
+            return Err(VersionError::UnexpectedComponent(s.to_string()));
+        }
+
         Ok(Version {
             major,
             minor,
             patch,
+            pre,
+            build,
         })
     }
-}
 
-impl Display for Version {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    pub fn is_prerelease(&self) -> bool {
+        !self.pre.is_empty()
+    }
+
+    /// Compare versions according to semver precedence rules
+    /// (build metadata is ignored).
+    pub fn cmp_precedence(&self, other: &Version) -> Ordering {
+        self.major
+            .cmp(&other.major)
+            .then_with(|| self.minor.cmp(&other.minor))
+            .then_with(|| self.patch.cmp(&other.patch))
+            .then_with(|| cmp_pre(&self.pre, &other.pre))
     }
-}
 
-impl Version {
     pub fn is_greater_than<V>(&self, other: V) -> bool
     where
         V: AsRef<Version>,
     {
-        use std::cmp::Ordering;
+        self.cmp_precedence(other.as_ref()) == Ordering::Greater
+    }
+}
+
+/// Comparison operator of a single [`Comparator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// `=1.2.3`
+    Exact,
+    /// `>1.2.3`
+    Greater,
+    /// `>=1.2.3`
+    GreaterEq,
+    /// `<1.2.3`
+    Less,
+    /// `<=1.2.3`
+    LessEq,
+    /// `~1.2.3` - patch-level changes
+    Tilde,
+    /// `^1.2.3` - compatible changes (default when no operator is given)
+    Caret,
+    /// `1.*`, `1.2.*`
+    Wildcard,
+}
+
+/// A single version constraint such as `>=1.2` or `^0.3.1`.
+/// Missing `minor` or `patch` components act as wildcards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparator {
+    pub op: Op,
+    pub major: u64,
+    pub minor: Option<u64>,
+    pub patch: Option<u64>,
+    pub pre: Vec<Identifier>,
+}
+
+impl Comparator {
+    pub fn matches(&self, version: &Version) -> bool {
+        match self.op {
+            Op::Exact | Op::Wildcard => matches_exact(self, version),
+            Op::Greater => matches_greater(self, version),
+            Op::GreaterEq => matches_exact(self, version) || matches_greater(self, version),
+            Op::Less => matches_less(self, version),
+            Op::LessEq => matches_exact(self, version) || matches_less(self, version),
+            Op::Tilde => matches_tilde(self, version),
+            Op::Caret => matches_caret(self, version),
+        }
+    }
+}
+
+impl FromStr for Comparator {
+    type Err = VersionError;
+
+    fn from_str(text: &str) -> std::result::Result<Self, VersionError> {
+        let s = text.trim();
+        let (op, s) = if let Some(s) = s.strip_prefix(">=") {
+            (Some(Op::GreaterEq), s)
+        } else if let Some(s) = s.strip_prefix("<=") {
+            (Some(Op::LessEq), s)
+        } else if let Some(s) = s.strip_prefix('>') {
+            (Some(Op::Greater), s)
+        } else if let Some(s) = s.strip_prefix('<') {
+            (Some(Op::Less), s)
+        } else if let Some(s) = s.strip_prefix('=') {
+            (Some(Op::Exact), s)
+        } else if let Some(s) = s.strip_prefix('~') {
+            (Some(Op::Tilde), s)
+        } else if let Some(s) = s.strip_prefix('^') {
+            (Some(Op::Caret), s)
+        } else {
+            (None, s)
+        };
+
+        let invalid = || VersionError::InvalidRequirement(text.to_string());
+
+        let s = s.trim();
+        let s = s.strip_prefix(['v', 'V']).unwrap_or(s);
+        // build metadata carries no meaning in a requirement
+        let s = s.split_once('+').map(|(s, _)| s).unwrap_or(s);
+        let (s, pre) = match s.split_once('-') {
+            Some((s, pre)) => (s, parse_pre(pre)?),
+            None => (s, Vec::new()),
+        };
+
+        let is_wildcard = |part: &str| matches!(part, "*" | "x" | "X");
+        let mut parts = s.split('.');
+        let major = parts.next().filter(|p| !p.is_empty()).ok_or_else(invalid)?;
+        if is_wildcard(major) {
+            return Err(invalid());
+        }
+        let major = parse_numeric(major)?;
+
+        let mut wildcard = false;
+        let mut component = |part: Option<&str>| -> std::result::Result<Option<u64>, VersionError> {
+            match part {
+                None => Ok(None),
+                Some(part) if is_wildcard(part) => {
+                    wildcard = true;
+                    Ok(None)
+                }
+                Some(_) if wildcard => Err(invalid()),
+                Some(part) => parse_numeric(part).map(Some),
+            }
+        };
+        let minor = component(parts.next())?;
+        let patch = component(parts.next())?;
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        let op = match (op, wildcard) {
+            (None, true) => Op::Wildcard,
+            (Some(_), true) => return Err(invalid()),
+            (None, false) => Op::Caret,
+            (Some(op), false) => op,
+        };
+
+        if !pre.is_empty() && patch.is_none() {
+            return Err(invalid());
+        }
+
+        Ok(Comparator {
+            op,
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+}
+
+impl Display for Comparator {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let op = match self.op {
+            Op::Exact => "=",
+            Op::Greater => ">",
+            Op::GreaterEq => ">=",
+            Op::Less => "<",
+            Op::LessEq => "<=",
+            Op::Tilde => "~",
+            Op::Caret => "^",
+            Op::Wildcard => "",
+        };
+        write!(f, "{op}{}", self.major)?;
+        match (self.minor, self.patch) {
+            (Some(minor), Some(patch)) => write!(f, ".{minor}.{patch}")?,
+            (Some(minor), None) if self.op == Op::Wildcard => write!(f, ".{minor}.*")?,
+            (Some(minor), None) => write!(f, ".{minor}")?,
+            (None, _) if self.op == Op::Wildcard => write!(f, ".*")?,
+            (None, _) => {}
+        }
+        if !self.pre.is_empty() {
+            write!(f, "-{}", join(&self.pre))?;
+        }
+        Ok(())
+    }
+}
+
+/// A version requirement composed of comma-separated comparators
+/// (for example `>=1.2.0, <2.0.0`), all of which must match.
+/// An empty requirement or `*` matches any release version.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionReq {
+    pub comparators: Vec<Comparator>,
+}
+
+impl VersionReq {
+    pub fn parse(s: &str) -> std::result::Result<Self, VersionError> {
+        let s = s.trim();
+        if s.is_empty() || matches!(s, "*" | "x" | "X") {
+            return Ok(VersionReq::default());
+        }
+        let comparators = s
+            .split(',')
+            .map(Comparator::from_str)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(VersionReq { comparators })
+    }
+
+    /// Check if the version satisfies this requirement. Pre-release
+    /// versions only match if at least one comparator refers to the
+    /// same `major.minor.patch` and carries a pre-release itself.
+    pub fn matches(&self, version: &Version) -> bool {
+        if !self.comparators.iter().all(|cmp| cmp.matches(version)) {
+            return false;
+        }
+
+        if version.pre.is_empty() {
+            return true;
+        }
+
+        self.comparators.iter().any(|cmp| {
+            cmp.major == version.major
+                && cmp.minor == Some(version.minor)
+                && cmp.patch == Some(version.patch)
+                && !cmp.pre.is_empty()
+        })
+    }
+}
+
+impl FromStr for VersionReq {
+    type Err = Error;
 
-        let other = other.as_ref();
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(VersionReq::parse(s)?)
+    }
+}
+
+impl Display for VersionReq {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.comparators.is_empty() {
+            return write!(f, "*");
+        }
+        let comparators = self
+            .comparators
+            .iter()
+            .map(|cmp| cmp.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "{}", comparators.join(", "))
+    }
+}
+
+fn matches_exact(cmp: &Comparator, ver: &Version) -> bool {
+    if ver.major != cmp.major {
+        return false;
+    }
+    if let Some(minor) = cmp.minor {
+        if ver.minor != minor {
+            return false;
+        }
+    }
+    if let Some(patch) = cmp.patch {
+        if ver.patch != patch {
+            return false;
+        }
+    }
+    ver.pre == cmp.pre
+}
+
+fn matches_greater(cmp: &Comparator, ver: &Version) -> bool {
+    if ver.major != cmp.major {
+        return ver.major > cmp.major;
+    }
+    match cmp.minor {
+        None => return false,
+        Some(minor) if ver.minor != minor => return ver.minor > minor,
+        _ => {}
+    }
+    match cmp.patch {
+        None => return false,
+        Some(patch) if ver.patch != patch => return ver.patch > patch,
+        _ => {}
+    }
+    cmp_pre(&ver.pre, &cmp.pre) == Ordering::Greater
+}
+
+fn matches_less(cmp: &Comparator, ver: &Version) -> bool {
+    if ver.major != cmp.major {
+        return ver.major < cmp.major;
+    }
+    match cmp.minor {
+        None => return false,
+        Some(minor) if ver.minor != minor => return ver.minor < minor,
+        _ => {}
+    }
+    match cmp.patch {
+        None => return false,
+        Some(patch) if ver.patch != patch => return ver.patch < patch,
+        _ => {}
+    }
+    cmp_pre(&ver.pre, &cmp.pre) == Ordering::Less
+}
+
+fn matches_tilde(cmp: &Comparator, ver: &Version) -> bool {
+    if ver.major != cmp.major {
+        return false;
+    }
+    if let Some(minor) = cmp.minor {
+        if ver.minor != minor {
+            return false;
+        }
+    }
+    if let Some(patch) = cmp.patch {
+        if ver.patch != patch {
+            return ver.patch > patch;
+        }
+    }
+    cmp_pre(&ver.pre, &cmp.pre) != Ordering::Less
+}
+
+fn matches_caret(cmp: &Comparator, ver: &Version) -> bool {
+    if ver.major != cmp.major {
+        return false;
+    }
+
+    let minor = match cmp.minor {
+        None => return true,
+        Some(minor) => minor,
+    };
+
+    let patch = match cmp.patch {
+        None if cmp.major > 0 => return ver.minor >= minor,
+        None => return ver.minor == minor,
+        Some(patch) => patch,
+    };
+
+    if cmp.major > 0 {
+        if ver.minor != minor {
+            return ver.minor > minor;
+        } else if ver.patch != patch {
+            return ver.patch > patch;
+        }
+    } else if minor > 0 {
+        if ver.minor != minor {
+            return false;
+        } else if ver.patch != patch {
+            return ver.patch > patch;
+        }
+    } else if ver.minor != minor || ver.patch != patch {
+        return false;
+    }
+
+    cmp_pre(&ver.pre, &cmp.pre) != Ordering::Less
+}
+
+/// A version without pre-release identifiers has higher
+/// precedence than the same version with pre-release identifiers.
+fn cmp_pre(a: &[Identifier], b: &[Identifier]) -> Ordering {
+    match (a.is_empty(), b.is_empty()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => a.cmp(b),
+    }
+}
+
+fn join(identifiers: &[Identifier]) -> String {
+    identifiers
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn parse_numeric(s: &str) -> std::result::Result<u64, VersionError> {
+    if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+        return Err(VersionError::InvalidNumber(s.to_string()));
+    }
+    if s.len() > 1 && s.starts_with('0') {
+        return Err(VersionError::LeadingZero(s.to_string()));
+    }
+    s.parse()
+        .map_err(|_| VersionError::InvalidNumber(s.to_string()))
+}
 
-        matches!(
-            (
-                self.major.cmp(&other.major),
-                self.minor.cmp(&other.minor),
-                self.patch.cmp(&other.patch),
-            ),
-            (Ordering::Greater, _, _)
-                | (Ordering::Equal, Ordering::Greater, _)
-                | (Ordering::Equal, Ordering::Equal, Ordering::Greater)
-        )
+fn validate_identifier(s: &str) -> std::result::Result<(), VersionError> {
+    if s.is_empty() {
+        Err(VersionError::EmptyIdentifier)
+    } else if !s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        Err(VersionError::InvalidIdentifier(s.to_string()))
+    } else {
+        Ok(())
     }
 }
 
+fn parse_pre(s: &str) -> std::result::Result<Vec<Identifier>, VersionError> {
+    s.split('.').map(Identifier::from_str).collect()
+}
+
+fn parse_build(s: &str) -> std::result::Result<Vec<String>, VersionError> {
+    s.split('.')
+        .map(|id| validate_identifier(id).map(|_| id.to_string()))
+        .collect()
+}
+
+/// Update manifest expected by [`check_for_update()`].
+///
+/// ```json
+/// { "version": "1.4.0", "url": "https://...", "notes": "..." }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateManifest {
+    pub version: String,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+/// Result of [`check_for_update()`].
+#[derive(Debug, Clone)]
+pub struct UpdateInfo {
+    /// `true` if `latest` is newer than the current version
+    pub available: bool,
+    pub latest: Version,
+    pub url: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl UpdateInfo {
+    pub fn from_manifest<V>(current: V, manifest: UpdateManifest) -> Result<Self>
+    where
+        V: AsRef<Version>,
+    {
+        let latest = Version::parse(&manifest.version)?;
+        Ok(UpdateInfo {
+            available: latest.is_greater_than(current),
+            latest,
+            url: manifest.url,
+            notes: manifest.notes,
+        })
+    }
+}
+
+/// Fetch a JSON [`UpdateManifest`] from `latest_url` and compare
+/// the advertised version against `current`.
+pub async fn check_for_update<V, U>(current: V, latest_url: U) -> Result<UpdateInfo>
+where
+    V: AsRef<Version>,
+    U: Display,
+{
+    let manifest = http::get_json::<UpdateManifest>(latest_url.to_string()).await?;
+    UpdateInfo::from_manifest(current, manifest)
+}
+
 #[derive(Debug, Deserialize)]
 struct CrateResponse {
     #[serde(rename = "crate")]
@@ -122,4 +667,136 @@ pub mod blocking {
             .json::<CrateResponse>()?;
         response.crate_.max_version.parse()
     }
+
+    pub fn check_for_update<V, U>(current: V, latest_url: U) -> Result<UpdateInfo>
+    where
+        V: AsRef<Version>,
+        U: Display,
+    {
+        let manifest = reqwest::blocking::get(latest_url.to_string())?
+            .error_for_status()?
+            .json::<UpdateManifest>()?;
+        UpdateInfo::from_manifest(current, manifest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    fn req(s: &str) -> VersionReq {
+        VersionReq::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_version_parse() {
+        let version = v("1.4.0-beta.2+build.7");
+        assert_eq!((version.major, version.minor, version.patch), (1, 4, 0));
+        assert_eq!(
+            version.pre,
+            vec![
+                Identifier::AlphaNumeric("beta".into()),
+                Identifier::Numeric(2)
+            ]
+        );
+        assert_eq!(version.build, vec!["build".to_string(), "7".to_string()]);
+        assert_eq!(version.to_string(), "1.4.0-beta.2+build.7");
+        assert_eq!(v("v0.18.0"), Version::new(0, 18, 0));
+    }
+
+    #[test]
+    fn test_version_parse_errors() {
+        assert_eq!(Version::parse(""), Err(VersionError::Empty));
+        assert_eq!(
+            Version::parse("1.2"),
+            Err(VersionError::MissingComponent("patch"))
+        );
+        assert_eq!(
+            Version::parse("1.02.3"),
+            Err(VersionError::LeadingZero("02".into()))
+        );
+        assert_eq!(
+            Version::parse("1.2.x"),
+            Err(VersionError::InvalidNumber("x".into()))
+        );
+        assert_eq!(
+            Version::parse("1.2.3-alpha..1"),
+            Err(VersionError::EmptyIdentifier)
+        );
+        assert_eq!(
+            Version::parse("1.2.3-alpha.01"),
+            Err(VersionError::LeadingZero("01".into()))
+        );
+        assert_eq!(
+            Version::parse("1.2.3+b@d"),
+            Err(VersionError::InvalidIdentifier("b@d".into()))
+        );
+        assert!(Version::parse("1.2.3.4").is_err());
+    }
+
+    #[test]
+    fn test_version_prerelease_ordering() {
+        let ordered = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+        ];
+        for pair in ordered.windows(2) {
+            assert!(v(pair[0]) < v(pair[1]), "{} < {}", pair[0], pair[1]);
+            assert!(v(pair[1]).is_greater_than(v(pair[0])));
+        }
+
+        assert!(v("1.4.0-beta.2").is_greater_than(v("1.3.9")));
+        assert_eq!(v("1.0.0+a").cmp_precedence(&v("1.0.0+b")), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_version_req() {
+        assert!(req("^1.2.3").matches(&v("1.9.0")));
+        assert!(!req("^1.2.3").matches(&v("2.0.0")));
+        assert!(!req("^1.2.3").matches(&v("1.2.2")));
+        assert!(req("^0.2.3").matches(&v("0.2.9")));
+        assert!(!req("^0.2.3").matches(&v("0.3.0")));
+        assert!(!req("^0.0.3").matches(&v("0.0.4")));
+
+        assert!(req("~1.2.3").matches(&v("1.2.9")));
+        assert!(!req("~1.2.3").matches(&v("1.3.0")));
+        assert!(req("~1").matches(&v("1.9.9")));
+
+        assert!(req(">=1.2.0, <2.0.0").matches(&v("1.5.0")));
+        assert!(!req(">=1.2.0, <2.0.0").matches(&v("2.0.0")));
+        assert!(req("=1.2.3").matches(&v("1.2.3")));
+        assert!(req("1.2.*").matches(&v("1.2.7")));
+        assert!(req("*").matches(&v("5.0.0")));
+
+        // pre-releases only match comparators that opt into them
+        assert!(!req(">=1.0.0").matches(&v("1.1.0-beta")));
+        assert!(req(">=1.1.0-alpha").matches(&v("1.1.0-beta")));
+
+        assert!(VersionReq::parse("^").is_err());
+        assert!(VersionReq::parse(">=1.*").is_err());
+        assert!(VersionReq::parse("1.*.3").is_err());
+        assert_eq!(req(">= 1.2, < 2").to_string(), ">=1.2, <2");
+    }
+
+    #[test]
+    fn test_update_info() {
+        let manifest = UpdateManifest {
+            version: "1.4.0".into(),
+            url: Some("https://example.com/download".into()),
+            notes: None,
+        };
+        let info = UpdateInfo::from_manifest(v("1.3.9"), manifest).unwrap();
+        assert!(info.available);
+        assert_eq!(info.latest, Version::new(1, 4, 0));
+    }
 }
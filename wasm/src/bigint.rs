@@ -0,0 +1,373 @@
+//!
+//! `BigInt`-safe conversion of `u64`, `i64` and `u128` values.
+//!
+//! JavaScript Numbers are `f64` values, representing integers exactly
+//! only up to [`MAX_SAFE_INTEGER`] (2^53 - 1). The `*_to_jsv()` functions
+//! produce `BigInt` values and the `jsv_to_*()` functions accept a
+//! `BigInt`, a decimal string or a Number, failing with [`BigIntError`]
+//! if the value is out of bounds of the target type or if the Number
+//! is not a safe integer (and hence may have lost its precision).
+//!
+//! Struct fields are converted via `BigInt` by `serde_wasm_bindgen`
+//! (see [`crate::serde`]) when annotated with [`serde`](self::serde):
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Balance {
+//!     #[serde(with = "workflow_wasm::bigint::serde")]
+//!     amount: u64,
+//! }
+//! ```
+//!
+
+use js_sys::BigInt;
+use std::num::{IntErrorKind, ParseIntError};
+use std::str::FromStr;
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+
+/// The largest integer represented exactly by a JavaScript Number (2^53 - 1)
+pub const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BigIntError {
+    #[error("value `{0}` is not a Number, BigInt or a decimal string")]
+    WrongType(String),
+
+    #[error("value `{value}` is out of bounds of `{ty}`")]
+    Overflow { value: String, ty: &'static str },
+
+    #[error("Number `{0}` exceeds the safe integer range (use BigInt or a decimal string)")]
+    Precision(String),
+
+    #[error("Number `{0}` is not an integer")]
+    NotAnInteger(String),
+
+    #[error("invalid decimal string `{0}`")]
+    Parse(String),
+}
+
+/// Integer types converted via `BigInt`
+pub trait BigIntValue: Sized + ToString + FromStr<Err = ParseIntError> + TryFrom<i64> {
+    const NAME: &'static str;
+
+    /// Converts the value into a `BigInt`
+    fn to_jsv(&self) -> JsValue;
+
+    /// Converts a `BigInt`, a decimal string or a Number into the value
+    fn try_from_jsv(jsv: &JsValue) -> Result<Self, BigIntError> {
+        if let Some(f) = jsv.as_f64() {
+            from_f64(f)
+        } else if jsv.is_bigint() {
+            let text = jsv
+                .unchecked_ref::<BigInt>()
+                .to_string(10)
+                .map_err(|_| BigIntError::WrongType(format!("{jsv:?}")))?;
+            parse_decimal(&String::from(text))
+        } else if let Some(text) = jsv.as_string() {
+            parse_decimal(&text)
+        } else {
+            Err(BigIntError::WrongType(format!("{jsv:?}")))
+        }
+    }
+}
+
+impl BigIntValue for u64 {
+    const NAME: &'static str = "u64";
+
+    fn to_jsv(&self) -> JsValue {
+        JsValue::from(*self)
+    }
+}
+
+impl BigIntValue for i64 {
+    const NAME: &'static str = "i64";
+
+    fn to_jsv(&self) -> JsValue {
+        JsValue::from(*self)
+    }
+}
+
+impl BigIntValue for u128 {
+    const NAME: &'static str = "u128";
+
+    fn to_jsv(&self) -> JsValue {
+        BigInt::new(&JsValue::from_str(&self.to_string()))
+            .expect("BigInt from a decimal string")
+            .into()
+    }
+}
+
+/// Parses the decimal string, distinguishing values out of
+/// bounds of `T` (including negative values of unsigned types).
+pub(crate) fn parse_decimal<T: BigIntValue>(text: &str) -> Result<T, BigIntError> {
+    text.parse::<T>().map_err(|err| {
+        let negative = text
+            .strip_prefix('-')
+            .is_some_and(|digits| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()));
+        match err.kind() {
+            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => BigIntError::Overflow {
+                value: text.to_string(),
+                ty: T::NAME,
+            },
+            IntErrorKind::InvalidDigit if negative => BigIntError::Overflow {
+                value: text.to_string(),
+                ty: T::NAME,
+            },
+            _ => BigIntError::Parse(text.to_string()),
+        }
+    })
+}
+
+/// Converts the Number, accepting only integers within the safe range.
+pub(crate) fn from_f64<T: BigIntValue>(f: f64) -> Result<T, BigIntError> {
+    if !f.is_finite() || f.fract() != 0.0 {
+        Err(BigIntError::NotAnInteger(f.to_string()))
+    } else if f.abs() > MAX_SAFE_INTEGER {
+        Err(BigIntError::Precision(f.to_string()))
+    } else {
+        T::try_from(f as i64).map_err(|_| BigIntError::Overflow {
+            value: f.to_string(),
+            ty: T::NAME,
+        })
+    }
+}
+
+/// Converts `u64` into a `BigInt`
+pub fn u64_to_jsv(value: u64) -> JsValue {
+    value.to_jsv()
+}
+
+/// Converts `i64` into a `BigInt`
+pub fn i64_to_jsv(value: i64) -> JsValue {
+    value.to_jsv()
+}
+
+/// Converts `u128` into a `BigInt`
+pub fn u128_to_jsv(value: u128) -> JsValue {
+    value.to_jsv()
+}
+
+/// Converts a `BigInt`, a decimal string or a safe integer Number into `u64`
+pub fn jsv_to_u64(jsv: &JsValue) -> Result<u64, BigIntError> {
+    u64::try_from_jsv(jsv)
+}
+
+/// Converts a `BigInt`, a decimal string or a safe integer Number into `i64`
+pub fn jsv_to_i64(jsv: &JsValue) -> Result<i64, BigIntError> {
+    i64::try_from_jsv(jsv)
+}
+
+/// Converts a `BigInt`, a decimal string or a safe integer Number into `u128`
+pub fn jsv_to_u128(jsv: &JsValue) -> Result<u128, BigIntError> {
+    u128::try_from_jsv(jsv)
+}
+
+pub mod serde {
+    //!
+    //! `#[serde(with = "workflow_wasm::bigint::serde")]` support for `u64`,
+    //! `i64` and `u128` fields: values are serialized as `BigInt` and
+    //! deserialized from a `BigInt`, a decimal string or a safe integer
+    //! Number. Supported only by the `serde_wasm_bindgen` serializers.
+    //!
+
+    use super::BigIntValue;
+    use ::serde::de::Error as _;
+    use ::serde::{Deserializer, Serializer};
+    use wasm_bindgen::JsValue;
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: BigIntValue,
+        S: Serializer,
+    {
+        serde_wasm_bindgen::preserve::serialize(&value.to_jsv(), serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: BigIntValue,
+        D: Deserializer<'de>,
+    {
+        let jsv: JsValue = serde_wasm_bindgen::preserve::deserialize(deserializer)?;
+        T::try_from_jsv(&jsv).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAFE: u64 = 1 << 53;
+
+    /// Values around the safe integer boundary and the type limits
+    fn boundary_values() -> Vec<i128> {
+        let mut values = vec![];
+        for base in [
+            0,
+            SAFE as i128,
+            -(SAFE as i128),
+            u64::MAX as i128,
+            i64::MAX as i128,
+            i64::MIN as i128,
+        ] {
+            values.extend((-3..=3).map(|delta| base + delta));
+        }
+        // xorshift sampling of the full range
+        let mut x = 0x2545_f491_4f6c_dd1d_u64;
+        for _ in 0..1000 {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            values.push(x as i128);
+            values.push(x as i64 as i128);
+            values.push((x >> 11) as i128);
+        }
+        values
+    }
+
+    #[test]
+    fn test_parse_decimal() {
+        for value in boundary_values() {
+            let text = value.to_string();
+            match u64::try_from(value) {
+                Ok(expected) => assert_eq!(parse_decimal::<u64>(&text), Ok(expected)),
+                Err(_) => assert!(matches!(
+                    parse_decimal::<u64>(&text),
+                    Err(BigIntError::Overflow { ty: "u64", .. })
+                )),
+            }
+            match i64::try_from(value) {
+                Ok(expected) => assert_eq!(parse_decimal::<i64>(&text), Ok(expected)),
+                Err(_) => assert!(matches!(
+                    parse_decimal::<i64>(&text),
+                    Err(BigIntError::Overflow { ty: "i64", .. })
+                )),
+            }
+        }
+
+        assert_eq!(parse_decimal::<u128>(&u128::MAX.to_string()), Ok(u128::MAX));
+        assert!(matches!(
+            parse_decimal::<u128>("340282366920938463463374607431768211456"),
+            Err(BigIntError::Overflow { ty: "u128", .. })
+        ));
+        for text in ["", "-", "12a", "1.5", "0x10", " 1"] {
+            assert_eq!(
+                parse_decimal::<u64>(text),
+                Err(BigIntError::Parse(text.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_f64() {
+        for value in boundary_values() {
+            let f = value as f64;
+            // the conversion to f64 is lossless within the safe range
+            if value.unsigned_abs() < SAFE as u128 {
+                assert_eq!(from_f64::<i64>(f), Ok(value as i64));
+                assert_eq!(from_f64::<u64>(f).ok(), u64::try_from(value).ok());
+                assert_eq!(from_f64::<u128>(f).ok(), u128::try_from(value).ok());
+            } else {
+                assert_eq!(
+                    from_f64::<u64>(f),
+                    Err(BigIntError::Precision(f.to_string()))
+                );
+                assert_eq!(
+                    from_f64::<i64>(f),
+                    Err(BigIntError::Precision(f.to_string()))
+                );
+            }
+        }
+
+        assert_eq!(from_f64::<u64>(MAX_SAFE_INTEGER), Ok(SAFE - 1));
+        assert!(matches!(
+            from_f64::<u64>(-1.0),
+            Err(BigIntError::Overflow { ty: "u64", .. })
+        ));
+        for f in [0.5, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                from_f64::<u64>(f),
+                Err(BigIntError::NotAnInteger(_))
+            ));
+        }
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use ::serde::{Deserialize, Serialize};
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_bigint_round_trip() {
+        let safe = 1u64 << 53;
+        for value in [0, 1, safe - 1, safe, safe + 1, u64::MAX - 1, u64::MAX] {
+            let jsv = u64_to_jsv(value);
+            assert!(jsv.is_bigint());
+            assert_eq!(jsv_to_u64(&jsv), Ok(value));
+            assert_eq!(
+                jsv_to_u64(&JsValue::from_str(&value.to_string())),
+                Ok(value)
+            );
+        }
+        for value in [i64::MIN, -(1 << 53) - 1, -1, 0, (1 << 53) + 1, i64::MAX] {
+            assert_eq!(jsv_to_i64(&i64_to_jsv(value)), Ok(value));
+        }
+        for value in [0, u64::MAX as u128 + 1, u128::MAX] {
+            assert_eq!(jsv_to_u128(&u128_to_jsv(value)), Ok(value));
+        }
+
+        assert_eq!(
+            jsv_to_u64(&JsValue::from_f64(MAX_SAFE_INTEGER)),
+            Ok(safe - 1)
+        );
+        assert!(matches!(
+            jsv_to_u64(&JsValue::from_f64((safe + 2) as f64)),
+            Err(BigIntError::Precision(_))
+        ));
+        assert!(matches!(
+            jsv_to_u64(&i64_to_jsv(-1)),
+            Err(BigIntError::Overflow { ty: "u64", .. })
+        ));
+        assert!(matches!(
+            jsv_to_i64(&u128_to_jsv(u64::MAX as u128)),
+            Err(BigIntError::Overflow { ty: "i64", .. })
+        ));
+        assert!(matches!(
+            jsv_to_u64(&JsValue::TRUE),
+            Err(BigIntError::WrongType(_))
+        ));
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Balance {
+        #[serde(with = "super::serde")]
+        amount: u64,
+        #[serde(with = "super::serde")]
+        delta: i64,
+        #[serde(with = "super::serde")]
+        total: u128,
+    }
+
+    #[wasm_bindgen_test]
+    fn test_bigint_serde() {
+        let balance = Balance {
+            amount: u64::MAX,
+            delta: -(1 << 53) - 1,
+            total: u128::MAX,
+        };
+        let jsv = crate::serde::to_value(&balance).unwrap();
+        let amount = js_sys::Reflect::get(&jsv, &"amount".into()).unwrap();
+        assert!(amount.is_bigint());
+        assert_eq!(crate::serde::from_value::<Balance>(jsv).unwrap(), balance);
+
+        // Numbers beyond the safe range are rejected
+        let jsv = crate::serde::to_value(&balance).unwrap();
+        js_sys::Reflect::set(&jsv, &"amount".into(), &JsValue::from_f64(u64::MAX as f64)).unwrap();
+        assert!(crate::serde::from_value::<Balance>(jsv).is_err());
+    }
+}
@@ -59,6 +59,18 @@ pub enum Error {
 
     #[error("object constructor `{0}` does not match expected class `{1}`")]
     ClassConstructorMatch(String, String),
+
+    #[error("storage quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    #[error("storage API `{0}` is not available")]
+    StorageNotAvailable(String),
+
+    #[error("transaction aborted")]
+    TransactionAborted,
+
+    #[error(transparent)]
+    BigInt(#[from] crate::bigint::BigIntError),
 }
 
 impl From<Error> for JsValue {
@@ -12,6 +12,7 @@
 
 extern crate self as workflow_wasm;
 
+pub mod bigint;
 pub mod callback;
 pub mod convert;
 pub mod error;
@@ -22,8 +23,10 @@ pub mod options;
 pub mod panic;
 pub mod prelude;
 pub mod printable;
+pub mod raf;
 pub mod result;
 pub mod serde;
+pub mod storage;
 pub mod utils;
 
 #[cfg(feature = "defer")]
@@ -4,9 +4,24 @@
 //! information output in case of a panic - useful on mobile devices or where
 //! the user otherwise has no access to console/developer tools)
 //!
+//! Following a panic, the WASM instance is no longer usable. The JS
+//! application can register callbacks receiving the panic report
+//! ([`set_panic_callback()`]), poll the panic state ([`has_panicked()`])
+//! and retrieve the report persisted in `localStorage` on the next
+//! page load ([`persist_panic_reports()`]). Reports are produced by
+//! `workflow-panic-hook` and include the recorded breadcrumbs.
+//!
 
+use crate::callback::{AsCallback, CallbackId, CallbackMap};
+use crate::storage::LocalStorage;
+use js_sys::{Array, Function, Object, Reflect, JSON};
+use std::sync::{Arc, OnceLock};
 use wasm_bindgen::prelude::*;
-use workflow_panic_hook::{set_once, show_logs as show_wasm_logs, Type};
+use workflow_log::log_error;
+use workflow_panic_hook::{
+    add_panic_observer, last_panic as last_panic_report, set_once, show_logs as show_wasm_logs,
+    PanicReport, Type,
+};
 
 /// Initialize Rust panic handler in console mode.
 ///
@@ -43,3 +58,234 @@ pub fn init_browser_panic_hook() {
 pub fn show_panic_hook_logs() {
     show_wasm_logs();
 }
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_PANIC_REPORT: &'static str = r#"
+/**
+ * Report of a Rust panic supplied to the callbacks registered
+ * using {@link setPanicCallback}.
+ *
+ * @category General
+ */
+export interface IPanicReport {
+    /** Panic message */
+    message : string,
+    /** Source location as `file:line:column` */
+    location? : string,
+    /** JavaScript stack */
+    stack : string,
+    /** Milliseconds since the UNIX epoch */
+    timestamp : number,
+    /** Breadcrumbs recorded before the panic, oldest first */
+    breadcrumbs : string[],
+}
+"#;
+
+/// Default `localStorage` key of [`persist_panic_reports()`]
+pub const PANIC_REPORT_KEY: &str = "workflow-panic-report";
+
+/// JS function retained in the panic callback map
+struct PanicCallback {
+    id: CallbackId,
+    function: Function,
+}
+
+unsafe impl Send for PanicCallback {}
+unsafe impl Sync for PanicCallback {}
+
+impl AsCallback for PanicCallback {
+    fn get_id(&self) -> CallbackId {
+        self.id
+    }
+
+    fn get_fn(&self) -> &Function {
+        &self.function
+    }
+}
+
+static PANIC_CALLBACKS: OnceLock<CallbackMap> = OnceLock::new();
+
+fn panic_callbacks() -> &'static CallbackMap {
+    PANIC_CALLBACKS.get_or_init(|| {
+        let callbacks = CallbackMap::new();
+        let callbacks_ = callbacks.clone();
+        add_panic_observer(Arc::new(move |report: &PanicReport| {
+            let report = panic_report_to_js(report);
+            let functions = callbacks_
+                .inner()
+                .values()
+                .map(|callback| callback.get_fn().clone())
+                .collect::<Vec<_>>();
+            for function in functions {
+                // an exception thrown by the callback must not mask the panic
+                if let Err(err) = function.call1(&JsValue::UNDEFINED, &report) {
+                    log_error!("panic callback error: {:?}", err);
+                }
+            }
+        }));
+        callbacks
+    })
+}
+
+/// Converts the [`PanicReport`] to the `IPanicReport` JS object.
+pub fn panic_report_to_js(report: &PanicReport) -> JsValue {
+    let object = Object::new();
+    let breadcrumbs = report
+        .breadcrumbs
+        .iter()
+        .map(|breadcrumb| JsValue::from(breadcrumb.as_str()))
+        .collect::<Array>();
+    let properties = [
+        ("message", JsValue::from(report.message.as_str())),
+        (
+            "location",
+            report
+                .location
+                .as_deref()
+                .map(JsValue::from)
+                .unwrap_or(JsValue::UNDEFINED),
+        ),
+        ("stack", JsValue::from(report.stack.as_str())),
+        ("timestamp", JsValue::from(report.timestamp as f64)),
+        ("breadcrumbs", breadcrumbs.into()),
+    ];
+    for (key, value) in properties {
+        Reflect::set(&object, &key.into(), &value).ok();
+    }
+    object.into()
+}
+
+/// Register a callback invoked with the {@link IPanicReport} object
+/// when a Rust panic occurs (after which the WASM instance should be
+/// considered unusable and reloaded). Multiple callbacks can be
+/// registered; exceptions thrown by the callbacks are logged to the
+/// console. Returns the callback id accepted by {@link removePanicCallback}.
+/// @see {@link hasPanicked}
+/// @category General
+#[wasm_bindgen(js_name = "setPanicCallback")]
+pub fn set_panic_callback(callback: Function) -> Result<String, JsValue> {
+    let id = CallbackId::new();
+    panic_callbacks().retain(PanicCallback {
+        id,
+        function: callback,
+    })?;
+    Ok(id.to_string())
+}
+
+/// Remove a callback registered using {@link setPanicCallback}.
+/// @category General
+#[wasm_bindgen(js_name = "removePanicCallback")]
+pub fn remove_panic_callback(id: &str) -> Result<(), JsValue> {
+    let id = id
+        .parse::<CallbackId>()
+        .map_err(|err| JsValue::from(err.to_string()))?;
+    panic_callbacks().remove(&id)?;
+    Ok(())
+}
+
+/// Returns `true` if a Rust panic has occurred.
+/// @category General
+#[wasm_bindgen(js_name = "hasPanicked")]
+pub fn has_panicked() -> bool {
+    workflow_panic_hook::has_panicked()
+}
+
+/// Returns the {@link IPanicReport} of the most recent Rust panic
+/// or `undefined` if no panic has occurred.
+/// @category General
+#[wasm_bindgen(js_name = "lastPanic")]
+pub fn last_panic() -> JsValue {
+    last_panic_report()
+        .map(|report| panic_report_to_js(&report))
+        .unwrap_or(JsValue::UNDEFINED)
+}
+
+/// Store the report of a Rust panic in `localStorage` (under the
+/// `workflow-panic-report` key by default), allowing the application
+/// to retrieve it on the next page load using {@link takePersistedPanic}.
+/// @category General
+#[wasm_bindgen(js_name = "persistPanicReports")]
+pub fn persist_panic_reports(key: Option<String>) {
+    let key = key.unwrap_or_else(|| PANIC_REPORT_KEY.to_string());
+    add_panic_observer(Arc::new(move |report: &PanicReport| {
+        if let Ok(json) = JSON::stringify(&panic_report_to_js(report)) {
+            if let Some(json) = json.as_string() {
+                LocalStorage::set(&key, &json).ok();
+            }
+        }
+    }));
+}
+
+/// Returns the {@link IPanicReport} stored by {@link persistPanicReports}
+/// (removing it from `localStorage`) or `undefined` if none is stored.
+/// @category General
+#[wasm_bindgen(js_name = "takePersistedPanic")]
+pub fn take_persisted_panic(key: Option<String>) -> Result<JsValue, JsValue> {
+    let key = key.unwrap_or_else(|| PANIC_REPORT_KEY.to_string());
+    match LocalStorage::get(&key)? {
+        Some(json) => {
+            LocalStorage::remove(&key)?;
+            JSON::parse(&json)
+        }
+        None => Ok(JsValue::UNDEFINED),
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn get(object: &JsValue, key: &str) -> JsValue {
+        Reflect::get(object, &key.into()).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_panic_callback() {
+        const KEY: &str = "workflow-wasm-test-panic-report";
+        init_console_panic_hook();
+        persist_panic_reports(Some(KEY.to_string()));
+        assert!(!has_panicked());
+
+        let reports = Array::new();
+        let collect = Function::new_with_args("reports", "return (report) => reports.push(report)")
+            .call1(&JsValue::UNDEFINED, &reports)
+            .unwrap();
+        let failing = Function::new_with_args("report", "throw new Error('callback failure')");
+        let removed = Function::new_with_args("report", "throw new Error('removed callback')");
+        set_panic_callback(failing).unwrap();
+        set_panic_callback(collect.into()).unwrap();
+        let id = set_panic_callback(removed).unwrap();
+        remove_panic_callback(&id).unwrap();
+
+        // the panic traps the closure invocation, caught by the JS caller
+        let trigger = Closure::<dyn Fn()>::new(|| panic!("panic {}", "test"));
+        let err = Function::new_with_args(
+            "trigger",
+            "try { trigger(); return null; } catch (err) { return err; }",
+        )
+        .call1(&JsValue::UNDEFINED, trigger.as_ref())
+        .unwrap();
+        // the exception thrown by the callback does not mask the panic
+        assert!(err.is_instance_of::<js_sys::WebAssembly::RuntimeError>());
+
+        assert!(has_panicked());
+        assert_eq!(reports.length(), 1);
+        let report = reports.get(0);
+        assert_eq!(get(&report, "message"), "panic test");
+        assert!(get(&report, "location")
+            .as_string()
+            .unwrap()
+            .contains("panic.rs"));
+        assert!(get(&report, "timestamp").as_f64().unwrap() > 0.0);
+        assert_eq!(get(&last_panic(), "message"), "panic test");
+
+        let persisted = take_persisted_panic(Some(KEY.to_string())).unwrap();
+        assert_eq!(get(&persisted, "message"), "panic test");
+        assert!(take_persisted_panic(Some(KEY.to_string()))
+            .unwrap()
+            .is_undefined());
+    }
+}
@@ -0,0 +1,404 @@
+//!
+//! [`RafLoop`] - animation (render) loop driven by the JavaScript
+//! `requestAnimationFrame()` and `cancelAnimationFrame()` APIs.
+//!
+//! ```ignore
+//! let raf = RafLoop::with_options(
+//!     |timestamp, delta| render(timestamp, delta),
+//!     RafOptions::default().with_fps(30.0),
+//! );
+//! raf.start()?;
+//! ...
+//! raf.stop()?;
+//! ```
+//!
+
+use crate::result::Result;
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(catch, js_name = requestAnimationFrame)]
+    fn request_animation_frame(
+        callback: &Closure<dyn FnMut(f64)>,
+    ) -> std::result::Result<i32, JsValue>;
+    #[wasm_bindgen(catch, js_name = cancelAnimationFrame)]
+    fn cancel_animation_frame(handle: i32) -> std::result::Result<(), JsValue>;
+
+    #[wasm_bindgen(extends = js_sys::Object)]
+    type Document;
+    #[wasm_bindgen(method, getter)]
+    fn hidden(this: &Document) -> bool;
+    #[wasm_bindgen(method, catch, js_name = addEventListener)]
+    fn add_event_listener(
+        this: &Document,
+        event: &str,
+        listener: &Closure<dyn FnMut()>,
+    ) -> std::result::Result<(), JsValue>;
+    #[wasm_bindgen(method, catch, js_name = removeEventListener)]
+    fn remove_event_listener(
+        this: &Document,
+        event: &str,
+        listener: &Closure<dyn FnMut()>,
+    ) -> std::result::Result<(), JsValue>;
+}
+
+/// Global `document` object, if present (not available in web workers and Node.js)
+fn document() -> Option<Document> {
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("document"))
+        .ok()
+        .filter(|document| document.is_object())
+        .map(JsCast::unchecked_into)
+}
+
+/// Frame timestamp jitter tolerated when throttling
+/// the loop to the target FPS (in milliseconds)
+const FPS_TOLERANCE: f64 = 1.0;
+
+/// [`RafLoop`] configuration options
+#[derive(Debug, Clone)]
+pub struct RafOptions {
+    fps: Option<f64>,
+    pause_when_hidden: bool,
+}
+
+impl Default for RafOptions {
+    fn default() -> Self {
+        Self {
+            fps: None,
+            pause_when_hidden: true,
+        }
+    }
+}
+
+impl RafOptions {
+    /// Target frame rate. Frames are skipped to approximate
+    /// the target budget (the display refresh rate is the upper bound).
+    pub fn with_fps(mut self, fps: f64) -> Self {
+        assert!(fps > 0.0, "RafOptions: fps must be greater than 0");
+        self.fps = Some(fps);
+        self
+    }
+
+    /// Pause the loop while the document is hidden (Page Visibility API)
+    /// and resume once it becomes visible again. Enabled by default.
+    pub fn with_pause_when_hidden(mut self, pause_when_hidden: bool) -> Self {
+        self.pause_when_hidden = pause_when_hidden;
+        self
+    }
+
+    fn interval(&self) -> Option<f64> {
+        self.fps.map(|fps| 1000.0 / fps)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Stopped,
+    Running,
+    Paused,
+}
+
+struct Visibility {
+    document: Document,
+    listener: Closure<dyn FnMut()>,
+}
+
+struct State {
+    status: Status,
+    hidden: bool,
+    handle: Option<i32>,
+    /// Timestamp the throttling interval is measured from
+    last: Option<f64>,
+    /// Timestamp of the last frame delivered to the callback
+    previous: Option<f64>,
+    frame: Option<Closure<dyn FnMut(f64)>>,
+    visibility: Option<Visibility>,
+}
+
+impl State {
+    fn cancel(&mut self) -> Result<()> {
+        if let Some(handle) = self.handle.take() {
+            cancel_animation_frame(handle)?;
+        }
+        Ok(())
+    }
+
+    fn request(&mut self) -> Result<()> {
+        if self.handle.is_none() && self.status == Status::Running && !self.hidden {
+            if let Some(frame) = self.frame.as_ref() {
+                self.handle = Some(request_animation_frame(frame)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// The frame following a start, resume or visibility change only
+    /// establishes the timing baseline, so that the time during which
+    /// the loop was inactive is not reported as a frame delta.
+    fn reset(&mut self) {
+        self.last = None;
+        self.previous = None;
+    }
+
+    fn release(&mut self) -> Result<()> {
+        self.cancel()?;
+        self.frame.take();
+        if let Some(Visibility { document, listener }) = self.visibility.take() {
+            document.remove_event_listener("visibilitychange", &listener)?;
+        }
+        Ok(())
+    }
+}
+
+struct Inner {
+    options: RafOptions,
+    state: RefCell<State>,
+    callback: RefCell<Box<dyn FnMut(f64, f64)>>,
+}
+
+impl Inner {
+    fn frame(&self, timestamp: f64) {
+        let delta = {
+            let mut state = self.state.borrow_mut();
+            state.handle = None;
+            if state.status != Status::Running {
+                return;
+            }
+            // request the next frame before invoking the callback
+            // so that the callback can pause or stop the loop
+            if let Err(err) = state.request() {
+                workflow_log::log_error!("RafLoop: unable to request animation frame: {err}");
+            }
+
+            let Some(last) = state.last else {
+                state.last = Some(timestamp);
+                state.previous = Some(timestamp);
+                return;
+            };
+
+            let elapsed = timestamp - last;
+            match self.options.interval() {
+                Some(interval) if elapsed < interval - FPS_TOLERANCE => return,
+                Some(interval) => {
+                    let excess = (elapsed - interval).max(0.0) % interval;
+                    state.last = Some(timestamp - excess);
+                }
+                None => state.last = Some(timestamp),
+            }
+
+            let delta = timestamp - state.previous.unwrap_or(last);
+            state.previous = Some(timestamp);
+            delta
+        };
+
+        if delta > 0.0 {
+            (self.callback.borrow_mut())(timestamp, delta);
+        }
+    }
+
+    fn visibility_change(&self) {
+        let mut state = self.state.borrow_mut();
+        let Some(visibility) = state.visibility.as_ref() else {
+            return;
+        };
+        let hidden = visibility.document.hidden();
+        state.hidden = hidden;
+        let result = if hidden {
+            state.cancel()
+        } else {
+            state.reset();
+            state.request()
+        };
+        if let Err(err) = result {
+            workflow_log::log_error!("RafLoop: visibility change error: {err}");
+        }
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        self.state.get_mut().release().ok();
+    }
+}
+
+///
+/// Animation loop invoking the callback with the frame timestamp and the
+/// time elapsed since the previous frame (both in milliseconds) on each
+/// `requestAnimationFrame()` cycle. The loop is inactive until [`RafLoop::start()`]
+/// is called and is stopped when [`RafLoop::stop()`] is called or when all clones
+/// of the `RafLoop` are dropped.
+///
+/// By default, the loop is paused while the document is hidden; this can be
+/// changed via [`RafOptions::with_pause_when_hidden()`].
+///
+#[derive(Clone)]
+pub struct RafLoop {
+    inner: Rc<Inner>,
+}
+
+impl RafLoop {
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: FnMut(f64, f64) + 'static,
+    {
+        Self::with_options(callback, RafOptions::default())
+    }
+
+    pub fn with_options<F>(callback: F, options: RafOptions) -> Self
+    where
+        F: FnMut(f64, f64) + 'static,
+    {
+        RafLoop {
+            inner: Rc::new(Inner {
+                options,
+                state: RefCell::new(State {
+                    status: Status::Stopped,
+                    hidden: false,
+                    handle: None,
+                    last: None,
+                    previous: None,
+                    frame: None,
+                    visibility: None,
+                }),
+                callback: RefCell::new(Box::new(callback)),
+            }),
+        }
+    }
+
+    /// Start the loop. Has no effect if the loop is already running or paused.
+    pub fn start(&self) -> Result<()> {
+        let mut state = self.inner.state.borrow_mut();
+        if state.status != Status::Stopped {
+            return Ok(());
+        }
+
+        let inner: Weak<Inner> = Rc::downgrade(&self.inner);
+        state.frame = Some(Closure::new(move |timestamp: f64| {
+            if let Some(inner) = inner.upgrade() {
+                inner.frame(timestamp);
+            }
+        }));
+
+        if self.inner.options.pause_when_hidden {
+            if let Some(document) = document() {
+                let inner: Weak<Inner> = Rc::downgrade(&self.inner);
+                let listener = Closure::new(move || {
+                    if let Some(inner) = inner.upgrade() {
+                        inner.visibility_change();
+                    }
+                });
+                document.add_event_listener("visibilitychange", &listener)?;
+                state.hidden = document.hidden();
+                state.visibility = Some(Visibility { document, listener });
+            }
+        }
+
+        state.status = Status::Running;
+        state.reset();
+        state.request()
+    }
+
+    /// Pause the loop, cancelling the pending frame
+    pub fn pause(&self) -> Result<()> {
+        let mut state = self.inner.state.borrow_mut();
+        if state.status == Status::Running {
+            state.status = Status::Paused;
+            state.cancel()?;
+        }
+        Ok(())
+    }
+
+    /// Resume the paused loop
+    pub fn resume(&self) -> Result<()> {
+        let mut state = self.inner.state.borrow_mut();
+        if state.status == Status::Paused {
+            state.status = Status::Running;
+            state.reset();
+            state.request()?;
+        }
+        Ok(())
+    }
+
+    /// Stop the loop, cancelling the pending frame and releasing the
+    /// JavaScript closures. The loop can be restarted using [`RafLoop::start()`].
+    pub fn stop(&self) -> Result<()> {
+        let mut state = self.inner.state.borrow_mut();
+        state.status = Status::Stopped;
+        state.hidden = false;
+        state.release()
+    }
+
+    /// `true` if the loop has been started and is not paused
+    /// (the loop can still be suspended while the document is hidden)
+    pub fn is_running(&self) -> bool {
+        self.inner.state.borrow().status == Status::Running
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.inner.state.borrow().status == Status::Paused
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use std::time::Duration;
+    use wasm_bindgen_test::*;
+    use workflow_core::task::sleep;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn counting_loop(options: RafOptions) -> (RafLoop, Rc<RefCell<Vec<f64>>>) {
+        let deltas = Rc::new(RefCell::new(Vec::new()));
+        let deltas_ = deltas.clone();
+        let raf = RafLoop::with_options(
+            move |_timestamp, delta| deltas_.borrow_mut().push(delta),
+            options.with_pause_when_hidden(false),
+        );
+        (raf, deltas)
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_raf_loop_stop() {
+        let (raf, deltas) = counting_loop(RafOptions::default());
+        raf.start().unwrap();
+        sleep(Duration::from_millis(250)).await;
+        raf.stop().unwrap();
+        assert!(!raf.is_running());
+
+        let frames = deltas.borrow().len();
+        assert!(frames > 0, "callback was not invoked");
+        assert!(deltas.borrow().iter().all(|delta| *delta > 0.0));
+
+        sleep(Duration::from_millis(250)).await;
+        assert_eq!(deltas.borrow().len(), frames);
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_raf_loop_pause_resume() {
+        let (raf, deltas) = counting_loop(RafOptions::default().with_fps(20.0));
+        raf.start().unwrap();
+        sleep(Duration::from_millis(250)).await;
+        raf.pause().unwrap();
+        assert!(raf.is_paused());
+
+        let frames = deltas.borrow().len();
+        assert!(frames > 0, "callback was not invoked");
+        sleep(Duration::from_millis(250)).await;
+        assert_eq!(deltas.borrow().len(), frames);
+
+        raf.resume().unwrap();
+        sleep(Duration::from_millis(250)).await;
+        raf.stop().unwrap();
+        assert!(deltas.borrow().len() > frames);
+        // deltas span the throttling interval (50ms) and do not include the pause
+        assert!(deltas
+            .borrow()
+            .iter()
+            .all(|delta| *delta > 0.0 && *delta < 250.0));
+    }
+}
@@ -1,5 +1,7 @@
 //! `to_value` utility that serializes data to a [`JsValue`] using
 //! `serde_wasm_bindgen` with `BigInt` serialization enabled.
+//! Fields annotated with [`bigint::serde`](crate::bigint::serde) are
+//! also deserialized from `BigInt` values and decimal strings.
 pub use serde_wasm_bindgen::*;
 use wasm_bindgen::JsValue;
 type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,823 @@
+//!
+//! [`IdbDatabase`] - promise-based typed access to IndexedDB.
+//!
+//! ```ignore
+//! let db = IdbDatabase::open("app", 2, |upgrade| {
+//!     if upgrade.old_version() < 1 {
+//!         upgrade.create_store("accounts", StoreOptions::default())?;
+//!     }
+//!     if upgrade.old_version() < 2 {
+//!         upgrade.create_store("settings", StoreOptions::default())?;
+//!     }
+//!     Ok(())
+//! })
+//! .await?;
+//!
+//! let accounts = db.store("accounts", TransactionMode::ReadWrite)?;
+//! accounts.put_with_key("alice", &account).await?;
+//! let account = accounts.get::<_, Account>("alice").await?;
+//!
+//! db.with_transaction(&["accounts", "settings"], TransactionMode::ReadWrite, |tx| async move {
+//!     tx.store("accounts")?.delete("alice").await?;
+//!     tx.store("settings")?.put_with_key("last", &"bob").await?;
+//!     Ok(())
+//! })
+//! .await?;
+//! ```
+//!
+//! IndexedDB transactions commit automatically once no requests are
+//! pending, as such, only IndexedDB operations should be awaited while
+//! a transaction is in use.
+//!
+//! All operations are cancel-safe: dropping a pending future detaches
+//! its event handlers, an abandoned [`IdbDatabase::open()`] closes the
+//! database once opened (or aborts the upgrade) and an abandoned
+//! [`IdbDatabase::with_transaction()`] aborts the transaction.
+//!
+
+use super::{from_value, global_storage, storage_error, to_key, to_value};
+use crate::error::Error;
+use crate::result::Result;
+use futures::Future;
+use serde::{de::DeserializeOwned, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use workflow_core::channel::{oneshot, Channel};
+use workflow_log::log_warn;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(extends = js_sys::Object)]
+    type Factory;
+    #[wasm_bindgen(method, catch)]
+    fn open(this: &Factory, name: &str, version: u32) -> std::result::Result<OpenRequest, JsValue>;
+    #[wasm_bindgen(method, catch, js_name = deleteDatabase)]
+    fn delete_database(this: &Factory, name: &str) -> std::result::Result<OpenRequest, JsValue>;
+
+    #[wasm_bindgen(extends = js_sys::Object)]
+    #[derive(Clone)]
+    type Request;
+    #[wasm_bindgen(method, getter, catch)]
+    fn result(this: &Request) -> std::result::Result<JsValue, JsValue>;
+    #[wasm_bindgen(method, getter, catch)]
+    fn error(this: &Request) -> std::result::Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(extends = Request)]
+    #[derive(Clone)]
+    type OpenRequest;
+    #[wasm_bindgen(method, getter)]
+    fn transaction(this: &OpenRequest) -> Option<Transaction>;
+
+    #[wasm_bindgen(extends = js_sys::Object)]
+    #[derive(Clone)]
+    type Database;
+    #[wasm_bindgen(method, getter)]
+    fn name(this: &Database) -> String;
+    #[wasm_bindgen(method, getter)]
+    fn version(this: &Database) -> f64;
+    #[wasm_bindgen(method, getter, js_name = objectStoreNames)]
+    fn object_store_names(this: &Database) -> JsValue;
+    #[wasm_bindgen(method, catch, js_name = createObjectStore)]
+    fn create_object_store(
+        this: &Database,
+        name: &str,
+        options: &js_sys::Object,
+    ) -> std::result::Result<ObjectStore, JsValue>;
+    #[wasm_bindgen(method, catch, js_name = deleteObjectStore)]
+    fn delete_object_store(this: &Database, name: &str) -> std::result::Result<(), JsValue>;
+    #[wasm_bindgen(method, catch, js_name = transaction)]
+    fn transaction(
+        this: &Database,
+        stores: &js_sys::Array,
+        mode: &str,
+    ) -> std::result::Result<Transaction, JsValue>;
+    #[wasm_bindgen(method)]
+    fn close(this: &Database);
+
+    #[wasm_bindgen(extends = js_sys::Object)]
+    #[derive(Clone)]
+    type Transaction;
+    #[wasm_bindgen(method, catch, js_name = objectStore)]
+    fn object_store(this: &Transaction, name: &str) -> std::result::Result<ObjectStore, JsValue>;
+    #[wasm_bindgen(method, catch)]
+    fn abort(this: &Transaction) -> std::result::Result<(), JsValue>;
+    #[wasm_bindgen(method, getter)]
+    fn error(this: &Transaction) -> JsValue;
+
+    #[wasm_bindgen(extends = js_sys::Object)]
+    #[derive(Clone)]
+    type ObjectStore;
+    #[wasm_bindgen(method, getter)]
+    fn name(this: &ObjectStore) -> String;
+    #[wasm_bindgen(method, catch)]
+    fn get(this: &ObjectStore, key: &JsValue) -> std::result::Result<Request, JsValue>;
+    #[wasm_bindgen(method, catch, js_name = put)]
+    fn put(this: &ObjectStore, value: &JsValue) -> std::result::Result<Request, JsValue>;
+    #[wasm_bindgen(method, catch, js_name = put)]
+    fn put_with_key(
+        this: &ObjectStore,
+        value: &JsValue,
+        key: &JsValue,
+    ) -> std::result::Result<Request, JsValue>;
+    #[wasm_bindgen(method, catch)]
+    fn delete(this: &ObjectStore, key: &JsValue) -> std::result::Result<Request, JsValue>;
+    #[wasm_bindgen(method, catch)]
+    fn clear(this: &ObjectStore) -> std::result::Result<Request, JsValue>;
+    #[wasm_bindgen(method, catch)]
+    fn count(this: &ObjectStore) -> std::result::Result<Request, JsValue>;
+    #[wasm_bindgen(method, catch, js_name = openCursor)]
+    fn open_cursor(this: &ObjectStore) -> std::result::Result<Request, JsValue>;
+
+    #[wasm_bindgen(extends = js_sys::Object)]
+    type Cursor;
+    #[wasm_bindgen(method, getter)]
+    fn key(this: &Cursor) -> JsValue;
+    #[wasm_bindgen(method, getter)]
+    fn value(this: &Cursor) -> JsValue;
+    #[wasm_bindgen(method, catch, js_name = continue)]
+    fn advance(this: &Cursor) -> std::result::Result<(), JsValue>;
+}
+
+/// Event name and the handler installed as its `on<event>` property.
+type EventListener = (&'static str, Closure<dyn FnMut(JsValue)>);
+
+/// Event handlers (`on<event>` properties) installed on an IndexedDB
+/// object, detached when dropped.
+struct Handlers {
+    target: JsValue,
+    events: Vec<EventListener>,
+}
+
+impl Handlers {
+    fn new(target: &JsValue) -> Self {
+        Self {
+            target: target.clone(),
+            events: Vec::new(),
+        }
+    }
+
+    fn on<F>(&mut self, event: &'static str, handler: F) -> Result<()>
+    where
+        F: FnMut(JsValue) + 'static,
+    {
+        let closure = Closure::wrap(Box::new(handler) as Box<dyn FnMut(JsValue)>);
+        js_sys::Reflect::set(&self.target, &format!("on{event}").into(), closure.as_ref())?;
+        self.events.push((event, closure));
+        Ok(())
+    }
+}
+
+impl Drop for Handlers {
+    fn drop(&mut self) {
+        for (event, _) in self.events.iter() {
+            js_sys::Reflect::set(&self.target, &format!("on{event}").into(), &JsValue::NULL).ok();
+        }
+    }
+}
+
+/// Resolves with the result of an `IDBRequest`
+async fn request(request: Request) -> Result<JsValue> {
+    let (sender, receiver) = oneshot();
+    let mut handlers = Handlers::new(&request);
+    let (success_sender, success_request) = (sender.clone(), request.clone());
+    handlers.on("success", move |_| {
+        success_sender.try_send(success_request.result()).ok();
+    })?;
+    handlers.on("error", move |_| {
+        sender
+            .try_send(Err(request.error().unwrap_or_else(|err| err)))
+            .ok();
+    })?;
+    let result = receiver.recv().await.map_err(Error::custom)?;
+    result.map_err(storage_error)
+}
+
+/// Error of the event target (the failed request or transaction)
+fn event_error(event: &JsValue) -> JsValue {
+    js_sys::Reflect::get(event, &"target".into())
+        .and_then(|target| js_sys::Reflect::get(&target, &"error".into()))
+        .unwrap_or_else(|err| err)
+}
+
+/// Options of an object store created during an upgrade
+#[derive(Debug, Clone, Default)]
+pub struct StoreOptions {
+    key_path: Option<String>,
+    auto_increment: bool,
+}
+
+impl StoreOptions {
+    /// Use in-line keys located at `key_path` within stored values
+    /// (values must then be stored using [`IdbStore::put()`])
+    pub fn with_key_path(mut self, key_path: impl Into<String>) -> Self {
+        self.key_path = Some(key_path.into());
+        self
+    }
+
+    /// Generate keys using a key generator
+    pub fn with_auto_increment(mut self, auto_increment: bool) -> Self {
+        self.auto_increment = auto_increment;
+        self
+    }
+
+    fn to_object(&self) -> Result<js_sys::Object> {
+        let options = js_sys::Object::new();
+        if let Some(key_path) = &self.key_path {
+            js_sys::Reflect::set(&options, &"keyPath".into(), &key_path.into())?;
+        }
+        js_sys::Reflect::set(
+            &options,
+            &"autoIncrement".into(),
+            &self.auto_increment.into(),
+        )?;
+        Ok(options)
+    }
+}
+
+/// Database schema access provided to the upgrade callback of
+/// [`IdbDatabase::open()`] when the database version increases
+pub struct IdbUpgrade {
+    database: Database,
+    transaction: Transaction,
+    old_version: u32,
+    new_version: u32,
+}
+
+impl IdbUpgrade {
+    /// Version of the existing database (`0` if the database is being created)
+    pub fn old_version(&self) -> u32 {
+        self.old_version
+    }
+
+    pub fn new_version(&self) -> u32 {
+        self.new_version
+    }
+
+    pub fn store_names(&self) -> Vec<String> {
+        store_names(&self.database)
+    }
+
+    pub fn has_store(&self, name: &str) -> bool {
+        self.store_names().iter().any(|store| store == name)
+    }
+
+    pub fn create_store(&self, name: &str, options: StoreOptions) -> Result<IdbStore> {
+        let store = self
+            .database
+            .create_object_store(name, &options.to_object()?)
+            .map_err(storage_error)?;
+        Ok(IdbStore { store })
+    }
+
+    pub fn delete_store(&self, name: &str) -> Result<()> {
+        self.database
+            .delete_object_store(name)
+            .map_err(storage_error)
+    }
+
+    /// Store within the upgrade transaction, allowing data migration.
+    /// Requests can be issued but not awaited within the (synchronous)
+    /// upgrade callback.
+    pub fn store(&self, name: &str) -> Result<IdbStore> {
+        let store = self.transaction.object_store(name).map_err(storage_error)?;
+        Ok(IdbStore { store })
+    }
+}
+
+fn store_names(database: &Database) -> Vec<String> {
+    js_sys::Array::from(&database.object_store_names())
+        .iter()
+        .filter_map(|name| name.as_string())
+        .collect()
+}
+
+/// State of an [`IdbDatabase::open()`] request, closing the database
+/// (or aborting the upgrade) if the open future is dropped while pending
+struct PendingOpen {
+    request: OpenRequest,
+    completed: bool,
+}
+
+impl Drop for PendingOpen {
+    fn drop(&mut self) {
+        if !self.completed {
+            let request = self.request.clone();
+            let close = Closure::once_into_js(move || {
+                if let Ok(database) = request.result() {
+                    database.unchecked_into::<Database>().close();
+                }
+            });
+            let request = self.request.clone();
+            let abort = Closure::once_into_js(move || {
+                if let Some(transaction) = request.transaction() {
+                    transaction.abort().ok();
+                }
+            });
+            js_sys::Reflect::set(&self.request, &"onsuccess".into(), &close).ok();
+            js_sys::Reflect::set(&self.request, &"onupgradeneeded".into(), &abort).ok();
+        }
+    }
+}
+
+/// Transaction mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionMode {
+    ReadOnly,
+    ReadWrite,
+}
+
+impl TransactionMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TransactionMode::ReadOnly => "readonly",
+            TransactionMode::ReadWrite => "readwrite",
+        }
+    }
+}
+
+/// IndexedDB database connection
+#[derive(Clone)]
+pub struct IdbDatabase {
+    database: Database,
+}
+
+impl IdbDatabase {
+    ///
+    /// Opens (or creates) the database `name`. If the database `version`
+    /// is greater than the existing version, `upgrade` is invoked to
+    /// migrate the schema; an error returned by `upgrade` aborts the
+    /// upgrade and is returned by `open()`.
+    ///
+    /// Opening a database is delayed while other connections to an older
+    /// version remain open (see [`IdbDatabase::close()`]).
+    ///
+    pub async fn open<F>(name: &str, version: u32, upgrade: F) -> Result<IdbDatabase>
+    where
+        F: FnOnce(&IdbUpgrade) -> Result<()> + 'static,
+    {
+        let factory = global_storage::<Factory>("indexedDB")?;
+        let request = factory.open(name, version).map_err(storage_error)?;
+
+        // handlers are detached before the pending state is finalized
+        let mut pending = PendingOpen {
+            request: request.clone(),
+            completed: false,
+        };
+        let mut handlers = Handlers::new(&request);
+
+        let upgrade_error = Rc::new(RefCell::new(None));
+        let upgrade_error_ = upgrade_error.clone();
+        let mut upgrade = Some(upgrade);
+        let upgrade_request = request.clone();
+        handlers.on("upgradeneeded", move |event| {
+            let version = |property: &str| {
+                js_sys::Reflect::get(&event, &property.into())
+                    .ok()
+                    .and_then(|version| version.as_f64())
+                    .unwrap_or_default() as u32
+            };
+            let (Ok(database), Some(transaction), Some(upgrade)) = (
+                upgrade_request.result(),
+                upgrade_request.transaction(),
+                upgrade.take(),
+            ) else {
+                return;
+            };
+            let context = IdbUpgrade {
+                database: database.unchecked_into(),
+                transaction: transaction.clone(),
+                old_version: version("oldVersion"),
+                new_version: version("newVersion"),
+            };
+            if let Err(err) = upgrade(&context) {
+                upgrade_error_.borrow_mut().replace(err);
+                transaction.abort().ok();
+            }
+        })?;
+
+        let (sender, receiver) = oneshot();
+        let (success_sender, success_request) = (sender.clone(), request.clone());
+        handlers.on("success", move |_| {
+            success_sender.try_send(success_request.result()).ok();
+        })?;
+        handlers.on("error", move |event| {
+            sender.try_send(Err(event_error(&event))).ok();
+        })?;
+        let database_name = name.to_string();
+        handlers.on("blocked", move |_| {
+            log_warn!("IndexedDB: opening `{database_name}` is blocked by open connections");
+        })?;
+
+        let result = receiver.recv().await.map_err(Error::custom);
+        pending.completed = true;
+        drop(handlers);
+
+        if let Some(err) = upgrade_error.borrow_mut().take() {
+            return Err(err);
+        }
+        let database = result?.map_err(storage_error)?;
+        Ok(IdbDatabase {
+            database: database.unchecked_into(),
+        })
+    }
+
+    /// Deletes the database `name`
+    pub async fn delete(name: &str) -> Result<()> {
+        let factory = global_storage::<Factory>("indexedDB")?;
+        let request = factory.delete_database(name).map_err(storage_error)?;
+        self::request(request.unchecked_into()).await?;
+        Ok(())
+    }
+
+    pub fn name(&self) -> String {
+        self.database.name()
+    }
+
+    pub fn version(&self) -> u32 {
+        self.database.version() as u32
+    }
+
+    pub fn store_names(&self) -> Vec<String> {
+        store_names(&self.database)
+    }
+
+    /// Closes the connection once pending transactions complete
+    pub fn close(&self) {
+        self.database.close();
+    }
+
+    /// Creates a transaction spanning `stores`
+    pub fn transaction(&self, stores: &[&str], mode: TransactionMode) -> Result<IdbTransaction> {
+        let stores = stores
+            .iter()
+            .map(|store| JsValue::from(*store))
+            .collect::<js_sys::Array>();
+        let transaction = self
+            .database
+            .transaction(&stores, mode.as_str())
+            .map_err(storage_error)?;
+        Ok(IdbTransaction { transaction })
+    }
+
+    /// Store `name` within a new single-store transaction
+    pub fn store(&self, name: &str, mode: TransactionMode) -> Result<IdbStore> {
+        self.transaction(&[name], mode)?.store(name)
+    }
+
+    ///
+    /// Runs `f` within a transaction spanning `stores`. The transaction is
+    /// committed if `f` succeeds (resolving once the transaction completes)
+    /// and aborted if `f` fails or if the returned future is dropped.
+    ///
+    pub async fn with_transaction<F, Fut, R>(
+        &self,
+        stores: &[&str],
+        mode: TransactionMode,
+        f: F,
+    ) -> Result<R>
+    where
+        F: FnOnce(IdbTransaction) -> Fut,
+        Fut: Future<Output = Result<R>>,
+    {
+        let transaction = self.transaction(stores, mode)?;
+        let completion = transaction.completion()?;
+        let mut guard = AbortGuard(Some(transaction.clone()));
+        let result = f(transaction.clone()).await;
+        guard.0.take();
+        match result {
+            Ok(value) => {
+                transaction.try_commit();
+                completion.await?;
+                Ok(value)
+            }
+            Err(err) => {
+                transaction.abort().ok();
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Aborts the transaction when dropped (unless taken)
+struct AbortGuard(Option<IdbTransaction>);
+
+impl Drop for AbortGuard {
+    fn drop(&mut self) {
+        if let Some(transaction) = self.0.take() {
+            transaction.abort().ok();
+        }
+    }
+}
+
+/// IndexedDB transaction
+#[derive(Clone)]
+pub struct IdbTransaction {
+    transaction: Transaction,
+}
+
+impl IdbTransaction {
+    pub fn store(&self, name: &str) -> Result<IdbStore> {
+        let store = self.transaction.object_store(name).map_err(storage_error)?;
+        Ok(IdbStore { store })
+    }
+
+    /// Aborts the transaction, reverting all changes
+    pub fn abort(&self) -> Result<()> {
+        self.transaction.abort().map_err(storage_error)
+    }
+
+    /// Commits the transaction, resolving once it is complete
+    pub async fn commit(self) -> Result<()> {
+        let completion = self.completion()?;
+        self.try_commit();
+        completion.await
+    }
+
+    /// Resolves once the transaction completes (fails if the transaction is aborted)
+    pub async fn done(&self) -> Result<()> {
+        self.completion()?.await
+    }
+
+    /// Requests an explicit commit (if supported by the browser)
+    fn try_commit(&self) {
+        let commit = js_sys::Reflect::get(&self.transaction, &"commit".into());
+        if let Ok(commit) = commit.and_then(|commit| commit.dyn_into::<js_sys::Function>()) {
+            // fails if the transaction has already been committed
+            commit.call0(&self.transaction).ok();
+        }
+    }
+
+    /// Installs the completion handlers, returning a future
+    /// resolving with the outcome of the transaction
+    fn completion(&self) -> Result<impl Future<Output = Result<()>>> {
+        let events = Channel::<std::result::Result<(), JsValue>>::oneshot();
+        let mut handlers = Handlers::new(&self.transaction);
+        let sender = events.sender.clone();
+        handlers.on("complete", move |_| {
+            sender.try_send(Ok(())).ok();
+        })?;
+        let sender = events.sender.clone();
+        handlers.on("error", move |event| {
+            sender.try_send(Err(event_error(&event))).ok();
+        })?;
+        let sender = events.sender.clone();
+        let transaction = self.transaction.clone();
+        handlers.on("abort", move |_| {
+            sender.try_send(Err(transaction.error())).ok();
+        })?;
+
+        Ok(async move {
+            let result = events.receiver.recv().await.map_err(Error::custom)?;
+            drop(handlers);
+            result.map_err(|err| {
+                if err.is_null() || err.is_undefined() {
+                    Error::TransactionAborted
+                } else {
+                    storage_error(err)
+                }
+            })
+        })
+    }
+}
+
+/// Object store accessed within a transaction
+#[derive(Clone)]
+pub struct IdbStore {
+    store: ObjectStore,
+}
+
+impl IdbStore {
+    pub fn name(&self) -> String {
+        self.store.name()
+    }
+
+    /// Value stored under `key`, `None` if absent
+    pub async fn get<K, T>(&self, key: &K) -> Result<Option<T>>
+    where
+        K: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let request_ = self.store.get(&to_key(key)?).map_err(storage_error)?;
+        let value = request(request_).await?;
+        if value.is_undefined() {
+            Ok(None)
+        } else {
+            from_value(value).map(Some)
+        }
+    }
+
+    /// Stores `value` using an in-line or generated key, returning the key
+    pub async fn put<K, T>(&self, value: &T) -> Result<K>
+    where
+        K: DeserializeOwned,
+        T: Serialize + ?Sized,
+    {
+        let request_ = self.store.put(&to_value(value)?).map_err(storage_error)?;
+        from_value(request(request_).await?)
+    }
+
+    /// Stores `value` under `key` (for stores using out-of-line keys)
+    pub async fn put_with_key<K, T>(&self, key: &K, value: &T) -> Result<()>
+    where
+        K: Serialize + ?Sized,
+        T: Serialize + ?Sized,
+    {
+        let request_ = self
+            .store
+            .put_with_key(&to_value(value)?, &to_key(key)?)
+            .map_err(storage_error)?;
+        request(request_).await?;
+        Ok(())
+    }
+
+    pub async fn delete<K>(&self, key: &K) -> Result<()>
+    where
+        K: Serialize + ?Sized,
+    {
+        let request_ = self.store.delete(&to_key(key)?).map_err(storage_error)?;
+        request(request_).await?;
+        Ok(())
+    }
+
+    pub async fn clear(&self) -> Result<()> {
+        request(self.store.clear().map_err(storage_error)?).await?;
+        Ok(())
+    }
+
+    pub async fn count(&self) -> Result<u32> {
+        let count = request(self.store.count().map_err(storage_error)?).await?;
+        Ok(count.as_f64().unwrap_or_default() as u32)
+    }
+
+    ///
+    /// Iterates over the entries of the store in key order, invoking `f`
+    /// with each key and value; iteration stops when `f` returns `false`.
+    ///
+    pub async fn iterate<K, T, F>(&self, mut f: F) -> Result<()>
+    where
+        K: DeserializeOwned,
+        T: DeserializeOwned,
+        F: FnMut(K, T) -> bool,
+    {
+        let request = self.store.open_cursor().map_err(storage_error)?;
+        let cursors = Channel::<std::result::Result<JsValue, JsValue>>::unbounded();
+        let mut handlers = Handlers::new(&request);
+        let sender = cursors.sender.clone();
+        let success = request.clone();
+        handlers.on("success", move |_| {
+            sender.try_send(success.result()).ok();
+        })?;
+        let sender = cursors.sender.clone();
+        handlers.on("error", move |event| {
+            sender.try_send(Err(event_error(&event))).ok();
+        })?;
+
+        loop {
+            let cursor = cursors
+                .receiver
+                .recv()
+                .await
+                .map_err(Error::custom)?
+                .map_err(storage_error)?;
+            if cursor.is_null() || cursor.is_undefined() {
+                break;
+            }
+            let cursor = cursor.unchecked_into::<Cursor>();
+            if !f(from_value(cursor.key())?, from_value(cursor.value())?) {
+                break;
+            }
+            cursor.advance().map_err(storage_error)?;
+        }
+        Ok(())
+    }
+
+    /// All entries of the store in key order
+    pub async fn entries<K, T>(&self) -> Result<Vec<(K, T)>>
+    where
+        K: DeserializeOwned,
+        T: DeserializeOwned,
+    {
+        let mut entries = Vec::new();
+        self.iterate(|key, value| {
+            entries.push((key, value));
+            true
+        })
+        .await?;
+        Ok(entries)
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use serde::Deserialize;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Item {
+        name: String,
+        amount: u64,
+    }
+
+    fn item(name: &str, amount: u64) -> Item {
+        Item {
+            name: name.to_string(),
+            amount,
+        }
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_idb_upgrade() {
+        const DB: &str = "workflow-wasm-test-upgrade";
+        IdbDatabase::delete(DB).await.unwrap();
+
+        let db = IdbDatabase::open(DB, 1, |upgrade| {
+            assert_eq!((upgrade.old_version(), upgrade.new_version()), (0, 1));
+            upgrade.create_store("items", StoreOptions::default())?;
+            Ok(())
+        })
+        .await
+        .unwrap();
+        assert_eq!(db.version(), 1);
+        let items = db.store("items", TransactionMode::ReadWrite).unwrap();
+        items.put_with_key("a", &item("a", 1)).await.unwrap();
+        items.put_with_key("b", &item("b", 2)).await.unwrap();
+        db.close();
+
+        // a failing upgrade leaves the database at the previous version
+        let result = IdbDatabase::open(DB, 2, |upgrade| {
+            upgrade.create_store("settings", StoreOptions::default())?;
+            Err(Error::custom("upgrade failure"))
+        })
+        .await;
+        assert!(matches!(result, Err(Error::Custom(msg)) if msg == "upgrade failure"));
+
+        let db = IdbDatabase::open(DB, 2, |upgrade| {
+            assert_eq!((upgrade.old_version(), upgrade.new_version()), (1, 2));
+            assert!(upgrade.has_store("items"));
+            upgrade.create_store("settings", StoreOptions::default().with_key_path("name"))?;
+            Ok(())
+        })
+        .await
+        .unwrap();
+        assert_eq!(db.version(), 2);
+        let mut stores = db.store_names();
+        stores.sort();
+        assert_eq!(stores, vec!["items", "settings"]);
+
+        // existing data is preserved
+        let items = db.store("items", TransactionMode::ReadWrite).unwrap();
+        assert_eq!(items.get::<_, Item>("a").await.unwrap(), Some(item("a", 1)));
+        assert_eq!(items.get::<_, Item>("c").await.unwrap(), None);
+        assert_eq!(items.count().await.unwrap(), 2);
+        items.delete("a").await.unwrap();
+        assert_eq!(
+            items.entries::<String, Item>().await.unwrap(),
+            vec![("b".to_string(), item("b", 2))]
+        );
+
+        // in-line keys
+        let settings = db.store("settings", TransactionMode::ReadWrite).unwrap();
+        let key: String = settings.put(&item("theme", 3)).await.unwrap();
+        assert_eq!(key, "theme");
+
+        // transaction scoping
+        let result = db
+            .with_transaction(
+                &["items", "settings"],
+                TransactionMode::ReadWrite,
+                |tx| async move {
+                    tx.store("items")?.put_with_key("c", &item("c", 4)).await?;
+                    tx.store("settings")?.delete("theme").await?;
+                    Err::<(), _>(Error::custom("rollback"))
+                },
+            )
+            .await;
+        assert!(result.is_err());
+        let items = db.store("items", TransactionMode::ReadOnly).unwrap();
+        assert_eq!(items.get::<_, Item>("c").await.unwrap(), None);
+
+        db.with_transaction(&["items"], TransactionMode::ReadWrite, |tx| async move {
+            tx.store("items")?.put_with_key("c", &item("c", 4)).await
+        })
+        .await
+        .unwrap();
+        let items = db.store("items", TransactionMode::ReadOnly).unwrap();
+        let mut names = Vec::new();
+        items
+            .iterate(|key: String, _: Item| {
+                names.push(key);
+                true
+            })
+            .await
+            .unwrap();
+        assert_eq!(names, vec!["b", "c"]);
+
+        db.close();
+        IdbDatabase::delete(DB).await.unwrap();
+    }
+}
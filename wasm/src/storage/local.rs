@@ -0,0 +1,151 @@
+//!
+//! [`LocalStorage`] - access to the browser `localStorage`
+//! with JSON-serialized typed values.
+//!
+//! ```ignore
+//! LocalStorage::set_json("settings", &settings)?;
+//! let settings = LocalStorage::get_json::<Settings>("settings")?;
+//! ```
+//!
+
+use super::{from_value, global_storage, storage_error};
+use crate::error::Error;
+use crate::result::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(extends = js_sys::Object)]
+    type Storage;
+    #[wasm_bindgen(method, getter)]
+    fn length(this: &Storage) -> u32;
+    #[wasm_bindgen(method, catch)]
+    fn key(this: &Storage, index: u32) -> std::result::Result<Option<String>, JsValue>;
+    #[wasm_bindgen(method, catch, js_name = getItem)]
+    fn get_item(this: &Storage, key: &str) -> std::result::Result<Option<String>, JsValue>;
+    #[wasm_bindgen(method, catch, js_name = setItem)]
+    fn set_item(this: &Storage, key: &str, value: &str) -> std::result::Result<(), JsValue>;
+    #[wasm_bindgen(method, catch, js_name = removeItem)]
+    fn remove_item(this: &Storage, key: &str) -> std::result::Result<(), JsValue>;
+    #[wasm_bindgen(method, catch)]
+    fn clear(this: &Storage) -> std::result::Result<(), JsValue>;
+}
+
+/// Browser `localStorage`. Operations fail with [`Error::StorageNotAvailable`]
+/// outside of the browser main thread (Node.js, web workers) or when the
+/// storage is disabled, and with [`Error::QuotaExceeded`] if the storage
+/// quota has been exhausted.
+pub struct LocalStorage;
+
+impl LocalStorage {
+    fn storage() -> Result<Storage> {
+        global_storage("localStorage")
+    }
+
+    /// `true` if `localStorage` is accessible
+    pub fn is_available() -> bool {
+        Self::storage().is_ok()
+    }
+
+    pub fn get(key: &str) -> Result<Option<String>> {
+        Self::storage()?.get_item(key).map_err(storage_error)
+    }
+
+    pub fn set(key: &str, value: &str) -> Result<()> {
+        Self::storage()?.set_item(key, value).map_err(storage_error)
+    }
+
+    pub fn remove(key: &str) -> Result<()> {
+        Self::storage()?.remove_item(key).map_err(storage_error)
+    }
+
+    /// Removes all keys of the origin
+    pub fn clear() -> Result<()> {
+        Self::storage()?.clear().map_err(storage_error)
+    }
+
+    pub fn keys() -> Result<Vec<String>> {
+        let storage = Self::storage()?;
+        (0..storage.length())
+            .filter_map(|index| storage.key(index).map_err(storage_error).transpose())
+            .collect()
+    }
+
+    /// Reads and deserializes a JSON value, `None` if the key is absent
+    pub fn get_json<T: DeserializeOwned>(key: &str) -> Result<Option<T>> {
+        Self::get(key)?
+            .map(|json| {
+                let value = js_sys::JSON::parse(&json)
+                    .map_err(|err| Error::convert(format!("`{key}`: {:?}", err)))?;
+                from_value(value)
+            })
+            .transpose()
+    }
+
+    /// Serializes `value` to JSON and stores it under `key`
+    pub fn set_json<T: Serialize + ?Sized>(key: &str, value: &T) -> Result<()> {
+        let value = value
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(Error::convert)?;
+        let json = js_sys::JSON::stringify(&value)
+            .map_err(storage_error)?
+            .as_string()
+            .ok_or_else(|| Error::convert(format!("`{key}`: value is not JSON-serializable")))?;
+        Self::set(key, &json)
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Settings {
+        theme: String,
+        scale: f64,
+        flags: HashMap<String, bool>,
+    }
+
+    #[wasm_bindgen_test]
+    fn test_local_storage_json() {
+        const KEY: &str = "workflow-wasm-test-settings";
+        let settings = Settings {
+            theme: "dark".to_string(),
+            scale: 1.5,
+            flags: HashMap::from([("beta".to_string(), true)]),
+        };
+        LocalStorage::set_json(KEY, &settings).unwrap();
+        assert_eq!(
+            LocalStorage::get_json::<Settings>(KEY).unwrap(),
+            Some(settings)
+        );
+        assert!(LocalStorage::keys().unwrap().iter().any(|key| key == KEY));
+
+        LocalStorage::set(KEY, "{ not json").unwrap();
+        assert!(matches!(
+            LocalStorage::get_json::<Settings>(KEY),
+            Err(Error::Convert(_))
+        ));
+
+        LocalStorage::remove(KEY).unwrap();
+        assert_eq!(LocalStorage::get_json::<Settings>(KEY).unwrap(), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_local_storage_quota() {
+        const KEY: &str = "workflow-wasm-test-quota";
+        // exceeds the per-origin quota of all major browsers (5-10MB)
+        let value = "x".repeat(16 * 1024 * 1024);
+        assert!(matches!(
+            LocalStorage::set(KEY, &value),
+            Err(Error::QuotaExceeded(_))
+        ));
+        LocalStorage::remove(KEY).unwrap();
+    }
+}
@@ -0,0 +1,62 @@
+//!
+//! Typed access to browser storage APIs: [`IdbDatabase`] (IndexedDB)
+//! and [`LocalStorage`] (`localStorage`).
+//!
+//! Values are converted using the [`serde`](crate::serde) bridge, errors
+//! are reported as the `workflow_wasm` [`Error`], with storage quota
+//! violations reported as [`Error::QuotaExceeded`].
+//!
+
+pub mod idb;
+pub mod local;
+
+pub use idb::{IdbDatabase, IdbStore, IdbTransaction, IdbUpgrade, StoreOptions, TransactionMode};
+pub use local::LocalStorage;
+
+use crate::error::Error;
+use crate::result::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Maps a `DOMException` raised by a storage API to [`Error`]
+pub(crate) fn storage_error(err: JsValue) -> Error {
+    let name = js_sys::Reflect::get(&err, &"name".into())
+        .ok()
+        .and_then(|name| name.as_string());
+    match name.as_deref() {
+        // `NS_ERROR_DOM_QUOTA_REACHED` is reported by older Firefox versions
+        Some("QuotaExceededError") | Some("NS_ERROR_DOM_QUOTA_REACHED") => {
+            let message = js_sys::Reflect::get(&err, &"message".into())
+                .ok()
+                .and_then(|message| message.as_string())
+                .unwrap_or_default();
+            Error::QuotaExceeded(message)
+        }
+        _ => Error::from(err),
+    }
+}
+
+/// Global object property (`indexedDB`, `localStorage`), reported as
+/// [`Error::StorageNotAvailable`] if absent or inaccessible
+pub(crate) fn global_storage<T: JsCast>(name: &str) -> Result<T> {
+    js_sys::Reflect::get(&js_sys::global(), &name.into())
+        .ok()
+        .filter(|value| value.is_object())
+        .map(JsCast::unchecked_into)
+        .ok_or_else(|| Error::StorageNotAvailable(name.to_string()))
+}
+
+/// Serializes a storage key (keys are serialized JSON-compatible,
+/// as IndexedDB does not accept `BigInt` or `Map` keys)
+pub(crate) fn to_key<K: Serialize + ?Sized>(key: &K) -> Result<JsValue> {
+    key.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(Error::convert)
+}
+
+pub(crate) fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<JsValue> {
+    crate::serde::to_value(value).map_err(Error::convert)
+}
+
+pub(crate) fn from_value<T: DeserializeOwned>(value: JsValue) -> Result<T> {
+    serde_wasm_bindgen::from_value(value).map_err(Error::convert)
+}
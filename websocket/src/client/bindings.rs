@@ -254,15 +254,24 @@ impl WebSocket {
     pub fn new_with_config(
         url: &str,
         config: &WebSocketConfig,
+        protocols: &[String],
     ) -> super::result::Result<WebSocket> {
+        let protocols = (!protocols.is_empty()).then(|| {
+            protocols
+                .iter()
+                .map(JsValue::from)
+                .collect::<::js_sys::Array>()
+        });
+
         if is_node() {
             let WebSocketNodeJsConfig {
-                protocols,
+                protocols: default_protocols,
                 origin,
                 headers,
                 request_options,
                 client_config,
             } = WebSocketNodeJsConfig::try_from(config)?;
+            let protocols = protocols.map(JsValue::from).unwrap_or(default_protocols);
 
             Ok(Self::new_with_nodejs_config_impl(
                 url,
@@ -272,6 +281,8 @@ impl WebSocket {
                 request_options,
                 client_config,
             )?)
+        } else if let Some(protocols) = protocols {
+            Ok(Self::new_with_str_sequence(url, &protocols)?)
         } else {
             Ok(Self::new(url)?)
         }
@@ -0,0 +1,83 @@
+//!
+//! Outgoing message coalescing and incoming container splitting
+//! used by the native and WASM WebSocket dispatchers.
+//!
+
+use super::{error::Error, message::Message, result::Result};
+use crate::coalesce::{split, Batch, Coalescing};
+use futures::future::{BoxFuture, Fuse, FutureExt};
+use std::sync::Arc;
+use workflow_core::channel::Sender;
+use workflow_log::*;
+
+pub(crate) type AckSender = Sender<std::result::Result<Arc<()>, Arc<Error>>>;
+
+pub(crate) struct Coalescer {
+    coalescing: Coalescing,
+    batch: Batch,
+    acks: Vec<AckSender>,
+    timer: Fuse<BoxFuture<'static, ()>>,
+}
+
+impl Coalescer {
+    pub fn new(coalescing: Coalescing) -> Self {
+        Self {
+            coalescing,
+            batch: Batch::new(),
+            acks: Vec::new(),
+            timer: Fuse::terminated(),
+        }
+    }
+
+    /// Appends the message to the pending container, starting the
+    /// coalescing window if the container was empty. Returns `true`
+    /// if the container has reached the size threshold.
+    pub fn push(&mut self, msg: Message, ack: Option<AckSender>) -> bool {
+        match msg {
+            Message::Text(text) => self.batch.push_text(&text),
+            Message::Binary(data) => self.batch.push_binary(&data),
+            _ => panic!("WebSocket coalescer - unsupported message type: {msg:?}"),
+        }
+        self.acks.extend(ack);
+
+        if self.batch.len() == 1 {
+            self.timer =
+                FutureExt::boxed(workflow_core::task::sleep(self.coalescing.window)).fuse();
+        }
+
+        self.batch.size() >= self.coalescing.max_bytes
+    }
+
+    /// Timer resolving when the coalescing window of the pending
+    /// container elapses. Never resolves if the container is empty.
+    pub fn timer(&mut self) -> &mut Fuse<BoxFuture<'static, ()>> {
+        &mut self.timer
+    }
+
+    /// Takes the pending container and acks of the messages it carries.
+    pub fn take(&mut self) -> Option<(Message, Vec<AckSender>)> {
+        self.timer = Fuse::terminated();
+        self.batch
+            .take()
+            .map(|data| (Message::Binary(data), std::mem::take(&mut self.acks)))
+    }
+
+    /// Splits the incoming container into individual messages.
+    pub fn split(data: &[u8]) -> Result<Vec<Message>> {
+        split(data)
+            .map(|payloads| payloads.into_iter().map(Message::from).collect())
+            .ok_or(Error::MalformedContainer)
+    }
+}
+
+/// Relays the container dispatch `result` to the message `acks`.
+pub(crate) async fn acknowledge(
+    acks: Vec<AckSender>,
+    result: &std::result::Result<(), Arc<Error>>,
+) {
+    for ack in acks {
+        ack.send(result.clone().map(Arc::new))
+            .await
+            .unwrap_or_else(|err| log_trace!("WebSocket error producing message ack {:?}", err));
+    }
+}
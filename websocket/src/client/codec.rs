@@ -0,0 +1,203 @@
+//!
+//! Typed message layer: [`TypedWebSocket`] serializes outgoing messages
+//! and deserializes incoming messages using a pluggable [`Codec`].
+//!
+//! The codec selection must be symmetric: both peers are expected to
+//! use the same codec. The websocket protocol does not carry the codec,
+//! so it is selected by convention (for example, by exposing separate
+//! endpoints per codec) or negotiated by the application during the
+//! [`Handshake`](super::Handshake) using [`Codec::name()`].
+//!
+//! | Codec | Frames | Feature |
+//! |-------|--------|---------|
+//! | [`JsonCodec`] | text | |
+//! | [`BorshCodec`] | binary | |
+//! | `MsgPackCodec` | binary | `msgpack` |
+//!
+//! ```ignore
+//! let ws = WebSocket::new(Some("ws://localhost:9090"), None)?;
+//! ws.connect(ConnectOptions::default()).await?;
+//! let ws = TypedWebSocket::<Request, _>::new(ws, JsonCodec);
+//! ws.send_msg(&Request::Ping).await?;
+//! loop {
+//!     match ws.recv_msg().await {
+//!         Ok(msg) => handle(msg),
+//!         // malformed frame, the connection remains usable
+//!         Err(Error::Decode(err)) => log_warn!("{err}"),
+//!         Err(err) => break,
+//!     }
+//! }
+//! ```
+//!
+
+use super::{Error, Message, Result, WebSocket};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+
+/// Conversion between typed messages and websocket frames
+pub trait Codec<T>: Send + Sync + 'static {
+    /// Codec identifier, allowing peers to agree on the codec
+    fn name(&self) -> &'static str;
+
+    fn encode(&self, msg: &T) -> Result<Message>;
+
+    /// Decode the frame. Failures are reported as [`Error::Decode`].
+    fn decode(&self, msg: Message) -> Result<T>;
+}
+
+/// JSON codec transmitting messages as text frames
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JsonCodec;
+
+impl<T> Codec<T> for JsonCodec
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn encode(&self, msg: &T) -> Result<Message> {
+        let text = serde_json::to_string(msg).map_err(|err| Error::Encode(err.to_string()))?;
+        Ok(Message::Text(text))
+    }
+
+    fn decode(&self, msg: Message) -> Result<T> {
+        match msg {
+            Message::Text(text) => {
+                serde_json::from_str(&text).map_err(|err| Error::Decode(err.to_string()))
+            }
+            _ => Err(Error::Decode("expected a text frame".to_string())),
+        }
+    }
+}
+
+/// Borsh codec transmitting messages as binary frames
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BorshCodec;
+
+impl<T> Codec<T> for BorshCodec
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    fn name(&self) -> &'static str {
+        "borsh"
+    }
+
+    fn encode(&self, msg: &T) -> Result<Message> {
+        let data = borsh::to_vec(msg).map_err(|err| Error::Encode(err.to_string()))?;
+        Ok(Message::Binary(data))
+    }
+
+    fn decode(&self, msg: Message) -> Result<T> {
+        match msg {
+            Message::Binary(data) => {
+                borsh::from_slice(&data).map_err(|err| Error::Decode(err.to_string()))
+            }
+            _ => Err(Error::Decode("expected a binary frame".to_string())),
+        }
+    }
+}
+
+/// MessagePack codec transmitting messages as binary frames.
+/// Structs are encoded as maps (with field names) for
+/// interoperability with non-Rust peers.
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MsgPackCodec;
+
+#[cfg(feature = "msgpack")]
+impl<T> Codec<T> for MsgPackCodec
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn name(&self) -> &'static str {
+        "msgpack"
+    }
+
+    fn encode(&self, msg: &T) -> Result<Message> {
+        let data = rmp_serde::to_vec_named(msg).map_err(|err| Error::Encode(err.to_string()))?;
+        Ok(Message::Binary(data))
+    }
+
+    fn decode(&self, msg: Message) -> Result<T> {
+        match msg {
+            Message::Binary(data) => {
+                rmp_serde::from_slice(&data).map_err(|err| Error::Decode(err.to_string()))
+            }
+            _ => Err(Error::Decode("expected a binary frame".to_string())),
+        }
+    }
+}
+
+///
+/// [`WebSocket`] wrapper sending and receiving messages of type `T`
+/// encoded with the codec `C` (JSON by default).
+///
+/// Frames that fail to decode are delivered as [`Error::Decode`] without
+/// affecting the connection, allowing the caller to skip malformed frames.
+///
+pub struct TypedWebSocket<T, C = JsonCodec> {
+    ws: WebSocket,
+    codec: C,
+    _message: PhantomData<fn() -> T>,
+}
+
+impl<T, C: Clone> Clone for TypedWebSocket<T, C> {
+    fn clone(&self) -> Self {
+        Self {
+            ws: self.ws.clone(),
+            codec: self.codec.clone(),
+            _message: PhantomData,
+        }
+    }
+}
+
+impl<T, C> TypedWebSocket<T, C>
+where
+    C: Codec<T>,
+{
+    pub fn new(ws: WebSocket, codec: C) -> Self {
+        Self {
+            ws,
+            codec,
+            _message: PhantomData,
+        }
+    }
+
+    /// Underlying [`WebSocket`] (used to connect, disconnect
+    /// or to exchange untyped messages)
+    pub fn ws(&self) -> &WebSocket {
+        &self.ws
+    }
+
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    pub fn into_inner(self) -> WebSocket {
+        self.ws
+    }
+
+    /// Encode and post the message (see [`WebSocket::post()`])
+    pub async fn send_msg(&self, msg: &T) -> Result<()> {
+        let msg = self.codec.encode(msg)?;
+        self.ws.post(msg).await?;
+        Ok(())
+    }
+
+    /// Receive and decode the next message. [`Message::Open`] notifications
+    /// are skipped, while [`Message::Close`] is reported as [`Error::NotConnected`]
+    /// (if the websocket reconnects, subsequent calls receive messages of
+    /// the new connection).
+    pub async fn recv_msg(&self) -> Result<T> {
+        loop {
+            match self.ws.recv().await? {
+                Message::Open => continue,
+                Message::Close => return Err(Error::NotConnected),
+                msg => return self.codec.decode(msg),
+            }
+        }
+    }
+}
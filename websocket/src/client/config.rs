@@ -3,6 +3,7 @@
 //!
 
 use super::{error::Error, result::Result, Handshake, Resolver};
+use crate::coalesce::Coalescing;
 use cfg_if::cfg_if;
 use js_sys::Object;
 use std::sync::Arc;
@@ -62,6 +63,13 @@ pub struct WebSocketConfig {
     /// an alternative to supplying the URL and will be invoked each time the
     /// websocket needs to be connected or reconnected.
     pub resolver: Option<Arc<dyn Resolver>>,
+    /// Enables coalescing of outgoing messages into containers and
+    /// splitting of incoming containers (see [`crate::coalesce`]).
+    /// The server must be configured with the same option.
+    /// Messages can bypass the coalescing window using
+    /// [`WebSocket::flush()`](super::WebSocket::flush) or
+    /// [`WebSocket::send_immediate()`](super::WebSocket::send_immediate).
+    pub coalescing: Option<Coalescing>,
 }
 
 impl Default for WebSocketConfig {
@@ -76,6 +84,7 @@ impl Default for WebSocketConfig {
             sender_channel_cap: None,
             handshake: None,
             resolver: None,
+            coalescing: None,
         }
     }
 }
@@ -47,6 +47,9 @@ pub enum Error {
     #[error("Unable to connect to {0}")]
     Connect(String),
 
+    #[error("WebSocket subprotocol negotiation failure: {0}")]
+    Subprotocol(String),
+
     #[error("Handshake negotiation failure (internal)")]
     NegotiationFailure,
 
@@ -92,6 +95,15 @@ pub enum Error {
 
     #[error("Invalid connect strategy")]
     InvalidConnectStrategy,
+
+    #[error("Malformed coalesced message container")]
+    MalformedContainer,
+
+    #[error("Message encoding error: {0}")]
+    Encode(String),
+
+    #[error("Message decoding error: {0}")]
+    Decode(String),
 }
 
 impl Error {
@@ -1,4 +1,5 @@
 use super::error::Error;
+use crate::coalesce::Payload;
 use std::sync::Arc;
 use workflow_core::channel::*;
 
@@ -38,6 +39,15 @@ impl From<Message> for Vec<u8> {
     }
 }
 
+impl From<Payload> for Message {
+    fn from(payload: Payload) -> Self {
+        match payload {
+            Payload::Text(text) => Message::Text(text),
+            Payload::Binary(data) => Message::Binary(data),
+        }
+    }
+}
+
 impl From<Vec<u8>> for Message {
     fn from(vec: Vec<u8>) -> Self {
         Message::Binary(vec)
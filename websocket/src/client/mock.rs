@@ -0,0 +1,264 @@
+//!
+//! In-process WebSocket transport for deterministic client tests
+//! (available with the `mock` feature).
+//!
+//! A [`WebSocket`](super::WebSocket) created via
+//! [`WebSocket::new_with_transport()`](super::WebSocket::new_with_transport)
+//! runs the same connection, reconnection, handshake, coalescing and
+//! dispatch logic as a WebSocket connected over the network; only the
+//! network connection is replaced by an in-memory connection controlled
+//! by the [`MockTransport`]:
+//!
+//! ```ignore
+//! let transport = MockTransport::new();
+//! let ws = WebSocket::new_with_transport(Some("ws://mock"), None, transport.clone())?;
+//! ws.connect(ConnectOptions::blocking_fallback()).await?;
+//! assert_eq!(ws.recv().await?, Message::Open);
+//!
+//! // messages sent by the client are recorded
+//! ws.send(Message::Text("ping".into())).await?;
+//! assert_eq!(transport.take_outgoing(), vec![Message::Text("ping".into())]);
+//!
+//! // messages injected by the test are received by the client
+//! transport.inject_incoming(Message::Text("pong".into()))?;
+//! assert_eq!(ws.recv().await?, Message::Text("pong".into()));
+//!
+//! // closing the connection on the "server" side causes a reconnect
+//! transport.disconnect();
+//! assert_eq!(ws.recv().await?, Message::Close);
+//! assert_eq!(ws.recv().await?, Message::Open);
+//! ```
+//!
+//! The mock transport is currently available only on native targets.
+//!
+
+use super::{error::Error, message::Message, result::Result};
+use futures::stream::BoxStream;
+use futures::{Sink, Stream, StreamExt};
+use std::collections::HashSet;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio_tungstenite::tungstenite::{protocol::Message as TsMessage, Error as TsError};
+use workflow_core::channel::{unbounded, Sender};
+
+/// Server side of an established mock connection
+struct Connection {
+    incoming: Sender<TsMessage>,
+    open: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct Inner {
+    connections: Vec<Connection>,
+    outgoing: Vec<Message>,
+    unreachable: HashSet<String>,
+    fail_next: usize,
+    attempts: Vec<String>,
+    established: usize,
+}
+
+/// Scriptable in-memory transport (see the [module](self) documentation).
+/// Clones share the same state.
+#[derive(Clone, Default)]
+pub struct MockTransport {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes connection attempts to `url` fail until [`MockTransport::accept()`]
+    pub fn refuse(&self, url: &str) {
+        self.inner
+            .lock()
+            .unwrap()
+            .unreachable
+            .insert(url.to_string());
+    }
+
+    /// Makes `url` reachable again (all URLs are reachable by default)
+    pub fn accept(&self, url: &str) {
+        self.inner.lock().unwrap().unreachable.remove(url);
+    }
+
+    /// Makes the next `count` connection attempts fail regardless of the URL
+    pub fn fail_next(&self, count: usize) {
+        self.inner.lock().unwrap().fail_next = count;
+    }
+
+    /// Delivers `message` to the client over the current connection.
+    /// [`Message::Close`] closes the connection (see [`MockTransport::disconnect()`]).
+    pub fn inject_incoming(&self, message: Message) -> Result<()> {
+        let message = match message {
+            Message::Text(text) => TsMessage::Text(text),
+            Message::Binary(data) => TsMessage::Binary(data),
+            Message::Close => {
+                self.disconnect();
+                return Ok(());
+            }
+            Message::Open => return Err(Error::InvalidMessageType),
+        };
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.prune();
+        let connection = inner.connections.last().ok_or(Error::NotConnected)?;
+        connection
+            .incoming
+            .try_send(message)
+            .map_err(|_| Error::NotConnected)
+    }
+
+    /// Returns (and clears) the messages sent by the client so far
+    pub fn take_outgoing(&self) -> Vec<Message> {
+        std::mem::take(&mut self.inner.lock().unwrap().outgoing)
+    }
+
+    /// Closes the current connection on the server side. The client
+    /// receives [`Message::Close`] and reconnects as it would following
+    /// a loss of a network connection.
+    pub fn disconnect(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        for connection in inner.connections.drain(..) {
+            connection.open.store(false, Ordering::SeqCst);
+        }
+    }
+
+    /// Returns `true` if a client connection is open
+    pub fn is_connected(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        inner.prune();
+        !inner.connections.is_empty()
+    }
+
+    /// URLs of all connection attempts so far
+    pub fn attempts(&self) -> Vec<String> {
+        self.inner.lock().unwrap().attempts.clone()
+    }
+
+    /// Number of connections established so far
+    /// (including primary candidate probes)
+    pub fn connections(&self) -> usize {
+        self.inner.lock().unwrap().established
+    }
+
+    pub(crate) fn connect(&self, url: &str) -> Result<MockStream> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.attempts.push(url.to_string());
+        if inner.fail_next > 0 {
+            inner.fail_next -= 1;
+            return Err(Error::Connect(url.to_string()));
+        }
+        if inner.unreachable.contains(url) {
+            return Err(Error::Connect(url.to_string()));
+        }
+
+        let (incoming, receiver) = unbounded();
+        let open = Arc::new(AtomicBool::new(true));
+        inner.prune();
+        inner.connections.push(Connection {
+            incoming,
+            open: open.clone(),
+        });
+        inner.established += 1;
+
+        Ok(MockStream {
+            transport: self.clone(),
+            incoming: receiver.map(Ok).boxed(),
+            open,
+        })
+    }
+}
+
+impl Inner {
+    /// Removes connections closed by the client
+    fn prune(&mut self) {
+        self.connections
+            .retain(|connection| connection.open.load(Ordering::SeqCst));
+    }
+}
+
+/// Client side of a mock connection
+pub(crate) struct MockStream {
+    transport: MockTransport,
+    incoming: BoxStream<'static, std::result::Result<TsMessage, TsError>>,
+    open: Arc<AtomicBool>,
+}
+
+impl MockStream {
+    pub fn close(&mut self) {
+        self.open.store(false, Ordering::SeqCst);
+    }
+}
+
+impl Drop for MockStream {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl Stream for MockStream {
+    type Item = std::result::Result<TsMessage, TsError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if !self.open.load(Ordering::SeqCst) {
+            return Poll::Ready(None);
+        }
+        self.incoming.poll_next_unpin(cx)
+    }
+}
+
+impl Sink<TsMessage> for MockStream {
+    type Error = TsError;
+
+    fn poll_ready(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<std::result::Result<(), TsError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, message: TsMessage) -> std::result::Result<(), TsError> {
+        if !self.open.load(Ordering::SeqCst) {
+            return Err(TsError::AlreadyClosed);
+        }
+        let message = match message {
+            TsMessage::Text(text) => Message::Text(text),
+            TsMessage::Binary(data) => Message::Binary(data),
+            TsMessage::Ping(data) => {
+                // answered as a WebSocket server would
+                let inner = self.transport.inner.lock().unwrap();
+                if let Some(connection) = inner
+                    .connections
+                    .iter()
+                    .find(|connection| Arc::ptr_eq(&connection.open, &self.open))
+                {
+                    connection.incoming.try_send(TsMessage::Pong(data)).ok();
+                }
+                return Ok(());
+            }
+            // other control frames are not recorded
+            _ => return Ok(()),
+        };
+        self.transport.inner.lock().unwrap().outgoing.push(message);
+        Ok(())
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<std::result::Result<(), TsError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<std::result::Result<(), TsError>> {
+        self.close();
+        Poll::Ready(Ok(()))
+    }
+}
@@ -13,21 +13,33 @@ cfg_if! {
     } else {
         mod native;
         use native::WebSocketInterface;
+
+        #[cfg(any(test, feature = "mock"))]
+        pub mod mock;
+        #[cfg(any(test, feature = "mock"))]
+        pub use mock::MockTransport;
     }
 }
 
 pub mod bindings;
+mod coalescer;
+pub mod codec;
 pub mod config;
 pub mod error;
 pub mod message;
 pub mod options;
 pub mod result;
 
+pub use crate::coalesce::Coalescing;
+use coalescer::AckSender;
+#[cfg(feature = "msgpack")]
+pub use codec::MsgPackCodec;
+pub use codec::{BorshCodec, Codec, JsonCodec, TypedWebSocket};
 pub use config::WebSocketConfig;
 pub use error::Error;
 use futures::Future;
 pub use message::*;
-pub use options::{ConnectOptions, ConnectStrategy};
+pub use options::{ConnectCandidate, ConnectEvent, ConnectOptions, ConnectStrategy};
 pub use result::Result;
 
 use async_trait::async_trait;
@@ -50,6 +62,12 @@ pub trait Handshake: Send + Sync + 'static {
 #[async_trait]
 pub trait Resolver: Send + Sync + 'static {
     async fn resolve_url(&self) -> ResolverResult;
+
+    /// Ordered list of connection candidates. Defaults to
+    /// the single URL returned by [`Resolver::resolve_url()`].
+    async fn resolve_candidates(&self) -> Result<Vec<ConnectCandidate>> {
+        Ok(vec![self.resolve_url().await?.into()])
+    }
 }
 pub type ResolverResult = Result<String>;
 pub type WebSocketError = Error;
@@ -58,6 +76,7 @@ struct Inner {
     client: Arc<WebSocketInterface>,
     sender_channel: Channel<(Message, Ack)>,
     receiver_channel: Channel<Message>,
+    flush_channel: Channel<AckSender>,
 }
 
 impl Inner {
@@ -65,11 +84,13 @@ impl Inner {
         client: Arc<WebSocketInterface>,
         sender_channel: Channel<(Message, Ack)>,
         receiver_channel: Channel<Message>,
+        flush_channel: Channel<AckSender>,
     ) -> Self {
         Self {
             client,
             sender_channel,
             receiver_channel,
+            flush_channel,
         }
     }
 }
@@ -105,25 +126,61 @@ impl WebSocket {
             Channel::<(Message, Ack)>::unbounded()
         };
 
+        let flush_channel = Channel::<AckSender>::unbounded();
+
         let client = Arc::new(WebSocketInterface::new(
             url,
             Some(config),
             sender_channel.clone(),
             receiver_channel.clone(),
+            flush_channel.clone(),
         )?);
 
         let websocket = WebSocket {
-            inner: Arc::new(Inner::new(client, sender_channel, receiver_channel)),
+            inner: Arc::new(Inner::new(
+                client,
+                sender_channel,
+                receiver_channel,
+                flush_channel,
+            )),
         };
 
         Ok(websocket)
     }
 
+    /// Create a new WebSocket instance using the in-memory `transport`
+    /// instead of network connections (see [`mock`]). Requires the
+    /// `mock` feature.
+    #[cfg(all(any(test, feature = "mock"), not(target_arch = "wasm32")))]
+    pub fn new_with_transport(
+        url: Option<&str>,
+        config: Option<WebSocketConfig>,
+        transport: MockTransport,
+    ) -> Result<WebSocket> {
+        let websocket = Self::new(url, config)?;
+        websocket.inner.client.set_transport(transport);
+        Ok(websocket)
+    }
+
     /// Get current websocket connection URL
     pub fn url(&self) -> Option<String> {
         self.inner.client.current_url()
     }
 
+    /// URL of the active connection (or of the connection
+    /// being attempted) when multiple candidates are supplied
+    /// via [`ConnectOptions::with_candidates()`].
+    pub fn current_url(&self) -> Option<String> {
+        self.inner.client.current_url()
+    }
+
+    /// Subprotocol selected by the server among the ones offered
+    /// via [`ConnectOptions::with_protocols()`] during the last
+    /// successful connection. `None` if no subprotocol was selected.
+    pub fn negotiated_protocol(&self) -> Option<String> {
+        self.inner.client.negotiated_protocol()
+    }
+
     /// Changes WebSocket connection URL.
     /// Following this call, you must invoke
     /// `WebSocket::reconnect().await` manually
@@ -226,6 +283,39 @@ impl WebSocket {
             .map(|_| self)
     }
 
+    /// Dispatches messages accumulated by the coalescing mode
+    /// (see [`WebSocketConfig::coalescing`]) without waiting for
+    /// the coalescing window to elapse. Blocks until all messages
+    /// posted prior to this call were relayed to the underlying
+    /// websocket implementation.
+    pub async fn flush(&self) -> std::result::Result<&Self, Arc<Error>> {
+        if !self.inner.client.is_connected() {
+            return Err(Arc::new(Error::NotConnected));
+        }
+
+        let (ack_sender, ack_receiver) = oneshot();
+        self.inner
+            .flush_channel
+            .send(ack_sender)
+            .await
+            .map_err(|err| Arc::new(err.into()))?;
+
+        ack_receiver
+            .recv()
+            .await
+            .map_err(|_| Arc::new(Error::DispatchChannelAck))?
+            .map(|_| self)
+    }
+
+    /// Sends a message to the destination server bypassing the
+    /// coalescing window (along with any messages accumulated
+    /// so far). Blocks until the message was relayed to the
+    /// underlying websocket implementation.
+    pub async fn send_immediate(&self, message: Message) -> std::result::Result<&Self, Arc<Error>> {
+        self.post(message).await.map_err(Arc::new)?;
+        self.flush().await
+    }
+
     /// Receives message from the websocket. Blocks until a message is
     /// received from the underlying websocket connection.
     pub async fn recv(&self) -> Result<Message> {
@@ -1,28 +1,49 @@
+#[cfg(any(test, feature = "mock"))]
+use super::mock::{MockStream, MockTransport};
 use super::{
-    error::Error, message::Message, result::Result, Ack, ConnectOptions, ConnectResult,
-    ConnectStrategy, Handshake, Resolver, WebSocketConfig,
+    coalescer::{acknowledge, AckSender, Coalescer},
+    error::Error,
+    message::Message,
+    result::Result,
+    Ack, ConnectCandidate, ConnectEvent, ConnectOptions, ConnectResult, ConnectStrategy, Handshake,
+    Resolver, WebSocketConfig,
 };
 use futures::{
+    future::Fuse,
     select_biased,
     stream::{SplitSink, SplitStream},
-    FutureExt,
+    FutureExt, Sink, Stream,
 };
 use futures_util::{SinkExt, StreamExt};
+use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
 #[allow(unused_imports)]
 use std::time::Instant;
 use tokio::net::TcpStream;
 use tokio::time::timeout;
 use tokio_tungstenite::{
-    connect_async_with_config, tungstenite::protocol::Message as TsMessage, MaybeTlsStream,
-    WebSocketStream,
+    connect_async_with_config,
+    tungstenite::{
+        client::IntoClientRequest,
+        error::ProtocolError,
+        http::{header::SEC_WEBSOCKET_PROTOCOL, HeaderName, HeaderValue},
+        protocol::Message as TsMessage,
+        Error as TsError,
+    },
+    MaybeTlsStream, WebSocketStream,
 };
 use tungstenite::protocol::WebSocketConfig as TsWebSocketConfig;
 pub use workflow_core as core;
 use workflow_core::channel::*;
 pub use workflow_log::*;
 
+/// Time allowed for the server to answer the ping re-validating
+/// the connection after a clock discontinuity (e.g. a system resume)
+const REVALIDATION_TIMEOUT: Duration = Duration::from_secs(5);
+
 impl From<Message> for tungstenite::Message {
     fn from(message: Message) -> Self {
         match message {
@@ -61,10 +82,94 @@ impl From<WebSocketConfig> for TsWebSocketConfig {
     }
 }
 
+/// Connection carrying the websocket messages
+#[allow(clippy::large_enum_variant)]
+enum Transport {
+    Tungstenite(WebSocketStream<MaybeTlsStream<TcpStream>>),
+    #[cfg(any(test, feature = "mock"))]
+    Mock(MockStream),
+}
+
+impl Transport {
+    async fn close(&mut self) {
+        match self {
+            Transport::Tungstenite(stream) => {
+                stream.close(None).await.ok();
+            }
+            #[cfg(any(test, feature = "mock"))]
+            Transport::Mock(stream) => stream.close(),
+        }
+    }
+}
+
+impl Stream for Transport {
+    type Item = std::result::Result<TsMessage, TsError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.get_mut() {
+            Transport::Tungstenite(stream) => stream.poll_next_unpin(cx),
+            #[cfg(any(test, feature = "mock"))]
+            Transport::Mock(stream) => stream.poll_next_unpin(cx),
+        }
+    }
+}
+
+impl Sink<TsMessage> for Transport {
+    type Error = TsError;
+
+    fn poll_ready(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<std::result::Result<(), TsError>> {
+        match self.get_mut() {
+            Transport::Tungstenite(stream) => stream.poll_ready_unpin(cx),
+            #[cfg(any(test, feature = "mock"))]
+            Transport::Mock(stream) => stream.poll_ready_unpin(cx),
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, message: TsMessage) -> std::result::Result<(), TsError> {
+        match self.get_mut() {
+            Transport::Tungstenite(stream) => stream.start_send_unpin(message),
+            #[cfg(any(test, feature = "mock"))]
+            Transport::Mock(stream) => stream.start_send_unpin(message),
+        }
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<std::result::Result<(), TsError>> {
+        match self.get_mut() {
+            Transport::Tungstenite(stream) => stream.poll_flush_unpin(cx),
+            #[cfg(any(test, feature = "mock"))]
+            Transport::Mock(stream) => stream.poll_flush_unpin(cx),
+        }
+    }
+
+    fn poll_close(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<std::result::Result<(), TsError>> {
+        match self.get_mut() {
+            Transport::Tungstenite(stream) => stream.poll_close_unpin(cx),
+            #[cfg(any(test, feature = "mock"))]
+            Transport::Mock(stream) => stream.poll_close_unpin(cx),
+        }
+    }
+}
+
+type WsSender<'a> = SplitSink<&'a mut Transport, TsMessage>;
+type WsReceiver<'a> = SplitStream<&'a mut Transport>;
+
 #[derive(Default)]
 struct Settings {
     default_url: Option<String>,
     current_url: Option<String>,
+    // index of the last successful connection candidate
+    preferred: usize,
+    // subprotocol selected by the server
+    protocol: Option<String>,
 }
 
 pub struct WebSocketInterface {
@@ -74,7 +179,10 @@ pub struct WebSocketInterface {
     is_connected: AtomicBool,
     receiver_channel: Channel<Message>,
     sender_channel: Channel<(Message, Ack)>,
+    flush_channel: Channel<AckSender>,
     shutdown: DuplexChannel<()>,
+    #[cfg(any(test, feature = "mock"))]
+    mock: Mutex<Option<MockTransport>>,
 }
 
 impl WebSocketInterface {
@@ -83,6 +191,7 @@ impl WebSocketInterface {
         config: Option<WebSocketConfig>,
         sender_channel: Channel<(Message, Ack)>,
         receiver_channel: Channel<Message>,
+        flush_channel: Channel<AckSender>,
     ) -> Result<WebSocketInterface> {
         let settings = Settings {
             default_url: url.map(String::from),
@@ -94,9 +203,12 @@ impl WebSocketInterface {
             config: Mutex::new(config.unwrap_or_default()),
             receiver_channel,
             sender_channel,
+            flush_channel,
             reconnect: AtomicBool::new(true),
             is_connected: AtomicBool::new(false),
             shutdown: DuplexChannel::unbounded(),
+            #[cfg(any(test, feature = "mock"))]
+            mock: Mutex::new(None),
         };
 
         Ok(iface)
@@ -126,6 +238,10 @@ impl WebSocketInterface {
             .replace(url.to_string());
     }
 
+    pub fn negotiated_protocol(self: &Arc<Self>) -> Option<String> {
+        self.settings.lock().unwrap().protocol.clone()
+    }
+
     pub fn is_connected(self: &Arc<Self>) -> bool {
         self.is_connected.load(Ordering::SeqCst)
     }
@@ -146,16 +262,128 @@ impl WebSocketInterface {
         self.config.lock().unwrap().clone()
     }
 
-    async fn resolve_url(self: &Arc<Self>, options: &ConnectOptions) -> Result<String> {
-        let url = if let Some(url) = options.url.as_ref().or(self.default_url().as_ref()) {
-            url.clone()
+    /// Replaces network connections with `transport`
+    #[cfg(any(test, feature = "mock"))]
+    pub fn set_transport(&self, transport: MockTransport) {
+        self.mock.lock().unwrap().replace(transport);
+    }
+
+    async fn resolve_candidates(
+        self: &Arc<Self>,
+        options: &ConnectOptions,
+    ) -> Result<Vec<ConnectCandidate>> {
+        let candidates = if !options.candidates.is_empty() {
+            options.candidates.clone()
+        } else if let Some(url) = options.url.as_ref().or(self.default_url().as_ref()) {
+            vec![ConnectCandidate::new(url)]
         } else if let Some(resolver) = self.resolver() {
-            resolver.resolve_url().await?
+            resolver.resolve_candidates().await?
         } else {
             return Err(Error::MissingUrl);
         };
-        self.set_current_url(&url);
-        Ok(url)
+
+        if candidates.is_empty() {
+            return Err(Error::MissingUrl);
+        }
+        if let Some(candidate) = candidates.iter().find(|candidate| {
+            !candidate.url.starts_with("ws://") && !candidate.url.starts_with("wss://")
+        }) {
+            return Err(Error::AddressSchema(candidate.url.clone()));
+        }
+
+        Ok(candidates)
+    }
+
+    /// Index of the candidate attempted first: the last
+    /// successful candidate or the primary one.
+    fn preferred(self: &Arc<Self>, len: usize) -> usize {
+        let preferred = self.settings.lock().unwrap().preferred;
+        if preferred < len {
+            preferred
+        } else {
+            0
+        }
+    }
+
+    fn set_preferred(self: &Arc<Self>, index: usize) {
+        self.settings.lock().unwrap().preferred = index;
+    }
+
+    /// Connects to the `candidate`, returning the transport along
+    /// with the subprotocol selected by the server (if any).
+    async fn connect_candidate(
+        self: &Arc<Self>,
+        candidate: &ConnectCandidate,
+        options: &ConnectOptions,
+        config: Option<TsWebSocketConfig>,
+    ) -> Result<(Transport, Option<String>)> {
+        #[cfg(any(test, feature = "mock"))]
+        {
+            let mock = self.mock.lock().unwrap().clone();
+            if let Some(mock) = mock {
+                return Ok((Transport::Mock(mock.connect(&candidate.url)?), None));
+            }
+        }
+
+        let mut request = candidate.url.as_str().into_client_request()?;
+        for (name, value) in candidate.headers.iter() {
+            request.headers_mut().insert(
+                HeaderName::from_bytes(name.as_bytes()).map_err(Error::custom)?,
+                HeaderValue::from_str(value).map_err(Error::custom)?,
+            );
+        }
+        // tungstenite splits the offered subprotocols on `,` without trimming
+        if !options.protocols.is_empty() {
+            request.headers_mut().insert(
+                SEC_WEBSOCKET_PROTOCOL,
+                HeaderValue::from_str(&options.protocols.join(",")).map_err(Error::custom)?,
+            );
+        }
+
+        let connect_future = connect_async_with_config(request, config, false);
+        match timeout(candidate.timeout(options), connect_future).await {
+            Ok(Ok((stream, response))) => {
+                let protocol = response
+                    .headers()
+                    .get(SEC_WEBSOCKET_PROTOCOL)
+                    .and_then(|protocol| protocol.to_str().ok())
+                    .map(String::from);
+                Ok((Transport::Tungstenite(stream), protocol))
+            }
+            // the server has selected a subprotocol that was not offered
+            // (or none of them), tungstenite fails the connection (RFC 6455 4.1)
+            Ok(Err(TsError::Protocol(ProtocolError::SecWebSocketSubProtocolError(err)))) => {
+                Err(Error::Subprotocol(err.to_string()))
+            }
+            Ok(Err(err)) => Err(err.into()),
+            Err(_) => Err(Error::ConnectionTimeout),
+        }
+    }
+
+    /// Resolves once the `primary` candidate accepts a connection,
+    /// probing it every [`ConnectOptions::primary_probe_interval()`].
+    async fn probe_primary(
+        self: &Arc<Self>,
+        primary: &ConnectCandidate,
+        options: &ConnectOptions,
+        config: Option<TsWebSocketConfig>,
+    ) {
+        loop {
+            workflow_core::task::sleep(options.primary_probe_interval()).await;
+            match self.connect_candidate(primary, options, config).await {
+                Ok((mut stream, _)) => {
+                    stream.close().await;
+                    return;
+                }
+                Err(err) => {
+                    log_trace!(
+                        "WebSocket primary {} is not reachable: {}",
+                        primary.url,
+                        err
+                    );
+                }
+            }
+        }
     }
 
     pub async fn connect(self: &Arc<Self>, options: ConnectOptions) -> ConnectResult<Error> {
@@ -175,78 +403,104 @@ impl WebSocketInterface {
 
         core::task::spawn(async move {
             'outer: loop {
-                match this.resolve_url(&options).await {
-                    Ok(url) => {
-                        let connect_future =
-                            connect_async_with_config(&url, ts_websocket_config, false);
-                        let timeout_future = timeout(options.connect_timeout(), connect_future);
-
-                        match timeout_future.await {
-                            // connect success
-                            Ok(Ok(stream)) => {
-                                // log_trace!("connected...");
-
-                                this.is_connected.store(true, Ordering::SeqCst);
-                                let (mut ws_stream, _) = stream;
-
-                                if connect_trigger.is_some() {
-                                    connect_trigger.take().unwrap().try_send(Ok(())).ok();
-                                }
-
-                                if let Err(err) = this.dispatcher(&mut ws_stream, &options).await {
-                                    log_trace!("WebSocket dispatcher error: {}", err);
-                                }
-
-                                this.is_connected.store(false, Ordering::SeqCst);
-                            }
-                            // connect error
-                            Ok(Err(e)) => {
-                                log_trace!("WebSocket failed to connect to {}: {}", url, e);
-                                if matches!(options.strategy, ConnectStrategy::Fallback) {
-                                    if options.block_async_connect && connect_trigger.is_some() {
-                                        connect_trigger
-                                            .take()
-                                            .unwrap()
-                                            .try_send(Err(e.into()))
-                                            .ok();
-                                    }
-                                    break;
-                                }
-                                workflow_core::task::sleep(options.retry_interval()).await;
-                            }
-                            // timeout error
-                            Err(_) => {
-                                log_trace!(
-                                    "WebSocket connection timeout while connecting to {}",
-                                    url
-                                );
-                                if matches!(options.strategy, ConnectStrategy::Fallback) {
-                                    if options.block_async_connect && connect_trigger.is_some() {
-                                        connect_trigger
-                                            .take()
-                                            .unwrap()
-                                            .try_send(Err(Error::ConnectionTimeout))
-                                            .ok();
-                                    }
-                                    break;
-                                }
-                                workflow_core::task::sleep(options.retry_interval()).await;
-                            }
-                        };
-
-                        if !this.reconnect.load(Ordering::SeqCst) {
-                            break 'outer;
-                        };
-                    }
+                let candidates = match this.resolve_candidates(&options).await {
+                    Ok(candidates) => candidates,
                     Err(err) => {
                         log_trace!("WebSocket failed to get session URL: {}", err);
                         if !this.reconnect.load(Ordering::SeqCst) {
                             break 'outer;
                         } else {
                             workflow_core::task::sleep(options.retry_interval()).await;
+                            continue 'outer;
+                        }
+                    }
+                };
+
+                let preferred = this.preferred(candidates.len());
+                let mut last_error = None;
+                for index in (0..candidates.len()).map(|n| (preferred + n) % candidates.len()) {
+                    let candidate = &candidates[index];
+                    this.set_current_url(&candidate.url);
+                    options.notify(ConnectEvent::Attempt {
+                        url: candidate.url.clone(),
+                    });
+
+                    match this
+                        .connect_candidate(candidate, &options, ts_websocket_config)
+                        .await
+                    {
+                        // connect success
+                        Ok((mut ws_stream, protocol)) => {
+                            this.set_preferred(index);
+                            this.settings.lock().unwrap().protocol = protocol;
+                            this.is_connected.store(true, Ordering::SeqCst);
+                            options.notify(ConnectEvent::Connected {
+                                url: candidate.url.clone(),
+                            });
+
+                            if connect_trigger.is_some() {
+                                connect_trigger.take().unwrap().try_send(Ok(())).ok();
+                            }
+
+                            // probe the primary candidate while connected to a fallback
+                            let primary = (index != 0).then(|| &candidates[0]);
+                            if let Err(err) = this
+                                .dispatcher(&mut ws_stream, &options, primary, ts_websocket_config)
+                                .await
+                            {
+                                log_trace!("WebSocket dispatcher error: {}", err);
+                            }
+
+                            this.is_connected.store(false, Ordering::SeqCst);
+                            last_error = None;
+                            break;
+                        }
+                        // connect error or timeout
+                        Err(err) => {
+                            log_trace!(
+                                "WebSocket connection attempt to {} has failed: {}",
+                                candidate.url,
+                                err
+                            );
+                            options.notify(ConnectEvent::Failed {
+                                url: candidate.url.clone(),
+                                error: err.to_string(),
+                            });
+                            // subprotocol mismatch fails the connection without retrying
+                            if matches!(err, Error::Subprotocol(_)) {
+                                if options.block_async_connect && connect_trigger.is_some() {
+                                    connect_trigger.take().unwrap().try_send(Err(err)).ok();
+                                }
+                                break 'outer;
+                            }
+                            last_error = Some(err);
                         }
                     }
                 }
+
+                // all candidates have failed
+                if let Some(err) = last_error {
+                    log_trace!(
+                        "WebSocket failed to connect to {}: {}",
+                        candidates
+                            .iter()
+                            .map(|candidate| candidate.url.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        err
+                    );
+                    if matches!(options.strategy, ConnectStrategy::Fallback) {
+                        if options.block_async_connect && connect_trigger.is_some() {
+                            connect_trigger.take().unwrap().try_send(Err(err)).ok();
+                        }
+                        break;
+                    }
+                    workflow_core::task::sleep(options.retry_interval()).await;
+                }
+
+                if !this.reconnect.load(Ordering::SeqCst) {
+                    break 'outer;
+                };
             }
         });
 
@@ -261,8 +515,8 @@ impl WebSocketInterface {
 
     async fn handshake_impl(
         self: &Arc<Self>,
-        ws_sender: &mut SplitSink<&mut WebSocketStream<MaybeTlsStream<TcpStream>>, TsMessage>,
-        ws_receiver: &mut SplitStream<&mut WebSocketStream<MaybeTlsStream<TcpStream>>>,
+        ws_sender: &mut WsSender<'_>,
+        ws_receiver: &mut WsReceiver<'_>,
     ) -> Result<()> {
         if let Some(handshake) = self.handshake() {
             let (sender_tx, sender_rx) = unbounded();
@@ -302,10 +556,56 @@ impl WebSocketInterface {
         Ok(())
     }
 
+    /// Relays the outgoing message to the websocket, or to the
+    /// `coalescer` if the coalescing mode is enabled.
+    async fn dispatch(
+        self: &Arc<Self>,
+        ws_sender: &mut WsSender<'_>,
+        coalescer: &mut Option<Coalescer>,
+        msg: Message,
+        ack: Ack,
+    ) -> Result<()> {
+        if let Some(coalescer) = coalescer {
+            if coalescer.push(msg, ack) {
+                self.flush(ws_sender, coalescer).await?;
+            }
+        } else if let Some(ack_sender) = ack {
+            let result = ws_sender
+                .send(msg.into())
+                .await
+                .map(Arc::new)
+                .map_err(|err| Arc::new(err.into()));
+            ack_sender.send(result).await?;
+        } else {
+            ws_sender.send(msg.into()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends the pending container of the `coalescer` (if any).
+    async fn flush(
+        self: &Arc<Self>,
+        ws_sender: &mut WsSender<'_>,
+        coalescer: &mut Coalescer,
+    ) -> Result<()> {
+        if let Some((msg, acks)) = coalescer.take() {
+            let result = ws_sender
+                .send(msg.into())
+                .await
+                .map_err(|err| Arc::new(err.into()));
+            acknowledge(acks, &result).await;
+            result.map_err(Error::custom)?;
+        }
+        Ok(())
+    }
+
     async fn dispatcher(
         self: &Arc<Self>,
-        ws_stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
-        _options: &ConnectOptions,
+        ws_stream: &mut Transport,
+        options: &ConnectOptions,
+        primary: Option<&ConnectCandidate>,
+        config: Option<TsWebSocketConfig>,
     ) -> Result<()> {
         let (mut ws_sender, mut ws_receiver) = ws_stream.split();
 
@@ -317,26 +617,86 @@ impl WebSocketInterface {
         #[cfg(feature = "delay-reconnect")]
         let mut closed_ungracefully = false;
 
+        let mut coalescer = self.config().coalescing.map(Coalescer::new);
+        let mut idle = Fuse::terminated();
+        // a connection idle across a system suspend may be silently lost;
+        // it is re-validated using a ping following each clock jump
+        let mut clock_jump = Box::pin(workflow_core::clock::clock_jump()).fuse();
+        let mut revalidation = Fuse::terminated();
+
+        let probe = async {
+            match primary {
+                Some(primary) => self.probe_primary(primary, options, config).await,
+                None => futures::future::pending().await,
+            }
+        }
+        .fuse();
+        futures::pin_mut!(probe);
+
         self.receiver_channel.send(Message::Open).await?;
 
         loop {
+            let timer = match coalescer.as_mut() {
+                Some(coalescer) => coalescer.timer(),
+                None => &mut idle,
+            };
+
             select_biased! {
+                flush = self.flush_channel.recv().fuse() => {
+                    if let Ok(ack_sender) = flush {
+                        // relay messages posted prior to the flush request
+                        while let Ok((msg, ack)) = self.sender_channel.try_recv() {
+                            self.dispatch(&mut ws_sender, &mut coalescer, msg, ack).await?;
+                        }
+                        let result = match coalescer.as_mut() {
+                            Some(coalescer) => self.flush(&mut ws_sender, coalescer).await,
+                            None => Ok(()),
+                        };
+                        ack_sender.send(result.as_ref().map(|_| Arc::new(())).map_err(|err| Arc::new(Error::custom(err)))).await?;
+                        result?;
+                    }
+                }
+                _ = &mut *timer => {
+                    if let Some(coalescer) = coalescer.as_mut() {
+                        self.flush(&mut ws_sender, coalescer).await?;
+                    }
+                }
                 dispatch = self.sender_channel.recv().fuse() => {
                     if let Ok((msg,ack)) = dispatch {
-                        if let Some(ack_sender) = ack {
-                            let result = ws_sender.send(msg.into()).await
-                                .map(Arc::new)
-                                .map_err(|err|Arc::new(err.into()));
-                            ack_sender.send(result).await?;
-                        } else {
-                            ws_sender.send(msg.into()).await?;
-                        }
+                        self.dispatch(&mut ws_sender, &mut coalescer, msg, ack).await?;
+                    }
+                }
+                jump = clock_jump.next() => {
+                    if let Some(jump) = jump {
+                        log_trace!("WebSocket re-validating connection after a clock jump: {:?}", jump);
+                        ws_sender.send(TsMessage::Ping(Default::default())).await?;
+                        revalidation = workflow_core::task::sleep(REVALIDATION_TIMEOUT).fuse();
                     }
                 }
+                _ = &mut revalidation => {
+                    self.receiver_channel.send(Message::Close).await?;
+                    log_trace!("WebSocket connection lost (no response following a clock jump)");
+                    #[cfg(feature = "delay-reconnect")] {
+                        closed_ungracefully = true;
+                    }
+                    break;
+                }
                 msg = ws_receiver.next().fuse() => {
                     match msg {
                         Some(Ok(msg)) => {
+                            // any message confirms the connection is alive
+                            revalidation = Fuse::terminated();
                             match msg {
+                                TsMessage::Binary(data) if coalescer.is_some() => {
+                                    match Coalescer::split(&data) {
+                                        Ok(messages) => {
+                                            for msg in messages {
+                                                self.receiver_channel.send(msg).await?;
+                                            }
+                                        }
+                                        Err(err) => log_trace!("WebSocket error: {}", err),
+                                    }
+                                }
                                 TsMessage::Binary(_) | TsMessage::Text(_) | TsMessage::Close(_) => {
                                     self
                                         .receiver_channel
@@ -369,17 +729,36 @@ impl WebSocketInterface {
                     }
                 }
                 _ = self.shutdown.request.receiver.recv().fuse() => {
+                    if let Some(coalescer) = coalescer.as_mut() {
+                        self.flush(&mut ws_sender, coalescer).await.ok();
+                    }
                     self.receiver_channel.send(Message::Close).await?;
                     self.shutdown.response.sender.send(()).await?;
                     break;
                 }
+                _ = probe => {
+                    // the primary candidate is reachable again
+                    if let Some(primary) = primary {
+                        log_trace!("WebSocket reconnecting to primary {}", primary.url);
+                        options.notify(ConnectEvent::Promoted {
+                            url: primary.url.clone(),
+                        });
+                    }
+                    if let Some(coalescer) = coalescer.as_mut() {
+                        self.flush(&mut ws_sender, coalescer).await.ok();
+                    }
+                    ws_sender.close().await.ok();
+                    self.set_preferred(0);
+                    self.receiver_channel.send(Message::Close).await?;
+                    break;
+                }
             }
         }
 
         // if connection has closed ungracefully within 1 second, wait for retry interval
         #[cfg(feature = "delay-reconnect")]
         if closed_ungracefully && connection_start.elapsed().as_millis() < 1_000 {
-            workflow_core::task::sleep(_options.retry_interval()).await;
+            workflow_core::task::sleep(options.retry_interval()).await;
         }
 
         Ok(())
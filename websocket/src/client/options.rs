@@ -5,6 +5,7 @@ use std::fmt::Display;
 use std::str::FromStr;
 use wasm_bindgen::convert::TryFromJsValue;
 use wasm_bindgen::prelude::*;
+use workflow_core::channel::Sender;
 use workflow_core::time::Duration;
 
 /// `ConnectionStrategy` specifies how the WebSocket `async fn connect()`
@@ -60,6 +61,85 @@ impl TryFrom<JsValue> for ConnectStrategy {
     }
 }
 
+/// A URL the WebSocket may connect to, along with the settings
+/// used for the connection attempt. Multiple candidates can be supplied
+/// via [`ConnectOptions::with_candidates()`] and are attempted in order.
+#[derive(Clone, Debug)]
+pub struct ConnectCandidate {
+    /// `ws://` or `wss://` URL of the candidate.
+    pub url: String,
+    /// Timeout of the connection attempt, overriding
+    /// [`ConnectOptions::connect_timeout`] for this candidate.
+    pub timeout: Option<Duration>,
+    /// Additional HTTP headers supplied with the upgrade request.
+    /// Headers are not supported by browsers and are ignored in WASM.
+    pub headers: Vec<(String, String)>,
+}
+
+impl ConnectCandidate {
+    pub fn new<S: Display>(url: S) -> Self {
+        Self {
+            url: url.to_string(),
+            timeout: None,
+            headers: Vec::new(),
+        }
+    }
+
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    pub fn with_header<K: Display, V: Display>(mut self, name: K, value: V) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn timeout(&self, options: &ConnectOptions) -> Duration {
+        self.timeout.unwrap_or_else(|| options.connect_timeout())
+    }
+}
+
+impl From<&str> for ConnectCandidate {
+    fn from(url: &str) -> Self {
+        ConnectCandidate::new(url)
+    }
+}
+
+impl From<String> for ConnectCandidate {
+    fn from(url: String) -> Self {
+        ConnectCandidate::new(url)
+    }
+}
+
+/// Connection progress reported to the channel supplied
+/// via [`ConnectOptions::with_events()`].
+#[derive(Clone, Debug)]
+pub enum ConnectEvent {
+    /// Connection to `url` is being attempted
+    Attempt { url: String },
+    /// Connection to `url` has been established
+    Connected { url: String },
+    /// Connection to `url` has failed or timed out
+    Failed { url: String, error: String },
+    /// The primary candidate `url` is reachable again; the connection
+    /// to the fallback candidate is closed and `url` is reconnected.
+    Promoted { url: String },
+}
+
+impl ConnectEvent {
+    pub fn url(&self) -> &str {
+        match self {
+            ConnectEvent::Attempt { url }
+            | ConnectEvent::Connected { url }
+            | ConnectEvent::Failed { url, .. }
+            | ConnectEvent::Promoted { url } => url,
+        }
+    }
+}
+
 ///
 /// `ConnectOptions` is used to configure the `WebSocket` connectivity behavior.
 ///
@@ -80,10 +160,27 @@ pub struct ConnectOptions {
     pub connect_timeout: Option<Duration>,
     /// Retry interval denotes the time to wait before attempting to reconnect.
     pub retry_interval: Option<Duration>,
+    /// Ordered list of URLs to connect to. Each candidate is attempted
+    /// (with its own timeout) before moving to the next one; the retry
+    /// interval applies only once all candidates have failed.
+    /// The last successful candidate is attempted first on reconnect.
+    /// Candidates override `url` and the resolver.
+    pub candidates: Vec<ConnectCandidate>,
+    /// Interval at which the primary (first) candidate is probed while
+    /// connected to a fallback candidate. Once the primary is reachable,
+    /// the WebSocket reconnects to it.
+    pub primary_probe_interval: Option<Duration>,
+    /// Optional channel receiving [`ConnectEvent`] notifications.
+    pub events: Option<Sender<ConnectEvent>>,
+    /// Subprotocols offered to the server (`Sec-WebSocket-Protocol`)
+    /// in the order of preference. The protocol selected by the server
+    /// is available via [`WebSocket::negotiated_protocol()`](super::WebSocket::negotiated_protocol).
+    pub protocols: Vec<String>,
 }
 
 pub const DEFAULT_CONNECT_TIMEOUT_MILLIS: u64 = 5_000;
 pub const DEFAULT_CONNECT_RETRY_MILLIS: u64 = 5_000;
+pub const DEFAULT_PRIMARY_PROBE_MILLIS: u64 = 30_000;
 
 impl Default for ConnectOptions {
     fn default() -> Self {
@@ -93,6 +190,10 @@ impl Default for ConnectOptions {
             url: None,
             connect_timeout: None,
             retry_interval: None,
+            candidates: Vec::new(),
+            primary_probe_interval: None,
+            events: None,
+            protocols: Vec::new(),
         }
     }
 }
@@ -105,6 +206,10 @@ impl ConnectOptions {
             url: None,
             connect_timeout: None,
             retry_interval: None,
+            candidates: Vec::new(),
+            primary_probe_interval: None,
+            events: None,
+            protocols: Vec::new(),
         }
     }
     pub fn blocking_retry() -> Self {
@@ -114,6 +219,10 @@ impl ConnectOptions {
             url: None,
             connect_timeout: None,
             retry_interval: None,
+            candidates: Vec::new(),
+            primary_probe_interval: None,
+            events: None,
+            protocols: Vec::new(),
         }
     }
 
@@ -124,6 +233,10 @@ impl ConnectOptions {
             url: None,
             connect_timeout: None,
             retry_interval: None,
+            candidates: Vec::new(),
+            primary_probe_interval: None,
+            events: None,
+            protocols: Vec::new(),
         }
     }
 
@@ -148,6 +261,45 @@ impl ConnectOptions {
         }
     }
 
+    pub fn with_candidates<I, C>(self, candidates: I) -> Self
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<ConnectCandidate>,
+    {
+        Self {
+            candidates: candidates.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    pub fn with_primary_probe_interval(self, interval: Duration) -> Self {
+        Self {
+            primary_probe_interval: Some(interval),
+            ..self
+        }
+    }
+
+    pub fn with_events(self, sender: Sender<ConnectEvent>) -> Self {
+        Self {
+            events: Some(sender),
+            ..self
+        }
+    }
+
+    pub fn with_protocols<I, S>(self, protocols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Display,
+    {
+        Self {
+            protocols: protocols
+                .into_iter()
+                .map(|protocol| protocol.to_string())
+                .collect(),
+            ..self
+        }
+    }
+
     pub fn connect_timeout(&self) -> Duration {
         self.connect_timeout
             .unwrap_or(Duration::from_millis(DEFAULT_CONNECT_TIMEOUT_MILLIS))
@@ -157,6 +309,17 @@ impl ConnectOptions {
         self.retry_interval
             .unwrap_or(Duration::from_millis(DEFAULT_CONNECT_RETRY_MILLIS))
     }
+
+    pub fn primary_probe_interval(&self) -> Duration {
+        self.primary_probe_interval
+            .unwrap_or(Duration::from_millis(DEFAULT_PRIMARY_PROBE_MILLIS))
+    }
+
+    pub(crate) fn notify(&self, event: ConnectEvent) {
+        if let Some(events) = &self.events {
+            events.try_send(event).ok();
+        }
+    }
 }
 
 cfg_if! {
@@ -199,6 +362,20 @@ cfg_if! {
              * A custom retry interval in milliseconds.
              */
             retryInterval?: number,
+            /**
+             * An ordered list of URLs attempted in turn until
+             * the connection succeeds. Overrides `url`.
+             */
+            urls?: string[],
+            /**
+             * Interval in milliseconds at which the first URL is probed
+             * while connected to a fallback URL.
+             */
+            primaryProbeInterval?: number,
+            /**
+             * Subprotocols offered to the server in the order of preference.
+             */
+            protocols?: string[],
         }
         "#;
 
@@ -233,6 +410,32 @@ cfg_if! {
                         .get_value("retryInterval")?
                         .as_f64()
                         .map(|f| Duration::from_millis(f as u64));
+                    let urls = args.get_value("urls")?;
+                    let candidates = if urls.is_undefined() || urls.is_null() {
+                        Vec::new()
+                    } else {
+                        urls.dyn_into::<js_sys::Array>()
+                            .map_err(|_| Error::custom("`urls` must be an array of strings"))?
+                            .iter()
+                            .map(|url| url.as_string().map(ConnectCandidate::new))
+                            .collect::<Option<Vec<_>>>()
+                            .ok_or_else(|| Error::custom("`urls` must be an array of strings"))?
+                    };
+                    let primary_probe_interval = args
+                        .get_value("primaryProbeInterval")?
+                        .as_f64()
+                        .map(|f| Duration::from_millis(f as u64));
+                    let protocols = args.get_value("protocols")?;
+                    let protocols = if protocols.is_undefined() || protocols.is_null() {
+                        Vec::new()
+                    } else {
+                        protocols.dyn_into::<js_sys::Array>()
+                            .map_err(|_| Error::custom("`protocols` must be an array of strings"))?
+                            .iter()
+                            .map(|protocol| protocol.as_string())
+                            .collect::<Option<Vec<_>>>()
+                            .ok_or_else(|| Error::custom("`protocols` must be an array of strings"))?
+                    };
 
                     ConnectOptions {
                         block_async_connect,
@@ -240,6 +443,9 @@ cfg_if! {
                         url,
                         connect_timeout: timeout,
                         retry_interval,
+                        candidates,
+                        primary_probe_interval,
+                        protocols,
                         ..Default::default()
                     }
                 } else if let Some(retry) = args.as_bool() {
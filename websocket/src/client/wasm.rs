@@ -1,11 +1,13 @@
 use super::{
     bindings::WebSocket as W3CWebSocket,
+    coalescer::{acknowledge, AckSender, Coalescer},
     error::Error,
     message::{Ack, Message},
     result::Result,
-    ConnectOptions, ConnectResult, Handshake, Resolver, WebSocketConfig,
+    ConnectCandidate, ConnectEvent, ConnectOptions, ConnectResult, Handshake, Resolver,
+    WebSocketConfig,
 };
-use futures::{select, select_biased, FutureExt};
+use futures::{future::Fuse, select, select_biased, FutureExt};
 use js_sys::{ArrayBuffer, Uint8Array};
 use std::ops::Deref;
 use std::sync::{
@@ -19,7 +21,8 @@ use web_sys::{
 use workflow_core::runtime::*;
 use workflow_core::{
     channel::{oneshot, unbounded, Channel, DuplexChannel, Sender},
-    task::spawn,
+    task::{sleep, spawn},
+    time::Duration,
 };
 use workflow_log::*;
 use workflow_wasm::callback::*;
@@ -68,8 +71,14 @@ impl WebSocket {
         Ok(WebSocket(W3CWebSocket::new(url)?))
     }
 
-    pub fn new_with_config(url: &str, config: &WebSocketConfig) -> Result<Self> {
-        Ok(WebSocket(W3CWebSocket::new_with_config(url, config)?))
+    pub fn new_with_config(
+        url: &str,
+        config: &WebSocketConfig,
+        protocols: &[String],
+    ) -> Result<Self> {
+        Ok(WebSocket(W3CWebSocket::new_with_config(
+            url, config, protocols,
+        )?))
     }
 
     fn cleanup(&self) {
@@ -92,6 +101,12 @@ struct Settings {
     default_url: Option<String>,
     // URL WebSocket is currently connected to
     current_url: Option<String>,
+    // index of the last successful connection candidate
+    preferred: usize,
+    // number of failed attempts since the last successful connection
+    attempt: usize,
+    // subprotocol selected by the server
+    protocol: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -112,6 +127,7 @@ pub struct WebSocketInterface {
     event_channel: Channel<Message>,
     sender_channel: Channel<(Message, Ack)>,
     receiver_channel: Channel<Message>,
+    flush_channel: Channel<AckSender>,
     dispatcher_shutdown: DuplexChannel,
 }
 
@@ -121,6 +137,7 @@ impl WebSocketInterface {
         config: Option<WebSocketConfig>,
         sender_channel: Channel<(Message, Ack)>,
         receiver_channel: Channel<Message>,
+        flush_channel: Channel<AckSender>,
     ) -> Result<WebSocketInterface> {
         sanity_checks()?;
 
@@ -135,6 +152,7 @@ impl WebSocketInterface {
             config: Mutex::new(config.unwrap_or_default()),
             sender_channel,
             receiver_channel,
+            flush_channel,
             event_channel: Channel::unbounded(),
             reconnect: AtomicBool::new(true),
             is_connected: AtomicBool::new(false),
@@ -168,6 +186,27 @@ impl WebSocketInterface {
             .replace(url.to_string());
     }
 
+    pub fn negotiated_protocol(self: &Arc<Self>) -> Option<String> {
+        self.settings.lock().unwrap().protocol.clone()
+    }
+
+    /// Records the subprotocol selected by the server, failing
+    /// the connection if it has not been offered (RFC 6455 4.1).
+    /// Browsers enforce this, while Node.js modules may not.
+    fn accept_protocol(self: &Arc<Self>, ws: &WebSocket, options: &ConnectOptions) -> Result<()> {
+        let protocol = Some(ws.protocol()).filter(|protocol| !protocol.is_empty());
+        if let Some(protocol) = protocol.as_ref() {
+            if !options.protocols.contains(protocol) {
+                ws.close_if_open()?;
+                return Err(Error::Subprotocol(format!(
+                    "server selected `{protocol}` that was not offered"
+                )));
+            }
+        }
+        self.settings.lock().unwrap().protocol = protocol;
+        Ok(())
+    }
+
     pub fn is_connected(self: &Arc<Self>) -> bool {
         self.is_connected.load(Ordering::SeqCst)
     }
@@ -184,16 +223,73 @@ impl WebSocketInterface {
         *self.config.lock().unwrap() = config;
     }
 
-    async fn resolve_url(self: &Arc<Self>, options: &ConnectOptions) -> Result<String> {
-        let url = if let Some(url) = options.url.as_ref().or(self.default_url().as_ref()) {
-            url.clone()
+    async fn resolve_candidates(
+        self: &Arc<Self>,
+        options: &ConnectOptions,
+    ) -> Result<Vec<ConnectCandidate>> {
+        let candidates = if !options.candidates.is_empty() {
+            options.candidates.clone()
+        } else if let Some(url) = options.url.as_ref().or(self.default_url().as_ref()) {
+            vec![ConnectCandidate::new(url)]
         } else if let Some(resolver) = self.resolver() {
-            resolver.resolve_url().await?
+            resolver.resolve_candidates().await?
         } else {
             return Err(Error::MissingUrl);
         };
-        self.set_current_url(&url);
-        Ok(url)
+
+        if candidates.is_empty() {
+            return Err(Error::MissingUrl);
+        }
+        if let Some(candidate) = candidates.iter().find(|candidate| {
+            !candidate.url.starts_with("ws://") && !candidate.url.starts_with("wss://")
+        }) {
+            return Err(Error::AddressSchema(candidate.url.clone()));
+        }
+
+        Ok(candidates)
+    }
+
+    /// Index of the candidate to attempt next: the last successful
+    /// candidate followed by the remaining ones in order.
+    fn next_candidate(self: &Arc<Self>, len: usize) -> usize {
+        let settings = self.settings.lock().unwrap();
+        (settings.preferred + settings.attempt) % len
+    }
+
+    /// Records a failed attempt. Returns `true` if candidates
+    /// remain to be attempted before the retry interval applies.
+    fn candidate_failed(self: &Arc<Self>, len: usize) -> bool {
+        let mut settings = self.settings.lock().unwrap();
+        settings.attempt += 1;
+        if settings.attempt < len {
+            true
+        } else {
+            settings.attempt = 0;
+            false
+        }
+    }
+
+    fn candidate_connected(self: &Arc<Self>, index: usize) {
+        let mut settings = self.settings.lock().unwrap();
+        settings.preferred = index;
+        settings.attempt = 0;
+    }
+
+    /// Resolves once the `primary` candidate accepts a connection,
+    /// probing it every [`ConnectOptions::primary_probe_interval()`]
+    /// while this WebSocket is connected.
+    async fn probe_primary(self: &Arc<Self>, primary: &ConnectCandidate, options: &ConnectOptions) {
+        loop {
+            sleep(options.primary_probe_interval()).await;
+            if !self.is_connected() {
+                continue;
+            }
+            match probe(&primary.url, primary.timeout(options)).await {
+                Ok(true) => return,
+                Ok(false) => log_trace!("WebSocket primary {} is not reachable", primary.url),
+                Err(err) => log_trace!("WebSocket unable to probe {}: {err}", primary.url),
+            }
+        }
     }
 
     pub async fn connect(self: &Arc<Self>, options: ConnectOptions) -> ConnectResult<Error> {
@@ -233,8 +329,8 @@ impl WebSocketInterface {
 
         self.reconnect.store(true, Ordering::SeqCst);
 
-        let url = match self.resolve_url(&options).await {
-            Ok(url) => url,
+        let candidates = match self.resolve_candidates(&options).await {
+            Ok(candidates) => candidates,
             Err(err) => {
                 log_trace!("WebSocket unable to resolve URL: {err}");
                 let self_ = self.clone();
@@ -274,9 +370,26 @@ impl WebSocketInterface {
             }
         };
 
+        let index = self.next_candidate(candidates.len());
+        let candidate = &candidates[index];
+        if !candidate.headers.is_empty() {
+            log_trace!(
+                "WebSocket headers are not supported in the browser, ignoring headers of {}",
+                candidate.url
+            );
+        }
+        self.set_current_url(&candidate.url);
+        options.notify(ConnectEvent::Attempt {
+            url: candidate.url.clone(),
+        });
+
         let mut inner = self.inner.lock().unwrap();
 
-        let ws = WebSocket::new_with_config(&url, &self.config.lock().unwrap())?;
+        let ws = WebSocket::new_with_config(
+            &candidate.url,
+            &self.config.lock().unwrap(),
+            &options.protocols,
+        )?;
         ws.set_binary_type(web_sys::BinaryType::Arraybuffer);
 
         // - Message
@@ -329,18 +442,30 @@ impl WebSocketInterface {
 
         let self_ = self.clone();
         spawn(async move {
-            self_
-                .dispatcher_task(&ws, options.clone(), connect_trigger.clone())
+            let immediate = self_
+                .dispatcher_task(
+                    &ws,
+                    options.clone(),
+                    &candidates,
+                    index,
+                    connect_trigger.clone(),
+                )
                 .await
-                .unwrap_or_else(|err| log_trace!("WebSocket error: {err}"));
+                .unwrap_or_else(|err| {
+                    log_trace!("WebSocket error: {err}");
+                    false
+                });
             // if reconnect is true, we sleep for reconnect interval and try to reconnect
+            // (unless the next candidate or the promoted primary is to be attempted)
             if self_.reconnect.load(Ordering::SeqCst) {
-                workflow_core::task::sleep(
-                    options
-                        .retry_interval
-                        .unwrap_or(std::time::Duration::from_millis(1000)),
-                )
-                .await;
+                if !immediate {
+                    workflow_core::task::sleep(
+                        options
+                            .retry_interval
+                            .unwrap_or(std::time::Duration::from_millis(1000)),
+                    )
+                    .await;
+                }
                 // check again if reconnect may have been disabled during sleep
                 if self_.reconnect.load(Ordering::SeqCst) {
                     self_.reconnect(options, connect_trigger).await.ok();
@@ -406,13 +531,77 @@ impl WebSocketInterface {
         Ok(())
     }
 
+    /// Relays the outgoing message to the websocket, or to the
+    /// `coalescer` if the coalescing mode is enabled.
+    async fn dispatch(
+        self: &Arc<Self>,
+        ws: &WebSocket,
+        coalescer: &mut Option<Coalescer>,
+        msg: Message,
+        ack: Ack,
+    ) {
+        if let Some(coalescer) = coalescer {
+            if coalescer.push(msg, ack) {
+                Self::flush(ws, coalescer).await.ok();
+            }
+        } else if let Some(ack) = ack {
+            let result = ws.try_send(&msg).map(Arc::new).map_err(Arc::new);
+            ack.send(result).await.unwrap_or_else(|err| {
+                log_trace!("WebSocket error producing message ack {:?}", err)
+            });
+        } else {
+            ws.try_send(&msg).unwrap_or_else(|err| {
+                log_trace!("WebSocket unable to send `raw ws` message: `{err}`")
+            });
+        }
+    }
+
+    /// Sends the pending container of the `coalescer` (if any).
+    async fn flush(ws: &WebSocket, coalescer: &mut Coalescer) -> Result<()> {
+        if let Some((msg, acks)) = coalescer.take() {
+            let result = ws.try_send(&msg).map_err(Arc::new);
+            acknowledge(acks, &result).await;
+            if let Err(err) = result {
+                log_trace!("WebSocket unable to send `raw ws` message: `{err}`");
+                return Err(Error::custom(err));
+            }
+        }
+        Ok(())
+    }
+
     async fn dispatcher_task(
         self: &Arc<Self>,
         ws: &WebSocket,
         options: ConnectOptions,
+        candidates: &[ConnectCandidate],
+        index: usize,
         connect_trigger: Arc<Mutex<Option<Sender<Result<()>>>>>,
-    ) -> Result<()> {
+    ) -> Result<bool> {
+        let mut coalescer = self.config.lock().unwrap().coalescing.map(Coalescer::new);
+        let mut idle = Fuse::terminated();
+        let candidate = &candidates[index];
+        // reconnect without waiting for the retry interval
+        let mut immediate = false;
+        let mut timed_out = false;
+
+        let deadline = sleep(candidate.timeout(&options)).fuse();
+        futures::pin_mut!(deadline);
+        // probe the primary candidate while connected to a fallback
+        let probe = async {
+            match (index != 0).then(|| &candidates[0]) {
+                Some(primary) => self.probe_primary(primary, &options).await,
+                None => futures::future::pending().await,
+            }
+        }
+        .fuse();
+        futures::pin_mut!(probe);
+
         'outer: loop {
+            let timer = match coalescer.as_mut() {
+                Some(coalescer) => coalescer.timer(),
+                None => &mut idle,
+            };
+
             select! {
                 _ = self.dispatcher_shutdown.request.receiver.recv().fuse() => {
                     break 'outer;
@@ -421,16 +610,31 @@ impl WebSocketInterface {
                     match msg {
                         Ok(msg) => {
                             match msg {
+                                Message::Binary(data) if coalescer.is_some() => {
+                                    match Coalescer::split(&data) {
+                                        Ok(messages) => {
+                                            for msg in messages {
+                                                self.receiver_channel.sender.send(msg).await.unwrap();
+                                            }
+                                        }
+                                        Err(err) => log_trace!("WebSocket error: {err}"),
+                                    }
+                                },
                                 Message::Binary(_) | Message::Text(_) => {
                                     self.receiver_channel.sender.send(msg).await.unwrap();
                                 },
                                 Message::Open => {
                                     // log_info!("WebSocket Message::Open");
-                                    // handle handshake failure
-                                    if let Err(err) = self.handshake_impl(ws).await {
+                                    // handle subprotocol or handshake failure
+                                    let negotiation = match self.accept_protocol(ws, &options) {
+                                        Ok(()) => self.handshake_impl(ws).await,
+                                        Err(err) => Err(err),
+                                    };
+                                    if let Err(err) = negotiation {
                                         log_info!("WebSocket handshake negotiation error: {err}");
 
-                                        if options.strategy.is_fallback() {
+                                        // subprotocol mismatch is not resolved by retrying
+                                        if options.strategy.is_fallback() || matches!(err, Error::Subprotocol(_)) {
                                             self.reconnect.store(false, Ordering::SeqCst);
                                         }
 
@@ -443,6 +647,10 @@ impl WebSocketInterface {
                                     }
 
                                     self.is_connected.store(true, Ordering::SeqCst);
+                                    self.candidate_connected(index);
+                                    options.notify(ConnectEvent::Connected {
+                                        url: candidate.url.clone(),
+                                    });
 
                                     let connect_trigger = connect_trigger.lock().unwrap().take();
                                     if let Some(connect_trigger) = connect_trigger {
@@ -461,14 +669,29 @@ impl WebSocketInterface {
                                     if self.is_connected.load(Ordering::SeqCst) {
                                         self.is_connected.store(false, Ordering::SeqCst);
                                         self.receiver_channel.sender.send(msg).await.unwrap();
-                                    } else if options.strategy.is_fallback() && options.block_async_connect {
-                                        // if we never connected and receiver Close while
-                                        // the strategy is Fallback, we disable reconnect
-                                        self.reconnect.store(false, Ordering::SeqCst);
+                                    } else {
+                                        let error = if timed_out {
+                                            Error::ConnectionTimeout
+                                        } else {
+                                            Error::Connect(candidate.url.clone())
+                                        };
+                                        options.notify(ConnectEvent::Failed {
+                                            url: candidate.url.clone(),
+                                            error: error.to_string(),
+                                        });
+
+                                        if self.candidate_failed(candidates.len()) {
+                                            // attempt the next candidate
+                                            immediate = true;
+                                        } else if options.strategy.is_fallback() && options.block_async_connect {
+                                            // if we never connected and receiver Close while
+                                            // the strategy is Fallback, we disable reconnect
+                                            self.reconnect.store(false, Ordering::SeqCst);
 
-                                        let connect_trigger = connect_trigger.lock().unwrap().take();
-                                        if let Some(connect_trigger) = connect_trigger {
-                                            connect_trigger.send(Err(Error::Connect(self.current_url().unwrap()))).await.ok();
+                                            let connect_trigger = connect_trigger.lock().unwrap().take();
+                                            if let Some(connect_trigger) = connect_trigger {
+                                                connect_trigger.send(Err(error)).await.ok();
+                                            }
                                         }
                                     }
 
@@ -481,6 +704,43 @@ impl WebSocketInterface {
                         }
                     }
                 },
+                flush = self.flush_channel.recv().fuse() => {
+                    if let Ok(ack) = flush {
+                        // relay messages posted prior to the flush request
+                        while let Ok((msg, msg_ack)) = self.sender_channel.try_recv() {
+                            self.dispatch(ws, &mut coalescer, msg, msg_ack).await;
+                        }
+                        let result = match coalescer.as_mut() {
+                            Some(coalescer) => Self::flush(ws, coalescer).await,
+                            None => Ok(()),
+                        };
+                        ack.send(result.map(Arc::new).map_err(Arc::new)).await.unwrap_or_else(|err| {
+                            log_trace!("WebSocket error producing message ack {:?}", err)
+                        });
+                    }
+                }
+                _ = &mut *timer => {
+                    if let Some(coalescer) = coalescer.as_mut() {
+                        Self::flush(ws, coalescer).await.ok();
+                    }
+                }
+                _ = deadline => {
+                    if !self.is_connected() {
+                        // produces `Close` via the `onclose` callback
+                        timed_out = true;
+                        ws.close_if_open().ok();
+                    }
+                }
+                _ = probe => {
+                    // the primary candidate is reachable again
+                    log_trace!("WebSocket reconnecting to primary {}", candidates[0].url);
+                    options.notify(ConnectEvent::Promoted {
+                        url: candidates[0].url.clone(),
+                    });
+                    self.candidate_connected(0);
+                    immediate = true;
+                    ws.close_if_open().ok();
+                }
                 msg = self.sender_channel.receiver.recv().fuse() => {
 
                     if let Ok((msg, ack)) = msg {
@@ -489,25 +749,13 @@ impl WebSocketInterface {
                         //     return Err(Error::NotConnected);
                         // }
 
-                        if let Some(ack) = ack {
-                            let result = ws
-                                .try_send(&msg)
-                                .map(Arc::new)
-                                .map_err(Arc::new);
-                            ack.send(result).await.unwrap_or_else(|err| {
-                                log_trace!("WebSocket error producing message ack {:?}", err)
-                            });
-                        } else {
-                            ws.try_send(&msg).unwrap_or_else(|err| {
-                                log_trace!("WebSocket unable to send `raw ws` message: `{err}`")
-                            });
-                        }
+                        self.dispatch(ws, &mut coalescer, msg, ack).await;
                     }
                 }
             }
         }
 
-        Ok(())
+        Ok(immediate)
     }
 
     async fn _shutdown(self: &Arc<Self>) -> Result<()> {
@@ -593,6 +841,31 @@ impl TrySendMessage for WebSocket {
     }
 }
 
+/// Opens a throwaway connection to `url`, returning `true`
+/// if it has been established within `timeout`.
+async fn probe(url: &str, timeout: Duration) -> Result<bool> {
+    let ws = WebSocket::new(url)?;
+    let (sender, receiver) = oneshot::<bool>();
+    let sender_ = sender.clone();
+    let onopen = callback!(move || {
+        sender_.try_send(true).ok();
+    });
+    let onclose = callback!(move |_event: WsCloseEvent| {
+        sender.try_send(false).ok();
+    });
+    ws.set_onopen(Some(onopen.as_ref()));
+    ws.set_onclose(Some(onclose.as_ref()));
+
+    let open = select! {
+        open = receiver.recv().fuse() => open.unwrap_or(false),
+        _ = sleep(timeout).fuse() => false,
+    };
+
+    ws.cleanup();
+    ws.close_if_open()?;
+    Ok(open)
+}
+
 fn w3c_websocket_available() -> Result<bool> {
     Ok(js_sys::Reflect::get(&js_sys::global(), &"WebSocket".into())
         .map(|v| !v.is_falsy())
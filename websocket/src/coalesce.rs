@@ -0,0 +1,186 @@
+//!
+//! Nagle-style message coalescing. Messages posted within a short
+//! window (or until a byte threshold is reached) are combined into
+//! a single binary frame carrying a length-prefixed container that
+//! the receiving side splits back into individual messages.
+//!
+//! Coalescing is not negotiated and must be configured symmetrically
+//! on the client ([`WebSocketConfig::coalescing`](crate::client::WebSocketConfig::coalescing))
+//! and the server (`WebSocketServer::new_with_coalescing()`).
+//! Once enabled, all text and binary messages exchanged over the
+//! connection are carried in containers.
+//!
+//! The container is a sequence of `[kind: u8][length: u32 LE][payload]`
+//! entries, where `kind` is `0` for binary and `1` for text messages.
+//!
+
+use std::time::Duration;
+
+const KIND_BINARY: u8 = 0;
+const KIND_TEXT: u8 = 1;
+const HEADER_LEN: usize = 5;
+
+pub const DEFAULT_COALESCING_WINDOW_MILLIS: u64 = 2;
+pub const DEFAULT_COALESCING_MAX_BYTES: usize = 64 * 1024;
+
+/// Coalescing settings. By default, messages are accumulated
+/// for 2 msec or until the container reaches 64 KiB.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Coalescing {
+    /// Period, starting with the first accumulated message,
+    /// after which the container is sent.
+    pub window: Duration,
+    /// Container size in bytes that causes the container
+    /// to be sent before the `window` elapses.
+    pub max_bytes: usize,
+}
+
+impl Default for Coalescing {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_millis(DEFAULT_COALESCING_WINDOW_MILLIS),
+            max_bytes: DEFAULT_COALESCING_MAX_BYTES,
+        }
+    }
+}
+
+impl Coalescing {
+    pub fn new(window: Duration, max_bytes: usize) -> Self {
+        Self { window, max_bytes }
+    }
+}
+
+/// Message carried in a container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Payload {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// Container accumulating messages until taken for dispatch.
+#[derive(Default)]
+pub struct Batch {
+    buffer: Vec<u8>,
+    messages: usize,
+}
+
+impl Batch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_text(&mut self, text: &str) {
+        self.push(KIND_TEXT, text.as_bytes());
+    }
+
+    pub fn push_binary(&mut self, data: &[u8]) {
+        self.push(KIND_BINARY, data);
+    }
+
+    fn push(&mut self, kind: u8, data: &[u8]) {
+        self.buffer.reserve(HEADER_LEN + data.len());
+        self.buffer.push(kind);
+        self.buffer
+            .extend_from_slice(&(data.len() as u32).to_le_bytes());
+        self.buffer.extend_from_slice(data);
+        self.messages += 1;
+    }
+
+    /// Number of accumulated messages
+    pub fn len(&self) -> usize {
+        self.messages
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages == 0
+    }
+
+    /// Size of the container in bytes
+    pub fn size(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Takes the container, leaving the batch empty.
+    /// Returns `None` if no messages have been accumulated.
+    pub fn take(&mut self) -> Option<Vec<u8>> {
+        if self.is_empty() {
+            None
+        } else {
+            self.messages = 0;
+            Some(std::mem::take(&mut self.buffer))
+        }
+    }
+}
+
+/// Splits the container into individual messages.
+/// Returns `None` if the container is malformed.
+pub fn split(data: &[u8]) -> Option<Vec<Payload>> {
+    let mut payloads = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let header = rest.get(..HEADER_LEN)?;
+        let len = u32::from_le_bytes(header[1..].try_into().ok()?) as usize;
+        let end = HEADER_LEN.checked_add(len)?;
+        let payload = rest.get(HEADER_LEN..end)?;
+        payloads.push(match header[0] {
+            KIND_BINARY => Payload::Binary(payload.to_vec()),
+            KIND_TEXT => Payload::Text(String::from_utf8(payload.to_vec()).ok()?),
+            _ => return None,
+        });
+        rest = &rest[end..];
+    }
+    Some(payloads)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_container_round_trip() {
+        let mut batch = Batch::new();
+        assert!(batch.take().is_none());
+
+        batch.push_text("hello");
+        batch.push_binary(&[1, 2, 3]);
+        batch.push_text("");
+        batch.push_binary(&[]);
+        assert_eq!(batch.len(), 4);
+        assert_eq!(batch.size(), 4 * HEADER_LEN + 8);
+
+        let container = batch.take().unwrap();
+        assert!(batch.is_empty() && batch.size() == 0);
+        assert_eq!(
+            split(&container).unwrap(),
+            vec![
+                Payload::Text("hello".to_string()),
+                Payload::Binary(vec![1, 2, 3]),
+                Payload::Text(String::new()),
+                Payload::Binary(vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_container() {
+        let mut batch = Batch::new();
+        batch.push_binary(&[1, 2, 3]);
+        let container = batch.take().unwrap();
+
+        // truncated header and payload
+        assert!(split(&container[..3]).is_none());
+        assert!(split(&container[..container.len() - 1]).is_none());
+        // unknown kind
+        let mut unknown = container.clone();
+        unknown[0] = 2;
+        assert!(split(&unknown).is_none());
+        // invalid utf-8 text
+        let mut text = container;
+        text[0] = KIND_TEXT;
+        text[HEADER_LEN] = 0xff;
+        assert!(split(&text).is_none());
+        // oversized length
+        assert!(split(&[KIND_BINARY, 0xff, 0xff, 0xff, 0xff]).is_none());
+        assert_eq!(split(&[]).unwrap(), vec![]);
+    }
+}
@@ -23,6 +23,7 @@
 //!
 
 pub mod client;
+pub mod coalesce;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod server;
 
@@ -68,6 +68,10 @@ pub enum Error {
     #[error("Error waiting for listener shutdown: {0}")]
     Join(String),
 
+    /// TLS configuration or handshake error
+    #[error("TLS error: {0}")]
+    Tls(String),
+
     #[error(transparent)]
     IoError(#[from] std::io::Error),
 }
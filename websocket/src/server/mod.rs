@@ -11,31 +11,41 @@ use futures_util::{
 };
 use std::net::SocketAddr;
 use std::pin::Pin;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 pub use tokio::net::TcpListener;
 use tokio::net::TcpStream;
 use tokio::sync::mpsc::{
     UnboundedReceiver as TokioUnboundedReceiver, UnboundedSender as TokioUnboundedSender,
 };
-use tokio_tungstenite::{accept_async_with_config, WebSocketStream};
+use tokio_tungstenite::{accept_hdr_async_with_config, WebSocketStream};
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
 use tungstenite::Error as WebSocketError;
-use workflow_core::channel::DuplexChannel;
+use workflow_core::channel::{Channel, DuplexChannel, Receiver};
 use workflow_log::*;
 pub mod error;
 pub mod result;
+pub mod router;
+pub mod stream;
+#[cfg(any(test, feature = "server-tls"))]
+pub mod tls;
 
+pub use crate::coalesce::Coalescing;
+use crate::coalesce::{split, Batch, Payload};
 pub use error::Error;
 pub use result::Result;
+pub use router::{Router, TextMatcher};
+pub use stream::ServerStream;
 pub use tungstenite::protocol::WebSocketConfig;
 pub use tungstenite::Message;
 /// WebSocket stream sender for dispatching [`tungstenite::Message`].
 /// This stream object must have a mutable reference and can not be cloned.
-pub type WebSocketSender = SplitSink<WebSocketStream<TcpStream>, Message>;
+pub type WebSocketSender = SplitSink<WebSocketStream<ServerStream>, Message>;
 /// WebSocket stream receiver for receiving [`tungstenite::Message`].
 /// This stream object must have a mutable reference and can not be cloned.
-pub type WebSocketReceiver = SplitStream<WebSocketStream<TcpStream>>;
+pub type WebSocketReceiver = SplitStream<WebSocketStream<ServerStream>>;
 /// WebSocketSink [`tokio::sync::mpsc::UnboundedSender`] for dispatching
 /// messages from within the [`WebSocketHandler::message`]. This is an
 /// `MPSC` channel that can be cloned and retained externally for the
@@ -50,9 +60,18 @@ pub type WebSocketSink = TokioUnboundedSender<Message>;
 pub struct WebSocketCounters {
     pub total_connections: Arc<AtomicUsize>,
     pub active_connections: Arc<AtomicUsize>,
+    /// Connections failing the TLS or the handler handshake
     pub handshake_failures: Arc<AtomicUsize>,
+    /// Connections rejected due to the maximum connection
+    /// limit or vetoed by the accept filter
+    pub rejected_connections: Arc<AtomicUsize>,
+    /// Connections rejected while the server is draining
+    pub drained_connections: Arc<AtomicUsize>,
     pub rx_bytes: Arc<AtomicUsize>,
     pub tx_bytes: Arc<AtomicUsize>,
+    /// TLS connections counted per certificate serial number
+    #[cfg(any(test, feature = "server-tls"))]
+    pub certificates: Arc<tls::CertificateCounters>,
 }
 
 impl Default for WebSocketCounters {
@@ -61,8 +80,12 @@ impl Default for WebSocketCounters {
             total_connections: Arc::new(AtomicUsize::new(0)),
             active_connections: Arc::new(AtomicUsize::new(0)),
             handshake_failures: Arc::new(AtomicUsize::new(0)),
+            rejected_connections: Arc::new(AtomicUsize::new(0)),
+            drained_connections: Arc::new(AtomicUsize::new(0)),
             rx_bytes: Arc::new(AtomicUsize::new(0)),
             tx_bytes: Arc::new(AtomicUsize::new(0)),
+            #[cfg(any(test, feature = "server-tls"))]
+            certificates: Arc::new(tls::CertificateCounters::default()),
         }
     }
 }
@@ -125,6 +148,44 @@ where
     }
 }
 
+/// Accept-time filter receiving the peer address and the upgrade request.
+/// Returning `false` vetoes the connection (responding `403 Forbidden`).
+pub type AcceptFilterFn = Arc<dyn Fn(&SocketAddr, &Request) -> bool + Send + Sync>;
+
+/// Handling of connections rejected due to the maximum
+/// connection limit or while the server is draining
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rejection {
+    /// Read the upgrade request and respond with `503 Service Unavailable`
+    #[default]
+    ServiceUnavailable,
+    /// Close the TCP connection immediately (the least expensive option)
+    Close,
+}
+
+/// Connection admission settings (see [`WebSocketServer::set_max_connections()`],
+/// [`WebSocketServer::set_accept_filter()`] and [`WebSocketServer::drain()`])
+#[derive(Default)]
+struct Admission {
+    /// `0` if the connection count is not limited
+    max_connections: AtomicUsize,
+    rejection: Mutex<Rejection>,
+    draining: AtomicBool,
+    filter: Mutex<Option<AcceptFilterFn>>,
+}
+
+/// Events posted by the [`WebSocketServer`] (see [`WebSocketServer::events()`])
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerEvent {
+    /// New connections are accepted using the certificate with the `serial` number
+    TlsReloaded { serial: String },
+    /// The certificate has been rejected and the previous TLS configuration remains in effect
+    TlsReloadFailed { error: String },
+}
+
+/// Upgrade request timeout of connections answered with `503 Service Unavailable`
+const REJECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// WebSocketServer that provides the main websocket connection
 /// and message processing loop that delivers messages to the
 /// installed WebSocketHandler trait.
@@ -136,6 +197,57 @@ where
     pub counters: Arc<WebSocketCounters>,
     pub handler: Arc<T>,
     pub stop: DuplexChannel,
+    /// Coalescing mode applied to all connections (see [`crate::coalesce`]).
+    pub coalescing: Option<Coalescing>,
+    admission: Admission,
+    events: Channel<ServerEvent>,
+    #[cfg(any(test, feature = "server-tls"))]
+    tls: tls::Tls,
+}
+
+impl From<Payload> for Message {
+    fn from(payload: Payload) -> Self {
+        match payload {
+            Payload::Text(text) => Message::Text(text),
+            Payload::Binary(data) => Message::Binary(data),
+        }
+    }
+}
+
+/// Container of outgoing messages accumulated within the coalescing window.
+struct Outbox {
+    coalescing: Coalescing,
+    batch: Batch,
+    deadline: Option<tokio::time::Instant>,
+}
+
+impl Outbox {
+    fn new(coalescing: Coalescing) -> Self {
+        Self {
+            coalescing,
+            batch: Batch::new(),
+            deadline: None,
+        }
+    }
+
+    /// Appends the message to the container, returning `true`
+    /// if the container has reached the size threshold.
+    fn push(&mut self, msg: &Message) -> bool {
+        match msg {
+            Message::Text(text) => self.batch.push_text(text),
+            Message::Binary(data) => self.batch.push_binary(data),
+            _ => unreachable!("only text and binary messages are coalesced"),
+        }
+        if self.deadline.is_none() {
+            self.deadline = Some(tokio::time::Instant::now() + self.coalescing.window);
+        }
+        self.batch.size() >= self.coalescing.max_bytes
+    }
+
+    fn take(&mut self) -> Option<Message> {
+        self.deadline = None;
+        self.batch.take().map(Message::Binary)
+    }
 }
 
 impl<T> WebSocketServer<T>
@@ -147,16 +259,270 @@ where
             counters: counters.unwrap_or_default(),
             handler,
             stop: DuplexChannel::oneshot(),
+            coalescing: None,
+            admission: Admission::default(),
+            events: Channel::unbounded(),
+            #[cfg(any(test, feature = "server-tls"))]
+            tls: tls::Tls::default(),
         })
     }
 
+    /// Create a server exchanging messages in containers coalesced
+    /// according to `coalescing` (see [`crate::coalesce`]). Clients
+    /// must be configured using the same option.
+    pub fn new_with_coalescing(
+        handler: Arc<T>,
+        counters: Option<Arc<WebSocketCounters>>,
+        coalescing: Coalescing,
+    ) -> Arc<Self> {
+        Arc::new(WebSocketServer {
+            counters: counters.unwrap_or_default(),
+            handler,
+            stop: DuplexChannel::oneshot(),
+            coalescing: Some(coalescing),
+            admission: Admission::default(),
+            events: Channel::unbounded(),
+            #[cfg(any(test, feature = "server-tls"))]
+            tls: tls::Tls::default(),
+        })
+    }
+
+    /// Limit the number of concurrent connections (`None` for no limit).
+    /// The limit is enforced before the websocket upgrade; connections
+    /// exceeding the limit are rejected according to [`Self::set_rejection()`].
+    pub fn set_max_connections(&self, max_connections: Option<usize>) {
+        self.admission
+            .max_connections
+            .store(max_connections.unwrap_or(0), Ordering::SeqCst);
+    }
+
+    pub fn max_connections(&self) -> Option<usize> {
+        match self.admission.max_connections.load(Ordering::SeqCst) {
+            0 => None,
+            max_connections => Some(max_connections),
+        }
+    }
+
+    /// Set the handling of connections rejected due to the
+    /// connection limit or while draining
+    pub fn set_rejection(&self, rejection: Rejection) {
+        *self.admission.rejection.lock().unwrap() = rejection;
+    }
+
+    /// Install a filter receiving the peer address and the upgrade
+    /// request of each connection, allowing the connection to be vetoed.
+    /// The filter is invoked in addition to [`WebSocketHandler::accept()`].
+    pub fn set_accept_filter<F>(&self, filter: F)
+    where
+        F: Fn(&SocketAddr, &Request) -> bool + Send + Sync + 'static,
+    {
+        *self.admission.filter.lock().unwrap() = Some(Arc::new(filter));
+    }
+
+    /// Accept connections only from peers allowed by the `ip_filter`
+    #[cfg(feature = "ip-filter")]
+    pub fn set_ip_filter(&self, ip_filter: workflow_utils::ip::IpFilter) {
+        self.set_accept_filter(move |peer, _request| ip_filter.accept(peer));
+    }
+
+    pub fn clear_accept_filter(&self) {
+        *self.admission.filter.lock().unwrap() = None;
+    }
+
+    /// Enter the drain mode: existing connections continue to be served
+    /// while new connections are rejected (according to [`Self::set_rejection()`]),
+    /// allowing the server to be retired once [`Self::active_connections()`] reaches zero.
+    pub fn drain(&self) {
+        self.admission.draining.store(true, Ordering::SeqCst);
+    }
+
+    /// Leave the drain mode, accepting new connections
+    pub fn resume(&self) {
+        self.admission.draining.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.admission.draining.load(Ordering::SeqCst)
+    }
+
+    pub fn active_connections(&self) -> usize {
+        self.counters.active_connections.load(Ordering::SeqCst)
+    }
+
+    /// Receiver of the [`ServerEvent`] notifications
+    pub fn events(&self) -> Receiver<ServerEvent> {
+        self.events.receiver.clone()
+    }
+
+    /// Enable TLS termination, or replace the certificate used for new
+    /// connections; established connections retain their sessions.
+    /// If the certificate chain or the key are rejected, the previous
+    /// configuration remains in effect and the error is both returned
+    /// and posted as [`ServerEvent::TlsReloadFailed`].
+    #[cfg(any(test, feature = "server-tls"))]
+    pub fn reload_tls(
+        &self,
+        cert_chain: Vec<tls::CertificateDer<'static>>,
+        key: tls::PrivateKeyDer<'static>,
+    ) -> Result<()> {
+        self.post_reload(self.tls.store(cert_chain, key))
+    }
+
+    /// Same as [`Self::reload_tls()`], receiving the PEM-encoded
+    /// certificate chain and private key.
+    #[cfg(any(test, feature = "server-tls"))]
+    pub fn reload_tls_pem(&self, cert_chain: &[u8], key: &[u8]) -> Result<()> {
+        let result = tls::load_pem(cert_chain, key)
+            .and_then(|(cert_chain, key)| self.tls.store(cert_chain, key));
+        self.post_reload(result)
+    }
+
+    #[cfg(any(test, feature = "server-tls"))]
+    fn post_reload(&self, result: Result<String>) -> Result<()> {
+        let event = match &result {
+            Ok(serial) => ServerEvent::TlsReloaded {
+                serial: serial.clone(),
+            },
+            Err(err) => ServerEvent::TlsReloadFailed {
+                error: err.to_string(),
+            },
+        };
+        self.events.try_send(event).ok();
+        result.map(|_| ())
+    }
+
+    /// Disable TLS termination for new connections
+    #[cfg(any(test, feature = "server-tls"))]
+    pub fn disable_tls(&self) {
+        self.tls.clear();
+    }
+
+    /// Serial number of the certificate used for new
+    /// connections (`None` if TLS is disabled)
+    #[cfg(any(test, feature = "server-tls"))]
+    pub fn tls_serial(&self) -> Option<String> {
+        self.tls.load().map(|tls| tls.serial().to_string())
+    }
+
+    /// Poll the PEM-encoded certificate chain and key files every `interval`,
+    /// reloading the TLS configuration (see [`Self::reload_tls()`]) when their
+    /// contents change. Read errors are posted as [`ServerEvent::TlsReloadFailed`].
+    /// Files that are not replaced simultaneously may produce a transient
+    /// failure until both are updated. The task terminates when the server is
+    /// dropped or can be aborted using the returned handle.
+    #[cfg(any(test, feature = "server-tls-watch"))]
+    pub fn watch_tls(
+        self: &Arc<Self>,
+        cert_chain: impl AsRef<std::path::Path>,
+        key: impl AsRef<std::path::Path>,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let server = Arc::downgrade(self);
+        let cert_chain = cert_chain.as_ref().to_path_buf();
+        let key = key.as_ref().to_path_buf();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            let mut current = None;
+            let mut read_error = None;
+            loop {
+                interval.tick().await;
+                let Some(server) = server.upgrade() else {
+                    break;
+                };
+                let contents = async {
+                    let cert_chain = async_std::fs::read(&cert_chain).await?;
+                    let key = async_std::fs::read(&key).await?;
+                    std::io::Result::Ok((cert_chain, key))
+                }
+                .await;
+                match contents {
+                    Ok(contents) => {
+                        read_error = None;
+                        if current.as_ref() != Some(&contents) {
+                            server.reload_tls_pem(&contents.0, &contents.1).ok();
+                            current = Some(contents);
+                        }
+                    }
+                    Err(err) => {
+                        let error = format!("unable to read the certificate: {err}");
+                        if read_error.as_ref() != Some(&error) {
+                            server
+                                .events
+                                .try_send(ServerEvent::TlsReloadFailed {
+                                    error: error.clone(),
+                                })
+                                .ok();
+                            read_error = Some(error);
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Relays the outgoing text or binary message to the websocket,
+    /// or to the `outbox` if the coalescing mode is enabled.
+    async fn dispatch(
+        &self,
+        ws_sender: &mut WebSocketSender,
+        outbox: &mut Option<Outbox>,
+        msg: Message,
+    ) -> Result<()> {
+        match outbox {
+            Some(outbox) => {
+                if outbox.push(&msg) {
+                    Self::flush(ws_sender, outbox).await?;
+                }
+            }
+            None => ws_sender.send(msg).await?,
+        }
+        Ok(())
+    }
+
+    /// Sends the pending container of the `outbox` (if any).
+    async fn flush(ws_sender: &mut WebSocketSender, outbox: &mut Outbox) -> Result<()> {
+        if let Some(msg) = outbox.take() {
+            ws_sender.send(msg).await?;
+        }
+        Ok(())
+    }
+
     async fn handle_connection(
         self: &Arc<Self>,
         peer: SocketAddr,
         stream: TcpStream,
         config: Option<WebSocketConfig>,
     ) -> Result<()> {
-        let ws_stream = accept_async_with_config(stream, config).await?;
+        #[cfg(any(test, feature = "server-tls"))]
+        let (stream, _certificate) = match self.tls.load() {
+            Some(tls) => match tls.accept(stream).await {
+                Ok(stream) => (
+                    stream,
+                    Some(self.counters.certificates.connect(tls.serial())),
+                ),
+                Err(err) => {
+                    self.counters
+                        .handshake_failures
+                        .fetch_add(1, Ordering::Relaxed);
+                    return Err(err);
+                }
+            },
+            None => (ServerStream::Plain(stream), None),
+        };
+        #[cfg(not(any(test, feature = "server-tls")))]
+        let stream = ServerStream::Plain(stream);
+
+        let filter = self.admission.filter.lock().unwrap().clone();
+        let callback = |request: &Request, response: Response| match filter {
+            Some(filter) if !filter(&peer, request) => {
+                self.counters
+                    .rejected_connections
+                    .fetch_add(1, Ordering::Relaxed);
+                Err(error_response(StatusCode::FORBIDDEN))
+            }
+            _ => Ok(response),
+        };
+        let ws_stream = accept_hdr_async_with_config(stream, callback, config).await?;
         self.handler.connect(&peer).await?;
         // log_trace!("WebSocket connected: {}", peer);
 
@@ -194,20 +560,32 @@ where
         sink_sender: TokioUnboundedSender<Message>,
         mut sink_receiver: TokioUnboundedReceiver<Message>,
     ) -> Result<()> {
+        let mut outbox = self.coalescing.map(Outbox::new);
+
         loop {
+            let deadline = outbox.as_ref().and_then(|outbox| outbox.deadline);
+
             tokio::select! {
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(tokio::time::Instant::now)), if deadline.is_some() => {
+                    if let Some(outbox) = outbox.as_mut() {
+                        Self::flush(&mut ws_sender, outbox).await?;
+                    }
+                },
                 msg = sink_receiver.recv() => {
                     let msg = msg.unwrap();
                     match msg {
                         Message::Binary(data)  => {
                             self.counters.tx_bytes.fetch_add(data.len(), Ordering::Relaxed);
-                            ws_sender.send(Message::Binary(data)).await?;
+                            self.dispatch(&mut ws_sender, &mut outbox, Message::Binary(data)).await?;
                         },
                         Message::Text(text)  => {
                             self.counters.tx_bytes.fetch_add(text.len(), Ordering::Relaxed);
-                            ws_sender.send(Message::Text(text)).await?;
+                            self.dispatch(&mut ws_sender, &mut outbox, Message::Text(text)).await?;
                         },
                         Message::Close(_) => {
+                            if let Some(outbox) = outbox.as_mut() {
+                                Self::flush(&mut ws_sender, outbox).await?;
+                            }
                             ws_sender.send(msg).await?;
                             break;
                         },
@@ -229,6 +607,12 @@ where
                         Some(msg) => {
                             let msg = msg?;
                             match msg {
+                                Message::Binary(data) if self.coalescing.is_some() => {
+                                    self.counters.rx_bytes.fetch_add(data.len(), Ordering::Relaxed);
+                                    for payload in split(&data).ok_or(Error::MalformedMessage)? {
+                                        self.handler.message(ctx, payload.into(), &sink_sender).await?;
+                                    }
+                                },
                                 Message::Binary(data)  => {
                                     self.counters.rx_bytes.fetch_add(data.len(), Ordering::Relaxed);
                                     self.handler.message(ctx, Message::Binary(data), &sink_sender).await?;
@@ -310,7 +694,9 @@ where
                 match e {
                     Error::WebSocketError(WebSocketError::ConnectionClosed)
                     | Error::WebSocketError(WebSocketError::Protocol(_))
-                    | Error::WebSocketError(WebSocketError::Utf8) => (),
+                    | Error::WebSocketError(WebSocketError::Utf8)
+                    | Error::WebSocketError(WebSocketError::Http(_))
+                    | Error::Tls(_) => (),
                     err => log_error!("Error processing connection: {}", err),
                 }
             }
@@ -321,6 +707,33 @@ where
         });
     }
 
+    /// Rejects the connection according to the [`Rejection`] setting
+    fn reject(&self, stream: TcpStream) {
+        let rejection = *self.admission.rejection.lock().unwrap();
+        match rejection {
+            Rejection::Close => drop(stream),
+            Rejection::ServiceUnavailable => {
+                #[cfg(any(test, feature = "server-tls"))]
+                let tls = self.tls.load();
+                tokio::spawn(async move {
+                    tokio::time::timeout(REJECTION_TIMEOUT, async move {
+                        #[cfg(any(test, feature = "server-tls"))]
+                        let stream = match tls {
+                            Some(tls) => tls.accept(stream).await?,
+                            None => ServerStream::Plain(stream),
+                        };
+                        #[cfg(not(any(test, feature = "server-tls")))]
+                        let stream = ServerStream::Plain(stream);
+                        accept_hdr_async_with_config(stream, service_unavailable, None).await?;
+                        Result::Ok(())
+                    })
+                    .await
+                    .ok();
+                });
+            }
+        }
+    }
+
     pub async fn listen(
         self: &Arc<Self>,
         listener: TcpListener,
@@ -330,7 +743,16 @@ where
             select! {
                 stream = listener.accept().fuse() => {
                     if let Ok((stream,socket_addr)) = stream {
-                        if self.handler.accept(&socket_addr) {
+                        if !self.handler.accept(&socket_addr) {
+                            continue;
+                        }
+                        if self.is_draining() {
+                            self.counters.drained_connections.fetch_add(1, Ordering::Relaxed);
+                            self.reject(stream);
+                        } else if self.max_connections().is_some_and(|max| self.active_connections() >= max) {
+                            self.counters.rejected_connections.fetch_add(1, Ordering::Relaxed);
+                            self.reject(stream);
+                        } else {
                             self.accept(stream, config).await;
                         }
                     }
@@ -370,6 +792,20 @@ where
     }
 }
 
+fn error_response(status: StatusCode) -> ErrorResponse {
+    let mut response = ErrorResponse::new(status.canonical_reason().map(String::from));
+    *response.status_mut() = status;
+    response
+}
+
+/// Upgrade callback rejecting the connection
+fn service_unavailable(
+    _request: &Request,
+    _response: Response,
+) -> std::result::Result<Response, ErrorResponse> {
+    Err(error_response(StatusCode::SERVICE_UNAVAILABLE))
+}
+
 /// Base WebSocketServer trait allows the [`WebSocketServer<T>`] struct
 /// to be retained by the trait reference by casting it to the trait
 /// as follows:
@@ -0,0 +1,457 @@
+//!
+//! Optional [`Router`] dispatching the messages received by a
+//! [`WebSocketHandler`](super::WebSocketHandler) to async handlers
+//! registered by message discriminant, in place of matching each
+//! [`Message`] within [`WebSocketHandler::message()`](super::WebSocketHandler::message).
+//!
+//! - text messages are matched by a prefix or by a predicate ([`TextMatcher`])
+//! - binary messages are matched by their leading magic bytes
+//! - messages not matched by any handler are delivered to the fallback
+//!   handler (if installed), otherwise they are counted (see [`Router::unhandled()`])
+//!   and optionally logged
+//!
+//! Matcher precedence (independent of the registration order, except
+//! between predicates):
+//! 1. the text handler with the longest matching prefix (the binary handler
+//!    with the longest matching magic bytes)
+//! 2. the first matching text predicate, in the registration order
+//! 3. the fallback handler
+//!
+//! Handlers receive a clone of the connection context, the message payload
+//! and the [`WebSocketSink`] of the connection. Broadcast responses can be
+//! produced by sinks retained in the context (or in the handler state) when
+//! the connection is established. Handler errors terminate the connection,
+//! same as errors returned by [`WebSocketHandler::message()`](super::WebSocketHandler::message).
+//!
+//! The router is immutable once built and is shared across connections
+//! using an [`Arc`]:
+//!
+//! ```ignore
+//! let router = Arc::new(
+//!     Router::<Arc<MyContext>>::new()
+//!         .on_text("echo:", |_ctx, text, sink| async move {
+//!             sink.send(Message::Text(text))?;
+//!             Ok(())
+//!         })
+//!         .on_binary([0xca, 0xfe], |_ctx, data, sink| async move {
+//!             sink.send(Message::Binary(data))?;
+//!             Ok(())
+//!         })
+//!         .with_max_concurrency(64),
+//! );
+//!
+//! // within WebSocketHandler::message()
+//! self.router.route(ctx, msg, sink).await
+//! ```
+//!
+
+use super::{Message, Result, WebSocketSink};
+use futures::future::BoxFuture;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use workflow_log::*;
+
+/// Handler of text messages
+pub type TextHandlerFn<Ctx> =
+    Arc<dyn Fn(Ctx, String, WebSocketSink) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// Handler of binary messages
+pub type BinaryHandlerFn<Ctx> =
+    Arc<dyn Fn(Ctx, Vec<u8>, WebSocketSink) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// Handler of messages not matched by any other handler
+pub type FallbackHandlerFn<Ctx> =
+    Arc<dyn Fn(Ctx, Message, WebSocketSink) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// Predicate matching text messages
+pub type TextPredicateFn = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Matcher of text messages supplied to [`Router::on_text()`]
+#[derive(Clone)]
+pub enum TextMatcher {
+    /// Matches messages starting with the prefix
+    Prefix(String),
+    /// Matches messages accepted by the predicate
+    Predicate(TextPredicateFn),
+}
+
+impl TextMatcher {
+    pub fn prefix<S: Into<String>>(prefix: S) -> Self {
+        TextMatcher::Prefix(prefix.into())
+    }
+
+    pub fn predicate<F>(predicate: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        TextMatcher::Predicate(Arc::new(predicate))
+    }
+}
+
+impl From<&str> for TextMatcher {
+    fn from(prefix: &str) -> Self {
+        TextMatcher::Prefix(prefix.to_string())
+    }
+}
+
+impl From<String> for TextMatcher {
+    fn from(prefix: String) -> Self {
+        TextMatcher::Prefix(prefix)
+    }
+}
+
+impl std::fmt::Debug for TextMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextMatcher::Prefix(prefix) => f.debug_tuple("Prefix").field(prefix).finish(),
+            TextMatcher::Predicate(_) => f.write_str("Predicate"),
+        }
+    }
+}
+
+/// Message router (see the [module](self) documentation)
+pub struct Router<Ctx> {
+    /// Prefix handlers ordered by the descending prefix length
+    text_prefixes: Vec<(String, TextHandlerFn<Ctx>)>,
+    text_predicates: Vec<(TextPredicateFn, TextHandlerFn<Ctx>)>,
+    /// Binary handlers ordered by the descending magic length
+    binary: Vec<(Vec<u8>, BinaryHandlerFn<Ctx>)>,
+    fallback: Option<FallbackHandlerFn<Ctx>>,
+    limiter: Option<Semaphore>,
+    log_unhandled: bool,
+    unhandled: AtomicUsize,
+}
+
+impl<Ctx> Default for Router<Ctx>
+where
+    Ctx: Clone + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Ctx> Router<Ctx>
+where
+    Ctx: Clone + Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Router {
+            text_prefixes: Vec::new(),
+            text_predicates: Vec::new(),
+            binary: Vec::new(),
+            fallback: None,
+            limiter: None,
+            log_unhandled: false,
+            unhandled: AtomicUsize::new(0),
+        }
+    }
+
+    /// Register the handler of text messages matched by the `matcher`
+    /// (a prefix if supplied as `&str` or `String`). The handler receives
+    /// the entire message text (including the prefix).
+    pub fn on_text<M, F, Fut>(mut self, matcher: M, handler: F) -> Self
+    where
+        M: Into<TextMatcher>,
+        F: Fn(Ctx, String, WebSocketSink) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let handler: TextHandlerFn<Ctx> =
+            Arc::new(move |ctx, text, sink| Box::pin(handler(ctx, text, sink)));
+        match matcher.into() {
+            TextMatcher::Prefix(prefix) => {
+                let index = self
+                    .text_prefixes
+                    .partition_point(|(existing, _)| existing.len() >= prefix.len());
+                self.text_prefixes.insert(index, (prefix, handler));
+            }
+            TextMatcher::Predicate(predicate) => {
+                self.text_predicates.push((predicate, handler));
+            }
+        }
+        self
+    }
+
+    /// Register the handler of binary messages starting with the
+    /// `magic` bytes. The handler receives the entire message data
+    /// (including the magic bytes).
+    pub fn on_binary<B, F, Fut>(mut self, magic: B, handler: F) -> Self
+    where
+        B: AsRef<[u8]>,
+        F: Fn(Ctx, Vec<u8>, WebSocketSink) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let magic = magic.as_ref().to_vec();
+        let handler: BinaryHandlerFn<Ctx> =
+            Arc::new(move |ctx, data, sink| Box::pin(handler(ctx, data, sink)));
+        let index = self
+            .binary
+            .partition_point(|(existing, _)| existing.len() >= magic.len());
+        self.binary.insert(index, (magic, handler));
+        self
+    }
+
+    /// Install the handler receiving the text and binary
+    /// messages not matched by any other handler
+    pub fn fallback<F, Fut>(mut self, handler: F) -> Self
+    where
+        F: Fn(Ctx, Message, WebSocketSink) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.fallback = Some(Arc::new(move |ctx, msg, sink| {
+            Box::pin(handler(ctx, msg, sink))
+        }));
+        self
+    }
+
+    /// Limit the number of handlers executing concurrently across all
+    /// connections sharing the router. Messages exceeding the limit wait
+    /// for execution (delaying the processing of the subsequent messages
+    /// of the same connection).
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        assert!(
+            max_concurrency > 0,
+            "router concurrency limit must be greater than 0"
+        );
+        self.limiter = Some(Semaphore::new(max_concurrency));
+        self
+    }
+
+    /// Log the messages not matched by any handler (at the `warn` level)
+    pub fn with_unhandled_logging(mut self, log_unhandled: bool) -> Self {
+        self.log_unhandled = log_unhandled;
+        self
+    }
+
+    /// Number of messages not matched by any handler
+    pub fn unhandled(&self) -> usize {
+        self.unhandled.load(Ordering::Relaxed)
+    }
+
+    /// Dispatch the text or binary message to the matching handler.
+    /// Other messages (such as [`Message::Close`]) are ignored.
+    pub async fn route(&self, ctx: &Ctx, msg: Message, sink: &WebSocketSink) -> Result<()> {
+        let handler = match msg {
+            Message::Text(text) => match self.match_text(&text) {
+                Some(handler) => handler(ctx.clone(), text, sink.clone()),
+                None => return self.unmatched(ctx, Message::Text(text), sink).await,
+            },
+            Message::Binary(data) => match self.match_binary(&data) {
+                Some(handler) => handler(ctx.clone(), data, sink.clone()),
+                None => return self.unmatched(ctx, Message::Binary(data), sink).await,
+            },
+            _ => return Ok(()),
+        };
+        self.execute(handler).await
+    }
+
+    fn match_text(&self, text: &str) -> Option<&TextHandlerFn<Ctx>> {
+        self.text_prefixes
+            .iter()
+            .find(|(prefix, _)| text.starts_with(prefix.as_str()))
+            .map(|(_, handler)| handler)
+            .or_else(|| {
+                self.text_predicates
+                    .iter()
+                    .find(|(predicate, _)| predicate(text))
+                    .map(|(_, handler)| handler)
+            })
+    }
+
+    fn match_binary(&self, data: &[u8]) -> Option<&BinaryHandlerFn<Ctx>> {
+        self.binary
+            .iter()
+            .find(|(magic, _)| data.starts_with(magic))
+            .map(|(_, handler)| handler)
+    }
+
+    async fn unmatched(&self, ctx: &Ctx, msg: Message, sink: &WebSocketSink) -> Result<()> {
+        if let Some(fallback) = &self.fallback {
+            return self.execute(fallback(ctx.clone(), msg, sink.clone())).await;
+        }
+        self.unhandled.fetch_add(1, Ordering::Relaxed);
+        if self.log_unhandled {
+            match &msg {
+                Message::Text(text) => {
+                    log_warn!("WebSocket router: unhandled text message `{text}`")
+                }
+                msg => log_warn!(
+                    "WebSocket router: unhandled binary message ({} bytes)",
+                    msg.len()
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    async fn execute(&self, future: BoxFuture<'static, Result<()>>) -> Result<()> {
+        let _permit = match &self.limiter {
+            Some(limiter) => Some(
+                limiter
+                    .acquire()
+                    .await
+                    .expect("router limiter is never closed"),
+            ),
+            None => None,
+        };
+        future.await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn tagged(
+        tag: &'static str,
+    ) -> impl Fn((), String, WebSocketSink) -> BoxFuture<'static, Result<()>> {
+        move |_, text, sink| {
+            Box::pin(async move {
+                sink.send(Message::Text(format!("{tag}:{text}")))?;
+                Ok(())
+            })
+        }
+    }
+
+    fn tagged_binary(
+        tag: &'static str,
+    ) -> impl Fn((), Vec<u8>, WebSocketSink) -> BoxFuture<'static, Result<()>> {
+        move |_, _, sink| {
+            Box::pin(async move {
+                sink.send(Message::Text(tag.to_string()))?;
+                Ok(())
+            })
+        }
+    }
+
+    async fn route(router: &Router<()>, msg: Message) -> Option<String> {
+        let (sink, mut receiver) = unbounded_channel();
+        router.route(&(), msg, &sink).await.unwrap();
+        receiver.try_recv().ok().map(|msg| msg.into_text().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_router_text_precedence() {
+        let router = Router::<()>::new()
+            .on_text(
+                TextMatcher::predicate(|text| text.contains("x")),
+                tagged("first-predicate"),
+            )
+            .on_text("a", tagged("a"))
+            .on_text(
+                TextMatcher::predicate(|text| text.len() > 3),
+                tagged("second-predicate"),
+            )
+            .on_text("abc", tagged("abc"))
+            .on_text("ab", tagged("ab"));
+
+        // the longest prefix wins regardless of the registration order
+        assert_eq!(
+            route(&router, Message::text("abcd")).await.unwrap(),
+            "abc:abcd"
+        );
+        assert_eq!(
+            route(&router, Message::text("abx")).await.unwrap(),
+            "ab:abx"
+        );
+        assert_eq!(route(&router, Message::text("a")).await.unwrap(), "a:a");
+        // prefixes take precedence over predicates
+        assert_eq!(route(&router, Message::text("ax")).await.unwrap(), "a:ax");
+        // predicates are evaluated in the registration order
+        assert_eq!(
+            route(&router, Message::text("xxxx")).await.unwrap(),
+            "first-predicate:xxxx"
+        );
+        assert_eq!(
+            route(&router, Message::text("zzzz")).await.unwrap(),
+            "second-predicate:zzzz"
+        );
+
+        // unmatched messages are counted
+        assert_eq!(route(&router, Message::text("zz")).await, None);
+        assert_eq!(route(&router, Message::binary(vec![1])).await, None);
+        assert_eq!(router.unhandled(), 2);
+
+        // control messages are not routed
+        assert_eq!(route(&router, Message::Close(None)).await, None);
+        assert_eq!(router.unhandled(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_router_binary_precedence() {
+        let router = Router::<()>::new()
+            .on_binary([0xca], tagged_binary("short"))
+            .on_binary([0xca, 0xfe], tagged_binary("long"))
+            .on_binary([], tagged_binary("any"))
+            .fallback(|_, msg, sink| async move {
+                sink.send(Message::Text(format!("fallback:{}", msg.len())))?;
+                Ok(())
+            });
+
+        assert_eq!(
+            route(&router, Message::binary(vec![0xca, 0xfe, 0]))
+                .await
+                .unwrap(),
+            "long"
+        );
+        assert_eq!(
+            route(&router, Message::binary(vec![0xca, 0]))
+                .await
+                .unwrap(),
+            "short"
+        );
+        // an empty magic matches all binary messages
+        assert_eq!(
+            route(&router, Message::binary(vec![0])).await.unwrap(),
+            "any"
+        );
+        // unmatched messages are delivered to the fallback
+        assert_eq!(
+            route(&router, Message::text("abc")).await.unwrap(),
+            "fallback:3"
+        );
+        assert_eq!(router.unhandled(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_router_concurrency() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let router = {
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            Arc::new(
+                Router::<()>::new()
+                    .with_max_concurrency(2)
+                    .on_text("", move |_, _, _| {
+                        let in_flight = in_flight.clone();
+                        let max_in_flight = max_in_flight.clone();
+                        async move {
+                            let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                            max_in_flight.fetch_max(current, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(20)).await;
+                            in_flight.fetch_sub(1, Ordering::SeqCst);
+                            Ok(())
+                        }
+                    }),
+            )
+        };
+
+        let (sink, _receiver) = unbounded_channel();
+        let tasks = (0..6)
+            .map(|_| {
+                let router = router.clone();
+                let sink = sink.clone();
+                tokio::spawn(async move { router.route(&(), Message::text("msg"), &sink).await })
+            })
+            .collect::<Vec<_>>();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+    }
+}
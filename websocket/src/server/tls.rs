@@ -0,0 +1,223 @@
+//!
+//! TLS termination for the [`WebSocketServer`](super::WebSocketServer)
+//! (available with the `server-tls` feature).
+//!
+//! The rustls configuration is kept in an atomically swappable holder.
+//! [`WebSocketServer::reload_tls()`](super::WebSocketServer::reload_tls)
+//! replaces the configuration used for new connections, while established
+//! connections continue on their existing sessions. A certificate that
+//! fails validation is reported as [`ServerEvent::TlsReloadFailed`](super::ServerEvent::TlsReloadFailed)
+//! and the previous configuration remains in effect.
+//!
+//! ```ignore
+//! let (cert_chain, key) = tls::load_pem(&std::fs::read("cert.pem")?, &std::fs::read("key.pem")?)?;
+//! server.reload_tls(cert_chain, key)?;
+//! // with the `server-tls-watch` feature
+//! server.watch_tls("cert.pem", "key.pem", Duration::from_secs(10));
+//! ```
+//!
+//! Connections are counted per serial number of the certificate they
+//! were established with (see [`CertificateCounters`]).
+//!
+
+use super::error::Error;
+use super::result::Result;
+use super::stream::ServerStream;
+use arc_swap::ArcSwapOption;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::{self, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+pub use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+/// Time allowed for the TLS handshake of an incoming connection
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Parses the PEM-encoded certificate chain (leaf certificate first)
+/// and private key.
+pub fn load_pem(
+    cert_chain: &[u8],
+    key: &[u8],
+) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)> {
+    let cert_chain = rustls_pemfile::certs(&mut &*cert_chain)
+        .collect::<std::io::Result<Vec<_>>>()
+        .map_err(|err| Error::Tls(format!("invalid certificate PEM: {err}")))?;
+    if cert_chain.is_empty() {
+        return Err(Error::Tls("no certificate found".to_string()));
+    }
+    let key = rustls_pemfile::private_key(&mut &*key)
+        .map_err(|err| Error::Tls(format!("invalid private key PEM: {err}")))?
+        .ok_or_else(|| Error::Tls("no private key found".to_string()))?;
+    Ok((cert_chain, key))
+}
+
+/// Hexadecimal serial number of the DER-encoded X.509 `certificate`
+/// (as displayed by `openssl x509 -serial`).
+pub fn certificate_serial(certificate: &[u8]) -> Option<String> {
+    let (certificate, _) = der_element(certificate, 0x30)?;
+    let (tbs_certificate, _) = der_element(certificate, 0x30)?;
+    // skip the (explicitly tagged) version
+    let fields = match tbs_certificate.first() {
+        Some(0xa0) => der_element(tbs_certificate, 0xa0)?.1,
+        _ => tbs_certificate,
+    };
+    let (serial, _) = der_element(fields, 0x02)?;
+    // a leading zero keeps positive integers positive
+    let serial = match serial {
+        [0, rest @ ..] if !rest.is_empty() => rest,
+        serial => serial,
+    };
+    Some(serial.iter().map(|byte| format!("{byte:02X}")).collect())
+}
+
+/// Splits the DER element with the given `tag` off `data`,
+/// returning the element contents and the remaining data.
+fn der_element(data: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (&actual, data) = data.split_first()?;
+    if actual != tag {
+        return None;
+    }
+    let (&length, data) = data.split_first()?;
+    let (length, data) = if length < 0x80 {
+        (length as usize, data)
+    } else {
+        let count = (length & 0x7f) as usize;
+        if count == 0 || count > std::mem::size_of::<usize>() || data.len() < count {
+            return None;
+        }
+        let (length, data) = data.split_at(count);
+        let length = length
+            .iter()
+            .fold(0usize, |length, byte| (length << 8) | *byte as usize);
+        (length, data)
+    };
+    (data.len() >= length).then(|| data.split_at(length))
+}
+
+/// Connections established using a certificate
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CertificateCount {
+    pub total: usize,
+    pub active: usize,
+}
+
+/// Connection counters keyed by the serial number of the
+/// server certificate (see [`certificate_serial()`])
+#[derive(Debug, Default)]
+pub struct CertificateCounters(Mutex<HashMap<String, CertificateCount>>);
+
+impl CertificateCounters {
+    pub fn get(&self, serial: &str) -> CertificateCount {
+        self.0
+            .lock()
+            .unwrap()
+            .get(serial)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn to_map(&self) -> HashMap<String, CertificateCount> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Counts a connection until the returned guard is dropped
+    pub(super) fn connect(self: &Arc<Self>, serial: &str) -> CertificateConnection {
+        let mut counters = self.0.lock().unwrap();
+        let count = counters.entry(serial.to_string()).or_default();
+        count.total += 1;
+        count.active += 1;
+        CertificateConnection {
+            counters: self.clone(),
+            serial: serial.to_string(),
+        }
+    }
+}
+
+/// Active connection counted by [`CertificateCounters`]
+pub(super) struct CertificateConnection {
+    counters: Arc<CertificateCounters>,
+    serial: String,
+}
+
+impl Drop for CertificateConnection {
+    fn drop(&mut self) {
+        if let Some(count) = self.counters.0.lock().unwrap().get_mut(&self.serial) {
+            count.active = count.active.saturating_sub(1);
+        }
+    }
+}
+
+/// TLS configuration applied to new connections
+pub(super) struct TlsConfig {
+    acceptor: TlsAcceptor,
+    serial: String,
+}
+
+impl TlsConfig {
+    fn try_new(
+        cert_chain: Vec<CertificateDer<'static>>,
+        key: PrivateKeyDer<'static>,
+    ) -> Result<Self> {
+        let leaf = cert_chain
+            .first()
+            .ok_or_else(|| Error::Tls("empty certificate chain".to_string()))?;
+        let serial = certificate_serial(leaf)
+            .ok_or_else(|| Error::Tls("malformed certificate".to_string()))?;
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .and_then(|builder| {
+                builder
+                    .with_no_client_auth()
+                    .with_single_cert(cert_chain, key)
+            })
+            .map_err(|err| Error::Tls(err.to_string()))?;
+        Ok(TlsConfig {
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+            serial,
+        })
+    }
+
+    pub fn serial(&self) -> &str {
+        &self.serial
+    }
+
+    /// Performs the TLS handshake on the accepted `stream`
+    pub async fn accept(&self, stream: TcpStream) -> Result<ServerStream> {
+        let stream = tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, self.acceptor.accept(stream))
+            .await
+            .map_err(|_| Error::Tls("handshake timeout".to_string()))?
+            .map_err(|err| Error::Tls(err.to_string()))?;
+        Ok(ServerStream::Tls(Box::new(stream)))
+    }
+}
+
+/// Atomically swappable [`TlsConfig`] (`None` if TLS is disabled)
+#[derive(Default)]
+pub(super) struct Tls(ArcSwapOption<TlsConfig>);
+
+impl Tls {
+    pub fn load(&self) -> Option<Arc<TlsConfig>> {
+        self.0.load_full()
+    }
+
+    /// Validates and installs the configuration, returning
+    /// the serial number of the certificate.
+    pub fn store(
+        &self,
+        cert_chain: Vec<CertificateDer<'static>>,
+        key: PrivateKeyDer<'static>,
+    ) -> Result<String> {
+        let config = TlsConfig::try_new(cert_chain, key)?;
+        let serial = config.serial.clone();
+        self.0.store(Some(Arc::new(config)));
+        Ok(serial)
+    }
+
+    pub fn clear(&self) {
+        self.0.store(None);
+    }
+}
@@ -1,11 +1,19 @@
-use crate::client::{ConnectOptions, Message as ClientMessage, WebSocket};
+use crate::client::{
+    BorshCodec, Coalescing, Codec, ConnectCandidate, ConnectEvent, ConnectOptions,
+    Error as ClientError, JsonCodec, Message as ClientMessage, MockTransport,
+    Result as ClientResult, TypedWebSocket, WebSocket, WebSocketConfig,
+};
 use crate::server::{
-    Message as ServerMessage, Result as ServerResult, WebSocketHandler, WebSocketReceiver,
-    WebSocketSender, WebSocketServer, WebSocketSink,
+    tls, Message as ServerMessage, Rejection, Result as ServerResult, ServerEvent,
+    WebSocketHandler, WebSocketReceiver, WebSocketSender, WebSocketServer, WebSocketSink,
 };
 use async_trait::async_trait;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 use workflow_core::task::spawn;
 use workflow_log::*;
@@ -147,3 +155,846 @@ async fn websocket_test() -> Result<()> {
 
     Ok(())
 }
+
+async fn echo_server(
+    addr: &str,
+    coalescing: Option<Coalescing>,
+) -> Result<Arc<WebSocketServer<EchoWsHandler>>> {
+    let handler = Arc::new(EchoWsHandler {});
+    let ws_server = match coalescing {
+        Some(coalescing) => WebSocketServer::new_with_coalescing(handler, None, coalescing),
+        None => WebSocketServer::new(handler, None),
+    };
+    let listener = ws_server.bind(addr).await?;
+    let ws_server_ = ws_server.clone();
+    spawn(async move {
+        ws_server_.listen(listener, None).await.ok();
+    });
+    Ok(ws_server)
+}
+
+async fn connect(url: &str, coalescing: Option<Coalescing>) -> Result<WebSocket> {
+    let config = WebSocketConfig {
+        coalescing,
+        ..Default::default()
+    };
+    let ws_client = WebSocket::new(Some(url), Some(config))?;
+    ws_client
+        .connect(ConnectOptions::blocking_fallback())
+        .await?;
+    assert_eq!(ws_client.recv().await?, ClientMessage::Open);
+    Ok(ws_client)
+}
+
+async fn recv_timeout(ws_client: &WebSocket) -> ClientMessage {
+    tokio::time::timeout(Duration::from_secs(5), ws_client.recv())
+        .await
+        .expect("timeout waiting for message")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn coalescing_test() -> Result<()> {
+    let addr = "127.0.0.1:19112";
+    let ws_server = echo_server(addr, Some(Coalescing::default())).await?;
+    // client window long enough to hold messages until
+    // the size threshold is reached or they are flushed
+    let coalescing = Coalescing::new(Duration::from_secs(60), 4096);
+    let ws_client = connect(&format!("ws://{addr}"), Some(coalescing)).await?;
+
+    // text and binary messages survive round-tripping in order
+    let messages = (0..256)
+        .map(|n| {
+            if n % 2 == 0 {
+                ClientMessage::Text(format!("message {n}"))
+            } else {
+                ClientMessage::Binary(vec![n as u8; n])
+            }
+        })
+        .collect::<Vec<_>>();
+    for msg in messages.iter() {
+        ws_client.post(msg.clone()).await?;
+    }
+    ws_client.flush().await.expect("Error flushing messages");
+    for msg in messages.iter() {
+        assert_eq!(&recv_timeout(&ws_client).await, msg);
+    }
+
+    // reaching the size threshold dispatches the container
+    let large = ClientMessage::Binary(vec![0xaa; 4096]);
+    ws_client.post(large.clone()).await?;
+    assert_eq!(recv_timeout(&ws_client).await, large);
+
+    // latency-sensitive messages bypass the coalescing window
+    ws_client
+        .post(ClientMessage::Text("first".to_string()))
+        .await?;
+    ws_client
+        .send_immediate(ClientMessage::Text("second".to_string()))
+        .await
+        .expect("Error sending message");
+    assert_eq!(
+        recv_timeout(&ws_client).await,
+        ClientMessage::Text("first".to_string())
+    );
+    assert_eq!(
+        recv_timeout(&ws_client).await,
+        ClientMessage::Text("second".to_string())
+    );
+
+    ws_client.disconnect().await?;
+    ws_server.stop_and_join().await?;
+
+    Ok(())
+}
+
+/// Throughput micro-benchmark echoing small messages with and without coalescing:
+/// `cargo test -p workflow-websocket --release -- --ignored --nocapture coalescing_throughput`
+#[tokio::test]
+#[ignore]
+async fn coalescing_throughput() -> Result<()> {
+    const MESSAGES: u64 = 100_000;
+
+    workflow_log::set_log_level(workflow_log::LevelFilter::Error);
+
+    for (port, coalescing) in [(19113, None), (19114, Some(Coalescing::default()))] {
+        let addr = format!("127.0.0.1:{port}");
+        let ws_server = echo_server(&addr, coalescing).await?;
+        let ws_client = connect(&format!("ws://{addr}"), coalescing).await?;
+
+        let start = std::time::Instant::now();
+        let receiver = ws_client.clone();
+        let echo = tokio::spawn(async move {
+            for n in 0..MESSAGES {
+                assert_eq!(
+                    receiver.recv().await.unwrap(),
+                    ClientMessage::Binary(n.to_le_bytes().to_vec())
+                );
+            }
+        });
+        for n in 0..MESSAGES {
+            ws_client
+                .post(ClientMessage::Binary(n.to_le_bytes().to_vec()))
+                .await?;
+        }
+        ws_client.flush().await.expect("Error flushing messages");
+        echo.await.unwrap();
+        let elapsed = start.elapsed();
+
+        println!(
+            "coalescing: {:<5} - {MESSAGES} messages echoed in {elapsed:?} ({:.0} msg/sec)",
+            coalescing.is_some(),
+            MESSAGES as f64 / elapsed.as_secs_f64()
+        );
+
+        ws_client.disconnect().await?;
+        ws_server.stop_and_join().await?;
+    }
+
+    Ok(())
+}
+
+/// Sends a websocket upgrade request for `path` over a raw TCP connection
+/// expected to be rejected, returning the HTTP status line of the response
+/// (empty if the connection has been closed without a response).
+async fn rejected_upgrade(addr: &str, path: &str) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let request = format!(
+        "GET {path} HTTP/1.1\r\nHost: {addr}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+        Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n"
+    );
+    // the write fails if the connection has already been closed
+    stream.write_all(request.as_bytes()).await.ok();
+    let mut response = Vec::new();
+    tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut response))
+        .await
+        .expect("timeout waiting for the connection to be closed")
+        .ok();
+    String::from_utf8_lossy(&response)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+async fn wait_active_connections(ws_server: &WebSocketServer<EchoWsHandler>, count: usize) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while ws_server.active_connections() != count {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("timeout waiting for the active connection count");
+}
+
+async fn echo(ws_client: &WebSocket, text: &str) -> Result<()> {
+    ws_client
+        .post(ClientMessage::Text(text.to_string()))
+        .await?;
+    assert_eq!(
+        recv_timeout(ws_client).await,
+        ClientMessage::Text(text.to_string())
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn connection_limit_test() -> Result<()> {
+    let addr = "127.0.0.1:19118";
+    let url = format!("ws://{addr}");
+    let ws_server = echo_server(addr, None).await?;
+    ws_server.set_max_connections(Some(2));
+
+    let first = connect(&url, None).await?;
+    let second = connect(&url, None).await?;
+    wait_active_connections(&ws_server, 2).await;
+
+    // the limit is enforced before the upgrade
+    assert_eq!(
+        rejected_upgrade(addr, "/").await,
+        "HTTP/1.1 503 Service Unavailable"
+    );
+    ws_server.set_rejection(Rejection::Close);
+    assert_eq!(rejected_upgrade(addr, "/").await, "");
+    let counters = &ws_server.counters;
+    assert_eq!(counters.rejected_connections.load(Ordering::SeqCst), 2);
+    assert_eq!(counters.total_connections.load(Ordering::SeqCst), 2);
+
+    // existing connections are not affected
+    echo(&second, "still connected").await?;
+
+    // connections are accepted again once below the limit
+    first.disconnect().await?;
+    wait_active_connections(&ws_server, 1).await;
+    let third = connect(&url, None).await?;
+    echo(&third, "recovered").await?;
+    assert_eq!(counters.total_connections.load(Ordering::SeqCst), 3);
+
+    ws_server.set_max_connections(None);
+    let fourth = connect(&url, None).await?;
+    wait_active_connections(&ws_server, 3).await;
+
+    for ws_client in [second, third, fourth] {
+        ws_client.disconnect().await?;
+    }
+    wait_active_connections(&ws_server, 0).await;
+    assert_eq!(counters.rejected_connections.load(Ordering::SeqCst), 2);
+    ws_server.stop_and_join().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn connection_drain_test() -> Result<()> {
+    let addr = "127.0.0.1:19119";
+    let url = format!("ws://{addr}");
+    let ws_server = echo_server(addr, None).await?;
+
+    let ws_client = connect(&url, None).await?;
+    ws_server.drain();
+    assert!(ws_server.is_draining());
+    assert_eq!(
+        rejected_upgrade(addr, "/").await,
+        "HTTP/1.1 503 Service Unavailable"
+    );
+    assert_eq!(
+        ws_server
+            .counters
+            .drained_connections
+            .load(Ordering::SeqCst),
+        1
+    );
+    // existing connections continue to be served
+    echo(&ws_client, "draining").await?;
+    ws_client.disconnect().await?;
+    wait_active_connections(&ws_server, 0).await;
+
+    ws_server.resume();
+    let ws_client = connect(&url, None).await?;
+    echo(&ws_client, "resumed").await?;
+    ws_client.disconnect().await?;
+    ws_server.stop_and_join().await?;
+
+    Ok(())
+}
+
+async fn recv_event(events: &workflow_core::channel::Receiver<ConnectEvent>) -> ConnectEvent {
+    tokio::time::timeout(Duration::from_secs(5), events.recv())
+        .await
+        .expect("timeout waiting for connect event")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn connect_failover_test() -> Result<()> {
+    let primary_addr = "127.0.0.1:19121";
+    let primary = format!("ws://{primary_addr}");
+    let fallback_addr = "127.0.0.1:19122";
+    let fallback = format!("ws://{fallback_addr}");
+    let fallback_server = echo_server(fallback_addr, None).await?;
+
+    let events = workflow_core::channel::Channel::unbounded();
+    let options = ConnectOptions::blocking_fallback()
+        .with_candidates([
+            ConnectCandidate::new(&primary).with_timeout(Duration::from_millis(500)),
+            ConnectCandidate::new(&fallback).with_header("X-Candidate", "fallback"),
+        ])
+        .with_primary_probe_interval(Duration::from_millis(50))
+        .with_events(events.sender.clone());
+
+    // the primary port is dead, the connection fails over
+    let ws_client = WebSocket::new(None, None)?;
+    ws_client.connect(options).await?;
+    assert_eq!(recv_timeout(&ws_client).await, ClientMessage::Open);
+    assert_eq!(ws_client.current_url(), Some(fallback.clone()));
+
+    let event = recv_event(&events.receiver).await;
+    assert!(matches!(event, ConnectEvent::Attempt { .. }) && event.url() == primary);
+    let event = recv_event(&events.receiver).await;
+    assert!(matches!(event, ConnectEvent::Failed { .. }) && event.url() == primary);
+    let event = recv_event(&events.receiver).await;
+    assert!(matches!(event, ConnectEvent::Attempt { .. }) && event.url() == fallback);
+    let event = recv_event(&events.receiver).await;
+    assert!(matches!(event, ConnectEvent::Connected { .. }) && event.url() == fallback);
+    echo(&ws_client, "fallback").await?;
+
+    // the primary comes up and is re-promoted
+    let primary_server = echo_server(primary_addr, None).await?;
+    assert_eq!(recv_timeout(&ws_client).await, ClientMessage::Close);
+    assert_eq!(recv_timeout(&ws_client).await, ClientMessage::Open);
+    assert_eq!(ws_client.current_url(), Some(primary.clone()));
+
+    let event = recv_event(&events.receiver).await;
+    assert!(matches!(event, ConnectEvent::Promoted { .. }) && event.url() == primary);
+    let event = recv_event(&events.receiver).await;
+    assert!(matches!(event, ConnectEvent::Attempt { .. }) && event.url() == primary);
+    let event = recv_event(&events.receiver).await;
+    assert!(matches!(event, ConnectEvent::Connected { .. }) && event.url() == primary);
+    echo(&ws_client, "primary").await?;
+    wait_active_connections(&fallback_server, 0).await;
+
+    ws_client.disconnect().await?;
+    wait_active_connections(&primary_server, 0).await;
+    primary_server.stop_and_join().await?;
+    fallback_server.stop_and_join().await?;
+
+    Ok(())
+}
+
+/// Round-trips `text` through the mock transport acting as an echo server
+async fn mock_echo(ws_client: &WebSocket, transport: &MockTransport, text: &str) -> Result<()> {
+    ws_client
+        .send(ClientMessage::Text(text.to_string()))
+        .await
+        .expect("Error sending message");
+    for msg in transport.take_outgoing() {
+        transport.inject_incoming(msg)?;
+    }
+    assert_eq!(
+        recv_timeout(ws_client).await,
+        ClientMessage::Text(text.to_string())
+    );
+    Ok(())
+}
+
+async fn wait_mock_disconnected(transport: &MockTransport) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while transport.is_connected() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("timeout waiting for the mock connection to close");
+}
+
+#[tokio::test]
+async fn mock_transport_test() -> Result<()> {
+    let transport = MockTransport::new();
+    let ws_client = WebSocket::new_with_transport(Some("ws://mock"), None, transport.clone())?;
+    ws_client
+        .connect(ConnectOptions::blocking_retry().with_retry_interval(Duration::from_millis(10)))
+        .await?;
+    assert_eq!(recv_timeout(&ws_client).await, ClientMessage::Open);
+    assert!(transport.is_connected());
+    mock_echo(&ws_client, &transport, "Hello, world!").await?;
+
+    transport.inject_incoming(ClientMessage::Binary(vec![1, 2, 3]))?;
+    assert_eq!(
+        recv_timeout(&ws_client).await,
+        ClientMessage::Binary(vec![1, 2, 3])
+    );
+
+    // the connection is lost and restored after two failed attempts
+    transport.fail_next(2);
+    transport.disconnect();
+    assert_eq!(recv_timeout(&ws_client).await, ClientMessage::Close);
+    assert_eq!(recv_timeout(&ws_client).await, ClientMessage::Open);
+    assert_eq!(transport.attempts().len(), 4);
+    assert_eq!(transport.connections(), 2);
+    mock_echo(&ws_client, &transport, "reconnected").await?;
+
+    ws_client.disconnect().await?;
+    assert_eq!(recv_timeout(&ws_client).await, ClientMessage::Close);
+    wait_mock_disconnected(&transport).await;
+    assert!(matches!(
+        ws_client
+            .post(ClientMessage::Text("closed".to_string()))
+            .await,
+        Err(ClientError::NotConnected)
+    ));
+    assert!(matches!(
+        transport.inject_incoming(ClientMessage::Text("closed".to_string())),
+        Err(ClientError::NotConnected)
+    ));
+
+    Ok(())
+}
+
+/// [`connect_failover_test`] using the mock transport
+#[tokio::test]
+async fn mock_failover_test() -> Result<()> {
+    let primary = "ws://primary";
+    let fallback = "ws://fallback";
+    let transport = MockTransport::new();
+    transport.refuse(primary);
+
+    let events = workflow_core::channel::Channel::unbounded();
+    let options = ConnectOptions::blocking_fallback()
+        .with_candidates([
+            ConnectCandidate::new(primary).with_timeout(Duration::from_millis(500)),
+            ConnectCandidate::new(fallback).with_header("X-Candidate", "fallback"),
+        ])
+        .with_primary_probe_interval(Duration::from_millis(50))
+        .with_events(events.sender.clone());
+
+    // the primary is unreachable, the connection fails over
+    let ws_client = WebSocket::new_with_transport(None, None, transport.clone())?;
+    ws_client.connect(options).await?;
+    assert_eq!(recv_timeout(&ws_client).await, ClientMessage::Open);
+    assert_eq!(ws_client.current_url().as_deref(), Some(fallback));
+
+    let event = recv_event(&events.receiver).await;
+    assert!(matches!(event, ConnectEvent::Attempt { .. }) && event.url() == primary);
+    let event = recv_event(&events.receiver).await;
+    assert!(matches!(event, ConnectEvent::Failed { .. }) && event.url() == primary);
+    let event = recv_event(&events.receiver).await;
+    assert!(matches!(event, ConnectEvent::Attempt { .. }) && event.url() == fallback);
+    let event = recv_event(&events.receiver).await;
+    assert!(matches!(event, ConnectEvent::Connected { .. }) && event.url() == fallback);
+    mock_echo(&ws_client, &transport, "fallback").await?;
+
+    // the primary becomes reachable and is re-promoted
+    transport.accept(primary);
+    assert_eq!(recv_timeout(&ws_client).await, ClientMessage::Close);
+    assert_eq!(recv_timeout(&ws_client).await, ClientMessage::Open);
+    assert_eq!(ws_client.current_url().as_deref(), Some(primary));
+
+    let event = recv_event(&events.receiver).await;
+    assert!(matches!(event, ConnectEvent::Promoted { .. }) && event.url() == primary);
+    let event = recv_event(&events.receiver).await;
+    assert!(matches!(event, ConnectEvent::Attempt { .. }) && event.url() == primary);
+    let event = recv_event(&events.receiver).await;
+    assert!(matches!(event, ConnectEvent::Connected { .. }) && event.url() == primary);
+    mock_echo(&ws_client, &transport, "primary").await?;
+
+    ws_client.disconnect().await?;
+    wait_mock_disconnected(&transport).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn accept_filter_test() -> Result<()> {
+    let addr = "127.0.0.1:19120";
+    let ws_server = echo_server(addr, None).await?;
+    ws_server.set_accept_filter(|peer, request| {
+        peer.ip().is_loopback() && request.uri().path() != "/blocked"
+    });
+
+    assert_eq!(
+        rejected_upgrade(addr, "/blocked").await,
+        "HTTP/1.1 403 Forbidden"
+    );
+    assert_eq!(
+        ws_server
+            .counters
+            .rejected_connections
+            .load(Ordering::SeqCst),
+        1
+    );
+    wait_active_connections(&ws_server, 0).await;
+
+    let ws_client = connect(&format!("ws://{addr}/allowed"), None).await?;
+    echo(&ws_client, "allowed").await?;
+    ws_client.disconnect().await?;
+
+    ws_server.clear_accept_filter();
+    let ws_client = connect(&format!("ws://{addr}/blocked"), None).await?;
+    ws_client.disconnect().await?;
+    ws_server.stop_and_join().await?;
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+enum Shape {
+    Point { x: i32, y: i32 },
+    Label(String),
+    Polygon(Vec<(i32, i32)>),
+}
+
+async fn recv_typed<C: Codec<Shape>>(ws: &TypedWebSocket<Shape, C>) -> ClientResult<Shape> {
+    tokio::time::timeout(Duration::from_secs(5), ws.recv_msg())
+        .await
+        .expect("timeout waiting for message")
+}
+
+/// Round-trips typed messages through the echo server, interleaving
+/// a `malformed` frame that must be reported without closing the connection.
+async fn typed_round_trip<C>(addr: &str, codec: C, malformed: ClientMessage) -> Result<()>
+where
+    C: Codec<Shape>,
+{
+    let ws_server = echo_server(addr, None).await?;
+    let ws_client = connect(&format!("ws://{addr}"), None).await?;
+    let ws = TypedWebSocket::new(ws_client, codec);
+
+    let shapes = vec![
+        Shape::Point { x: -1, y: 2 },
+        Shape::Label("hello".to_string()),
+        Shape::Polygon(vec![(0, 0), (0, 1), (1, 1)]),
+    ];
+    for shape in shapes.iter() {
+        ws.send_msg(shape).await?;
+    }
+    for shape in shapes.iter() {
+        assert_eq!(&recv_typed(&ws).await?, shape);
+    }
+
+    ws.ws().post(malformed).await?;
+    ws.send_msg(&shapes[0]).await?;
+    assert!(matches!(recv_typed(&ws).await, Err(ClientError::Decode(_))));
+    assert_eq!(recv_typed(&ws).await?, shapes[0]);
+
+    ws.ws().disconnect().await?;
+    ws_server.stop_and_join().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn typed_json_test() -> Result<()> {
+    typed_round_trip(
+        "127.0.0.1:19115",
+        JsonCodec,
+        ClientMessage::Text("{\"Point\":{\"x\":1}}".to_string()),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn typed_borsh_test() -> Result<()> {
+    // variant index out of range
+    typed_round_trip(
+        "127.0.0.1:19116",
+        BorshCodec,
+        ClientMessage::Binary(vec![0xff]),
+    )
+    .await
+}
+
+#[cfg(feature = "msgpack")]
+#[tokio::test]
+async fn typed_msgpack_test() -> Result<()> {
+    typed_round_trip(
+        "127.0.0.1:19117",
+        crate::client::MsgPackCodec,
+        ClientMessage::Text("not a binary frame".to_string()),
+    )
+    .await
+}
+
+/// Accepts a single connection selecting the subprotocol
+/// returned by `select` from the ones offered by the client.
+async fn subprotocol_server(
+    addr: &str,
+    select: fn(&[String]) -> Option<String>,
+) -> Result<tokio::task::JoinHandle<()>> {
+    use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+    use tokio_tungstenite::tungstenite::http::header::SEC_WEBSOCKET_PROTOCOL;
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(crate::server::Error::from)?;
+    Ok(tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let callback = |request: &Request, mut response: Response| {
+            let offered = request
+                .headers()
+                .get(SEC_WEBSOCKET_PROTOCOL)
+                .and_then(|protocols| protocols.to_str().ok())
+                .map(|protocols| protocols.split(',').map(String::from).collect::<Vec<_>>())
+                .unwrap_or_default();
+            if let Some(protocol) = select(&offered) {
+                response
+                    .headers_mut()
+                    .insert(SEC_WEBSOCKET_PROTOCOL, protocol.parse().unwrap());
+            }
+            Ok(response)
+        };
+        if let Ok(mut ws_stream) = tokio_tungstenite::accept_hdr_async(stream, callback).await {
+            use futures::StreamExt;
+            while let Some(Ok(_)) = ws_stream.next().await {}
+        }
+    }))
+}
+
+#[tokio::test]
+async fn subprotocol_test() -> Result<()> {
+    let addr = "127.0.0.1:19123";
+    let server = subprotocol_server(addr, |offered| offered.get(1).cloned()).await?;
+
+    let ws_client = WebSocket::new(Some(&format!("ws://{addr}")), None)?;
+    ws_client
+        .connect(ConnectOptions::blocking_fallback().with_protocols(&["wrpc.borsh", "wrpc.json"]))
+        .await?;
+    assert_eq!(recv_timeout(&ws_client).await, ClientMessage::Open);
+    assert_eq!(
+        ws_client.negotiated_protocol(),
+        Some("wrpc.json".to_string())
+    );
+    ws_client.disconnect().await?;
+    server.await.unwrap();
+
+    // the client fails the connection if the server
+    // selects a subprotocol that was not offered
+    let addr = "127.0.0.1:19124";
+    let server = subprotocol_server(addr, |_| Some("wrpc.unknown".to_string())).await?;
+    let ws_client = WebSocket::new(Some(&format!("ws://{addr}")), None)?;
+    let result = ws_client
+        .connect(ConnectOptions::blocking_retry().with_protocols(["wrpc.borsh"]))
+        .await;
+    assert!(matches!(result, Err(ClientError::Subprotocol(_))));
+    assert_eq!(ws_client.negotiated_protocol(), None);
+    server.await.unwrap();
+
+    Ok(())
+}
+
+// self-signed certificates for `localhost` and `127.0.0.1`
+// with serial numbers `1001` and `2002` respectively
+const FIRST_CERT: &[u8] = include_bytes!("../test/certs/first.crt");
+const FIRST_KEY: &[u8] = include_bytes!("../test/certs/first.key");
+const SECOND_CERT: &[u8] = include_bytes!("../test/certs/second.crt");
+const SECOND_KEY: &[u8] = include_bytes!("../test/certs/second.key");
+
+type TlsClient =
+    tokio_tungstenite::WebSocketStream<tokio_rustls::client::TlsStream<tokio::net::TcpStream>>;
+
+/// Connects to the TLS server trusting both test certificates
+async fn tls_connect(addr: &str) -> TlsClient {
+    use tokio_rustls::rustls::{self, pki_types::ServerName, ClientConfig, RootCertStore};
+
+    let mut roots = RootCertStore::empty();
+    for cert in [FIRST_CERT, SECOND_CERT] {
+        let (cert_chain, _) = tls::load_pem(cert, FIRST_KEY).unwrap();
+        roots.add(cert_chain[0].clone()).unwrap();
+    }
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let connector = tokio_rustls::TlsConnector::from(Arc::new(config));
+    let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let server_name = ServerName::try_from("localhost").unwrap();
+    let stream = connector.connect(server_name, stream).await.unwrap();
+    let (ws_stream, _) = tokio_tungstenite::client_async(format!("wss://{addr}"), stream)
+        .await
+        .unwrap();
+    ws_stream
+}
+
+/// Serial number of the certificate presented by the server
+fn tls_serial(ws_stream: &TlsClient) -> String {
+    let (_, session) = ws_stream.get_ref().get_ref();
+    let certificates = session.peer_certificates().unwrap();
+    tls::certificate_serial(&certificates[0]).unwrap()
+}
+
+async fn tls_echo(ws_stream: &mut TlsClient, text: &str) {
+    use futures::{SinkExt, StreamExt};
+
+    ws_stream
+        .send(ServerMessage::Text(text.to_string()))
+        .await
+        .unwrap();
+    let msg = tokio::time::timeout(Duration::from_secs(5), ws_stream.next())
+        .await
+        .expect("timeout waiting for message");
+    assert_eq!(msg.unwrap().unwrap(), ServerMessage::Text(text.to_string()));
+}
+
+async fn recv_server_event(events: &workflow_core::channel::Receiver<ServerEvent>) -> ServerEvent {
+    tokio::time::timeout(Duration::from_secs(5), events.recv())
+        .await
+        .expect("timeout waiting for server event")
+        .unwrap()
+}
+
+/// Receives the next [`ServerEvent::TlsReloaded`] serial, skipping failures
+/// caused by observing partially replaced certificate files.
+async fn recv_reloaded(events: &workflow_core::channel::Receiver<ServerEvent>) -> String {
+    loop {
+        if let ServerEvent::TlsReloaded { serial } = recv_server_event(events).await {
+            return serial;
+        }
+    }
+}
+
+#[test]
+fn certificate_serial_test() {
+    for (cert, serial) in [(FIRST_CERT, "1001"), (SECOND_CERT, "2002")] {
+        let (cert_chain, _) = tls::load_pem(cert, FIRST_KEY).unwrap();
+        assert_eq!(tls::certificate_serial(&cert_chain[0]).unwrap(), serial);
+    }
+    assert_eq!(tls::certificate_serial(&[0x30, 0x82, 0xff]), None);
+    assert!(tls::load_pem(b"garbage", FIRST_KEY).is_err());
+    assert!(tls::load_pem(FIRST_CERT, b"garbage").is_err());
+}
+
+#[tokio::test]
+async fn tls_reload_test() -> Result<()> {
+    let addr = "127.0.0.1:19140";
+    let ws_server = echo_server(addr, None).await?;
+    let events = ws_server.events();
+    assert_eq!(ws_server.tls_serial(), None);
+
+    ws_server.reload_tls_pem(FIRST_CERT, FIRST_KEY)?;
+    assert_eq!(
+        recv_server_event(&events).await,
+        ServerEvent::TlsReloaded {
+            serial: "1001".to_string()
+        }
+    );
+    let mut first = tls_connect(addr).await;
+    assert_eq!(tls_serial(&first), "1001");
+    tls_echo(&mut first, "first").await;
+
+    // rotate the certificate while the first connection is open
+    let (cert_chain, key) = tls::load_pem(SECOND_CERT, SECOND_KEY)?;
+    ws_server.reload_tls(cert_chain, key)?;
+    assert_eq!(
+        recv_server_event(&events).await,
+        ServerEvent::TlsReloaded {
+            serial: "2002".to_string()
+        }
+    );
+    assert_eq!(ws_server.tls_serial().as_deref(), Some("2002"));
+
+    let mut second = tls_connect(addr).await;
+    assert_eq!(tls_serial(&second), "2002");
+    tls_echo(&mut second, "second").await;
+    // the established connection retains the previous certificate
+    tls_echo(&mut first, "still connected").await;
+    assert_eq!(tls_serial(&first), "1001");
+
+    // rejected certificates are reported, retaining the current configuration
+    assert!(ws_server.reload_tls_pem(FIRST_CERT, SECOND_KEY).is_err());
+    assert!(matches!(
+        recv_server_event(&events).await,
+        ServerEvent::TlsReloadFailed { .. }
+    ));
+    assert!(ws_server.reload_tls_pem(b"garbage", SECOND_KEY).is_err());
+    assert!(matches!(
+        recv_server_event(&events).await,
+        ServerEvent::TlsReloadFailed { .. }
+    ));
+    let mut third = tls_connect(addr).await;
+    assert_eq!(tls_serial(&third), "2002");
+    tls_echo(&mut third, "third").await;
+
+    let certificates = &ws_server.counters.certificates;
+    assert_eq!(
+        certificates.get("1001"),
+        tls::CertificateCount {
+            total: 1,
+            active: 1
+        }
+    );
+    assert_eq!(
+        certificates.get("2002"),
+        tls::CertificateCount {
+            total: 2,
+            active: 2
+        }
+    );
+
+    // plain connections fail the TLS handshake
+    assert!(!rejected_upgrade(addr, "/").await.starts_with("HTTP"));
+    assert_eq!(
+        ws_server.counters.handshake_failures.load(Ordering::SeqCst),
+        1
+    );
+
+    for mut ws_stream in [first, second, third] {
+        ws_stream.close(None).await.unwrap();
+    }
+    wait_active_connections(&ws_server, 0).await;
+    assert_eq!(certificates.get("1001").active, 0);
+    assert_eq!(certificates.get("2002").active, 0);
+    assert_eq!(certificates.to_map().len(), 2);
+
+    ws_server.disable_tls();
+    let ws_client = connect(&format!("ws://{addr}"), None).await?;
+    echo(&ws_client, "plain").await?;
+    ws_client.disconnect().await?;
+    ws_server.stop_and_join().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn tls_watch_test() -> Result<()> {
+    let addr = "127.0.0.1:19141";
+    let ws_server = echo_server(addr, None).await?;
+    let events = ws_server.events();
+
+    let dir = std::env::temp_dir().join(format!("workflow-websocket-tls-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (cert, key) = (dir.join("cert.pem"), dir.join("key.pem"));
+    std::fs::remove_file(&cert).ok();
+
+    let watcher = ws_server.watch_tls(&cert, &key, Duration::from_millis(20));
+    // missing files are reported once
+    assert!(matches!(
+        recv_server_event(&events).await,
+        ServerEvent::TlsReloadFailed { .. }
+    ));
+
+    std::fs::write(&key, FIRST_KEY).unwrap();
+    std::fs::write(&cert, FIRST_CERT).unwrap();
+    assert_eq!(recv_reloaded(&events).await, "1001");
+    let mut first = tls_connect(addr).await;
+    assert_eq!(tls_serial(&first), "1001");
+
+    std::fs::write(&key, SECOND_KEY).unwrap();
+    std::fs::write(&cert, SECOND_CERT).unwrap();
+    assert_eq!(recv_reloaded(&events).await, "2002");
+    let mut second = tls_connect(addr).await;
+    assert_eq!(tls_serial(&second), "2002");
+    tls_echo(&mut first, "first").await;
+    tls_echo(&mut second, "second").await;
+
+    watcher.abort();
+    std::fs::remove_dir_all(&dir).ok();
+    for mut ws_stream in [first, second] {
+        ws_stream.close(None).await.unwrap();
+    }
+    ws_server.stop_and_join().await?;
+
+    Ok(())
+}